@@ -1,9 +1,19 @@
 import { AnthropicAIClient } from '$lib/ai/anthropicClient';
 import { ButlerAIClient } from '$lib/ai/butlerClient';
+import { OllamaClient } from '$lib/ai/ollamaClient';
 import { OpenAIClient } from '$lib/ai/openAIClient';
 import { SHORT_DEFAULT_BRANCH_TEMPLATE, SHORT_DEFAULT_COMMIT_TEMPLATE } from '$lib/ai/prompts';
-import { AISecretHandle, AIService, GitAIConfigKey, KeyOption, buildDiff } from '$lib/ai/service';
 import {
+	AISecretHandle,
+	AIService,
+	GitAIConfigKey,
+	KeyOption,
+	buildDiff,
+	buildRedactions,
+	redact
+} from '$lib/ai/service';
+import {
+	AIBackend,
 	AnthropicModelName,
 	ModelKind,
 	OpenAIModelName,
@@ -11,7 +21,7 @@ import {
 	type Prompt
 } from '$lib/ai/types';
 import { HttpClient } from '$lib/backend/httpClient';
-import { buildFailureFromAny, ok, unwrap, type Result } from '$lib/result';
+import { buildFailureFromAny, isFailure, ok, unwrap, type Result } from '$lib/result';
 import { Hunk } from '$lib/vbranches/types';
 import { plainToInstance } from 'class-transformer';
 import { expect, test, describe, vi } from 'vitest';
@@ -205,6 +215,40 @@ describe.concurrent('AIService', () => {
 				)
 			);
 		});
+
+		test('When the project backend is local, It returns OllamaClient', async () => {
+			const aiService = buildDefaultAIService();
+
+			expect(
+				unwrap(await aiService.buildClient(undefined, { backend: AIBackend.Local }))
+			).toBeInstanceOf(OllamaClient);
+		});
+
+		test('When the project is sensitive, When a cloud backend is configured. It returns a failure', async () => {
+			const gitConfig = new DummyGitConfigService({
+				...defaultGitConfig,
+				[GitAIConfigKey.ModelProvider]: ModelKind.Anthropic,
+				[GitAIConfigKey.AnthropicKeyOption]: KeyOption.BringYourOwn
+			});
+			const secretsService = new DummySecretsService({
+				[AISecretHandle.AnthropicKey]: 'test-key'
+			});
+			const aiService = new AIService(gitConfig, secretsService, cloud);
+
+			expect(await aiService.buildClient('token', { sensitive: true })).toStrictEqual(
+				buildFailureFromAny(
+					'This project is marked as sensitive and may only use a local AI backend'
+				)
+			);
+		});
+
+		test('When the project is sensitive, When the project backend is local. It returns OllamaClient', async () => {
+			const aiService = buildDefaultAIService();
+
+			expect(
+				unwrap(await aiService.buildClient('token', { backend: AIBackend.Local, sensitive: true }))
+			).toBeInstanceOf(OllamaClient);
+		});
 	});
 
 	describe.concurrent('#summarizeCommit', async () => {
@@ -261,6 +305,122 @@ describe.concurrent('AIService', () => {
 				await aiService.summarizeCommit({ hunks: exampleHunks, useBriefStyle: true })
 			).toStrictEqual(ok('one'));
 		});
+
+		test('When the project has commit message directives, they apply unless styles are chosen', async () => {
+			const aiService = buildDefaultAIService();
+
+			const aiClient = new DummyAIClient('one\nnew line');
+			const evaluate = vi.spyOn(aiClient, 'evaluate');
+			vi.spyOn(aiService, 'buildClient').mockReturnValue(
+				(async () => ok<AIClient, Error>(aiClient))()
+			);
+
+			expect(
+				await aiService.summarizeCommit({
+					hunks: exampleHunks,
+					directives: { brief: true, emoji: true }
+				})
+			).toStrictEqual(ok('one'));
+
+			const prompt = JSON.stringify(evaluate.mock.calls[0][0]);
+			expect(prompt).toContain('Make use of GitMoji in the title prefix.');
+			expect(prompt).toContain(
+				'The commit message must be only one sentence and as short as possible.'
+			);
+		});
+
+		test('When styles are chosen, they override the commit message directives', async () => {
+			const aiService = buildDefaultAIService();
+
+			const aiClient = new DummyAIClient('one\nnew line');
+			const evaluate = vi.spyOn(aiClient, 'evaluate');
+			vi.spyOn(aiService, 'buildClient').mockReturnValue(
+				(async () => ok<AIClient, Error>(aiClient))()
+			);
+
+			expect(
+				await aiService.summarizeCommit({
+					hunks: exampleHunks,
+					useEmojiStyle: false,
+					useBriefStyle: false,
+					directives: { brief: true, emoji: true }
+				})
+			).toStrictEqual(ok('one\n\nnew line'));
+
+			const prompt = JSON.stringify(evaluate.mock.calls[0][0]);
+			expect(prompt).toContain("Don't use any emoji.");
+			expect(prompt).not.toContain(
+				'The commit message must be only one sentence and as short as possible.'
+			);
+		});
+
+		test('When the project leaves commit message directives unset, the chosen styles are used', async () => {
+			const aiService = buildDefaultAIService();
+
+			const aiClient = new DummyAIClient();
+			const evaluate = vi.spyOn(aiClient, 'evaluate');
+			vi.spyOn(aiService, 'buildClient').mockReturnValue(
+				(async () => ok<AIClient, Error>(aiClient))()
+			);
+
+			await aiService.summarizeCommit({
+				hunks: exampleHunks,
+				useEmojiStyle: true,
+				directives: { brief: null, emoji: null }
+			});
+
+			const prompt = JSON.stringify(evaluate.mock.calls[0][0]);
+			expect(prompt).toContain('Make use of GitMoji in the title prefix.');
+		});
+
+		test('When redaction patterns are configured, it redacts the diff before calling the AI client', async () => {
+			const aiService = buildDefaultAIService();
+
+			const aiClient = new DummyAIClient();
+			const evaluate = vi.spyOn(aiClient, 'evaluate');
+			vi.spyOn(aiService, 'buildClient').mockReturnValue(
+				(async () => ok<AIClient, Error>(aiClient))()
+			);
+
+			await aiService.summarizeCommit({
+				hunks: exampleHunks,
+				redactionPatterns: ['claude-3-\\w+-\\d+']
+			});
+
+			const prompt = JSON.stringify(evaluate.mock.calls[0][0]);
+			expect(prompt).not.toContain('claude-3-opus-20240229');
+			expect(prompt).toContain('[REDACTED]');
+		});
+
+		test('When a redaction pattern is invalid, it fails without calling the AI client', async () => {
+			const aiService = buildDefaultAIService();
+
+			const buildClient = vi.spyOn(aiService, 'buildClient');
+
+			expect(
+				await aiService.summarizeCommit({
+					hunks: exampleHunks,
+					redactionPatterns: ['(unclosed']
+				})
+			).toStrictEqual(buildFailureFromAny('Invalid redaction pattern: (unclosed'));
+			expect(buildClient).not.toHaveBeenCalled();
+		});
+
+		test('When the project is sensitive, When a cloud backend is configured, it fails', async () => {
+			const aiService = buildDefaultAIService();
+
+			expect(
+				await aiService.summarizeCommit({
+					hunks: exampleHunks,
+					userToken: 'token',
+					sensitive: true
+				})
+			).toStrictEqual(
+				buildFailureFromAny(
+					'This project is marked as sensitive and may only use a local AI backend'
+				)
+			);
+		});
 	});
 
 	describe.concurrent('#summarizeBranch', async () => {
@@ -317,6 +477,25 @@ describe.concurrent('AIService', () => {
 				ok('with-new-lines-included')
 			);
 		});
+
+		test('When redaction patterns are configured, it redacts the diff before calling the AI client', async () => {
+			const aiService = buildDefaultAIService();
+
+			const aiClient = new DummyAIClient();
+			const evaluate = vi.spyOn(aiClient, 'evaluate');
+			vi.spyOn(aiService, 'buildClient').mockReturnValue(
+				(async () => ok<AIClient, Error>(aiClient))()
+			);
+
+			await aiService.summarizeBranch({
+				hunks: exampleHunks,
+				redactionPatterns: ['claude-3-\\w+-\\d+']
+			});
+
+			const prompt = JSON.stringify(evaluate.mock.calls[0][0]);
+			expect(prompt).not.toContain('claude-3-opus-20240229');
+			expect(prompt).toContain('[REDACTED]');
+		});
 	});
 });
 
@@ -342,3 +521,21 @@ describe.concurrent('buildDiff', () => {
 		expect(outputMatchesExpectedValue).toBeTruthy;
 	});
 });
+
+describe.concurrent('redact', () => {
+	test('When provided no redactions, it returns the text unchanged', () => {
+		expect(redact('secret=hunter2', [])).to.eq('secret=hunter2');
+	});
+
+	test('When provided redactions, it replaces every match', () => {
+		const redactions = unwrap(buildRedactions(['hunter\\d', 'AKIA[0-9A-Z]{4}']));
+
+		expect(redact('a=hunter2 b=hunter3 c=AKIAABCD', redactions)).to.eq(
+			'a=[REDACTED] b=[REDACTED] c=[REDACTED]'
+		);
+	});
+
+	test('When provided an invalid pattern, it returns a failure', () => {
+		expect(isFailure(buildRedactions(['(']))).toBeTruthy();
+	});
+});
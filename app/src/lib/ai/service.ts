@@ -7,11 +7,13 @@ import {
 } from '$lib/ai/ollamaClient';
 import { OpenAIClient } from '$lib/ai/openAIClient';
 import {
+	AIBackend,
 	OpenAIModelName,
 	type AIClient,
 	AnthropicModelName,
 	ModelKind,
 	MessageRole,
+	type CommitMessageDirectives,
 	type Prompt
 } from '$lib/ai/types';
 import { buildFailureFromAny, isFailure, ok, type Result } from '$lib/result';
@@ -45,27 +47,53 @@ export enum GitAIConfigKey {
 	OllamaModelName = 'gitbutler.aiOllamaModelName'
 }
 
-type SummarizeCommitOpts = {
+const redactedPlaceholder = '[REDACTED]';
+
+type AIProjectOpts = {
+	backend?: AIBackend;
+	redactionPatterns?: string[];
+	sensitive?: boolean;
+};
+
+type SummarizeCommitOpts = AIProjectOpts & {
 	hunks: Hunk[];
 	useEmojiStyle?: boolean;
 	useBriefStyle?: boolean;
+	directives?: CommitMessageDirectives;
 	commitTemplate?: Prompt;
 	userToken?: string;
 };
 
-type SummarizeBranchOpts = {
+type SummarizeBranchOpts = AIProjectOpts & {
 	hunks: Hunk[];
 	branchTemplate?: Prompt;
 	userToken?: string;
 };
 
 // Exported for testing only
-export function buildDiff(hunks: Hunk[], limit: number) {
-	return shuffle(hunks.map((h) => `${h.filePath} - ${h.diff}`))
+export function buildDiff(hunks: Hunk[], limit: number, redactions: RegExp[] = []) {
+	// Redact before truncating so that a match cut off by the limit can't leak partially
+	return shuffle(hunks.map((h) => redact(`${h.filePath} - ${h.diff}`, redactions)))
 		.join('\n')
 		.slice(0, limit);
 }
 
+export function redact(text: string, redactions: RegExp[]) {
+	return redactions.reduce((acc, redaction) => acc.replace(redaction, redactedPlaceholder), text);
+}
+
+export function buildRedactions(patterns: string[]): Result<RegExp[], Error> {
+	const redactions: RegExp[] = [];
+	for (const pattern of patterns) {
+		try {
+			redactions.push(new RegExp(pattern, 'g'));
+		} catch {
+			return buildFailureFromAny(`Invalid redaction pattern: ${pattern}`);
+		}
+	}
+	return ok(redactions);
+}
+
 function shuffle<T>(items: T[]): T[] {
 	return items
 		.map((item) => ({ item, value: Math.random() }))
@@ -194,10 +222,20 @@ export class AIService {
 	// This optionally returns a summarizer. There are a few conditions for how this may occur
 	// Firstly, if the user has opted to use the GB API and isn't logged in, it will return undefined
 	// Secondly, if the user has opted to bring their own key but hasn't provided one, it will return undefined
-	async buildClient(userToken?: string): Promise<Result<AIClient, Error>> {
-		const modelKind = await this.getModelKind();
+	// Thirdly, if the project is sensitive and its backend isn't local, it will return undefined
+	async buildClient(
+		userToken?: string,
+		{ backend = AIBackend.Global, sensitive = false }: AIProjectOpts = {}
+	): Promise<Result<AIClient, Error>> {
+		const modelKind = backend === AIBackend.Local ? ModelKind.Ollama : await this.getModelKind();
+
+		if (sensitive && modelKind !== ModelKind.Ollama) {
+			return buildFailureFromAny(
+				'This project is marked as sensitive and may only use a local AI backend'
+			);
+		}
 
-		if (await this.usingGitButlerAPI()) {
+		if (modelKind !== ModelKind.Ollama && (await this.usingGitButlerAPI())) {
 			if (!userToken) {
 				return buildFailureFromAny(
 					"When using GitButler's API to summarize code, you must be logged in"
@@ -244,15 +282,27 @@ export class AIService {
 
 	async summarizeCommit({
 		hunks,
-		useEmojiStyle = false,
-		useBriefStyle = false,
+		useEmojiStyle,
+		useBriefStyle,
+		directives,
 		commitTemplate,
-		userToken
+		userToken,
+		backend,
+		redactionPatterns = [],
+		sensitive
 	}: SummarizeCommitOpts): Promise<Result<string, Error>> {
-		const aiClientResult = await this.buildClient(userToken);
+		const redactionsResult = buildRedactions(redactionPatterns);
+		if (isFailure(redactionsResult)) return redactionsResult;
+		const redactions = redactionsResult.value;
+
+		const aiClientResult = await this.buildClient(userToken, { backend, sensitive });
 		if (isFailure(aiClientResult)) return aiClientResult;
 		const aiClient = aiClientResult.value;
 
+		// Choices made in the UI take precedence over the directives of the project
+		const briefStyle = useBriefStyle ?? directives?.brief ?? false;
+		const emojiStyle = useEmojiStyle ?? directives?.emoji ?? false;
+
 		const diffLengthLimit = await this.getDiffLengthLimitConsideringAPI();
 		const defaultedCommitTemplate = commitTemplate || aiClient.defaultCommitTemplate;
 
@@ -261,14 +311,17 @@ export class AIService {
 				return promptMessage;
 			}
 
-			let content = promptMessage.content.replaceAll('%{diff}', buildDiff(hunks, diffLengthLimit));
+			let content = promptMessage.content.replaceAll(
+				'%{diff}',
+				buildDiff(hunks, diffLengthLimit, redactions)
+			);
 
-			const briefPart = useBriefStyle
+			const briefPart = briefStyle
 				? 'The commit message must be only one sentence and as short as possible.'
 				: '';
 			content = content.replaceAll('%{brief_style}', briefPart);
 
-			const emojiPart = useEmojiStyle
+			const emojiPart = emojiStyle
 				? 'Make use of GitMoji in the title prefix.'
 				: "Don't use any emoji.";
 			content = content.replaceAll('%{emoji_style}', emojiPart);
@@ -283,7 +336,7 @@ export class AIService {
 		if (isFailure(messageResult)) return messageResult;
 		let message = messageResult.value;
 
-		if (useBriefStyle) {
+		if (briefStyle) {
 			message = message.split('\n')[0];
 		}
 
@@ -294,9 +347,16 @@ export class AIService {
 	async summarizeBranch({
 		hunks,
 		branchTemplate,
-		userToken = undefined
+		userToken = undefined,
+		backend,
+		redactionPatterns = [],
+		sensitive
 	}: SummarizeBranchOpts): Promise<Result<string, Error>> {
-		const aiClientResult = await this.buildClient(userToken);
+		const redactionsResult = buildRedactions(redactionPatterns);
+		if (isFailure(redactionsResult)) return redactionsResult;
+		const redactions = redactionsResult.value;
+
+		const aiClientResult = await this.buildClient(userToken, { backend, sensitive });
 		if (isFailure(aiClientResult)) return aiClientResult;
 		const aiClient = aiClientResult.value;
 
@@ -309,7 +369,10 @@ export class AIService {
 
 			return {
 				role: MessageRole.User,
-				content: promptMessage.content.replaceAll('%{diff}', buildDiff(hunks, diffLengthLimit))
+				content: promptMessage.content.replaceAll(
+					'%{diff}',
+					buildDiff(hunks, diffLengthLimit, redactions)
+				)
 			};
 		});
 
@@ -7,6 +7,19 @@ export enum ModelKind {
 	Ollama = 'ollama'
 }
 
+export enum AIBackend {
+	Global = 'global',
+	Local = 'local'
+}
+
+/**
+ * Project-level defaults for generating commit messages, each overriding the choice made in the UI if set.
+ */
+export type CommitMessageDirectives = {
+	brief: boolean | null;
+	emoji: boolean | null;
+};
+
 export enum OpenAIModelName {
 	GPT35Turbo = 'gpt-3.5-turbo',
 	GPT4 = 'gpt-4',
@@ -5,6 +5,7 @@ import * as toasts from '$lib/utils/toasts';
 import { open } from '@tauri-apps/api/dialog';
 import { plainToInstance } from 'class-transformer';
 import { get, writable } from 'svelte/store';
+import type { AIBackend, CommitMessageDirectives } from '$lib/ai/types';
 import type { HttpClient } from './httpClient';
 import { goto } from '$app/navigation';
 
@@ -15,6 +16,13 @@ export type LocalKey = {
 
 export type Key = Exclude<KeyType, 'local'> | LocalKey;
 
+export type RetentionPolicy =
+	| { keepLast: number }
+	| { keepDays: number }
+	| { keepUnderBytes: number };
+
+export type SnapshotStorage = 'loose' | 'packed';
+
 export class Project {
 	id!: string;
 	title!: string;
@@ -28,6 +36,14 @@ export class Project {
 	snapshot_lines_threshold!: number | undefined;
 	use_new_locking!: boolean;
 	ignore_project_semaphore!: boolean;
+	ai_backend!: AIBackend;
+	ai_redaction_patterns!: string[];
+	ai_sensitive!: boolean;
+	commit_message_directives!: CommitMessageDirectives;
+	use_fsmonitor!: boolean;
+	snapshot_retention: RetentionPolicy | undefined;
+	auto_snapshot_interval_secs: number | undefined;
+	snapshot_storage!: SnapshotStorage;
 
 	// Produced just for the frontend to determine if the project is open in any window.
 	is_open!: boolean;
@@ -10,14 +10,20 @@ export function projectMergeUpstreamWarningDismissed(projectId: string): Persist
 	return persisted(false, key + projectId);
 }
 
-export function projectCommitGenerationExtraConcise(projectId: string): Persisted<boolean> {
+export function projectCommitGenerationExtraConcise(
+	projectId: string,
+	initial = false
+): Persisted<boolean> {
 	const key = 'projectCommitGenerationExtraConcise_';
-	return persisted(false, key + projectId);
+	return persisted(initial, key + projectId);
 }
 
-export function projectCommitGenerationUseEmojis(projectId: string): Persisted<boolean> {
+export function projectCommitGenerationUseEmojis(
+	projectId: string,
+	initial = false
+): Persisted<boolean> {
 	const key = 'projectCommitGenerationUseEmojis_';
-	return persisted(false, key + projectId);
+	return persisted(initial, key + projectId);
 }
 
 export enum ListPRsFilter {
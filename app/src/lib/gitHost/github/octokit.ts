@@ -1,9 +1,14 @@
+import { throttle } from './rateLimit';
+import { invoke } from '$lib/backend/ipc';
 import { Octokit } from '@octokit/rest';
 
 export function octokitFromAccessToken(accessToken: string) {
-	return new Octokit({
+	const octokit = new Octokit({
 		auth: accessToken,
 		userAgent: 'GitButler Client',
 		baseUrl: 'https://api.github.com'
 	});
+	// The backend keeps the state for `rate_limit_status`, failing to report it just leaves it outdated.
+	throttle(octokit, (state) => invoke('report_rate_limit', { state }).catch(() => {}));
+	return octokit;
 }
@@ -0,0 +1,72 @@
+import { rateLimitDelay, rateLimitFromHeaders, retryDelay, throttle } from './rateLimit';
+import { Octokit } from '@octokit/rest';
+import { expect, test, describe, vi } from 'vitest';
+import type { RateLimitState } from './rateLimit';
+
+function response(status: number, headers: Record<string, string>) {
+	return new Response(status === 200 ? '{}' : '{"message":"rate limited"}', {
+		status,
+		headers: { 'content-type': 'application/json', ...headers }
+	});
+}
+
+describe('rate limit', () => {
+	test('the state is read from headers', () => {
+		const state = rateLimitFromHeaders({
+			'x-ratelimit-limit': '5000',
+			'x-ratelimit-remaining': '0',
+			'x-ratelimit-reset': '1000'
+		});
+		expect(state).toEqual({ limit: 5000, remaining: 0, resetAt: 1000 });
+		expect(rateLimitDelay(state!, 970_000)).toEqual(30_000);
+		expect(rateLimitDelay(state!, 1_000_000)).toBeUndefined();
+		expect(rateLimitDelay({ ...state, remaining: 1 }, 970_000)).toBeUndefined();
+		expect(rateLimitFromHeaders({})).toBeUndefined();
+	});
+
+	test('retry-after is honored for secondary rate limits', () => {
+		expect(retryDelay(403, { 'retry-after': '10' }, 0)).toEqual(10_000);
+		expect(
+			retryDelay(429, { 'x-ratelimit-remaining': '0', 'x-ratelimit-reset': '5' }, 0)
+		).toEqual(5_000);
+		expect(retryDelay(200, { 'retry-after': '10' }, 0)).toBeUndefined();
+		expect(retryDelay(403, {}, 0)).toBeUndefined();
+	});
+
+	test('rate limited requests are retried and the state is reported', async () => {
+		const fetch = vi
+			.fn()
+			.mockResolvedValueOnce(response(403, { 'retry-after': '0' }))
+			.mockResolvedValueOnce(
+				response(200, {
+					'x-ratelimit-limit': '5000',
+					'x-ratelimit-remaining': '4999',
+					'x-ratelimit-reset': '1000'
+				})
+			);
+		const octokit = new Octokit({ request: { fetch } });
+		const states: RateLimitState[] = [];
+		throttle(octokit, (state) => states.push(state));
+
+		await octokit.request('GET /rate_limit');
+		expect(fetch).toHaveBeenCalledTimes(2);
+		expect(states).toEqual([{ limit: 5000, remaining: 4999, resetAt: 1000 }]);
+	});
+
+	test('requests fail right away if the limit resets too late', async () => {
+		const resetAt = Math.floor(Date.now() / 1000) + 3600;
+		const fetch = vi.fn().mockResolvedValue(
+			response(200, {
+				'x-ratelimit-limit': '5000',
+				'x-ratelimit-remaining': '0',
+				'x-ratelimit-reset': resetAt.toString()
+			})
+		);
+		const octokit = new Octokit({ request: { fetch } });
+		throttle(octokit, () => {});
+
+		await octokit.request('GET /rate_limit');
+		await expect(octokit.request('GET /rate_limit')).rejects.toThrow('rate limit exceeded');
+		expect(fetch).toHaveBeenCalledTimes(1);
+	});
+});
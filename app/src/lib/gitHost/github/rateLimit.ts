@@ -0,0 +1,113 @@
+import { sleep } from '$lib/utils/sleep';
+import type { Octokit } from '@octokit/rest';
+
+/** The longest time to wait for a rate limit to reset before giving up on a request. */
+export const MAX_RATE_LIMIT_DELAY_MS = 60_000;
+/** How often to retry a request after it was rejected due to the rate limit. */
+const MAX_RATE_LIMIT_RETRIES = 2;
+
+/** The rate limit of the GitHub API, as of the last response that reported it. */
+export type RateLimitState = {
+	/** The amount of requests allowed per window. */
+	limit?: number;
+	/** The amount of requests left in the current window. */
+	remaining?: number;
+	/** When the current window ends, in seconds since the Unix epoch. */
+	resetAt?: number;
+};
+
+type ResponseHeaders = Record<string, string | number | undefined>;
+
+function numberHeader(headers: ResponseHeaders, name: string): number | undefined {
+	const value = headers[name];
+	if (value === undefined) return;
+	const number = Number(String(value).trim());
+	return Number.isFinite(number) ? number : undefined;
+}
+
+/** Read the state from the `X-RateLimit-*` headers of a response, or return `undefined` if there are none. */
+export function rateLimitFromHeaders(headers: ResponseHeaders): RateLimitState | undefined {
+	const state: RateLimitState = {
+		limit: numberHeader(headers, 'x-ratelimit-limit'),
+		remaining: numberHeader(headers, 'x-ratelimit-remaining'),
+		resetAt: numberHeader(headers, 'x-ratelimit-reset')
+	};
+	if (state.limit === undefined && state.remaining === undefined && state.resetAt === undefined) {
+		return;
+	}
+	return state;
+}
+
+/** How many milliseconds to wait at `nowMs` before the next request can succeed, if no requests are left. */
+export function rateLimitDelay(state: RateLimitState, nowMs: number): number | undefined {
+	if (state.remaining !== 0 || state.resetAt === undefined) return;
+	const delay = state.resetAt * 1000 - nowMs;
+	return delay > 0 ? delay : undefined;
+}
+
+/**
+ * How many milliseconds to wait before retrying a request that was rejected with `status` and `headers`,
+ * or `undefined` if it wasn't rejected due to a rate limit.
+ */
+export function retryDelay(
+	status: number,
+	headers: ResponseHeaders,
+	nowMs: number
+): number | undefined {
+	if (status !== 403 && status !== 429) return;
+	// Secondary rate limits tell how long to wait explicitly.
+	const retryAfter = numberHeader(headers, 'retry-after');
+	if (retryAfter !== undefined) return retryAfter * 1000;
+	const state = rateLimitFromHeaders(headers);
+	return state && rateLimitDelay(state, nowMs);
+}
+
+/**
+ * Make all requests of `octokit` wait while the rate limit is exhausted, and retry them if they are
+ * rejected due to a primary or secondary rate limit, as long as the wait doesn't exceed
+ * `MAX_RATE_LIMIT_DELAY_MS`. Each new state of the rate limit is passed to `onRateLimit`.
+ */
+export function throttle(octokit: Octokit, onRateLimit: (state: RateLimitState) => void) {
+	let state: RateLimitState | undefined;
+	function update(headers: ResponseHeaders | undefined) {
+		const newState = headers && rateLimitFromHeaders(headers);
+		if (!newState) return;
+		state = newState;
+		onRateLimit(newState);
+	}
+
+	octokit.hook.wrap('request', async (request, options) => {
+		let retries = 0;
+		for (;;) {
+			const delay = state && rateLimitDelay(state, Date.now());
+			if (delay !== undefined) {
+				if (delay > MAX_RATE_LIMIT_DELAY_MS) {
+					throw new Error(
+						`GitHub API rate limit exceeded, it resets in ${Math.ceil(delay / 1000)} seconds`
+					);
+				}
+				await sleep(delay);
+			}
+
+			try {
+				const response = await request(options);
+				update(response.headers as ResponseHeaders);
+				return response;
+			} catch (err: any) {
+				const headers: ResponseHeaders | undefined = err?.response?.headers;
+				update(headers);
+				const delay = headers && retryDelay(err.status, headers, Date.now());
+				if (
+					delay !== undefined &&
+					retries < MAX_RATE_LIMIT_RETRIES &&
+					delay <= MAX_RATE_LIMIT_DELAY_MS
+				) {
+					retries += 1;
+					await sleep(delay);
+					continue;
+				}
+				throw err;
+			}
+		}
+	});
+}
@@ -16,6 +16,18 @@ export class HeadService {
 
 	constructor(private projectId: string) {}
 
+	/** Calls `callback` whenever HEAD moves off the integration branch, which is then deleted. */
+	onIntegrationReset(callback: () => void) {
+		return listen<object>(`project://${this.projectId}/git/integration-reset`, () => callback());
+	}
+
+	/** Calls `callback` whenever HEAD moves onto the integration branch from elsewhere, like a terminal. */
+	onHeadOnIntegration(callback: () => void) {
+		return listen<object>(`project://${this.projectId}/git/head-on-integration`, () =>
+			callback()
+		);
+	}
+
 	private async refresh() {
 		let head = await invoke<string>('git_head', { projectId: this.projectId });
 		head = head.replace('refs/heads/', '');
@@ -1,4 +1,4 @@
-import { Snapshot, SnapshotDiff } from './types';
+import { Snapshot, SnapshotDiff, SnapshotFileChange } from './types';
 import { invoke } from '$lib/backend/ipc';
 import { plainToInstance } from 'class-transformer';
 import { get, writable } from 'svelte/store';
@@ -70,6 +70,30 @@ export class HistoryService {
 		}, {});
 	}
 
+	async diffSnapshots(projectId: string, from: string, to: string) {
+		const resp = await invoke<any[]>('diff_snapshots', {
+			projectId: projectId,
+			from: from,
+			to: to
+		});
+		return plainToInstance(SnapshotFileChange, resp);
+	}
+
+	async createNamedSnapshot(projectId: string, label: string) {
+		return await invoke<string>('create_named_snapshot', {
+			projectId: projectId,
+			label: label
+		});
+	}
+
+	async restoreSnapshotFile(projectId: string, sha: string, path: string) {
+		await invoke<void>('restore_snapshot_file', {
+			projectId: projectId,
+			sha: sha,
+			path: path
+		});
+	}
+
 	async restoreSnapshot(projectId: string, sha: string) {
 		await invoke<string>('restore_snapshot', {
 			projectId: projectId,
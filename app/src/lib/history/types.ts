@@ -29,7 +29,12 @@ export type Operation =
 	| 'ReorderCommit'
 	| 'InsertBlankCommit'
 	| 'MoveCommitFile'
-	| 'FileChanges';
+	| 'FileChanges'
+	| 'TriggeredFileChange'
+	| 'ReparentCommit'
+	| 'SaveWorkspace'
+	| 'SwitchWorkspace'
+	| 'RepairProject';
 
 export class Trailer {
 	key!: string;
@@ -47,6 +52,17 @@ export class SnapshotDiff {
 	skipped!: boolean;
 }
 
+export type FileChangeKind = 'added' | 'modified' | 'deleted' | 'renamed';
+
+export class SnapshotFileChange {
+	path!: string;
+	previousPath?: string;
+	kind!: FileChangeKind;
+	linesAdded!: number;
+	linesRemoved!: number;
+	binary!: boolean;
+}
+
 export class SnapshotDetails {
 	title!: string;
 	operation!: Operation;
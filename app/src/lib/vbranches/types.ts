@@ -130,6 +130,8 @@ export class VirtualBranch {
 	mergeBase!: string;
 	/// The fork point between the target branch and the virtual branch
 	forkPoint!: string;
+	/// The branch this branch is stacked on, if any
+	parentId?: string;
 	allowRebasing!: boolean;
 	pr?: PullRequest;
 
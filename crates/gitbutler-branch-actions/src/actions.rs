@@ -1,30 +1,52 @@
+use std::path::{Path, PathBuf};
+
 use crate::{
     base::{
-        get_base_branch_data, set_base_branch, set_target_push_remote, update_base_branch,
-        BaseBranch,
+        autodetect_base_branch, get_base_branch_data, set_base_branch, set_target_push_remote,
+        update_base_branch, BaseBranch, BranchDivergence,
+    },
+    base_rewrite::BaseHistoryRewrite,
+    blame::LineAttribution,
+    blast_radius::TouchedFile,
+    branch_manager::{
+        BranchManagerExt, LocalBranchImport, LocalBranchImportOptions, StackApplication,
     },
-    branch_manager::BranchManagerExt,
+    commit_lint::CommitLintReport,
+    conflicts::{self, ConflictResolution, ConflictedFile, RepoConflictsExt},
+    diagnostics::{ProjectDiagnostics, ProjectRepair},
+    duplicates::DuplicateBranch,
+    external_diff::DiffSide,
+    fast_forward::FastForward,
+    hunk_selection::{HunkSelection, HunkSelector},
+    linkage::DanglingReference,
+    ownership_conflicts::OwnershipConflict,
     remote::{get_branch_data, list_remote_branches, RemoteBranch, RemoteBranchData},
+    risk::{CommitAtRisk, RiskyOperation},
+    unapply_report::UnapplyReport,
+    workspaces::{Workspace, WorkspaceConflict, WorkspaceSwitch},
     VirtualBranchesExt,
 };
-use anyhow::Result;
+use anyhow::{anyhow, Context, Result};
 use gitbutler_branch::{
-    BranchOwnershipClaims, {BranchCreateRequest, BranchId, BranchUpdateRequest},
+    AssignmentMode, BranchOwnershipClaims, OwnershipClaim,
+    {BranchCreateRequest, BranchId, BranchUpdateRequest},
 };
 use gitbutler_command_context::ProjectRepository;
+use gitbutler_commit::commit_ext::CommitExt;
+use gitbutler_error::error::Code;
 use gitbutler_oplog::{
     entry::{OperationKind, SnapshotDetails},
     OplogExt, SnapshotExt,
 };
 use gitbutler_project::{FetchResult, Project};
 use gitbutler_reference::ReferenceName;
-use gitbutler_reference::{Refname, RemoteRefname};
-use gitbutler_repo::{credentials::Helper, RepoActionsExt, RepositoryExt};
+use gitbutler_reference::{validate_refname, Refname, RemoteRefname};
+use gitbutler_repo::{credentials::Helper, FetchDepth, RepoActionsExt, RepositoryExt};
 use tracing::instrument;
 
 use super::r#virtual as branch;
 
-use crate::files::RemoteBranchFile;
+use crate::files::{FileAtCommit, RemoteBranchFile, SubsetNetDiff};
 
 #[derive(Clone, Copy, Default)]
 pub struct VirtualBranchActions;
@@ -38,19 +60,84 @@ impl VirtualBranchActions {
         ownership: Option<&BranchOwnershipClaims>,
         run_hooks: bool,
     ) -> Result<git2::Oid> {
+        project.assure_writable()?;
         let project_repository = open_with_verify(project)?;
         let mut guard = project.exclusive_worktree_access();
         let snapshot_tree = project_repository
             .project()
             .prepare_snapshot(guard.read_permission());
-        let result = branch::commit(
-            &project_repository,
-            branch_id,
-            message,
-            ownership,
-            run_hooks,
-        )
-        .map_err(Into::into);
+        let result = crate::stack::restacking(&project_repository, || {
+            branch::commit(
+                &project_repository,
+                branch_id,
+                message,
+                ownership,
+                run_hooks,
+            )
+        });
+        let _ = snapshot_tree.and_then(|snapshot_tree| {
+            project_repository.project().snapshot_commit_creation(
+                snapshot_tree,
+                result.as_ref().err(),
+                message.to_owned(),
+                None,
+                guard.write_permission(),
+            )
+        });
+        result
+    }
+
+    /// Show which uncommitted hunks of `path` in the branch with `branch_id` would be committed with
+    /// [`commit_hunks()`](Self::commit_hunks) for `selectors`, and which would be left in the worktree.
+    pub async fn preview_hunk_selection(
+        &self,
+        project: &Project,
+        branch_id: BranchId,
+        path: &Path,
+        selectors: &[HunkSelector],
+    ) -> Result<HunkSelection> {
+        let project_repository = open_with_verify(project)?;
+        crate::hunk_selection::select_hunks(&project_repository, branch_id, path, selectors)
+    }
+
+    /// Attribute each line of the file at `path` in the working directory to the commit that last changed
+    /// it, or to the virtual branch owning its uncommitted change.
+    pub async fn virtual_blame(
+        &self,
+        project: &Project,
+        path: &Path,
+    ) -> Result<Vec<LineAttribution>> {
+        let project_repository = open_with_verify(project)?;
+        crate::blame::virtual_blame(&project_repository, path)
+    }
+
+    /// Commit only the hunks of `path` matching `selectors` to the branch with `branch_id`, leaving the
+    /// remaining hunks of the file uncommitted.
+    pub async fn commit_hunks(
+        &self,
+        project: &Project,
+        branch_id: BranchId,
+        path: &Path,
+        selectors: &[HunkSelector],
+        message: &str,
+        run_hooks: bool,
+    ) -> Result<git2::Oid> {
+        project.assure_writable()?;
+        let project_repository = open_with_verify(project)?;
+        let mut guard = project.exclusive_worktree_access();
+        let snapshot_tree = project_repository
+            .project()
+            .prepare_snapshot(guard.read_permission());
+        let result = crate::stack::restacking(&project_repository, || {
+            crate::hunk_selection::commit_hunks(
+                &project_repository,
+                branch_id,
+                path,
+                selectors,
+                message,
+                run_hooks,
+            )
+        });
         let _ = snapshot_tree.and_then(|snapshot_tree| {
             project_repository.project().snapshot_commit_creation(
                 snapshot_tree,
@@ -63,6 +150,19 @@ impl VirtualBranchActions {
         result
     }
 
+    /// Check `message` against the commit message rules of `project`, the same way creating a commit does,
+    /// without committing anything.
+    pub async fn lint_commit_message(
+        &self,
+        project: &Project,
+        message: &str,
+    ) -> Result<CommitLintReport> {
+        Ok(crate::commit_lint::lint_commit_message(
+            &project.commit_lint_rules,
+            message,
+        ))
+    }
+
     pub async fn can_apply_remote_branch(
         &self,
         project: &Project,
@@ -88,6 +188,10 @@ impl VirtualBranchActions {
         project: &Project,
         create: &BranchCreateRequest,
     ) -> Result<BranchId> {
+        project.assure_writable()?;
+        if let Some(name) = &create.name {
+            validate_name(name)?;
+        }
         let project_repository = open_with_verify(project)?;
         let mut guard = project.exclusive_worktree_access();
         let branch_manager = project_repository.branch_manager();
@@ -113,21 +217,194 @@ impl VirtualBranchActions {
             .map_err(Into::into)
     }
 
+    pub async fn integration_net_diff(&self, project: &Project) -> Result<Vec<RemoteBranchFile>> {
+        let project_repository = open_with_verify(project)?;
+        crate::files::integration_net_diff(&project_repository).map_err(Into::into)
+    }
+
+    pub async fn subset_net_diff(
+        &self,
+        project: &Project,
+        branch_ids: &[BranchId],
+    ) -> Result<SubsetNetDiff> {
+        let project_repository = open_with_verify(project)?;
+        crate::files::subset_net_diff(&project_repository, branch_ids)
+    }
+
+    pub async fn branch_changed_files(
+        &self,
+        project: &Project,
+        branch_id: BranchId,
+    ) -> Result<Vec<PathBuf>> {
+        let project_repository = open_with_verify(project)?;
+        let _guard = project.shared_worktree_access();
+        crate::files::branch_changed_files(&project_repository, branch_id)
+    }
+
+    pub async fn show_file_at_commit(
+        &self,
+        project: &Project,
+        branch_id: BranchId,
+        commit_id: git2::Oid,
+        path: &Path,
+    ) -> Result<FileAtCommit> {
+        let project_repository = open_with_verify(project)?;
+        crate::files::show_file_at_commit(&project_repository, branch_id, commit_id, path)
+    }
+
+    /// Return the change-id of `commit_id`, which stays the same when the commit is rebased.
+    pub async fn change_id_of(&self, project: &Project, commit_id: git2::Oid) -> Result<String> {
+        let project_repository = open_with_verify(project)?;
+        let commit = project_repository
+            .repo()
+            .find_commit(commit_id)
+            .context("commit not found")?;
+        Ok(commit.stable_change_id())
+    }
+
+    pub async fn find_duplicate_branches(&self, project: &Project) -> Result<Vec<DuplicateBranch>> {
+        let project_repository = open_with_verify(project)?;
+        crate::duplicates::find_duplicate_branches(&project_repository)
+    }
+
+    pub async fn check_repo_linkage(&self, project: &Project) -> Result<Vec<DanglingReference>> {
+        let project_repository = ProjectRepository::open(project)?;
+        crate::linkage::check_repo_linkage(&project_repository)
+    }
+
+    /// Check the GitButler state of the project and report all problems that were found.
+    pub async fn diagnose_project(&self, project: &Project) -> Result<ProjectDiagnostics> {
+        // Verifying the project would fail on the problems that are to be diagnosed.
+        let project_repository = ProjectRepository::open(project)?;
+        crate::diagnostics::diagnose_project(&project_repository)
+    }
+
+    /// Fix the problems found by [`diagnose_project`](Self::diagnose_project) that can be fixed safely,
+    /// after taking a snapshot.
+    pub async fn repair_project(&self, project: &Project) -> Result<ProjectRepair> {
+        project.assure_writable()?;
+        let project_repository = ProjectRepository::open(project)?;
+        let mut guard = project.exclusive_worktree_access();
+        let _ = project_repository.project().create_snapshot(
+            SnapshotDetails::new(OperationKind::RepairProject),
+            guard.write_permission(),
+        );
+        crate::diagnostics::repair_project(&project_repository, guard.write_permission())
+    }
+
+    pub async fn commits_at_risk(
+        &self,
+        project: &Project,
+        branch_id: BranchId,
+        operation: RiskyOperation,
+    ) -> Result<Vec<CommitAtRisk>> {
+        let project_repository = open_with_verify(project)?;
+        crate::risk::commits_at_risk(&project_repository, branch_id, operation)
+    }
+
+    /// Report the files that would conflict when unapplying the branch with `branch_id` and applying it again,
+    /// without changing anything.
+    pub async fn can_unapply(
+        &self,
+        project: &Project,
+        branch_id: BranchId,
+    ) -> Result<UnapplyReport> {
+        let project_repository = open_with_verify(project)?;
+        crate::unapply_report::can_unapply(&project_repository, branch_id)
+    }
+
+    /// List the saved workspaces of the project, sorted by name.
+    pub async fn list_workspaces(&self, project: &Project) -> Result<Vec<Workspace>> {
+        let project_repository = open_with_verify(project)?;
+        crate::workspaces::list_workspaces(&project_repository)
+    }
+
+    /// Save the virtual branches and the working directory as the workspace `name`, replacing any workspace
+    /// of the same name.
+    pub async fn save_workspace(&self, project: &Project, name: &str) -> Result<Workspace> {
+        project.assure_writable()?;
+        let project_repository = open_with_verify(project)?;
+        let mut guard = project.exclusive_worktree_access();
+        crate::workspaces::save_workspace(&project_repository, name, guard.write_permission())
+    }
+
+    /// Switch to the workspace `name`, after taking a snapshot of the current state to be able to go back.
+    pub async fn switch_workspace(&self, project: &Project, name: &str) -> Result<WorkspaceSwitch> {
+        project.assure_writable()?;
+        let project_repository = open_with_verify(project)?;
+        let mut guard = project.exclusive_worktree_access();
+        crate::workspaces::switch_workspace(&project_repository, name, guard.write_permission())
+    }
+
+    /// Return the branches whose current state would be replaced by switching to the workspace `name`.
+    pub async fn workspace_conflicts(
+        &self,
+        project: &Project,
+        name: &str,
+    ) -> Result<Vec<WorkspaceConflict>> {
+        let project_repository = open_with_verify(project)?;
+        crate::workspaces::workspace_conflicts(&project_repository, name)
+    }
+
+    pub async fn can_fast_forward(
+        &self,
+        project: &Project,
+        branch_id: BranchId,
+    ) -> Result<FastForward> {
+        let project_repository = open_with_verify(project)?;
+        crate::fast_forward::can_fast_forward(&project_repository, branch_id)
+    }
+
+    /// Call this after fetching to learn if the base branch was force-pushed, and which branches need to be re-anchored.
+    pub async fn detect_base_history_rewrite(
+        &self,
+        project: &Project,
+    ) -> Result<Option<BaseHistoryRewrite>> {
+        let project_repository = open_with_verify(project)?;
+        crate::base_rewrite::detect_base_history_rewrite(&project_repository)
+    }
+
+    pub async fn apply_blast_radius(
+        &self,
+        project: &Project,
+        branch_id: BranchId,
+    ) -> Result<Vec<TouchedFile>> {
+        let project_repository = open_with_verify(project)?;
+        crate::blast_radius::apply_blast_radius(&project_repository, branch_id)
+    }
+
     pub async fn set_base_branch(
         &self,
         project: &Project,
         target_branch: &RemoteRefname,
     ) -> Result<BaseBranch> {
+        project.assure_writable()?;
+        let project_repository = ProjectRepository::open(project)?;
+        let mut guard = project.exclusive_worktree_access();
+        let _ = project_repository.project().create_snapshot(
+            SnapshotDetails::new(OperationKind::SetBaseBranch),
+            guard.write_permission(),
+        );
+        let base_branch = set_base_branch(&project_repository, target_branch)?;
+        crate::base_cache::clear_base_cache(project)?;
+        Ok(base_branch)
+    }
+
+    pub async fn autodetect_base_branch(&self, project: &Project) -> Result<BaseBranch> {
+        project.assure_writable()?;
         let project_repository = ProjectRepository::open(project)?;
         let mut guard = project.exclusive_worktree_access();
         let _ = project_repository.project().create_snapshot(
             SnapshotDetails::new(OperationKind::SetBaseBranch),
             guard.write_permission(),
         );
-        set_base_branch(&project_repository, target_branch)
+        let base_branch = autodetect_base_branch(&project_repository, &Helper::default())?;
+        crate::base_cache::clear_base_cache(project)?;
+        Ok(base_branch)
     }
 
     pub async fn set_target_push_remote(&self, project: &Project, push_remote: &str) -> Result<()> {
+        project.assure_writable()?;
         let project_repository = ProjectRepository::open(project)?;
         set_target_push_remote(&project_repository, push_remote)
     }
@@ -137,23 +414,86 @@ impl VirtualBranchActions {
         project: &Project,
         branch_id: BranchId,
     ) -> Result<()> {
+        project.assure_writable()?;
         let project_repository = open_with_verify(project)?;
         let mut guard = project.exclusive_worktree_access();
         let _ = project_repository.project().create_snapshot(
             SnapshotDetails::new(OperationKind::MergeUpstream),
             guard.write_permission(),
         );
-        branch::integrate_upstream_commits(&project_repository, branch_id).map_err(Into::into)
+        crate::stack::restacking(&project_repository, || {
+            branch::integrate_upstream_commits(&project_repository, branch_id).map_err(Into::into)
+        })
     }
 
     pub async fn update_base_branch(&self, project: &Project) -> Result<Vec<ReferenceName>> {
+        project.assure_writable()?;
         let project_repository = open_with_verify(project)?;
         let mut guard = project.exclusive_worktree_access();
         let _ = project_repository.project().create_snapshot(
             SnapshotDetails::new(OperationKind::UpdateWorkspaceBase),
             guard.write_permission(),
         );
-        update_base_branch(&project_repository, guard.write_permission()).map_err(Into::into)
+        let unapplied_branches = crate::stack::restacking(&project_repository, || {
+            update_base_branch(&project_repository, guard.write_permission())
+        })?;
+        crate::base_cache::clear_base_cache(project)?;
+        Ok(unapplied_branches)
+    }
+
+    /// Return the merge-base between the head of the base branch and the commit the workspace is based on.
+    ///
+    /// It's cached until the next fetch or change of the base branch.
+    pub async fn base_merge_base(&self, project: &Project) -> Result<git2::Oid> {
+        let project_repository = open_with_verify(project)?;
+        crate::base_cache::base_merge_base(&project_repository)
+    }
+
+    /// Return how far the commit the workspace is based on and the head of the base branch diverged,
+    /// or `None` if the base branch doesn't exist.
+    ///
+    /// It's cached until the next fetch or change of the base branch.
+    pub async fn branch_divergence(&self, project: &Project) -> Result<Option<BranchDivergence>> {
+        let project_repository = open_with_verify(project)?;
+        crate::base_cache::base_divergence(&project_repository)
+    }
+
+    /// Forget the cached merge-base of the base branch, so it's computed again on next use.
+    pub async fn clear_base_cache(&self, project: &Project) -> Result<()> {
+        crate::base_cache::clear_base_cache(project)
+    }
+
+    /// Update the workspace to the moved base branch, but only if the project opted into
+    /// [`auto_rebase_on_base_update`](Project::auto_rebase_on_base_update).
+    ///
+    /// Branches that rebase cleanly are rebased onto it. Branches that conflict with it can't stay
+    /// in a workspace based on it, so they are set aside with their commits and changes as they are,
+    /// and their names are returned so they can be flagged as needing a rebase.
+    pub async fn auto_update_base_branch(&self, project: &Project) -> Result<Vec<String>> {
+        if !project.auto_rebase_on_base_update || project.is_read_only() {
+            return Ok(vec![]);
+        }
+        let project_repository = ProjectRepository::open(project)?;
+        let mut guard = project.exclusive_worktree_access();
+        if project_repository.is_resolving()
+            || get_base_branch_data(&project_repository)?.behind == 0
+        {
+            return Ok(vec![]);
+        }
+        let conflicting_branches =
+            crate::base::branches_conflicting_with_base_update(&project_repository)?
+                .into_iter()
+                .map(|branch| branch.name)
+                .collect();
+        let _ = project_repository.project().create_snapshot(
+            SnapshotDetails::new(OperationKind::UpdateWorkspaceBase),
+            guard.write_permission(),
+        );
+        crate::stack::restacking(&project_repository, || {
+            update_base_branch(&project_repository, guard.write_permission())
+        })?;
+        crate::base_cache::clear_base_cache(project)?;
+        Ok(conflicting_branches)
     }
 
     pub async fn update_virtual_branch(
@@ -161,6 +501,10 @@ impl VirtualBranchActions {
         project: &Project,
         branch_update: BranchUpdateRequest,
     ) -> Result<()> {
+        project.assure_writable()?;
+        if let Some(name) = &branch_update.name {
+            validate_name(name)?;
+        }
         let project_repository = open_with_verify(project)?;
         let mut guard = project.exclusive_worktree_access();
         let snapshot_tree = project_repository
@@ -183,22 +527,150 @@ impl VirtualBranchActions {
         result?;
         Ok(())
     }
+
+    /// Set the free-form description of a branch, like "waiting on API review", with an empty `description`
+    /// clearing it. It is stored as the notes of the branch.
+    pub async fn set_branch_description(
+        &self,
+        project: &Project,
+        branch_id: BranchId,
+        description: &str,
+    ) -> Result<()> {
+        self.update_virtual_branch(
+            project,
+            BranchUpdateRequest {
+                id: branch_id,
+                notes: Some(description.trim().to_owned()),
+                ..Default::default()
+            },
+        )
+        .await
+    }
+
     pub async fn delete_virtual_branch(
         &self,
         project: &Project,
         branch_id: BranchId,
     ) -> Result<()> {
+        project.assure_writable()?;
         let project_repository = open_with_verify(project)?;
         let branch_manager = project_repository.branch_manager();
         let mut guard = project.exclusive_worktree_access();
         branch_manager.delete_branch(branch_id, guard.write_permission())
     }
 
+    pub async fn reconcile_ownership(
+        &self,
+        project: &Project,
+    ) -> Result<Vec<branch::OwnershipDrift>> {
+        project.assure_writable()?;
+        let project_repository = open_with_verify(project)?;
+        let mut guard = project.exclusive_worktree_access();
+        branch::reconcile_ownership(&project_repository, guard.write_permission())
+    }
+
+    pub async fn set_assignment_mode(&self, project: &Project, mode: AssignmentMode) -> Result<()> {
+        project.assure_writable()?;
+        let project_repository = open_with_verify(project)?;
+        let mut guard = project.exclusive_worktree_access();
+        branch::set_assignment_mode(&project_repository, mode, guard.write_permission())
+    }
+
+    /// Show the branches of the workspace in the order of `ordered_ids`, followed by those that aren't listed.
+    pub async fn reorder_virtual_branches(
+        &self,
+        project: &Project,
+        ordered_ids: &[BranchId],
+    ) -> Result<()> {
+        project.assure_writable()?;
+        let project_repository = open_with_verify(project)?;
+        let mut guard = project.exclusive_worktree_access();
+        let _ = project_repository.project().create_snapshot(
+            SnapshotDetails::new(OperationKind::ReorderBranches),
+            guard.write_permission(),
+        );
+        project.virtual_branches().reorder_branches(ordered_ids)
+    }
+
+    pub async fn list_unassigned_files(
+        &self,
+        project: &Project,
+    ) -> Result<Vec<branch::VirtualBranchFile>> {
+        let project_repository = open_with_verify(project)?;
+        let mut guard = project.exclusive_worktree_access();
+        branch::list_unassigned_files(&project_repository, guard.write_permission())
+    }
+
+    pub async fn launch_external_diff(
+        &self,
+        project: &Project,
+        path: &Path,
+        side: DiffSide,
+    ) -> Result<()> {
+        let project_repository = open_with_verify(project)?;
+        crate::external_diff::launch_external_diff(&project_repository, path, side)
+    }
+
+    pub async fn detect_ownership_conflicts(
+        &self,
+        project: &Project,
+    ) -> Result<Vec<OwnershipConflict>> {
+        let project_repository = open_with_verify(project)?;
+        crate::ownership_conflicts::detect_ownership_conflicts(&project_repository)
+    }
+
+    /// Move the uncommitted `changes` from the branch with `from_branch_id` to the one with `to_branch_id`,
+    /// without touching the working directory. A change without hunks moves the whole file.
+    pub async fn move_changes(
+        &self,
+        project: &Project,
+        from_branch_id: BranchId,
+        to_branch_id: BranchId,
+        changes: &[OwnershipClaim],
+    ) -> Result<()> {
+        project.assure_writable()?;
+        let project_repository = open_with_verify(project)?;
+        let mut guard = project.exclusive_worktree_access();
+        let _ = project_repository.project().create_snapshot(
+            SnapshotDetails::new(OperationKind::MoveHunk),
+            guard.write_permission(),
+        );
+        crate::ownership_conflicts::move_changes(
+            &project_repository,
+            from_branch_id,
+            to_branch_id,
+            changes,
+            guard.write_permission(),
+        )
+    }
+
+    pub async fn resolve_ownership_conflicts(
+        &self,
+        project: &Project,
+        owner: BranchId,
+        ownership: &BranchOwnershipClaims,
+    ) -> Result<()> {
+        project.assure_writable()?;
+        let project_repository = open_with_verify(project)?;
+        let mut guard = project.exclusive_worktree_access();
+        let _ = project_repository.project().create_snapshot(
+            SnapshotDetails::new(OperationKind::MoveHunk),
+            guard.write_permission(),
+        );
+        crate::ownership_conflicts::resolve_ownership_conflicts(
+            &project_repository,
+            owner,
+            ownership,
+            guard.write_permission(),
+        )
+    }
+
     pub async fn unapply_ownership(
         &self,
         project: &Project,
         ownership: &BranchOwnershipClaims,
     ) -> Result<()> {
+        project.assure_writable()?;
         let project_repository = open_with_verify(project)?;
         let mut guard = project.exclusive_worktree_access();
         let _ = project_repository.project().create_snapshot(
@@ -210,6 +682,7 @@ impl VirtualBranchActions {
     }
 
     pub async fn reset_files(&self, project: &Project, files: &Vec<String>) -> Result<()> {
+        project.assure_writable()?;
         let project_repository = open_with_verify(project)?;
         let mut guard = project.exclusive_worktree_access();
         let _ = project_repository.project().create_snapshot(
@@ -219,6 +692,9 @@ impl VirtualBranchActions {
         branch::reset_files(&project_repository, files).map_err(Into::into)
     }
 
+    /// Amend the hunks of `ownership` into `commit_oid`, which may be any commit of the branch with `branch_id`,
+    /// and rebase the commits after it onto the amended commit. If they conflict with the amended changes,
+    /// the branch is left as it was and the conflicting files are reported in the error.
     pub async fn amend(
         &self,
         project: &Project,
@@ -226,13 +702,16 @@ impl VirtualBranchActions {
         commit_oid: git2::Oid,
         ownership: &BranchOwnershipClaims,
     ) -> Result<git2::Oid> {
+        project.assure_writable()?;
         let project_repository = open_with_verify(project)?;
         let mut guard = project.exclusive_worktree_access();
         let _ = project_repository.project().create_snapshot(
             SnapshotDetails::new(OperationKind::AmendCommit),
             guard.write_permission(),
         );
-        branch::amend(&project_repository, branch_id, commit_oid, ownership)
+        crate::stack::restacking(&project_repository, || {
+            branch::amend(&project_repository, branch_id, commit_oid, ownership)
+        })
     }
 
     pub async fn move_commit_file(
@@ -243,20 +722,23 @@ impl VirtualBranchActions {
         to_commit_oid: git2::Oid,
         ownership: &BranchOwnershipClaims,
     ) -> Result<git2::Oid> {
+        project.assure_writable()?;
         let project_repository = open_with_verify(project)?;
         let mut guard = project.exclusive_worktree_access();
         let _ = project_repository.project().create_snapshot(
             SnapshotDetails::new(OperationKind::MoveCommitFile),
             guard.write_permission(),
         );
-        branch::move_commit_file(
-            &project_repository,
-            branch_id,
-            from_commit_oid,
-            to_commit_oid,
-            ownership,
-        )
-        .map_err(Into::into)
+        crate::stack::restacking(&project_repository, || {
+            branch::move_commit_file(
+                &project_repository,
+                branch_id,
+                from_commit_oid,
+                to_commit_oid,
+                ownership,
+            )
+            .map_err(Into::into)
+        })
     }
 
     pub async fn undo_commit(
@@ -265,13 +747,15 @@ impl VirtualBranchActions {
         branch_id: BranchId,
         commit_oid: git2::Oid,
     ) -> Result<()> {
+        project.assure_writable()?;
         let project_repository = open_with_verify(project)?;
         let mut guard = project.exclusive_worktree_access();
         let snapshot_tree = project_repository
             .project()
             .prepare_snapshot(guard.read_permission());
-        let result: Result<()> =
-            branch::undo_commit(&project_repository, branch_id, commit_oid).map_err(Into::into);
+        let result: Result<()> = crate::stack::restacking(&project_repository, || {
+            branch::undo_commit(&project_repository, branch_id, commit_oid).map_err(Into::into)
+        });
         let _ = snapshot_tree.and_then(|snapshot_tree| {
             project_repository.project().snapshot_commit_undo(
                 snapshot_tree,
@@ -290,14 +774,17 @@ impl VirtualBranchActions {
         commit_oid: git2::Oid,
         offset: i32,
     ) -> Result<()> {
+        project.assure_writable()?;
         let project_repository = open_with_verify(project)?;
         let mut guard = project.exclusive_worktree_access();
         let _ = project_repository.project().create_snapshot(
             SnapshotDetails::new(OperationKind::InsertBlankCommit),
             guard.write_permission(),
         );
-        branch::insert_blank_commit(&project_repository, branch_id, commit_oid, offset)
-            .map_err(Into::into)
+        crate::stack::restacking(&project_repository, || {
+            branch::insert_blank_commit(&project_repository, branch_id, commit_oid, offset)
+                .map_err(Into::into)
+        })
     }
 
     pub async fn reorder_commit(
@@ -307,13 +794,34 @@ impl VirtualBranchActions {
         commit_oid: git2::Oid,
         offset: i32,
     ) -> Result<()> {
+        project.assure_writable()?;
         let project_repository = open_with_verify(project)?;
         let mut guard = project.exclusive_worktree_access();
         let _ = project_repository.project().create_snapshot(
             SnapshotDetails::new(OperationKind::ReorderCommit),
             guard.write_permission(),
         );
-        branch::reorder_commit(&project_repository, branch_id, commit_oid, offset)
+        crate::stack::restacking(&project_repository, || {
+            branch::reorder_commit(&project_repository, branch_id, commit_oid, offset)
+                .map_err(Into::into)
+        })
+    }
+
+    pub async fn reparent_commit(
+        &self,
+        project: &Project,
+        branch_id: BranchId,
+        commit_oid: git2::Oid,
+        new_parent: git2::Oid,
+    ) -> Result<()> {
+        project.assure_writable()?;
+        let project_repository = open_with_verify(project)?;
+        let mut guard = project.exclusive_worktree_access();
+        let _ = project_repository.project().create_snapshot(
+            SnapshotDetails::new(OperationKind::ReparentCommit),
+            guard.write_permission(),
+        );
+        branch::reparent_commit(&project_repository, branch_id, commit_oid, new_parent)
             .map_err(Into::into)
     }
 
@@ -323,13 +831,34 @@ impl VirtualBranchActions {
         branch_id: BranchId,
         target_commit_oid: git2::Oid,
     ) -> Result<()> {
+        project.assure_writable()?;
         let project_repository = open_with_verify(project)?;
         let mut guard = project.exclusive_worktree_access();
         let _ = project_repository.project().create_snapshot(
             SnapshotDetails::new(OperationKind::UndoCommit),
             guard.write_permission(),
         );
-        branch::reset_branch(&project_repository, branch_id, target_commit_oid).map_err(Into::into)
+        crate::stack::restacking(&project_repository, || {
+            branch::reset_branch(&project_repository, branch_id, target_commit_oid)
+                .map_err(Into::into)
+        })
+    }
+
+    /// Stack the branch with `branch_id` on the branch with `parent_id`, or unstack it if it's `None`.
+    pub async fn set_branch_parent(
+        &self,
+        project: &Project,
+        branch_id: BranchId,
+        parent_id: Option<BranchId>,
+    ) -> Result<()> {
+        project.assure_writable()?;
+        let project_repository = open_with_verify(project)?;
+        let mut guard = project.exclusive_worktree_access();
+        let _ = project_repository.project().create_snapshot(
+            SnapshotDetails::new(OperationKind::GenericBranchUpdate),
+            guard.write_permission(),
+        );
+        crate::stack::set_branch_parent(&project_repository, branch_id, parent_id)
     }
 
     pub async fn convert_to_real_branch(
@@ -338,6 +867,7 @@ impl VirtualBranchActions {
         branch_id: BranchId,
         name_conflict_resolution: branch::NameConflictResolution,
     ) -> Result<ReferenceName> {
+        project.assure_writable()?;
         let project_repository = open_with_verify(project)?;
         let mut guard = project.exclusive_worktree_access();
         let snapshot_tree = project_repository
@@ -368,6 +898,7 @@ impl VirtualBranchActions {
         with_force: bool,
         askpass: Option<Option<BranchId>>,
     ) -> Result<()> {
+        project.assure_writable()?;
         let helper = Helper::default();
         let project_repository = open_with_verify(project)?;
         branch::push(&project_repository, branch_id, with_force, &helper, askpass)
@@ -393,13 +924,37 @@ impl VirtualBranchActions {
         branch_id: BranchId,
         commit_oid: git2::Oid,
     ) -> Result<()> {
+        project.assure_writable()?;
         let project_repository = open_with_verify(project)?;
         let mut guard = project.exclusive_worktree_access();
         let _ = project_repository.project().create_snapshot(
             SnapshotDetails::new(OperationKind::SquashCommit),
             guard.write_permission(),
         );
-        branch::squash(&project_repository, branch_id, commit_oid).map_err(Into::into)
+        crate::stack::restacking(&project_repository, || {
+            branch::squash(&project_repository, branch_id, commit_oid).map_err(Into::into)
+        })
+    }
+
+    /// Squash the contiguous range of `commit_ids` of the branch with `branch_id` into a single commit,
+    /// using `message` or the combined messages of the squashed commits, and return its id.
+    pub async fn squash_commits(
+        &self,
+        project: &Project,
+        branch_id: BranchId,
+        commit_ids: Vec<git2::Oid>,
+        message: Option<&str>,
+    ) -> Result<git2::Oid> {
+        project.assure_writable()?;
+        let project_repository = open_with_verify(project)?;
+        let mut guard = project.exclusive_worktree_access();
+        let _ = project_repository.project().create_snapshot(
+            SnapshotDetails::new(OperationKind::SquashCommit),
+            guard.write_permission(),
+        );
+        crate::stack::restacking(&project_repository, || {
+            branch::squash_commits(&project_repository, branch_id, &commit_ids, message)
+        })
     }
 
     pub async fn update_commit_message(
@@ -409,20 +964,25 @@ impl VirtualBranchActions {
         commit_oid: git2::Oid,
         message: &str,
     ) -> Result<()> {
+        project.assure_writable()?;
         let project_repository = open_with_verify(project)?;
         let mut guard = project.exclusive_worktree_access();
         let _ = project_repository.project().create_snapshot(
             SnapshotDetails::new(OperationKind::UpdateCommitMessage),
             guard.write_permission(),
         );
-        branch::update_commit_message(&project_repository, branch_id, commit_oid, message)
-            .map_err(Into::into)
+        crate::stack::restacking(&project_repository, || {
+            branch::update_commit_message(&project_repository, branch_id, commit_oid, message)
+                .map_err(Into::into)
+        })
     }
 
+    /// Fetch all remotes of `project`, limited to `depth` if set.
     pub async fn fetch_from_remotes(
         &self,
         project: &Project,
         askpass: Option<String>,
+        depth: Option<FetchDepth>,
     ) -> Result<FetchResult> {
         let project_repository = ProjectRepository::open(project)?;
 
@@ -430,8 +990,10 @@ impl VirtualBranchActions {
         let remotes = project_repository.repo().remotes_as_string()?;
         let fetch_results: Vec<Result<(), _>> = remotes
             .iter()
-            .map(|remote| project_repository.fetch(remote, &helper, askpass.clone()))
+            .map(|remote| project_repository.fetch(remote, &helper, askpass.clone(), depth))
             .collect();
+        // Fetching may have moved the base branch.
+        crate::base_cache::clear_base_cache(project)?;
 
         let project_data_last_fetched = if fetch_results.iter().any(Result::is_err) {
             FetchResult::Error {
@@ -460,13 +1022,17 @@ impl VirtualBranchActions {
         target_branch_id: BranchId,
         commit_oid: git2::Oid,
     ) -> Result<()> {
+        project.assure_writable()?;
         let project_repository = open_with_verify(project)?;
         let mut guard = project.exclusive_worktree_access();
         let _ = project_repository.project().create_snapshot(
             SnapshotDetails::new(OperationKind::MoveCommit),
             guard.write_permission(),
         );
-        branch::move_commit(&project_repository, target_branch_id, commit_oid).map_err(Into::into)
+        crate::stack::restacking(&project_repository, || {
+            branch::move_commit(&project_repository, target_branch_id, commit_oid)
+                .map_err(Into::into)
+        })
     }
 
     pub async fn create_virtual_branch_from_branch(
@@ -474,6 +1040,7 @@ impl VirtualBranchActions {
         project: &Project,
         branch: &Refname,
     ) -> Result<BranchId> {
+        project.assure_writable()?;
         let project_repository = open_with_verify(project)?;
         let branch_manager = project_repository.branch_manager();
         let mut guard = project.exclusive_worktree_access();
@@ -481,6 +1048,98 @@ impl VirtualBranchActions {
             .create_virtual_branch_from_branch(branch, guard.write_permission())
             .map_err(Into::into)
     }
+
+    pub async fn adopt_orphan_commits(
+        &self,
+        project: &Project,
+        commit_ids: &[git2::Oid],
+        branch_name: &str,
+    ) -> Result<BranchId> {
+        project.assure_writable()?;
+        let project_repository = open_with_verify(project)?;
+        let branch_manager = project_repository.branch_manager();
+        let mut guard = project.exclusive_worktree_access();
+        branch_manager
+            .adopt_orphan_commits(commit_ids, branch_name, guard.write_permission())
+            .map_err(Into::into)
+    }
+
+    pub async fn apply_stack(
+        &self,
+        project: &Project,
+        stack: &[BranchId],
+    ) -> Result<StackApplication> {
+        project.assure_writable()?;
+        let project_repository = open_with_verify(project)?;
+        let branch_manager = project_repository.branch_manager();
+        let mut guard = project.exclusive_worktree_access();
+        branch_manager
+            .apply_stack(stack, guard.write_permission())
+            .map_err(Into::into)
+    }
+
+    pub async fn import_all_local_branches(
+        &self,
+        project: &Project,
+        options: &LocalBranchImportOptions,
+    ) -> Result<LocalBranchImport> {
+        project.assure_writable()?;
+        let project_repository = open_with_verify(project)?;
+        let branch_manager = project_repository.branch_manager();
+        let mut guard = project.exclusive_worktree_access();
+        branch_manager
+            .import_all_local_branches(options, guard.write_permission())
+            .map_err(Into::into)
+    }
+
+    /// List the files that are still conflicted after applying a branch, along with their conflict hunks.
+    pub async fn list_conflicts(&self, project: &Project) -> Result<Vec<ConflictedFile>> {
+        let project_repository = open_with_verify(project)?;
+        conflicts::list_conflicts(&project_repository)
+    }
+
+    pub async fn resolve_conflict(
+        &self,
+        project: &Project,
+        path: &str,
+        resolution: ConflictResolution,
+    ) -> Result<()> {
+        self.resolve_conflicts(project, &[(path, resolution)]).await
+    }
+
+    pub async fn resolve_conflicts<P: AsRef<Path>>(
+        &self,
+        project: &Project,
+        resolutions: &[(P, ConflictResolution)],
+    ) -> Result<()> {
+        project.assure_writable()?;
+        let project_repository = open_with_verify(project)?;
+        let mut guard = project.exclusive_worktree_access();
+        // nothing is resolved unless all of the files can be
+        for (path, _) in resolutions {
+            let path = path.as_ref();
+            if !conflicts::is_conflicting(&project_repository, Some(path))? {
+                return Err(anyhow!("file '{}' is not conflicted", path.display())
+                    .context(Code::Validation));
+            }
+        }
+        let _ = project_repository.project().create_snapshot(
+            SnapshotDetails::new(OperationKind::ResolveConflict),
+            guard.write_permission(),
+        );
+        for (path, resolution) in resolutions {
+            conflicts::resolve_with(&project_repository, path, resolution.clone())?;
+        }
+        Ok(())
+    }
+}
+
+/// Fail with a validation error if `name`, once normalized, can't be the name of a branch's reference.
+/// The name itself is kept as typed, as it's only normalized where the reference is derived from it.
+fn validate_name(name: &str) -> Result<()> {
+    validate_refname(name)
+        .map(|_normalized| ())
+        .map_err(|violation| anyhow::Error::from(violation).context(Code::Validation))
 }
 
 fn open_with_verify(project: &Project) -> Result<ProjectRepository> {
@@ -11,8 +11,9 @@ use gitbutler_command_context::ProjectRepository;
 use gitbutler_project::FetchResult;
 use gitbutler_reference::ReferenceName;
 use gitbutler_reference::{Refname, RemoteRefname};
+use gitbutler_repo::credentials::{Credential, HelpError, Helper};
 use gitbutler_repo::{LogUntil, RepoActionsExt, RepositoryExt};
-use serde::Serialize;
+use serde::{Deserialize, Serialize};
 
 use super::r#virtual as vb;
 use crate::branch_manager::BranchManagerExt;
@@ -21,7 +22,7 @@ use crate::integration::{get_workspace_head, update_gitbutler_integration};
 use crate::remote::{commit_to_remote_commit, RemoteCommit};
 use crate::{VirtualBranchHunk, VirtualBranchesExt};
 use gitbutler_branch::GITBUTLER_INTEGRATION_REFERENCE;
-use gitbutler_error::error::Marker;
+use gitbutler_error::error::{Code, Marker};
 use gitbutler_project::access::WorktreeWritePermission;
 use gitbutler_repo::rebase::cherry_rebase;
 
@@ -43,6 +44,18 @@ pub struct BaseBranch {
     pub last_fetched_ms: Option<u128>,
 }
 
+/// How far the commit the workspace is based on and the head of the base branch diverged.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct BranchDivergence {
+    /// The amount of commits the workspace is based on that aren't in the base branch.
+    pub ahead: usize,
+    /// The amount of commits of the base branch that the workspace isn't based on yet.
+    pub behind: usize,
+    /// `true` if the two have no common history, in which case all of their commits are counted.
+    pub unrelated_histories: bool,
+}
+
 pub(crate) fn get_base_branch_data(project_repository: &ProjectRepository) -> Result<BaseBranch> {
     let target = default_target(&project_repository.project().gb_dir())?;
     let base = target_to_base_branch(project_repository, &target)?;
@@ -157,13 +170,16 @@ pub(crate) fn set_base_branch(
         .context("Failed to peel HEAD reference to commit")?;
 
     // calculate the commit as the merge-base between HEAD in project_repository and this target commit
-    let target_commit_oid = repo
-        .merge_base(current_head_commit.id(), target_branch_head.id())
-        .context(format!(
-            "Failed to calculate merge base between {} and {}",
-            current_head_commit.id(),
-            target_branch_head.id()
-        ))?;
+    let target_commit_oid = crate::base_cache::merge_base(
+        project_repository,
+        current_head_commit.id(),
+        target_branch_head.id(),
+    )?
+    .context(format!(
+        "Failed to calculate merge base between {} and {}",
+        current_head_commit.id(),
+        target_branch_head.id()
+    ))?;
 
     let target = Target {
         branch: target_branch_ref.clone(),
@@ -257,6 +273,7 @@ pub(crate) fn set_base_branch(
                 applied: true,
                 in_workspace: true,
                 not_in_workspace_wip_change_id: None,
+                parent_id: None,
             };
 
             vb_state.set_branch(branch)?;
@@ -271,6 +288,150 @@ pub(crate) fn set_base_branch(
     Ok(base)
 }
 
+/// Ask the remote for the branch it advertises as default, i.e. what its `HEAD` points to, and make it the base branch.
+/// The remote of the current base branch is asked, or `origin` or the only remote if there is no base branch yet.
+/// If the remote can't be reached, its `HEAD` as known locally is used instead, and if that's unknown as well
+/// the current base branch is kept, or without one, the branch of the remote named like the local default branch
+/// is used.
+pub(crate) fn autodetect_base_branch(
+    project_repository: &ProjectRepository,
+    credentials: &Helper,
+) -> Result<BaseBranch> {
+    let repo = project_repository.repo();
+    let current_target = default_target(&project_repository.project().gb_dir()).ok();
+
+    let remote_name = match &current_target {
+        Some(target) => Some(target.branch.remote().to_owned()),
+        None => {
+            let remotes = repo.remotes().context("failed to list remotes")?;
+            let remotes: Vec<_> = remotes.iter().flatten().collect();
+            if remotes.contains(&"origin") {
+                Some("origin".to_owned())
+            } else if let [only_remote] = remotes.as_slice() {
+                Some((*only_remote).to_owned())
+            } else {
+                None
+            }
+        }
+    };
+    let Some(remote_name) = remote_name else {
+        return match current_target {
+            Some(target) => target_to_base_branch(project_repository, &target),
+            None => Err(anyhow!(
+                "couldn't detect the default branch as there is no remote to ask"
+            )),
+        };
+    };
+    let detected_branch = remote_default_branch(project_repository, &remote_name, credentials)?;
+
+    match (detected_branch, current_target) {
+        (Some(branch), _) => set_base_branch(project_repository, &branch),
+        (None, Some(target)) => target_to_base_branch(project_repository, &target),
+        (None, None) => {
+            let branch = local_default_branch_names(repo)
+                .into_iter()
+                .map(|name| RemoteRefname::new(&remote_name, &name))
+                .find(|branch| repo.find_reference(&branch.to_string()).is_ok())
+                .with_context(|| {
+                    format!("couldn't detect the default branch of remote {remote_name}")
+                })?;
+            set_base_branch(project_repository, &branch)
+        }
+    }
+}
+
+/// Returns the default branch advertised by `remote_name`, or the one it had when last fetched
+/// if it can't be reached.
+fn remote_default_branch(
+    project_repository: &ProjectRepository,
+    remote_name: &str,
+    credentials: &Helper,
+) -> Result<Option<RemoteRefname>> {
+    let repo = project_repository.repo();
+    let advertised_head = advertised_default_branch(project_repository, remote_name, credentials)?;
+    if advertised_head.is_none() {
+        tracing::warn!(
+            "failed to ask remote {remote_name} for its HEAD - using its last known HEAD"
+        );
+    }
+
+    let branch_name = match advertised_head {
+        Some(head) => head.strip_prefix("refs/heads/").map(ToOwned::to_owned),
+        None => repo
+            .find_reference(&format!("refs/remotes/{remote_name}/HEAD"))
+            .ok()
+            .and_then(|head| {
+                head.symbolic_target()?
+                    .strip_prefix(&format!("refs/remotes/{remote_name}/"))
+                    .map(ToOwned::to_owned)
+            }),
+    };
+    Ok(branch_name.map(|branch_name| RemoteRefname::new(remote_name, &branch_name)))
+}
+
+/// Ask `remote_name` for the branch its `HEAD` points to, authenticating like a fetch does,
+/// or return `None` if it can't be reached with any of the credentials.
+fn advertised_default_branch(
+    project_repository: &ProjectRepository,
+    remote_name: &str,
+    credentials: &Helper,
+) -> Result<Option<String>> {
+    let candidates = match credentials.help(project_repository, remote_name) {
+        Ok(candidates) => candidates,
+        Err(HelpError::Git(err)) if err.code() == git2::ErrorCode::NotFound => {
+            return Err(err).context(format!("failed to find remote {remote_name}"));
+        }
+        Err(err) => {
+            tracing::warn!("failed to get credentials for remote {remote_name}: {err}");
+            return Ok(None);
+        }
+    };
+    for (mut remote, mut callbacks) in candidates {
+        if callbacks.is_empty() {
+            callbacks.push(Credential::Noop);
+        }
+        for callback in callbacks {
+            // there is nobody to ask for the passphrase of a key, so try the next credential
+            let Ok(credential) = callback.with_passphrase(|_| Ok(None)) else {
+                continue;
+            };
+            let mut cbs: git2::RemoteCallbacks = credential.into();
+            if project_repository
+                .project()
+                .omit_certificate_check
+                .unwrap_or(false)
+            {
+                cbs.certificate_check(|_, _| Ok(git2::CertificateCheckStatus::CertificateOk));
+            }
+            match remote.connect_auth(git2::Direction::Fetch, Some(cbs), None) {
+                Ok(connection) => {
+                    return Ok(connection
+                        .default_branch()
+                        .ok()
+                        .and_then(|head| head.as_str().map(ToOwned::to_owned)));
+                }
+                Err(err) => {
+                    tracing::warn!("failed to connect to remote {remote_name}: {err}");
+                }
+            }
+        }
+    }
+    Ok(None)
+}
+
+/// Returns the names a local default branch may have, most likely first: the one configured in
+/// `init.defaultBranch`, followed by `main` and `master`.
+fn local_default_branch_names(repo: &git2::Repository) -> Vec<String> {
+    let configured = repo
+        .config()
+        .ok()
+        .and_then(|config| config.get_string("init.defaultBranch").ok());
+    configured
+        .into_iter()
+        .chain(["main".to_owned(), "master".to_owned()])
+        .collect()
+}
+
 pub(crate) fn set_target_push_remote(
     project_repository: &ProjectRepository,
     push_remote_name: &str,
@@ -323,6 +484,45 @@ fn _print_tree(repo: &git2::Repository, tree: &git2::Tree) -> Result<()> {
     Ok(())
 }
 
+/// Return the applied branches whose uncommitted changes or commits conflict with the base branch,
+/// merging them onto its current head just like [`update_base_branch()`] would, but without changing anything.
+pub(crate) fn branches_conflicting_with_base_update(
+    project_repository: &ProjectRepository,
+) -> Result<Vec<Branch>> {
+    let target = default_target(&project_repository.project().gb_dir())?;
+    let repo = project_repository.repo();
+    let new_target_commit = repo
+        .find_branch_by_refname(&target.branch.clone().into())?
+        .ok_or(anyhow!("failed to get branch"))?
+        .get()
+        .peel_to_commit()?;
+    if new_target_commit.id() == target.sha {
+        return Ok(vec![]);
+    }
+    let new_target_tree = new_target_commit.tree()?;
+    let old_target_tree = repo.find_commit(target.sha)?.tree()?;
+
+    let conflicts = |tree: &git2::Tree| -> Result<bool> {
+        Ok(repo
+            .merge_trees(&old_target_tree, tree, &new_target_tree, None)?
+            .has_conflicts())
+    };
+    let mut conflicting = Vec::new();
+    for branch in project_repository
+        .project()
+        .virtual_branches()
+        .list_branches_in_workspace()?
+    {
+        let head_tree = repo.find_commit(branch.head)?.tree()?;
+        if conflicts(&repo.find_tree(branch.tree)?)?
+            || (branch.head != target.sha && conflicts(&head_tree)?)
+        {
+            conflicting.push(branch);
+        }
+    }
+    Ok(conflicting)
+}
+
 // try to update the target branch
 // this means that we need to:
 // determine if what the target branch is now pointing to is mergeable with our current working directory
@@ -353,6 +553,14 @@ pub(crate) fn update_base_branch(
         return Ok(unapplied_branch_names);
     }
 
+    // the worktree is rebuilt from the branches, which would drop changes that none of them owns
+    if !vb::list_unassigned_files(project_repository, perm)?.is_empty() {
+        return Err(
+            anyhow!("assign all changes to a branch before updating the base branch")
+                .context(Code::Validation),
+        );
+    }
+
     let new_target_tree = new_target_commit
         .tree()
         .context("failed to get new target commit tree")?;
@@ -365,180 +573,197 @@ pub(crate) fn update_base_branch(
     let vb_state = project_repository.project().virtual_branches();
     let integration_commit = get_workspace_head(&vb_state, project_repository)?;
 
-    // try to update every branch
-    let updated_vbranches =
-        vb::get_status_by_branch(project_repository, Some(&integration_commit), None)?
-            .0
-            .into_iter()
-            .map(|(branch, _)| branch)
-            .map(|mut branch: Branch| -> Result<Option<Branch>> {
-                let branch_tree = repo.find_tree(branch.tree)?;
-
-                let branch_head_commit = repo.find_commit(branch.head).context(format!(
-                    "failed to find commit {} for branch {}",
-                    branch.head, branch.id
-                ))?;
-                let branch_head_tree = branch_head_commit.tree().context(format!(
-                    "failed to find tree for commit {} for branch {}",
-                    branch.head, branch.id
-                ))?;
+    let mut statuses =
+        vb::get_status_by_branch(project_repository, Some(&integration_commit), None)?.0;
+    // Stacked branches build on the updated heads of their parents.
+    crate::stack::sort_parents_first(&mut statuses, |(branch, _)| branch);
 
-                let result_integrated_detected = |mut branch: Branch| -> Result<Option<Branch>> {
-                    // branch head tree is the same as the new target tree.
-                    // meaning we can safely use the new target commit as the branch head.
-
-                    branch.head = new_target_commit.id();
-
-                    // it also means that the branch is fully integrated into the target.
-                    // disconnect it from the upstream
-                    branch.upstream = None;
-                    branch.upstream_head = None;
-
-                    let non_commited_files = gitbutler_diff::trees(
-                        project_repository.repo(),
-                        &branch_head_tree,
-                        &branch_tree,
-                    )?;
-                    if non_commited_files.is_empty() {
-                        // if there are no commited files, then the branch is fully merged
-                        // and we can delete it.
-                        vb_state.mark_as_not_in_workspace(branch.id)?;
-                        project_repository.delete_branch_reference(&branch)?;
-                        Ok(None)
-                    } else {
-                        vb_state.set_branch(branch.clone())?;
-                        Ok(Some(branch))
+    // try to update every branch
+    let updated_vbranches = statuses
+        .into_iter()
+        .map(|(branch, _)| branch)
+        .map(|mut branch: Branch| -> Result<Option<Branch>> {
+            // the parent may have left the workspace while updating, which unstacks the branch
+            let is_stacked = match branch.parent_id {
+                Some(parent_id) => {
+                    let parent_in_workspace =
+                        vb_state.try_branch_in_workspace(parent_id)?.is_some();
+                    if !parent_in_workspace {
+                        branch.parent_id = None;
                     }
-                };
-
-                if branch_head_tree.id() == new_target_tree.id() {
-                    return result_integrated_detected(branch);
-                }
-
-                // try to merge branch head with new target
-                let mut branch_tree_merge_index = repo
-                    .merge_trees(&old_target_tree, &branch_tree, &new_target_tree, None)
-                    .context(format!("failed to merge trees for branch {}", branch.id))?;
-
-                if branch_tree_merge_index.has_conflicts() {
-                    // branch tree conflicts with new target, unapply branch for now. we'll handle it later, when user applies it back.
-                    let branch_manager = project_repository.branch_manager();
-                    let unapplied_real_branch = branch_manager.convert_to_real_branch(
-                        branch.id,
-                        Default::default(),
-                        perm,
-                    )?;
-
-                    unapplied_branch_names.push(unapplied_real_branch);
-
-                    return Ok(None);
-                }
-
-                let branch_merge_index_tree_oid =
-                    branch_tree_merge_index.write_tree_to(project_repository.repo())?;
-
-                if branch_merge_index_tree_oid == new_target_tree.id() {
-                    return result_integrated_detected(branch);
+                    parent_in_workspace
                 }
+                None => false,
+            };
+            let branch_tree = repo.find_tree(branch.tree)?;
 
-                if branch.head == target.sha {
-                    // there are no commits on the branch, so we can just update the head to the new target and calculate the new tree
-                    branch.head = new_target_commit.id();
-                    branch.tree = branch_merge_index_tree_oid;
+            let branch_head_commit = repo.find_commit(branch.head).context(format!(
+                "failed to find commit {} for branch {}",
+                branch.head, branch.id
+            ))?;
+            let branch_head_tree = branch_head_commit.tree().context(format!(
+                "failed to find tree for commit {} for branch {}",
+                branch.head, branch.id
+            ))?;
+
+            let result_integrated_detected = |mut branch: Branch| -> Result<Option<Branch>> {
+                // branch head tree is the same as the new target tree.
+                // meaning we can safely use the new target commit as the branch head.
+
+                branch.head = new_target_commit.id();
+
+                // it also means that the branch is fully integrated into the target.
+                // disconnect it from the upstream
+                branch.upstream = None;
+                branch.upstream_head = None;
+
+                let non_commited_files = gitbutler_diff::trees(
+                    project_repository.repo(),
+                    &branch_head_tree,
+                    &branch_tree,
+                )?;
+                if non_commited_files.is_empty() {
+                    // if there are no commited files, then the branch is fully merged
+                    // and we can delete it.
+                    vb_state.mark_as_not_in_workspace(branch.id)?;
+                    project_repository.delete_branch_reference(&branch)?;
+                    Ok(None)
+                } else {
                     vb_state.set_branch(branch.clone())?;
-                    return Ok(Some(branch));
+                    Ok(Some(branch))
                 }
+            };
 
-                let mut branch_head_merge_index = repo
-                    .merge_trees(&old_target_tree, &branch_head_tree, &new_target_tree, None)
-                    .context(format!(
-                        "failed to merge head tree for branch {}",
-                        branch.id
-                    ))?;
-
-                if branch_head_merge_index.has_conflicts() {
-                    // branch commits conflict with new target, make sure the branch is
-                    // unapplied. conflicts witll be dealt with when applying it back.
-                    let branch_manager = project_repository.branch_manager();
-                    let unapplied_real_branch = branch_manager.convert_to_real_branch(
-                        branch.id,
-                        Default::default(),
-                        perm,
-                    )?;
-                    unapplied_branch_names.push(unapplied_real_branch);
-
-                    return Ok(None);
-                }
+            if branch_head_tree.id() == new_target_tree.id() {
+                return result_integrated_detected(branch);
+            }
+
+            // try to merge branch head with new target
+            let mut branch_tree_merge_index = repo
+                .merge_trees(&old_target_tree, &branch_tree, &new_target_tree, None)
+                .context(format!("failed to merge trees for branch {}", branch.id))?;
+
+            if branch_tree_merge_index.has_conflicts() {
+                // branch tree conflicts with new target, unapply branch for now. we'll handle it later, when user applies it back.
+                let branch_manager = project_repository.branch_manager();
+                let unapplied_real_branch =
+                    branch_manager.convert_to_real_branch(branch.id, Default::default(), perm)?;
+
+                unapplied_branch_names.push(unapplied_real_branch);
+
+                return Ok(None);
+            }
+
+            let branch_merge_index_tree_oid =
+                branch_tree_merge_index.write_tree_to(project_repository.repo())?;
+
+            if branch_merge_index_tree_oid == new_target_tree.id() {
+                return result_integrated_detected(branch);
+            }
+
+            if is_stacked {
+                // its commits are restacked onto the updated parent afterwards, rebasing them onto
+                // the new target here would duplicate the commits of the parent
+                branch.tree = branch_merge_index_tree_oid;
+                vb_state.set_branch(branch.clone())?;
+                return Ok(Some(branch));
+            }
+
+            if branch.head == target.sha {
+                // there are no commits on the branch, so we can just update the head to the new target and calculate the new tree
+                branch.head = new_target_commit.id();
+                branch.tree = branch_merge_index_tree_oid;
+                vb_state.set_branch(branch.clone())?;
+                return Ok(Some(branch));
+            }
+
+            let mut branch_head_merge_index = repo
+                .merge_trees(&old_target_tree, &branch_head_tree, &new_target_tree, None)
+                .context(format!(
+                    "failed to merge head tree for branch {}",
+                    branch.id
+                ))?;
 
-                // branch commits do not conflict with new target, so lets merge them
-                let branch_head_merge_tree_oid = branch_head_merge_index
-                    .write_tree_to(project_repository.repo())
-                    .context(format!(
-                        "failed to write head merge index for {}",
-                        branch.id
-                    ))?;
-
-                let ok_with_force_push = branch.allow_rebasing;
-
-                let result_merge = |mut branch: Branch| -> Result<Option<Branch>> {
-                    // branch was pushed to upstream, and user doesn't like force pushing.
-                    // create a merge commit to avoid the need of force pushing then.
-                    let branch_head_merge_tree = repo
-                        .find_tree(branch_head_merge_tree_oid)
-                        .context("failed to find tree")?;
-
-                    let new_target_head = project_repository
-                        .commit(
-                            format!(
-                                "Merged {}/{} into {}",
-                                target.branch.remote(),
-                                target.branch.branch(),
-                                branch.name,
-                            )
-                            .as_str(),
-                            &branch_head_merge_tree,
-                            &[&branch_head_commit, &new_target_commit],
-                            None,
+            if branch_head_merge_index.has_conflicts() {
+                // branch commits conflict with new target, make sure the branch is
+                // unapplied. conflicts witll be dealt with when applying it back.
+                let branch_manager = project_repository.branch_manager();
+                let unapplied_real_branch =
+                    branch_manager.convert_to_real_branch(branch.id, Default::default(), perm)?;
+                unapplied_branch_names.push(unapplied_real_branch);
+
+                return Ok(None);
+            }
+
+            // branch commits do not conflict with new target, so lets merge them
+            let branch_head_merge_tree_oid = branch_head_merge_index
+                .write_tree_to(project_repository.repo())
+                .context(format!(
+                    "failed to write head merge index for {}",
+                    branch.id
+                ))?;
+
+            let ok_with_force_push = branch.allow_rebasing;
+
+            let result_merge = |mut branch: Branch| -> Result<Option<Branch>> {
+                // branch was pushed to upstream, and user doesn't like force pushing.
+                // create a merge commit to avoid the need of force pushing then.
+                let branch_head_merge_tree = repo
+                    .find_tree(branch_head_merge_tree_oid)
+                    .context("failed to find tree")?;
+
+                let new_target_head = project_repository
+                    .commit(
+                        format!(
+                            "Merged {}/{} into {}",
+                            target.branch.remote(),
+                            target.branch.branch(),
+                            branch.name,
                         )
-                        .context("failed to commit merge")?;
+                        .as_str(),
+                        &branch_head_merge_tree,
+                        &[&branch_head_commit, &new_target_commit],
+                        None,
+                    )
+                    .context("failed to commit merge")?;
+
+                branch.head = new_target_head;
+                branch.tree = branch_merge_index_tree_oid;
+                vb_state.set_branch(branch.clone())?;
+                Ok(Some(branch))
+            };
 
-                    branch.head = new_target_head;
-                    branch.tree = branch_merge_index_tree_oid;
-                    vb_state.set_branch(branch.clone())?;
-                    Ok(Some(branch))
-                };
+            if branch.upstream.is_some() && !ok_with_force_push {
+                return result_merge(branch);
+            }
 
-                if branch.upstream.is_some() && !ok_with_force_push {
-                    return result_merge(branch);
-                }
+            // branch was not pushed to upstream yet. attempt a rebase,
+            let rebased_head_oid = cherry_rebase(
+                project_repository,
+                new_target_commit.id(),
+                new_target_commit.id(),
+                branch.head,
+            );
 
-                // branch was not pushed to upstream yet. attempt a rebase,
-                let rebased_head_oid = cherry_rebase(
-                    project_repository,
-                    new_target_commit.id(),
-                    new_target_commit.id(),
-                    branch.head,
-                );
-
-                // rebase failed, just do the merge
-                if rebased_head_oid.is_err() {
-                    return result_merge(branch);
-                }
+            // rebase failed, just do the merge
+            if rebased_head_oid.is_err() {
+                return result_merge(branch);
+            }
 
-                if let Some(rebased_head_oid) = rebased_head_oid? {
-                    // rebase worked out, rewrite the branch head
-                    branch.head = rebased_head_oid;
-                    branch.tree = branch_merge_index_tree_oid;
-                    vb_state.set_branch(branch.clone())?;
-                    return Ok(Some(branch));
-                }
+            if let Some(rebased_head_oid) = rebased_head_oid? {
+                // rebase worked out, rewrite the branch head
+                branch.head = rebased_head_oid;
+                branch.tree = branch_merge_index_tree_oid;
+                vb_state.set_branch(branch.clone())?;
+                return Ok(Some(branch));
+            }
 
-                result_merge(branch)
-            })
-            .collect::<Result<Vec<_>>>()?
-            .into_iter()
-            .flatten()
-            .collect::<Vec<_>>();
+            result_merge(branch)
+        })
+        .collect::<Result<Vec<_>>>()?
+        .into_iter()
+        .flatten()
+        .collect::<Vec<_>>();
 
     // ok, now all the problematic branches have been unapplied
     // now we calculate and checkout new tree for the working directory
@@ -0,0 +1,168 @@
+use std::path::{Path, PathBuf};
+
+use anyhow::{Context, Result};
+use gitbutler_command_context::ProjectRepository;
+use gitbutler_fs::read_toml_file_or_default;
+use gitbutler_project::Project;
+use serde::{Deserialize, Serialize};
+
+use crate::{base::BranchDivergence, VirtualBranchesExt};
+
+/// The name of the file in the GitButler directory of a project that holds the cached merge-base.
+const BASE_CACHE_FILE_NAME: &str = "base_cache.toml";
+
+/// The merge-base between the head of the base branch and the commit the workspace is based on,
+/// and how far they diverged, along with the commits it was computed from, so it's only computed
+/// again once one of them moves.
+#[derive(Serialize, Deserialize, Debug, Default)]
+struct BaseCache {
+    #[serde(with = "gitbutler_serde::serde::oid_opt", default)]
+    target_sha: Option<git2::Oid>,
+    #[serde(with = "gitbutler_serde::serde::oid_opt", default)]
+    base_head: Option<git2::Oid>,
+    #[serde(with = "gitbutler_serde::serde::oid_opt", default)]
+    merge_base: Option<git2::Oid>,
+    #[serde(default)]
+    divergence: Option<BranchDivergence>,
+}
+
+fn base_cache_path(project: &Project) -> PathBuf {
+    project.gb_dir().join(BASE_CACHE_FILE_NAME)
+}
+
+/// Returns what's cached for `target_sha` and `base_head`, or an empty cache for them if the cache is for other commits.
+fn read_base_cache(path: &Path, target_sha: git2::Oid, base_head: git2::Oid) -> Result<BaseCache> {
+    let cache: BaseCache = read_toml_file_or_default(path)?;
+    if cache.target_sha == Some(target_sha) && cache.base_head == Some(base_head) {
+        return Ok(cache);
+    }
+    Ok(BaseCache {
+        target_sha: Some(target_sha),
+        base_head: Some(base_head),
+        ..Default::default()
+    })
+}
+
+/// Returns the merge-base between the head of the base branch and the commit the workspace is based on,
+/// computing it only if it isn't cached yet, or if the base branch or the target moved since.
+pub(crate) fn base_merge_base(ctx: &ProjectRepository) -> Result<git2::Oid> {
+    let target = ctx.project().virtual_branches().get_default_target()?;
+    let base_head = ctx
+        .repo()
+        .find_reference(&target.branch.to_string())
+        .and_then(|reference| reference.peel_to_commit())
+        .with_context(|| format!("failed to find head of base branch {}", target.branch))?
+        .id();
+    merge_base(ctx, base_head, target.sha)?.with_context(|| {
+        format!(
+            "base branch {} and the workspace base {} have no merge base",
+            target.branch, target.sha
+        )
+    })
+}
+
+/// Returns the merge-base of the commits `one` and `two`, or `None` if they have no common history.
+///
+/// If they are the head of the base branch and the commit the workspace is based on, in any order, it's
+/// taken from the cache, and cached if it wasn't yet, so all callers share it.
+pub(crate) fn merge_base(
+    ctx: &ProjectRepository,
+    one: git2::Oid,
+    two: git2::Oid,
+) -> Result<Option<git2::Oid>> {
+    let repo = ctx.repo();
+    let compute = || match repo.merge_base(one, two) {
+        Ok(merge_base) => Ok(Some(merge_base)),
+        Err(err) if err.code() == git2::ErrorCode::NotFound => Ok(None),
+        Err(err) => Err(err)
+            .with_context(|| format!("failed to calculate merge base between {one} and {two}")),
+    };
+
+    let Ok(target) = ctx.project().virtual_branches().get_default_target() else {
+        return compute();
+    };
+    let base_head = match repo
+        .find_reference(&target.branch.to_string())
+        .and_then(|reference| reference.peel_to_commit())
+    {
+        Ok(commit) => commit.id(),
+        Err(_) => return compute(),
+    };
+    if (one, two) != (base_head, target.sha) && (two, one) != (base_head, target.sha) {
+        return compute();
+    }
+
+    let path = base_cache_path(ctx.project());
+    let mut cache = read_base_cache(&path, target.sha, base_head)?;
+    if let Some(merge_base) = cache.merge_base {
+        return Ok(Some(merge_base));
+    }
+    let merge_base = compute()?;
+    if merge_base.is_some() {
+        cache.merge_base = merge_base;
+        gitbutler_fs::write(&path, toml::to_string(&cache)?)?;
+    }
+    Ok(merge_base)
+}
+
+/// Returns how far the commit the workspace is based on and the head of the base branch diverged, computing
+/// it only if it isn't cached yet, or if the base branch or the target moved since.
+/// Returns `None` if the base branch doesn't exist.
+pub(crate) fn base_divergence(ctx: &ProjectRepository) -> Result<Option<BranchDivergence>> {
+    let target = ctx.project().virtual_branches().get_default_target()?;
+    let repo = ctx.repo();
+    let base_head = match repo
+        .find_reference(&target.branch.to_string())
+        .and_then(|reference| reference.peel_to_commit())
+    {
+        Ok(commit) => commit.id(),
+        Err(err) if err.code() == git2::ErrorCode::NotFound => return Ok(None),
+        Err(err) => {
+            return Err(err)
+                .with_context(|| format!("failed to find head of base branch {}", target.branch))
+        }
+    };
+
+    let path = base_cache_path(ctx.project());
+    let cache = read_base_cache(&path, target.sha, base_head)?;
+    if let Some(divergence) = cache.divergence {
+        return Ok(Some(divergence));
+    }
+
+    let merge_base = merge_base(ctx, target.sha, base_head)?;
+    let divergence = BranchDivergence {
+        ahead: count_commits(repo, target.sha, merge_base)?,
+        behind: count_commits(repo, base_head, merge_base)?,
+        unrelated_histories: merge_base.is_none(),
+    };
+    // re-read what was cached along with the merge-base
+    let mut cache = read_base_cache(&path, target.sha, base_head)?;
+    cache.divergence = Some(divergence);
+    gitbutler_fs::write(&path, toml::to_string(&cache)?)?;
+    Ok(Some(divergence))
+}
+
+/// Count the commits reachable from `tip`, but not from `hide` if set.
+fn count_commits(
+    repo: &git2::Repository,
+    tip: git2::Oid,
+    hide: Option<git2::Oid>,
+) -> Result<usize> {
+    let mut revwalk = repo.revwalk()?;
+    revwalk.push(tip)?;
+    if let Some(hide) = hide {
+        revwalk.hide(hide)?;
+    }
+    Ok(revwalk.count())
+}
+
+/// Forget the cached merge-base of `project`, which is needed whenever the base branch might have moved.
+pub(crate) fn clear_base_cache(project: &Project) -> Result<()> {
+    let path = base_cache_path(project);
+    match std::fs::remove_file(&path) {
+        Err(err) if err.kind() != std::io::ErrorKind::NotFound => {
+            Err(err).with_context(|| format!("failed to remove {}", path.display()))
+        }
+        _ => Ok(()),
+    }
+}
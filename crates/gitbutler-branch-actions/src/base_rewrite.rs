@@ -0,0 +1,59 @@
+use anyhow::{Context, Result};
+use gitbutler_branch::BranchId;
+use gitbutler_command_context::ProjectRepository;
+use serde::Serialize;
+
+use crate::VirtualBranchesExt;
+
+/// The history of the base branch was rewritten upstream, for instance by a force-push,
+/// so it doesn't contain the commit the workspace is based on anymore.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct BaseHistoryRewrite {
+    /// The commit the workspace is based on, which is no longer part of the base branch.
+    #[serde(with = "gitbutler_serde::serde::oid")]
+    pub previous_base: git2::Oid,
+    /// The head of the rewritten base branch, onto which the commits of the affected branches should be
+    /// rebased to re-anchor them.
+    #[serde(with = "gitbutler_serde::serde::oid")]
+    pub suggested_base: git2::Oid,
+    /// The most recent commit the previous base and the rewritten base branch still have in common, if any.
+    /// Commits between it and `previous_base` were dropped upstream.
+    #[serde(with = "gitbutler_serde::serde::oid_opt")]
+    pub common_ancestor: Option<git2::Oid>,
+    /// The branches in the workspace with commits on top of the previous base, in workspace order.
+    pub affected_branches: Vec<BranchId>,
+}
+
+/// Return how the base branch was rewritten if its head, as last fetched, doesn't descend from the commit
+/// the workspace is based on anymore, or `None` if it only moved forward or didn't move at all.
+pub(crate) fn detect_base_history_rewrite(
+    ctx: &ProjectRepository,
+) -> Result<Option<BaseHistoryRewrite>> {
+    let vb_state = ctx.project().virtual_branches();
+    let target = vb_state.get_default_target()?;
+    let repo = ctx.repo();
+    let base_head = repo
+        .find_reference(&target.branch.to_string())
+        .and_then(|reference| reference.peel_to_commit())
+        .with_context(|| format!("failed to find head of base branch {}", target.branch))?
+        .id();
+
+    if base_head == target.sha || repo.graph_descendant_of(base_head, target.sha)? {
+        return Ok(None);
+    }
+
+    let common_ancestor = crate::base_cache::merge_base(ctx, base_head, target.sha)?;
+    let mut branches = vb_state.list_branches_in_workspace()?;
+    branches.sort_by_key(|branch| branch.order);
+    Ok(Some(BaseHistoryRewrite {
+        previous_base: target.sha,
+        suggested_base: base_head,
+        common_ancestor,
+        affected_branches: branches
+            .into_iter()
+            .filter(|branch| branch.head != target.sha)
+            .map(|branch| branch.id)
+            .collect(),
+    }))
+}
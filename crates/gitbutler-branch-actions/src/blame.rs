@@ -0,0 +1,127 @@
+use std::collections::HashMap;
+use std::path::Path;
+
+use anyhow::{anyhow, Context, Result};
+use bstr::ByteSlice;
+use gitbutler_branch::BranchId;
+use gitbutler_command_context::ProjectRepository;
+use gitbutler_diff::GitHunk;
+use gitbutler_error::error::Code;
+use serde::Serialize;
+
+use crate::{
+    external_diff::is_binary, integration::get_workspace_head, r#virtual as branch,
+    VirtualBranchesExt,
+};
+
+/// Where a line of a file in the working directory comes from.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub enum LineOrigin {
+    /// The line is committed, in the commit of `commit_id`.
+    Committed,
+    /// The line is an uncommitted change owned by the virtual branch of `branch_id`.
+    Uncommitted,
+    /// The line is an uncommitted change that no applied virtual branch owns.
+    Unattributed,
+}
+
+/// The origin of a line of a file in the working directory.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct LineAttribution {
+    /// The 1-based number of the line in the working directory.
+    pub line: u32,
+    pub origin: LineOrigin,
+    /// The commit that last changed the line, if it's committed.
+    #[serde(with = "gitbutler_serde::serde::oid_opt")]
+    pub commit_id: Option<git2::Oid>,
+    /// The virtual branch owning the line, if it's an uncommitted change of one.
+    pub branch_id: Option<BranchId>,
+}
+
+/// Attribute each line of the file at `path` in the working directory to the commit that last changed it,
+/// or, if it's uncommitted, to the virtual branch owning the change.
+///
+/// Files that only exist in the working directory have all their lines attributed to the branch owning them.
+/// It's an error if the file is binary, or if `path` isn't relative to the worktree.
+pub(crate) fn virtual_blame(ctx: &ProjectRepository, path: &Path) -> Result<Vec<LineAttribution>> {
+    crate::files::ensure_within_worktree(path)?;
+    let content = std::fs::read(ctx.project().path.join(path))
+        .with_context(|| format!("failed to read {}", path.display()))?;
+    if is_binary(&content) {
+        return Err(anyhow!("Binary files can't be blamed").context(Code::Validation));
+    }
+
+    let vb_state = ctx.project().virtual_branches();
+    let integration_commit_id = get_workspace_head(&vb_state, ctx)?;
+    let (statuses, _, _) = branch::get_status_by_branch(ctx, Some(&integration_commit_id), None)
+        .context("failed to get status by branch")?;
+    let mut owners = HashMap::new();
+    for (branch, mut files) in statuses {
+        for hunk in files.remove(path).unwrap_or_default() {
+            if hunk.binary {
+                return Err(anyhow!("Binary files can't be blamed").context(Code::Validation));
+            }
+            for line in added_lines(&hunk) {
+                owners.insert(line, branch.id);
+            }
+        }
+    }
+
+    let repo = ctx.repo();
+    let mut opts = git2::BlameOptions::new();
+    opts.newest_commit(integration_commit_id);
+    let committed_blame = match repo.blame_file(path, Some(&mut opts)) {
+        Ok(blame) => Some(blame),
+        // the file only exists in the working directory
+        Err(err) if err.code() == git2::ErrorCode::NotFound => None,
+        Err(err) => return Err(err).context("failed to blame file"),
+    };
+    let blame = committed_blame
+        .as_ref()
+        .map(|blame| blame.blame_buffer(&content))
+        .transpose()
+        .context("failed to blame the working directory")?;
+
+    let line_count = content.lines().count() as u32;
+    Ok((1..=line_count)
+        .map(|line| {
+            let commit_id = blame
+                .as_ref()
+                .and_then(|blame| blame.get_line(line as usize))
+                .map(|hunk| hunk.final_commit_id())
+                .filter(|id| !id.is_zero());
+            let branch_id = owners.get(&line).copied();
+            let (origin, commit_id) = match (branch_id, commit_id) {
+                (Some(_), _) => (LineOrigin::Uncommitted, None),
+                (None, Some(commit_id)) => (LineOrigin::Committed, Some(commit_id)),
+                (None, None) => (LineOrigin::Unattributed, None),
+            };
+            LineAttribution {
+                line,
+                origin,
+                commit_id,
+                branch_id,
+            }
+        })
+        .collect())
+}
+
+/// Return the numbers of the lines `hunk` adds to the new version of the file.
+fn added_lines(hunk: &GitHunk) -> Vec<u32> {
+    let mut added = Vec::new();
+    let mut line = hunk.new_start;
+    for diff_line in hunk.diff_lines.lines() {
+        match diff_line.first() {
+            Some(b'+') => {
+                added.push(line);
+                line += 1;
+            }
+            Some(b' ') => line += 1,
+            // deletions, the hunk header and end-of-file markers aren't in the new version
+            _ => {}
+        }
+    }
+    added
+}
@@ -0,0 +1,121 @@
+use std::path::{Path, PathBuf};
+
+use anyhow::{Context, Result};
+use gitbutler_branch::BranchId;
+use gitbutler_command_context::ProjectRepository;
+use serde::Serialize;
+
+use crate::VirtualBranchesExt;
+
+/// Why changing a file is considered high-risk.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub enum FileRisk {
+    /// The file matches one of the project's protected paths.
+    Protected,
+    /// The file configures continuous integration, like a GitHub workflow.
+    ContinuousIntegration,
+    /// The file is a database migration, i.e. it is in a `migrations` directory.
+    Migration,
+}
+
+/// A file that applying a branch changes in the workspace.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct TouchedFile {
+    /// The worktree-relative path of the file.
+    pub path: PathBuf,
+    /// Why changing the file is high-risk, empty if it isn't.
+    pub risks: Vec<FileRisk>,
+}
+
+/// Paths of files that configure continuous integration, with patterns without a `/` matching the file name.
+const CI_PATTERNS: &[&str] = &[
+    ".github/workflows/*",
+    ".gitlab-ci.yml",
+    ".circleci/*",
+    ".buildkite/*",
+    "Jenkinsfile",
+    "azure-pipelines.yml",
+    ".travis.yml",
+];
+
+/// Names of directories that contain database migrations.
+const MIGRATION_DIRECTORIES: &[&str] = &["migrations", "migrate"];
+
+/// Return all files that applying the branch with `branch_id` changes compared to the default target,
+/// committed or not, sorted by path and annotated with the reasons they are high-risk.
+pub(crate) fn apply_blast_radius(
+    ctx: &ProjectRepository,
+    branch_id: BranchId,
+) -> Result<Vec<TouchedFile>> {
+    let vb_state = ctx.project().virtual_branches();
+    let default_target = vb_state.get_default_target()?;
+    let branch = vb_state.get_branch(branch_id)?;
+    let repo = ctx.repo();
+    let target_tree = repo
+        .find_commit(default_target.sha)
+        .and_then(|commit| commit.tree())
+        .context("failed to find tree of default target")?;
+    let branch_tree = repo
+        .find_tree(branch.tree)
+        .with_context(|| format!("failed to find tree of branch {}", branch.name))?;
+    let diff = repo.diff_tree_to_tree(Some(&target_tree), Some(&branch_tree), None)?;
+
+    let protected = patterns(&ctx.project().protected_paths);
+    let ci = patterns(CI_PATTERNS);
+    let mut files: Vec<_> = diff
+        .deltas()
+        .filter_map(|delta| delta.new_file().path().or_else(|| delta.old_file().path()))
+        .map(|path| {
+            let mut risks = Vec::new();
+            if matches_any(&protected, path) {
+                risks.push(FileRisk::Protected);
+            }
+            if matches_any(&ci, path) {
+                risks.push(FileRisk::ContinuousIntegration);
+            }
+            if path.parent().map_or(false, |parent| {
+                parent.components().any(|component| {
+                    MIGRATION_DIRECTORIES
+                        .iter()
+                        .any(|dir| component.as_os_str() == *dir)
+                })
+            }) {
+                risks.push(FileRisk::Migration);
+            }
+            TouchedFile {
+                path: path.to_owned(),
+                risks,
+            }
+        })
+        .collect();
+    files.sort_by(|a, b| a.path.cmp(&b.path));
+    files.dedup_by(|a, b| a.path == b.path);
+    Ok(files)
+}
+
+/// Compile `patterns`, remembering whether each should also match file names at any depth.
+fn patterns(patterns: &[impl AsRef<str>]) -> Vec<(glob::Pattern, bool)> {
+    patterns
+        .iter()
+        .map(AsRef::as_ref)
+        .filter_map(|pattern| match glob::Pattern::new(pattern) {
+            Ok(glob) => Some((glob, !pattern.contains('/'))),
+            Err(err) => {
+                tracing::warn!("ignoring invalid path pattern {pattern:?}: {err}");
+                None
+            }
+        })
+        .collect()
+}
+
+fn matches_any(patterns: &[(glob::Pattern, bool)], path: &Path) -> bool {
+    patterns.iter().any(|(glob, match_file_name)| {
+        glob.matches_path(path)
+            || (*match_file_name
+                && path
+                    .file_name()
+                    .map_or(false, |name| glob.matches_path(Path::new(name))))
+    })
+}
@@ -1,7 +1,7 @@
 use super::BranchManager;
 use crate::{
     conflicts::{self, RepoConflictsExt},
-    ensure_selected_for_changes,
+    ensure_selected_for_changes, find_base_tree,
     integration::update_gitbutler_integration,
     set_ownership, undo_commit, VirtualBranchHunk, VirtualBranchesExt,
 };
@@ -10,12 +10,53 @@ use gitbutler_branch::{
     dedup, Branch, BranchOwnershipClaims, {self, BranchCreateRequest, BranchId},
 };
 use gitbutler_commit::commit_headers::HasCommitHeaders;
-use gitbutler_error::error::Marker;
+use gitbutler_error::error::{Code, Marker};
 use gitbutler_oplog::SnapshotExt;
 use gitbutler_project::access::WorktreeWritePermission;
-use gitbutler_reference::Refname;
+use gitbutler_reference::{validate_refname, LocalRefname, Refname};
 use gitbutler_repo::{rebase::cherry_rebase, RepoActionsExt, RepositoryExt};
 use gitbutler_time::time::now_since_unix_epoch_ms;
+use itertools::Itertools;
+use serde::{Deserialize, Serialize};
+
+/// The outcome of applying a stack of branches with [`BranchManager::apply_stack()`].
+#[derive(Debug, Default, PartialEq, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct StackApplication {
+    /// The ids of the virtual branches that were applied, in stack order.
+    pub applied: Vec<BranchId>,
+    /// The first branch of the stack that conflicts with the workspace, if any.
+    /// It and all branches after it are left unapplied.
+    pub conflicting: Option<BranchId>,
+}
+
+/// Options for [`BranchManager::import_all_local_branches()`].
+#[derive(Debug, Default, PartialEq, Clone, Deserialize)]
+#[serde(rename_all = "camelCase", default)]
+pub struct LocalBranchImportOptions {
+    /// The names of local branches that shouldn't be imported, like `feature` for `refs/heads/feature`.
+    pub exclude: Vec<String>,
+    /// If `true`, branches without any commits that aren't in the base branch yet are skipped.
+    pub skip_merged: bool,
+}
+
+/// A local branch that [`BranchManager::import_all_local_branches()`] didn't import.
+#[derive(Debug, PartialEq, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SkippedLocalBranch {
+    pub name: Refname,
+    /// Why the branch wasn't imported, for display to the user.
+    pub reason: String,
+}
+
+/// The outcome of importing local branches with [`BranchManager::import_all_local_branches()`].
+#[derive(Debug, Default, PartialEq, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct LocalBranchImport {
+    /// The ids of the virtual branches that were created, one per imported local branch.
+    pub imported: Vec<BranchId>,
+    pub skipped: Vec<SkippedLocalBranch>,
+}
 
 impl BranchManager<'_> {
     pub fn create_virtual_branch(
@@ -51,6 +92,8 @@ impl BranchManager<'_> {
                 .unwrap_or(&"Virtual branch".to_string()),
         );
 
+        self.ensure_applied_branch_limit(&name)?;
+
         _ = self
             .project_repository
             .project()
@@ -109,6 +152,7 @@ impl BranchManager<'_> {
             applied: true,
             in_workspace: true,
             not_in_workspace_wip_change_id: None,
+            parent_id: None,
             source_refname: None,
         };
 
@@ -142,6 +186,8 @@ impl BranchManager<'_> {
             .expect("always a branch reference")
             .to_string();
 
+        self.ensure_applied_branch_limit(&branch_name)?;
+
         let _ = self
             .project_repository
             .project()
@@ -230,6 +276,12 @@ impl BranchManager<'_> {
             branch.allow_rebasing = self.project_repository.project().ok_with_force_push.into();
             branch.applied = true;
             branch.in_workspace = true;
+            // it can only build on its parent's commits if the parent is in the workspace too
+            if let Some(parent_id) = branch.parent_id {
+                if vb_state.try_branch_in_workspace(parent_id)?.is_none() {
+                    branch.parent_id = None;
+                }
+            }
 
             branch
         } else {
@@ -251,6 +303,7 @@ impl BranchManager<'_> {
                 applied: true,
                 in_workspace: true,
                 not_in_workspace_wip_change_id: None,
+                parent_id: None,
             }
         };
 
@@ -270,10 +323,259 @@ impl BranchManager<'_> {
             Err(err) => Err(err).context("failed to apply"),
         }
     }
+
+    /// Create a virtual branch named after `branch_name` from `commit_ids`, commits that aren't part
+    /// of any virtual branch in the workspace anymore, like the ones of a deleted branch.
+    ///
+    /// `commit_ids` are expected from oldest to newest, and have to form a chain on top of the
+    /// default target, i.e. the first commit has the target as its parent, and every other commit
+    /// has the previous one as its parent.
+    pub fn adopt_orphan_commits(
+        &self,
+        commit_ids: &[git2::Oid],
+        branch_name: &str,
+        perm: &mut WorktreeWritePermission,
+    ) -> Result<BranchId> {
+        let repo = self.project_repository.repo();
+        let vb_state = self.project_repository.project().virtual_branches();
+        let default_target = vb_state.get_default_target()?;
+
+        let Some(&head) = commit_ids.last() else {
+            return Err(anyhow!("there are no commits to adopt").context(Code::Validation));
+        };
+
+        let mut expected_parent = default_target.sha;
+        for &commit_id in commit_ids {
+            let commit = repo
+                .find_commit(commit_id)
+                .map_err(|err| match err.code() {
+                    git2::ErrorCode::NotFound => anyhow!("commit {commit_id} not found"),
+                    _ => err.into(),
+                })?;
+            if commit.parent_count() != 1 || commit.parent_id(0)? != expected_parent {
+                return Err(anyhow!(
+                    "commit {commit_id} is not a child of {expected_parent}, commits must form a chain on top of the base branch"
+                )
+                .context(Code::Validation));
+            }
+            expected_parent = commit_id;
+        }
+
+        for branch in vb_state.list_branches_in_workspace()? {
+            for &commit_id in commit_ids {
+                if branch.head == commit_id || repo.graph_descendant_of(branch.head, commit_id)? {
+                    return Err(anyhow!(
+                        "commit {commit_id} already belongs to branch {}",
+                        branch.name
+                    )
+                    .context(Code::Validation));
+                }
+            }
+        }
+
+        let branch_name = validate_refname(branch_name)
+            .map_err(|violation| anyhow::Error::from(violation).context(Code::Validation))?;
+        let refname = Refname::Local(LocalRefname::new(&branch_name, None));
+        repo.reference(&refname.to_string(), head, false, "adopted orphan commits")
+            .map_err(|err| match err.code() {
+                git2::ErrorCode::Exists => {
+                    anyhow!("branch {branch_name} already exists").context(Code::Validation)
+                }
+                _ => err.into(),
+            })?;
+
+        self.create_virtual_branch_from_branch(&refname, perm)
+    }
+
+    /// Apply the unapplied virtual branches of `stack` in order, each on top of the workspace left by the
+    /// previous one.
+    ///
+    /// Stops at the first branch that would conflict with the workspace, which is reported
+    /// in the returned [`StackApplication`] along with the branches that were applied before it.
+    pub fn apply_stack(
+        &self,
+        stack: &[BranchId],
+        perm: &mut WorktreeWritePermission,
+    ) -> Result<StackApplication> {
+        let vb_state = self.project_repository.project().virtual_branches();
+        // all branches are checked before any of them is applied
+        let stack = stack
+            .iter()
+            .map(|branch_id| {
+                let branch = vb_state.get_branch(*branch_id)?;
+                if branch.in_workspace {
+                    return Err(anyhow!("branch {} is applied already", branch.name)
+                        .context(Code::Validation));
+                }
+                let refname = branch.source_refname.with_context(|| {
+                    format!("branch {} has no reference to apply it from", branch.name)
+                })?;
+                Ok((branch.id, refname))
+            })
+            .collect::<Result<Vec<_>>>()?;
+
+        let mut application = StackApplication::default();
+        for (branch_id, refname) in stack {
+            if !self.is_mergeable_into_workspace(&refname)? {
+                application.conflicting = Some(branch_id);
+                break;
+            }
+            let branch_id = self.create_virtual_branch_from_branch(&refname, perm)?;
+            application.applied.push(branch_id);
+        }
+        Ok(application)
+    }
+
+    /// Create a virtual branch for each local branch of the repository, except for the base branch,
+    /// GitButler's own branches and the ones excluded by `options`.
+    ///
+    /// Branches whose base can't be determined, because they share no history with the base branch
+    /// or have more than one merge-base with it, are skipped and reported along with all other
+    /// branches that weren't imported.
+    pub fn import_all_local_branches(
+        &self,
+        options: &LocalBranchImportOptions,
+        perm: &mut WorktreeWritePermission,
+    ) -> Result<LocalBranchImport> {
+        let repo = self.project_repository.repo();
+        let vb_state = self.project_repository.project().virtual_branches();
+        let default_target = vb_state.get_default_target()?;
+        let in_workspace = vb_state
+            .list_branches_in_workspace()?
+            .into_iter()
+            .filter_map(|branch| branch.source_refname)
+            .collect::<Vec<_>>();
+
+        let mut candidates = Vec::new();
+        for (branch, _) in repo
+            .branches(Some(git2::BranchType::Local))
+            .context("failed to list local branches")?
+            .flatten()
+        {
+            let Ok(Refname::Local(local)) = Refname::try_from(&branch) else {
+                continue;
+            };
+            let is_base = local.branch() == default_target.branch.branch()
+                || local.remote() == Some(&default_target.branch);
+            if is_base
+                || local.branch().starts_with("gitbutler/")
+                || options.exclude.iter().any(|name| name == local.branch())
+            {
+                continue;
+            }
+            let head = branch.get().peel_to_commit()?.id();
+            candidates.push((Refname::Local(local), head));
+        }
+        candidates.sort_by(|(a, _), (b, _)| a.to_string().cmp(&b.to_string()));
+
+        let mut import = LocalBranchImport::default();
+        for (name, head) in candidates {
+            let skip = |reason: &str| SkippedLocalBranch {
+                name: name.clone(),
+                reason: reason.to_owned(),
+            };
+            if in_workspace.contains(&name) {
+                import.skipped.push(skip("it is applied already"));
+                continue;
+            }
+            match repo.merge_bases(default_target.sha, head) {
+                Ok(bases) if bases.len() > 1 => {
+                    import
+                        .skipped
+                        .push(skip("it has more than one merge-base with the base branch"));
+                    continue;
+                }
+                Ok(bases) => {
+                    if options.skip_merged && bases.first() == Some(&head) {
+                        import
+                            .skipped
+                            .push(skip("all of its commits are in the base branch"));
+                        continue;
+                    }
+                }
+                Err(err) if err.code() == git2::ErrorCode::NotFound => {
+                    import
+                        .skipped
+                        .push(skip("it shares no history with the base branch"));
+                    continue;
+                }
+                Err(err) => return Err(err.into()),
+            }
+
+            match self.create_virtual_branch_from_branch(&name, perm) {
+                Ok(branch_id) => import.imported.push(branch_id),
+                // Like reaching the limit of applied branches, which shouldn't stop the others from being reported.
+                Err(err) if err.downcast_ref::<Code>() == Some(&Code::Validation) => {
+                    import.skipped.push(skip(&err.root_cause().to_string()));
+                }
+                Err(err) => return Err(err.context(format!("failed to import {name}"))),
+            }
+        }
+        Ok(import)
+    }
 }
 
 /// Holding private methods associated to branch creation
 impl BranchManager<'_> {
+    fn is_mergeable_into_workspace(&self, branch: &Refname) -> Result<bool> {
+        let repo = self.project_repository.repo();
+        let default_target = self
+            .project_repository
+            .project()
+            .virtual_branches()
+            .get_default_target()?;
+        let target_commit = repo
+            .find_commit(default_target.sha)
+            .context("failed to find target commit")?;
+
+        let branch_commit = repo
+            .find_reference(&branch.to_string())
+            .map_err(|err| match err {
+                err if err.code() == git2::ErrorCode::NotFound => {
+                    anyhow!("branch {branch} was not found")
+                }
+                err => err.into(),
+            })?
+            .peel_to_commit()
+            .context("failed to peel to commit")?;
+
+        let base_tree = find_base_tree(repo, &branch_commit, &target_commit)?;
+        let branch_tree = branch_commit.tree().context("failed to find branch tree")?;
+        let wd_tree = repo.get_wd_tree()?;
+
+        let mergeable = !repo
+            .merge_trees(&base_tree, &branch_tree, &wd_tree, None)
+            .context("failed to merge trees")?
+            .has_conflicts();
+        Ok(mergeable)
+    }
+
+    /// Fail with a validation error if applying `branch_name` would exceed the
+    /// [`max_applied_branches`](gitbutler_project::Project::max_applied_branches) of the project.
+    fn ensure_applied_branch_limit(&self, branch_name: &str) -> Result<()> {
+        let max_applied_branches = self.project_repository.project().max_applied_branches;
+        if max_applied_branches == 0 {
+            return Ok(());
+        }
+        let applied_branches = self
+            .project_repository
+            .project()
+            .virtual_branches()
+            .list_branches_in_workspace()?;
+        if applied_branches.len() >= max_applied_branches {
+            let applied_names = applied_branches
+                .iter()
+                .map(|branch| branch.name.as_str())
+                .join(", ");
+            return Err(anyhow!(
+                "cannot apply {branch_name}, at most {max_applied_branches} branches may be applied at once \
+                 and these are applied already: {applied_names}"
+            )
+            .context(Code::Validation));
+        }
+        Ok(())
+    }
+
     fn apply_branch(
         &self,
         branch_id: BranchId,
@@ -69,6 +69,37 @@ impl BranchManager<'_> {
             .snapshot_branch_deletion(branch.name.clone(), perm);
 
         let repo = self.project_repository.repo();
+        let (final_tree, _) = self.trees_without_branch(branch_id)?;
+        let final_tree = repo.find_tree(final_tree)?;
+
+        // checkout final_tree into the working directory
+        repo.checkout_tree_builder(&final_tree)
+            .force()
+            .remove_untracked()
+            .checkout()
+            .context("failed to checkout tree")?;
+
+        vb_state
+            .mark_as_not_in_workspace(branch.id)
+            .context("Failed to remove branch")?;
+
+        self.project_repository.delete_branch_reference(&branch)?;
+
+        ensure_selected_for_changes(&vb_state).context("failed to ensure selected for changes")?;
+
+        Ok(())
+    }
+}
+
+impl BranchManager<'_> {
+    /// Return the tree the working directory would have without the branch with `branch_id`, along with the
+    /// tree of that branch including its uncommitted changes, if it's applied.
+    pub(crate) fn trees_without_branch(
+        &self,
+        branch_id: BranchId,
+    ) -> Result<(git2::Oid, Option<git2::Oid>)> {
+        let vb_state = self.project_repository.project().virtual_branches();
+        let repo = self.project_repository.repo();
 
         let integration_commit = repo.integration_commit()?;
         let target_commit = repo.target_commit()?;
@@ -78,25 +109,33 @@ impl BranchManager<'_> {
             .list_branches_in_workspace()
             .context("failed to read virtual branches")?;
 
-        let (applied_statuses, _, _) = get_applied_status(
+        let (applied_statuses, _, _, unassigned) = get_applied_status(
             self.project_repository,
             &integration_commit.id(),
             virtual_branches,
             None,
         )
         .context("failed to get status by branch")?;
+        let (removed, remaining): (Vec<_>, Vec<_>) = applied_statuses
+            .into_iter()
+            .partition(|(branch, _)| branch.id == branch_id);
+        let branch_tree = removed
+            .into_iter()
+            .next()
+            .map(|(branch, files)| write_tree(self.project_repository, &branch.head, files))
+            .transpose()?;
 
-        // go through the other applied branches and merge them into the final tree
-        // then check that out into the working directory
-        let final_tree = applied_statuses
+        // go through the other applied branches and the unassigned changes and merge them
+        // into the final tree
+        let final_tree = remaining
             .into_iter()
-            .filter(|(branch, _)| branch.id != branch_id)
+            .map(|(branch, files)| (branch.head, files))
+            .chain(Some((target_commit.id(), unassigned)))
             .fold(
                 target_commit.tree().context("failed to get target tree"),
-                |final_tree, status| {
+                |final_tree, (head, files)| {
                     let final_tree = final_tree?;
-                    let branch = status.0;
-                    let tree_oid = write_tree(self.project_repository, &branch.head, status.1)?;
+                    let tree_oid = write_tree(self.project_repository, &head, files)?;
                     let branch_tree = repo.find_tree(tree_oid)?;
                     let mut result =
                         repo.merge_trees(&base_tree, &final_tree, &branch_tree, None)?;
@@ -105,27 +144,9 @@ impl BranchManager<'_> {
                         .context("failed to find tree")
                 },
             )?;
-
-        // checkout final_tree into the working directory
-        repo.checkout_tree_builder(&final_tree)
-            .force()
-            .remove_untracked()
-            .checkout()
-            .context("failed to checkout tree")?;
-
-        vb_state
-            .mark_as_not_in_workspace(branch.id)
-            .context("Failed to remove branch")?;
-
-        self.project_repository.delete_branch_reference(&branch)?;
-
-        ensure_selected_for_changes(&vb_state).context("failed to ensure selected for changes")?;
-
-        Ok(())
+        Ok((final_tree.id(), branch_tree))
     }
-}
 
-impl BranchManager<'_> {
     fn build_real_branch(
         &self,
         vbranch: &mut Branch,
@@ -1,6 +1,9 @@
 use gitbutler_command_context::ProjectRepository;
 
 mod branch_creation;
+pub use branch_creation::{
+    LocalBranchImport, LocalBranchImportOptions, SkippedLocalBranch, StackApplication,
+};
 mod branch_removal;
 
 pub struct BranchManager<'l> {
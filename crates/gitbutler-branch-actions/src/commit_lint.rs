@@ -0,0 +1,142 @@
+use gitbutler_project::CommitLintRules;
+use serde::Serialize;
+
+/// A way in which a commit message doesn't follow the [rules](CommitLintRules) of its project.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "camelCase", tag = "kind")]
+pub enum CommitLintViolation {
+    /// The first line is longer than allowed.
+    #[serde(rename_all = "camelCase")]
+    SubjectTooLong { length: usize, max: usize },
+    /// The subject doesn't start with one of the allowed types, like `feat: `.
+    #[serde(rename_all = "camelCase")]
+    MissingType { allowed: Vec<String> },
+    /// A line of the body is longer than allowed, with `line` being 1-based within the whole message.
+    #[serde(rename_all = "camelCase")]
+    BodyLineTooLong {
+        line: usize,
+        length: usize,
+        max: usize,
+    },
+}
+
+impl std::fmt::Display for CommitLintViolation {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            CommitLintViolation::SubjectTooLong { length, max } => {
+                write!(
+                    f,
+                    "subject is {length} characters long, at most {max} are allowed"
+                )
+            }
+            CommitLintViolation::MissingType { allowed } => {
+                write!(
+                    f,
+                    "subject must start with one of the types {}",
+                    allowed.join(", ")
+                )
+            }
+            CommitLintViolation::BodyLineTooLong { line, length, max } => write!(
+                f,
+                "line {line} is {length} characters long, at most {max} are allowed"
+            ),
+        }
+    }
+}
+
+/// The result of checking a commit message against the rules of its project.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct CommitLintReport {
+    /// The violations that remain in `message`, so it's acceptable if empty.
+    pub violations: Vec<CommitLintViolation>,
+    /// The message to commit, which has its body wrapped if the rules ask to fix wrapping.
+    pub message: String,
+}
+
+impl CommitLintReport {
+    pub fn is_ok(&self) -> bool {
+        self.violations.is_empty()
+    }
+}
+
+/// Check `message` against `rules`, wrapping the body first if `rules` ask for it.
+/// Lines of the body that can't be wrapped, like long URLs, remain violations.
+pub(crate) fn lint_commit_message(rules: &CommitLintRules, message: &str) -> CommitLintReport {
+    let message = match rules.body_wrap {
+        Some(max) if rules.fix_wrapping => wrap_body(message, max),
+        _ => message.to_owned(),
+    };
+    let mut violations = Vec::new();
+    let mut lines = message.lines();
+    let subject = lines.next().unwrap_or_default();
+
+    if let Some(max) = rules.max_subject_length {
+        let length = subject.chars().count();
+        if length > max {
+            violations.push(CommitLintViolation::SubjectTooLong { length, max });
+        }
+    }
+    if !rules.required_types.is_empty()
+        && !subject_type(subject).map_or(false, |ty| rules.required_types.iter().any(|t| t == ty))
+    {
+        violations.push(CommitLintViolation::MissingType {
+            allowed: rules.required_types.clone(),
+        });
+    }
+    if let Some(max) = rules.body_wrap {
+        for (idx, line) in lines.enumerate() {
+            let length = line.chars().count();
+            if length > max {
+                violations.push(CommitLintViolation::BodyLineTooLong {
+                    line: idx + 2,
+                    length,
+                    max,
+                });
+            }
+        }
+    }
+    CommitLintReport {
+        violations,
+        message,
+    }
+}
+
+/// Return the type of a conventional-commits `subject`, like `fix` in `fix(ui)!: description`.
+fn subject_type(subject: &str) -> Option<&str> {
+    let (prefix, _description) = subject.split_once(": ")?;
+    let ty = prefix
+        .split_once('(')
+        .map_or(prefix, |(ty, _scope)| ty)
+        .trim_end_matches('!');
+    (!ty.is_empty() && ty.chars().all(|c| c.is_ascii_alphanumeric() || c == '-')).then_some(ty)
+}
+
+/// Wrap each line after the subject that is longer than `max` characters at word boundaries.
+/// Words longer than `max` are put on their own line as they are.
+fn wrap_body(message: &str, max: usize) -> String {
+    let mut lines = message.lines();
+    let mut wrapped: Vec<String> = lines.next().map(ToOwned::to_owned).into_iter().collect();
+    for line in lines {
+        if line.chars().count() <= max {
+            wrapped.push(line.to_owned());
+            continue;
+        }
+        let mut current = String::new();
+        for word in line.split_whitespace() {
+            if !current.is_empty() && current.chars().count() + 1 + word.chars().count() > max {
+                wrapped.push(std::mem::take(&mut current));
+            }
+            if !current.is_empty() {
+                current.push(' ');
+            }
+            current.push_str(word);
+        }
+        wrapped.push(current);
+    }
+    let mut wrapped = wrapped.join("\n");
+    if message.ends_with('\n') {
+        wrapped.push('\n');
+    }
+    wrapped
+}
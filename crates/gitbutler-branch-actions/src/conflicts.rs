@@ -9,9 +9,10 @@ use std::{
     path::{Path, PathBuf},
 };
 
-use anyhow::{anyhow, Context, Result};
+use anyhow::{anyhow, bail, Context, Result};
 use gitbutler_command_context::ProjectRepository;
 use itertools::Itertools;
+use serde::{Deserialize, Serialize};
 
 use gitbutler_error::error::Marker;
 
@@ -83,6 +84,188 @@ pub fn resolve<P: AsRef<Path>>(repository: &ProjectRepository, path: P) -> Resul
     Ok(())
 }
 
+/// How to resolve a conflicted file as a whole.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum ConflictResolution {
+    /// Keep the version that was in the workspace.
+    Ours,
+    /// Keep the version that was merged in.
+    Theirs,
+    /// Keep the version of the common ancestor.
+    Base,
+    /// Replace the file with the given content, like a version merged by hand.
+    Manual(String),
+}
+
+/// Resolve the conflicted `path` according to `resolution`, taking a side as recorded in the index
+/// or the given content, writing it to the worktree and marking the file as resolved.
+///
+/// If the chosen side doesn't have the file, it is removed from the worktree.
+pub fn resolve_with<P: AsRef<Path>>(
+    repository: &ProjectRepository,
+    path: P,
+    resolution: ConflictResolution,
+) -> Result<()> {
+    let path = path.as_ref();
+    if !is_conflicting(repository, Some(path))? {
+        bail!("file '{}' is not conflicted", path.display());
+    }
+
+    let repo = repository.repo();
+    let mut index = repo.index().context("failed to open index")?;
+    let content = match resolution {
+        ConflictResolution::Ours => side_content(repo, &index, path, |conflict| conflict.our)?,
+        ConflictResolution::Theirs => side_content(repo, &index, path, |conflict| conflict.their)?,
+        ConflictResolution::Base => side_content(repo, &index, path, |conflict| conflict.ancestor)?,
+        ConflictResolution::Manual(content) => Some(content.into_bytes()),
+    };
+
+    let worktree_path = repo.workdir().context("repository is bare")?.join(path);
+    match content {
+        Some(content) => {
+            gitbutler_fs::create_dirs_then_write(&worktree_path, content)?;
+            index.add_path(path)?;
+        }
+        None => {
+            if worktree_path.exists() {
+                std::fs::remove_file(&worktree_path)?;
+            }
+            index.conflict_remove(path)?;
+        }
+    }
+    index.write().context("failed to write index")?;
+
+    resolve(repository, path)
+}
+
+/// Return the content of the side of the conflict on `path` that `pick` selects from the index,
+/// or `None` if that side doesn't have the file.
+fn side_content(
+    repo: &git2::Repository,
+    index: &git2::Index,
+    path: &Path,
+    pick: impl FnOnce(git2::IndexConflict) -> Option<git2::IndexEntry>,
+) -> Result<Option<Vec<u8>>> {
+    let path_bytes = path.as_os_str().as_encoded_bytes();
+    let conflict = index
+        .conflicts()
+        .context("failed to get index conflicts")?
+        .filter_map(Result::ok)
+        .find(|conflict| {
+            [&conflict.ancestor, &conflict.our, &conflict.their]
+                .into_iter()
+                .flatten()
+                .any(|entry| entry.path == path_bytes)
+        })
+        .ok_or_else(|| anyhow!("no conflict recorded in the index for '{}'", path.display()))?;
+
+    pick(conflict)
+        .map(|entry| {
+            repo.find_blob(entry.id)
+                .map(|blob| blob.content().to_vec())
+                .context(format!("failed to find blob {}", entry.id))
+        })
+        .transpose()
+}
+
+/// A region of a conflicted file between conflict markers.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ConflictHunk {
+    /// The 1-based line of the `<<<<<<<` marker.
+    pub start_line: usize,
+    /// The 1-based line of the `>>>>>>>` marker.
+    pub end_line: usize,
+    /// The lines of the version that was in the workspace.
+    pub ours: String,
+    /// The lines of the common ancestor, if the markers include them.
+    pub base: Option<String>,
+    /// The lines of the version that was merged in.
+    pub theirs: String,
+}
+
+/// A file that is still conflicted, along with the conflict hunks found in its worktree content.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ConflictedFile {
+    pub path: PathBuf,
+    /// The conflict hunks in the order they appear, empty if the file is binary or was deleted.
+    pub hunks: Vec<ConflictHunk>,
+}
+
+/// List the files that are still conflicted, parsing the conflict markers in their worktree content.
+pub(crate) fn list_conflicts(repository: &ProjectRepository) -> Result<Vec<ConflictedFile>> {
+    let workdir = repository.repo().workdir().context("repository is bare")?;
+    conflicting_files(repository)?
+        .into_iter()
+        .map(|path| {
+            let path = PathBuf::from(path);
+            let hunks = match std::fs::read(workdir.join(&path)) {
+                Ok(content) => std::str::from_utf8(&content)
+                    .map(parse_conflict_hunks)
+                    .unwrap_or_default(),
+                Err(err) if err.kind() == std::io::ErrorKind::NotFound => Vec::new(),
+                Err(err) => return Err(err.into()),
+            };
+            Ok(ConflictedFile { path, hunks })
+        })
+        .collect()
+}
+
+fn parse_conflict_hunks(content: &str) -> Vec<ConflictHunk> {
+    enum Section {
+        Ours,
+        Base,
+        Theirs,
+    }
+
+    fn is_marker(line: &str, marker: &str) -> bool {
+        line.strip_prefix(marker)
+            .map_or(false, |rest| rest.is_empty() || rest.starts_with(' '))
+    }
+
+    let mut hunks = Vec::new();
+    let mut current: Option<(Section, ConflictHunk)> = None;
+    for (idx, line) in content.split_inclusive('\n').enumerate() {
+        let marker = line.trim_end_matches(['\n', '\r']);
+        current = match current.take() {
+            None if is_marker(marker, "<<<<<<<") => Some((
+                Section::Ours,
+                ConflictHunk {
+                    start_line: idx + 1,
+                    end_line: idx + 1,
+                    ours: String::new(),
+                    base: None,
+                    theirs: String::new(),
+                },
+            )),
+            None => None,
+            Some((Section::Ours, mut hunk)) if is_marker(marker, "|||||||") => {
+                hunk.base = Some(String::new());
+                Some((Section::Base, hunk))
+            }
+            Some((Section::Ours | Section::Base, hunk)) if marker == "=======" => {
+                Some((Section::Theirs, hunk))
+            }
+            Some((Section::Theirs, mut hunk)) if is_marker(marker, ">>>>>>>") => {
+                hunk.end_line = idx + 1;
+                hunks.push(hunk);
+                None
+            }
+            Some((section, mut hunk)) => {
+                match section {
+                    Section::Ours => hunk.ours.push_str(line),
+                    Section::Base => hunk.base.get_or_insert_with(String::new).push_str(line),
+                    Section::Theirs => hunk.theirs.push_str(line),
+                }
+                Some((section, hunk))
+            }
+        };
+    }
+    hunks
+}
+
 pub(crate) fn conflicting_files(repository: &ProjectRepository) -> Result<Vec<String>> {
     let conflicts_path = repository.repo().path().join("conflicts");
     if !conflicts_path.exists() {
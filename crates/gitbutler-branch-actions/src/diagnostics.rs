@@ -0,0 +1,302 @@
+use anyhow::{Context, Result};
+use gitbutler_branch::{BranchId, GITBUTLER_INTEGRATION_REFERENCE};
+use gitbutler_command_context::ProjectRepository;
+use gitbutler_oplog::OplogExt;
+use gitbutler_project::access::WorktreeWritePermission;
+use serde::Serialize;
+
+use crate::{integration::update_gitbutler_integration, VirtualBranchesExt};
+
+/// How bad a finding of [`diagnose_project`] is.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub enum FindingSeverity {
+    /// GitButler keeps working, but something may be out of date.
+    Warning,
+    /// GitButler can't work with the project as expected until this is fixed.
+    Error,
+}
+
+/// What is wrong with the GitButler state of a project.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub enum FindingKind {
+    /// No base branch is set.
+    BaseBranchMissing,
+    /// The upstream of the base branch doesn't exist in the repository.
+    BaseUpstreamUnresolvable,
+    /// The `gitbutler/integration` branch doesn't exist.
+    IntegrationMissing,
+    /// The `gitbutler/integration` branch doesn't point to a commit on top of the base branch.
+    IntegrationInvalid,
+    /// The virtual branches can't be read, e.g. as `virtual_branches.toml` is corrupt.
+    BranchesUnreadable,
+    /// An applied virtual branch points to a commit that doesn't exist.
+    BranchHeadMissing,
+    /// `HEAD` of the repository can't be read.
+    HeadUnresolvable,
+    /// The oplog head can't be read, e.g. as `operations-log.toml` is corrupt.
+    OplogUnreadable,
+    /// The oplog head points to a commit that doesn't exist.
+    OplogHeadMissing,
+    /// The chain of snapshots of the oplog isn't linear.
+    OplogChainNotLinear,
+}
+
+/// A problem with the GitButler state of a project.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Finding {
+    pub kind: FindingKind,
+    pub severity: FindingSeverity,
+    /// A human readable description of the problem.
+    pub message: String,
+    /// The virtual branch the problem is about, if any.
+    pub branch_id: Option<BranchId>,
+    /// `true` if [`repair_project`] can fix the problem without losing any work.
+    pub repairable: bool,
+}
+
+/// The outcome of checking the GitButler state of a project.
+#[derive(Debug, Clone, Default, PartialEq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ProjectDiagnostics {
+    /// All problems that were found, the most severe first.
+    pub findings: Vec<Finding>,
+}
+
+impl ProjectDiagnostics {
+    /// Return `true` if no problem was found.
+    pub fn is_healthy(&self) -> bool {
+        self.findings.is_empty()
+    }
+}
+
+/// The outcome of repairing the GitButler state of a project.
+#[derive(Debug, Clone, Default, PartialEq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ProjectRepair {
+    /// The findings that were fixed.
+    pub repaired: Vec<Finding>,
+    /// The findings that couldn't be fixed safely and need attention.
+    pub remaining: Vec<Finding>,
+}
+
+/// Check the GitButler state of the project: whether the integration branch is valid, the applied virtual branches
+/// point to existing commits, the oplog is consistent and the upstream of the base branch can be resolved.
+///
+/// All problems are reported as findings instead of stopping at the first one.
+pub(crate) fn diagnose_project(ctx: &ProjectRepository) -> Result<ProjectDiagnostics> {
+    let repo = ctx.repo();
+    let vb_state = ctx.project().virtual_branches();
+    let mut findings = Vec::new();
+
+    match vb_state.get_default_target() {
+        Ok(target) => {
+            if let Err(err) = repo
+                .find_reference(&target.branch.to_string())
+                .and_then(|reference| reference.peel_to_commit())
+            {
+                findings.push(Finding {
+                    kind: FindingKind::BaseUpstreamUnresolvable,
+                    severity: FindingSeverity::Warning,
+                    message: format!(
+                        "The base branch {} can't be resolved, fetch {} to restore it: {}",
+                        target.branch,
+                        target.branch.remote(),
+                        err.message()
+                    ),
+                    branch_id: None,
+                    repairable: false,
+                });
+            }
+            findings.extend(check_integration(ctx, target.sha)?);
+        }
+        Err(err) => findings.push(Finding {
+            kind: FindingKind::BaseBranchMissing,
+            severity: FindingSeverity::Error,
+            message: format!("No base branch is set: {err:#}"),
+            branch_id: None,
+            repairable: false,
+        }),
+    }
+
+    match vb_state.list_branches_in_workspace() {
+        Ok(branches) => {
+            for branch in branches {
+                if repo.find_commit(branch.head).is_err() {
+                    findings.push(Finding {
+                        kind: FindingKind::BranchHeadMissing,
+                        severity: FindingSeverity::Error,
+                        message: format!(
+                            "The head {} of branch {} doesn't exist, reset or delete the branch",
+                            branch.head, branch.name
+                        ),
+                        branch_id: Some(branch.id),
+                        repairable: false,
+                    });
+                }
+            }
+        }
+        Err(err) => findings.push(Finding {
+            kind: FindingKind::BranchesUnreadable,
+            severity: FindingSeverity::Error,
+            message: format!("The virtual branches can't be read: {err:#}"),
+            branch_id: None,
+            repairable: false,
+        }),
+    }
+
+    findings.extend(check_oplog(ctx)?);
+
+    findings.sort_by(|a, b| b.severity.cmp(&a.severity));
+    Ok(ProjectDiagnostics { findings })
+}
+
+/// Fix the repairable findings of [`diagnose_project`], and report which ones remain.
+///
+/// The integration branch is recreated from the virtual branches, but only if it's checked out as the user
+/// may be working on another branch otherwise. The oplog chain is made linear again, which backs up the oplog first.
+///
+/// `perm` is held throughout, so nothing changes between the diagnosis and the repairs.
+pub(crate) fn repair_project(
+    ctx: &ProjectRepository,
+    perm: &mut WorktreeWritePermission,
+) -> Result<ProjectRepair> {
+    let diagnostics = diagnose_project(ctx)?;
+    let mut repair = ProjectRepair::default();
+    for finding in diagnostics.findings {
+        if !finding.repairable {
+            repair.remaining.push(finding);
+            continue;
+        }
+        match finding.kind {
+            FindingKind::IntegrationMissing | FindingKind::IntegrationInvalid => {
+                let vb_state = ctx.project().virtual_branches();
+                // Updating the integration branch needs `HEAD` to point to a commit, so start over from the base.
+                let target = vb_state.get_default_target()?;
+                ctx.repo().reference(
+                    &GITBUTLER_INTEGRATION_REFERENCE.to_string(),
+                    target.sha,
+                    true,
+                    "recreated integration branch",
+                )?;
+                update_gitbutler_integration(&vb_state, ctx)
+                    .context("failed to recreate the integration branch")?;
+            }
+            FindingKind::OplogChainNotLinear => {
+                ctx.project()
+                    .repair_oplog_chain(perm)
+                    .context("failed to repair the oplog")?;
+            }
+            _ => {
+                repair.remaining.push(finding);
+                continue;
+            }
+        }
+        repair.repaired.push(finding);
+    }
+    Ok(repair)
+}
+
+/// Check that `HEAD` can be read, and that the integration branch exists and points to a descendant of the
+/// base commit `target_id`.
+fn check_integration(ctx: &ProjectRepository, target_id: git2::Oid) -> Result<Vec<Finding>> {
+    let repo = ctx.repo();
+    let integration_refname = GITBUTLER_INTEGRATION_REFERENCE.to_string();
+    let mut findings = Vec::new();
+    // Recreating the integration branch checks it out, which is only safe if it's checked out already.
+    let head_on_integration = match repo.find_reference("HEAD") {
+        Ok(head) => head
+            .symbolic_target()
+            .is_some_and(|target| target == integration_refname),
+        Err(err) => {
+            findings.push(Finding {
+                kind: FindingKind::HeadUnresolvable,
+                severity: FindingSeverity::Error,
+                message: format!("HEAD can't be read: {}", err.message()),
+                branch_id: None,
+                repairable: false,
+            });
+            false
+        }
+    };
+
+    let integration_commit = match repo.find_reference(&integration_refname) {
+        Ok(reference) => reference.peel_to_commit(),
+        Err(err) if err.code() == git2::ErrorCode::NotFound => {
+            findings.push(Finding {
+                kind: FindingKind::IntegrationMissing,
+                severity: FindingSeverity::Error,
+                message: format!("The branch {integration_refname} doesn't exist"),
+                branch_id: None,
+                repairable: head_on_integration,
+            });
+            return Ok(findings);
+        }
+        Err(err) => Err(err),
+    };
+    let is_valid = match integration_commit {
+        Ok(commit) => {
+            commit.id() == target_id || repo.graph_descendant_of(commit.id(), target_id)?
+        }
+        Err(_) => false,
+    };
+    if !is_valid {
+        findings.push(Finding {
+            kind: FindingKind::IntegrationInvalid,
+            severity: FindingSeverity::Warning,
+            message: format!(
+                "The branch {integration_refname} doesn't point to a commit on top of the base branch"
+            ),
+            branch_id: None,
+            repairable: head_on_integration,
+        });
+    }
+    Ok(findings)
+}
+
+/// Check that the oplog head exists and that the chain of snapshots leading to it is linear.
+fn check_oplog(ctx: &ProjectRepository) -> Result<Option<Finding>> {
+    let oplog_head = match ctx.project().oplog_head() {
+        Ok(Some(oplog_head)) => oplog_head,
+        Ok(None) => return Ok(None),
+        Err(err) => {
+            return Ok(Some(Finding {
+                kind: FindingKind::OplogUnreadable,
+                severity: FindingSeverity::Error,
+                message: format!("The oplog can't be read: {err:#}"),
+                branch_id: None,
+                repairable: false,
+            }));
+        }
+    };
+    let Ok(mut snapshot) = ctx.repo().find_commit(oplog_head) else {
+        return Ok(Some(Finding {
+            kind: FindingKind::OplogHeadMissing,
+            severity: FindingSeverity::Error,
+            message: format!("The oplog head {oplog_head} doesn't exist"),
+            branch_id: None,
+            repairable: false,
+        }));
+    };
+    loop {
+        if snapshot.parent_count() > 1 {
+            return Ok(Some(Finding {
+                kind: FindingKind::OplogChainNotLinear,
+                severity: FindingSeverity::Warning,
+                message: format!(
+                    "The snapshot {} has more than one parent, so older snapshots may be missing from the history",
+                    snapshot.id()
+                ),
+                branch_id: None,
+                repairable: true,
+            }));
+        }
+        // A missing parent is the end of the chain as far as the oplog is concerned.
+        let Ok(parent) = snapshot.parent(0) else {
+            return Ok(None);
+        };
+        snapshot = parent;
+    }
+}
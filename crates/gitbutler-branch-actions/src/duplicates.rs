@@ -0,0 +1,67 @@
+use std::collections::HashSet;
+
+use anyhow::Result;
+use gitbutler_branch::BranchId;
+use gitbutler_command_context::ProjectRepository;
+use gitbutler_repo::{LogUntil, RepoActionsExt};
+use serde::Serialize;
+
+use crate::VirtualBranchesExt;
+
+/// A virtual branch whose commits are all contained in another virtual branch.
+#[derive(Debug, PartialEq, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct DuplicateBranch {
+    pub branch_id: BranchId,
+    /// The branch that contains all commits of `branch_id`.
+    pub duplicate_of: BranchId,
+    /// `true` if both branches have exactly the same commits, `false` if `branch_id` only has a subset of them.
+    pub identical: bool,
+}
+
+/// Find the virtual branches in the workspace whose commits, relative to the target, are the same as or
+/// a subset of those of another branch. Branches without commits are never considered duplicates.
+///
+/// Identical branches are reported once, with the one later in the workspace order being the duplicate.
+pub(crate) fn find_duplicate_branches(ctx: &ProjectRepository) -> Result<Vec<DuplicateBranch>> {
+    let vb_state = ctx.project().virtual_branches();
+    let default_target = vb_state.get_default_target()?;
+
+    let mut branches = vb_state.list_branches_in_workspace()?;
+    branches.sort_by_key(|branch| branch.order);
+    let branch_commits = branches
+        .iter()
+        .map(|branch| {
+            let commits = ctx
+                .l(branch.head, LogUntil::Commit(default_target.sha))?
+                .into_iter()
+                .collect::<HashSet<_>>();
+            Ok((branch.id, commits))
+        })
+        .collect::<Result<Vec<_>>>()?;
+
+    let mut duplicates = Vec::new();
+    for (index, (branch_id, commits)) in branch_commits.iter().enumerate() {
+        if commits.is_empty() {
+            continue;
+        }
+        let duplicate = branch_commits
+            .iter()
+            .enumerate()
+            .filter(|(other_index, _)| *other_index != index)
+            .find_map(|(other_index, (other_id, other_commits))| {
+                let identical = commits == other_commits;
+                // of two identical branches, only the later one is a duplicate
+                if identical && other_index > index {
+                    return None;
+                }
+                commits.is_subset(other_commits).then_some(DuplicateBranch {
+                    branch_id: *branch_id,
+                    duplicate_of: *other_id,
+                    identical,
+                })
+            });
+        duplicates.extend(duplicate);
+    }
+    Ok(duplicates)
+}
@@ -0,0 +1,155 @@
+use std::path::{Path, PathBuf};
+use std::process::Command;
+
+use anyhow::{anyhow, Context, Result};
+use gitbutler_command_context::ProjectRepository;
+use gitbutler_error::error::Code;
+use serde::Deserialize;
+
+use crate::VirtualBranchesExt;
+
+/// The version a worktree file is compared with in an external diff tool.
+#[derive(Debug, PartialEq, Eq, Clone, Copy, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum DiffSide {
+    /// The version of the base branch.
+    Base,
+    /// The version of the last commit in the workspace.
+    Head,
+}
+
+/// Write the `side` version and the worktree version of the file at the worktree-relative `path` to temporary
+/// files, and open them in the diff tool configured with `diff.tool`, or `merge.tool` if there is none, just
+/// like `git difftool` would.
+///
+/// Returns once the tool exits, after removing the temporary files. Binary files are refused.
+pub(crate) fn launch_external_diff(
+    ctx: &ProjectRepository,
+    path: &Path,
+    side: DiffSide,
+) -> Result<()> {
+    crate::files::ensure_within_worktree(path)?;
+    let repo = ctx.repo();
+    let config = repo.config()?.snapshot()?;
+    let tool = config
+        .get_string("diff.tool")
+        .or_else(|_| config.get_string("merge.tool"))
+        .map_err(|_| {
+            anyhow!(
+                "No external diff tool is configured, set one with `git config diff.tool <tool>`"
+            )
+            .context(Code::Validation)
+        })?;
+
+    let tree = match side {
+        DiffSide::Base => {
+            let target = ctx.project().virtual_branches().get_default_target()?;
+            repo.find_commit(target.sha)?.tree()?
+        }
+        DiffSide::Head => repo.head()?.peel_to_tree()?,
+    };
+    // Files that don't exist on one side are compared with an empty file, like git does.
+    let side_content = match tree.get_path(path) {
+        Ok(entry) => repo.find_blob(entry.id())?.content().to_owned(),
+        Err(err) if err.code() == git2::ErrorCode::NotFound => Vec::new(),
+        Err(err) => return Err(err.into()),
+    };
+    let worktree_content = match std::fs::read(ctx.project().path.join(path)) {
+        Ok(content) => content,
+        Err(err) if err.kind() == std::io::ErrorKind::NotFound => Vec::new(),
+        Err(err) => return Err(err.into()),
+    };
+    if is_binary(&side_content) || is_binary(&worktree_content) {
+        return Err(
+            anyhow!("Binary files can't be opened in an external diff tool")
+                .context(Code::Validation),
+        );
+    }
+
+    // Keep the file name so the tool can pick syntax highlighting by extension.
+    let file_name = path
+        .file_name()
+        .with_context(|| format!("{} isn't a file", path.display()))?;
+    let temp_dir = tempfile::tempdir()?;
+    let local = temp_dir.path().join("local").join(file_name);
+    let remote = temp_dir.path().join("remote").join(file_name);
+    gitbutler_fs::create_dirs_then_write(&local, &side_content)?;
+    gitbutler_fs::create_dirs_then_write(&remote, &worktree_content)?;
+
+    let mut command = match config.get_string(&format!("difftool.{tool}.cmd")) {
+        // Like git, run custom commands with a POSIX shell so they can refer to the files by variable.
+        Ok(cmd) => {
+            let mut command = Command::new(shell());
+            command.arg("-c").arg(cmd);
+            command
+        }
+        Err(_) => {
+            let (program, args) = builtin_tool(&tool).unwrap_or((tool.as_str(), &[]));
+            let program = config
+                .get_path(&format!("difftool.{tool}.path"))
+                .unwrap_or_else(|_| program.into());
+            let mut command = Command::new(program);
+            command.args(args).arg(&local).arg(&remote);
+            command
+        }
+    };
+    // Some tools signal differences with their exit code, so it isn't considered.
+    command
+        .env("LOCAL", &local)
+        .env("REMOTE", &remote)
+        .env("MERGED", path)
+        .env("BASE", path)
+        .current_dir(&ctx.project().path)
+        .status()
+        .with_context(|| format!("failed to run the diff tool '{tool}'"))?;
+    Ok(())
+}
+
+/// The program and the arguments before the two files to compare for the tools git knows without
+/// configuration, a subset of those listed by `git difftool --tool-help`.
+fn builtin_tool(tool: &str) -> Option<(&'static str, &'static [&'static str])> {
+    Some(match tool {
+        "bc" | "bc3" | "bc4" => ("bcompare", &[]),
+        "codecompare" => ("CodeCompare", &[]),
+        "deltawalker" => ("DeltaWalker", &[]),
+        "diffmerge" => ("diffmerge", &[]),
+        "diffuse" => ("diffuse", &[]),
+        "ecmerge" => ("ecmerge", &["--default", "--mode=diff2"]),
+        "gvimdiff" => ("gvim", &["-R", "-f", "-d"]),
+        "kdiff3" => ("kdiff3", &[]),
+        "kompare" => ("kompare", &[]),
+        "meld" => ("meld", &[]),
+        "nvimdiff" => ("nvim", &["-R", "-f", "-d"]),
+        "opendiff" => ("opendiff", &[]),
+        "p4merge" => ("p4merge", &[]),
+        "smerge" => ("smerge", &["mergetool"]),
+        "tkdiff" => ("tkdiff", &[]),
+        "vimdiff" => ("vim", &["-R", "-f", "-d"]),
+        "winmerge" => ("WinMergeU", &["-u", "-e"]),
+        "xxdiff" => ("xxdiff", &[]),
+        _ => return None,
+    })
+}
+
+/// The POSIX shell to run custom diff commands with, which on Windows is the one of Git for Windows.
+fn shell() -> PathBuf {
+    #[cfg(windows)]
+    {
+        // Git for Windows keeps `git.exe` in `cmd` or `bin` and `sh.exe` in `bin` of its installation.
+        gix_path::env::exe_invocation()
+            .parent()
+            .and_then(Path::parent)
+            .map(|root| root.join("bin").join("sh.exe"))
+            .filter(|sh| sh.is_file())
+            .unwrap_or_else(|| "sh.exe".into())
+    }
+    #[cfg(not(windows))]
+    {
+        "sh".into()
+    }
+}
+
+/// Like git, consider content binary if it has a NUL byte in its first 8000 bytes.
+pub(crate) fn is_binary(content: &[u8]) -> bool {
+    content.iter().take(8000).any(|b| *b == 0)
+}
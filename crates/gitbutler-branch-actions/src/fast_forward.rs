@@ -0,0 +1,47 @@
+use anyhow::{Context, Result};
+use gitbutler_branch::BranchId;
+use gitbutler_command_context::ProjectRepository;
+use serde::Serialize;
+
+use crate::VirtualBranchesExt;
+
+/// How the head of a virtual branch relates to the head of the base branch, to know whether one can be
+/// moved to the other without creating a merge commit.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct FastForward {
+    /// The head of the base branch is an ancestor of the branch head, so merging the branch into the base
+    /// is a fast-forward.
+    pub base_is_ancestor: bool,
+    /// The branch head is an ancestor of the head of the base branch, so the branch can be fast-forwarded
+    /// to the base.
+    pub branch_is_ancestor: bool,
+}
+
+/// Return how the branch with `branch_id` relates to the head of the base branch.
+/// If both point to the same commit, each is an ancestor of the other.
+pub(crate) fn can_fast_forward(
+    ctx: &ProjectRepository,
+    branch_id: BranchId,
+) -> Result<FastForward> {
+    let vb_state = ctx.project().virtual_branches();
+    let target = vb_state.get_default_target()?;
+    let branch = vb_state.get_branch_in_workspace(branch_id)?;
+    let repo = ctx.repo();
+    let base_head = repo
+        .find_reference(&target.branch.to_string())
+        .and_then(|reference| reference.peel_to_commit())
+        .with_context(|| format!("failed to find head of base branch {}", target.branch))?
+        .id();
+
+    if base_head == branch.head {
+        return Ok(FastForward {
+            base_is_ancestor: true,
+            branch_is_ancestor: true,
+        });
+    }
+    Ok(FastForward {
+        base_is_ancestor: repo.graph_descendant_of(branch.head, base_head)?,
+        branch_is_ancestor: repo.graph_descendant_of(base_head, branch.head)?,
+    })
+}
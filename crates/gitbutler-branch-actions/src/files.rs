@@ -1,8 +1,16 @@
-use std::path;
+use std::{collections::BTreeSet, path};
 
-use anyhow::{anyhow, Context, Result};
+use anyhow::{anyhow, bail, Context, Result};
+use bstr::ByteSlice;
 use serde::Serialize;
 
+use gitbutler_branch::BranchId;
+use gitbutler_command_context::ProjectRepository;
+use gitbutler_error::error::Code;
+use gitbutler_repo::{LogUntil, RepoActionsExt};
+
+use crate::{integration::get_workspace_head, r#virtual as vb, VirtualBranchesExt};
+
 #[derive(Debug, PartialEq, Clone, Serialize)]
 #[serde(rename_all = "camelCase")]
 pub struct RemoteBranchFile {
@@ -11,6 +19,35 @@ pub struct RemoteBranchFile {
     pub binary: bool,
 }
 
+/// The net effect of a subset of the branches in the workspace, as if only they were applied.
+#[derive(Debug, PartialEq, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SubsetNetDiff {
+    /// The diff between the tree of the default target and the tree merging the heads of the
+    /// branches that merged cleanly, sorted by path.
+    pub files: Vec<RemoteBranchFile>,
+    /// The branches that conflict with the branches selected before them, which are left out of `files`.
+    pub conflicts: Vec<SubsetConflict>,
+}
+
+/// A branch of a subset that couldn't be merged with the branches selected before it.
+#[derive(Debug, PartialEq, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SubsetConflict {
+    pub branch_id: BranchId,
+    /// The paths that conflict, sorted.
+    pub paths: Vec<path::PathBuf>,
+}
+
+/// The content of a file as of a commit.
+#[derive(Debug, PartialEq, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct FileAtCommit {
+    pub content: Vec<u8>,
+    /// `true` if the content isn't text.
+    pub binary: bool,
+}
+
 pub(crate) fn list_remote_commit_files(
     repository: &git2::Repository,
     commit_id: git2::Oid,
@@ -32,7 +69,81 @@ pub(crate) fn list_remote_commit_files(
     let parent_tree = parent.tree().context("failed to get parent tree")?;
     let diff_files = gitbutler_diff::trees(repository, &parent_tree, &commit_tree)?;
 
-    Ok(diff_files
+    Ok(into_remote_branch_files(diff_files))
+}
+
+/// Returns the net effect of all branches in the workspace, i.e. the diff between the tree of the
+/// default target and the tree that merges the heads of all applied branches, sorted by path.
+/// Uncommitted changes are not part of it.
+pub(crate) fn integration_net_diff(ctx: &ProjectRepository) -> Result<Vec<RemoteBranchFile>> {
+    let repository = ctx.repo();
+    let vb_state = ctx.project().virtual_branches();
+    let default_target = vb_state.get_default_target()?;
+    let base_tree = repository
+        .find_commit(default_target.sha)
+        .and_then(|commit| commit.tree())
+        .context("failed to get target tree")?;
+    let integration_tree = repository
+        .find_commit(get_workspace_head(&vb_state, ctx)?)
+        .and_then(|commit| commit.tree())
+        .context("failed to get integration tree")?;
+    let diff_files = gitbutler_diff::trees(repository, &base_tree, &integration_tree)?;
+
+    let mut files = into_remote_branch_files(diff_files);
+    files.sort_by(|a, b| a.path.cmp(&b.path));
+    Ok(files)
+}
+
+/// Returns the net effect of the branches with `branch_ids` in the workspace, merging their heads in the given
+/// order onto the tree of the default target without touching the workspace, and diffing the result against it.
+/// Uncommitted changes are not part of it.
+pub(crate) fn subset_net_diff(
+    ctx: &ProjectRepository,
+    branch_ids: &[BranchId],
+) -> Result<SubsetNetDiff> {
+    let repository = ctx.repo();
+    let vb_state = ctx.project().virtual_branches();
+    let default_target = vb_state.get_default_target()?;
+    let base_tree = repository
+        .find_commit(default_target.sha)
+        .and_then(|commit| commit.tree())
+        .context("failed to get target tree")?;
+
+    let mut subset_tree = base_tree.clone();
+    let mut conflicts = Vec::new();
+    let mut seen = BTreeSet::new();
+    for branch_id in branch_ids.iter().copied().filter(|id| seen.insert(*id)) {
+        let branch = vb_state.get_branch_in_workspace(branch_id)?;
+        let branch_tree = repository
+            .find_commit(branch.head)
+            .and_then(|commit| commit.tree())
+            .with_context(|| format!("failed to get head tree of branch {}", branch.name))?;
+        let mut index = repository.merge_trees(&base_tree, &subset_tree, &branch_tree, None)?;
+        if index.has_conflicts() {
+            let mut paths = index
+                .conflicts()?
+                .flatten()
+                .filter_map(|conflict| conflict.our.or(conflict.their).or(conflict.ancestor))
+                .map(|entry| entry.path.to_path_lossy().into_owned())
+                .collect::<Vec<_>>();
+            paths.sort();
+            paths.dedup();
+            conflicts.push(SubsetConflict { branch_id, paths });
+        } else {
+            subset_tree = repository.find_tree(index.write_tree_to(repository)?)?;
+        }
+    }
+    let diff_files = gitbutler_diff::trees(repository, &base_tree, &subset_tree)?;
+
+    let mut files = into_remote_branch_files(diff_files);
+    files.sort_by(|a, b| a.path.cmp(&b.path));
+    Ok(SubsetNetDiff { files, conflicts })
+}
+
+fn into_remote_branch_files(
+    diff_files: impl IntoIterator<Item = (path::PathBuf, gitbutler_diff::FileDiff)>,
+) -> Vec<RemoteBranchFile> {
+    diff_files
         .into_iter()
         .map(|(path, file)| {
             let binary = file.hunks.iter().any(|h| h.binary);
@@ -42,5 +153,102 @@ pub(crate) fn list_remote_commit_files(
                 binary,
             }
         })
-        .collect())
+        .collect()
+}
+
+/// Returns the paths the branch `branch_id` changes relative to its merge base with the target, i.e. the
+/// files touched by its commits as well as its uncommitted files, sorted and de-duplicated.
+/// Renamed files are listed with both their previous and their current path.
+pub(crate) fn branch_changed_files(
+    ctx: &ProjectRepository,
+    branch_id: BranchId,
+) -> Result<Vec<path::PathBuf>> {
+    let repository = ctx.repo();
+    let vb_state = ctx.project().virtual_branches();
+    let default_target = vb_state.get_default_target()?;
+    let integration_commit = get_workspace_head(&vb_state, ctx)?;
+    let (statuses, _, _) = vb::get_status_by_branch(ctx, Some(&integration_commit), None)?;
+    let (branch, uncommitted_files) = statuses
+        .into_iter()
+        .find(|(branch, _)| branch.id == branch_id)
+        .with_context(|| format!("branch {branch_id} is not in the workspace"))?;
+
+    let merge_base = repository
+        .merge_base(default_target.sha, branch.head)
+        .context("failed to find merge base")?;
+    let base_tree = repository
+        .find_commit(merge_base)
+        .and_then(|commit| commit.tree())
+        .context("failed to get merge base tree")?;
+    let head_tree = repository
+        .find_commit(branch.head)
+        .and_then(|commit| commit.tree())
+        .context("failed to get branch head tree")?;
+
+    let mut diff = repository.diff_tree_to_tree(Some(&base_tree), Some(&head_tree), None)?;
+    diff.find_similar(Some(git2::DiffFindOptions::new().renames(true)))?;
+
+    let mut paths = BTreeSet::new();
+    for delta in diff.deltas() {
+        paths.extend(delta.old_file().path().map(path::Path::to_owned));
+        paths.extend(delta.new_file().path().map(path::Path::to_owned));
+    }
+    paths.extend(uncommitted_files.into_keys());
+    Ok(paths.into_iter().collect())
+}
+
+/// Returns the content of the file at `path` in the tree of `commit_id`, which has to be one of the commits of `branch_id`.
+pub(crate) fn show_file_at_commit(
+    ctx: &ProjectRepository,
+    branch_id: BranchId,
+    commit_id: git2::Oid,
+    path: &path::Path,
+) -> Result<FileAtCommit> {
+    let vb_state = ctx.project().virtual_branches();
+    let branch = vb_state.get_branch(branch_id)?;
+    let default_target = vb_state.get_default_target()?;
+    if !ctx
+        .l(branch.head, LogUntil::Commit(default_target.sha))?
+        .contains(&commit_id)
+    {
+        bail!("commit {commit_id} is not on branch {}", branch.name);
+    }
+
+    let repository = ctx.repo();
+    let tree = repository
+        .find_commit(commit_id)?
+        .tree()
+        .context("failed to get commit tree")?;
+    let entry = tree.get_path(path).map_err(|err| match err.code() {
+        git2::ErrorCode::NotFound => {
+            anyhow!("{} does not exist in commit {commit_id}", path.display())
+        }
+        _ => err.into(),
+    })?;
+    let blob = entry
+        .to_object(repository)?
+        .into_blob()
+        .map_err(|_| anyhow!("{} is not a file in commit {commit_id}", path.display()))?;
+    Ok(FileAtCommit {
+        content: blob.content().to_vec(),
+        binary: blob.is_binary(),
+    })
+}
+
+/// Fail with a validation error unless `path` stays within the worktree, i.e. is relative and doesn't
+/// leave it through `..`.
+pub(crate) fn ensure_within_worktree(path: &path::Path) -> Result<()> {
+    if path.components().any(|component| {
+        !matches!(
+            component,
+            path::Component::Normal(_) | path::Component::CurDir
+        )
+    }) {
+        return Err(anyhow!(
+            "{} must be a path relative to the worktree that stays within it",
+            path.display()
+        )
+        .context(Code::Validation));
+    }
+    Ok(())
 }
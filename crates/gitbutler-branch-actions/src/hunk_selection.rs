@@ -0,0 +1,134 @@
+use std::path::{Path, PathBuf};
+
+use anyhow::{anyhow, Context, Result};
+use gitbutler_branch::{BranchId, BranchOwnershipClaims, OwnershipClaim};
+use gitbutler_command_context::ProjectRepository;
+use gitbutler_diff::{GitHunk, Hunk};
+use gitbutler_error::error::Code;
+use serde::{Deserialize, Serialize};
+
+use crate::{integration::get_workspace_head, r#virtual as branch, VirtualBranchesExt};
+
+/// How many lines a hunk may have moved since it was selected and still be recognized as the same hunk.
+const MAX_LINE_SHIFT: u32 = 5;
+
+/// Identifies a hunk of a file by the lines it covers before and after the change, as in its
+/// `@@ -old_start,old_lines +new_start,new_lines @@` header.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct HunkSelector {
+    pub old_start: u32,
+    pub old_lines: u32,
+    pub new_start: u32,
+    pub new_lines: u32,
+}
+
+impl HunkSelector {
+    /// Return how far `hunk` moved from the lines of this selector, or `None` if it isn't the selected hunk
+    /// as it changes a different amount of lines or moved too far.
+    fn shift(&self, hunk: &GitHunk) -> Option<u32> {
+        if hunk.old_lines != self.old_lines || hunk.new_lines != self.new_lines {
+            return None;
+        }
+        let shift = hunk
+            .old_start
+            .abs_diff(self.old_start)
+            .max(hunk.new_start.abs_diff(self.new_start));
+        (shift <= MAX_LINE_SHIFT).then_some(shift)
+    }
+}
+
+/// The hunks of a file that a selection would commit, and those that would stay in the working directory.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct HunkSelection {
+    pub path: PathBuf,
+    /// The hunks matching the selectors, in the order they appear in the file.
+    pub selected: Vec<GitHunk>,
+    /// The hunks that aren't selected, in the order they appear in the file.
+    pub remaining: Vec<GitHunk>,
+}
+
+/// Match `selectors` against the uncommitted hunks of `path` owned by the branch with `branch_id`.
+///
+/// Each selector picks the hunk changing the same amount of lines that is closest to the lines it names, so
+/// a selection stays valid if other changes shifted the hunk by a few lines. It's an error if a selector
+/// doesn't match any hunk.
+pub(crate) fn select_hunks(
+    ctx: &ProjectRepository,
+    branch_id: BranchId,
+    path: &Path,
+    selectors: &[HunkSelector],
+) -> Result<HunkSelection> {
+    let vb_state = ctx.project().virtual_branches();
+    let integration_commit_id = get_workspace_head(&vb_state, ctx)?;
+    let (statuses, _, _) = branch::get_status_by_branch(ctx, Some(&integration_commit_id), None)
+        .context("failed to get status by branch")?;
+    let (_, mut files) = statuses
+        .into_iter()
+        .find(|(branch, _)| branch.id == branch_id)
+        .with_context(|| format!("branch {branch_id} not found"))?;
+    let mut hunks = files.remove(path).ok_or_else(|| {
+        anyhow!(
+            "The branch has no uncommitted changes in '{}'",
+            path.display()
+        )
+        .context(Code::Validation)
+    })?;
+    hunks.sort_by_key(|hunk| hunk.new_start);
+
+    let mut is_selected = vec![false; hunks.len()];
+    for selector in selectors {
+        let closest = hunks
+            .iter()
+            .enumerate()
+            .filter(|(idx, _)| !is_selected[*idx])
+            .filter_map(|(idx, hunk)| selector.shift(hunk).map(|shift| (shift, idx)))
+            .min();
+        let Some((_, idx)) = closest else {
+            return Err(anyhow!(
+                "No change in '{}' matches the hunk at -{},{} +{},{}",
+                path.display(),
+                selector.old_start,
+                selector.old_lines,
+                selector.new_start,
+                selector.new_lines
+            )
+            .context(Code::Validation));
+        };
+        is_selected[idx] = true;
+    }
+
+    let (selected, remaining): (Vec<_>, Vec<_>) = hunks
+        .into_iter()
+        .zip(is_selected)
+        .partition(|(_, is_selected)| *is_selected);
+    Ok(HunkSelection {
+        path: path.to_owned(),
+        selected: selected.into_iter().map(|(hunk, _)| hunk).collect(),
+        remaining: remaining.into_iter().map(|(hunk, _)| hunk).collect(),
+    })
+}
+
+/// Commit only the hunks of `path` matched by `selectors` to the branch with `branch_id`, leaving the other
+/// changes of the file in the working directory.
+pub(crate) fn commit_hunks(
+    ctx: &ProjectRepository,
+    branch_id: BranchId,
+    path: &Path,
+    selectors: &[HunkSelector],
+    message: &str,
+    run_hooks: bool,
+) -> Result<git2::Oid> {
+    let selection = select_hunks(ctx, branch_id, path, selectors)?;
+    if selection.selected.is_empty() {
+        return Err(anyhow!("At least one hunk has to be selected").context(Code::Validation));
+    }
+    let ownership = BranchOwnershipClaims {
+        claims: vec![OwnershipClaim {
+            file_path: selection.path,
+            hunks: selection.selected.iter().map(Hunk::from).collect(),
+        }],
+    };
+    branch::commit(ctx, branch_id, message, Some(&ownership), run_hooks)
+}
@@ -359,7 +359,7 @@ fn verify_head_is_clean(ctx: &ProjectRepository, perm: &mut WorktreeWritePermiss
             &BranchCreateRequest {
                 name: extra_commits
                     .last()
-                    .map(|commit| commit.message_bstr().to_string()),
+                    .map(|commit| commit.message_decoded().to_string()),
                 ..Default::default()
             },
             perm,
@@ -382,7 +382,7 @@ fn verify_head_is_clean(ctx: &ProjectRepository, perm: &mut WorktreeWritePermiss
                 None,
                 &commit.author(),
                 &commit.committer(),
-                &commit.message_bstr().to_str_lossy(),
+                &commit.message_decoded().to_str_lossy(),
                 &commit.tree().unwrap(),
                 &[&new_branch_head],
                 None,
@@ -6,16 +6,65 @@ mod r#virtual;
 pub use r#virtual::*;
 
 mod branch_manager;
-pub use branch_manager::{BranchManager, BranchManagerExt};
+pub use branch_manager::{
+    BranchManager, BranchManagerExt, LocalBranchImport, LocalBranchImportOptions,
+    SkippedLocalBranch, StackApplication,
+};
 
 mod base;
-pub use base::BaseBranch;
+pub use base::{BaseBranch, BranchDivergence};
+
+mod base_cache;
+
+mod base_rewrite;
+pub use base_rewrite::BaseHistoryRewrite;
 
 mod integration;
 pub use integration::{update_gitbutler_integration, verify_branch};
 
 mod files;
-pub use files::RemoteBranchFile;
+pub use files::{FileAtCommit, RemoteBranchFile, SubsetConflict, SubsetNetDiff};
+
+mod diagnostics;
+pub use diagnostics::{Finding, FindingKind, FindingSeverity, ProjectDiagnostics, ProjectRepair};
+
+mod duplicates;
+pub use duplicates::DuplicateBranch;
+
+mod linkage;
+pub use linkage::{DanglingReference, LinkageKind};
+
+mod risk;
+pub use risk::{CommitAtRisk, RiskyOperation};
+
+mod fast_forward;
+pub use fast_forward::FastForward;
+
+mod ownership_conflicts;
+pub use ownership_conflicts::OwnershipConflict;
+
+mod blast_radius;
+pub use blast_radius::{FileRisk, TouchedFile};
+
+mod hunk_selection;
+pub use hunk_selection::{HunkSelection, HunkSelector};
+
+mod blame;
+pub use blame::{LineAttribution, LineOrigin};
+
+mod external_diff;
+pub use external_diff::DiffSide;
+
+mod unapply_report;
+pub use unapply_report::{UnapplyConflict, UnapplyConflictKind, UnapplyReport};
+
+mod commit_lint;
+pub use commit_lint::{CommitLintReport, CommitLintViolation};
+
+mod stack;
+
+mod workspaces;
+pub use workspaces::{Workspace, WorkspaceConflict, WorkspaceConflictKind, WorkspaceSwitch};
 
 mod remote;
 pub use remote::{list_remote_branches, RemoteBranch, RemoteBranchData, RemoteCommit};
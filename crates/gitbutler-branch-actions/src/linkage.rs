@@ -0,0 +1,81 @@
+use anyhow::Result;
+use gitbutler_branch::BranchId;
+use gitbutler_command_context::ProjectRepository;
+use serde::Serialize;
+
+use crate::VirtualBranchesExt;
+
+/// What an object id recorded in the virtual branches state is used for.
+#[derive(Debug, PartialEq, Eq, Clone, Copy, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub enum LinkageKind {
+    /// The commit of the default target.
+    Target,
+    /// The head commit of a branch.
+    BranchHead,
+    /// The tree of a branch, including its uncommitted changes.
+    BranchTree,
+    /// The last known head of the upstream of a branch.
+    UpstreamHead,
+}
+
+/// An object id recorded in the virtual branches state that doesn't exist in the project repository,
+/// for instance after the object was pruned by `git gc --prune`.
+#[derive(Debug, PartialEq, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct DanglingReference {
+    /// The branch recording the id, or `None` if it is recorded for the default target.
+    pub branch_id: Option<BranchId>,
+    pub kind: LinkageKind,
+    #[serde(with = "gitbutler_serde::serde::oid")]
+    pub id: git2::Oid,
+    /// A human readable hint on how to repair the reference.
+    pub suggestion: String,
+}
+
+/// Verify that all object ids recorded in the virtual branches state still exist in the project repository,
+/// and return the ones that don't.
+pub(crate) fn check_repo_linkage(ctx: &ProjectRepository) -> Result<Vec<DanglingReference>> {
+    let vb_state = ctx.project().virtual_branches();
+    let odb = ctx.repo().odb()?;
+
+    let mut dangling = Vec::new();
+    let default_target = vb_state.get_default_target()?;
+    if !odb.exists(default_target.sha) {
+        dangling.push(DanglingReference {
+            branch_id: None,
+            kind: LinkageKind::Target,
+            id: default_target.sha,
+            suggestion: format!(
+                "fetch {} or set the base branch again",
+                default_target.branch
+            ),
+        });
+    }
+
+    for branch in vb_state.list_all_branches()? {
+        let recorded = [
+            (LinkageKind::BranchHead, Some(branch.head)),
+            (LinkageKind::BranchTree, Some(branch.tree)),
+            (LinkageKind::UpstreamHead, branch.upstream_head),
+        ];
+        for (kind, id) in recorded {
+            let Some(id) = id.filter(|id| !odb.exists(*id)) else {
+                continue;
+            };
+            let suggestion = match kind {
+                LinkageKind::UpstreamHead => {
+                    format!("fetch to update the upstream of branch {}", branch.name)
+                }
+                _ => format!("reset or delete branch {}", branch.name),
+            };
+            dangling.push(DanglingReference {
+                branch_id: Some(branch.id),
+                kind,
+                id,
+                suggestion,
+            });
+        }
+    }
+    Ok(dangling)
+}
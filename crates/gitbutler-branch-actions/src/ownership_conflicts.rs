@@ -0,0 +1,249 @@
+use std::collections::BTreeMap;
+use std::path::{Path, PathBuf};
+
+use anyhow::{anyhow, Result};
+use gitbutler_branch::{BranchId, BranchOwnershipClaims, OwnershipClaim};
+use gitbutler_command_context::ProjectRepository;
+use gitbutler_diff::Hunk;
+use gitbutler_error::error::Code;
+use gitbutler_project::access::WorktreeWritePermission;
+use serde::Serialize;
+
+use crate::VirtualBranchesExt;
+
+/// Lines that are claimed by more than one virtual branch, even though ownership should be exclusive.
+#[derive(Debug, PartialEq, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct OwnershipConflict {
+    pub file_path: PathBuf,
+    /// The hunk of the first branch claiming the lines as `start-end[-hash]`, which together with `file_path`
+    /// is the claim to pass when resolving it.
+    pub hunk: String,
+    /// The branches claiming the hunk, in workspace order.
+    pub branch_ids: Vec<BranchId>,
+}
+
+/// Find the lines that are claimed by more than one branch in the workspace, sorted by path.
+///
+/// Hunks conflict as soon as their lines overlap, and all hunks that overlap each other, directly or through
+/// others, make up a single conflict.
+pub(crate) fn detect_ownership_conflicts(
+    ctx: &ProjectRepository,
+) -> Result<Vec<OwnershipConflict>> {
+    let mut branches = ctx
+        .project()
+        .virtual_branches()
+        .list_branches_in_workspace()?;
+    branches.sort_by_key(|branch| branch.order);
+
+    // The groups of overlapping hunks per path, each with the branches claiming them in workspace order.
+    let mut groups_by_path: BTreeMap<PathBuf, Vec<Vec<(Hunk, BranchId)>>> = BTreeMap::new();
+    for branch in &branches {
+        for claim in &branch.ownership.claims {
+            let groups = groups_by_path.entry(claim.file_path.clone()).or_default();
+            for hunk in &claim.hunks {
+                let mut group = vec![(hunk.clone(), branch.id)];
+                let mut idx = 0;
+                while idx < groups.len() {
+                    if groups[idx]
+                        .iter()
+                        .any(|(claimed, _)| overlaps(claimed, hunk))
+                    {
+                        let mut overlapping = groups.remove(idx);
+                        overlapping.append(&mut group);
+                        group = overlapping;
+                    } else {
+                        idx += 1;
+                    }
+                }
+                groups.push(group);
+            }
+        }
+    }
+
+    Ok(groups_by_path
+        .into_iter()
+        .flat_map(|(file_path, groups)| {
+            groups.into_iter().filter_map(move |group| {
+                let branch_ids = branches
+                    .iter()
+                    .map(|branch| branch.id)
+                    .filter(|id| group.iter().any(|(_, claimant)| claimant == id))
+                    .collect::<Vec<_>>();
+                (branch_ids.len() > 1).then(|| OwnershipConflict {
+                    file_path: file_path.clone(),
+                    hunk: group
+                        .iter()
+                        .find(|(_, claimant)| *claimant == branch_ids[0])
+                        .map(|(hunk, _)| hunk.to_string())
+                        .unwrap_or_default(),
+                    branch_ids,
+                })
+            })
+        })
+        .collect())
+}
+
+/// Return `true` if `a` and `b` share lines. Hunks span the lines from `start` up to but excluding `end`, except
+/// for empty ones, like those of deletions, which cover the line at `start` they are anchored to.
+fn overlaps(a: &Hunk, b: &Hunk) -> bool {
+    let end = |hunk: &Hunk| hunk.end.max(hunk.start + 1);
+    a.start < end(b) && b.start < end(a)
+}
+
+/// Return the hunks claimed in `ownership` for `file_path` that overlap with any of `hunks`.
+fn overlapping_hunks(
+    ownership: &BranchOwnershipClaims,
+    file_path: &Path,
+    hunks: &[Hunk],
+) -> Vec<Hunk> {
+    ownership
+        .claims
+        .iter()
+        .filter(|owned| owned.file_path == file_path)
+        .flat_map(|owned| &owned.hunks)
+        .filter(|owned| hunks.iter().any(|hunk| overlaps(owned, hunk)))
+        .cloned()
+        .collect()
+}
+
+/// Make `owner` the only branch in the workspace that claims the lines of the hunks in `ownership`, releasing
+/// every hunk overlapping them from all other branches.
+pub(crate) fn resolve_ownership_conflicts(
+    ctx: &ProjectRepository,
+    owner: BranchId,
+    ownership: &BranchOwnershipClaims,
+    _perm: &mut WorktreeWritePermission,
+) -> Result<()> {
+    let vb_state = ctx.project().virtual_branches();
+    // make sure the new owner is applied before changing anything
+    vb_state.get_branch_in_workspace(owner)?;
+
+    for mut branch in vb_state.list_branches_in_workspace()? {
+        for claim in &ownership.claims {
+            // claims without hunks are for whole files
+            if claim.hunks.is_empty() {
+                if branch.id == owner {
+                    branch.ownership.put(claim.clone());
+                } else {
+                    branch.ownership.take(claim);
+                }
+                continue;
+            }
+            let overlapping = overlapping_hunks(&branch.ownership, &claim.file_path, &claim.hunks);
+            if branch.id == owner {
+                // the owner keeps its own version of the lines it already claims
+                let missing = claim
+                    .hunks
+                    .iter()
+                    .filter(|hunk| !overlapping.iter().any(|owned| overlaps(owned, hunk)))
+                    .cloned()
+                    .collect::<Vec<_>>();
+                if !missing.is_empty() {
+                    branch.ownership.put(OwnershipClaim {
+                        file_path: claim.file_path.clone(),
+                        hunks: missing,
+                    });
+                }
+            } else if !overlapping.is_empty() {
+                branch.ownership.take(&OwnershipClaim {
+                    file_path: claim.file_path.clone(),
+                    hunks: overlapping,
+                });
+            }
+        }
+        vb_state.set_branch(branch)?;
+    }
+    Ok(())
+}
+
+/// Move the uncommitted `changes` from the branch with `from` to the branch with `to` by reassigning their
+/// ownership, leaving the working directory as is.
+///
+/// A change without hunks moves all hunks of its file. It's an error if `from` doesn't own a change, or if
+/// `to` already owns other hunks covering the same lines.
+pub(crate) fn move_changes(
+    ctx: &ProjectRepository,
+    from: BranchId,
+    to: BranchId,
+    changes: &[OwnershipClaim],
+    _perm: &mut WorktreeWritePermission,
+) -> Result<()> {
+    if from == to {
+        return Err(
+            anyhow!("Changes can only be moved to another branch").context(Code::Validation)
+        );
+    }
+    let vb_state = ctx.project().virtual_branches();
+    let mut from_branch = vb_state.get_branch_in_workspace(from)?;
+    let mut to_branch = vb_state.get_branch_in_workspace(to)?;
+
+    let mut moved = Vec::new();
+    for change in changes {
+        let owned = from_branch
+            .ownership
+            .claims
+            .iter()
+            .find(|claim| claim.file_path == change.file_path)
+            .ok_or_else(|| {
+                anyhow!(
+                    "Branch {} has no uncommitted changes in '{}'",
+                    from_branch.name,
+                    change.file_path.display()
+                )
+                .context(Code::Validation)
+            })?;
+        // Move the hunks as owned, so they keep their hash even if the change was given without it.
+        let hunks = if change.hunks.is_empty() {
+            owned.hunks.clone()
+        } else {
+            change
+                .hunks
+                .iter()
+                .map(|hunk| {
+                    owned
+                        .hunks
+                        .iter()
+                        .find(|owned| *owned == hunk)
+                        .cloned()
+                        .ok_or_else(|| {
+                            anyhow!(
+                                "Branch {} doesn't own the hunk {hunk} of '{}'",
+                                from_branch.name,
+                                change.file_path.display()
+                            )
+                            .context(Code::Validation)
+                        })
+                })
+                .collect::<Result<_>>()?
+        };
+        moved.push(OwnershipClaim {
+            file_path: change.file_path.clone(),
+            hunks,
+        });
+    }
+
+    for claim in &moved {
+        let overlapping = overlapping_hunks(&to_branch.ownership, &claim.file_path, &claim.hunks)
+            .iter()
+            .map(ToString::to_string)
+            .collect::<Vec<_>>();
+        if !overlapping.is_empty() {
+            return Err(anyhow!(
+                "Branch {} already owns the lines of '{}' at {}",
+                to_branch.name,
+                claim.file_path.display(),
+                overlapping.join(", ")
+            )
+            .context(Code::Validation));
+        }
+    }
+
+    for claim in moved {
+        from_branch.ownership.take(&claim);
+        to_branch.ownership.put(claim);
+    }
+    vb_state.set_branch(from_branch)?;
+    vb_state.set_branch(to_branch)?;
+    Ok(())
+}
@@ -186,7 +186,7 @@ pub(crate) fn commit_to_remote_commit(commit: &git2::Commit) -> RemoteCommit {
     let parent_ids = commit.parents().map(|c| c.id()).collect();
     RemoteCommit {
         id: commit.id().to_string(),
-        description: commit.message_bstr().to_owned(),
+        description: commit.message_decoded(),
         created_at: commit.time().seconds().try_into().unwrap(),
         author: commit.author().into(),
         change_id: commit.change_id(),
@@ -0,0 +1,96 @@
+use anyhow::{bail, Result};
+use gitbutler_branch::{BranchId, GITBUTLER_INTEGRATION_REFERENCE};
+use gitbutler_command_context::ProjectRepository;
+use gitbutler_repo::{LogUntil, RepoActionsExt};
+use serde::{Deserialize, Serialize};
+
+use crate::VirtualBranchesExt;
+
+/// An operation on a branch that can drop some of its commits.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "camelCase", tag = "type")]
+pub enum RiskyOperation {
+    /// Resetting the branch to `target`, one of its commits or the default target.
+    Reset {
+        #[serde(with = "gitbutler_serde::serde::oid")]
+        target: git2::Oid,
+    },
+    /// Deleting the branch.
+    Delete,
+}
+
+/// A commit that would not be reachable from any reference anymore after a [`RiskyOperation`].
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct CommitAtRisk {
+    #[serde(with = "gitbutler_serde::serde::oid")]
+    pub id: git2::Oid,
+    /// The first line of the commit message.
+    pub summary: String,
+}
+
+/// Return the commits of the branch with `branch_id` that `operation` would drop and that no other reference
+/// keeps reachable, newest first. These can then only be recovered through the oplog.
+pub(crate) fn commits_at_risk(
+    ctx: &ProjectRepository,
+    branch_id: BranchId,
+    operation: RiskyOperation,
+) -> Result<Vec<CommitAtRisk>> {
+    let vb_state = ctx.project().virtual_branches();
+    let default_target = vb_state.get_default_target()?;
+    let branch = vb_state.get_branch_in_workspace(branch_id)?;
+    let branch_commits = ctx.l(branch.head, LogUntil::Commit(default_target.sha))?;
+
+    let dropped = match operation {
+        RiskyOperation::Reset { target } if target == default_target.sha => branch_commits,
+        RiskyOperation::Reset { target } => {
+            let Some(position) = branch_commits.iter().position(|id| *id == target) else {
+                bail!("commit {target} not in the branch");
+            };
+            branch_commits[..position].to_vec()
+        }
+        RiskyOperation::Delete => branch_commits,
+    };
+
+    // The integration commit and the branch's own reference point to the commits about to be dropped,
+    // but are updated by the operation, so only the other references can keep them reachable.
+    let repo = ctx.repo();
+    let ignored_refnames = [
+        GITBUTLER_INTEGRATION_REFERENCE.to_string(),
+        branch.refname().to_string(),
+    ];
+    let mut ref_targets = Vec::new();
+    for reference in repo.references()? {
+        let reference = reference?;
+        if reference.name().map_or(true, |name| {
+            ignored_refnames.iter().any(|ignored| ignored == name)
+        }) {
+            continue;
+        }
+        if let Ok(commit) = reference.peel_to_commit() {
+            ref_targets.push(commit.id());
+        }
+    }
+
+    let mut at_risk = Vec::new();
+    for id in dropped {
+        let mut reachable = false;
+        for ref_target in &ref_targets {
+            if *ref_target == id || repo.graph_descendant_of(*ref_target, id)? {
+                reachable = true;
+                break;
+            }
+        }
+        if !reachable {
+            at_risk.push(CommitAtRisk {
+                id,
+                summary: repo
+                    .find_commit(id)?
+                    .summary()
+                    .unwrap_or_default()
+                    .to_owned(),
+            });
+        }
+    }
+    Ok(at_risk)
+}
@@ -0,0 +1,167 @@
+use std::collections::{HashMap, HashSet};
+
+use anyhow::{anyhow, Context, Result};
+use gitbutler_branch::{Branch, BranchId, VirtualBranchesHandle};
+use gitbutler_command_context::ProjectRepository;
+use gitbutler_error::error::Code;
+use gitbutler_repo::rebase::cherry_rebase;
+
+use crate::VirtualBranchesExt;
+
+/// Stack the branch with `branch_id` on the branch with `parent_id`, or unstack it if `parent_id` is `None`.
+///
+/// The commits of the branch, without those of its current parent, are rebased onto the head of the new
+/// parent, or onto the base branch when unstacking. Both branches have to be in the workspace, and a branch
+/// can't be stacked on itself or on one of the branches stacked on it.
+pub(crate) fn set_branch_parent(
+    ctx: &ProjectRepository,
+    branch_id: BranchId,
+    parent_id: Option<BranchId>,
+) -> Result<()> {
+    let vb_state = ctx.project().virtual_branches();
+    let target = vb_state.get_default_target()?;
+    let mut branch = vb_state.get_branch_in_workspace(branch_id)?;
+    if branch.parent_id == parent_id {
+        return Ok(());
+    }
+
+    let onto = match parent_id {
+        Some(parent_id) => {
+            let parent = vb_state
+                .try_branch_in_workspace(parent_id)?
+                .ok_or_else(|| {
+                    anyhow!("A branch can only be stacked on a branch in the workspace")
+                        .context(Code::Validation)
+                })?;
+            if creates_cycle(&vb_state, branch_id, &parent)? {
+                return Err(anyhow!(
+                    "Can't stack '{}' on '{}' as it would depend on itself",
+                    branch.name,
+                    parent.name
+                )
+                .context(Code::Validation));
+            }
+            parent.head
+        }
+        None => target.sha,
+    };
+    let base = match branch
+        .parent_id
+        .map(|parent_id| vb_state.try_branch_in_workspace(parent_id))
+        .transpose()?
+        .flatten()
+    {
+        Some(old_parent) => old_parent.head,
+        None => target.sha,
+    };
+
+    branch.head = cherry_rebase(ctx, onto, base, branch.head)
+        .context("failed to rebase the branch onto its new parent")?
+        .unwrap_or(onto);
+    branch.parent_id = parent_id;
+    branch.updated_timestamp_ms = gitbutler_time::time::now_ms();
+    vb_state.set_branch(branch)?;
+
+    crate::integration::update_gitbutler_integration(&vb_state, ctx)
+        .context("failed to update gitbutler integration")?;
+    Ok(())
+}
+
+/// Run `op`, and afterwards rebase the branches stacked on branches whose head it changed onto their new
+/// heads, so that rewriting the commits of a branch carries over to the branches that depend on it.
+pub(crate) fn restacking<T>(ctx: &ProjectRepository, op: impl FnOnce() -> Result<T>) -> Result<T> {
+    let vb_state = ctx.project().virtual_branches();
+    let heads_before: HashMap<BranchId, git2::Oid> = vb_state
+        .list_branches_in_workspace()?
+        .into_iter()
+        .map(|branch| (branch.id, branch.head))
+        .collect();
+    let result = op()?;
+    restack_children(ctx, &vb_state, &heads_before)?;
+    Ok(result)
+}
+
+fn restack_children(
+    ctx: &ProjectRepository,
+    vb_state: &VirtualBranchesHandle,
+    heads_before: &HashMap<BranchId, git2::Oid>,
+) -> Result<()> {
+    let mut branches = vb_state.list_branches_in_workspace()?;
+    if branches.iter().all(|branch| branch.parent_id.is_none()) {
+        return Ok(());
+    }
+    // Parents are restacked before their children, so changes cascade down the whole stack.
+    sort_parents_first(&mut branches, |branch| branch);
+
+    let mut heads: HashMap<BranchId, git2::Oid> = branches
+        .iter()
+        .map(|branch| (branch.id, branch.head))
+        .collect();
+    let mut restacked = false;
+    for mut branch in branches {
+        let Some(parent_id) = branch.parent_id else {
+            continue;
+        };
+        let (Some(parent_before), Some(parent_head)) =
+            (heads_before.get(&parent_id), heads.get(&parent_id))
+        else {
+            continue;
+        };
+        if parent_before == parent_head || heads_before.get(&branch.id) != Some(&branch.head) {
+            continue;
+        }
+        branch.head = cherry_rebase(ctx, *parent_head, *parent_before, branch.head)
+            .with_context(|| format!("failed to restack '{}' onto its parent", branch.name))?
+            .unwrap_or(*parent_head);
+        branch.updated_timestamp_ms = gitbutler_time::time::now_ms();
+        heads.insert(branch.id, branch.head);
+        vb_state.set_branch(branch)?;
+        restacked = true;
+    }
+
+    if restacked {
+        crate::integration::update_gitbutler_integration(vb_state, ctx)
+            .context("failed to update gitbutler integration")?;
+    }
+    Ok(())
+}
+
+/// Return `true` if `branch_id` is `parent` or one of its ancestors in the stack.
+fn creates_cycle(
+    vb_state: &VirtualBranchesHandle,
+    branch_id: BranchId,
+    parent: &Branch,
+) -> Result<bool> {
+    let mut visited = HashSet::new();
+    let mut next = Some(parent.id);
+    while let Some(id) = next {
+        if id == branch_id || !visited.insert(id) {
+            return Ok(true);
+        }
+        next = vb_state.try_branch(id)?.and_then(|branch| branch.parent_id);
+    }
+    Ok(false)
+}
+
+/// Sort `items` by the depth of the branch of each in its stack, so parents come before the branches
+/// stacked on them.
+pub(crate) fn sort_parents_first<T>(items: &mut [T], branch: impl Fn(&T) -> &Branch) {
+    let parents: HashMap<BranchId, Option<BranchId>> = items
+        .iter()
+        .map(|item| (branch(item).id, branch(item).parent_id))
+        .collect();
+    items.sort_by_key(|item| depth(&parents, branch(item).id));
+}
+
+fn depth(parents: &HashMap<BranchId, Option<BranchId>>, branch_id: BranchId) -> usize {
+    let mut depth = 0;
+    let mut next = parents.get(&branch_id).copied().flatten();
+    while let Some(id) = next {
+        depth += 1;
+        if depth > parents.len() {
+            break;
+        }
+        next = parents.get(&id).copied().flatten();
+    }
+    depth
+}
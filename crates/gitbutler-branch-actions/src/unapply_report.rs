@@ -0,0 +1,86 @@
+use std::path::PathBuf;
+
+use anyhow::{Context, Result};
+use bstr::ByteSlice;
+use gitbutler_branch::BranchId;
+use gitbutler_command_context::ProjectRepository;
+use gitbutler_repo::RepositoryExt;
+use serde::Serialize;
+
+use crate::{BranchManagerExt, VirtualBranchesExt};
+
+/// How a file conflicts when a branch is applied again after unapplying it.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub enum UnapplyConflictKind {
+    /// Both the branch and the rest of the workspace changed the content of the file.
+    Content,
+    /// One side deleted the file while the other one modified it.
+    DeleteModify,
+    /// Both the branch and the rest of the workspace added the file with different content.
+    BothAdded,
+}
+
+/// A file that would conflict when applying a branch again after unapplying it.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct UnapplyConflict {
+    pub path: PathBuf,
+    pub kind: UnapplyConflictKind,
+}
+
+/// What would happen when unapplying a branch and applying it again.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct UnapplyReport {
+    /// The files that would conflict on reapply, sorted by path.
+    pub conflicts: Vec<UnapplyConflict>,
+}
+
+/// Simulate unapplying the branch with `branch_id` and applying it again, in memory only, and report the
+/// files that would conflict on reapply. Neither the working directory nor the branch state is changed.
+pub(crate) fn can_unapply(ctx: &ProjectRepository, branch_id: BranchId) -> Result<UnapplyReport> {
+    let vb_state = ctx.project().virtual_branches();
+    let branch = vb_state.get_branch_in_workspace(branch_id)?;
+    let repo = ctx.repo();
+
+    let (wd_tree, branch_tree) = ctx.branch_manager().trees_without_branch(branch.id)?;
+    let Some(branch_tree) = branch_tree else {
+        return Ok(UnapplyReport::default());
+    };
+    let target_tree = repo.target_commit()?.tree()?;
+    let merge_index = repo
+        .merge_trees(
+            &target_tree,
+            &repo.find_tree(wd_tree)?,
+            &repo.find_tree(branch_tree)?,
+            None,
+        )
+        .context("failed to merge trees")?;
+
+    let mut conflicts = Vec::new();
+    for conflict in merge_index
+        .conflicts()
+        .context("failed to get merge index conflicts")?
+    {
+        let conflict = conflict?;
+        let (kind, entry) = match (conflict.ancestor, conflict.our, conflict.their) {
+            (None, Some(ours), Some(_)) => (UnapplyConflictKind::BothAdded, ours),
+            (Some(_), Some(ours), Some(_)) => (UnapplyConflictKind::Content, ours),
+            (_, Some(entry), None) | (_, None, Some(entry)) => {
+                (UnapplyConflictKind::DeleteModify, entry)
+            }
+            (_, None, None) => continue,
+        };
+        conflicts.push(UnapplyConflict {
+            path: entry
+                .path
+                .to_path()
+                .context("failed to convert path")?
+                .to_owned(),
+            kind,
+        });
+    }
+    conflicts.sort_by(|a, b| a.path.cmp(&b.path));
+    Ok(UnapplyReport { conflicts })
+}
@@ -1,7 +1,7 @@
 use gitbutler_branch::{dedup, BranchUpdateRequest, VirtualBranchesHandle};
 use gitbutler_branch::{dedup_fmt, Branch, BranchCreateRequest, BranchId};
 use gitbutler_branch::{reconcile_claims, BranchOwnershipClaims};
-use gitbutler_branch::{OwnershipClaim, Target};
+use gitbutler_branch::{AssignmentMode, OwnershipClaim, Target};
 use gitbutler_command_context::ProjectRepository;
 use gitbutler_commit::commit_ext::CommitExt;
 use gitbutler_commit::commit_headers::HasCommitHeaders;
@@ -9,7 +9,7 @@ use gitbutler_diff::{diff_files_into_hunks, trees, FileDiff, GitHunk};
 use gitbutler_diff::{Hunk, HunkHash};
 use gitbutler_reference::{normalize_branch_name, Refname, RemoteRefname};
 use gitbutler_repo::credentials::Helper;
-use gitbutler_repo::{LogUntil, RepoActionsExt, RepositoryExt};
+use gitbutler_repo::{GitButlerIgnore, LogUntil, RepoActionsExt, RepositoryExt};
 use itertools::Itertools;
 use md5::Digest;
 use std::borrow::Borrow;
@@ -17,7 +17,7 @@ use std::borrow::Borrow;
 use std::os::unix::prelude::PermissionsExt;
 use std::time::SystemTime;
 use std::{
-    collections::HashMap,
+    collections::{BTreeMap, BTreeSet, HashMap, HashSet},
     path::{Path, PathBuf},
     time, vec,
 };
@@ -31,6 +31,7 @@ use serde::{Deserialize, Serialize};
 
 use crate::author::Author;
 use crate::branch_manager::BranchManagerExt;
+use crate::commit_lint::lint_commit_message;
 use crate::conflicts::{self, RepoConflictsExt};
 use crate::integration::get_workspace_head;
 use crate::remote::{branch_to_remote_branch, RemoteBranch};
@@ -78,6 +79,8 @@ pub struct VirtualBranch {
     /// The fork point between the target branch and the virtual branch
     #[serde(with = "gitbutler_serde::serde::oid_opt", default)]
     pub fork_point: Option<git2::Oid>,
+    /// The branch this branch is stacked on, if any.
+    pub parent_id: Option<BranchId>,
 }
 
 #[derive(Debug, PartialEq, Clone, Serialize)]
@@ -237,7 +240,7 @@ pub fn unapply_ownership(
 
     let integration_commit_id = get_workspace_head(&vb_state, project_repository)?;
 
-    let (applied_statuses, _, _) = get_applied_status(
+    let (applied_statuses, _, _, unassigned) = get_applied_status(
         project_repository,
         &integration_commit_id,
         virtual_branches,
@@ -290,18 +293,23 @@ pub fn unapply_ownership(
         .context("failed to find target commit")?;
 
     let base_tree = target_commit.tree().context("failed to get target tree")?;
-    let final_tree = applied_statuses.into_iter().fold(
-        target_commit.tree().context("failed to get target tree"),
-        |final_tree, status| {
-            let final_tree = final_tree?;
-            let tree_oid = write_tree(project_repository, &integration_commit_id, status.1)?;
-            let branch_tree = repo.find_tree(tree_oid)?;
-            let mut result = repo.merge_trees(&base_tree, &final_tree, &branch_tree, None)?;
-            let final_tree_oid = result.write_tree_to(project_repository.repo())?;
-            repo.find_tree(final_tree_oid)
-                .context("failed to find tree")
-        },
-    )?;
+    // unassigned changes are kept just like those of the branches
+    let final_tree = applied_statuses
+        .into_iter()
+        .map(|(_branch, files)| files)
+        .chain(Some(unassigned))
+        .fold(
+            target_commit.tree().context("failed to get target tree"),
+            |final_tree, files| {
+                let final_tree = final_tree?;
+                let tree_oid = write_tree(project_repository, &integration_commit_id, files)?;
+                let branch_tree = repo.find_tree(tree_oid)?;
+                let mut result = repo.merge_trees(&base_tree, &final_tree, &branch_tree, None)?;
+                let final_tree_oid = result.write_tree_to(project_repository.repo())?;
+                repo.find_tree(final_tree_oid)
+                    .context("failed to find tree")
+            },
+        )?;
 
     let final_tree_oid = write_tree_onto_tree(project_repository, &final_tree, diff)?;
     let final_tree = repo
@@ -347,7 +355,7 @@ pub(crate) fn reset_files(
 
     Ok(())
 }
-fn find_base_tree<'a>(
+pub(crate) fn find_base_tree<'a>(
     repo: &'a git2::Repository,
     branch_commit: &'a git2::Commit<'a>,
     target_commit: &'a git2::Commit<'a>,
@@ -403,8 +411,12 @@ pub fn list_virtual_branches(
     let mut branches: Vec<VirtualBranch> = Vec::new();
 
     let vb_state = ctx.project().virtual_branches();
+    // Projects that are only observed are listed as they are, without migrating or updating any state.
+    let read_only = ctx.project().is_read_only();
 
-    resolve_old_applied_state(ctx, &vb_state, perm)?;
+    if !read_only {
+        resolve_old_applied_state(ctx, &vb_state, perm)?;
+    }
 
     let default_target = vb_state
         .get_default_target()
@@ -420,10 +432,16 @@ pub fn list_virtual_branches(
         .filter_map(|(branch, _)| branch.selected_for_changes)
         .max()
         .unwrap_or(-1);
+    let heads: HashMap<BranchId, git2::Oid> = statuses
+        .iter()
+        .map(|(branch, _)| (branch.id, branch.head))
+        .collect();
 
     for (branch, files) in statuses {
         let repo = ctx.repo();
-        update_conflict_markers(ctx, &files)?;
+        if !read_only {
+            update_conflict_markers(ctx, &files)?;
+        }
 
         let upstream_branch = match branch.clone().upstream {
             Some(upstream) => repo.find_branch_by_refname(&Refname::from(upstream))?,
@@ -458,7 +476,15 @@ pub fn list_virtual_branches(
         let mut is_remote = false;
 
         // find all commits on head that are not on target.sha
-        let commits = ctx.log(branch.head, LogUntil::Commit(default_target.sha))?;
+        let mut commits = ctx.log(branch.head, LogUntil::Commit(default_target.sha))?;
+        // the commits of the parent of a stacked branch are listed with the parent only
+        if let Some(parent_head) = branch.parent_id.and_then(|parent_id| heads.get(&parent_id)) {
+            let parent_commits: HashSet<git2::Oid> = ctx
+                .l(*parent_head, LogUntil::Commit(default_target.sha))?
+                .into_iter()
+                .collect();
+            commits.retain(|commit| !parent_commits.contains(&commit.id()));
+        }
         let vbranch_commits = commits
             .iter()
             .map(|commit| {
@@ -533,6 +559,7 @@ pub fn list_virtual_branches(
             head: branch.head,
             merge_base,
             fork_point,
+            parent_id: branch.parent_id,
         };
         branches.push(branch);
     }
@@ -617,7 +644,7 @@ fn commit_to_vbranch_commit(
     is_remote: bool,
 ) -> Result<VirtualBranchCommit> {
     let timestamp = u128::try_from(commit.time().seconds())?;
-    let message = commit.message_bstr().to_owned();
+    let message = commit.message_decoded();
 
     let files =
         list_virtual_commit_files(repository, commit).context("failed to list commit files")?;
@@ -1090,7 +1117,7 @@ pub fn get_status_by_branch(
         .list_branches_in_workspace()
         .context("failed to read virtual branches")?;
 
-    let (applied_status, skipped_files, locks) = get_applied_status(
+    let (applied_status, skipped_files, locks, _) = get_applied_status(
         project_repository,
         // TODO: Keep this optional or update lots of tests?
         integration_commit.unwrap_or(&default_target.sha),
@@ -1101,6 +1128,100 @@ pub fn get_status_by_branch(
     Ok((applied_status, skipped_files, locks))
 }
 
+/// Assign changes that no branch claims yet according to `mode` from now on.
+/// Changes that are already owned by a branch stay where they are.
+pub(crate) fn set_assignment_mode(
+    ctx: &ProjectRepository,
+    mode: AssignmentMode,
+    _perm: &mut WorktreeWritePermission,
+) -> Result<()> {
+    let vb_state = ctx.project().virtual_branches();
+    if let AssignmentMode::Auto(branch_id) = mode {
+        // make sure changes can go to the branch
+        vb_state.get_branch_in_workspace(branch_id)?;
+    }
+    vb_state.set_assignment_mode(mode)
+}
+
+/// Return the changes that no branch in the workspace claims, sorted by path.
+///
+/// There are only such changes if they are [assigned manually](AssignmentMode::Manual).
+pub(crate) fn list_unassigned_files(
+    ctx: &ProjectRepository,
+    perm: &mut WorktreeWritePermission,
+) -> Result<Vec<VirtualBranchFile>> {
+    let vb_state = ctx.project().virtual_branches();
+    let integration_commit_id = get_workspace_head(&vb_state, ctx)?;
+    let (_, _, locks, unassigned) = get_applied_status(
+        ctx,
+        &integration_commit_id,
+        vb_state.list_branches_in_workspace()?,
+        Some(perm),
+    )?;
+    let hunks_by_filepath =
+        virtual_hunks_by_git_hunks(&ctx.project().path, unassigned, Some(&locks));
+    let mut files = virtual_hunks_into_virtual_files(ctx, hunks_by_filepath);
+    files.sort_by(|a, b| a.path.cmp(&b.path));
+    Ok(files)
+}
+
+/// The files whose ownership by a branch changed when reconciling it with the worktree.
+#[derive(Debug, PartialEq, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct OwnershipDrift {
+    pub branch_id: BranchId,
+    /// The files whose hunks were claimed, released or moved since ownership was last computed,
+    /// typically because they were edited outside of GitButler.
+    pub files: Vec<PathBuf>,
+}
+
+/// Re-derive the hunks owned by each branch in the workspace from the current worktree, persist them,
+/// and return the branches whose ownership drifted from what was recorded.
+pub(crate) fn reconcile_ownership(
+    ctx: &ProjectRepository,
+    perm: &mut WorktreeWritePermission,
+) -> Result<Vec<OwnershipDrift>> {
+    fn hunks_by_path(ownership: &BranchOwnershipClaims) -> BTreeMap<&Path, &[Hunk]> {
+        ownership
+            .claims
+            .iter()
+            .map(|claim| (claim.file_path.as_path(), claim.hunks.as_slice()))
+            .collect()
+    }
+
+    let vb_state = ctx.project().virtual_branches();
+    let recorded = vb_state
+        .list_branches_in_workspace()?
+        .into_iter()
+        .map(|branch| (branch.id, branch.ownership))
+        .collect::<HashMap<_, _>>();
+
+    let integration_commit_id = get_workspace_head(&vb_state, ctx)?;
+    let (statuses, _, _) = get_status_by_branch(ctx, Some(&integration_commit_id), Some(perm))?;
+
+    let mut drifts = Vec::new();
+    for (branch, _) in statuses {
+        let before = recorded
+            .get(&branch.id)
+            .map(hunks_by_path)
+            .unwrap_or_default();
+        let after = hunks_by_path(&branch.ownership);
+        let files = before
+            .keys()
+            .chain(after.keys())
+            .filter(|path| before.get(*path) != after.get(*path))
+            .map(|path| path.to_path_buf())
+            .collect::<BTreeSet<_>>();
+        if !files.is_empty() {
+            drifts.push(OwnershipDrift {
+                branch_id: branch.id,
+                files: files.into_iter().collect(),
+            });
+        }
+    }
+    Ok(drifts)
+}
+
 fn new_compute_locks(
     repository: &git2::Repository,
     unstaged_hunks_by_path: &HashMap<PathBuf, Vec<gitbutler_diff::GitHunk>>,
@@ -1202,6 +1323,7 @@ pub(crate) fn get_applied_status(
     AppliedStatuses,
     Vec<gitbutler_diff::FileDiff>,
     HashMap<Digest, Vec<HunkLock>>,
+    BranchStatus,
 )> {
     let base_file_diffs =
         gitbutler_diff::workdir(project_repository.repo(), &integration_commit.to_owned())
@@ -1219,8 +1341,20 @@ pub(crate) fn get_applied_status(
     virtual_branches.sort_by(|a, b| a.order.cmp(&b.order));
 
     let branch_manager = project_repository.branch_manager();
-
-    if virtual_branches.is_empty() && !base_diffs.is_empty() {
+    let assignment_mode = project_repository
+        .project()
+        .virtual_branches()
+        .get_assignment_mode()?;
+    let gitbutler_ignore = GitButlerIgnore::load(&project_repository.project().path)?;
+    let read_only = project_repository.project().is_read_only();
+
+    if virtual_branches.is_empty()
+        && base_diffs
+            .keys()
+            .any(|path| !gitbutler_ignore.is_ignored(path))
+        && assignment_mode != AssignmentMode::Manual
+        && !read_only
+    {
         if let Some(perm) = perm {
             virtual_branches = vec![branch_manager
                 .create_virtual_branch(&BranchCreateRequest::default(), perm)
@@ -1299,28 +1433,47 @@ pub(crate) fn get_applied_status(
         .filter_map(|b| b.selected_for_changes)
         .max()
         .unwrap_or(-1);
-    let default_vbranch_pos = virtual_branches
+    let selected_vbranch_pos = virtual_branches
         .iter()
         .position(|b| b.selected_for_changes == Some(max_selected_for_changes))
         .unwrap_or(0);
+    let default_vbranch_pos = match assignment_mode {
+        AssignmentMode::Selected => Some(selected_vbranch_pos),
+        AssignmentMode::Auto(branch_id) => Some(
+            virtual_branches
+                .iter()
+                .position(|b| b.id == branch_id)
+                .unwrap_or(selected_vbranch_pos),
+        ),
+        AssignmentMode::Manual => None,
+    }
+    // Without any branch, which read-only projects don't get by default, all changes are unassigned.
+    .filter(|_| !virtual_branches.is_empty());
+    let mut unassigned = BranchStatus::new();
 
     // Everything claimed has been removed from `base_diffs`, here we just
     // process the remaining ones.
     for (filepath, hunks) in base_diffs {
+        // Changes to `.gitbutlerignore`d files only go to a branch if they depend on its commits.
+        let default_vbranch_pos = if gitbutler_ignore.is_ignored(&filepath) {
+            None
+        } else {
+            default_vbranch_pos
+        };
         for hunk in hunks {
             let hash = Hunk::hash_diff(&hunk.diff_lines);
             let locked_to = locks.get(&hash);
 
-            let vbranch_pos = if let Some(locks) = locked_to {
-                let p = virtual_branches
-                    .iter()
-                    .position(|vb| vb.id == locks[0].branch_id);
-                match p {
-                    Some(p) => p,
-                    _ => default_vbranch_pos,
-                }
-            } else {
-                default_vbranch_pos
+            let vbranch_pos = locked_to
+                .and_then(|locks| {
+                    virtual_branches
+                        .iter()
+                        .position(|vb| vb.id == locks[0].branch_id)
+                })
+                .or(default_vbranch_pos);
+            let Some(vbranch_pos) = vbranch_pos else {
+                unassigned.entry(filepath.clone()).or_default().push(hunk);
+                continue;
             };
 
             // let hash = Hunk::hash_diff(&hunk.diff_lines);
@@ -1362,8 +1515,8 @@ pub(crate) fn get_applied_status(
         })
         .collect::<Vec<_>>();
 
-    // write updated state if not resolving
-    if !project_repository.is_resolving() {
+    // write updated state if not resolving, and if the project may be changed at all
+    if !project_repository.is_resolving() && !read_only {
         let vb_state = project_repository.project().virtual_branches();
         for (vbranch, files) in &mut hunks_by_branch {
             vbranch.tree = write_tree(project_repository, &vbranch.head, files)?;
@@ -1373,7 +1526,7 @@ pub(crate) fn get_applied_status(
         }
     }
 
-    Ok((hunks_by_branch, skipped_files, locks))
+    Ok((hunks_by_branch, skipped_files, locks, unassigned))
 }
 
 /// NOTE: There is no use returning an iterator here as this acts like the final product.
@@ -1712,7 +1865,18 @@ pub fn commit(
         }
     }
 
-    let message = &message_buffer;
+    let report = lint_commit_message(
+        &project_repository.project().commit_lint_rules,
+        &message_buffer,
+    );
+    if !report.is_ok() {
+        return Err(anyhow!(
+            "commit message doesn't follow the project's rules: {}",
+            report.violations.iter().join("; ")
+        )
+        .context(Code::Validation));
+    }
+    let message = &report.message;
 
     let integration_commit_id = get_workspace_head(&vb_state, project_repository)?;
     // get the files to commit
@@ -1865,6 +2029,7 @@ pub(crate) fn push(
         remote_branch.remote(),
         credentials,
         askpass.map(|_| "modal".to_string()),
+        None,
     )?;
 
     Ok(())
@@ -2106,7 +2271,7 @@ pub(crate) fn move_commit_file(
                 None,
                 &from_commit.author(),
                 &from_commit.committer(),
-                &from_commit.message_bstr().to_str_lossy(),
+                &from_commit.message_decoded().to_str_lossy(),
                 new_from_tree,
                 &[&from_parent],
                 from_commit.gitbutler_headers(),
@@ -2174,7 +2339,7 @@ pub(crate) fn move_commit_file(
             None,
             &amend_commit.author(),
             &amend_commit.committer(),
-            &amend_commit.message_bstr().to_str_lossy(),
+            &amend_commit.message_decoded().to_str_lossy(),
             &new_tree,
             &parents.iter().collect::<Vec<_>>(),
             amend_commit.gitbutler_headers(),
@@ -2235,7 +2400,7 @@ pub(crate) fn amend(
 
     let integration_commit_id = get_workspace_head(&vb_state, project_repository)?;
 
-    let (mut applied_statuses, _, _) = get_applied_status(
+    let (mut applied_statuses, _, _, _) = get_applied_status(
         project_repository,
         &integration_commit_id,
         virtual_branches,
@@ -2310,7 +2475,7 @@ pub(crate) fn amend(
             None,
             &amend_commit.author(),
             &amend_commit.committer(),
-            &amend_commit.message_bstr().to_str_lossy(),
+            &amend_commit.message_decoded().to_str_lossy(),
             &new_tree,
             &parents.iter().collect::<Vec<_>>(),
             amend_commit.gitbutler_headers(),
@@ -2433,6 +2598,71 @@ pub(crate) fn reorder_commit(
     Ok(())
 }
 
+// rebase a commit and its descendants onto `new_parent`, which is either the base of the branch
+// or a commit of the branch below the commit's current parent.
+// The commits that were between `new_parent` and the commit are rebased on top of the moved commits,
+// so no commit is lost. Nothing is changed if the rebase conflicts.
+pub(crate) fn reparent_commit(
+    project_repository: &ProjectRepository,
+    branch_id: BranchId,
+    commit_oid: git2::Oid,
+    new_parent: git2::Oid,
+) -> Result<()> {
+    let vb_state = project_repository.project().virtual_branches();
+    let default_target = vb_state.get_default_target()?;
+    let mut branch = vb_state.get_branch_in_workspace(branch_id)?;
+
+    // the commits of the branch, newest first
+    let branch_commits = project_repository.l(branch.head, LogUntil::Commit(default_target.sha))?;
+    let commit_index = branch_commits
+        .iter()
+        .position(|id| *id == commit_oid)
+        .with_context(|| format!("commit {commit_oid} not in the branch"))?;
+    let new_parent_index = if new_parent == default_target.sha {
+        branch_commits.len()
+    } else {
+        branch_commits
+            .iter()
+            .position(|id| *id == new_parent)
+            .filter(|index| *index > commit_index)
+            .ok_or_else(|| {
+                anyhow!("commit {new_parent} is neither the base nor below commit {commit_oid}")
+                    .context(Code::Validation)
+            })?
+    };
+
+    let mut ids_to_rebase = branch_commits[commit_index + 1..new_parent_index].to_vec();
+    if ids_to_rebase.is_empty() {
+        // the commit already sits on top of the new parent
+        return Ok(());
+    }
+    ids_to_rebase.extend_from_slice(&branch_commits[..=commit_index]);
+
+    let pushed_commit_oids = branch.upstream_head.map_or_else(
+        || Ok(vec![]),
+        |upstream_head| project_repository.l(upstream_head, LogUntil::Commit(default_target.sha)),
+    )?;
+    if !branch.allow_rebasing
+        && ids_to_rebase
+            .iter()
+            .any(|id| pushed_commit_oids.contains(id))
+    {
+        // rewriting pushed commits will cause a force push that is not allowed
+        return Err(anyhow!("force push not allowed").context(Code::Validation));
+    }
+
+    let new_head = cherry_rebase_group(project_repository, new_parent, &mut ids_to_rebase)
+        .context("rebase failed")?;
+    branch.head = new_head;
+    branch.updated_timestamp_ms = gitbutler_time::time::now_ms();
+    vb_state.set_branch(branch.clone())?;
+
+    crate::integration::update_gitbutler_integration(&vb_state, project_repository)
+        .context("failed to update gitbutler integration")?;
+
+    Ok(())
+}
+
 // create and insert a blank commit (no tree change) either above or below a commit
 // if offset is positive, insert below, if negative, insert above
 // return the oid of the new head commit of the branch with the inserted blank commit
@@ -2595,8 +2825,8 @@ pub(crate) fn squash(
             &commit_to_squash.committer(),
             &format!(
                 "{}\n{}",
-                parent_commit.message_bstr(),
-                commit_to_squash.message_bstr(),
+                parent_commit.message_decoded(),
+                commit_to_squash.message_decoded(),
             ),
             &commit_to_squash.tree().context("failed to find tree")?,
             &parents.iter().collect::<Vec<_>>(),
@@ -2629,6 +2859,119 @@ pub(crate) fn squash(
     }
 }
 
+/// Squash the contiguous range of `commit_ids` of a virtual branch into a single commit and rebase the
+/// commits above it onto the result, returning the id of the squashed commit.
+///
+/// The squashed commit uses `message` if given, or the messages of the squashed commits from the oldest
+/// to the newest otherwise.
+pub(crate) fn squash_commits(
+    project_repository: &ProjectRepository,
+    branch_id: BranchId,
+    commit_ids: &[git2::Oid],
+    message: Option<&str>,
+) -> Result<git2::Oid> {
+    project_repository.assure_resolved()?;
+    if commit_ids.len() < 2 {
+        return Err(anyhow!("At least two commits are needed to squash").context(Code::Validation));
+    }
+    if message.is_some_and(|message| message.trim().is_empty()) {
+        return Err(anyhow!("commit message can not be empty").context(Code::Validation));
+    }
+
+    let vb_state = project_repository.project().virtual_branches();
+    let mut branch = vb_state.get_branch_in_workspace(branch_id)?;
+    let default_target = vb_state.get_default_target()?;
+    let branch_commit_oids =
+        project_repository.l(branch.head, LogUntil::Commit(default_target.sha))?;
+
+    let mut positions = commit_ids
+        .iter()
+        .map(|commit_id| {
+            branch_commit_oids
+                .iter()
+                .position(|oid| oid == commit_id)
+                .ok_or_else(|| {
+                    anyhow!("commit {commit_id} not in the branch").context(Code::Validation)
+                })
+        })
+        .collect::<Result<Vec<_>>>()?;
+    positions.sort_unstable();
+    positions.dedup();
+    if positions.len() != commit_ids.len() {
+        return Err(anyhow!("A commit can only be squashed once").context(Code::Validation));
+    }
+    let (newest, oldest) = (positions[0], positions[positions.len() - 1]);
+    if oldest - newest + 1 != positions.len() {
+        return Err(
+            anyhow!("Only a contiguous range of commits can be squashed").context(Code::Validation),
+        );
+    }
+    // newest first, like the branch commits
+    let commits_to_squash = branch_commit_oids[newest..=oldest]
+        .iter()
+        .map(|oid| project_repository.repo().find_commit(*oid))
+        .collect::<Result<Vec<_>, _>>()
+        .context("failed to find commits to squash")?;
+
+    let pushed_commit_oids = branch.upstream_head.map_or_else(
+        || Ok(vec![]),
+        |upstream_head| project_repository.l(upstream_head, LogUntil::Commit(default_target.sha)),
+    )?;
+    if !branch.allow_rebasing
+        && commits_to_squash
+            .iter()
+            .any(|commit| pushed_commit_oids.contains(&commit.id()))
+    {
+        // squashing pushed commits will cause a force push that is not allowed
+        bail!("force push not allowed");
+    }
+
+    let newest_commit = &commits_to_squash[0];
+    let oldest_commit = &commits_to_squash[commits_to_squash.len() - 1];
+    let message = match message {
+        Some(message) => message.to_owned(),
+        None => commits_to_squash
+            .iter()
+            .rev()
+            .map(|commit| commit.message_decoded().to_string())
+            .collect::<Vec<_>>()
+            .join("\n"),
+    };
+
+    // create a commit that:
+    //  * has the tree of the newest commit
+    //  * has the combined or given message
+    //  * has the parents of the oldest commit.
+    let parents: Vec<_> = oldest_commit.parents().collect();
+    let new_commit_oid = project_repository
+        .repo()
+        .commit_with_signature(
+            None,
+            &newest_commit.author(),
+            &newest_commit.committer(),
+            &message,
+            &newest_commit.tree().context("failed to find tree")?,
+            &parents.iter().collect::<Vec<_>>(),
+            // use the newest commit's headers
+            newest_commit.gitbutler_headers(),
+        )
+        .context("failed to commit")?;
+
+    let mut ids_to_rebase = branch_commit_oids[..newest].to_vec();
+    match cherry_rebase_group(project_repository, new_commit_oid, &mut ids_to_rebase) {
+        Ok(new_head_id) => {
+            branch.head = new_head_id;
+            branch.updated_timestamp_ms = gitbutler_time::time::now_ms();
+            vb_state.set_branch(branch.clone())?;
+
+            crate::integration::update_gitbutler_integration(&vb_state, project_repository)
+                .context("failed to update gitbutler integration")?;
+            Ok(new_commit_oid)
+        }
+        Err(err) => Err(err.context("rebase error").context(Code::Unknown)),
+    }
+}
+
 // changes a commit message for commit_oid, rebases everything above it, updates branch head if successful
 pub(crate) fn update_commit_message(
     project_repository: &ProjectRepository,
@@ -2722,7 +3065,7 @@ pub(crate) fn move_commit(
 
     let integration_commit_id = get_workspace_head(&vb_state, project_repository)?;
 
-    let (mut applied_statuses, _, _) = get_applied_status(
+    let (mut applied_statuses, _, _, _) = get_applied_status(
         project_repository,
         &integration_commit_id,
         applied_branches,
@@ -2819,7 +3162,7 @@ pub(crate) fn move_commit(
 
         let new_destination_head_oid = project_repository
             .commit(
-                &source_branch_head.message_bstr().to_str_lossy(),
+                &source_branch_head.message_decoded().to_str_lossy(),
                 &new_destination_tree,
                 &[&project_repository
                     .repo()
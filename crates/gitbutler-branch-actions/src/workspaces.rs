@@ -0,0 +1,223 @@
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+use anyhow::{anyhow, Context, Result};
+use gitbutler_branch::{BranchId, VirtualBranchesState};
+use gitbutler_command_context::ProjectRepository;
+use gitbutler_error::error::Code;
+use gitbutler_oplog::{
+    entry::{OperationKind, SnapshotDetails, Trailer},
+    OplogExt,
+};
+use gitbutler_project::access::WorktreeWritePermission;
+use serde::Serialize;
+
+use crate::VirtualBranchesExt;
+
+/// The prefix of the references that keep the snapshot of each workspace.
+const WORKSPACE_REFS_PREFIX: &str = "refs/gitbutler/workspaces/";
+
+/// A named set of virtual branches, saved as a snapshot, that can be switched to.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Workspace {
+    pub name: String,
+    /// The snapshot holding the virtual branches and the working directory of the workspace.
+    #[serde(with = "gitbutler_serde::serde::oid")]
+    pub snapshot_id: git2::Oid,
+    /// The names of the branches applied in the workspace.
+    pub branches: Vec<String>,
+    pub saved_at: SystemTime,
+}
+
+/// How the state of a branch is affected by switching workspaces.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub enum WorkspaceConflictKind {
+    /// The branch has different commits in the workspace, which replace the current ones.
+    Diverged,
+    /// The branch isn't part of the workspace, and is removed.
+    Missing,
+}
+
+/// A branch whose current state is replaced when switching workspaces.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct WorkspaceConflict {
+    pub branch_id: BranchId,
+    pub branch_name: String,
+    pub kind: WorkspaceConflictKind,
+}
+
+/// The outcome of switching to a workspace.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct WorkspaceSwitch {
+    pub workspace: Workspace,
+    /// The snapshot of the state right before the switch, which can be restored to undo it.
+    #[serde(with = "gitbutler_serde::serde::oid")]
+    pub snapshot_id: git2::Oid,
+    /// The branches whose state before the switch only remains in the snapshot, sorted by name.
+    pub conflicts: Vec<WorkspaceConflict>,
+}
+
+/// List all saved workspaces, sorted by name.
+pub(crate) fn list_workspaces(ctx: &ProjectRepository) -> Result<Vec<Workspace>> {
+    let repo = ctx.repo();
+    let mut workspaces = Vec::new();
+    for reference in repo.references_glob(&format!("{WORKSPACE_REFS_PREFIX}*"))? {
+        let reference = reference?;
+        let Some(name) = reference
+            .name()
+            .and_then(|name| name.strip_prefix(WORKSPACE_REFS_PREFIX))
+        else {
+            continue;
+        };
+        let snapshot = reference.peel_to_commit()?;
+        workspaces.push(workspace_from_snapshot(ctx, name, &snapshot)?);
+    }
+    workspaces.sort_by(|a, b| a.name.cmp(&b.name));
+    Ok(workspaces)
+}
+
+/// Snapshot the virtual branches and the working directory and save them as the workspace `name`,
+/// replacing any workspace of the same name.
+pub(crate) fn save_workspace(
+    ctx: &ProjectRepository,
+    name: &str,
+    perm: &mut WorktreeWritePermission,
+) -> Result<Workspace> {
+    let name = name.trim();
+    let refname = workspace_refname(name)?;
+    let snapshot_id = ctx
+        .project()
+        .create_snapshot(
+            workspace_snapshot_details(OperationKind::SaveWorkspace, name),
+            perm,
+        )?
+        .context("snapshot wasn't created")?;
+    ctx.repo()
+        .reference(&refname, snapshot_id, true, "save workspace")
+        .context("failed to save the workspace reference")?;
+    workspace_from_snapshot(ctx, name, &ctx.repo().find_commit(snapshot_id)?)
+}
+
+/// Return the current branches whose state would be replaced by switching to the workspace `name`, sorted by name,
+/// so they can be reported before switching.
+pub(crate) fn workspace_conflicts(
+    ctx: &ProjectRepository,
+    name: &str,
+) -> Result<Vec<WorkspaceConflict>> {
+    conflicts(ctx, &find_workspace(ctx, name.trim())?)
+}
+
+/// Snapshot the current state, then restore the virtual branches and the working directory of the
+/// workspace `name`. The branches whose current state is replaced are determined before anything changes,
+/// and reported as conflicts.
+pub(crate) fn switch_workspace(
+    ctx: &ProjectRepository,
+    name: &str,
+    perm: &mut WorktreeWritePermission,
+) -> Result<WorkspaceSwitch> {
+    let name = name.trim();
+    let workspace_commit = find_workspace(ctx, name)?;
+    let workspace = workspace_from_snapshot(ctx, name, &workspace_commit)?;
+    let conflicts = conflicts(ctx, &workspace_commit)?;
+
+    let snapshot_id = ctx
+        .project()
+        .create_snapshot(
+            workspace_snapshot_details(OperationKind::SwitchWorkspace, name),
+            perm,
+        )?
+        .context("snapshot wasn't created")?;
+    ctx.project()
+        .restore_snapshot_with_permission(workspace.snapshot_id, perm)?;
+
+    Ok(WorkspaceSwitch {
+        workspace,
+        snapshot_id,
+        conflicts,
+    })
+}
+
+fn find_workspace<'repo>(ctx: &'repo ProjectRepository, name: &str) -> Result<git2::Commit<'repo>> {
+    ctx.repo()
+        .find_reference(&workspace_refname(name)?)
+        .and_then(|reference| reference.peel_to_commit())
+        .map_err(|_| anyhow!("There is no workspace named '{name}'").context(Code::Validation))
+}
+
+fn workspace_refname(name: &str) -> Result<String> {
+    let refname = format!("{WORKSPACE_REFS_PREFIX}{name}");
+    if name.is_empty() || name.contains('/') || !git2::Reference::is_valid_name(&refname) {
+        return Err(anyhow!("'{name}' isn't a valid workspace name").context(Code::Validation));
+    }
+    Ok(refname)
+}
+
+fn workspace_snapshot_details(kind: OperationKind, name: &str) -> SnapshotDetails {
+    SnapshotDetails::new(kind).with_trailers(vec![Trailer {
+        key: "name".to_string(),
+        value: name.to_string(),
+    }])
+}
+
+fn branches_of_snapshot(
+    ctx: &ProjectRepository,
+    snapshot: &git2::Commit,
+) -> Result<VirtualBranchesState> {
+    let tree = snapshot.tree()?;
+    let entry = tree
+        .get_name("virtual_branches.toml")
+        .context("the workspace snapshot has no virtual branches")?;
+    let blob = ctx.repo().find_blob(entry.id())?;
+    Ok(toml::from_str(std::str::from_utf8(blob.content())?)?)
+}
+
+fn workspace_from_snapshot(
+    ctx: &ProjectRepository,
+    name: &str,
+    snapshot: &git2::Commit,
+) -> Result<Workspace> {
+    let mut branches = branches_of_snapshot(ctx, snapshot)?.list_branches_in_workspace()?;
+    branches.sort_by_key(|branch| branch.order);
+    Ok(Workspace {
+        name: name.to_owned(),
+        snapshot_id: snapshot.id(),
+        branches: branches.into_iter().map(|branch| branch.name).collect(),
+        saved_at: UNIX_EPOCH + Duration::from_secs(snapshot.time().seconds().max(0) as u64),
+    })
+}
+
+/// Return the current branches whose state would be replaced by the one in `workspace_snapshot`.
+fn conflicts(
+    ctx: &ProjectRepository,
+    workspace_snapshot: &git2::Commit,
+) -> Result<Vec<WorkspaceConflict>> {
+    let workspace_branches = branches_of_snapshot(ctx, workspace_snapshot)?.list_all_branches()?;
+    let mut conflicts: Vec<_> = ctx
+        .project()
+        .virtual_branches()
+        .list_all_branches()?
+        .into_iter()
+        .filter_map(|branch| {
+            let kind = match workspace_branches
+                .iter()
+                .find(|workspace_branch| workspace_branch.id == branch.id)
+            {
+                None => WorkspaceConflictKind::Missing,
+                Some(workspace_branch) if workspace_branch.head != branch.head => {
+                    WorkspaceConflictKind::Diverged
+                }
+                Some(_) => return None,
+            };
+            Some(WorkspaceConflict {
+                branch_id: branch.id,
+                branch_name: branch.name,
+                kind,
+            })
+        })
+        .collect();
+    conflicts.sort_by(|a, b| a.branch_name.cmp(&b.branch_name));
+    Ok(conflicts)
+}
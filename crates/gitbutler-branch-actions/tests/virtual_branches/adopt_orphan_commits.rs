@@ -0,0 +1,135 @@
+use super::*;
+
+#[tokio::test]
+async fn adopts_commits_of_a_deleted_branch() {
+    let Test {
+        repository,
+        project,
+        controller,
+        ..
+    } = &Test::default();
+
+    controller
+        .set_base_branch(project, &"refs/remotes/origin/master".parse().unwrap())
+        .await
+        .unwrap();
+
+    let branch_id = controller
+        .create_virtual_branch(project, &BranchCreateRequest::default())
+        .await
+        .unwrap();
+    fs::write(repository.path().join("file.txt"), "one").unwrap();
+    let first_commit_id = controller
+        .create_commit(project, branch_id, "one", None, false)
+        .await
+        .unwrap();
+    fs::write(repository.path().join("file.txt"), "two").unwrap();
+    let second_commit_id = controller
+        .create_commit(project, branch_id, "two", None, false)
+        .await
+        .unwrap();
+
+    controller
+        .delete_virtual_branch(project, branch_id)
+        .await
+        .unwrap();
+    assert!(!repository.path().join("file.txt").exists());
+
+    let adopted_id = controller
+        .adopt_orphan_commits(project, &[first_commit_id, second_commit_id], "adopted")
+        .await
+        .unwrap();
+
+    let (branches, _) = controller.list_virtual_branches(project).await.unwrap();
+    assert_eq!(branches.len(), 1);
+    assert_eq!(branches[0].id, adopted_id);
+    assert_eq!(branches[0].name, "adopted");
+    assert!(branches[0].active);
+    assert_eq!(
+        branches[0]
+            .commits
+            .iter()
+            .map(|commit| commit.id)
+            .collect::<Vec<_>>(),
+        [second_commit_id, first_commit_id]
+    );
+    assert_eq!(
+        fs::read_to_string(repository.path().join("file.txt")).unwrap(),
+        "two"
+    );
+}
+
+#[tokio::test]
+async fn commits_must_form_a_chain_on_the_base() {
+    let Test {
+        repository,
+        project,
+        controller,
+        ..
+    } = &Test::default();
+
+    controller
+        .set_base_branch(project, &"refs/remotes/origin/master".parse().unwrap())
+        .await
+        .unwrap();
+
+    let branch_id = controller
+        .create_virtual_branch(project, &BranchCreateRequest::default())
+        .await
+        .unwrap();
+    fs::write(repository.path().join("file.txt"), "one").unwrap();
+    controller
+        .create_commit(project, branch_id, "one", None, false)
+        .await
+        .unwrap();
+    fs::write(repository.path().join("file.txt"), "two").unwrap();
+    let second_commit_id = controller
+        .create_commit(project, branch_id, "two", None, false)
+        .await
+        .unwrap();
+
+    controller
+        .delete_virtual_branch(project, branch_id)
+        .await
+        .unwrap();
+
+    let err = controller
+        .adopt_orphan_commits(project, &[second_commit_id], "adopted")
+        .await
+        .unwrap_err();
+    assert!(format!("{err:#}").contains("must form a chain on top of the base branch"));
+
+    let (branches, _) = controller.list_virtual_branches(project).await.unwrap();
+    assert!(branches.is_empty());
+}
+
+#[tokio::test]
+async fn commits_of_applied_branches_are_not_orphans() {
+    let Test {
+        repository,
+        project,
+        controller,
+        ..
+    } = &Test::default();
+
+    controller
+        .set_base_branch(project, &"refs/remotes/origin/master".parse().unwrap())
+        .await
+        .unwrap();
+
+    let branch_id = controller
+        .create_virtual_branch(project, &BranchCreateRequest::default())
+        .await
+        .unwrap();
+    fs::write(repository.path().join("file.txt"), "one").unwrap();
+    let commit_id = controller
+        .create_commit(project, branch_id, "one", None, false)
+        .await
+        .unwrap();
+
+    let err = controller
+        .adopt_orphan_commits(project, &[commit_id], "adopted")
+        .await
+        .unwrap_err();
+    assert!(format!("{err:#}").contains("already belongs to branch"));
+}
@@ -315,3 +315,163 @@ async fn non_existing_ownership() {
         );
     }
 }
+
+#[tokio::test]
+async fn middle_commit() {
+    let Test {
+        repository,
+        project,
+        controller,
+        ..
+    } = &Test::default();
+
+    controller
+        .set_base_branch(project, &"refs/remotes/origin/master".parse().unwrap())
+        .await
+        .unwrap();
+
+    let branch_id = controller
+        .create_virtual_branch(project, &BranchCreateRequest::default())
+        .await
+        .unwrap();
+
+    let mut commit_oids = Vec::new();
+    for (file, message) in [
+        ("file1.txt", "commit one"),
+        ("file2.txt", "commit two"),
+        ("file3.txt", "commit three"),
+    ] {
+        fs::write(repository.path().join(file), message).unwrap();
+        commit_oids.push(
+            controller
+                .create_commit(project, branch_id, message, None, false)
+                .await
+                .unwrap(),
+        );
+    }
+    let last_before = controller
+        .list_virtual_branches(project)
+        .await
+        .unwrap()
+        .0
+        .into_iter()
+        .find(|b| b.id == branch_id)
+        .unwrap()
+        .commits
+        .remove(0);
+
+    fs::write(repository.path().join("file4.txt"), "content4").unwrap();
+    let to_amend: BranchOwnershipClaims = "file4.txt:1-2".parse().unwrap();
+    controller
+        .amend(project, branch_id, commit_oids[1], &to_amend)
+        .await
+        .unwrap();
+
+    let branch = controller
+        .list_virtual_branches(project)
+        .await
+        .unwrap()
+        .0
+        .into_iter()
+        .find(|b| b.id == branch_id)
+        .unwrap();
+    assert_eq!(branch.files.len(), 0);
+    assert_eq!(
+        branch
+            .commits
+            .iter()
+            .map(|commit| commit.description.to_string())
+            .collect::<Vec<_>>(),
+        vec!["commit three", "commit two", "commit one"]
+    );
+    assert_eq!(
+        branch
+            .commits
+            .iter()
+            .map(|commit| commit.files.len())
+            .collect::<Vec<_>>(),
+        vec![1, 2, 1]
+    );
+    // the amended commit and the one after it are rewritten, the one before it is kept
+    assert_eq!(branch.commits[2].id, commit_oids[0]);
+    assert_ne!(branch.commits[1].id, commit_oids[1]);
+    assert_ne!(branch.commits[0].id, commit_oids[2]);
+    assert_eq!(branch.commits[0].change_id, last_before.change_id);
+    assert_eq!(branch.commits[0].files[0].path, last_before.files[0].path);
+}
+
+#[tokio::test]
+async fn middle_commit_conflicting_with_a_later_commit() {
+    let Test {
+        repository,
+        project,
+        controller,
+        ..
+    } = &Test::default();
+
+    controller
+        .set_base_branch(project, &"refs/remotes/origin/master".parse().unwrap())
+        .await
+        .unwrap();
+
+    let branch_id = controller
+        .create_virtual_branch(project, &BranchCreateRequest::default())
+        .await
+        .unwrap();
+
+    let mut commit_oids = Vec::new();
+    for (file, message) in [
+        ("file1.txt", "commit one"),
+        ("file2.txt", "commit two"),
+        ("file.txt", "commit three"),
+    ] {
+        fs::write(repository.path().join(file), message).unwrap();
+        commit_oids.push(
+            controller
+                .create_commit(project, branch_id, message, None, false)
+                .await
+                .unwrap(),
+        );
+    }
+
+    // the file doesn't exist in the second commit yet, so amending it there adds it, which the third
+    // commit adds as well
+    fs::write(repository.path().join("file.txt"), "amended").unwrap();
+    let to_amend: BranchOwnershipClaims = "file.txt:1-2".parse().unwrap();
+    let err = controller
+        .amend(project, branch_id, commit_oids[1], &to_amend)
+        .await
+        .unwrap_err();
+    let message = format!("{err:#}");
+    assert!(
+        message.contains(&commit_oids[2].to_string()),
+        "the conflicting commit is named: {message}"
+    );
+    assert!(
+        message.contains("file.txt"),
+        "the conflicting file is named: {message}"
+    );
+
+    let branch = controller
+        .list_virtual_branches(project)
+        .await
+        .unwrap()
+        .0
+        .into_iter()
+        .find(|b| b.id == branch_id)
+        .unwrap();
+    assert_eq!(
+        branch
+            .commits
+            .iter()
+            .map(|commit| commit.id)
+            .rev()
+            .collect::<Vec<_>>(),
+        commit_oids,
+        "the branch is left as it was"
+    );
+    assert_eq!(
+        fs::read_to_string(repository.path().join("file.txt")).unwrap(),
+        "amended"
+    );
+}
@@ -0,0 +1,73 @@
+use gitbutler_branch_actions::FileRisk;
+
+use super::*;
+
+#[tokio::test]
+async fn touched_files_are_annotated_with_their_risks() {
+    let Test {
+        repository,
+        project_id,
+        controller,
+        projects,
+        ..
+    } = &Test::default();
+
+    let project = &projects
+        .update(&projects::UpdateRequest {
+            id: *project_id,
+            protected_paths: Some(vec!["release/*".into(), "Cargo.lock".into()]),
+            ..Default::default()
+        })
+        .await
+        .unwrap();
+
+    controller
+        .set_base_branch(project, &"refs/remotes/origin/master".parse().unwrap())
+        .await
+        .unwrap();
+
+    let branch_id = controller
+        .create_virtual_branch(project, &BranchCreateRequest::default())
+        .await
+        .unwrap();
+    for path in [
+        "release/version.txt",
+        "crate/Cargo.lock",
+        ".github/workflows/ci.yml",
+        "db/migrations/001_init.sql",
+        "src/main.rs",
+    ] {
+        let path = repository.path().join(path);
+        fs::create_dir_all(path.parent().unwrap()).unwrap();
+        fs::write(path, "content").unwrap();
+    }
+    controller
+        .create_commit(project, branch_id, "commit", None, false)
+        .await
+        .unwrap();
+    fs::write(repository.path().join("uncommitted.txt"), "content").unwrap();
+    controller.list_virtual_branches(project).await.unwrap();
+
+    let touched = controller
+        .apply_blast_radius(project, branch_id)
+        .await
+        .unwrap();
+    let expected: [(&str, &[FileRisk]); 6] = [
+        (
+            ".github/workflows/ci.yml",
+            &[FileRisk::ContinuousIntegration],
+        ),
+        ("crate/Cargo.lock", &[FileRisk::Protected]),
+        ("db/migrations/001_init.sql", &[FileRisk::Migration]),
+        ("release/version.txt", &[FileRisk::Protected]),
+        ("src/main.rs", &[]),
+        ("uncommitted.txt", &[]),
+    ];
+    assert_eq!(
+        touched
+            .iter()
+            .map(|file| (file.path.to_str().unwrap(), file.risks.as_slice()))
+            .collect::<Vec<_>>(),
+        expected
+    );
+}
@@ -0,0 +1,103 @@
+use gitbutler_branch::BranchId;
+
+use super::*;
+
+async fn unapplied_branch_with_file(
+    Test {
+        repository,
+        project,
+        controller,
+        ..
+    }: &Test,
+    path: &str,
+    content: &str,
+) -> BranchId {
+    let branch_id = controller
+        .create_virtual_branch(project, &BranchCreateRequest::default())
+        .await
+        .unwrap();
+    fs::write(repository.path().join(path), content).unwrap();
+    controller
+        .create_commit(project, branch_id, content, None, false)
+        .await
+        .unwrap();
+    controller
+        .convert_to_real_branch(project, branch_id, Default::default())
+        .await
+        .unwrap();
+    branch_id
+}
+
+#[tokio::test]
+async fn applies_all_branches_in_order() {
+    let test = Test::default();
+    let Test {
+        repository,
+        project,
+        controller,
+        ..
+    } = &test;
+
+    controller
+        .set_base_branch(project, &"refs/remotes/origin/master".parse().unwrap())
+        .await
+        .unwrap();
+
+    let first = unapplied_branch_with_file(&test, "first.txt", "first").await;
+    let second = unapplied_branch_with_file(&test, "second.txt", "second").await;
+
+    let application = controller
+        .apply_stack(project, &[first, second])
+        .await
+        .unwrap();
+    assert_eq!(application.applied, [first, second]);
+    assert_eq!(application.conflicting, None);
+
+    let (branches, _) = controller.list_virtual_branches(project).await.unwrap();
+    assert_eq!(branches.len(), 2);
+    assert!(branches.iter().all(|branch| branch.active));
+    assert_eq!(
+        fs::read_to_string(repository.path().join("first.txt")).unwrap(),
+        "first"
+    );
+    assert_eq!(
+        fs::read_to_string(repository.path().join("second.txt")).unwrap(),
+        "second"
+    );
+}
+
+#[tokio::test]
+async fn stops_at_the_first_conflicting_branch() {
+    let test = Test::default();
+    let Test {
+        repository,
+        project,
+        controller,
+        ..
+    } = &test;
+
+    controller
+        .set_base_branch(project, &"refs/remotes/origin/master".parse().unwrap())
+        .await
+        .unwrap();
+
+    let first = unapplied_branch_with_file(&test, "file.txt", "first").await;
+    let second = unapplied_branch_with_file(&test, "file.txt", "second").await;
+    let third = unapplied_branch_with_file(&test, "third.txt", "third").await;
+
+    let application = controller
+        .apply_stack(project, &[first, second, third])
+        .await
+        .unwrap();
+    assert_eq!(application.applied, [first]);
+    assert_eq!(application.conflicting, Some(second));
+
+    let (branches, _) = controller.list_virtual_branches(project).await.unwrap();
+    assert_eq!(branches.len(), 1);
+    assert_eq!(branches[0].id, application.applied[0]);
+    assert_eq!(
+        fs::read_to_string(repository.path().join("file.txt")).unwrap(),
+        "first"
+    );
+    assert!(!repository.path().join("third.txt").exists());
+}
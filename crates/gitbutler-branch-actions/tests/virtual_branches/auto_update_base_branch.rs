@@ -0,0 +1,177 @@
+use super::*;
+
+#[tokio::test]
+async fn disabled_by_default() {
+    let Test {
+        repository,
+        project,
+        controller,
+        ..
+    } = &Test::default();
+
+    // make sure we have an undiscovered commit in the remote branch
+    {
+        fs::write(repository.path().join("file.txt"), "first").unwrap();
+        let first_commit_oid = repository.commit_all("first");
+        fs::write(repository.path().join("file.txt"), "second").unwrap();
+        repository.commit_all("second");
+        repository.push();
+        repository.reset_hard(Some(first_commit_oid));
+    }
+
+    controller
+        .set_base_branch(project, &"refs/remotes/origin/master".parse().unwrap())
+        .await
+        .unwrap();
+
+    controller
+        .create_virtual_branch(project, &BranchCreateRequest::default())
+        .await
+        .unwrap();
+    fs::write(repository.path().join("file2.txt"), "no conflict").unwrap();
+
+    let unapplied_branches = controller.auto_update_base_branch(project).await.unwrap();
+    assert!(unapplied_branches.is_empty());
+
+    let (branches, _) = controller.list_virtual_branches(project).await.unwrap();
+    assert_eq!(branches.len(), 1);
+    assert!(!branches[0].base_current);
+}
+
+/// Enable automatic base updates for the project and set up a base branch that is one commit behind
+/// its remote, which changes `file.txt` from "first" to "second".
+async fn project_behind_its_base(test: &Test) -> Project {
+    let Test {
+        repository,
+        project_id,
+        controller,
+        projects,
+        ..
+    } = test;
+
+    let project = projects
+        .update(&projects::UpdateRequest {
+            id: *project_id,
+            auto_rebase_on_base_update: Some(true),
+            ..Default::default()
+        })
+        .await
+        .unwrap();
+
+    // make sure we have an undiscovered commit in the remote branch
+    {
+        fs::write(repository.path().join("file.txt"), "first").unwrap();
+        let first_commit_oid = repository.commit_all("first");
+        fs::write(repository.path().join("file.txt"), "second").unwrap();
+        repository.commit_all("second");
+        repository.push();
+        repository.reset_hard(Some(first_commit_oid));
+    }
+
+    controller
+        .set_base_branch(&project, &"refs/remotes/origin/master".parse().unwrap())
+        .await
+        .unwrap();
+    project
+}
+
+#[tokio::test]
+async fn rebases_clean_branches() {
+    let test = Test::default();
+    let project = &project_behind_its_base(&test).await;
+    let Test {
+        repository,
+        controller,
+        ..
+    } = &test;
+
+    let branch_id = controller
+        .create_virtual_branch(project, &BranchCreateRequest::default())
+        .await
+        .unwrap();
+    fs::write(repository.path().join("file2.txt"), "no conflict").unwrap();
+    controller
+        .create_commit(project, branch_id, "no conflicts", None, false)
+        .await
+        .unwrap();
+
+    let conflicting_branches = controller.auto_update_base_branch(project).await.unwrap();
+    assert!(conflicting_branches.is_empty());
+
+    let (branches, _) = controller.list_virtual_branches(project).await.unwrap();
+    assert_eq!(branches.len(), 1);
+    assert_eq!(branches[0].id, branch_id);
+    assert!(branches[0].base_current);
+    assert_eq!(branches[0].commits.len(), 1);
+    assert_eq!(
+        fs::read_to_string(repository.path().join("file.txt")).unwrap(),
+        "second"
+    );
+
+    // once up to date, there is nothing left to do
+    assert!(controller
+        .auto_update_base_branch(project)
+        .await
+        .unwrap()
+        .is_empty());
+}
+
+#[tokio::test]
+async fn rebases_clean_branches_and_flags_conflicting_ones() {
+    let test = Test::default();
+    let project = &project_behind_its_base(&test).await;
+    let Test {
+        repository,
+        controller,
+        ..
+    } = &test;
+
+    let clean_branch_id = controller
+        .create_virtual_branch(project, &BranchCreateRequest::default())
+        .await
+        .unwrap();
+    fs::write(repository.path().join("file2.txt"), "no conflict").unwrap();
+    controller
+        .create_commit(project, clean_branch_id, "no conflicts", None, false)
+        .await
+        .unwrap();
+
+    let conflicting_branch_id = controller
+        .create_virtual_branch(
+            project,
+            &BranchCreateRequest {
+                name: Some("conflicting".into()),
+                selected_for_changes: Some(true),
+                ..Default::default()
+            },
+        )
+        .await
+        .unwrap();
+    fs::write(repository.path().join("file.txt"), "conflict").unwrap();
+    let conflicting_head = controller
+        .create_commit(project, conflicting_branch_id, "conflict", None, false)
+        .await
+        .unwrap();
+
+    let conflicting_branches = controller.auto_update_base_branch(project).await.unwrap();
+    assert_eq!(conflicting_branches, ["conflicting"]);
+
+    let (branches, _) = controller.list_virtual_branches(project).await.unwrap();
+    assert_eq!(branches.len(), 1, "only the clean branch stays applied");
+    assert_eq!(branches[0].id, clean_branch_id);
+    assert_eq!(branches[0].commits.len(), 1);
+    assert_eq!(
+        fs::read_to_string(repository.path().join("file.txt")).unwrap(),
+        "second"
+    );
+
+    let repo = git2::Repository::open(repository.path()).unwrap();
+    let conflicting_branch = repo
+        .find_branch("conflicting", git2::BranchType::Local)
+        .unwrap();
+    assert_eq!(
+        conflicting_branch.get().target(),
+        Some(conflicting_head),
+        "the conflicting branch keeps its commits as they were"
+    );
+}
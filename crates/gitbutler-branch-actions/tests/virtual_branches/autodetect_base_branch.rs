@@ -0,0 +1,129 @@
+use gitbutler_reference::LocalRefname;
+
+use super::*;
+
+#[tokio::test]
+async fn uses_default_branch_advertised_by_remote() {
+    let Test {
+        repository,
+        project,
+        controller,
+        ..
+    } = &Test::default();
+
+    let repo = git2::Repository::open(repository.path()).unwrap();
+    let head_commit = repo.head().unwrap().peel_to_commit().unwrap();
+    repo.branch("main", &head_commit, false).unwrap();
+    repository.push_branch(&LocalRefname::new("main", None));
+
+    let remote_url = repo
+        .find_remote("origin")
+        .unwrap()
+        .url()
+        .unwrap()
+        .to_owned();
+    git2::Repository::open(remote_url)
+        .unwrap()
+        .set_head("refs/heads/main")
+        .unwrap();
+    repository.fetch();
+
+    let base = controller.autodetect_base_branch(project).await.unwrap();
+    assert_eq!(base.branch_name, "origin/main");
+    assert_eq!(
+        VirtualBranchActions::get_base_branch_data(project)
+            .await
+            .unwrap()
+            .branch_name,
+        "origin/main"
+    );
+}
+
+#[tokio::test]
+async fn replaces_wrong_base_branch() {
+    let Test {
+        repository,
+        project,
+        controller,
+        ..
+    } = &Test::default();
+
+    let repo = git2::Repository::open(repository.path()).unwrap();
+    let head_commit = repo.head().unwrap().peel_to_commit().unwrap();
+    repo.branch("other", &head_commit, false).unwrap();
+    repository.push_branch(&LocalRefname::new("other", None));
+    repository.fetch();
+
+    controller
+        .set_base_branch(project, &"refs/remotes/origin/other".parse().unwrap())
+        .await
+        .unwrap();
+
+    let base = controller.autodetect_base_branch(project).await.unwrap();
+    assert_eq!(base.branch_name, "origin/master");
+}
+
+#[tokio::test]
+async fn keeps_current_base_branch_if_remote_is_unreachable() {
+    let Test {
+        repository,
+        project,
+        controller,
+        ..
+    } = &Test::default();
+
+    controller
+        .set_base_branch(project, &"refs/remotes/origin/master".parse().unwrap())
+        .await
+        .unwrap();
+
+    let repo = git2::Repository::open(repository.path()).unwrap();
+    repo.remote_set_url(
+        "origin",
+        &repository.path().join("missing").to_string_lossy(),
+    )
+    .unwrap();
+
+    let base = controller.autodetect_base_branch(project).await.unwrap();
+    assert_eq!(base.branch_name, "origin/master");
+}
+
+#[tokio::test]
+async fn falls_back_to_the_local_default_branch() {
+    let Test {
+        repository,
+        project,
+        controller,
+        ..
+    } = &Test::default();
+
+    let repo = git2::Repository::open(repository.path()).unwrap();
+    repo.remote_set_url(
+        "origin",
+        &repository.path().join("missing").to_string_lossy(),
+    )
+    .unwrap();
+    if let Ok(mut remote_head) = repo.find_reference("refs/remotes/origin/HEAD") {
+        remote_head.delete().unwrap();
+    }
+
+    let base = controller.autodetect_base_branch(project).await.unwrap();
+    assert_eq!(base.branch_name, "origin/master");
+}
+
+#[tokio::test]
+async fn fails_without_any_remote() {
+    let Test {
+        repository,
+        project,
+        controller,
+        ..
+    } = &Test::default();
+
+    git2::Repository::open(repository.path())
+        .unwrap()
+        .remote_delete("origin")
+        .unwrap();
+
+    assert!(controller.autodetect_base_branch(project).await.is_err());
+}
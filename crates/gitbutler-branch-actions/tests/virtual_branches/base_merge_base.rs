@@ -0,0 +1,277 @@
+use super::*;
+
+fn cache_path(project: &Project) -> PathBuf {
+    project.gb_dir().join("base_cache.toml")
+}
+
+/// Sets the base so the workspace is based on the first of two commits of the base branch,
+/// and returns the first commit.
+async fn set_base_behind_by_one(
+    repository: &TestProject,
+    project: &Project,
+    controller: &VirtualBranchActions,
+) -> git2::Oid {
+    fs::write(repository.path().join("file.txt"), "first").unwrap();
+    let first_commit_oid = repository.commit_all("first");
+    fs::write(repository.path().join("file.txt"), "second").unwrap();
+    repository.commit_all("second");
+    repository.push();
+    repository.reset_hard(Some(first_commit_oid));
+
+    controller
+        .set_base_branch(project, &"refs/remotes/origin/master".parse().unwrap())
+        .await
+        .unwrap();
+    first_commit_oid
+}
+
+/// Force-push a commit onto the remote base branch that forks off before `commit`.
+fn rewrite_remote_base_before(repository: &TestProject, commit: git2::Oid) -> git2::Oid {
+    let repo = git2::Repository::open(repository.path()).unwrap();
+    let parent = repo.find_commit(commit).unwrap().parent(0).unwrap();
+    let signature = git2::Signature::now("test", "test@email.com").unwrap();
+    let rewritten = repo
+        .commit(
+            Some("refs/heads/rewritten"),
+            &signature,
+            &signature,
+            "rewritten",
+            &parent.tree().unwrap(),
+            &[&parent],
+        )
+        .unwrap();
+    repo.find_remote("origin")
+        .unwrap()
+        .push(&["+refs/heads/rewritten:refs/heads/master"], None)
+        .unwrap();
+    rewritten
+}
+
+#[tokio::test]
+async fn cached_value_matches_fresh_computation() {
+    let Test {
+        repository,
+        project,
+        controller,
+        ..
+    } = &Test::default();
+
+    let first_commit_oid = set_base_behind_by_one(repository, project, controller).await;
+
+    let merge_base = controller.base_merge_base(project).await.unwrap();
+    let repo = git2::Repository::open(repository.path()).unwrap();
+    let base_head = repo
+        .find_reference("refs/remotes/origin/master")
+        .unwrap()
+        .peel_to_commit()
+        .unwrap()
+        .id();
+    assert_eq!(
+        merge_base,
+        repo.merge_base(base_head, first_commit_oid).unwrap()
+    );
+    assert!(cache_path(project).exists(), "the merge-base is cached");
+    assert_eq!(
+        controller.base_merge_base(project).await.unwrap(),
+        merge_base
+    );
+}
+
+#[tokio::test]
+async fn invalidated_when_base_moves() {
+    let Test {
+        repository,
+        project,
+        controller,
+        ..
+    } = &Test::default();
+
+    let first_commit_oid = set_base_behind_by_one(repository, project, controller).await;
+    assert_eq!(
+        controller.base_merge_base(project).await.unwrap(),
+        first_commit_oid
+    );
+
+    let rewritten = rewrite_remote_base_before(repository, first_commit_oid);
+    controller
+        .fetch_from_remotes(project, None, None)
+        .await
+        .unwrap();
+    assert!(
+        !cache_path(project).exists(),
+        "fetching clears the cache as the base may have moved"
+    );
+
+    let repo = git2::Repository::open(repository.path()).unwrap();
+    let merge_base = controller.base_merge_base(project).await.unwrap();
+    assert_eq!(
+        merge_base,
+        repo.merge_base(rewritten, first_commit_oid).unwrap()
+    );
+    assert_ne!(merge_base, first_commit_oid);
+}
+
+#[tokio::test]
+async fn recomputed_if_base_moved_outside_of_fetch() {
+    let Test {
+        repository,
+        project,
+        controller,
+        ..
+    } = &Test::default();
+
+    let first_commit_oid = set_base_behind_by_one(repository, project, controller).await;
+    assert_eq!(
+        controller.base_merge_base(project).await.unwrap(),
+        first_commit_oid
+    );
+
+    rewrite_remote_base_before(repository, first_commit_oid);
+    repository.fetch();
+
+    let parent = repository
+        .find_commit(first_commit_oid)
+        .unwrap()
+        .parent_id(0)
+        .unwrap();
+    assert_eq!(controller.base_merge_base(project).await.unwrap(), parent);
+}
+
+#[tokio::test]
+async fn clear_base_cache() {
+    let Test {
+        repository,
+        project,
+        controller,
+        ..
+    } = &Test::default();
+
+    set_base_behind_by_one(repository, project, controller).await;
+    controller.base_merge_base(project).await.unwrap();
+    assert!(cache_path(project).exists());
+
+    controller.clear_base_cache(project).await.unwrap();
+    assert!(!cache_path(project).exists());
+    controller
+        .clear_base_cache(project)
+        .await
+        .expect("clearing an empty cache is fine");
+}
+
+#[tokio::test]
+async fn divergence_is_counted_and_cached() {
+    let Test {
+        repository,
+        project,
+        controller,
+        ..
+    } = &Test::default();
+
+    let first_commit_oid = set_base_behind_by_one(repository, project, controller).await;
+    let divergence = controller
+        .branch_divergence(project)
+        .await
+        .unwrap()
+        .unwrap();
+    assert_eq!(
+        (
+            divergence.ahead,
+            divergence.behind,
+            divergence.unrelated_histories
+        ),
+        (0, 1, false)
+    );
+    assert!(cache_path(project).exists(), "the divergence is cached");
+    assert_eq!(
+        controller.branch_divergence(project).await.unwrap(),
+        Some(divergence)
+    );
+
+    rewrite_remote_base_before(repository, first_commit_oid);
+    repository.fetch();
+    let divergence = controller
+        .branch_divergence(project)
+        .await
+        .unwrap()
+        .unwrap();
+    assert_eq!(
+        (divergence.ahead, divergence.behind),
+        (1, 1),
+        "the cache is keyed on both commits, so moving the base branch computes it again"
+    );
+}
+
+#[tokio::test]
+async fn divergence_from_unrelated_history_is_reported() {
+    let Test {
+        repository,
+        project,
+        controller,
+        ..
+    } = &Test::default();
+
+    set_base_behind_by_one(repository, project, controller).await;
+    let repo = git2::Repository::open(repository.path()).unwrap();
+    let signature = git2::Signature::now("test", "test@email.com").unwrap();
+    let tree = repo
+        .find_tree(repo.treebuilder(None).unwrap().write().unwrap())
+        .unwrap();
+    repo.commit(
+        Some("refs/heads/unrelated"),
+        &signature,
+        &signature,
+        "unrelated",
+        &tree,
+        &[],
+    )
+    .unwrap();
+    repo.find_remote("origin")
+        .unwrap()
+        .push(&["+refs/heads/unrelated:refs/heads/master"], None)
+        .unwrap();
+    repository.fetch();
+
+    let divergence = controller
+        .branch_divergence(project)
+        .await
+        .unwrap()
+        .unwrap();
+    assert!(divergence.unrelated_histories);
+    assert_eq!(divergence.behind, 1);
+}
+
+#[tokio::test]
+async fn shared_with_the_detection_of_base_rewrites() {
+    let Test {
+        repository,
+        project,
+        controller,
+        ..
+    } = &Test::default();
+
+    let first_commit_oid = set_base_behind_by_one(repository, project, controller).await;
+    rewrite_remote_base_before(repository, first_commit_oid);
+    repository.fetch();
+
+    let merge_base = controller.base_merge_base(project).await.unwrap();
+    let rewrite = controller
+        .detect_base_history_rewrite(project)
+        .await
+        .unwrap()
+        .expect("the base was rewritten");
+    assert_eq!(rewrite.common_ancestor, Some(merge_base));
+
+    // prove that the cached merge-base is used by replacing it
+    let cache = fs::read_to_string(cache_path(project)).unwrap();
+    fs::write(
+        cache_path(project),
+        cache.replace(&merge_base.to_string(), &first_commit_oid.to_string()),
+    )
+    .unwrap();
+    let rewrite = controller
+        .detect_base_history_rewrite(project)
+        .await
+        .unwrap()
+        .expect("the base was rewritten");
+    assert_eq!(rewrite.common_ancestor, Some(first_commit_oid));
+}
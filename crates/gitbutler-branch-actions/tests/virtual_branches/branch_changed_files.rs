@@ -0,0 +1,125 @@
+use gitbutler_branch::BranchId;
+use std::path::PathBuf;
+
+use super::*;
+
+#[tokio::test]
+async fn committed_and_uncommitted_files() {
+    let Test {
+        repository,
+        project,
+        controller,
+        ..
+    } = &Test::default();
+
+    fs::write(repository.path().join("file.txt"), "content").unwrap();
+    repository.commit_all("file");
+    repository.push();
+
+    controller
+        .set_base_branch(project, &"refs/remotes/origin/master".parse().unwrap())
+        .await
+        .unwrap();
+
+    let branch_id = controller
+        .create_virtual_branch(project, &BranchCreateRequest::default())
+        .await
+        .unwrap();
+
+    fs::rename(
+        repository.path().join("file.txt"),
+        repository.path().join("moved.txt"),
+    )
+    .unwrap();
+    fs::write(repository.path().join("committed.txt"), "committed").unwrap();
+    controller
+        .create_commit(project, branch_id, "commit", None, false)
+        .await
+        .unwrap();
+
+    fs::write(repository.path().join("uncommitted.txt"), "uncommitted").unwrap();
+
+    let files = controller
+        .branch_changed_files(project, branch_id)
+        .await
+        .unwrap();
+    assert_eq!(
+        files,
+        ["committed.txt", "file.txt", "moved.txt", "uncommitted.txt"]
+            .into_iter()
+            .map(PathBuf::from)
+            .collect::<Vec<_>>(),
+        "renames contribute both paths"
+    );
+}
+
+#[tokio::test]
+async fn files_of_other_branches_are_excluded() {
+    let Test {
+        repository,
+        project,
+        controller,
+        ..
+    } = &Test::default();
+
+    controller
+        .set_base_branch(project, &"refs/remotes/origin/master".parse().unwrap())
+        .await
+        .unwrap();
+
+    let branch1_id = controller
+        .create_virtual_branch(project, &BranchCreateRequest::default())
+        .await
+        .unwrap();
+    fs::write(repository.path().join("one.txt"), "one").unwrap();
+    controller
+        .create_commit(project, branch1_id, "one", None, false)
+        .await
+        .unwrap();
+
+    let branch2_id = controller
+        .create_virtual_branch(
+            project,
+            &BranchCreateRequest {
+                selected_for_changes: Some(true),
+                ..Default::default()
+            },
+        )
+        .await
+        .unwrap();
+    fs::write(repository.path().join("two.txt"), "two").unwrap();
+
+    assert_eq!(
+        controller
+            .branch_changed_files(project, branch1_id)
+            .await
+            .unwrap(),
+        vec![PathBuf::from("one.txt")]
+    );
+    assert_eq!(
+        controller
+            .branch_changed_files(project, branch2_id)
+            .await
+            .unwrap(),
+        vec![PathBuf::from("two.txt")]
+    );
+}
+
+#[tokio::test]
+async fn unknown_branch() {
+    let Test {
+        project,
+        controller,
+        ..
+    } = &Test::default();
+
+    controller
+        .set_base_branch(project, &"refs/remotes/origin/master".parse().unwrap())
+        .await
+        .unwrap();
+
+    assert!(controller
+        .branch_changed_files(project, BranchId::generate())
+        .await
+        .is_err());
+}
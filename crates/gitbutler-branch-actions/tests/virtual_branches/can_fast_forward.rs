@@ -0,0 +1,104 @@
+use gitbutler_branch_actions::FastForward;
+
+use super::*;
+
+#[tokio::test]
+async fn branch_ahead_of_base() {
+    let Test {
+        repository,
+        project,
+        controller,
+        ..
+    } = &Test::default();
+
+    controller
+        .set_base_branch(project, &"refs/remotes/origin/master".parse().unwrap())
+        .await
+        .unwrap();
+
+    let branch_id = controller
+        .create_virtual_branch(project, &BranchCreateRequest::default())
+        .await
+        .unwrap();
+    assert_eq!(
+        controller
+            .can_fast_forward(project, branch_id)
+            .await
+            .unwrap(),
+        FastForward {
+            base_is_ancestor: true,
+            branch_is_ancestor: true,
+        },
+        "a branch without commits points to the base"
+    );
+
+    fs::write(repository.path().join("file.txt"), "content").unwrap();
+    controller
+        .create_commit(project, branch_id, "commit", None, false)
+        .await
+        .unwrap();
+    assert_eq!(
+        controller
+            .can_fast_forward(project, branch_id)
+            .await
+            .unwrap(),
+        FastForward {
+            base_is_ancestor: true,
+            branch_is_ancestor: false,
+        }
+    );
+}
+
+#[tokio::test]
+async fn branch_diverged_from_base() {
+    let Test {
+        repository,
+        project,
+        controller,
+        ..
+    } = &Test::default();
+
+    fs::write(repository.path().join("file.txt"), "first").unwrap();
+    let first_commit_oid = repository.commit_all("first");
+    fs::write(repository.path().join("file.txt"), "second").unwrap();
+    repository.commit_all("second");
+    repository.push();
+    repository.reset_hard(Some(first_commit_oid));
+
+    controller
+        .set_base_branch(project, &"refs/remotes/origin/master".parse().unwrap())
+        .await
+        .unwrap();
+
+    let branch_id = controller
+        .create_virtual_branch(project, &BranchCreateRequest::default())
+        .await
+        .unwrap();
+    assert_eq!(
+        controller
+            .can_fast_forward(project, branch_id)
+            .await
+            .unwrap(),
+        FastForward {
+            base_is_ancestor: false,
+            branch_is_ancestor: true,
+        },
+        "the branch is behind the base"
+    );
+
+    fs::write(repository.path().join("other.txt"), "content").unwrap();
+    controller
+        .create_commit(project, branch_id, "commit", None, false)
+        .await
+        .unwrap();
+    assert_eq!(
+        controller
+            .can_fast_forward(project, branch_id)
+            .await
+            .unwrap(),
+        FastForward {
+            base_is_ancestor: false,
+            branch_is_ancestor: false,
+        }
+    );
+}
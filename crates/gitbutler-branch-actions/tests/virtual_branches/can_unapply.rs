@@ -0,0 +1,173 @@
+use gitbutler_branch::{BranchId, VirtualBranchesHandle};
+use gitbutler_branch_actions::{UnapplyConflict, UnapplyConflictKind, UnapplyReport};
+
+use super::*;
+
+#[tokio::test]
+async fn reports_no_conflicts_for_independent_branches() {
+    let Test {
+        repository,
+        project,
+        controller,
+        ..
+    } = &Test::default();
+
+    controller
+        .set_base_branch(project, &"refs/remotes/origin/master".parse().unwrap())
+        .await
+        .unwrap();
+    let branch_id = controller
+        .create_virtual_branch(project, &BranchCreateRequest::default())
+        .await
+        .unwrap();
+    fs::write(repository.path().join("committed.txt"), "committed").unwrap();
+    controller
+        .create_commit(project, branch_id, "commit", None, false)
+        .await
+        .unwrap();
+    fs::write(repository.path().join("uncommitted.txt"), "uncommitted").unwrap();
+
+    let report = controller.can_unapply(project, branch_id).await.unwrap();
+    assert!(report.conflicts.is_empty());
+
+    // nothing was unapplied
+    assert_eq!(
+        fs::read_to_string(repository.path().join("committed.txt")).unwrap(),
+        "committed"
+    );
+    assert_eq!(
+        fs::read_to_string(repository.path().join("uncommitted.txt")).unwrap(),
+        "uncommitted"
+    );
+    let (branches, _) = controller.list_virtual_branches(project).await.unwrap();
+    assert_eq!(branches.len(), 1);
+    assert!(branches[0].active);
+    assert_eq!(branches[0].files.len(), 1);
+}
+
+#[tokio::test]
+async fn needs_a_branch_in_the_workspace() {
+    let Test {
+        project,
+        controller,
+        ..
+    } = &Test::default();
+
+    controller
+        .set_base_branch(project, &"refs/remotes/origin/master".parse().unwrap())
+        .await
+        .unwrap();
+    let branch_id = controller
+        .create_virtual_branch(project, &BranchCreateRequest::default())
+        .await
+        .unwrap();
+    controller
+        .convert_to_real_branch(project, branch_id, Default::default())
+        .await
+        .unwrap();
+
+    assert!(controller.can_unapply(project, branch_id).await.is_err());
+}
+
+/// Make the branch with `branch_id` a single commit on top of the base that sets `file.txt` to `content`,
+/// or deletes it if `None`, without touching the worktree so it can conflict with other branches.
+fn set_branch_file(project: &Project, branch_id: BranchId, content: Option<&str>) {
+    let repo = git2::Repository::open(&project.path).unwrap();
+    let vb_state = VirtualBranchesHandle::new(project.gb_dir());
+    let base = repo
+        .find_commit(vb_state.get_default_target().unwrap().sha)
+        .unwrap();
+    let mut builder = repo.treebuilder(Some(&base.tree().unwrap())).unwrap();
+    match content {
+        Some(content) => {
+            let blob = repo.blob(content.as_bytes()).unwrap();
+            builder.insert("file.txt", blob, 0o100644).unwrap();
+        }
+        None => builder.remove("file.txt").unwrap(),
+    }
+    let tree = repo.find_tree(builder.write().unwrap()).unwrap();
+    let signature = git2::Signature::now("test", "test@example.com").unwrap();
+    let head = repo
+        .commit(None, &signature, &signature, "change", &tree, &[&base])
+        .unwrap();
+    let mut branch = vb_state.get_branch(branch_id).unwrap();
+    branch.head = head;
+    branch.tree = tree.id();
+    vb_state.set_branch(branch).unwrap();
+}
+
+/// Report unapplying a branch that sets `file.txt` to `unapplied` while another one sets it to `other`, with
+/// the base containing `base`.
+async fn report_for(
+    base: Option<&str>,
+    unapplied: Option<&str>,
+    other: Option<&str>,
+) -> UnapplyReport {
+    let Test {
+        repository,
+        project,
+        controller,
+        ..
+    } = &Test::default();
+
+    if let Some(base) = base {
+        fs::write(repository.path().join("file.txt"), base).unwrap();
+        repository.commit_all("base");
+        repository.push();
+    }
+    controller
+        .set_base_branch(project, &"refs/remotes/origin/master".parse().unwrap())
+        .await
+        .unwrap();
+    let unapplied_id = controller
+        .create_virtual_branch(project, &BranchCreateRequest::default())
+        .await
+        .unwrap();
+    let other_id = controller
+        .create_virtual_branch(project, &BranchCreateRequest::default())
+        .await
+        .unwrap();
+    set_branch_file(project, unapplied_id, unapplied);
+    set_branch_file(project, other_id, other);
+
+    controller.can_unapply(project, unapplied_id).await.unwrap()
+}
+
+#[tokio::test]
+async fn reports_content_conflicts() {
+    let report = report_for(Some("base"), Some("unapplied"), Some("other")).await;
+    assert_eq!(
+        report.conflicts,
+        [UnapplyConflict {
+            path: "file.txt".into(),
+            kind: UnapplyConflictKind::Content,
+        }]
+    );
+}
+
+#[tokio::test]
+async fn reports_delete_modify_conflicts() {
+    for (unapplied, other) in [(Some("unapplied"), None), (None, Some("other"))] {
+        let report = report_for(Some("base"), unapplied, other).await;
+        assert_eq!(
+            report.conflicts,
+            [UnapplyConflict {
+                path: "file.txt".into(),
+                kind: UnapplyConflictKind::DeleteModify,
+            }],
+            "{unapplied:?} vs {other:?}"
+        );
+    }
+}
+
+#[tokio::test]
+async fn reports_files_added_by_both_sides() {
+    let report = report_for(None, Some("unapplied"), Some("other")).await;
+    assert_eq!(
+        report.conflicts,
+        [UnapplyConflict {
+            path: "file.txt".into(),
+            kind: UnapplyConflictKind::BothAdded,
+        }]
+    );
+}
@@ -0,0 +1,87 @@
+use super::*;
+
+#[tokio::test]
+async fn is_stable_across_rebases() {
+    let Test {
+        repository,
+        project,
+        controller,
+        ..
+    } = &Test::default();
+
+    controller
+        .set_base_branch(project, &"refs/remotes/origin/master".parse().unwrap())
+        .await
+        .unwrap();
+
+    let branch_id = controller
+        .create_virtual_branch(project, &BranchCreateRequest::default())
+        .await
+        .unwrap();
+    fs::write(repository.path().join("file.txt"), "content").unwrap();
+    let commit_id = controller
+        .create_commit(project, branch_id, "commit one", None, false)
+        .await
+        .unwrap();
+    let change_id = controller.change_id_of(project, commit_id).await.unwrap();
+
+    // inserting a commit below rebases the commit onto it
+    controller
+        .insert_blank_commit(project, branch_id, commit_id, 1)
+        .await
+        .unwrap();
+    let (branches, _) = controller.list_virtual_branches(project).await.unwrap();
+    let rebased = branches[0]
+        .commits
+        .iter()
+        .find(|commit| commit.description == "commit one")
+        .unwrap();
+    assert_ne!(rebased.id, commit_id);
+    assert_eq!(
+        controller.change_id_of(project, rebased.id).await.unwrap(),
+        change_id
+    );
+}
+
+#[tokio::test]
+async fn is_derived_for_commits_without_one() {
+    let Test {
+        repository,
+        project,
+        controller,
+        ..
+    } = &Test::default();
+
+    controller
+        .set_base_branch(project, &"refs/remotes/origin/master".parse().unwrap())
+        .await
+        .unwrap();
+
+    let repo = git2::Repository::open(repository.path()).unwrap();
+    let head = repo.head().unwrap().peel_to_commit().unwrap();
+    let tree = head.tree().unwrap();
+    let author =
+        git2::Signature::new("author", "author@example.com", &git2::Time::new(1, 0)).unwrap();
+    let committer =
+        git2::Signature::new("committer", "committer@example.com", &git2::Time::new(2, 0)).unwrap();
+    let commit = |message: &str, committer: &git2::Signature<'_>, parents: &[&git2::Commit<'_>]| {
+        repo.commit(None, &author, committer, message, &tree, parents)
+            .unwrap()
+    };
+
+    let original = commit("message", &committer, &[&head]);
+    let rebased_committer =
+        git2::Signature::new("rebaser", "rebaser@example.com", &git2::Time::new(3, 0)).unwrap();
+    let rebased = commit("message", &rebased_committer, &[]);
+    let other = commit("other message", &committer, &[&head]);
+
+    let change_id = controller.change_id_of(project, original).await.unwrap();
+    assert_eq!(
+        controller.change_id_of(project, rebased).await.unwrap(),
+        change_id
+    );
+    assert_ne!(
+        controller.change_id_of(project, other).await.unwrap(),
+        change_id
+    );
+}
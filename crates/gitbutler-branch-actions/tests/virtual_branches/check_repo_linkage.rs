@@ -0,0 +1,75 @@
+use gitbutler_branch_actions::LinkageKind;
+
+use super::*;
+
+#[tokio::test]
+async fn no_dangling_references() {
+    let Test {
+        repository,
+        project,
+        controller,
+        ..
+    } = &Test::default();
+
+    controller
+        .set_base_branch(project, &"refs/remotes/origin/master".parse().unwrap())
+        .await
+        .unwrap();
+
+    let branch_id = controller
+        .create_virtual_branch(project, &BranchCreateRequest::default())
+        .await
+        .unwrap();
+    fs::write(repository.path().join("file.txt"), "content").unwrap();
+    controller
+        .create_commit(project, branch_id, "commit", None, false)
+        .await
+        .unwrap();
+
+    let dangling = controller.check_repo_linkage(project).await.unwrap();
+    assert!(dangling.is_empty());
+}
+
+#[tokio::test]
+async fn pruned_branch_head() {
+    let Test {
+        repository,
+        project,
+        controller,
+        ..
+    } = &Test::default();
+
+    controller
+        .set_base_branch(project, &"refs/remotes/origin/master".parse().unwrap())
+        .await
+        .unwrap();
+
+    let branch_id = controller
+        .create_virtual_branch(project, &BranchCreateRequest::default())
+        .await
+        .unwrap();
+    fs::write(repository.path().join("file.txt"), "content").unwrap();
+    let commit_id = controller
+        .create_commit(project, branch_id, "commit", None, false)
+        .await
+        .unwrap();
+
+    // simulate the commit being pruned by removing its loose object
+    let hex = commit_id.to_string();
+    fs::remove_file(
+        repository
+            .path()
+            .join(".git")
+            .join("objects")
+            .join(&hex[..2])
+            .join(&hex[2..]),
+    )
+    .unwrap();
+
+    let dangling = controller.check_repo_linkage(project).await.unwrap();
+    assert_eq!(dangling.len(), 1);
+    assert_eq!(dangling[0].branch_id, Some(branch_id));
+    assert_eq!(dangling[0].kind, LinkageKind::BranchHead);
+    assert_eq!(dangling[0].id, commit_id);
+    assert!(dangling[0].suggestion.contains("reset or delete branch"));
+}
@@ -0,0 +1,150 @@
+use gitbutler_branch_actions::HunkSelector;
+
+use super::*;
+
+fn file_content(changed: &[usize]) -> String {
+    (1..=30)
+        .map(|line| {
+            if changed.contains(&line) {
+                format!("changed {line}\n")
+            } else {
+                format!("line {line}\n")
+            }
+        })
+        .collect()
+}
+
+fn hunk_at(start: u32) -> HunkSelector {
+    HunkSelector {
+        old_start: start,
+        old_lines: 7,
+        new_start: start,
+        new_lines: 7,
+    }
+}
+
+/// Set up a branch with uncommitted changes to lines 5, 15 and 25 of `file.txt`, which makes three hunks.
+async fn branch_with_three_hunks(
+    repository: &TestProject,
+    project: &Project,
+    controller: &VirtualBranchActions,
+) -> gitbutler_branch::BranchId {
+    fs::write(repository.path().join("file.txt"), file_content(&[])).unwrap();
+    repository.commit_all("thirty lines");
+    repository.push();
+
+    controller
+        .set_base_branch(project, &"refs/remotes/origin/master".parse().unwrap())
+        .await
+        .unwrap();
+    let branch_id = controller
+        .create_virtual_branch(project, &BranchCreateRequest::default())
+        .await
+        .unwrap();
+    fs::write(
+        repository.path().join("file.txt"),
+        file_content(&[5, 15, 25]),
+    )
+    .unwrap();
+    branch_id
+}
+
+fn committed_content(repository: &TestProject, commit_id: git2::Oid) -> String {
+    let repo = git2::Repository::open(repository.path()).unwrap();
+    let tree = repo.find_commit(commit_id).unwrap().tree().unwrap();
+    let entry = tree.get_path(path::Path::new("file.txt")).unwrap();
+    let blob = repo.find_blob(entry.id()).unwrap();
+    String::from_utf8(blob.content().to_vec()).unwrap()
+}
+
+#[tokio::test]
+async fn split_three_hunks_into_two_commits() {
+    let Test {
+        repository,
+        project,
+        controller,
+        ..
+    } = &Test::default();
+
+    let branch_id = branch_with_three_hunks(repository, project, controller).await;
+    let path = path::Path::new("file.txt");
+
+    // the last selector is off by a line, but still matches the last hunk
+    let selectors = [hunk_at(2), hunk_at(23)];
+    let selection = controller
+        .preview_hunk_selection(project, branch_id, path, &selectors)
+        .await
+        .unwrap();
+    assert_eq!(
+        selection
+            .selected
+            .iter()
+            .map(|hunk| hunk.new_start)
+            .collect::<Vec<_>>(),
+        vec![2, 22]
+    );
+    assert_eq!(
+        selection
+            .remaining
+            .iter()
+            .map(|hunk| hunk.new_start)
+            .collect::<Vec<_>>(),
+        vec![12]
+    );
+
+    let first = controller
+        .commit_hunks(project, branch_id, path, &selectors, "outer", false)
+        .await
+        .unwrap();
+    assert_eq!(committed_content(repository, first), file_content(&[5, 25]));
+
+    let (branches, _) = controller.list_virtual_branches(project).await.unwrap();
+    assert_eq!(branches[0].commits.len(), 1);
+    assert_eq!(branches[0].files.len(), 1);
+    assert_eq!(branches[0].files[0].hunks.len(), 1);
+    assert_eq!(
+        fs::read_to_string(repository.path().join("file.txt")).unwrap(),
+        file_content(&[5, 15, 25])
+    );
+
+    let second = controller
+        .commit_hunks(project, branch_id, path, &[hunk_at(12)], "middle", false)
+        .await
+        .unwrap();
+    assert_eq!(
+        committed_content(repository, second),
+        file_content(&[5, 15, 25])
+    );
+
+    let (branches, _) = controller.list_virtual_branches(project).await.unwrap();
+    assert_eq!(branches[0].commits.len(), 2);
+    assert!(branches[0].files.is_empty());
+}
+
+#[tokio::test]
+async fn unmatched_selector() {
+    let Test {
+        repository,
+        project,
+        controller,
+        ..
+    } = &Test::default();
+
+    let branch_id = branch_with_three_hunks(repository, project, controller).await;
+
+    let err = controller
+        .commit_hunks(
+            project,
+            branch_id,
+            path::Path::new("file.txt"),
+            &[hunk_at(2), hunk_at(40)],
+            "nothing",
+            false,
+        )
+        .await
+        .unwrap_err();
+    assert!(format!("{err:#}").contains("No change in 'file.txt' matches the hunk at -40,7 +40,7"));
+
+    let (branches, _) = controller.list_virtual_branches(project).await.unwrap();
+    assert!(branches[0].commits.is_empty());
+}
@@ -0,0 +1,113 @@
+use gitbutler_branch_actions::RiskyOperation;
+
+use super::*;
+
+#[tokio::test]
+async fn hard_reset_drops_newer_commits() {
+    let Test {
+        repository,
+        project,
+        controller,
+        ..
+    } = &Test::default();
+
+    controller
+        .set_base_branch(project, &"refs/remotes/origin/master".parse().unwrap())
+        .await
+        .unwrap();
+
+    let branch_id = controller
+        .create_virtual_branch(project, &BranchCreateRequest::default())
+        .await
+        .unwrap();
+    let mut commit_ids = Vec::new();
+    for (file, message) in [("1.txt", "one"), ("2.txt", "two"), ("3.txt", "three")] {
+        fs::write(repository.path().join(file), message).unwrap();
+        commit_ids.push(
+            controller
+                .create_commit(project, branch_id, message, None, false)
+                .await
+                .unwrap(),
+        );
+    }
+
+    let at_risk = controller
+        .commits_at_risk(
+            project,
+            branch_id,
+            RiskyOperation::Reset {
+                target: commit_ids[0],
+            },
+        )
+        .await
+        .unwrap();
+    assert_eq!(
+        at_risk
+            .iter()
+            .map(|commit| (commit.id, commit.summary.as_str()))
+            .collect::<Vec<_>>(),
+        [(commit_ids[2], "three"), (commit_ids[1], "two")]
+    );
+
+    let at_risk = controller
+        .commits_at_risk(project, branch_id, RiskyOperation::Delete)
+        .await
+        .unwrap();
+    assert_eq!(at_risk.len(), 3);
+
+    // nothing is lost when resetting to the head
+    let at_risk = controller
+        .commits_at_risk(
+            project,
+            branch_id,
+            RiskyOperation::Reset {
+                target: commit_ids[2],
+            },
+        )
+        .await
+        .unwrap();
+    assert!(at_risk.is_empty());
+}
+
+#[tokio::test]
+async fn pushed_commits_are_not_at_risk() {
+    let Test {
+        repository,
+        project,
+        controller,
+        ..
+    } = &Test::default();
+
+    controller
+        .set_base_branch(project, &"refs/remotes/origin/master".parse().unwrap())
+        .await
+        .unwrap();
+
+    let branch_id = controller
+        .create_virtual_branch(project, &BranchCreateRequest::default())
+        .await
+        .unwrap();
+    fs::write(repository.path().join("1.txt"), "one").unwrap();
+    controller
+        .create_commit(project, branch_id, "one", None, false)
+        .await
+        .unwrap();
+    controller
+        .push_virtual_branch(project, branch_id, false, None)
+        .await
+        .unwrap();
+    fs::write(repository.path().join("2.txt"), "two").unwrap();
+    let unpushed_commit_id = controller
+        .create_commit(project, branch_id, "two", None, false)
+        .await
+        .unwrap();
+
+    let at_risk = controller
+        .commits_at_risk(project, branch_id, RiskyOperation::Delete)
+        .await
+        .unwrap();
+    assert_eq!(
+        at_risk.iter().map(|commit| commit.id).collect::<Vec<_>>(),
+        [unpushed_commit_id]
+    );
+}
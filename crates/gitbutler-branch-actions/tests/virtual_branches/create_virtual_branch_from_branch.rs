@@ -105,7 +105,7 @@ async fn integration() {
 
     {
         // should mark commits as integrated
-        controller.fetch_from_remotes(project, None).await.unwrap();
+        controller.fetch_from_remotes(project, None, None).await.unwrap();
 
         let branch = controller
             .list_virtual_branches(project)
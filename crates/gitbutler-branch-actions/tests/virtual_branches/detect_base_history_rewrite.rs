@@ -0,0 +1,112 @@
+use gitbutler_branch_actions::BaseHistoryRewrite;
+
+use super::*;
+
+#[tokio::test]
+async fn force_pushed_base_is_detected() {
+    let Test {
+        repository,
+        project,
+        controller,
+        ..
+    } = &Test::default();
+
+    fs::write(repository.path().join("file.txt"), "first").unwrap();
+    let first_commit_oid = repository.commit_all("first");
+    fs::write(repository.path().join("file.txt"), "second").unwrap();
+    let second_commit_oid = repository.commit_all("second");
+    repository.push();
+
+    controller
+        .set_base_branch(project, &"refs/remotes/origin/master".parse().unwrap())
+        .await
+        .unwrap();
+    let branch_id = controller
+        .create_virtual_branch(project, &BranchCreateRequest::default())
+        .await
+        .unwrap();
+    fs::write(repository.path().join("other.txt"), "content").unwrap();
+    controller
+        .create_commit(project, branch_id, "commit", None, false)
+        .await
+        .unwrap();
+    let empty_branch_id = controller
+        .create_virtual_branch(project, &BranchCreateRequest::default())
+        .await
+        .unwrap();
+    assert_eq!(
+        controller
+            .detect_base_history_rewrite(project)
+            .await
+            .unwrap(),
+        None
+    );
+
+    // Upstream replaces the second commit, as if it was force-pushed, and the rewrite is fetched.
+    let repo = git2::Repository::open(repository.path()).unwrap();
+    let first_commit = repo.find_commit(first_commit_oid).unwrap();
+    let signature = git2::Signature::now("test", "test@example.com").unwrap();
+    let rewritten_commit_oid = repo
+        .commit(
+            None,
+            &signature,
+            &signature,
+            "rewritten second",
+            &first_commit.tree().unwrap(),
+            &[&first_commit],
+        )
+        .unwrap();
+    repo.reference(
+        "refs/remotes/origin/master",
+        rewritten_commit_oid,
+        true,
+        "forced-update",
+    )
+    .unwrap();
+
+    let rewrite = controller
+        .detect_base_history_rewrite(project)
+        .await
+        .unwrap()
+        .expect("the rewrite is detected");
+    assert_eq!(
+        rewrite,
+        BaseHistoryRewrite {
+            previous_base: second_commit_oid,
+            suggested_base: rewritten_commit_oid,
+            common_ancestor: Some(first_commit_oid),
+            affected_branches: vec![branch_id],
+        }
+    );
+    assert!(!rewrite.affected_branches.contains(&empty_branch_id));
+}
+
+#[tokio::test]
+async fn base_moving_forward_is_no_rewrite() {
+    let Test {
+        repository,
+        project,
+        controller,
+        ..
+    } = &Test::default();
+
+    fs::write(repository.path().join("file.txt"), "first").unwrap();
+    repository.commit_all("first");
+    repository.push();
+    controller
+        .set_base_branch(project, &"refs/remotes/origin/master".parse().unwrap())
+        .await
+        .unwrap();
+
+    fs::write(repository.path().join("file.txt"), "second").unwrap();
+    repository.commit_all("second");
+    repository.push();
+    repository.fetch();
+    assert_eq!(
+        controller
+            .detect_base_history_rewrite(project)
+            .await
+            .unwrap(),
+        None
+    );
+}
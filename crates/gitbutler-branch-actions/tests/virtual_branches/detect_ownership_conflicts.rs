@@ -0,0 +1,180 @@
+use gitbutler_branch::{BranchId, BranchOwnershipClaims, VirtualBranchesHandle};
+use gitbutler_branch_actions::OwnershipConflict;
+
+use super::*;
+
+#[tokio::test]
+async fn double_owned_hunk_is_detected_and_assigned_to_one_branch() {
+    let Test {
+        repository,
+        project,
+        controller,
+        ..
+    } = &Test::default();
+
+    controller
+        .set_base_branch(project, &"refs/remotes/origin/master".parse().unwrap())
+        .await
+        .unwrap();
+
+    let branch_1_id = controller
+        .create_virtual_branch(project, &BranchCreateRequest::default())
+        .await
+        .unwrap();
+    fs::write(repository.path().join("file.txt"), "content").unwrap();
+    controller.list_virtual_branches(project).await.unwrap();
+    let branch_2_id = controller
+        .create_virtual_branch(project, &BranchCreateRequest::default())
+        .await
+        .unwrap();
+    assert!(controller
+        .detect_ownership_conflicts(project)
+        .await
+        .unwrap()
+        .is_empty());
+
+    // simulate a bug that makes the second branch claim the hunk of the first one as well
+    let vb_state = VirtualBranchesHandle::new(project.gb_dir());
+    let ownership = vb_state.get_branch(branch_1_id).unwrap().ownership;
+    assert_eq!(ownership.claims.len(), 1);
+    let hunk = ownership.claims[0].hunks[0].to_string();
+    let mut branch_2 = vb_state.get_branch(branch_2_id).unwrap();
+    branch_2.ownership = ownership.clone();
+    vb_state.set_branch(branch_2).unwrap();
+
+    let conflicts = controller
+        .detect_ownership_conflicts(project)
+        .await
+        .unwrap();
+    assert_eq!(
+        conflicts,
+        [OwnershipConflict {
+            file_path: "file.txt".into(),
+            hunk: hunk.clone(),
+            branch_ids: vec![branch_1_id, branch_2_id],
+        }]
+    );
+
+    let claim: BranchOwnershipClaims = format!("file.txt:{hunk}").parse().unwrap();
+    controller
+        .resolve_ownership_conflicts(project, branch_2_id, &claim)
+        .await
+        .unwrap();
+
+    assert!(controller
+        .detect_ownership_conflicts(project)
+        .await
+        .unwrap()
+        .is_empty());
+    let owners = [branch_1_id, branch_2_id].map(|id: BranchId| vb_state.get_branch(id).unwrap());
+    assert!(
+        owners[0].ownership.claims.is_empty(),
+        "the hunk was released"
+    );
+    assert_eq!(owners[1].ownership, ownership);
+}
+
+#[tokio::test]
+async fn resolving_for_an_unknown_branch_changes_nothing() {
+    let Test {
+        repository,
+        project,
+        controller,
+        ..
+    } = &Test::default();
+
+    controller
+        .set_base_branch(project, &"refs/remotes/origin/master".parse().unwrap())
+        .await
+        .unwrap();
+
+    let branch_id = controller
+        .create_virtual_branch(project, &BranchCreateRequest::default())
+        .await
+        .unwrap();
+    fs::write(repository.path().join("file.txt"), "content").unwrap();
+    controller.list_virtual_branches(project).await.unwrap();
+
+    let vb_state = VirtualBranchesHandle::new(project.gb_dir());
+    let ownership = vb_state.get_branch(branch_id).unwrap().ownership;
+    assert!(controller
+        .resolve_ownership_conflicts(project, BranchId::generate(), &ownership)
+        .await
+        .is_err());
+    assert_eq!(vb_state.get_branch(branch_id).unwrap().ownership, ownership);
+}
+
+#[tokio::test]
+async fn overlapping_hunks_are_detected_and_released() {
+    let Test {
+        repository,
+        project,
+        controller,
+        ..
+    } = &Test::default();
+
+    controller
+        .set_base_branch(project, &"refs/remotes/origin/master".parse().unwrap())
+        .await
+        .unwrap();
+
+    let branch_1_id = controller
+        .create_virtual_branch(project, &BranchCreateRequest::default())
+        .await
+        .unwrap();
+    fs::write(repository.path().join("file.txt"), "one\ntwo\nthree\n").unwrap();
+    controller.list_virtual_branches(project).await.unwrap();
+    let branch_2_id = controller
+        .create_virtual_branch(project, &BranchCreateRequest::default())
+        .await
+        .unwrap();
+
+    // simulate a bug that makes the second branch claim some of the same lines with a different hunk
+    let vb_state = VirtualBranchesHandle::new(project.gb_dir());
+    let ownership = vb_state.get_branch(branch_1_id).unwrap().ownership;
+    let hunk = ownership.claims[0].hunks[0].to_string();
+    assert!(hunk.starts_with("1-4-"));
+    let mut branch_2 = vb_state.get_branch(branch_2_id).unwrap();
+    branch_2.ownership.put("file.txt:3-6".parse().unwrap());
+    branch_2.ownership.put("file.txt:4-6".parse().unwrap());
+    vb_state.set_branch(branch_2).unwrap();
+
+    let conflicts = controller
+        .detect_ownership_conflicts(project)
+        .await
+        .unwrap();
+    assert_eq!(
+        conflicts,
+        [OwnershipConflict {
+            file_path: "file.txt".into(),
+            hunk: hunk.clone(),
+            branch_ids: vec![branch_1_id, branch_2_id],
+        }],
+        "hunks overlapping each other are a single conflict"
+    );
+
+    let claim: BranchOwnershipClaims = format!("file.txt:{hunk}").parse().unwrap();
+    controller
+        .resolve_ownership_conflicts(project, branch_1_id, &claim)
+        .await
+        .unwrap();
+
+    assert!(controller
+        .detect_ownership_conflicts(project)
+        .await
+        .unwrap()
+        .is_empty());
+    assert_eq!(
+        vb_state.get_branch(branch_1_id).unwrap().ownership,
+        ownership
+    );
+    assert_eq!(
+        vb_state
+            .get_branch(branch_2_id)
+            .unwrap()
+            .ownership
+            .to_string(),
+        "file.txt:4-6\n",
+        "only the overlapping hunk was released"
+    );
+}
@@ -0,0 +1,143 @@
+use gitbutler_branch_actions::{FindingKind, FindingSeverity};
+use gitbutler_oplog::{entry::OperationKind, OplogExt};
+
+use super::*;
+
+async fn project_with_commit(
+    Test {
+        repository,
+        project,
+        controller,
+        ..
+    }: &Test,
+) {
+    controller
+        .set_base_branch(project, &"refs/remotes/origin/master".parse().unwrap())
+        .await
+        .unwrap();
+    let branch_id = controller
+        .create_virtual_branch(project, &BranchCreateRequest::default())
+        .await
+        .unwrap();
+    fs::write(repository.path().join("file.txt"), "content").unwrap();
+    controller
+        .create_commit(project, branch_id, "commit", None, false)
+        .await
+        .unwrap();
+}
+
+fn delete_reference(repository: &TestProject, name: &str) {
+    let repo = git2::Repository::open(repository.path()).unwrap();
+    repo.find_reference(name).unwrap().delete().unwrap();
+}
+
+#[tokio::test]
+async fn healthy() {
+    let test = Test::default();
+    project_with_commit(&test).await;
+
+    let diagnostics = test
+        .controller
+        .diagnose_project(&test.project)
+        .await
+        .unwrap();
+    assert!(diagnostics.is_healthy(), "{diagnostics:?}");
+}
+
+#[tokio::test]
+async fn missing_integration_is_repaired() {
+    let test = Test::default();
+    let Test {
+        repository,
+        project,
+        controller,
+        ..
+    } = &test;
+    project_with_commit(&test).await;
+
+    delete_reference(repository, "refs/heads/gitbutler/integration");
+
+    let diagnostics = controller.diagnose_project(project).await.unwrap();
+    assert_eq!(diagnostics.findings.len(), 1);
+    assert_eq!(
+        diagnostics.findings[0].kind,
+        FindingKind::IntegrationMissing
+    );
+    assert_eq!(diagnostics.findings[0].severity, FindingSeverity::Error);
+    assert!(diagnostics.findings[0].repairable);
+
+    let repair = controller.repair_project(project).await.unwrap();
+    assert_eq!(repair.repaired, diagnostics.findings);
+    assert!(repair.remaining.is_empty());
+
+    assert!(controller
+        .diagnose_project(project)
+        .await
+        .unwrap()
+        .is_healthy());
+    let (branches, _) = controller.list_virtual_branches(project).await.unwrap();
+    assert_eq!(branches.len(), 1);
+    assert_eq!(branches[0].commits.len(), 1);
+}
+
+#[tokio::test]
+async fn unresolvable_upstream_is_reported_but_not_repaired() {
+    let test = Test::default();
+    let Test {
+        repository,
+        project,
+        controller,
+        ..
+    } = &test;
+    project_with_commit(&test).await;
+
+    delete_reference(repository, "refs/remotes/origin/master");
+
+    let diagnostics = controller.diagnose_project(project).await.unwrap();
+    assert_eq!(diagnostics.findings.len(), 1);
+    assert_eq!(
+        diagnostics.findings[0].kind,
+        FindingKind::BaseUpstreamUnresolvable
+    );
+    assert!(!diagnostics.findings[0].repairable);
+
+    let repair = controller.repair_project(project).await.unwrap();
+    assert!(repair.repaired.is_empty());
+    assert_eq!(repair.remaining, diagnostics.findings);
+
+    let snapshots = project.list_snapshots(10, None).unwrap();
+    assert_eq!(
+        snapshots[0].details.as_ref().unwrap().operation,
+        OperationKind::RepairProject
+    );
+}
+
+#[tokio::test]
+async fn unreadable_state_is_reported_as_findings() {
+    let test = Test::default();
+    let Test {
+        project,
+        controller,
+        ..
+    } = &test;
+    project_with_commit(&test).await;
+
+    fs::write(project.gb_dir().join("virtual_branches.toml"), "not toml [").unwrap();
+    fs::write(project.gb_dir().join("operations-log.toml"), "not toml [").unwrap();
+
+    let diagnostics = controller.diagnose_project(project).await.unwrap();
+    let kinds = diagnostics
+        .findings
+        .iter()
+        .map(|finding| finding.kind)
+        .collect::<Vec<_>>();
+    assert!(
+        kinds.contains(&FindingKind::BranchesUnreadable),
+        "{kinds:?}"
+    );
+    assert!(kinds.contains(&FindingKind::OplogUnreadable), "{kinds:?}");
+    assert!(diagnostics
+        .findings
+        .iter()
+        .all(|finding| finding.severity == FindingSeverity::Error && !finding.repairable));
+}
@@ -0,0 +1,124 @@
+use gitbutler_branch::{BranchCreateRequest, BranchId, VirtualBranchesHandle};
+use gitbutler_branch_actions::DuplicateBranch;
+
+use super::*;
+
+#[tokio::test]
+async fn identical_and_subset_branches_are_flagged() {
+    let Test {
+        repository,
+        project,
+        controller,
+        ..
+    } = &Test::default();
+
+    controller
+        .set_base_branch(project, &"refs/remotes/origin/master".parse().unwrap())
+        .await
+        .unwrap();
+
+    let branch_id = controller
+        .create_virtual_branch(project, &BranchCreateRequest::default())
+        .await
+        .unwrap();
+    fs::write(repository.path().join("file.txt"), "one").unwrap();
+    let first_commit_id = controller
+        .create_commit(project, branch_id, "one", None, false)
+        .await
+        .unwrap();
+    fs::write(repository.path().join("file.txt"), "two").unwrap();
+    controller
+        .create_commit(project, branch_id, "two", None, false)
+        .await
+        .unwrap();
+
+    // simulate the aftermath of a mishap by copying the branch, fully and partially
+    let vb_state = VirtualBranchesHandle::new(project.gb_dir());
+    let branch = vb_state.get_branch(branch_id).unwrap();
+    let mut identical = branch.clone();
+    identical.id = BranchId::generate();
+    identical.name = "identical".into();
+    identical.order = branch.order + 1;
+    identical.ownership = Default::default();
+    identical.selected_for_changes = None;
+    vb_state.set_branch(identical.clone()).unwrap();
+    let mut subset = identical.clone();
+    subset.id = BranchId::generate();
+    subset.name = "subset".into();
+    subset.order = branch.order + 2;
+    subset.head = first_commit_id;
+    vb_state.set_branch(subset.clone()).unwrap();
+
+    let duplicates = controller.find_duplicate_branches(project).await.unwrap();
+    assert_eq!(
+        duplicates,
+        vec![
+            DuplicateBranch {
+                branch_id: identical.id,
+                duplicate_of: branch_id,
+                identical: true,
+            },
+            DuplicateBranch {
+                branch_id: subset.id,
+                duplicate_of: branch_id,
+                identical: false,
+            },
+        ]
+    );
+}
+
+#[tokio::test]
+async fn distinct_and_empty_branches_are_not_flagged() {
+    let Test {
+        repository,
+        project,
+        controller,
+        ..
+    } = &Test::default();
+
+    controller
+        .set_base_branch(project, &"refs/remotes/origin/master".parse().unwrap())
+        .await
+        .unwrap();
+
+    let branch_id = controller
+        .create_virtual_branch(project, &BranchCreateRequest::default())
+        .await
+        .unwrap();
+    fs::write(repository.path().join("one.txt"), "one").unwrap();
+    controller
+        .create_commit(project, branch_id, "one", None, false)
+        .await
+        .unwrap();
+
+    let other_branch_id = controller
+        .create_virtual_branch(
+            project,
+            &BranchCreateRequest {
+                selected_for_changes: Some(true),
+                ..Default::default()
+            },
+        )
+        .await
+        .unwrap();
+    fs::write(repository.path().join("two.txt"), "two").unwrap();
+    controller
+        .create_commit(project, other_branch_id, "two", None, false)
+        .await
+        .unwrap();
+
+    controller
+        .create_virtual_branch(project, &BranchCreateRequest::default())
+        .await
+        .unwrap();
+    controller
+        .create_virtual_branch(project, &BranchCreateRequest::default())
+        .await
+        .unwrap();
+
+    assert!(controller
+        .find_duplicate_branches(project)
+        .await
+        .unwrap()
+        .is_empty());
+}
@@ -0,0 +1,51 @@
+use super::*;
+
+#[tokio::test]
+async fn ignored_changes_stay_unassigned() {
+    let Test {
+        repository,
+        project,
+        controller,
+        ..
+    } = &Test::default();
+
+    controller
+        .set_base_branch(project, &"refs/remotes/origin/master".parse().unwrap())
+        .await
+        .unwrap();
+    let branch_id = controller
+        .create_virtual_branch(project, &BranchCreateRequest::default())
+        .await
+        .unwrap();
+
+    fs::write(repository.path().join(".gitbutlerignore"), "target/**\n").unwrap();
+    fs::create_dir_all(repository.path().join("target/debug")).unwrap();
+    fs::write(repository.path().join("target/debug/out.o"), "artifact").unwrap();
+    fs::write(repository.path().join("file.txt"), "content").unwrap();
+
+    let (branches, _) = controller.list_virtual_branches(project).await.unwrap();
+    let branch = branches.iter().find(|b| b.id == branch_id).unwrap();
+    let files: Vec<_> = branch.files.iter().map(|file| file.path.clone()).collect();
+    assert_eq!(
+        files,
+        [PathBuf::from(".gitbutlerignore"), PathBuf::from("file.txt")]
+    );
+
+    let unassigned = controller.list_unassigned_files(project).await.unwrap();
+    assert_eq!(unassigned.len(), 1);
+    assert_eq!(unassigned[0].path, PathBuf::from("target/debug/out.o"));
+
+    // changes to the ignore file apply right away
+    fs::write(repository.path().join(".gitbutlerignore"), "").unwrap();
+    let (branches, _) = controller.list_virtual_branches(project).await.unwrap();
+    let branch = branches.iter().find(|b| b.id == branch_id).unwrap();
+    assert!(branch
+        .files
+        .iter()
+        .any(|file| file.path == PathBuf::from("target/debug/out.o")));
+    assert!(controller
+        .list_unassigned_files(project)
+        .await
+        .unwrap()
+        .is_empty());
+}
@@ -0,0 +1,231 @@
+use gitbutler_branch_actions::LocalBranchImportOptions;
+
+use super::*;
+
+/// Create the local branch `name` with a commit that adds `path`, on top of `parent` or without any parent.
+fn local_branch(
+    repository: &TestProject,
+    name: &str,
+    path: &str,
+    parent: Option<git2::Oid>,
+) -> git2::Oid {
+    let repo = git2::Repository::open(repository.path()).unwrap();
+    let parent = parent.map(|parent| repo.find_commit(parent).unwrap());
+    let parent_tree = parent.as_ref().map(|parent| parent.tree().unwrap());
+    let mut tree = repo.treebuilder(parent_tree.as_ref()).unwrap();
+    tree.insert(path, repo.blob(path.as_bytes()).unwrap(), 0o100644)
+        .unwrap();
+    let tree = repo.find_tree(tree.write().unwrap()).unwrap();
+    let signature = git2::Signature::now("test", "test@example.com").unwrap();
+    let parents: Vec<_> = parent.iter().collect();
+    let commit = repo
+        .commit(None, &signature, &signature, path, &tree, &parents)
+        .unwrap();
+    repo.reference(&format!("refs/heads/{name}"), commit, false, "test")
+        .unwrap();
+    commit
+}
+
+#[tokio::test]
+async fn imports_all_local_branches() {
+    let Test {
+        repository,
+        project,
+        controller,
+        ..
+    } = &Test::default();
+
+    let base = controller
+        .set_base_branch(project, &"refs/remotes/origin/master".parse().unwrap())
+        .await
+        .unwrap();
+    let first = local_branch(repository, "first", "first.txt", Some(base.base_sha));
+    let second = local_branch(repository, "second", "second.txt", Some(base.base_sha));
+
+    let import = controller
+        .import_all_local_branches(project, &LocalBranchImportOptions::default())
+        .await
+        .unwrap();
+    assert_eq!(import.imported.len(), 2);
+    assert_eq!(import.skipped, vec![]);
+
+    let (branches, _) = controller.list_virtual_branches(project).await.unwrap();
+    let mut heads: Vec<_> = branches
+        .iter()
+        .map(|branch| (branch.name.as_str(), branch.head))
+        .collect();
+    heads.sort();
+    assert_eq!(heads, vec![("first", first), ("second", second)]);
+    assert!(repository.path().join("first.txt").exists());
+    assert!(repository.path().join("second.txt").exists());
+}
+
+#[tokio::test]
+async fn skips_branches_without_a_base() {
+    let Test {
+        repository,
+        project,
+        controller,
+        ..
+    } = &Test::default();
+
+    let base = controller
+        .set_base_branch(project, &"refs/remotes/origin/master".parse().unwrap())
+        .await
+        .unwrap();
+    local_branch(repository, "feature", "feature.txt", Some(base.base_sha));
+    local_branch(repository, "orphan", "orphan.txt", None);
+
+    let import = controller
+        .import_all_local_branches(project, &LocalBranchImportOptions::default())
+        .await
+        .unwrap();
+    assert_eq!(import.imported.len(), 1);
+    assert_eq!(import.skipped.len(), 1);
+    assert_eq!(
+        import.skipped[0].name,
+        Refname::from_str("refs/heads/orphan").unwrap()
+    );
+    assert!(import.skipped[0].reason.contains("no history"));
+
+    let (branches, _) = controller.list_virtual_branches(project).await.unwrap();
+    assert_eq!(branches.len(), 1);
+    assert_eq!(branches[0].name, "feature");
+}
+
+#[tokio::test]
+async fn respects_the_options() {
+    let Test {
+        repository,
+        project,
+        controller,
+        ..
+    } = &Test::default();
+
+    let base = controller
+        .set_base_branch(project, &"refs/remotes/origin/master".parse().unwrap())
+        .await
+        .unwrap();
+    local_branch(repository, "wanted", "wanted.txt", Some(base.base_sha));
+    local_branch(repository, "unwanted", "unwanted.txt", Some(base.base_sha));
+    let repo = git2::Repository::open(repository.path()).unwrap();
+    repo.reference("refs/heads/merged", base.base_sha, false, "test")
+        .unwrap();
+
+    let import = controller
+        .import_all_local_branches(
+            project,
+            &LocalBranchImportOptions {
+                exclude: vec!["unwanted".into()],
+                skip_merged: true,
+            },
+        )
+        .await
+        .unwrap();
+    assert_eq!(import.imported.len(), 1);
+    assert_eq!(import.skipped.len(), 1);
+    assert_eq!(
+        import.skipped[0].name,
+        Refname::from_str("refs/heads/merged").unwrap()
+    );
+
+    let (branches, _) = controller.list_virtual_branches(project).await.unwrap();
+    assert_eq!(branches.len(), 1);
+    assert_eq!(branches[0].name, "wanted");
+}
+
+#[tokio::test]
+async fn skips_applied_branches() {
+    let Test {
+        repository,
+        project,
+        controller,
+        ..
+    } = &Test::default();
+
+    let base = controller
+        .set_base_branch(project, &"refs/remotes/origin/master".parse().unwrap())
+        .await
+        .unwrap();
+    local_branch(repository, "feature", "feature.txt", Some(base.base_sha));
+
+    controller
+        .import_all_local_branches(project, &LocalBranchImportOptions::default())
+        .await
+        .unwrap();
+    let import = controller
+        .import_all_local_branches(project, &LocalBranchImportOptions::default())
+        .await
+        .unwrap();
+    assert_eq!(import.imported, vec![]);
+    assert_eq!(import.skipped.len(), 1);
+    assert!(import.skipped[0].reason.contains("applied already"));
+}
+
+#[tokio::test]
+async fn skips_branches_with_more_than_one_merge_base() {
+    let Test {
+        repository,
+        project,
+        controller,
+        ..
+    } = &Test::default();
+
+    // A base that merges two commits, and a branch that merges them the other way around,
+    // which leaves both of them as merge-bases.
+    let repo = git2::Repository::open(repository.path()).unwrap();
+    let root = repo.head().unwrap().peel_to_commit().unwrap().id();
+    let one = local_branch(repository, "one", "one.txt", Some(root));
+    let two = local_branch(repository, "two", "two.txt", Some(root));
+    let signature = git2::Signature::now("test", "test@example.com").unwrap();
+    let merge = |message: &str, first: git2::Oid, second: git2::Oid| {
+        let (first, second) = (
+            repo.find_commit(first).unwrap(),
+            repo.find_commit(second).unwrap(),
+        );
+        let mut index = repo.merge_commits(&first, &second, None).unwrap();
+        let tree = repo.find_tree(index.write_tree_to(&repo).unwrap()).unwrap();
+        repo.commit(
+            None,
+            &signature,
+            &signature,
+            message,
+            &tree,
+            &[&first, &second],
+        )
+        .unwrap()
+    };
+    let base = merge("base", one, two);
+    repository.reset_hard(Some(base));
+    repository.push();
+    let criss_cross = merge("criss-cross", two, one);
+    repo.reference("refs/heads/criss-cross", criss_cross, false, "test")
+        .unwrap();
+    for name in ["one", "two"] {
+        repo.find_reference(&format!("refs/heads/{name}"))
+            .unwrap()
+            .delete()
+            .unwrap();
+    }
+
+    controller
+        .set_base_branch(project, &"refs/remotes/origin/master".parse().unwrap())
+        .await
+        .unwrap();
+    let import = controller
+        .import_all_local_branches(project, &LocalBranchImportOptions::default())
+        .await
+        .unwrap();
+    assert_eq!(import.imported, vec![]);
+    assert_eq!(import.skipped.len(), 1);
+    assert_eq!(
+        import.skipped[0].name,
+        Refname::from_str("refs/heads/criss-cross").unwrap()
+    );
+    assert!(import.skipped[0]
+        .reason
+        .contains("more than one merge-base"));
+
+    let (branches, _) = controller.list_virtual_branches(project).await.unwrap();
+    assert!(branches.is_empty());
+}
@@ -0,0 +1,83 @@
+use super::*;
+
+#[tokio::test]
+async fn is_empty_without_branches() {
+    let Test {
+        project,
+        controller,
+        ..
+    } = &Test::default();
+
+    controller
+        .set_base_branch(project, &"refs/remotes/origin/master".parse().unwrap())
+        .await
+        .unwrap();
+
+    let files = controller.integration_net_diff(project).await.unwrap();
+    assert!(files.is_empty());
+}
+
+#[tokio::test]
+async fn is_the_union_of_non_overlapping_branches() {
+    let Test {
+        repository,
+        project,
+        controller,
+        ..
+    } = &Test::default();
+
+    controller
+        .set_base_branch(project, &"refs/remotes/origin/master".parse().unwrap())
+        .await
+        .unwrap();
+
+    let branch_1_id = controller
+        .create_virtual_branch(project, &BranchCreateRequest::default())
+        .await
+        .unwrap();
+    fs::write(repository.path().join("a.txt"), "a\n").unwrap();
+    let commit_1_id = controller
+        .create_commit(project, branch_1_id, "a", None, false)
+        .await
+        .unwrap();
+
+    let branch_2_id = controller
+        .create_virtual_branch(
+            project,
+            &BranchCreateRequest {
+                selected_for_changes: Some(true),
+                ..Default::default()
+            },
+        )
+        .await
+        .unwrap();
+    fs::write(repository.path().join("b.txt"), "b\n").unwrap();
+    let commit_2_id = controller
+        .create_commit(project, branch_2_id, "b", None, false)
+        .await
+        .unwrap();
+
+    // uncommitted changes are not part of the net diff
+    fs::write(repository.path().join("c.txt"), "c\n").unwrap();
+
+    let mut expected = controller
+        .list_remote_commit_files(project, commit_1_id)
+        .await
+        .unwrap();
+    expected.extend(
+        controller
+            .list_remote_commit_files(project, commit_2_id)
+            .await
+            .unwrap(),
+    );
+
+    let files = controller.integration_net_diff(project).await.unwrap();
+    assert_eq!(
+        files
+            .iter()
+            .map(|file| file.path.clone())
+            .collect::<Vec<_>>(),
+        [PathBuf::from("a.txt"), PathBuf::from("b.txt")]
+    );
+    assert_eq!(files, expected);
+}
@@ -0,0 +1,201 @@
+use std::path::Path;
+
+use gitbutler_branch_actions::DiffSide;
+use gitbutler_error::error::Code;
+
+use super::*;
+
+/// Configure a diff tool that records the paths it's invoked with, along with their contents, in `out`.
+fn configure_stub_tool(repository: &TestProject, out: &Path) {
+    configure_stub_tool_as(repository, out, "diff.tool");
+}
+
+/// Like [`configure_stub_tool()`], but select the tool with the configuration `key`.
+fn configure_stub_tool_as(repository: &TestProject, out: &Path, key: &str) {
+    // The shell of Git for Windows understands forward slashes, but takes backslashes as escapes.
+    let out = out.display().to_string().replace('\\', "/");
+    let cmd = format!(
+        r#"printf '%s\n%s\n' "$LOCAL" "$REMOTE" > '{out}/paths' && cp "$LOCAL" '{out}/local' && cp "$REMOTE" '{out}/remote'"#
+    );
+    let mut config = config(repository);
+    config.set_str(key, "stub").unwrap();
+    config.set_str("difftool.stub.cmd", &cmd).unwrap();
+}
+
+fn config(repository: &TestProject) -> git2::Config {
+    git2::Repository::open(repository.path())
+        .unwrap()
+        .config()
+        .unwrap()
+}
+
+#[tokio::test]
+async fn tool_is_invoked_with_temporary_files() {
+    let Test {
+        repository,
+        project,
+        controller,
+        ..
+    } = &Test::default();
+
+    controller
+        .set_base_branch(project, &"refs/remotes/origin/master".parse().unwrap())
+        .await
+        .unwrap();
+    let out = tempfile::tempdir().unwrap();
+    configure_stub_tool(repository, out.path());
+    fs::write(repository.path().join("file.txt"), "content").unwrap();
+
+    controller
+        .launch_external_diff(project, Path::new("file.txt"), DiffSide::Base)
+        .await
+        .unwrap();
+
+    let paths = fs::read_to_string(out.path().join("paths")).unwrap();
+    let paths: Vec<_> = paths.lines().map(PathBuf::from).collect();
+    assert_eq!(paths.len(), 2);
+    assert_ne!(paths[0], paths[1]);
+    for path in &paths {
+        assert_eq!(path.file_name().unwrap(), "file.txt", "the name is kept");
+        assert!(!path.exists(), "temporary files are removed afterwards");
+    }
+    assert_eq!(
+        fs::read_to_string(out.path().join("local")).unwrap(),
+        "",
+        "the file doesn't exist in the base branch"
+    );
+    assert_eq!(
+        fs::read_to_string(out.path().join("remote")).unwrap(),
+        "content"
+    );
+}
+
+#[tokio::test]
+async fn binary_files_are_refused() {
+    let Test {
+        repository,
+        project,
+        controller,
+        ..
+    } = &Test::default();
+
+    controller
+        .set_base_branch(project, &"refs/remotes/origin/master".parse().unwrap())
+        .await
+        .unwrap();
+    let out = tempfile::tempdir().unwrap();
+    configure_stub_tool(repository, out.path());
+    fs::write(repository.path().join("image.bin"), [0u8, 1, 2]).unwrap();
+
+    let err = controller
+        .launch_external_diff(project, Path::new("image.bin"), DiffSide::Head)
+        .await
+        .unwrap_err();
+    assert!(format!("{err:#}").contains("Binary files"));
+    assert!(!out.path().join("paths").exists(), "the tool isn't invoked");
+}
+
+#[tokio::test]
+async fn merge_tool_is_used_without_diff_tool() {
+    let Test {
+        repository,
+        project,
+        controller,
+        ..
+    } = &Test::default();
+
+    controller
+        .set_base_branch(project, &"refs/remotes/origin/master".parse().unwrap())
+        .await
+        .unwrap();
+    let out = tempfile::tempdir().unwrap();
+    configure_stub_tool_as(repository, out.path(), "merge.tool");
+    fs::write(repository.path().join("file.txt"), "content").unwrap();
+
+    controller
+        .launch_external_diff(project, Path::new("file.txt"), DiffSide::Head)
+        .await
+        .unwrap();
+
+    assert_eq!(
+        fs::read_to_string(out.path().join("remote")).unwrap(),
+        "content"
+    );
+}
+
+#[tokio::test]
+async fn paths_outside_of_the_worktree_are_refused() {
+    let Test {
+        repository,
+        project,
+        controller,
+        ..
+    } = &Test::default();
+
+    controller
+        .set_base_branch(project, &"refs/remotes/origin/master".parse().unwrap())
+        .await
+        .unwrap();
+    let out = tempfile::tempdir().unwrap();
+    configure_stub_tool(repository, out.path());
+    let outside = out.path().join("outside.txt");
+    fs::write(&outside, "secret").unwrap();
+
+    for path in [Path::new("../outside.txt"), outside.as_path()] {
+        let err = controller
+            .launch_external_diff(project, path, DiffSide::Head)
+            .await
+            .unwrap_err();
+        assert_eq!(
+            err.downcast_ref::<Code>(),
+            Some(&Code::Validation),
+            "{path:?} is refused"
+        );
+    }
+    assert!(!out.path().join("paths").exists(), "the tool isn't invoked");
+}
+
+#[cfg(unix)]
+#[tokio::test]
+async fn builtin_tools_run_the_program_at_their_configured_path() {
+    use std::os::unix::fs::PermissionsExt;
+
+    let Test {
+        repository,
+        project,
+        controller,
+        ..
+    } = &Test::default();
+
+    controller
+        .set_base_branch(project, &"refs/remotes/origin/master".parse().unwrap())
+        .await
+        .unwrap();
+    let out = tempfile::tempdir().unwrap();
+    let program = out.path().join("fake-meld");
+    fs::write(
+        &program,
+        format!(
+            "#!/bin/sh\nprintf '%s\\n' \"$@\" > '{}/args'\n",
+            out.path().display()
+        ),
+    )
+    .unwrap();
+    fs::set_permissions(&program, fs::Permissions::from_mode(0o755)).unwrap();
+    let mut config = config(repository);
+    config.set_str("diff.tool", "meld").unwrap();
+    config
+        .set_str("difftool.meld.path", program.to_str().unwrap())
+        .unwrap();
+    fs::write(repository.path().join("file.txt"), "content").unwrap();
+
+    controller
+        .launch_external_diff(project, Path::new("file.txt"), DiffSide::Head)
+        .await
+        .unwrap();
+
+    let args = fs::read_to_string(out.path().join("args")).unwrap();
+    let args: Vec<_> = args.lines().map(PathBuf::from).collect();
+    assert_eq!(args.len(), 2, "meld is passed both files and nothing else");
+    assert!(args.iter().all(|arg| arg.ends_with("file.txt")));
+}
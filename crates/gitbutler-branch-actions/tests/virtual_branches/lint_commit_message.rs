@@ -0,0 +1,103 @@
+use gitbutler_branch_actions::CommitLintViolation;
+
+use super::*;
+
+async fn project_with_rules(test: &Test) -> Project {
+    let project = test
+        .projects
+        .update(&projects::UpdateRequest {
+            id: test.project_id,
+            commit_lint_rules: Some(projects::CommitLintRules {
+                max_subject_length: Some(20),
+                required_types: vec!["feat".into(), "fix".into()],
+                body_wrap: Some(12),
+                fix_wrapping: true,
+            }),
+            ..Default::default()
+        })
+        .await
+        .unwrap();
+    test.controller
+        .set_base_branch(&project, &"refs/remotes/origin/master".parse().unwrap())
+        .await
+        .unwrap();
+    project
+}
+
+#[tokio::test]
+async fn subject_too_long_is_rejected() {
+    let test = Test::default();
+    let project = &project_with_rules(&test).await;
+    let controller = &test.controller;
+
+    let message = "feat: a subject that is far too long";
+    let report = controller
+        .lint_commit_message(project, message)
+        .await
+        .unwrap();
+    assert_eq!(
+        report.violations,
+        [CommitLintViolation::SubjectTooLong {
+            length: 36,
+            max: 20
+        }]
+    );
+
+    let branch_id = controller
+        .create_virtual_branch(project, &BranchCreateRequest::default())
+        .await
+        .unwrap();
+    fs::write(test.repository.path().join("file.txt"), "content").unwrap();
+    let err = controller
+        .create_commit(project, branch_id, message, None, false)
+        .await
+        .unwrap_err();
+    assert!(format!("{err:#}").contains("subject is 36 characters long"));
+    let (branches, _) = controller.list_virtual_branches(project).await.unwrap();
+    assert!(branches[0].commits.is_empty(), "nothing was committed");
+}
+
+#[tokio::test]
+async fn conforming_message_is_committed_with_wrapped_body() {
+    let test = Test::default();
+    let project = &project_with_rules(&test).await;
+    let controller = &test.controller;
+
+    let message = "fix(ui): a button\n\nthe body is wrapped";
+    let report = controller
+        .lint_commit_message(project, message)
+        .await
+        .unwrap();
+    assert!(report.is_ok());
+    assert_eq!(report.message, "fix(ui): a button\n\nthe body is\nwrapped");
+
+    let branch_id = controller
+        .create_virtual_branch(project, &BranchCreateRequest::default())
+        .await
+        .unwrap();
+    fs::write(test.repository.path().join("file.txt"), "content").unwrap();
+    let commit_id = controller
+        .create_commit(project, branch_id, message, None, false)
+        .await
+        .unwrap();
+    let commit = test.repository.find_commit(commit_id).unwrap();
+    assert_eq!(commit.message(), Some(report.message.as_str()));
+}
+
+#[tokio::test]
+async fn missing_type_is_reported() {
+    let test = Test::default();
+    let project = &project_with_rules(&test).await;
+
+    let report = test
+        .controller
+        .lint_commit_message(project, "chore: tidy")
+        .await
+        .unwrap();
+    assert_eq!(
+        report.violations,
+        [CommitLintViolation::MissingType {
+            allowed: vec!["feat".into(), "fix".into()]
+        }]
+    );
+}
@@ -0,0 +1,165 @@
+use gitbutler_branch::{BranchCreateRequest, BranchId};
+use gitbutler_error::error::Code;
+use gitbutler_reference::ReferenceName;
+
+use super::*;
+
+/// Create a branch with a commit in `file_name` and unapply it, returning its reference.
+async fn unapplied_branch(
+    repository: &TestProject,
+    project: &Project,
+    controller: &VirtualBranchActions,
+    file_name: &str,
+) -> ReferenceName {
+    let branch_id = controller
+        .create_virtual_branch(project, &BranchCreateRequest::default())
+        .await
+        .unwrap();
+    fs::write(repository.path().join(file_name), "content").unwrap();
+    controller
+        .create_commit(project, branch_id, file_name, None, false)
+        .await
+        .unwrap();
+    controller
+        .convert_to_real_branch(project, branch_id, Default::default())
+        .await
+        .unwrap()
+}
+
+async fn applied_branch(
+    project: &Project,
+    controller: &VirtualBranchActions,
+    name: &str,
+) -> BranchId {
+    controller
+        .create_virtual_branch(
+            project,
+            &BranchCreateRequest {
+                name: Some(name.to_owned()),
+                ..Default::default()
+            },
+        )
+        .await
+        .unwrap()
+}
+
+#[tokio::test]
+async fn limit_is_enforced_and_unapplying_frees_a_slot() {
+    let Test {
+        repository,
+        project_id,
+        controller,
+        projects,
+        ..
+    } = &Test::default();
+
+    let project = &projects
+        .update(&projects::UpdateRequest {
+            id: *project_id,
+            max_applied_branches: Some(1),
+            ..Default::default()
+        })
+        .await
+        .unwrap();
+
+    controller
+        .set_base_branch(project, &"refs/remotes/origin/master".parse().unwrap())
+        .await
+        .unwrap();
+
+    let unapplied = unapplied_branch(repository, project, controller, "file.txt").await;
+    let applied_id = applied_branch(project, controller, "applied").await;
+
+    let err = controller
+        .create_virtual_branch_from_branch(project, &Refname::from_str(&unapplied).unwrap())
+        .await
+        .unwrap_err();
+    assert!(
+        format!("{err:#}").contains("applied already: applied"),
+        "the error lists the applied branches: {err:#}"
+    );
+    let (branches, _) = controller.list_virtual_branches(project).await.unwrap();
+    assert_eq!(branches.len(), 1, "nothing was applied");
+
+    controller
+        .convert_to_real_branch(project, applied_id, Default::default())
+        .await
+        .unwrap();
+    controller
+        .create_virtual_branch_from_branch(project, &Refname::from_str(&unapplied).unwrap())
+        .await
+        .unwrap();
+    let (branches, _) = controller.list_virtual_branches(project).await.unwrap();
+    assert_eq!(branches.len(), 1);
+    assert!(repository.path().join("file.txt").exists());
+}
+
+#[tokio::test]
+async fn limit_applies_to_new_branches() {
+    let Test {
+        project_id,
+        controller,
+        projects,
+        ..
+    } = &Test::default();
+
+    let project = &projects
+        .update(&projects::UpdateRequest {
+            id: *project_id,
+            max_applied_branches: Some(1),
+            ..Default::default()
+        })
+        .await
+        .unwrap();
+
+    controller
+        .set_base_branch(project, &"refs/remotes/origin/master".parse().unwrap())
+        .await
+        .unwrap();
+
+    applied_branch(project, controller, "applied").await;
+    let err = controller
+        .create_virtual_branch(
+            project,
+            &BranchCreateRequest {
+                name: Some("new".to_owned()),
+                ..Default::default()
+            },
+        )
+        .await
+        .unwrap_err();
+    assert_eq!(err.downcast_ref::<Code>(), Some(&Code::Validation));
+    assert!(
+        format!("{err:#}").contains("cannot apply new"),
+        "the error names the new branch: {err:#}"
+    );
+    let (branches, _) = controller.list_virtual_branches(project).await.unwrap();
+    assert_eq!(branches.len(), 1, "no branch was created");
+}
+
+#[tokio::test]
+async fn zero_means_unlimited() {
+    let Test {
+        repository,
+        project,
+        controller,
+        ..
+    } = &Test::default();
+    assert_eq!(project.max_applied_branches, 0);
+
+    controller
+        .set_base_branch(project, &"refs/remotes/origin/master".parse().unwrap())
+        .await
+        .unwrap();
+
+    let unapplied = unapplied_branch(repository, project, controller, "file.txt").await;
+    applied_branch(project, controller, "one").await;
+    applied_branch(project, controller, "two").await;
+
+    controller
+        .create_virtual_branch_from_branch(project, &Refname::from_str(&unapplied).unwrap())
+        .await
+        .unwrap();
+    let (branches, _) = controller.list_virtual_branches(project).await.unwrap();
+    assert_eq!(branches.len(), 3);
+}
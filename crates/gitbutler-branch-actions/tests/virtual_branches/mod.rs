@@ -57,29 +57,67 @@ impl Test {
     }
 }
 
+mod adopt_orphan_commits;
 mod amend;
+mod apply_blast_radius;
+mod apply_stack;
 mod apply_virtual_branch;
+mod auto_update_base_branch;
+mod autodetect_base_branch;
+mod base_merge_base;
+mod branch_changed_files;
+mod can_fast_forward;
+mod can_unapply;
+mod change_id_of;
+mod check_repo_linkage;
+mod commit_hunks;
+mod commits_at_risk;
 mod convert_to_real_branch;
 mod create_commit;
 mod create_virtual_branch_from_branch;
 mod delete_virtual_branch;
+mod detect_base_history_rewrite;
+mod detect_ownership_conflicts;
+mod diagnose_project;
+mod find_duplicate_branches;
+mod gitbutler_ignore;
+mod import_all_local_branches;
 mod init;
 mod insert_blank_commit;
+mod integration_net_diff;
+#[cfg(unix)]
+mod launch_external_diff;
+mod lint_commit_message;
+mod max_applied_branches;
+mod move_changes;
 mod move_commit_file;
 mod move_commit_to_vbranch;
 mod oplog;
+mod read_only;
+mod reconcile_ownership;
 mod references;
 mod reorder_commit;
+mod reorder_virtual_branches;
+mod reparent_commit;
 mod reset_virtual_branch;
+mod resolve_conflict;
 mod selected_for_changes;
+mod set_assignment_mode;
 mod set_base_branch;
+mod set_branch_description;
+mod set_branch_parent;
+mod show_file_at_commit;
 mod squash;
+mod squash_commits;
+mod subset_net_diff;
 mod unapply_ownership;
 mod undo_commit;
 mod update_base_branch;
 mod update_commit_message;
 mod upstream;
 mod verify_branch;
+mod virtual_blame;
+mod workspaces;
 
 #[tokio::test]
 async fn resolve_conflict_flow() {
@@ -0,0 +1,246 @@
+use gitbutler_branch::{BranchId, OwnershipClaim, VirtualBranchesHandle};
+use gitbutler_branch_actions::VirtualBranch;
+use gitbutler_diff::Hunk;
+use gitbutler_error::error::Code;
+
+use super::*;
+
+fn file_content(changed: &[usize]) -> String {
+    (1..=30)
+        .map(|line| {
+            if changed.contains(&line) {
+                format!("changed {line}\n")
+            } else {
+                format!("line {line}\n")
+            }
+        })
+        .collect()
+}
+
+async fn branch(
+    project: &Project,
+    controller: &VirtualBranchActions,
+    branch_id: BranchId,
+) -> VirtualBranch {
+    controller
+        .list_virtual_branches(project)
+        .await
+        .unwrap()
+        .0
+        .into_iter()
+        .find(|b| b.id == branch_id)
+        .unwrap()
+}
+
+#[tokio::test]
+async fn whole_file() {
+    let Test {
+        repository,
+        project,
+        controller,
+        ..
+    } = &Test::default();
+
+    controller
+        .set_base_branch(project, &"refs/remotes/origin/master".parse().unwrap())
+        .await
+        .unwrap();
+    let branch_1_id = controller
+        .create_virtual_branch(project, &BranchCreateRequest::default())
+        .await
+        .unwrap();
+    fs::write(repository.path().join("one.txt"), "one").unwrap();
+    fs::write(repository.path().join("two.txt"), "two").unwrap();
+    controller.list_virtual_branches(project).await.unwrap();
+    let branch_2_id = controller
+        .create_virtual_branch(project, &BranchCreateRequest::default())
+        .await
+        .unwrap();
+
+    controller
+        .move_changes(
+            project,
+            branch_1_id,
+            branch_2_id,
+            &[OwnershipClaim {
+                file_path: "two.txt".into(),
+                hunks: Vec::new(),
+            }],
+        )
+        .await
+        .unwrap();
+
+    let branch_1 = branch(project, controller, branch_1_id).await;
+    let branch_2 = branch(project, controller, branch_2_id).await;
+    assert_eq!(branch_1.files.len(), 1);
+    assert_eq!(branch_1.files[0].path.display().to_string(), "one.txt");
+    assert_eq!(branch_2.files.len(), 1);
+    assert_eq!(branch_2.files[0].path.display().to_string(), "two.txt");
+    assert_eq!(
+        fs::read_to_string(repository.path().join("two.txt")).unwrap(),
+        "two"
+    );
+}
+
+#[tokio::test]
+async fn single_hunk() {
+    let Test {
+        repository,
+        project,
+        controller,
+        ..
+    } = &Test::default();
+
+    fs::write(repository.path().join("file.txt"), file_content(&[])).unwrap();
+    repository.commit_all("thirty lines");
+    repository.push();
+
+    controller
+        .set_base_branch(project, &"refs/remotes/origin/master".parse().unwrap())
+        .await
+        .unwrap();
+    let branch_1_id = controller
+        .create_virtual_branch(project, &BranchCreateRequest::default())
+        .await
+        .unwrap();
+    fs::write(repository.path().join("file.txt"), file_content(&[5, 25])).unwrap();
+    let hunks = branch(project, controller, branch_1_id).await.files[0]
+        .hunks
+        .clone();
+    assert_eq!(hunks.len(), 2);
+    let branch_2_id = controller
+        .create_virtual_branch(project, &BranchCreateRequest::default())
+        .await
+        .unwrap();
+
+    let moved = hunks.iter().max_by_key(|hunk| hunk.start).unwrap();
+    controller
+        .move_changes(
+            project,
+            branch_1_id,
+            branch_2_id,
+            &[OwnershipClaim {
+                file_path: "file.txt".into(),
+                hunks: vec![Hunk::new(moved.start, moved.end, Some(moved.hash)).unwrap()],
+            }],
+        )
+        .await
+        .unwrap();
+
+    let branch_1 = branch(project, controller, branch_1_id).await;
+    let branch_2 = branch(project, controller, branch_2_id).await;
+    assert_eq!(branch_1.files[0].hunks.len(), 1);
+    assert!(branch_1.files[0].hunks[0].start < moved.start);
+    assert_eq!(branch_2.files[0].hunks.len(), 1);
+    assert_eq!(branch_2.files[0].hunks[0].start, moved.start);
+    assert_eq!(
+        fs::read_to_string(repository.path().join("file.txt")).unwrap(),
+        file_content(&[5, 25])
+    );
+}
+
+#[tokio::test]
+async fn overlapping_ownership_is_reported() {
+    let Test {
+        repository,
+        project,
+        controller,
+        ..
+    } = &Test::default();
+
+    controller
+        .set_base_branch(project, &"refs/remotes/origin/master".parse().unwrap())
+        .await
+        .unwrap();
+    let branch_1_id = controller
+        .create_virtual_branch(project, &BranchCreateRequest::default())
+        .await
+        .unwrap();
+    fs::write(repository.path().join("file.txt"), "content").unwrap();
+    controller.list_virtual_branches(project).await.unwrap();
+    let branch_2_id = controller
+        .create_virtual_branch(project, &BranchCreateRequest::default())
+        .await
+        .unwrap();
+
+    // simulate the second branch owning the same lines of the file already
+    let vb_state = VirtualBranchesHandle::new(project.gb_dir());
+    let mut branch_2 = vb_state.get_branch(branch_2_id).unwrap();
+    branch_2.ownership.put("file.txt:1-1".parse().unwrap());
+    vb_state.set_branch(branch_2).unwrap();
+
+    let err = controller
+        .move_changes(
+            project,
+            branch_1_id,
+            branch_2_id,
+            &[OwnershipClaim {
+                file_path: "file.txt".into(),
+                hunks: Vec::new(),
+            }],
+        )
+        .await
+        .unwrap_err();
+    assert_eq!(err.downcast_ref::<Code>(), Some(&Code::Validation));
+    assert!(format!("{err:#}").contains("already owns the lines of 'file.txt' at 1-1"));
+    let ownership = vb_state.get_branch(branch_1_id).unwrap().ownership;
+    assert_eq!(ownership.claims.len(), 1);
+}
+
+#[tokio::test]
+async fn hunks_keep_their_hash_and_may_be_next_to_owned_lines() {
+    let Test {
+        repository,
+        project,
+        controller,
+        ..
+    } = &Test::default();
+
+    fs::write(repository.path().join("file.txt"), file_content(&[])).unwrap();
+    repository.commit_all("thirty lines");
+    repository.push();
+
+    controller
+        .set_base_branch(project, &"refs/remotes/origin/master".parse().unwrap())
+        .await
+        .unwrap();
+    let branch_1_id = controller
+        .create_virtual_branch(project, &BranchCreateRequest::default())
+        .await
+        .unwrap();
+    fs::write(repository.path().join("file.txt"), file_content(&[25])).unwrap();
+    let moved = branch(project, controller, branch_1_id).await.files[0].hunks[0].clone();
+    let branch_2_id = controller
+        .create_virtual_branch(project, &BranchCreateRequest::default())
+        .await
+        .unwrap();
+
+    // the second branch owns the lines right before the moved hunk
+    let vb_state = VirtualBranchesHandle::new(project.gb_dir());
+    let mut branch_2 = vb_state.get_branch(branch_2_id).unwrap();
+    branch_2
+        .ownership
+        .put(format!("file.txt:1-{}", moved.start).parse().unwrap());
+    vb_state.set_branch(branch_2).unwrap();
+
+    controller
+        .move_changes(
+            project,
+            branch_1_id,
+            branch_2_id,
+            &[OwnershipClaim {
+                file_path: "file.txt".into(),
+                hunks: vec![Hunk::new(moved.start, moved.end, None).unwrap()],
+            }],
+        )
+        .await
+        .unwrap();
+
+    let ownership = vb_state.get_branch(branch_2_id).unwrap().ownership;
+    let hunk = ownership.claims[0]
+        .hunks
+        .iter()
+        .find(|hunk| hunk.start == moved.start)
+        .unwrap();
+    assert_eq!(hunk.hash, Some(moved.hash), "the owned hunk was moved");
+}
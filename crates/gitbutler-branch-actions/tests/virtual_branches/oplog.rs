@@ -1,6 +1,10 @@
 use super::*;
 use gitbutler_branch::{BranchCreateRequest, VirtualBranchesHandle};
-use gitbutler_oplog::OplogExt;
+use gitbutler_oplog::{
+    entry::{FileChangeKind, OperationKind, RestoreResolution, SnapshotDetails},
+    OplogExt,
+};
+use gitbutler_project::{RetentionPolicy, SnapshotStorage};
 use itertools::Itertools;
 use std::io::Write;
 use std::path::Path;
@@ -369,6 +373,244 @@ async fn restores_gitbutler_integration() -> anyhow::Result<()> {
     Ok(())
 }
 
+#[tokio::test]
+async fn restores_stash_if_enabled() -> anyhow::Result<()> {
+    let Test {
+        repository,
+        controller,
+        project_id,
+        projects,
+        ..
+    } = &Test::default();
+
+    let project = &projects
+        .update(&projects::UpdateRequest {
+            id: *project_id,
+            snapshot_stash: Some(true),
+            ..Default::default()
+        })
+        .await?;
+
+    controller
+        .set_base_branch(project, &"refs/remotes/origin/master".parse()?)
+        .await?;
+
+    let mut repo = git2::Repository::open(&project.path)?;
+    let signature = git2::Signature::now("test", "test@example.com")?;
+    for name in ["one", "two"] {
+        fs::write(repository.path().join(format!("{name}.txt")), name)?;
+        repo.stash_save(&signature, name, Some(git2::StashFlags::INCLUDE_UNTRACKED))?;
+    }
+    let stash_at_snapshot = stash_list(&mut repo)?;
+    assert_eq!(stash_at_snapshot.len(), 2);
+
+    let snapshot_id = project
+        .create_snapshot(
+            SnapshotDetails::new(OperationKind::Unknown),
+            project.exclusive_worktree_access().write_permission(),
+        )?
+        .expect("a snapshot is created");
+
+    repo.stash_drop(0)?;
+    fs::write(repository.path().join("three.txt"), "three")?;
+    repo.stash_save(
+        &signature,
+        "three",
+        Some(git2::StashFlags::INCLUDE_UNTRACKED),
+    )?;
+    assert_ne!(stash_list(&mut repo)?, stash_at_snapshot);
+
+    project.restore_snapshot(snapshot_id)?;
+    assert_eq!(
+        stash_list(&mut repo)?,
+        stash_at_snapshot,
+        "the stash is exactly what it was when the snapshot was taken"
+    );
+
+    repo.stash_pop(0, None)?;
+    assert_eq!(
+        fs::read_to_string(repository.path().join("two.txt"))?,
+        "two"
+    );
+    Ok(())
+}
+
+#[tokio::test]
+async fn restore_clears_stash_that_was_empty_at_snapshot() -> anyhow::Result<()> {
+    let Test {
+        repository,
+        controller,
+        project_id,
+        projects,
+        ..
+    } = &Test::default();
+
+    let project = &projects
+        .update(&projects::UpdateRequest {
+            id: *project_id,
+            snapshot_stash: Some(true),
+            ..Default::default()
+        })
+        .await?;
+
+    controller
+        .set_base_branch(project, &"refs/remotes/origin/master".parse()?)
+        .await?;
+
+    let snapshot_id = project
+        .create_snapshot(
+            SnapshotDetails::new(OperationKind::Unknown),
+            project.exclusive_worktree_access().write_permission(),
+        )?
+        .expect("a snapshot is created");
+
+    let mut repo = git2::Repository::open(&project.path)?;
+    let signature = git2::Signature::now("test", "test@example.com")?;
+    fs::write(repository.path().join("one.txt"), "one")?;
+    repo.stash_save(&signature, "one", Some(git2::StashFlags::INCLUDE_UNTRACKED))?;
+    assert_eq!(stash_list(&mut repo)?.len(), 1);
+
+    project.restore_snapshot(snapshot_id)?;
+    assert!(stash_list(&mut repo)?.is_empty());
+    Ok(())
+}
+
+#[tokio::test]
+async fn stash_is_left_alone_if_not_enabled() -> anyhow::Result<()> {
+    let Test {
+        repository,
+        controller,
+        project,
+        ..
+    } = &Test::default();
+
+    controller
+        .set_base_branch(project, &"refs/remotes/origin/master".parse()?)
+        .await?;
+
+    let snapshot_id = project
+        .create_snapshot(
+            SnapshotDetails::new(OperationKind::Unknown),
+            project.exclusive_worktree_access().write_permission(),
+        )?
+        .expect("a snapshot is created");
+
+    let mut repo = git2::Repository::open(&project.path)?;
+    let signature = git2::Signature::now("test", "test@example.com")?;
+    fs::write(repository.path().join("one.txt"), "one")?;
+    repo.stash_save(&signature, "one", Some(git2::StashFlags::INCLUDE_UNTRACKED))?;
+    let stash = stash_list(&mut repo)?;
+
+    project.restore_snapshot(snapshot_id)?;
+    assert_eq!(stash_list(&mut repo)?, stash);
+    Ok(())
+}
+
+fn stash_list(repo: &mut git2::Repository) -> anyhow::Result<Vec<(String, git2::Oid)>> {
+    let mut stashes = Vec::new();
+    repo.stash_foreach(|_index, message, id| {
+        stashes.push((message.to_owned(), *id));
+        true
+    })?;
+    Ok(stashes)
+}
+
+#[tokio::test]
+async fn snapshot_triggers_match_changed_paths() -> anyhow::Result<()> {
+    let Test {
+        project_id,
+        projects,
+        ..
+    } = &Test::default();
+
+    let project = &projects
+        .update(&projects::UpdateRequest {
+            id: *project_id,
+            snapshot_triggers: Some(vec!["Cargo.lock".into(), "migrations/*.sql".into()]),
+            ..Default::default()
+        })
+        .await?;
+
+    let paths = |paths: &[&str]| paths.iter().map(PathBuf::from).collect::<Vec<_>>();
+    assert_eq!(project.snapshot_trigger(&paths(&["src/main.rs"])), None);
+    assert_eq!(
+        project.snapshot_trigger(&paths(&["src/main.rs", "Cargo.lock"])),
+        Some(Path::new("Cargo.lock"))
+    );
+    assert_eq!(
+        project.snapshot_trigger(&paths(&["crates/a/Cargo.lock"])),
+        Some(Path::new("crates/a/Cargo.lock")),
+        "patterns without a slash match the file name anywhere"
+    );
+    assert_eq!(
+        project.snapshot_trigger(&paths(&["migrations/001.sql"])),
+        Some(Path::new("migrations/001.sql"))
+    );
+    assert_eq!(project.snapshot_trigger(&paths(&["other/001.sql"])), None);
+    Ok(())
+}
+
+#[tokio::test]
+async fn deleted_branches_are_recoverable_until_pruned() -> anyhow::Result<()> {
+    let Test {
+        repository,
+        controller,
+        project,
+        ..
+    } = &Test::default();
+
+    controller
+        .set_base_branch(project, &"refs/remotes/origin/master".parse()?)
+        .await?;
+
+    let branch_id = controller
+        .create_virtual_branch(
+            project,
+            &BranchCreateRequest {
+                name: Some("recoverable".into()),
+                ..Default::default()
+            },
+        )
+        .await?;
+    fs::write(repository.path().join("file.txt"), "content")?;
+    let head = controller
+        .create_commit(project, branch_id, "commit one", None, false)
+        .await?;
+
+    assert!(
+        project
+            .list_recoverable_branches(Duration::from_secs(60))?
+            .is_empty(),
+        "nothing was deleted yet"
+    );
+
+    controller.delete_virtual_branch(project, branch_id).await?;
+
+    let recoverable = project.list_recoverable_branches(Duration::from_secs(60))?;
+    assert_eq!(recoverable.len(), 1);
+    assert_eq!(recoverable[0].id, branch_id);
+    assert_eq!(recoverable[0].name, "recoverable");
+    assert_eq!(recoverable[0].head, head);
+
+    assert!(
+        project
+            .list_recoverable_branches(Duration::ZERO)?
+            .is_empty(),
+        "deletions older than the retention window are pruned"
+    );
+
+    project.restore_snapshot(recoverable[0].snapshot_id)?;
+    let (branches, _) = controller.list_virtual_branches(project).await?;
+    assert!(branches.iter().any(|branch| branch.id == branch_id));
+    assert!(
+        project
+            .list_recoverable_branches(Duration::from_secs(60))?
+            .is_empty(),
+        "recovered branches are back in the workspace"
+    );
+    Ok(())
+}
+
 // test operations-log.toml head is not a commit
 #[tokio::test]
 async fn head_corrupt_is_recreated_automatically() {
@@ -415,3 +657,712 @@ async fn head_corrupt_is_recreated_automatically() {
         "it should have just reset the oplog head, so only 1, not 2"
     );
 }
+
+#[tokio::test]
+async fn repair_oplog_chain_linearizes_and_drops_duplicates() -> anyhow::Result<()> {
+    let Test {
+        repository,
+        controller,
+        project,
+        ..
+    } = &Test::default();
+
+    controller
+        .set_base_branch(project, &"refs/remotes/origin/master".parse()?)
+        .await?;
+    let mut snapshot_ids = vec![project.oplog_head()?.expect("setting the base snapshots")];
+    for round in 0..2 {
+        fs::write(
+            repository.path().join(format!("file{round}.txt")),
+            "content",
+        )?;
+        snapshot_ids.push(
+            project
+                .create_snapshot(
+                    SnapshotDetails::new(OperationKind::FileChanges),
+                    project.exclusive_worktree_access().write_permission(),
+                )?
+                .expect("snapshots are enabled"),
+        );
+    }
+
+    // Corrupt the chain with a duplicate of the last snapshot, and a snapshot merging it with the first one.
+    let repo = git2::Repository::open(repository.path())?;
+    let last = repo.find_commit(snapshot_ids[2])?;
+    let duplicate = repo.find_commit(repo.commit(
+        None,
+        &last.author(),
+        &last.committer(),
+        last.message().unwrap(),
+        &last.tree()?,
+        &[&last],
+    )?)?;
+    fs::write(repository.path().join("file2.txt"), "content")?;
+    let merge_tree =
+        project.prepare_snapshot(project.exclusive_worktree_access().read_permission())?;
+    let merge = repo.commit(
+        None,
+        &last.author(),
+        &last.committer(),
+        &SnapshotDetails::new(OperationKind::FileChanges).to_string(),
+        &repo.find_tree(merge_tree)?,
+        &[&duplicate, &repo.find_commit(snapshot_ids[0])?],
+    )?;
+    let oplog_path = repository.path().join(".git/gitbutler/operations-log.toml");
+    fs::write(&oplog_path, format!("head_sha = \"{merge}\""))?;
+    assert!(
+        project.list_snapshots(10, None)?.is_empty(),
+        "the merge stops the traversal right away"
+    );
+
+    let repair =
+        project.repair_oplog_chain(project.exclusive_worktree_access().write_permission())?;
+    assert_eq!(repair.previous_head, Some(merge));
+    assert_eq!(repair.duplicates_removed, 1);
+    assert_eq!(repair.merges_linearized, 1);
+    assert_eq!(repair.non_snapshots_removed, 0);
+    assert_eq!(repair.head, project.oplog_head()?);
+    assert!(
+        fs::read_to_string(oplog_path.with_extension("toml.backup"))?.contains(&merge.to_string()),
+        "the previous state is backed up"
+    );
+    assert_eq!(
+        repo.find_reference(&format!("refs/gitbutler/oplog-backups/{merge}"))?
+            .target(),
+        Some(merge),
+        "the previous head stays reachable"
+    );
+
+    let snapshots = project.list_snapshots(10, None)?;
+    assert_eq!(snapshots.len(), 4, "all distinct snapshots are reachable");
+    assert_eq!(
+        snapshots[1..]
+            .iter()
+            .rev()
+            .map(|snapshot| snapshot.commit_id)
+            .collect::<Vec<_>>(),
+        snapshot_ids,
+        "the part of the chain that was intact is kept as is"
+    );
+    assert_eq!(
+        repo.find_commit(snapshots[0].commit_id)?.tree_id(),
+        merge_tree
+    );
+
+    let repair_again =
+        project.repair_oplog_chain(project.exclusive_worktree_access().write_permission())?;
+    assert_eq!(
+        repair_again.head, repair.head,
+        "a linear chain is left untouched"
+    );
+    Ok(())
+}
+
+#[tokio::test]
+async fn preview_snapshot_reads_past_content_without_restoring() -> anyhow::Result<()> {
+    let Test {
+        repository,
+        controller,
+        project,
+        ..
+    } = &Test::default();
+
+    controller
+        .set_base_branch(project, &"refs/remotes/origin/master".parse()?)
+        .await?;
+    let branch_id = controller
+        .create_virtual_branch(project, &BranchCreateRequest::default())
+        .await?;
+    fs::create_dir_all(repository.path().join("dir"))?;
+    fs::write(repository.path().join("dir/file.txt"), "before")?;
+    controller
+        .create_commit(project, branch_id, "commit one", None, false)
+        .await?;
+    fs::write(
+        repository.path().join("uncommitted.txt"),
+        "work in progress",
+    )?;
+    // listing updates the branch trees the snapshot is made of
+    controller.list_virtual_branches(project).await?;
+    let snapshot_id = project
+        .create_snapshot(
+            SnapshotDetails::new(OperationKind::FileChanges),
+            project.exclusive_worktree_access().write_permission(),
+        )?
+        .expect("snapshots are enabled");
+
+    fs::write(repository.path().join("dir/file.txt"), "after")?;
+    fs::remove_file(repository.path().join("uncommitted.txt"))?;
+    let oplog_head = project.oplog_head()?;
+
+    assert_eq!(
+        project.preview_snapshot(snapshot_id)?,
+        [
+            PathBuf::from("dir/file.txt"),
+            PathBuf::from("uncommitted.txt")
+        ]
+    );
+    let file = project.snapshot_file_content(snapshot_id, Path::new("dir/file.txt"))?;
+    assert_eq!(file.content, b"before");
+    assert!(!file.binary);
+    assert_eq!(
+        project
+            .snapshot_file_content(snapshot_id, Path::new("uncommitted.txt"))?
+            .content,
+        b"work in progress",
+        "uncommitted changes are part of the snapshot"
+    );
+    let err = project
+        .snapshot_file_content(snapshot_id, Path::new("missing.txt"))
+        .unwrap_err();
+    assert!(format!("{err:#}").contains("missing.txt does not exist"));
+
+    assert_eq!(
+        fs::read_to_string(repository.path().join("dir/file.txt"))?,
+        "after",
+        "the working tree is untouched"
+    );
+    assert!(!repository.path().join("uncommitted.txt").exists());
+    assert_eq!(project.oplog_head()?, oplog_head, "the oplog is untouched");
+    Ok(())
+}
+
+#[tokio::test]
+async fn interrupted_restore_is_detected_and_can_be_rolled_back() -> anyhow::Result<()> {
+    let Test {
+        repository,
+        controller,
+        project,
+        ..
+    } = &Test::default();
+
+    controller
+        .set_base_branch(project, &"refs/remotes/origin/master".parse()?)
+        .await?;
+    let branch_id = controller
+        .create_virtual_branch(project, &BranchCreateRequest::default())
+        .await?;
+    fs::write(repository.path().join("file.txt"), "content")?;
+    controller
+        .create_commit(project, branch_id, "commit one", None, false)
+        .await?;
+    fs::write(repository.path().join("file.txt"), "changed content")?;
+    controller
+        .create_commit(project, branch_id, "commit two", None, false)
+        .await?;
+    fs::write(repository.path().join("wip.txt"), "work in progress")?;
+    controller.list_virtual_branches(project).await?;
+    assert_eq!(project.detect_incomplete_restore()?, None);
+
+    // Restoring the integration commit fails midway if HEAD isn't on the integration branch anymore.
+    let repo = git2::Repository::open(&project.path)?;
+    let integration_commit_id = repo.head()?.peel_to_commit()?.id();
+    repo.set_head_detached(integration_commit_id)?;
+    let snapshots = project.list_snapshots(10, None)?;
+    project
+        .restore_snapshot(snapshots[0].commit_id)
+        .expect_err("the restore is interrupted");
+
+    let incomplete = project
+        .detect_incomplete_restore()?
+        .expect("the interrupted restore was recorded");
+    assert_eq!(incomplete.snapshot_id, snapshots[0].commit_id);
+    assert_eq!(
+        repo.find_reference("refs/gitbutler/restore-rollback")?
+            .target(),
+        Some(incomplete.rollback_id),
+        "the state to roll back to can't be garbage collected"
+    );
+
+    repo.set_head("refs/heads/gitbutler/integration")?;
+    project.complete_or_rollback_restore(RestoreResolution::Rollback)?;
+    assert_eq!(
+        project.detect_incomplete_restore()?,
+        None,
+        "a successful restore clears the record"
+    );
+    assert!(repo
+        .find_reference("refs/gitbutler/restore-rollback")
+        .is_err());
+    assert_eq!(
+        fs::read_to_string(repository.path().join("file.txt"))?,
+        "changed content"
+    );
+    assert_eq!(
+        fs::read_to_string(repository.path().join("wip.txt"))?,
+        "work in progress",
+        "uncommitted changes from before the restore are back"
+    );
+    assert_eq!(repo.head()?.peel_to_commit()?.id(), integration_commit_id);
+
+    let err = project
+        .complete_or_rollback_restore(RestoreResolution::Complete)
+        .unwrap_err();
+    assert!(err.to_string().contains("no incomplete restore"));
+    Ok(())
+}
+
+#[tokio::test]
+async fn named_snapshots_can_be_listed_and_restored() -> anyhow::Result<()> {
+    let Test {
+        repository,
+        controller,
+        project,
+        ..
+    } = &Test::default();
+
+    controller
+        .set_base_branch(project, &"refs/remotes/origin/master".parse()?)
+        .await?;
+    fs::write(repository.path().join("file.txt"), "before rebase")?;
+    controller.list_virtual_branches(project).await?;
+
+    let snapshot_id = project.create_named_snapshot("  before the risky rebase\n")?;
+    let snapshots = project.list_snapshots(10, None)?;
+    assert_eq!(snapshots[0].commit_id, snapshot_id);
+    let details = snapshots[0].details.as_ref().expect("details are parsed");
+    assert_eq!(details.operation, OperationKind::FileChanges);
+    assert_eq!(details.title, "before the risky rebase");
+
+    fs::write(repository.path().join("file.txt"), "after rebase")?;
+    project.restore_snapshot(snapshot_id)?;
+    assert_eq!(
+        fs::read_to_string(repository.path().join("file.txt"))?,
+        "before rebase"
+    );
+
+    for label in ["", "two\nlines", "two\rlines"] {
+        assert!(project.create_named_snapshot(label).is_err());
+    }
+    Ok(())
+}
+
+#[tokio::test]
+async fn single_file_is_restored_from_snapshot() -> anyhow::Result<()> {
+    let Test {
+        repository,
+        controller,
+        project,
+        ..
+    } = &Test::default();
+
+    controller
+        .set_base_branch(project, &"refs/remotes/origin/master".parse()?)
+        .await?;
+    controller
+        .create_virtual_branch(project, &BranchCreateRequest::default())
+        .await?;
+    fs::create_dir_all(repository.path().join("dir"))?;
+    fs::write(repository.path().join("dir/deleted.txt"), "precious")?;
+    fs::write(repository.path().join("other.txt"), "before")?;
+    controller.list_virtual_branches(project).await?;
+    let snapshot_id = project.create_named_snapshot("checkpoint")?;
+
+    fs::remove_dir_all(repository.path().join("dir"))?;
+    fs::write(repository.path().join("other.txt"), "after")?;
+
+    let oplog_head = project.oplog_head()?;
+    let err = project
+        .restore_snapshot_file(snapshot_id, Path::new("missing.txt"))
+        .unwrap_err();
+    assert!(format!("{err:#}").contains("missing.txt does not exist"));
+    assert_eq!(
+        project.oplog_head()?,
+        oplog_head,
+        "nothing happens if the file can't be restored"
+    );
+
+    let restore_snapshot_id = project
+        .restore_snapshot_file(snapshot_id, Path::new("dir/deleted.txt"))?
+        .expect("snapshots are enabled");
+    assert_eq!(
+        fs::read_to_string(repository.path().join("dir/deleted.txt"))?,
+        "precious"
+    );
+    assert_eq!(
+        fs::read_to_string(repository.path().join("other.txt"))?,
+        "after",
+        "other files are left alone"
+    );
+
+    let snapshots = project.list_snapshots(10, None)?;
+    assert_eq!(snapshots[0].commit_id, restore_snapshot_id);
+    assert_eq!(
+        snapshots[0]
+            .details
+            .as_ref()
+            .map(|details| details.operation),
+        Some(OperationKind::RestoreFromSnapshot)
+    );
+    assert!(
+        !project
+            .preview_snapshot(restore_snapshot_id)?
+            .contains(&PathBuf::from("dir/deleted.txt")),
+        "the snapshot captures the state before the restore"
+    );
+
+    let (branches, _) = controller.list_virtual_branches(project).await?;
+    assert!(branches[0]
+        .files
+        .iter()
+        .any(|file| file.path == Path::new("dir/deleted.txt")));
+    Ok(())
+}
+
+#[tokio::test]
+async fn pruning_keeps_the_most_recent_snapshots() -> anyhow::Result<()> {
+    let Test {
+        repository,
+        controller,
+        project,
+        ..
+    } = &Test::default();
+
+    controller
+        .set_base_branch(project, &"refs/remotes/origin/master".parse()?)
+        .await?;
+    for i in 0..50 {
+        fs::write(repository.path().join("file.txt"), i.to_string())?;
+        project.create_named_snapshot(&i.to_string())?;
+    }
+
+    let snapshot_count = project.list_snapshots(100, None)?.len();
+    let previous_head = project.oplog_head()?.unwrap();
+    assert_eq!(
+        project.prune_snapshots(RetentionPolicy::KeepLast(10))?,
+        snapshot_count - 10
+    );
+    let git_repo = git2::Repository::open(repository.path())?;
+    assert_eq!(
+        git_repo
+            .find_reference(&format!("refs/gitbutler/oplog-backups/{previous_head}"))?
+            .target(),
+        Some(previous_head),
+        "the pruned snapshots stay reachable"
+    );
+    let snapshots = project.list_snapshots(100, None)?;
+    assert_eq!(snapshots.len(), 10);
+    assert_eq!(
+        snapshots
+            .iter()
+            .map(|snapshot| snapshot.details.as_ref().unwrap().title.as_str())
+            .collect::<Vec<_>>(),
+        ["49", "48", "47", "46", "45", "44", "43", "42", "41", "40"]
+    );
+
+    assert_eq!(
+        project.prune_snapshots(RetentionPolicy::KeepLast(10))?,
+        0,
+        "nothing is left to prune"
+    );
+    project.create_named_snapshot("50")?;
+    assert_eq!(
+        project.prune_snapshots(RetentionPolicy::KeepLast(10))?,
+        0,
+        "a single new snapshot doesn't cause the kept ones to be rewritten"
+    );
+    assert_eq!(project.list_snapshots(100, None)?.len(), 11);
+    fs::write(repository.path().join("file.txt"), "40")?;
+    project.restore_snapshot(snapshots.last().unwrap().commit_id)?;
+    assert_eq!(
+        fs::read_to_string(repository.path().join("file.txt"))?,
+        "40"
+    );
+    Ok(())
+}
+
+#[tokio::test]
+async fn pruning_keeps_snapshots_since_the_latest_restore_target() -> anyhow::Result<()> {
+    let Test {
+        repository,
+        controller,
+        project,
+        ..
+    } = &Test::default();
+
+    controller
+        .set_base_branch(project, &"refs/remotes/origin/master".parse()?)
+        .await?;
+    let mut snapshot_ids = Vec::new();
+    for i in 0..20 {
+        fs::write(repository.path().join("file.txt"), i.to_string())?;
+        snapshot_ids.push(project.create_named_snapshot(&i.to_string())?);
+    }
+    project.restore_snapshot(snapshot_ids[5])?;
+
+    project.prune_snapshots(RetentionPolicy::KeepLast(2))?;
+    let snapshots = project.list_snapshots(100, None)?;
+    assert_eq!(
+        snapshots.len(),
+        16,
+        "the restore, the snapshots after its target, and the target are kept"
+    );
+    let target = snapshots.last().unwrap();
+    assert_eq!(target.details.as_ref().unwrap().title, "5");
+
+    let restore = snapshots[0].details.as_ref().unwrap();
+    assert_eq!(restore.operation, OperationKind::RestoreFromSnapshot);
+    assert_eq!(
+        restore.trailers[0].value,
+        target.commit_id.to_string(),
+        "the restore points to the rewritten target"
+    );
+    Ok(())
+}
+
+#[tokio::test]
+async fn diff_snapshots_detects_renames_and_binary_files() -> anyhow::Result<()> {
+    let Test {
+        repository,
+        controller,
+        project,
+        ..
+    } = &Test::default();
+
+    controller
+        .set_base_branch(project, &"refs/remotes/origin/master".parse()?)
+        .await?;
+    let content = (1..=20).map(|n| format!("line {n}\n")).collect::<String>();
+    fs::write(repository.path().join("file.txt"), &content)?;
+    fs::write(repository.path().join("image.bin"), [0u8, 1, 2, 3])?;
+    controller.list_virtual_branches(project).await?;
+    let from = project.create_named_snapshot("from")?;
+
+    fs::remove_file(repository.path().join("file.txt"))?;
+    fs::write(
+        repository.path().join("renamed.txt"),
+        format!("{content}line 21\n"),
+    )?;
+    fs::write(repository.path().join("image.bin"), [0u8, 4, 5, 6])?;
+    controller.list_virtual_branches(project).await?;
+    let to = project.create_named_snapshot("to")?;
+
+    let changes = project.diff_snapshots(from, to)?;
+    assert_eq!(changes.len(), 2);
+
+    let image = &changes[0];
+    assert_eq!(image.path, Path::new("image.bin"));
+    assert_eq!(image.kind, FileChangeKind::Modified);
+    assert!(image.binary);
+    assert_eq!((image.lines_added, image.lines_removed), (0, 0));
+
+    let renamed = &changes[1];
+    assert_eq!(renamed.path, Path::new("renamed.txt"));
+    assert_eq!(
+        renamed.previous_path.as_deref(),
+        Some(Path::new("file.txt"))
+    );
+    assert_eq!(renamed.kind, FileChangeKind::Renamed);
+    assert!(!renamed.binary);
+    assert_eq!((renamed.lines_added, renamed.lines_removed), (1, 0));
+    Ok(())
+}
+
+/// The total size of the files in the object database of the repository at `repo_path`.
+fn object_database_size(repo_path: &Path) -> u64 {
+    fn dir_size(dir: &Path) -> u64 {
+        fs::read_dir(dir)
+            .unwrap()
+            .map(|entry| {
+                let entry = entry.unwrap();
+                let metadata = entry.metadata().unwrap();
+                if metadata.is_dir() {
+                    dir_size(&entry.path())
+                } else {
+                    metadata.len()
+                }
+            })
+            .sum()
+    }
+    dir_size(&repo_path.join(".git").join("objects"))
+}
+
+/// Snapshot many similar files with `storage`, and return how many bytes the snapshot added to the object database.
+async fn snapshot_size_with(storage: SnapshotStorage) -> anyhow::Result<u64> {
+    let Test {
+        repository,
+        controller,
+        project,
+        ..
+    } = &Test::default();
+
+    controller
+        .set_base_branch(project, &"refs/remotes/origin/master".parse()?)
+        .await?;
+    let content: String = (0..1000).map(|line| format!("line {line}\n")).collect();
+    for i in 0..20 {
+        fs::write(
+            repository.path().join(format!("file{i}.txt")),
+            format!("{content}file {i}\n"),
+        )?;
+    }
+
+    let mut project = project.clone();
+    project.snapshot_storage = storage;
+    let size_before = object_database_size(repository.path());
+    let snapshot_id = project.create_named_snapshot("snapshot")?;
+    let size_after = object_database_size(repository.path());
+
+    assert_eq!(
+        project
+            .snapshot_file_content(snapshot_id, Path::new("file7.txt"))?
+            .content,
+        format!("{content}file 7\n").into_bytes(),
+        "the snapshot is readable no matter how it's stored"
+    );
+    Ok(size_after - size_before)
+}
+
+#[tokio::test]
+async fn packed_snapshot_storage_uses_less_space() -> anyhow::Result<()> {
+    let loose = snapshot_size_with(SnapshotStorage::Loose).await?;
+    let packed = snapshot_size_with(SnapshotStorage::Packed).await?;
+    assert!(
+        packed < loose,
+        "packing stores similar files as deltas: {packed} bytes packed, {loose} bytes loose"
+    );
+    Ok(())
+}
+
+#[tokio::test]
+async fn checkpoints_restore_branches_and_worktree() -> anyhow::Result<()> {
+    let Test {
+        repository,
+        controller,
+        project,
+        ..
+    } = &Test::default();
+
+    controller
+        .set_base_branch(project, &"refs/remotes/origin/master".parse()?)
+        .await?;
+    let branch_id = controller
+        .create_virtual_branch(project, &BranchCreateRequest::default())
+        .await?;
+    fs::write(repository.path().join("committed.txt"), "committed")?;
+    controller
+        .create_commit(project, branch_id, "commit", None, false)
+        .await?;
+    fs::write(repository.path().join("uncommitted.txt"), "uncommitted")?;
+    controller.list_virtual_branches(project).await?;
+
+    let checkpoint = project.create_checkpoint(" before the cleanup \n")?;
+    assert_eq!(checkpoint.name, "before the cleanup");
+    assert_eq!(project.oplog_head()?, Some(checkpoint.snapshot_id));
+    let git_repo = git2::Repository::open(repository.path())?;
+    assert_eq!(
+        git_repo
+            .find_reference("refs/gitbutler/checkpoints/before%20the%20cleanup")?
+            .target(),
+        Some(checkpoint.snapshot_id),
+        "the snapshot of the checkpoint is kept reachable"
+    );
+    assert!(project.create_checkpoint("two\nlines").is_err());
+    assert!(project.create_checkpoint("two\rlines").is_err());
+
+    // change everything: unapply the branch and start another one
+    controller
+        .convert_to_real_branch(project, branch_id, Default::default())
+        .await?;
+    controller
+        .create_virtual_branch(project, &BranchCreateRequest::default())
+        .await?;
+    fs::write(repository.path().join("other.txt"), "other")?;
+    controller.list_virtual_branches(project).await?;
+    let after_cleanup = project.create_named_snapshot("after the cleanup")?;
+
+    assert!(project.restore_checkpoint("missing").is_err());
+    let restored = project.restore_checkpoint("before the cleanup")?;
+    assert_eq!(restored, checkpoint);
+
+    assert_eq!(
+        fs::read_to_string(repository.path().join("committed.txt"))?,
+        "committed"
+    );
+    assert_eq!(
+        fs::read_to_string(repository.path().join("uncommitted.txt"))?,
+        "uncommitted"
+    );
+    assert!(!repository.path().join("other.txt").exists());
+    let (branches, _) = controller.list_virtual_branches(project).await?;
+    assert_eq!(branches.len(), 1);
+    assert_eq!(branches[0].id, branch_id);
+    assert!(branches[0].active);
+    assert_eq!(branches[0].commits.len(), 1);
+    let snapshots = project.list_snapshots(10, None)?;
+    assert_eq!(
+        snapshots[0].details.as_ref().map(|d| d.operation),
+        Some(OperationKind::RestoreFromSnapshot),
+        "the restore is recorded on top of the oplog"
+    );
+    assert_eq!(
+        snapshots[1].commit_id, after_cleanup,
+        "snapshots after the checkpoint stay in the oplog"
+    );
+    assert_eq!(project.list_checkpoints()?, [checkpoint]);
+    Ok(())
+}
+
+#[tokio::test]
+async fn packed_snapshots_do_not_pile_up_packs() -> anyhow::Result<()> {
+    let Test {
+        repository,
+        controller,
+        project,
+        ..
+    } = &Test::default();
+
+    controller
+        .set_base_branch(project, &"refs/remotes/origin/master".parse()?)
+        .await?;
+    let mut project = project.clone();
+    project.snapshot_storage = SnapshotStorage::Packed;
+    let pack_count = || -> anyhow::Result<usize> {
+        Ok(fs::read_dir(repository.path().join(".git/objects/pack"))?
+            .filter(|entry| {
+                entry
+                    .as_ref()
+                    .is_ok_and(|entry| entry.path().extension() == Some("pack".as_ref()))
+            })
+            .count())
+    };
+
+    // a small pack that isn't from the oplog, like one of a fetch
+    let git_repo = git2::Repository::open(repository.path())?;
+    let mut builder = git_repo.packbuilder()?;
+    builder.insert_commit(git_repo.head()?.peel_to_commit()?.id())?;
+    let mut pack = git2::Buf::new();
+    builder.write_buf(&mut pack)?;
+    let odb = git_repo.odb()?;
+    let mut writer = odb.packwriter()?;
+    writer.write_all(&pack)?;
+    let foreign_pack = repository
+        .path()
+        .join(format!(".git/objects/pack/pack-{}.pack", writer.commit()?));
+
+    let mut snapshot_ids = Vec::new();
+    for i in 0..60 {
+        fs::write(repository.path().join("file.txt"), format!("change {i}\n"))?;
+        snapshot_ids.push(project.create_named_snapshot(&format!("snapshot {i}"))?);
+    }
+
+    assert!(
+        pack_count()? <= 50,
+        "small packs are consolidated, got {}",
+        pack_count()?
+    );
+    assert!(
+        foreign_pack.exists(),
+        "only the packs of snapshots are consolidated"
+    );
+    for (i, snapshot_id) in snapshot_ids.into_iter().enumerate() {
+        assert_eq!(
+            project
+                .snapshot_file_content(snapshot_id, Path::new("file.txt"))?
+                .content,
+            format!("change {i}\n").into_bytes(),
+            "all snapshots are still readable"
+        );
+    }
+    Ok(())
+}
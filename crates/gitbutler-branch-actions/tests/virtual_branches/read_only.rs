@@ -0,0 +1,72 @@
+use gitbutler_branch::BranchUpdateRequest;
+use gitbutler_error::error::Code;
+use gitbutler_oplog::OplogExt;
+use gitbutler_project::ProjectMode;
+
+use super::*;
+
+#[tokio::test]
+async fn changes_are_rejected_but_reading_works() {
+    let Test {
+        repository,
+        project,
+        controller,
+        ..
+    } = &Test::default();
+
+    controller
+        .set_base_branch(project, &"refs/remotes/origin/master".parse().unwrap())
+        .await
+        .unwrap();
+    let branch_id = controller
+        .create_virtual_branch(project, &BranchCreateRequest::default())
+        .await
+        .unwrap();
+    fs::write(repository.path().join("file.txt"), "content").unwrap();
+
+    let mut project = project.clone();
+    project.mode = ProjectMode::ReadOnly;
+
+    let err = controller
+        .create_commit(&project, branch_id, "commit", None, false)
+        .await
+        .unwrap_err();
+    assert_eq!(err.downcast_ref::<Code>(), Some(&Code::ProjectReadOnly));
+    let err = controller
+        .update_virtual_branch(
+            &project,
+            BranchUpdateRequest {
+                id: branch_id,
+                name: Some("renamed".into()),
+                ..Default::default()
+            },
+        )
+        .await
+        .unwrap_err();
+    assert_eq!(err.downcast_ref::<Code>(), Some(&Code::ProjectReadOnly));
+    assert!(controller
+        .create_virtual_branch(&project, &BranchCreateRequest::default())
+        .await
+        .is_err());
+
+    let state_path = project.gb_dir().join("virtual_branches.toml");
+    let state = fs::read(&state_path).unwrap();
+    let (branches, _) = controller.list_virtual_branches(&project).await.unwrap();
+    assert_eq!(
+        fs::read(&state_path).unwrap(),
+        state,
+        "listing doesn't update the state"
+    );
+    assert_eq!(branches.len(), 1);
+    assert_ne!(branches[0].name, "renamed");
+    assert!(branches[0].commits.is_empty());
+    assert_eq!(branches[0].files.len(), 1);
+    assert!(controller.get_base_branch_data(&project).await.is_ok());
+
+    let err = project.create_checkpoint("checkpoint").unwrap_err();
+    assert_eq!(err.downcast_ref::<Code>(), Some(&Code::ProjectReadOnly));
+    let err = project
+        .repair_oplog_chain(project.exclusive_worktree_access().write_permission())
+        .unwrap_err();
+    assert_eq!(err.downcast_ref::<Code>(), Some(&Code::ProjectReadOnly));
+}
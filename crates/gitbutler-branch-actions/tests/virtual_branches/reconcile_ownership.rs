@@ -0,0 +1,53 @@
+use super::*;
+
+#[tokio::test]
+async fn external_edits() {
+    let Test {
+        repository,
+        project,
+        controller,
+        ..
+    } = &Test::default();
+
+    controller
+        .set_base_branch(project, &"refs/remotes/origin/master".parse().unwrap())
+        .await
+        .unwrap();
+
+    let branch_id = controller
+        .create_virtual_branch(project, &BranchCreateRequest::default())
+        .await
+        .unwrap();
+
+    fs::write(repository.path().join("file.txt"), "one\n").unwrap();
+    controller.list_virtual_branches(project).await.unwrap();
+
+    // nothing changed since ownership was computed
+    let drifts = controller.reconcile_ownership(project).await.unwrap();
+    assert!(drifts.is_empty());
+
+    // the claimed hunk grows
+    fs::write(repository.path().join("file.txt"), "one\ntwo\nthree\n").unwrap();
+    let drifts = controller.reconcile_ownership(project).await.unwrap();
+    assert_eq!(drifts.len(), 1);
+    assert_eq!(drifts[0].branch_id, branch_id);
+    assert_eq!(drifts[0].files, [PathBuf::from("file.txt")]);
+
+    let (branches, _) = controller.list_virtual_branches(project).await.unwrap();
+    let claims = &branches[0].ownership.claims;
+    assert_eq!(claims.len(), 1);
+    assert_eq!(claims[0].hunks.len(), 1);
+    assert_eq!((claims[0].hunks[0].start, claims[0].hunks[0].end), (1, 4));
+
+    // the file is removed, so is its claim
+    fs::remove_file(repository.path().join("file.txt")).unwrap();
+    let drifts = controller.reconcile_ownership(project).await.unwrap();
+    assert_eq!(drifts.len(), 1);
+    assert_eq!(drifts[0].files, [PathBuf::from("file.txt")]);
+
+    let (branches, _) = controller.list_virtual_branches(project).await.unwrap();
+    assert!(branches[0].ownership.claims.is_empty());
+
+    let drifts = controller.reconcile_ownership(project).await.unwrap();
+    assert!(drifts.is_empty());
+}
@@ -87,6 +87,35 @@ mod create_virtual_branch {
         assert!(refnames.contains(&"refs/gitbutler/name".to_string()));
         assert!(refnames.contains(&"refs/gitbutler/name-1".to_string()));
     }
+
+    #[tokio::test]
+    async fn invalid_name() {
+        let Test {
+            project,
+            controller,
+            ..
+        } = &Test::default();
+
+        controller
+            .set_base_branch(project, &"refs/remotes/origin/master".parse().unwrap())
+            .await
+            .unwrap();
+
+        let err = controller
+            .create_virtual_branch(
+                project,
+                &BranchCreateRequest {
+                    name: Some("feature..branch".to_string()),
+                    ..Default::default()
+                },
+            )
+            .await
+            .unwrap_err();
+        assert!(format!("{err:#}").contains("branch name cannot contain '..'"));
+
+        let (branches, _) = controller.list_virtual_branches(project).await.unwrap();
+        assert!(branches.is_empty());
+    }
 }
 
 mod update_virtual_branch {
@@ -206,6 +235,47 @@ mod update_virtual_branch {
         assert!(refnames.contains(&"refs/gitbutler/name".to_string()));
         assert!(refnames.contains(&"refs/gitbutler/name-1".to_string()));
     }
+
+    #[tokio::test]
+    async fn invalid_name() {
+        let Test {
+            project,
+            controller,
+            ..
+        } = &Test::default();
+
+        controller
+            .set_base_branch(project, &"refs/remotes/origin/master".parse().unwrap())
+            .await
+            .unwrap();
+
+        let branch_id = controller
+            .create_virtual_branch(
+                project,
+                &BranchCreateRequest {
+                    name: Some("name".to_string()),
+                    ..Default::default()
+                },
+            )
+            .await
+            .unwrap();
+
+        let err = controller
+            .update_virtual_branch(
+                project,
+                BranchUpdateRequest {
+                    id: branch_id,
+                    name: Some("feature..branch".to_string()),
+                    ..Default::default()
+                },
+            )
+            .await
+            .unwrap_err();
+        assert!(format!("{err:#}").contains("branch name cannot contain '..'"));
+
+        let (branches, _) = controller.list_virtual_branches(project).await.unwrap();
+        assert_eq!(branches[0].name, "name", "the branch keeps its name");
+    }
 }
 
 mod push_virtual_branch {
@@ -0,0 +1,89 @@
+use gitbutler_branch::BranchId;
+
+use super::*;
+
+async fn branch_ids(
+    Test {
+        project,
+        controller,
+        ..
+    }: &Test,
+) -> Vec<BranchId> {
+    let (branches, _) = controller.list_virtual_branches(project).await.unwrap();
+    branches.into_iter().map(|branch| branch.id).collect()
+}
+
+#[tokio::test]
+async fn lists_branches_in_the_given_order() {
+    let test = Test::default();
+    let Test {
+        project,
+        controller,
+        ..
+    } = &test;
+
+    controller
+        .set_base_branch(project, &"refs/remotes/origin/master".parse().unwrap())
+        .await
+        .unwrap();
+    let mut ids = Vec::new();
+    for _ in 0..3 {
+        ids.push(
+            controller
+                .create_virtual_branch(project, &BranchCreateRequest::default())
+                .await
+                .unwrap(),
+        );
+    }
+    assert_eq!(branch_ids(&test).await, ids);
+
+    controller
+        .reorder_virtual_branches(project, &[ids[2], ids[0], ids[1]])
+        .await
+        .unwrap();
+    assert_eq!(branch_ids(&test).await, [ids[2], ids[0], ids[1]]);
+
+    // branches that aren't listed keep their order after the listed ones
+    controller
+        .reorder_virtual_branches(project, &[ids[1]])
+        .await
+        .unwrap();
+    assert_eq!(branch_ids(&test).await, [ids[1], ids[2], ids[0]]);
+}
+
+#[tokio::test]
+async fn rejects_unknown_and_duplicate_ids() {
+    let test = Test::default();
+    let Test {
+        project,
+        controller,
+        ..
+    } = &test;
+
+    controller
+        .set_base_branch(project, &"refs/remotes/origin/master".parse().unwrap())
+        .await
+        .unwrap();
+    let first = controller
+        .create_virtual_branch(project, &BranchCreateRequest::default())
+        .await
+        .unwrap();
+    let second = controller
+        .create_virtual_branch(project, &BranchCreateRequest::default())
+        .await
+        .unwrap();
+
+    assert!(controller
+        .reorder_virtual_branches(project, &[second, BranchId::generate()])
+        .await
+        .is_err());
+    assert!(controller
+        .reorder_virtual_branches(project, &[second, second])
+        .await
+        .is_err());
+    assert_eq!(
+        branch_ids(&test).await,
+        [first, second],
+        "nothing changes on error"
+    );
+}
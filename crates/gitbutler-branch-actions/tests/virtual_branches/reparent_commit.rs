@@ -0,0 +1,232 @@
+use super::*;
+use gitbutler_branch::{BranchCreateRequest, BranchUpdateRequest};
+use gitbutler_error::error::Code;
+
+#[tokio::test]
+async fn reparent_onto_base() {
+    let Test {
+        repository,
+        project,
+        controller,
+        ..
+    } = &Test::default();
+
+    let base = controller
+        .set_base_branch(project, &"refs/remotes/origin/master".parse().unwrap())
+        .await
+        .unwrap();
+
+    let branch_id = controller
+        .create_virtual_branch(project, &BranchCreateRequest::default())
+        .await
+        .unwrap();
+
+    fs::write(repository.path().join("file1.txt"), "content1").unwrap();
+    controller
+        .create_commit(project, branch_id, "commit one", None, false)
+        .await
+        .unwrap();
+
+    fs::write(repository.path().join("file2.txt"), "content2").unwrap();
+    let commit2_id = controller
+        .create_commit(project, branch_id, "commit two", None, false)
+        .await
+        .unwrap();
+
+    fs::write(repository.path().join("file3.txt"), "content3").unwrap();
+    controller
+        .create_commit(project, branch_id, "commit three", None, false)
+        .await
+        .unwrap();
+
+    controller
+        .reparent_commit(project, branch_id, commit2_id, base.base_sha)
+        .await
+        .unwrap();
+
+    let branch = controller
+        .list_virtual_branches(project)
+        .await
+        .unwrap()
+        .0
+        .into_iter()
+        .find(|b| b.id == branch_id)
+        .unwrap();
+
+    let descriptions = branch
+        .commits
+        .iter()
+        .map(|c| c.description.clone())
+        .collect::<Vec<_>>();
+    assert_eq!(
+        descriptions,
+        vec!["commit one", "commit three", "commit two"]
+    );
+
+    let oldest = repository.find_commit(branch.commits[2].id).unwrap();
+    assert_eq!(oldest.parent(0).unwrap().id(), base.base_sha);
+    for (commit, file) in branch
+        .commits
+        .iter()
+        .zip(["file1.txt", "file3.txt", "file2.txt"])
+    {
+        assert_eq!(commit.files.len(), 1);
+        assert_eq!(commit.files[0].path.display().to_string(), file);
+    }
+}
+
+#[tokio::test]
+async fn reparent_conflicting_rolls_back() {
+    let Test {
+        repository,
+        project,
+        controller,
+        ..
+    } = &Test::default();
+
+    let base = controller
+        .set_base_branch(project, &"refs/remotes/origin/master".parse().unwrap())
+        .await
+        .unwrap();
+
+    let branch_id = controller
+        .create_virtual_branch(project, &BranchCreateRequest::default())
+        .await
+        .unwrap();
+
+    fs::write(repository.path().join("file.txt"), "content").unwrap();
+    controller
+        .create_commit(project, branch_id, "commit one", None, false)
+        .await
+        .unwrap();
+
+    fs::write(repository.path().join("file.txt"), "changed content").unwrap();
+    let commit2_id = controller
+        .create_commit(project, branch_id, "commit two", None, false)
+        .await
+        .unwrap();
+
+    let (branches, _) = controller.list_virtual_branches(project).await.unwrap();
+    let head_before = branches[0].head;
+
+    assert!(controller
+        .reparent_commit(project, branch_id, commit2_id, base.base_sha)
+        .await
+        .is_err());
+
+    let (branches, _) = controller.list_virtual_branches(project).await.unwrap();
+    assert_eq!(branches[0].head, head_before);
+    let descriptions = branches[0]
+        .commits
+        .iter()
+        .map(|c| c.description.clone())
+        .collect::<Vec<_>>();
+    assert_eq!(descriptions, vec!["commit two", "commit one"]);
+    assert_eq!(
+        fs::read_to_string(repository.path().join("file.txt")).unwrap(),
+        "changed content"
+    );
+}
+
+#[tokio::test]
+async fn reparent_onto_descendant_is_rejected() {
+    let Test {
+        repository,
+        project,
+        controller,
+        ..
+    } = &Test::default();
+
+    controller
+        .set_base_branch(project, &"refs/remotes/origin/master".parse().unwrap())
+        .await
+        .unwrap();
+
+    let branch_id = controller
+        .create_virtual_branch(project, &BranchCreateRequest::default())
+        .await
+        .unwrap();
+
+    fs::write(repository.path().join("file1.txt"), "content1").unwrap();
+    let commit1_id = controller
+        .create_commit(project, branch_id, "commit one", None, false)
+        .await
+        .unwrap();
+
+    fs::write(repository.path().join("file2.txt"), "content2").unwrap();
+    let commit2_id = controller
+        .create_commit(project, branch_id, "commit two", None, false)
+        .await
+        .unwrap();
+
+    let err = controller
+        .reparent_commit(project, branch_id, commit1_id, commit2_id)
+        .await
+        .unwrap_err();
+    assert!(
+        format!("{err:#}").contains("is neither the base nor below"),
+        "a commit can't be moved onto its own descendant: {err:#}"
+    );
+}
+
+#[tokio::test]
+async fn reparent_pushed_commit_without_rebasing_is_rejected() {
+    let Test {
+        repository,
+        project,
+        controller,
+        ..
+    } = &Test::default();
+
+    let base = controller
+        .set_base_branch(project, &"refs/remotes/origin/master".parse().unwrap())
+        .await
+        .unwrap();
+
+    let branch_id = controller
+        .create_virtual_branch(project, &BranchCreateRequest::default())
+        .await
+        .unwrap();
+    controller
+        .update_virtual_branch(
+            project,
+            BranchUpdateRequest {
+                id: branch_id,
+                allow_rebasing: Some(false),
+                ..Default::default()
+            },
+        )
+        .await
+        .unwrap();
+
+    fs::write(repository.path().join("file1.txt"), "content1").unwrap();
+    controller
+        .create_commit(project, branch_id, "commit one", None, false)
+        .await
+        .unwrap();
+    fs::write(repository.path().join("file2.txt"), "content2").unwrap();
+    let commit2_id = controller
+        .create_commit(project, branch_id, "commit two", None, false)
+        .await
+        .unwrap();
+    controller
+        .push_virtual_branch(project, branch_id, false, None)
+        .await
+        .unwrap();
+
+    let err = controller
+        .reparent_commit(project, branch_id, commit2_id, base.base_sha)
+        .await
+        .unwrap_err();
+    assert_eq!(err.downcast_ref::<Code>(), Some(&Code::Validation));
+
+    let branch = controller
+        .list_virtual_branches(project)
+        .await
+        .unwrap()
+        .0
+        .into_iter()
+        .find(|b| b.id == branch_id)
+        .unwrap();
+    assert_eq!(branch.head, commit2_id, "the pushed commits are untouched");
+}
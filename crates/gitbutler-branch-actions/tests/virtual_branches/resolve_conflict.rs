@@ -0,0 +1,267 @@
+use gitbutler_branch_actions::conflicts::ConflictResolution;
+use gitbutler_error::error::Code;
+
+use super::*;
+
+/// Apply a branch that conflicts with the base in `file.txt` and `other.txt`.
+async fn apply_conflicting_branch(
+    repository: &TestProject,
+    project: &Project,
+    controller: &VirtualBranchActions,
+) {
+    {
+        fs::write(repository.path().join("file.txt"), "first").unwrap();
+        fs::write(repository.path().join("other.txt"), "first").unwrap();
+        let first_commit_oid = repository.commit_all("first");
+        fs::write(repository.path().join("file.txt"), "second").unwrap();
+        fs::write(repository.path().join("other.txt"), "second").unwrap();
+        repository.commit_all("second");
+        repository.push();
+        repository.reset_hard(Some(first_commit_oid));
+    }
+
+    controller
+        .set_base_branch(project, &"refs/remotes/origin/master".parse().unwrap())
+        .await
+        .unwrap();
+
+    controller
+        .create_virtual_branch(project, &BranchCreateRequest::default())
+        .await
+        .unwrap();
+    fs::write(repository.path().join("file.txt"), "conflict").unwrap();
+    fs::write(repository.path().join("other.txt"), "conflict").unwrap();
+
+    let unapplied_branches = controller.update_base_branch(project).await.unwrap();
+    assert_eq!(unapplied_branches.len(), 1);
+    let unapplied_branch = Refname::from_str(&unapplied_branches[0]).unwrap();
+
+    controller
+        .create_virtual_branch_from_branch(project, &unapplied_branch)
+        .await
+        .unwrap();
+
+    let (branches, _) = controller.list_virtual_branches(project).await.unwrap();
+    assert_eq!(branches.len(), 1);
+    assert!(branches[0].conflicted);
+}
+
+async fn resolve_file_with(resolution: ConflictResolution) -> String {
+    let Test {
+        repository,
+        project,
+        controller,
+        ..
+    } = &Test::default();
+
+    apply_conflicting_branch(repository, project, controller).await;
+
+    controller
+        .resolve_conflict(project, "file.txt", resolution)
+        .await
+        .unwrap();
+
+    // only the other file is still conflicting
+    let (branches, _) = controller.list_virtual_branches(project).await.unwrap();
+    assert!(branches[0]
+        .files
+        .iter()
+        .filter(|file| file.path == path::Path::new("file.txt"))
+        .all(|file| !file.conflicted));
+    let other = branches[0]
+        .files
+        .iter()
+        .find(|file| file.path == path::Path::new("other.txt"))
+        .unwrap();
+    assert!(other.conflicted);
+
+    fs::read_to_string(repository.path().join("file.txt")).unwrap()
+}
+
+#[tokio::test]
+async fn ours() {
+    assert_eq!(
+        resolve_file_with(ConflictResolution::Ours).await,
+        "conflict"
+    );
+}
+
+#[tokio::test]
+async fn theirs() {
+    assert_eq!(
+        resolve_file_with(ConflictResolution::Theirs).await,
+        "second"
+    );
+}
+
+#[tokio::test]
+async fn base() {
+    assert_eq!(resolve_file_with(ConflictResolution::Base).await, "first");
+}
+
+#[tokio::test]
+async fn batch() {
+    let Test {
+        repository,
+        project,
+        controller,
+        ..
+    } = &Test::default();
+
+    apply_conflicting_branch(repository, project, controller).await;
+
+    controller
+        .resolve_conflicts(
+            project,
+            &[
+                ("file.txt", ConflictResolution::Ours),
+                ("other.txt", ConflictResolution::Theirs),
+            ],
+        )
+        .await
+        .unwrap();
+
+    assert_eq!(
+        fs::read_to_string(repository.path().join("file.txt")).unwrap(),
+        "conflict"
+    );
+    assert_eq!(
+        fs::read_to_string(repository.path().join("other.txt")).unwrap(),
+        "second"
+    );
+
+    // with all files resolved, the resolution can be committed
+    let (branches, _) = controller.list_virtual_branches(project).await.unwrap();
+    assert!(branches[0].files.iter().all(|file| !file.conflicted));
+    controller
+        .create_commit(project, branches[0].id, "resolution", None, false)
+        .await
+        .unwrap();
+}
+
+#[tokio::test]
+async fn not_conflicted() {
+    let Test {
+        repository,
+        project,
+        controller,
+        ..
+    } = &Test::default();
+
+    apply_conflicting_branch(repository, project, controller).await;
+
+    assert!(controller
+        .resolve_conflict(project, "unknown.txt", ConflictResolution::Ours)
+        .await
+        .is_err());
+}
+
+#[tokio::test]
+async fn batch_with_a_file_that_is_not_conflicted_resolves_nothing() {
+    let Test {
+        repository,
+        project,
+        controller,
+        ..
+    } = &Test::default();
+
+    apply_conflicting_branch(repository, project, controller).await;
+
+    let err = controller
+        .resolve_conflicts(
+            project,
+            &[
+                ("file.txt", ConflictResolution::Ours),
+                ("unknown.txt", ConflictResolution::Ours),
+            ],
+        )
+        .await
+        .unwrap_err();
+    assert_eq!(err.downcast_ref::<Code>(), Some(&Code::Validation));
+
+    let conflicts = controller.list_conflicts(project).await.unwrap();
+    assert_eq!(
+        conflicts
+            .iter()
+            .map(|file| file.path.as_path())
+            .collect::<Vec<_>>(),
+        vec![path::Path::new("file.txt"), path::Path::new("other.txt")],
+        "files before the one that isn't conflicted stay conflicted"
+    );
+}
+
+#[tokio::test]
+async fn list_conflicts() {
+    let Test {
+        repository,
+        project,
+        controller,
+        ..
+    } = &Test::default();
+
+    apply_conflicting_branch(repository, project, controller).await;
+
+    let conflicts = controller.list_conflicts(project).await.unwrap();
+    assert_eq!(
+        conflicts
+            .iter()
+            .map(|file| file.path.as_path())
+            .collect::<Vec<_>>(),
+        vec![path::Path::new("file.txt"), path::Path::new("other.txt")]
+    );
+    for file in &conflicts {
+        assert_eq!(file.hunks.len(), 1);
+        let hunk = &file.hunks[0];
+        assert_eq!(hunk.start_line, 1);
+        assert_eq!(hunk.ours.trim_end(), "conflict");
+        assert_eq!(hunk.theirs.trim_end(), "second");
+    }
+
+    controller
+        .resolve_conflict(project, "file.txt", ConflictResolution::Theirs)
+        .await
+        .unwrap();
+    let conflicts = controller.list_conflicts(project).await.unwrap();
+    assert_eq!(conflicts.len(), 1);
+    assert_eq!(conflicts[0].path, path::Path::new("other.txt"));
+}
+
+#[tokio::test]
+async fn manual() {
+    let Test {
+        repository,
+        project,
+        controller,
+        ..
+    } = &Test::default();
+
+    apply_conflicting_branch(repository, project, controller).await;
+
+    controller
+        .resolve_conflict(project, "file.txt", ConflictResolution::Ours)
+        .await
+        .unwrap();
+    controller
+        .resolve_conflict(
+            project,
+            "other.txt",
+            ConflictResolution::Manual("conflict and second".into()),
+        )
+        .await
+        .unwrap();
+    assert_eq!(
+        fs::read_to_string(repository.path().join("other.txt")).unwrap(),
+        "conflict and second"
+    );
+
+    // resolving the last conflict lets the apply be finalized with a commit
+    assert!(controller.list_conflicts(project).await.unwrap().is_empty());
+    let (branches, _) = controller.list_virtual_branches(project).await.unwrap();
+    assert!(branches[0].files.iter().all(|file| !file.conflicted));
+    controller
+        .create_commit(project, branches[0].id, "resolution", None, false)
+        .await
+        .unwrap();
+    let (branches, _) = controller.list_virtual_branches(project).await.unwrap();
+    assert!(!branches[0].conflicted);
+}
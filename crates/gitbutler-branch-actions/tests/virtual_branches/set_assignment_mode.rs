@@ -0,0 +1,167 @@
+use gitbutler_branch::{AssignmentMode, BranchCreateRequest, BranchId, BranchUpdateRequest};
+
+use super::*;
+
+fn files(branch: &gitbutler_branch_actions::VirtualBranch) -> Vec<PathBuf> {
+    branch.files.iter().map(|file| file.path.clone()).collect()
+}
+
+#[tokio::test]
+async fn auto_assigns_new_changes_to_the_chosen_branch() {
+    let Test {
+        repository,
+        project,
+        controller,
+        ..
+    } = &Test::default();
+
+    controller
+        .set_base_branch(project, &"refs/remotes/origin/master".parse().unwrap())
+        .await
+        .unwrap();
+    let selected_id = controller
+        .create_virtual_branch(project, &BranchCreateRequest::default())
+        .await
+        .unwrap();
+    let chosen_id = controller
+        .create_virtual_branch(project, &BranchCreateRequest::default())
+        .await
+        .unwrap();
+
+    controller
+        .set_assignment_mode(project, AssignmentMode::Auto(chosen_id))
+        .await
+        .unwrap();
+    fs::write(repository.path().join("file.txt"), "content").unwrap();
+
+    let (branches, _) = controller.list_virtual_branches(project).await.unwrap();
+    let selected = branches.iter().find(|b| b.id == selected_id).unwrap();
+    let chosen = branches.iter().find(|b| b.id == chosen_id).unwrap();
+    assert!(selected.selected_for_changes);
+    assert!(files(selected).is_empty());
+    assert_eq!(files(chosen), [PathBuf::from("file.txt")]);
+}
+
+#[tokio::test]
+async fn auto_assignment_needs_a_branch_in_the_workspace() {
+    let Test {
+        project,
+        controller,
+        ..
+    } = &Test::default();
+
+    controller
+        .set_base_branch(project, &"refs/remotes/origin/master".parse().unwrap())
+        .await
+        .unwrap();
+    assert!(controller
+        .set_assignment_mode(project, AssignmentMode::Auto(BranchId::generate()))
+        .await
+        .is_err());
+}
+
+#[tokio::test]
+async fn manual_leaves_new_changes_unassigned() {
+    let Test {
+        repository,
+        project,
+        controller,
+        ..
+    } = &Test::default();
+
+    controller
+        .set_base_branch(project, &"refs/remotes/origin/master".parse().unwrap())
+        .await
+        .unwrap();
+    let branch_id = controller
+        .create_virtual_branch(project, &BranchCreateRequest::default())
+        .await
+        .unwrap();
+    fs::write(repository.path().join("owned.txt"), "owned").unwrap();
+    controller.list_virtual_branches(project).await.unwrap();
+
+    controller
+        .set_assignment_mode(project, AssignmentMode::Manual)
+        .await
+        .unwrap();
+    fs::write(repository.path().join("new.txt"), "new").unwrap();
+
+    let (branches, _) = controller.list_virtual_branches(project).await.unwrap();
+    assert_eq!(
+        files(&branches[0]),
+        [PathBuf::from("owned.txt")],
+        "changes owned before keep their branch"
+    );
+    let unassigned = controller.list_unassigned_files(project).await.unwrap();
+    assert_eq!(unassigned.len(), 1);
+    assert_eq!(unassigned[0].path, PathBuf::from("new.txt"));
+
+    // placing the change explicitly assigns it
+    controller
+        .update_virtual_branch(
+            project,
+            BranchUpdateRequest {
+                id: branch_id,
+                ownership: Some(
+                    format!("new.txt:{}", unassigned[0].hunks[0].id)
+                        .parse()
+                        .unwrap(),
+                ),
+                ..Default::default()
+            },
+        )
+        .await
+        .unwrap();
+    let (branches, _) = controller.list_virtual_branches(project).await.unwrap();
+    let mut assigned = files(&branches[0]);
+    assigned.sort();
+    assert_eq!(
+        assigned,
+        [PathBuf::from("new.txt"), PathBuf::from("owned.txt")]
+    );
+    assert!(controller
+        .list_unassigned_files(project)
+        .await
+        .unwrap()
+        .is_empty());
+}
+
+#[tokio::test]
+async fn manual_keeps_unassigned_changes_when_unapplying() {
+    let Test {
+        repository,
+        project,
+        controller,
+        ..
+    } = &Test::default();
+
+    controller
+        .set_base_branch(project, &"refs/remotes/origin/master".parse().unwrap())
+        .await
+        .unwrap();
+    let branch_id = controller
+        .create_virtual_branch(project, &BranchCreateRequest::default())
+        .await
+        .unwrap();
+    fs::write(repository.path().join("owned.txt"), "owned").unwrap();
+    controller.list_virtual_branches(project).await.unwrap();
+
+    controller
+        .set_assignment_mode(project, AssignmentMode::Manual)
+        .await
+        .unwrap();
+    fs::write(repository.path().join("new.txt"), "new").unwrap();
+    controller
+        .convert_to_real_branch(project, branch_id, Default::default())
+        .await
+        .unwrap();
+
+    assert!(!repository.path().join("owned.txt").exists());
+    assert_eq!(
+        fs::read_to_string(repository.path().join("new.txt")).unwrap(),
+        "new"
+    );
+    let unassigned = controller.list_unassigned_files(project).await.unwrap();
+    assert_eq!(unassigned.len(), 1);
+    assert_eq!(unassigned[0].path, PathBuf::from("new.txt"));
+}
@@ -0,0 +1,78 @@
+use super::*;
+
+#[tokio::test]
+async fn set_and_clear() {
+    let Test {
+        project,
+        controller,
+        ..
+    } = &Test::default();
+
+    controller
+        .set_base_branch(project, &"refs/remotes/origin/master".parse().unwrap())
+        .await
+        .unwrap();
+
+    let branch_id = controller
+        .create_virtual_branch(project, &BranchCreateRequest::default())
+        .await
+        .unwrap();
+
+    controller
+        .set_branch_description(project, branch_id, "  waiting on API review\n")
+        .await
+        .unwrap();
+    let (branches, _) = controller.list_virtual_branches(project).await.unwrap();
+    assert_eq!(branches[0].notes, "waiting on API review");
+
+    controller
+        .set_branch_description(project, branch_id, "")
+        .await
+        .unwrap();
+    let (branches, _) = controller.list_virtual_branches(project).await.unwrap();
+    assert_eq!(branches[0].notes, "");
+}
+
+#[tokio::test]
+async fn survives_rebase() {
+    let Test {
+        repository,
+        project,
+        controller,
+        ..
+    } = &Test::default();
+
+    controller
+        .set_base_branch(project, &"refs/remotes/origin/master".parse().unwrap())
+        .await
+        .unwrap();
+
+    let branch_id = controller
+        .create_virtual_branch(project, &BranchCreateRequest::default())
+        .await
+        .unwrap();
+    controller
+        .set_branch_description(project, branch_id, "waiting on API review")
+        .await
+        .unwrap();
+
+    fs::write(repository.path().join("file.txt"), "content").unwrap();
+    controller
+        .create_commit(project, branch_id, "commit one", None, false)
+        .await
+        .unwrap();
+    fs::write(repository.path().join("file2.txt"), "content2").unwrap();
+    let commit2_id = controller
+        .create_commit(project, branch_id, "commit two", None, false)
+        .await
+        .unwrap();
+
+    controller
+        .reorder_commit(project, branch_id, commit2_id, 1)
+        .await
+        .unwrap();
+
+    let (branches, _) = controller.list_virtual_branches(project).await.unwrap();
+    assert_eq!(branches[0].commits[1].description, "commit two");
+    assert_eq!(branches[0].notes, "waiting on API review");
+}
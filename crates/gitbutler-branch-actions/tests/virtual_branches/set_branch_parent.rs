@@ -0,0 +1,254 @@
+use gitbutler_branch::BranchId;
+use gitbutler_branch_actions::VirtualBranch;
+
+use super::*;
+
+/// Create two branches with a commit each, returning their ids.
+async fn two_branches(
+    Test {
+        repository,
+        project,
+        controller,
+        ..
+    }: &Test,
+) -> (BranchId, BranchId) {
+    controller
+        .set_base_branch(project, &"refs/remotes/origin/master".parse().unwrap())
+        .await
+        .unwrap();
+
+    let a = controller
+        .create_virtual_branch(project, &BranchCreateRequest::default())
+        .await
+        .unwrap();
+    fs::write(repository.path().join("a.txt"), "a").unwrap();
+    controller
+        .create_commit(project, a, "commit a", None, false)
+        .await
+        .unwrap();
+
+    let b = controller
+        .create_virtual_branch(
+            project,
+            &BranchCreateRequest {
+                selected_for_changes: Some(true),
+                ..Default::default()
+            },
+        )
+        .await
+        .unwrap();
+    fs::write(repository.path().join("b.txt"), "b").unwrap();
+    controller
+        .create_commit(project, b, "commit b", None, false)
+        .await
+        .unwrap();
+    (a, b)
+}
+
+async fn branch(
+    Test {
+        project,
+        controller,
+        ..
+    }: &Test,
+    id: BranchId,
+) -> VirtualBranch {
+    let (branches, _) = controller.list_virtual_branches(project).await.unwrap();
+    branches.into_iter().find(|branch| branch.id == id).unwrap()
+}
+
+#[tokio::test]
+async fn stacked_branch_is_rebased_onto_its_parent() {
+    let test = Test::default();
+    let (a, b) = two_branches(&test).await;
+    let Test {
+        repository,
+        project,
+        controller,
+        ..
+    } = &test;
+
+    controller
+        .set_branch_parent(project, b, Some(a))
+        .await
+        .unwrap();
+
+    let parent = branch(&test, a).await;
+    let child = branch(&test, b).await;
+    assert_eq!(child.parent_id, Some(a));
+    assert_eq!(parent.parent_id, None);
+    assert_eq!(child.commits.len(), 1, "only its own commits are listed");
+    assert_eq!(child.commits[0].description, "commit b");
+    assert_eq!(child.commits[0].parent_ids, vec![parent.commits[0].id]);
+    assert_eq!(
+        fs::read_to_string(repository.path().join("a.txt")).unwrap(),
+        "a"
+    );
+    assert_eq!(
+        fs::read_to_string(repository.path().join("b.txt")).unwrap(),
+        "b"
+    );
+}
+
+#[tokio::test]
+async fn rewriting_the_parent_restacks_the_child() {
+    let test = Test::default();
+    let (a, b) = two_branches(&test).await;
+    let Test {
+        project,
+        controller,
+        ..
+    } = &test;
+
+    controller
+        .set_branch_parent(project, b, Some(a))
+        .await
+        .unwrap();
+    let commit_a = branch(&test, a).await.commits[0].id;
+    controller
+        .update_commit_message(project, a, commit_a, "commit a, reworded")
+        .await
+        .unwrap();
+
+    let parent = branch(&test, a).await;
+    let child = branch(&test, b).await;
+    assert_eq!(parent.commits[0].description, "commit a, reworded");
+    assert_eq!(child.commits.len(), 1);
+    assert_eq!(child.commits[0].parent_ids, vec![parent.commits[0].id]);
+}
+
+#[tokio::test]
+async fn unstacking_rebases_onto_the_base() {
+    let test = Test::default();
+    let (a, b) = two_branches(&test).await;
+    let Test {
+        project,
+        controller,
+        ..
+    } = &test;
+
+    controller
+        .set_branch_parent(project, b, Some(a))
+        .await
+        .unwrap();
+    controller
+        .set_branch_parent(project, b, None)
+        .await
+        .unwrap();
+
+    let base = VirtualBranchActions::get_base_branch_data(project)
+        .await
+        .unwrap()
+        .base_sha;
+    let child = branch(&test, b).await;
+    assert_eq!(child.parent_id, None);
+    assert_eq!(child.commits.len(), 1);
+    assert_eq!(child.commits[0].parent_ids, vec![base]);
+}
+
+#[tokio::test]
+async fn cycles_are_rejected() {
+    let test = Test::default();
+    let (a, b) = two_branches(&test).await;
+    let Test {
+        project,
+        controller,
+        ..
+    } = &test;
+
+    let err = controller
+        .set_branch_parent(project, a, Some(a))
+        .await
+        .unwrap_err();
+    assert!(err.to_string().contains("would depend on itself"));
+
+    controller
+        .set_branch_parent(project, b, Some(a))
+        .await
+        .unwrap();
+    let err = controller
+        .set_branch_parent(project, a, Some(b))
+        .await
+        .unwrap_err();
+    assert!(err.to_string().contains("would depend on itself"));
+    assert_eq!(branch(&test, a).await.parent_id, None);
+}
+
+#[tokio::test]
+async fn updating_the_base_cascades_down_the_stack() {
+    let test = Test::default();
+    let Test {
+        repository,
+        project,
+        controller,
+        ..
+    } = &test;
+    // make sure we have an undiscovered commit in the remote branch
+    {
+        fs::write(repository.path().join("file.txt"), "first").unwrap();
+        let first_commit_oid = repository.commit_all("first");
+        fs::write(repository.path().join("upstream.txt"), "upstream").unwrap();
+        repository.commit_all("upstream");
+        repository.push();
+        repository.reset_hard(Some(first_commit_oid));
+    }
+    let (a, b) = two_branches(&test).await;
+    controller
+        .set_branch_parent(project, b, Some(a))
+        .await
+        .unwrap();
+
+    let unapplied_branches = controller.update_base_branch(project).await.unwrap();
+    assert!(unapplied_branches.is_empty());
+
+    let base = VirtualBranchActions::get_base_branch_data(project)
+        .await
+        .unwrap()
+        .base_sha;
+    let parent = branch(&test, a).await;
+    let child = branch(&test, b).await;
+    assert_eq!(parent.commits.len(), 1);
+    assert_eq!(parent.commits[0].parent_ids, vec![base]);
+    assert_eq!(child.parent_id, Some(a));
+    assert_eq!(
+        child.commits.len(),
+        1,
+        "the commits of the parent aren't duplicated"
+    );
+    assert_eq!(child.commits[0].description, "commit b");
+    assert_eq!(child.commits[0].parent_ids, vec![parent.commits[0].id]);
+    for (file, content) in [("a.txt", "a"), ("b.txt", "b"), ("upstream.txt", "upstream")] {
+        assert_eq!(
+            fs::read_to_string(repository.path().join(file)).unwrap(),
+            content
+        );
+    }
+}
+
+#[tokio::test]
+async fn unapplying_the_parent_unstacks_the_child() {
+    let test = Test::default();
+    let (a, b) = two_branches(&test).await;
+    let Test {
+        project,
+        controller,
+        ..
+    } = &test;
+
+    controller
+        .set_branch_parent(project, b, Some(a))
+        .await
+        .unwrap();
+    controller
+        .convert_to_real_branch(project, a, Default::default())
+        .await
+        .unwrap();
+
+    let child = branch(&test, b).await;
+    assert_eq!(child.parent_id, None);
+    assert_eq!(
+        child.commits.len(),
+        2,
+        "the commits of the former parent are part of the branch now"
+    );
+}
@@ -0,0 +1,126 @@
+use std::path::Path;
+
+use gitbutler_branch::BranchCreateRequest;
+
+use super::*;
+
+#[tokio::test]
+async fn content_at_old_commit() {
+    let Test {
+        repository,
+        project,
+        controller,
+        ..
+    } = &Test::default();
+
+    controller
+        .set_base_branch(project, &"refs/remotes/origin/master".parse().unwrap())
+        .await
+        .unwrap();
+
+    let branch_id = controller
+        .create_virtual_branch(project, &BranchCreateRequest::default())
+        .await
+        .unwrap();
+
+    fs::write(repository.path().join("file.txt"), "first").unwrap();
+    let first_commit_id = controller
+        .create_commit(project, branch_id, "first", None, false)
+        .await
+        .unwrap();
+    fs::write(repository.path().join("file.txt"), "second").unwrap();
+    fs::write(repository.path().join("image.bin"), [0u8, 159, 146, 150]).unwrap();
+    let second_commit_id = controller
+        .create_commit(project, branch_id, "second", None, false)
+        .await
+        .unwrap();
+
+    let file = controller
+        .show_file_at_commit(project, branch_id, first_commit_id, Path::new("file.txt"))
+        .await
+        .unwrap();
+    assert_eq!(file.content, b"first");
+    assert!(!file.binary);
+
+    let file = controller
+        .show_file_at_commit(project, branch_id, second_commit_id, Path::new("file.txt"))
+        .await
+        .unwrap();
+    assert_eq!(file.content, b"second");
+
+    let file = controller
+        .show_file_at_commit(project, branch_id, second_commit_id, Path::new("image.bin"))
+        .await
+        .unwrap();
+    assert_eq!(file.content, [0u8, 159, 146, 150]);
+    assert!(file.binary);
+}
+
+#[tokio::test]
+async fn missing_path() {
+    let Test {
+        repository,
+        project,
+        controller,
+        ..
+    } = &Test::default();
+
+    controller
+        .set_base_branch(project, &"refs/remotes/origin/master".parse().unwrap())
+        .await
+        .unwrap();
+
+    let branch_id = controller
+        .create_virtual_branch(project, &BranchCreateRequest::default())
+        .await
+        .unwrap();
+    fs::write(repository.path().join("file.txt"), "content").unwrap();
+    let commit_id = controller
+        .create_commit(project, branch_id, "commit", None, false)
+        .await
+        .unwrap();
+
+    let err = controller
+        .show_file_at_commit(project, branch_id, commit_id, Path::new("missing.txt"))
+        .await
+        .unwrap_err();
+    assert_eq!(
+        err.to_string(),
+        format!("missing.txt does not exist in commit {commit_id}")
+    );
+}
+
+#[tokio::test]
+async fn commit_of_other_branch() {
+    let Test {
+        repository,
+        project,
+        controller,
+        ..
+    } = &Test::default();
+
+    controller
+        .set_base_branch(project, &"refs/remotes/origin/master".parse().unwrap())
+        .await
+        .unwrap();
+
+    let branch_id = controller
+        .create_virtual_branch(project, &BranchCreateRequest::default())
+        .await
+        .unwrap();
+    fs::write(repository.path().join("file.txt"), "content").unwrap();
+    let commit_id = controller
+        .create_commit(project, branch_id, "commit", None, false)
+        .await
+        .unwrap();
+
+    let other_branch_id = controller
+        .create_virtual_branch(project, &BranchCreateRequest::default())
+        .await
+        .unwrap();
+
+    assert!(controller
+        .show_file_at_commit(project, other_branch_id, commit_id, Path::new("file.txt"))
+        .await
+        .is_err());
+}
@@ -0,0 +1,158 @@
+use super::*;
+use gitbutler_branch::{BranchCreateRequest, BranchId};
+use gitbutler_error::error::Code;
+use gitbutler_oplog::{entry::OperationKind, OplogExt};
+
+/// Create a branch with three commits, each adding a file, and return its id with the commit ids
+/// from the oldest to the newest.
+async fn branch_with_three_commits(
+    Test {
+        repository,
+        project,
+        controller,
+        ..
+    }: &Test,
+) -> (BranchId, Vec<git2::Oid>) {
+    controller
+        .set_base_branch(project, &"refs/remotes/origin/master".parse().unwrap())
+        .await
+        .unwrap();
+
+    let branch_id = controller
+        .create_virtual_branch(project, &BranchCreateRequest::default())
+        .await
+        .unwrap();
+
+    let mut commit_ids = Vec::new();
+    for name in ["one", "two", "three"] {
+        fs::write(repository.path().join(format!("file {name}.txt")), name).unwrap();
+        commit_ids.push(
+            controller
+                .create_commit(project, branch_id, &format!("commit {name}"), None, false)
+                .await
+                .unwrap(),
+        );
+    }
+    (branch_id, commit_ids)
+}
+
+#[tokio::test]
+async fn first_two_of_three() {
+    let test = Test::default();
+    let Test {
+        repository,
+        project,
+        controller,
+        ..
+    } = &test;
+    let (branch_id, commit_ids) = branch_with_three_commits(&test).await;
+
+    let squashed_id = controller
+        .squash_commits(project, branch_id, vec![commit_ids[1], commit_ids[0]], None)
+        .await
+        .unwrap();
+
+    let branch = controller
+        .list_virtual_branches(project)
+        .await
+        .unwrap()
+        .0
+        .into_iter()
+        .find(|b| b.id == branch_id)
+        .unwrap();
+
+    let descriptions = branch
+        .commits
+        .iter()
+        .map(|c| c.description.clone())
+        .collect::<Vec<_>>();
+    assert_eq!(descriptions, vec!["commit three", "commit one\ncommit two"]);
+    assert_eq!(branch.commits[1].id, squashed_id);
+
+    // the later commit is rebased on the squashed one
+    let head = repository.find_commit(branch.head).unwrap();
+    assert_eq!(head.parent(0).unwrap().id(), squashed_id);
+    let squashed = repository.find_commit(squashed_id).unwrap();
+    assert_eq!(squashed.parent_count(), 1);
+    let tree = squashed.tree().unwrap();
+    assert!(tree.get_name("file one.txt").is_some());
+    assert!(tree.get_name("file two.txt").is_some());
+    assert!(tree.get_name("file three.txt").is_none());
+}
+
+#[tokio::test]
+async fn with_message() {
+    let test = Test::default();
+    let Test {
+        project,
+        controller,
+        ..
+    } = &test;
+    let (branch_id, commit_ids) = branch_with_three_commits(&test).await;
+
+    controller
+        .squash_commits(
+            project,
+            branch_id,
+            vec![commit_ids[1], commit_ids[2]],
+            Some("commits two and three"),
+        )
+        .await
+        .unwrap();
+
+    let branch = controller
+        .list_virtual_branches(project)
+        .await
+        .unwrap()
+        .0
+        .into_iter()
+        .find(|b| b.id == branch_id)
+        .unwrap();
+
+    let descriptions = branch
+        .commits
+        .iter()
+        .map(|c| c.description.clone())
+        .collect::<Vec<_>>();
+    assert_eq!(descriptions, vec!["commits two and three", "commit one"]);
+}
+
+#[tokio::test]
+async fn non_contiguous_forbidden() {
+    let test = Test::default();
+    let Test {
+        project,
+        controller,
+        ..
+    } = &test;
+    let (branch_id, commit_ids) = branch_with_three_commits(&test).await;
+
+    let err = controller
+        .squash_commits(project, branch_id, vec![commit_ids[0], commit_ids[2]], None)
+        .await
+        .unwrap_err();
+    assert_eq!(err.downcast_ref::<Code>(), Some(&Code::Validation));
+    assert!(format!("{err:#}").contains("Only a contiguous range of commits can be squashed"));
+}
+
+#[tokio::test]
+async fn creates_snapshot() {
+    let test = Test::default();
+    let Test {
+        project,
+        controller,
+        ..
+    } = &test;
+    let (branch_id, commit_ids) = branch_with_three_commits(&test).await;
+
+    controller
+        .squash_commits(project, branch_id, vec![commit_ids[0], commit_ids[1]], None)
+        .await
+        .unwrap();
+
+    let snapshots = project.list_snapshots(10, None).unwrap();
+    assert_eq!(
+        snapshots[0].details.as_ref().unwrap().operation,
+        OperationKind::SquashCommit
+    );
+}
@@ -0,0 +1,159 @@
+use gitbutler_branch::VirtualBranchesHandle;
+use gitbutler_branch_actions::SubsetConflict;
+
+use super::*;
+
+#[tokio::test]
+async fn is_the_union_of_the_selected_non_overlapping_branches() {
+    let Test {
+        repository,
+        project,
+        controller,
+        ..
+    } = &Test::default();
+
+    controller
+        .set_base_branch(project, &"refs/remotes/origin/master".parse().unwrap())
+        .await
+        .unwrap();
+
+    let mut branch_ids = Vec::new();
+    let mut commit_ids = Vec::new();
+    for name in ["a", "b", "c"] {
+        let branch_id = controller
+            .create_virtual_branch(
+                project,
+                &BranchCreateRequest {
+                    selected_for_changes: Some(true),
+                    ..Default::default()
+                },
+            )
+            .await
+            .unwrap();
+        fs::write(repository.path().join(format!("{name}.txt")), "content\n").unwrap();
+        commit_ids.push(
+            controller
+                .create_commit(project, branch_id, name, None, false)
+                .await
+                .unwrap(),
+        );
+        branch_ids.push(branch_id);
+    }
+
+    let mut expected = controller
+        .list_remote_commit_files(project, commit_ids[0])
+        .await
+        .unwrap();
+    expected.extend(
+        controller
+            .list_remote_commit_files(project, commit_ids[2])
+            .await
+            .unwrap(),
+    );
+
+    let diff = controller
+        .subset_net_diff(project, &[branch_ids[2], branch_ids[0]])
+        .await
+        .unwrap();
+    assert!(diff.conflicts.is_empty());
+    assert_eq!(
+        diff.files
+            .iter()
+            .map(|file| file.path.clone())
+            .collect::<Vec<_>>(),
+        [PathBuf::from("a.txt"), PathBuf::from("c.txt")],
+        "the unselected branch isn't part of it"
+    );
+    assert_eq!(diff.files, expected);
+
+    let all = controller
+        .subset_net_diff(project, &branch_ids)
+        .await
+        .unwrap();
+    assert_eq!(
+        all.files,
+        controller.integration_net_diff(project).await.unwrap(),
+        "selecting all branches is the same as the integration"
+    );
+}
+
+#[tokio::test]
+async fn is_empty_without_selected_branches() {
+    let Test {
+        project,
+        controller,
+        ..
+    } = &Test::default();
+
+    controller
+        .set_base_branch(project, &"refs/remotes/origin/master".parse().unwrap())
+        .await
+        .unwrap();
+
+    let diff = controller.subset_net_diff(project, &[]).await.unwrap();
+    assert!(diff.files.is_empty());
+    assert!(diff.conflicts.is_empty());
+}
+
+#[tokio::test]
+async fn leaves_out_branches_conflicting_with_those_selected_before() {
+    let Test {
+        project,
+        controller,
+        ..
+    } = &Test::default();
+
+    controller
+        .set_base_branch(project, &"refs/remotes/origin/master".parse().unwrap())
+        .await
+        .unwrap();
+
+    // Branches in the workspace never conflict, so give them conflicting heads directly.
+    let repo = git2::Repository::open(&project.path).unwrap();
+    let vb_state = VirtualBranchesHandle::new(project.gb_dir());
+    let base = repo
+        .find_commit(vb_state.get_default_target().unwrap().sha)
+        .unwrap();
+    let mut branch_ids = Vec::new();
+    for content in ["first", "second"] {
+        let branch_id = controller
+            .create_virtual_branch(project, &BranchCreateRequest::default())
+            .await
+            .unwrap();
+        let mut builder = repo.treebuilder(Some(&base.tree().unwrap())).unwrap();
+        builder
+            .insert("file.txt", repo.blob(content.as_bytes()).unwrap(), 0o100644)
+            .unwrap();
+        let tree = repo.find_tree(builder.write().unwrap()).unwrap();
+        let signature = git2::Signature::now("test", "test@example.com").unwrap();
+        let mut branch = vb_state.get_branch(branch_id).unwrap();
+        branch.head = repo
+            .commit(None, &signature, &signature, content, &tree, &[&base])
+            .unwrap();
+        branch.tree = tree.id();
+        vb_state.set_branch(branch).unwrap();
+        branch_ids.push(branch_id);
+    }
+
+    let diff = controller
+        .subset_net_diff(project, &[branch_ids[1], branch_ids[0]])
+        .await
+        .unwrap();
+    assert_eq!(
+        diff.conflicts,
+        [SubsetConflict {
+            branch_id: branch_ids[0],
+            paths: vec![PathBuf::from("file.txt")],
+        }],
+        "the branch selected later is the one that conflicts"
+    );
+    assert_eq!(diff.files.len(), 1);
+    assert_eq!(diff.files[0].path, PathBuf::from("file.txt"));
+    assert!(
+        diff.files[0].hunks[0]
+            .diff_lines
+            .to_string()
+            .contains("+second"),
+        "only the branch selected first is part of the diff"
+    );
+}
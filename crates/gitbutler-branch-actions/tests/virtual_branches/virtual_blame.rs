@@ -0,0 +1,132 @@
+use gitbutler_branch_actions::LineOrigin;
+use gitbutler_error::error::Code;
+
+use super::*;
+
+#[tokio::test]
+async fn committed_and_uncommitted_lines() {
+    let Test {
+        repository,
+        project,
+        controller,
+        ..
+    } = &Test::default();
+
+    fs::write(repository.path().join("file.txt"), "one\ntwo\nthree\n").unwrap();
+    let base_commit_id = repository.commit_all("three lines");
+    repository.push();
+
+    controller
+        .set_base_branch(project, &"refs/remotes/origin/master".parse().unwrap())
+        .await
+        .unwrap();
+    let branch_id = controller
+        .create_virtual_branch(project, &BranchCreateRequest::default())
+        .await
+        .unwrap();
+
+    fs::write(repository.path().join("file.txt"), "ONE\ntwo\nthree\n").unwrap();
+    let commit_id = controller
+        .create_commit(project, branch_id, "change line one", None, false)
+        .await
+        .unwrap();
+    fs::write(repository.path().join("file.txt"), "ONE\ntwo\nTHREE\n").unwrap();
+
+    let lines = controller
+        .virtual_blame(project, path::Path::new("file.txt"))
+        .await
+        .unwrap();
+    assert_eq!(
+        lines
+            .iter()
+            .map(|line| (line.line, line.origin, line.commit_id, line.branch_id))
+            .collect::<Vec<_>>(),
+        vec![
+            (1, LineOrigin::Committed, Some(commit_id), None),
+            (2, LineOrigin::Committed, Some(base_commit_id), None),
+            (3, LineOrigin::Uncommitted, None, Some(branch_id)),
+        ]
+    );
+}
+
+#[tokio::test]
+async fn file_only_in_working_tree() {
+    let Test {
+        repository,
+        project,
+        controller,
+        ..
+    } = &Test::default();
+
+    controller
+        .set_base_branch(project, &"refs/remotes/origin/master".parse().unwrap())
+        .await
+        .unwrap();
+    let branch_id = controller
+        .create_virtual_branch(project, &BranchCreateRequest::default())
+        .await
+        .unwrap();
+
+    fs::write(repository.path().join("new.txt"), "one\ntwo\n").unwrap();
+
+    let lines = controller
+        .virtual_blame(project, path::Path::new("new.txt"))
+        .await
+        .unwrap();
+    assert_eq!(lines.len(), 2);
+    assert!(lines
+        .iter()
+        .all(|line| line.origin == LineOrigin::Uncommitted
+            && line.branch_id == Some(branch_id)
+            && line.commit_id.is_none()));
+}
+
+#[tokio::test]
+async fn binary_file() {
+    let Test {
+        repository,
+        project,
+        controller,
+        ..
+    } = &Test::default();
+
+    controller
+        .set_base_branch(project, &"refs/remotes/origin/master".parse().unwrap())
+        .await
+        .unwrap();
+
+    fs::write(repository.path().join("image.bin"), [0u8, 1, 2, 3]).unwrap();
+
+    let err = controller
+        .virtual_blame(project, path::Path::new("image.bin"))
+        .await
+        .unwrap_err();
+    assert_eq!(err.downcast_ref::<Code>(), Some(&Code::Validation));
+    assert!(format!("{err:#}").contains("Binary files can't be blamed"));
+}
+
+#[tokio::test]
+async fn paths_outside_of_the_worktree() {
+    let Test {
+        project,
+        controller,
+        ..
+    } = &Test::default();
+
+    controller
+        .set_base_branch(project, &"refs/remotes/origin/master".parse().unwrap())
+        .await
+        .unwrap();
+    let outside = tempfile::tempdir().unwrap();
+    let outside_file = outside.path().join("file.txt");
+    fs::write(&outside_file, "outside").unwrap();
+
+    for path in [outside_file.as_path(), path::Path::new("../file.txt")] {
+        let err = controller.virtual_blame(project, path).await.unwrap_err();
+        assert_eq!(
+            err.downcast_ref::<Code>(),
+            Some(&Code::Validation),
+            "{path:?} is refused"
+        );
+    }
+}
@@ -0,0 +1,166 @@
+use gitbutler_branch_actions::{WorkspaceConflict, WorkspaceConflictKind};
+use gitbutler_oplog::OplogExt;
+
+use super::*;
+
+#[tokio::test]
+async fn switching_restores_the_branches_of_the_workspace() {
+    let Test {
+        repository,
+        project,
+        controller,
+        ..
+    } = &Test::default();
+
+    controller
+        .set_base_branch(project, &"refs/remotes/origin/master".parse().unwrap())
+        .await
+        .unwrap();
+
+    let one_id = controller
+        .create_virtual_branch(
+            project,
+            &BranchCreateRequest {
+                name: Some("one".into()),
+                ..Default::default()
+            },
+        )
+        .await
+        .unwrap();
+    fs::write(repository.path().join("one.txt"), "one").unwrap();
+    controller.save_workspace(project, "first").await.unwrap();
+
+    let two_id = controller
+        .create_virtual_branch(
+            project,
+            &BranchCreateRequest {
+                name: Some("two".into()),
+                selected_for_changes: Some(true),
+                ..Default::default()
+            },
+        )
+        .await
+        .unwrap();
+    fs::write(repository.path().join("two.txt"), "two").unwrap();
+    let second = controller.save_workspace(project, "second").await.unwrap();
+    assert_eq!(second.branches, ["one", "two"]);
+
+    let workspaces = controller.list_workspaces(project).await.unwrap();
+    assert_eq!(
+        workspaces
+            .iter()
+            .map(|workspace| workspace.name.as_str())
+            .collect::<Vec<_>>(),
+        ["first", "second"]
+    );
+    assert_eq!(workspaces[0].branches, ["one"]);
+
+    let switch = controller.switch_workspace(project, "first").await.unwrap();
+    assert_eq!(switch.workspace.name, "first");
+    assert_eq!(
+        switch.conflicts,
+        [WorkspaceConflict {
+            branch_id: two_id,
+            branch_name: "two".into(),
+            kind: WorkspaceConflictKind::Missing,
+        }],
+        "the branch that isn't part of the workspace is reported"
+    );
+    assert!(repository.path().join("one.txt").exists());
+    assert!(!repository.path().join("two.txt").exists());
+    let (branches, _) = controller.list_virtual_branches(project).await.unwrap();
+    assert_eq!(branches.len(), 1);
+    assert_eq!(branches[0].id, one_id);
+
+    let switch = controller
+        .switch_workspace(project, "second")
+        .await
+        .unwrap();
+    assert!(switch.conflicts.is_empty());
+    assert_eq!(
+        fs::read_to_string(repository.path().join("two.txt")).unwrap(),
+        "two"
+    );
+    let (branches, _) = controller.list_virtual_branches(project).await.unwrap();
+    assert_eq!(branches.len(), 2);
+}
+
+#[tokio::test]
+async fn switching_reports_diverged_branches() {
+    let Test {
+        repository,
+        project,
+        controller,
+        ..
+    } = &Test::default();
+
+    controller
+        .set_base_branch(project, &"refs/remotes/origin/master".parse().unwrap())
+        .await
+        .unwrap();
+    let branch_id = controller
+        .create_virtual_branch(project, &BranchCreateRequest::default())
+        .await
+        .unwrap();
+    controller.save_workspace(project, "saved").await.unwrap();
+
+    fs::write(repository.path().join("file.txt"), "content").unwrap();
+    let commit_id = controller
+        .create_commit(project, branch_id, "commit", None, false)
+        .await
+        .unwrap();
+
+    let conflicts = controller
+        .workspace_conflicts(project, "saved")
+        .await
+        .unwrap();
+    let (branches, _) = controller.list_virtual_branches(project).await.unwrap();
+    assert_eq!(
+        branches[0].commits[0].id, commit_id,
+        "the conflicts are reported without switching"
+    );
+
+    let switch = controller.switch_workspace(project, "saved").await.unwrap();
+    assert_eq!(switch.conflicts, conflicts);
+    assert_eq!(switch.conflicts.len(), 1);
+    assert_eq!(switch.conflicts[0].branch_id, branch_id);
+    assert_eq!(switch.conflicts[0].kind, WorkspaceConflictKind::Diverged);
+    let (branches, _) = controller.list_virtual_branches(project).await.unwrap();
+    assert!(branches[0].commits.is_empty());
+
+    // the state before the switch is recoverable from its snapshot
+    project.restore_snapshot(switch.snapshot_id).unwrap();
+    let (branches, _) = controller.list_virtual_branches(project).await.unwrap();
+    assert_eq!(branches[0].commits[0].id, commit_id);
+}
+
+#[tokio::test]
+async fn names_must_be_valid_and_exist() {
+    let Test {
+        project,
+        controller,
+        ..
+    } = &Test::default();
+
+    controller
+        .set_base_branch(project, &"refs/remotes/origin/master".parse().unwrap())
+        .await
+        .unwrap();
+
+    for name in ["", "a/b", "with space", "x..y"] {
+        assert!(
+            controller.save_workspace(project, name).await.is_err(),
+            "{name:?}"
+        );
+    }
+    let err = controller
+        .switch_workspace(project, "unknown")
+        .await
+        .unwrap_err();
+    assert!(format!("{err:#}").contains("There is no workspace named 'unknown'"));
+    assert!(controller
+        .list_workspaces(project)
+        .await
+        .unwrap()
+        .is_empty());
+}
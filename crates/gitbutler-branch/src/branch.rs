@@ -60,6 +60,10 @@ pub struct Branch {
     pub in_workspace: bool,
     #[serde(default)]
     pub not_in_workspace_wip_change_id: Option<String>,
+    /// The branch this branch is stacked on, so its commits build on the commits of the parent
+    /// instead of the base branch.
+    #[serde(default)]
+    pub parent_id: Option<BranchId>,
 }
 
 fn default_true() -> bool {
@@ -14,7 +14,7 @@ pub use target::Target;
 
 mod state;
 pub use state::VirtualBranches as VirtualBranchesState;
-pub use state::VirtualBranchesHandle;
+pub use state::{AssignmentMode, VirtualBranchesHandle};
 
 use lazy_static::lazy_static;
 lazy_static! {
@@ -1,5 +1,5 @@
 use std::{
-    collections::HashMap,
+    collections::{HashMap, HashSet},
     path::{Path, PathBuf},
 };
 
@@ -25,13 +25,30 @@ pub struct VirtualBranches {
     branch_targets: HashMap<BranchId, Target>,
     /// The current state of the virtual branches
     branches: HashMap<BranchId, Branch>,
+    /// How changes that no branch claims yet are assigned
+    #[serde(default)]
+    assignment_mode: AssignmentMode,
+}
+
+/// How changes that no branch in the workspace claims yet are assigned to a branch.
+#[derive(Serialize, Deserialize, Debug, Default, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "camelCase", tag = "mode", content = "branchId")]
+pub enum AssignmentMode {
+    /// New changes go to the branch that is selected for changes.
+    #[default]
+    Selected,
+    /// New changes go to the given branch, or to the branch selected for changes if it isn't in the workspace.
+    Auto(BranchId),
+    /// New changes stay unassigned until they are moved to a branch explicitly.
+    /// Changes that depend on the commits of a branch still go to that branch.
+    Manual,
 }
 
 impl VirtualBranches {
-    /// Lists all virtual branches that are in the user's workspace.
+    /// Lists all virtual branches, including those that aren't in the user's workspace.
     ///
     /// Errors if the file cannot be read or written.
-    pub(crate) fn list_all_branches(&self) -> Result<Vec<Branch>> {
+    pub fn list_all_branches(&self) -> Result<Vec<Branch>> {
         let branches: Vec<Branch> = self.branches.values().cloned().collect();
         Ok(branches)
     }
@@ -94,6 +111,23 @@ impl VirtualBranchesHandle {
             .ok_or(anyhow!("there is no default target").context(Code::DefaultTargetNotFound))
     }
 
+    /// Persists how changes that no branch claims yet are assigned.
+    ///
+    /// Errors if the file cannot be read or written.
+    pub fn set_assignment_mode(&self, mode: AssignmentMode) -> Result<()> {
+        let mut virtual_branches = self.read_file()?;
+        virtual_branches.assignment_mode = mode;
+        self.write_file(&virtual_branches)?;
+        Ok(())
+    }
+
+    /// Gets how changes that no branch claims yet are assigned.
+    ///
+    /// Errors if the file cannot be read.
+    pub fn get_assignment_mode(&self) -> Result<AssignmentMode> {
+        Ok(self.read_file()?.assignment_mode)
+    }
+
     /// Sets the target for the given virtual branch.
     ///
     /// Errors if the file cannot be read or written.
@@ -114,6 +148,36 @@ impl VirtualBranchesHandle {
         Ok(())
     }
 
+    /// Removes the given virtual branch, along with its target, from the state entirely.
+    ///
+    /// Errors if the file cannot be read or written.
+    pub fn delete_branch_entry(&self, id: &BranchId) -> Result<()> {
+        let mut virtual_branches = self.read_file()?;
+        virtual_branches.branches.remove(id);
+        virtual_branches.branch_targets.remove(id);
+        self.write_file(&virtual_branches)?;
+        self.unstack_children(*id)
+    }
+
+    /// Unstacks the branches stacked on the branch with `parent_id`, as they can't build on its commits
+    /// in the workspace anymore.
+    ///
+    /// Errors if the file cannot be read or written.
+    fn unstack_children(&self, parent_id: BranchId) -> Result<()> {
+        let mut virtual_branches = self.read_file()?;
+        let mut changed = false;
+        for branch in virtual_branches.branches.values_mut() {
+            if branch.parent_id == Some(parent_id) {
+                branch.parent_id = None;
+                changed = true;
+            }
+        }
+        if changed {
+            self.write_file(&virtual_branches)?;
+        }
+        Ok(())
+    }
+
     /// Marks a particular branch as not in the workspace
     ///
     /// Errors if the file cannot be read or written.
@@ -122,7 +186,7 @@ impl VirtualBranchesHandle {
         branch.in_workspace = false;
         branch.applied = false;
         self.set_branch(branch)?;
-        Ok(())
+        self.unstack_children(id)
     }
 
     pub fn find_by_source_refname_where_not_in_workspace(
@@ -232,6 +296,45 @@ impl VirtualBranchesHandle {
         }
     }
 
+    /// Orders the branches in the workspace as in `ordered_ids`, with the branches that aren't listed following
+    /// in their current order, and persists the new order all at once.
+    ///
+    /// Errors if an id is listed twice or doesn't belong to a branch in the workspace.
+    pub fn reorder_branches(&self, ordered_ids: &[BranchId]) -> Result<()> {
+        let mut virtual_branches = self.read_file()?;
+        let mut listed = HashSet::new();
+        for id in ordered_ids {
+            let in_workspace = virtual_branches.branches.get(id).map_or(false, |branch| {
+                branch.in_workspace && !branch.is_old_unapplied()
+            });
+            if !in_workspace {
+                return Err(
+                    anyhow!("branch with ID {id} isn't in the workspace").context(Code::Validation)
+                );
+            }
+            if !listed.insert(*id) {
+                return Err(anyhow!("branch with ID {id} is listed more than once")
+                    .context(Code::Validation));
+            }
+        }
+
+        let unlisted: Vec<BranchId> = virtual_branches
+            .branches
+            .values()
+            .filter(|branch| {
+                branch.in_workspace && !branch.is_old_unapplied() && !listed.contains(&branch.id)
+            })
+            .sorted_by_key(|branch| branch.order)
+            .map(|branch| branch.id)
+            .collect();
+        for (order, id) in ordered_ids.iter().chain(&unlisted).enumerate() {
+            if let Some(branch) = virtual_branches.branches.get_mut(id) {
+                branch.order = order;
+            }
+        }
+        self.write_file(&virtual_branches)
+    }
+
     pub fn next_order_index(&self) -> Result<usize> {
         self.update_ordering()?;
         let order = self
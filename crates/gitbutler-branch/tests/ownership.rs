@@ -40,6 +40,7 @@ fn reconcile_ownership_simple() {
         applied: true,
         in_workspace: true,
         not_in_workspace_wip_change_id: None,
+        parent_id: None,
         source_refname: None,
     };
     let branch_b = Branch {
@@ -68,6 +69,7 @@ fn reconcile_ownership_simple() {
         applied: true,
         in_workspace: true,
         not_in_workspace_wip_change_id: None,
+        parent_id: None,
         source_refname: None,
     };
     let all_branches: Vec<Branch> = vec![branch_a.clone(), branch_b.clone()];
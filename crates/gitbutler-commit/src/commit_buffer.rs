@@ -44,6 +44,11 @@ impl CommitBuffer {
         }
     }
 
+    /// Replace the message, which is expected to be in the encoding named by the `encoding` header.
+    pub fn set_message(&mut self, message: impl Into<BString>) {
+        self.message = message.into();
+    }
+
     /// Defers to the CommitHeadersV2 struct about which headers should be injected.
     /// If `commit_headers: None` is provided, a default set of headers, including a generated change-id will be used
     pub fn set_gitbutler_headers(&mut self, commit_headers: Option<CommitHeadersV2>) {
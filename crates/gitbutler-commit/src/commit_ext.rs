@@ -1,4 +1,6 @@
-use bstr::BStr;
+use bstr::{BStr, BString};
+use encoding_rs::Encoding;
+use uuid::Uuid;
 
 use crate::commit_headers::HasCommitHeaders;
 
@@ -8,7 +10,15 @@ use crate::commit_headers::HasCommitHeaders;
 pub trait CommitExt {
     /// Obtain the commit-message as bytes, but without assuming any encoding.
     fn message_bstr(&self) -> &BStr;
+    /// Obtain the commit-message decoded from the encoding named in its `encoding` header.
+    /// Without such header, or if the encoding is unknown, the message is returned as is.
+    fn message_decoded(&self) -> BString;
     fn change_id(&self) -> Option<String>;
+    /// Obtain the [change-id](Self::change_id()) of the commit, or for commits that weren't created by GitButler
+    /// and have none, one derived from their author and message.
+    ///
+    /// Either way it survives rebases that only change the parents, the tree or the committer of the commit.
+    fn stable_change_id(&self) -> String;
     fn is_signed(&self) -> bool;
 }
 
@@ -17,9 +27,38 @@ impl<'repo> CommitExt for git2::Commit<'repo> {
         self.message_bytes().as_ref()
     }
 
+    fn message_decoded(&self) -> BString {
+        match self
+            .message_encoding()
+            .and_then(|label| Encoding::for_label(label.as_bytes()))
+        {
+            Some(encoding) if encoding != encoding_rs::UTF_8 => encoding
+                .decode_without_bom_handling(self.message_bytes())
+                .0
+                .into_owned()
+                .into(),
+            _ => self.message_bytes().into(),
+        }
+    }
+
     fn change_id(&self) -> Option<String> {
         self.gitbutler_headers().map(|headers| headers.change_id)
     }
+    fn stable_change_id(&self) -> String {
+        self.change_id().unwrap_or_else(|| {
+            let author = self.author();
+            let mut identity = Vec::new();
+            identity.extend_from_slice(author.name_bytes());
+            identity.push(0);
+            identity.extend_from_slice(author.email_bytes());
+            identity.push(0);
+            identity.extend_from_slice(author.when().seconds().to_string().as_bytes());
+            identity.push(0);
+            identity.extend_from_slice(self.message_bytes());
+            Uuid::new_v5(&Uuid::NAMESPACE_OID, &identity).to_string()
+        })
+    }
+
     fn is_signed(&self) -> bool {
         self.header_field_bytes("gpgsig").is_ok()
     }
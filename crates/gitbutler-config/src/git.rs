@@ -1,5 +1,6 @@
 use anyhow::Result;
 use git2::ConfigLevel;
+use gitbutler_project::SigningSettings;
 
 use serde::Deserialize;
 use serde::Serialize;
@@ -12,12 +13,39 @@ pub struct GbConfig {
     pub signing_format: Option<String>,
     pub gpg_program: Option<String>,
     pub gpg_ssh_program: Option<String>,
+    pub commit_encoding: Option<String>,
 }
+impl From<GbConfig> for SigningSettings {
+    fn from(config: GbConfig) -> Self {
+        SigningSettings {
+            sign_commits: config.sign_commits,
+            signing_key: config.signing_key,
+            signing_format: config.signing_format,
+            gpg_program: config.gpg_program,
+            gpg_ssh_program: config.gpg_ssh_program,
+        }
+    }
+}
+
+impl From<SigningSettings> for GbConfig {
+    fn from(settings: SigningSettings) -> Self {
+        GbConfig {
+            sign_commits: settings.sign_commits,
+            signing_key: settings.signing_key,
+            signing_format: settings.signing_format,
+            gpg_program: settings.gpg_program,
+            gpg_ssh_program: settings.gpg_ssh_program,
+            commit_encoding: None,
+        }
+    }
+}
+
 const SIGN_COMMITS: &str = "gitbutler.signCommits";
 const SIGNING_KEY: &str = "user.signingKey";
 const SIGNING_FORMAT: &str = "gpg.format";
 const GPG_PROGRAM: &str = "gpg.program";
 const GPG_SSH_PROGRAM: &str = "gpg.ssh.program";
+const COMMIT_ENCODING: &str = "i18n.commitEncoding";
 
 pub trait GitConfig {
     fn gb_config(&self) -> Result<GbConfig>;
@@ -31,12 +59,14 @@ impl GitConfig for git2::Repository {
         let signing_format = get_string(self, SIGNING_FORMAT)?;
         let gpg_program = get_string(self, GPG_PROGRAM)?;
         let gpg_ssh_program = get_string(self, GPG_SSH_PROGRAM)?;
+        let commit_encoding = get_string(self, COMMIT_ENCODING)?;
         Ok(GbConfig {
             sign_commits,
             signing_key,
             signing_format,
             gpg_program,
             gpg_ssh_program,
+            commit_encoding,
         })
     }
     fn set_gb_config(&self, config: GbConfig) -> Result<()> {
@@ -55,6 +85,9 @@ impl GitConfig for git2::Repository {
         if let Some(gpg_ssh_program) = config.gpg_ssh_program {
             set_local_string(self, GPG_SSH_PROGRAM, &gpg_ssh_program)?;
         }
+        if let Some(commit_encoding) = config.commit_encoding {
+            set_local_string(self, COMMIT_ENCODING, &commit_encoding)?;
+        }
         Ok(())
     }
 }
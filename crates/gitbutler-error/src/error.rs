@@ -132,6 +132,7 @@ pub enum Code {
     ProjectGitAuth,
     DefaultTargetNotFound,
     CommitSigningFailed,
+    ProjectReadOnly,
 }
 
 impl std::fmt::Display for Code {
@@ -142,6 +143,7 @@ impl std::fmt::Display for Code {
             Code::ProjectGitAuth => "errors.projects.git.auth",
             Code::DefaultTargetNotFound => "errors.projects.default_target.not_found",
             Code::CommitSigningFailed => "errors.commit.signing_failed",
+            Code::ProjectReadOnly => "errors.projects.read_only",
         };
         f.write_str(code)
     }
@@ -1,7 +1,7 @@
 use anyhow::Result;
 use gitbutler_project as projects;
 use gitbutler_project::ProjectId;
-use std::path::PathBuf;
+use std::path::{Path, PathBuf};
 
 use crate::zipper::Zipper;
 
@@ -31,4 +31,32 @@ impl Archival {
     pub fn logs_archive(&self) -> Result<PathBuf> {
         self.zipper().zip(&self.logs_dir).map_err(Into::into)
     }
+
+    /// Creates a reproducible archive of the whole state of the project, including its virtual branches
+    /// and operations log, and returns its path.
+    /// With `signing_key`, the path to a private SSH key, the archive is signed with it.
+    pub fn archive_project_state(
+        &self,
+        project_id: ProjectId,
+        signing_key: Option<&Path>,
+    ) -> Result<PathBuf> {
+        let project = self.projects_controller.get(project_id)?;
+        crate::state_archive::archive(&self.cache_dir, project_id, &project.path, signing_key)
+    }
+
+    /// Fails if the project state archive at `archive_path` was modified or is incomplete.
+    /// Returns the fingerprint of the SSH key that signed the archive, if it is signed.
+    pub fn verify_project_state(&self, archive_path: &Path) -> Result<Option<String>> {
+        crate::state_archive::verify(archive_path)
+    }
+
+    /// Verifies the project state archive at `archive_path` and restores it into the empty `target_dir`.
+    pub fn restore_project_state(&self, archive_path: &Path, target_dir: &Path) -> Result<()> {
+        crate::state_archive::restore(archive_path, target_dir)
+    }
+
+    /// Returns up to the last `lines` log lines that were emitted on behalf of the given project.
+    pub fn tail_logs(&self, project_id: ProjectId, lines: usize) -> Result<Vec<String>> {
+        crate::logs::tail(&self.logs_dir, project_id, lines)
+    }
 }
@@ -1,3 +1,5 @@
 mod controller;
 pub use controller::Archival;
+mod logs;
+mod state_archive;
 mod zipper;
@@ -0,0 +1,44 @@
+use anyhow::Result;
+use gitbutler_project::ProjectId;
+use std::{fs, path::Path, time};
+
+/// Returns up to the last `lines` log lines in `logs_dir` that were emitted within a span
+/// carrying the `project_id` field of the given project, oldest first.
+///
+/// All `*.log` files in `logs_dir` are considered, starting with the most recently modified one.
+pub fn tail(logs_dir: &Path, project_id: ProjectId, lines: usize) -> Result<Vec<String>> {
+    let mut log_files = fs::read_dir(logs_dir)?
+        .filter_map(|entry| {
+            let entry = entry.ok()?;
+            let metadata = entry.metadata().ok()?;
+            if !metadata.is_file() || entry.path().extension()? != "log" {
+                return None;
+            }
+            let modified = metadata.modified().unwrap_or(time::UNIX_EPOCH);
+            Some((entry.path(), modified))
+        })
+        .collect::<Vec<_>>();
+    log_files.sort_by_key(|(_, modified)| std::cmp::Reverse(*modified));
+
+    let needle = format!("project_id={project_id}");
+    let mut tail = Vec::with_capacity(lines);
+    for (path, _) in log_files {
+        if tail.len() == lines {
+            break;
+        }
+        let content = fs::read_to_string(path)?;
+        tail.extend(
+            content
+                .lines()
+                .rev()
+                .filter(|line| line.contains(&needle))
+                .take(lines - tail.len())
+                .map(ToOwned::to_owned),
+        );
+    }
+    tail.reverse();
+    Ok(tail)
+}
+
+#[cfg(test)]
+mod tests;
@@ -0,0 +1,70 @@
+use super::*;
+
+use std::{fs::File, sync::Mutex};
+use tempfile::tempdir;
+use tracing::info;
+
+fn emit_logs(logs_dir: &Path, emit: impl FnOnce()) {
+    let file = File::create(logs_dir.join("GitButler.2024-01-01.log")).unwrap();
+    let subscriber = tracing_subscriber::fmt()
+        .compact()
+        .with_ansi(false)
+        .with_writer(Mutex::new(file))
+        .finish();
+    tracing::subscriber::with_default(subscriber, emit);
+}
+
+#[test]
+fn returns_lines_of_the_project_only() {
+    let logs_dir = tempdir().unwrap();
+    let project_id = ProjectId::generate();
+    let other_project_id = ProjectId::generate();
+
+    emit_logs(logs_dir.path(), || {
+        info!("before any operation");
+        tracing::info_span!("operation", project_id = %project_id).in_scope(|| {
+            info!("first");
+            info!("second");
+        });
+        tracing::info_span!("operation", project_id = %other_project_id).in_scope(|| {
+            info!("other project");
+        });
+    });
+
+    let lines = tail(logs_dir.path(), project_id, 10).unwrap();
+    assert_eq!(lines.len(), 2);
+    assert!(lines[0].contains("first"));
+    assert!(lines[1].contains("second"));
+}
+
+#[test]
+fn returns_the_last_lines() {
+    let logs_dir = tempdir().unwrap();
+    let project_id = ProjectId::generate();
+
+    emit_logs(logs_dir.path(), || {
+        tracing::info_span!("operation", project_id = %project_id).in_scope(|| {
+            for i in 0..5 {
+                info!("line {i}");
+            }
+        });
+    });
+
+    let lines = tail(logs_dir.path(), project_id, 2).unwrap();
+    assert_eq!(lines.len(), 2);
+    assert!(lines[0].contains("line 3"));
+    assert!(lines[1].contains("line 4"));
+}
+
+#[test]
+fn ignores_files_that_are_not_logs() {
+    let logs_dir = tempdir().unwrap();
+    let project_id = ProjectId::generate();
+    fs::write(
+        logs_dir.path().join("tokio-console"),
+        format!("project_id={project_id}"),
+    )
+    .unwrap();
+
+    assert!(tail(logs_dir.path(), project_id, 10).unwrap().is_empty());
+}
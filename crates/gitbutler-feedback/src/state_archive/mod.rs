@@ -0,0 +1,297 @@
+use std::{
+    fs,
+    io::{self, Read, Write},
+    path::{Path, PathBuf},
+    process::{Command, Stdio},
+};
+
+use anyhow::{anyhow, bail, Context, Result};
+use gitbutler_project::ProjectId;
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use walkdir::WalkDir;
+use zip::{write::FileOptions, CompressionMethod, DateTime, ZipArchive, ZipWriter};
+
+const MANIFEST_NAME: &str = "manifest.json";
+/// The SSH signature of the manifest, if the archive is signed.
+const SIGNATURE_NAME: &str = "manifest.json.sig";
+/// The namespace of SSH signatures of archives, so they can't be mistaken for signatures of anything else.
+const SIGNATURE_NAMESPACE: &str = "gitbutler-state-archive";
+const GIT_PREFIX: &str = "git/";
+const MANIFEST_VERSION: u32 = 1;
+
+/// Describes the content of a project state archive, so it can be verified before being restored.
+#[derive(Debug, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct Manifest {
+    version: u32,
+    project_id: ProjectId,
+    files: Vec<ManifestEntry>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct ManifestEntry {
+    /// The path of the file in the archive.
+    path: String,
+    size: u64,
+    /// The hex encoded sha256 of the file content.
+    sha256: String,
+}
+
+/// Writes the entire `.git` directory of the project at `project_path` into an archive inside of `cache_dir`.
+///
+/// This includes the base, all virtual branches, their commits and the operations log, which is all
+/// that is needed to bring the project back to its current state.
+/// The archive is reproducible: the same state always results in the same bytes and the same file name.
+/// With `signing_key`, the path to a private SSH key, the manifest is signed with it using `ssh-keygen`.
+pub(crate) fn archive(
+    cache_dir: &Path,
+    project_id: ProjectId,
+    project_path: &Path,
+    signing_key: Option<&Path>,
+) -> Result<PathBuf> {
+    let git_dir = project_path.join(".git");
+    if !git_dir.is_dir() {
+        bail!("{} is not a git repository", project_path.display());
+    }
+
+    let archives_dir = cache_dir.join("archives");
+    fs::create_dir_all(&archives_dir).context("failed to create cache dir")?;
+    // Write to a temporary file first so a partially written archive is never picked up as complete.
+    let tmp = tempfile::NamedTempFile::new_in(&archives_dir)?;
+    let mut zip = ZipWriter::new(tmp.reopen()?);
+    let options = FileOptions::default()
+        .compression_method(CompressionMethod::Bzip2)
+        .last_modified_time(DateTime::default())
+        .unix_permissions(0o644);
+
+    // Files are streamed into the archive one by one, and the manifest follows once all are known.
+    let mut files = Vec::new();
+    for entry in WalkDir::new(&git_dir).sort_by_file_name() {
+        let entry = entry?;
+        if !entry.file_type().is_file() {
+            continue;
+        }
+        let relative = entry.path().strip_prefix(&git_dir)?;
+        let name = relative
+            .components()
+            .map(|c| c.as_os_str().to_string_lossy())
+            .collect::<Vec<_>>()
+            .join("/");
+        if name.ends_with(".lock") {
+            continue;
+        }
+        let path = format!("{GIT_PREFIX}{name}");
+        let mut file = fs::File::open(entry.path())
+            .with_context(|| format!("failed to read {}", entry.path().display()))?;
+        zip.start_file(path.as_str(), options)?;
+        let mut writer = HashingWriter {
+            inner: &mut zip,
+            hasher: Sha256::new(),
+        };
+        let size = io::copy(&mut file, &mut writer)
+            .with_context(|| format!("failed to read {}", entry.path().display()))?;
+        files.push(ManifestEntry {
+            path,
+            size,
+            sha256: format!("{:x}", writer.hasher.finalize()),
+        });
+    }
+
+    let manifest = serde_json::to_vec_pretty(&Manifest {
+        version: MANIFEST_VERSION,
+        project_id,
+        files,
+    })?;
+    zip.start_file(MANIFEST_NAME, options)?;
+    zip.write_all(&manifest)?;
+    if let Some(signing_key) = signing_key {
+        let signature = sign(&manifest, signing_key)?;
+        zip.start_file(SIGNATURE_NAME, options)?;
+        zip.write_all(&signature)?;
+    }
+    zip.finish()?;
+
+    let file_name = match signing_key {
+        Some(_) => format!("state-{}.signed.zip", sha256_hex(&manifest)),
+        None => format!("state-{}.zip", sha256_hex(&manifest)),
+    };
+    let archive_path = archives_dir.join(file_name);
+    tmp.persist(&archive_path)?;
+    Ok(archive_path)
+}
+
+/// Checks that every file of the archive matches the manifest, and that no file is missing or was added.
+/// If the archive is signed, the signature must be valid as well, and the fingerprint of the key that
+/// signed it is returned. Whether that key is trusted is up to the caller.
+pub(crate) fn verify(archive_path: &Path) -> Result<Option<String>> {
+    let mut zip = ZipArchive::new(fs::File::open(archive_path)?)?;
+    verify_archive(&mut zip).map(|(_, signed_by)| signed_by)
+}
+
+/// Verifies the archive and restores the repository it contains into `target_dir`, which must not exist
+/// or be empty, checking out the archived `HEAD`.
+pub(crate) fn restore(archive_path: &Path, target_dir: &Path) -> Result<()> {
+    let mut zip = ZipArchive::new(fs::File::open(archive_path)?)?;
+    let (manifest, _) = verify_archive(&mut zip)?;
+
+    if target_dir.exists() && fs::read_dir(target_dir)?.next().is_some() {
+        bail!("{} is not empty", target_dir.display());
+    }
+
+    let git_dir = target_dir.join(".git");
+    for entry in &manifest.files {
+        let relative = entry
+            .path
+            .strip_prefix(GIT_PREFIX)
+            .ok_or_else(|| anyhow!("unexpected archive entry {}", entry.path))?;
+        if relative
+            .split('/')
+            .any(|c| c.is_empty() || c == "." || c == "..")
+        {
+            bail!("unsafe archive entry {}", entry.path);
+        }
+        let path = git_dir.join(relative);
+        if let Some(parent) = path.parent() {
+            fs::create_dir_all(parent)?;
+        }
+        let mut file = zip.by_name(&entry.path)?;
+        io::copy(&mut file, &mut fs::File::create(&path)?)?;
+    }
+
+    let repo = git2::Repository::open(target_dir).context("failed to open restored repository")?;
+    if repo.head().is_ok() {
+        repo.checkout_head(Some(git2::build::CheckoutBuilder::new().force()))
+            .context("failed to checkout restored HEAD")?;
+    }
+    Ok(())
+}
+
+/// Returns the manifest of the verified archive, along with the fingerprint of the key that signed it, if any.
+fn verify_archive<R: io::Read + io::Seek>(
+    zip: &mut ZipArchive<R>,
+) -> Result<(Manifest, Option<String>)> {
+    let manifest_content = read_entry(zip, MANIFEST_NAME).context("archive has no manifest")?;
+    let manifest: Manifest =
+        serde_json::from_slice(&manifest_content).context("archive manifest is malformed")?;
+    if manifest.version != MANIFEST_VERSION {
+        bail!("unsupported archive version {}", manifest.version);
+    }
+
+    for entry in &manifest.files {
+        let mut file = zip
+            .by_name(&entry.path)
+            .with_context(|| format!("{} is missing from the archive", entry.path))?;
+        let mut hasher = Sha256::new();
+        let size = io::copy(&mut file, &mut hasher)?;
+        if size != entry.size || format!("{:x}", hasher.finalize()) != entry.sha256 {
+            bail!("{} does not match the archive manifest", entry.path);
+        }
+    }
+
+    let signed_by = if zip.file_names().any(|name| name == SIGNATURE_NAME) {
+        let signature = read_entry(zip, SIGNATURE_NAME)?;
+        Some(verify_signature(&manifest_content, &signature)?)
+    } else {
+        None
+    };
+
+    let expected = manifest.files.len() + 1 + usize::from(signed_by.is_some());
+    if zip.len() != expected {
+        let unexpected = zip
+            .file_names()
+            .find(|name| {
+                *name != MANIFEST_NAME
+                    && *name != SIGNATURE_NAME
+                    && !manifest.files.iter().any(|e| e.path == *name)
+            })
+            .unwrap_or_default()
+            .to_owned();
+        bail!("archive contains unexpected entry {unexpected}");
+    }
+
+    Ok((manifest, signed_by))
+}
+
+fn read_entry<R: io::Read + io::Seek>(zip: &mut ZipArchive<R>, name: &str) -> Result<Vec<u8>> {
+    let mut file = zip.by_name(name)?;
+    let mut content = Vec::new();
+    file.read_to_end(&mut content)?;
+    Ok(content)
+}
+
+/// Sign `data` with the private SSH key at `key_path`, returning the armored signature.
+fn sign(data: &[u8], key_path: &Path) -> Result<Vec<u8>> {
+    run_ssh_keygen(
+        Command::new("ssh-keygen")
+            .args(["-Y", "sign", "-n", SIGNATURE_NAMESPACE, "-f"])
+            .arg(key_path),
+        data,
+    )
+    .context("failed to sign the archive")
+}
+
+/// Check that `signature` is a valid signature of `data`, and return the fingerprint of the key that made it.
+fn verify_signature(data: &[u8], signature: &[u8]) -> Result<String> {
+    let mut signature_file = tempfile::NamedTempFile::new()?;
+    signature_file.write_all(signature)?;
+    let output = run_ssh_keygen(
+        Command::new("ssh-keygen")
+            .args(["-Y", "check-novalidate", "-n", SIGNATURE_NAMESPACE, "-s"])
+            .arg(signature_file.path()),
+        data,
+    )
+    .context("the signature of the archive is invalid")?;
+    // It reports something like `Good "namespace" signature with ED25519 key SHA256:…`.
+    String::from_utf8_lossy(&output)
+        .split_whitespace()
+        .last()
+        .map(ToOwned::to_owned)
+        .context("ssh-keygen didn't report the signing key")
+}
+
+/// Run `command`, passing `stdin` to it, and return what it printed, or an error with what it complained about.
+fn run_ssh_keygen(command: &mut Command, stdin: &[u8]) -> Result<Vec<u8>> {
+    let mut child = command
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .spawn()
+        .context("failed to run ssh-keygen")?;
+    child
+        .stdin
+        .take()
+        .context("stdin of ssh-keygen is piped")?
+        .write_all(stdin)?;
+    let output = child.wait_with_output()?;
+    if !output.status.success() {
+        bail!("{}", String::from_utf8_lossy(&output.stderr).trim());
+    }
+    Ok(output.stdout)
+}
+
+/// Passes everything written to it on to `inner`, while hashing it.
+struct HashingWriter<W> {
+    inner: W,
+    hasher: Sha256,
+}
+
+impl<W: Write> Write for HashingWriter<W> {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        let written = self.inner.write(buf)?;
+        self.hasher.update(&buf[..written]);
+        Ok(written)
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        self.inner.flush()
+    }
+}
+
+fn sha256_hex(content: &[u8]) -> String {
+    format!("{:x}", Sha256::digest(content))
+}
+
+#[cfg(test)]
+mod tests;
@@ -0,0 +1,136 @@
+use super::*;
+
+use tempfile::tempdir;
+
+fn project_with_state() -> tempfile::TempDir {
+    let dir = tempdir().unwrap();
+    let repo = git2::Repository::init(dir.path()).unwrap();
+    fs::write(dir.path().join("file.txt"), "content").unwrap();
+    let mut index = repo.index().unwrap();
+    index.add_path(Path::new("file.txt")).unwrap();
+    index.write().unwrap();
+    let tree = repo.find_tree(index.write_tree().unwrap()).unwrap();
+    let signature =
+        git2::Signature::new("test", "test@example.com", &git2::Time::new(0, 0)).unwrap();
+    repo.commit(Some("HEAD"), &signature, &signature, "initial", &tree, &[])
+        .unwrap();
+
+    let gb_dir = dir.path().join(".git").join("gitbutler");
+    fs::create_dir_all(&gb_dir).unwrap();
+    fs::write(gb_dir.join("virtual_branches.toml"), "[branches]\n").unwrap();
+    dir
+}
+
+#[test]
+fn verifies_and_restores_an_equivalent_project() {
+    let project = project_with_state();
+    let cache = tempdir().unwrap();
+    let archive_path = archive(cache.path(), ProjectId::generate(), project.path(), None).unwrap();
+
+    assert_eq!(verify(&archive_path).unwrap(), None);
+
+    let restored = tempdir().unwrap();
+    let target = restored.path().join("project");
+    restore(&archive_path, &target).unwrap();
+
+    let original = git2::Repository::open(project.path()).unwrap();
+    let copy = git2::Repository::open(&target).unwrap();
+    assert_eq!(
+        original.head().unwrap().target(),
+        copy.head().unwrap().target()
+    );
+    assert_eq!(
+        fs::read_to_string(target.join("file.txt")).unwrap(),
+        "content"
+    );
+    assert_eq!(
+        fs::read(target.join(".git/gitbutler/virtual_branches.toml")).unwrap(),
+        fs::read(project.path().join(".git/gitbutler/virtual_branches.toml")).unwrap()
+    );
+}
+
+#[test]
+fn same_state_produces_identical_archives() {
+    let project = project_with_state();
+    let project_id = ProjectId::generate();
+    let first_cache = tempdir().unwrap();
+    let second_cache = tempdir().unwrap();
+
+    let first = archive(first_cache.path(), project_id, project.path(), None).unwrap();
+    let second = archive(second_cache.path(), project_id, project.path(), None).unwrap();
+
+    assert_eq!(first.file_name(), second.file_name());
+    assert_eq!(fs::read(first).unwrap(), fs::read(second).unwrap());
+}
+
+#[test]
+fn tampered_archive_fails_verification() {
+    let project = project_with_state();
+    let cache = tempdir().unwrap();
+    let archive_path = archive(cache.path(), ProjectId::generate(), project.path(), None).unwrap();
+
+    // rewrite the archive with a modified HEAD but the original manifest
+    let mut original = ZipArchive::new(fs::File::open(&archive_path).unwrap()).unwrap();
+    let tampered_path = cache.path().join("tampered.zip");
+    let mut tampered = ZipWriter::new(fs::File::create(&tampered_path).unwrap());
+    for i in 0..original.len() {
+        let mut file = original.by_index(i).unwrap();
+        let name = file.name().to_owned();
+        let mut content = Vec::new();
+        file.read_to_end(&mut content).unwrap();
+        if name == "git/HEAD" {
+            content = b"ref: refs/heads/other\n".to_vec();
+        }
+        tampered.start_file(name, FileOptions::default()).unwrap();
+        tampered.write_all(&content).unwrap();
+    }
+    tampered.finish().unwrap();
+
+    let err = verify(&tampered_path).unwrap_err();
+    assert!(format!("{err:#}").contains("git/HEAD does not match"));
+    let target = tempdir().unwrap();
+    restore(&tampered_path, target.path()).unwrap_err();
+}
+
+#[test]
+fn signed_archives_are_verified_with_their_signature() {
+    let project = project_with_state();
+    let cache = tempdir().unwrap();
+    let key = cache.path().join("key");
+    let status = std::process::Command::new("ssh-keygen")
+        .args(["-q", "-t", "ed25519", "-N", "", "-f"])
+        .arg(&key)
+        .status()
+        .unwrap();
+    assert!(status.success());
+
+    let archive_path = archive(
+        cache.path(),
+        ProjectId::generate(),
+        project.path(),
+        Some(&key),
+    )
+    .unwrap();
+    let fingerprint = verify(&archive_path).unwrap().expect("archive is signed");
+    assert!(fingerprint.starts_with("SHA256:"));
+
+    // replace the signature with one of different data
+    let mut original = ZipArchive::new(fs::File::open(&archive_path).unwrap()).unwrap();
+    let tampered_path = cache.path().join("tampered.zip");
+    let mut tampered = ZipWriter::new(fs::File::create(&tampered_path).unwrap());
+    for i in 0..original.len() {
+        let mut file = original.by_index(i).unwrap();
+        let name = file.name().to_owned();
+        let mut content = Vec::new();
+        file.read_to_end(&mut content).unwrap();
+        if name == SIGNATURE_NAME {
+            content = sign(b"something else", &key).unwrap();
+        }
+        tampered.start_file(name, FileOptions::default()).unwrap();
+        tampered.write_all(&content).unwrap();
+    }
+    tampered.finish().unwrap();
+
+    let err = verify(&tampered_path).unwrap_err();
+    assert!(format!("{err:#}").contains("signature of the archive is invalid"));
+}
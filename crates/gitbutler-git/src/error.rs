@@ -27,4 +27,8 @@ pub enum Error<BE: std::error::Error + core::fmt::Debug + Send + Sync + 'static>
     /// the remote already existed.
     #[error("remote already exists: {0}")]
     RemoteExists(String, #[source] BE),
+    /// A shallow fetch was requested, but the remote doesn't support
+    /// shallow clients.
+    #[error("the remote doesn't support shallow fetches: {0}")]
+    ShallowNotSupported(String, #[source] BE),
 }
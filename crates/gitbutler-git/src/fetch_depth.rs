@@ -0,0 +1,36 @@
+/// How much history to fetch, to create or extend a shallow clone.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(
+    feature = "serde",
+    derive(serde::Serialize, serde::Deserialize),
+    serde(rename_all = "camelCase")
+)]
+pub enum FetchDepth {
+    /// Fetch at most the given amount of commits from the tip of each fetched ref,
+    /// like `git fetch --depth`.
+    Depth(u32),
+    /// Fetch the given amount of commits beyond the current boundary of a shallow clone,
+    /// like `git fetch --deepen`.
+    Deepen(u32),
+}
+
+impl FetchDepth {
+    /// The argument to pass to `git fetch`.
+    pub(crate) fn to_arg(self) -> String {
+        match self {
+            FetchDepth::Depth(depth) => format!("--depth={depth}"),
+            FetchDepth::Deepen(depth) => format!("--deepen={depth}"),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn to_arg() {
+        assert_eq!(FetchDepth::Depth(1).to_arg(), "--depth=1");
+        assert_eq!(FetchDepth::Deepen(50).to_arg(), "--deepen=50");
+    }
+}
@@ -15,6 +15,7 @@ compile_error!("BUG: in production code this flag should not be set, nor do we r
 
 mod error;
 pub(crate) mod executor;
+mod fetch_depth;
 mod refspec;
 mod repository;
 
@@ -23,6 +24,7 @@ pub use self::executor::tokio;
 
 pub use self::{
     error::Error,
+    fetch_depth::FetchDepth,
     refspec::{Error as RefSpecError, RefSpec},
-    repository::{fetch, push, sign_commit},
+    repository::{fetch, ls_remote, push, sign_commit},
 };
@@ -1,5 +1,5 @@
 use super::executor::{AskpassServer, GitExecutor, Pid, Socket};
-use crate::RefSpec;
+use crate::{FetchDepth, RefSpec};
 use futures::{select, FutureExt};
 use rand::Rng;
 use std::{collections::HashMap, path::Path, time::Duration};
@@ -273,15 +273,16 @@ where
 }
 
 /// Fetches the given refspec from the given remote in the repository
-/// at the given path. Any prompts for the user are passed to the asynchronous
-/// callback `on_prompt` which should return the user's response or `None` if the
-/// operation should be aborted, in which case an `Err` value is returned from this
-/// function.
+/// at the given path, limited to `depth` if set. Any prompts for the user are
+/// passed to the asynchronous callback `on_prompt` which should return the user's
+/// response or `None` if the operation should be aborted, in which case an `Err`
+/// value is returned from this function.
 pub async fn fetch<P, F, Fut, E, Extra>(
     repo_path: P,
     executor: E,
     remote: &str,
     refspec: RefSpec,
+    depth: Option<FetchDepth>,
     on_prompt: F,
     extra: Extra,
 ) -> Result<(), crate::Error<Error<E>>>
@@ -295,7 +296,11 @@ where
     let mut args = vec!["fetch", "--quiet", "--prune"];
 
     let refspec = refspec.to_string();
+    let depth = depth.map(FetchDepth::to_arg);
 
+    if let Some(depth) = &depth {
+        args.push(depth);
+    }
     args.push(remote);
     args.push(&refspec);
 
@@ -319,6 +324,16 @@ where
                 stdout,
                 stderr,
             }))?
+        } else if depth.is_some() && is_shallow_unsupported(&stderr) {
+            Err(crate::Error::ShallowNotSupported(
+                remote.to_owned(),
+                Error::<E>::Failed {
+                    status,
+                    args: args.into_iter().map(Into::into).collect(),
+                    stdout,
+                    stderr,
+                },
+            ))?
         } else {
             Err(Error::<E>::Failed {
                 status,
@@ -330,6 +345,50 @@ where
     }
 }
 
+/// Lists the branches of the given remote of the repository at the given path
+/// without fetching anything, which verifies that the remote is reachable and
+/// accepts the credentials. Any prompts for the user are passed to the asynchronous
+/// callback `on_prompt` which should return the user's response or `None` if the
+/// operation should be aborted, in which case an `Err` value is returned from this
+/// function.
+pub async fn ls_remote<P, F, Fut, E, Extra>(
+    repo_path: P,
+    executor: E,
+    remote: &str,
+    on_prompt: F,
+    extra: Extra,
+) -> Result<(), crate::Error<Error<E>>>
+where
+    P: AsRef<Path>,
+    E: GitExecutor,
+    F: FnMut(String, Extra) -> Fut,
+    Fut: std::future::Future<Output = Option<String>>,
+    Extra: Send + Clone,
+{
+    let args = vec!["ls-remote", "--quiet", "--heads", remote];
+
+    let (status, stdout, stderr) =
+        execute_with_auth_harness(repo_path, &executor, &args, None, on_prompt, extra).await?;
+
+    if status == 0 {
+        Ok(())
+    } else if stderr.to_lowercase().contains("permission denied") {
+        Err(crate::Error::AuthorizationFailed(Error::<E>::Failed {
+            status,
+            args: args.into_iter().map(Into::into).collect(),
+            stdout,
+            stderr,
+        }))?
+    } else {
+        Err(Error::<E>::Failed {
+            status,
+            args: args.into_iter().map(Into::into).collect(),
+            stdout,
+            stderr,
+        })?
+    }
+}
+
 /// Pushes a refspec to the given remote in the repository at the given path.
 /// Any prompts for the user are passed to the asynchronous callback `on_prompt`,
 /// which should return the user's response or `None` if the operation should be
@@ -520,3 +579,10 @@ async fn get_core_sshcommand<E: GitExecutor, P: AsRef<Path>>(
         })
         .unwrap_or(None)
 }
+
+/// Return `true` if `stderr` of a fetch says that the remote, or the transport to it,
+/// doesn't support shallow clients.
+fn is_shallow_unsupported(stderr: &str) -> bool {
+    let stderr = stderr.to_lowercase();
+    stderr.contains("shallow") && (stderr.contains("not support") || stderr.contains("unsupported"))
+}
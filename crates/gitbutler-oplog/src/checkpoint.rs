@@ -0,0 +1,52 @@
+use std::path::PathBuf;
+
+use anyhow::Result;
+use gitbutler_project::Project;
+use serde::{Deserialize, Serialize};
+
+use crate::entry::Checkpoint;
+
+/// The name of the file in the GitButler directory of a project that holds its checkpoints.
+const CHECKPOINTS_FILE_NAME: &str = "checkpoints.toml";
+/// The prefix of the references that keep the snapshots of checkpoints reachable.
+const CHECKPOINT_REFS_PREFIX: &str = "refs/gitbutler/checkpoints/";
+
+#[derive(Default, Serialize, Deserialize)]
+struct Checkpoints {
+    #[serde(default)]
+    checkpoints: Vec<Checkpoint>,
+}
+
+fn path(project: &Project) -> PathBuf {
+    project.gb_dir().join(CHECKPOINTS_FILE_NAME)
+}
+
+/// Returns the checkpoints of `project`, in the order they were stored.
+pub(crate) fn read(project: &Project) -> Result<Vec<Checkpoint>> {
+    let checkpoints: Checkpoints = gitbutler_fs::read_toml_file_or_default(&path(project))?;
+    Ok(checkpoints.checkpoints)
+}
+
+/// Returns the reference that keeps the snapshot of the checkpoint `name` reachable.
+///
+/// Checkpoint names are free text, so every byte that isn't alphanumeric, `-` or `_` is percent-encoded to
+/// obtain a valid reference name that is distinct for each checkpoint.
+pub(crate) fn refname(name: &str) -> String {
+    let mut refname = CHECKPOINT_REFS_PREFIX.to_owned();
+    for byte in name.bytes() {
+        if byte.is_ascii_alphanumeric() || byte == b'-' || byte == b'_' {
+            refname.push(char::from(byte));
+        } else {
+            refname.push_str(&format!("%{byte:02X}"));
+        }
+    }
+    refname
+}
+
+/// Replaces the checkpoints of `project` with `checkpoints`.
+pub(crate) fn write(project: &Project, checkpoints: &[Checkpoint]) -> Result<()> {
+    let checkpoints = Checkpoints {
+        checkpoints: checkpoints.to_vec(),
+    };
+    gitbutler_fs::write(path(project), toml::to_string(&checkpoints)?)
+}
@@ -1,5 +1,6 @@
 use anyhow::anyhow;
 use anyhow::Result;
+use gitbutler_branch::BranchId;
 use itertools::Itertools;
 use serde::Deserialize;
 use std::fmt;
@@ -32,6 +33,118 @@ pub struct Snapshot {
     pub details: Option<SnapshotDetails>,
 }
 
+/// A virtual branch that was deleted and can still be recovered from the snapshot taken right before its deletion.
+#[derive(Debug, PartialEq, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct RecoverableBranch {
+    /// The id the branch had before it was deleted
+    pub id: BranchId,
+    /// The name the branch had before it was deleted
+    pub name: String,
+    /// The last known head commit of the branch
+    #[serde(with = "gitbutler_serde::serde::oid")]
+    pub head: git2::Oid,
+    /// The id of the snapshot that was taken right before the branch was deleted, and that restores it
+    #[serde(with = "gitbutler_serde::serde::oid")]
+    pub snapshot_id: git2::Oid,
+    /// The time at which the branch was deleted, in seconds from Unix epoch.
+    #[serde(serialize_with = "gitbutler_serde::serde::as_time_seconds_from_unix_epoch")]
+    pub deleted_at: git2::Time,
+}
+
+/// The content of a worktree file as of a snapshot.
+#[derive(Debug, PartialEq, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct FileAtSnapshot {
+    /// The content of the file
+    pub content: Vec<u8>,
+    /// `true` if the content isn't text
+    pub binary: bool,
+}
+
+/// How a file changed between two snapshots.
+#[derive(Debug, PartialEq, Eq, Clone, Copy, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub enum FileChangeKind {
+    Added,
+    Modified,
+    Deleted,
+    Renamed,
+}
+
+/// A worktree file that changed between two snapshots.
+#[derive(Debug, PartialEq, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct FileChange {
+    /// The path of the file, or the path it was deleted from
+    pub path: PathBuf,
+    /// The path of the file before it was renamed, if it was
+    pub previous_path: Option<PathBuf>,
+    pub kind: FileChangeKind,
+    /// The amount of added lines, always `0` for binary files
+    pub lines_added: usize,
+    /// The amount of removed lines, always `0` for binary files
+    pub lines_removed: usize,
+    /// `true` if the content isn't text
+    pub binary: bool,
+}
+
+/// A restore of a snapshot that was started but never finished, for instance as the application was closed,
+/// which may leave the worktree and the virtual branches half-restored.
+#[derive(Debug, PartialEq, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct IncompleteRestore {
+    /// The snapshot that was being restored
+    #[serde(with = "gitbutler_serde::serde::oid")]
+    pub snapshot_id: git2::Oid,
+    /// A commit holding the state right before the restore started, to roll back to.
+    /// It isn't part of the oplog, so it's only kept until git prunes unreachable objects.
+    #[serde(with = "gitbutler_serde::serde::oid")]
+    pub rollback_id: git2::Oid,
+    /// The time the restore started
+    pub started_at: std::time::SystemTime,
+}
+
+/// A named restore point, capturing the working directory, the virtual branches and which of them are applied.
+#[derive(Debug, PartialEq, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Checkpoint {
+    pub name: String,
+    /// The snapshot holding the state, which also was the head of the oplog when the checkpoint was created.
+    #[serde(with = "gitbutler_serde::serde::oid")]
+    pub snapshot_id: git2::Oid,
+    /// The time the checkpoint was created
+    pub created_at: std::time::SystemTime,
+}
+
+/// How to deal with an [`IncompleteRestore`].
+#[derive(Debug, PartialEq, Eq, Clone, Copy, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum RestoreResolution {
+    /// Restore the snapshot again, from the start.
+    Complete,
+    /// Restore the state from right before the restore.
+    Rollback,
+}
+
+/// The outcome of repairing the chain of snapshots of the oplog.
+#[derive(Debug, Default, PartialEq, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct OplogRepair {
+    /// The oplog head before the repair
+    #[serde(with = "gitbutler_serde::serde::oid_opt")]
+    pub previous_head: Option<git2::Oid>,
+    /// The oplog head after the repair, which is the same as `previous_head` if nothing had to be repaired
+    #[serde(with = "gitbutler_serde::serde::oid_opt")]
+    pub head: Option<git2::Oid>,
+    /// The amount of snapshots that were dropped as they repeated the snapshot right before them
+    pub duplicates_removed: usize,
+    /// The amount of commits in the chain that weren't snapshots and were dropped
+    pub non_snapshots_removed: usize,
+    /// The amount of snapshots with more than one parent, whose ancestry was linearized
+    pub merges_linearized: usize,
+}
+
 /// The payload of a snapshot commit
 ///
 /// This is persisted as a commit message in the title, body and trailers format (<https://git-scm.com/docs/git-interpret-trailers>)
@@ -65,6 +178,11 @@ impl SnapshotDetails {
         self.trailers = trailers;
         self
     }
+    /// Use `title` instead of the name of the operation, which must be a single line.
+    pub fn with_title(mut self, title: String) -> Self {
+        self.title = title;
+        self
+    }
 }
 
 impl FromStr for SnapshotDetails {
@@ -156,6 +274,12 @@ pub enum OperationKind {
     InsertBlankCommit,
     MoveCommitFile,
     FileChanges,
+    ResolveConflict,
+    TriggeredFileChange,
+    ReparentCommit,
+    SaveWorkspace,
+    SwitchWorkspace,
+    RepairProject,
     #[default]
     Unknown,
 }
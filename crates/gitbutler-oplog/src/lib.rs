@@ -1,10 +1,17 @@
 pub mod entry;
 mod oplog;
 pub use oplog::OplogExt;
+mod checkpoint;
 mod reflog;
+mod restore;
 mod snapshot;
 pub use snapshot::SnapshotExt;
 mod state;
 
 /// The name of the file holding our state, useful for watching for changes.
 pub const OPLOG_FILE_NAME: &str = "operations-log.toml";
+
+/// How long deleted branches remain recoverable, matching the default `gc.pruneExpire` of git after which
+/// their then unreachable commits may be garbage collected.
+pub const BRANCH_RECOVERY_RETENTION: std::time::Duration =
+    std::time::Duration::from_secs(14 * 24 * 60 * 60);
@@ -2,20 +2,26 @@ use anyhow::{anyhow, bail, Context};
 use git2::{DiffOptions, FileMode};
 use gitbutler_branch::{Branch, VirtualBranchesHandle, VirtualBranchesState};
 use gitbutler_diff::{hunks_by_filepath, FileDiff};
-use gitbutler_project::Project;
-use gitbutler_repo::RepositoryExt;
-use std::collections::HashMap;
+use gitbutler_project::{Project, RetentionPolicy, SnapshotStorage};
+use gitbutler_repo::{RepositoryExt, SNAPSHOT_FILE_LIMIT_BYTES};
+use std::collections::{HashMap, HashSet};
+use std::io::Write;
 use std::path::Path;
 use std::str::{from_utf8, FromStr};
-use std::time::Duration;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
 use std::{fs, path::PathBuf};
 
 use anyhow::Result;
 use tracing::instrument;
 
 use super::{
-    entry::{OperationKind, Snapshot, SnapshotDetails, Trailer},
+    checkpoint,
+    entry::{
+        Checkpoint, FileAtSnapshot, FileChange, FileChangeKind, IncompleteRestore, OperationKind,
+        OplogRepair, RecoverableBranch, RestoreResolution, Snapshot, SnapshotDetails, Trailer,
+    },
     reflog::set_reference_to_oplog,
+    restore,
     state::OplogHandle,
 };
 use gitbutler_branch::{
@@ -23,7 +29,15 @@ use gitbutler_branch::{
 };
 use gitbutler_project::access::{WorktreeReadPermission, WorktreeWritePermission};
 
-const SNAPSHOT_FILE_LIMIT_BYTES: u64 = 32 * 1024 * 1024;
+const STASH_REFERENCE: &str = "refs/stash";
+/// The prefix of the references that keep previous oplog heads reachable after the oplog was rewritten.
+const OPLOG_BACKUP_REFS_PREFIX: &str = "refs/gitbutler/oplog-backups/";
+/// Packs of [packed snapshots](SnapshotStorage::Packed) smaller than this are consolidated once there are
+/// too many of them.
+const SMALL_PACK_SIZE_BYTES: u64 = 8 * 1024 * 1024;
+/// The amount of small packs beyond which they are consolidated into one, like `gc.autoPackLimit` does for
+/// `git gc --auto`, as every pack slows down object lookups.
+const MAX_SMALL_PACKS: usize = 50;
 
 /// The Oplog allows for crating snapshots of the current state of the project as well as restoring to a previous snapshot.
 /// Snapshots include the state of the working directory as well as all additional GitButler state (e.g. virtual branches, conflict state).
@@ -33,6 +47,9 @@ const SNAPSHOT_FILE_LIMIT_BYTES: u64 = 32 * 1024 * 1024;
 /// .
 /// ├── conflicts/…
 /// ├── index/
+/// ├── stash (only if stash snapshots are enabled for the project)
+/// │   ├── commits/…
+/// │   └── reflog
 /// ├── target_tree/…
 /// ├── virtual_branches
 /// │   └── [branch-id]
@@ -79,6 +96,13 @@ pub trait OplogExt {
         perm: &mut WorktreeWritePermission,
     ) -> Result<Option<git2::Oid>>;
 
+    /// Creates a [`FileChanges`](OperationKind::FileChanges) snapshot titled `label` right away, as a checkpoint
+    /// to return to with [`restore_snapshot`](Self::restore_snapshot), independently of any automatic snapshots.
+    /// Returns the sha of the snapshot commit.
+    ///
+    /// `label` is trimmed and must be a single, non-empty line.
+    fn create_named_snapshot(&self, label: &str) -> Result<git2::Oid>;
+
     /// Lists the snapshots that have been created for the given repository, up to the given limit,
     /// and with the most recent snapshot first, and at the end of the vec.
     ///
@@ -103,11 +127,34 @@ pub trait OplogExt {
     ///  - The state of the working directory is checked out from the subtree `workdir` in the snapshot.
     ///  - The state of virtual branches is restored from the blob `virtual_branches.toml` in the snapshot.
     ///  - The state of conflicts (.git/base_merge_parent and .git/conflicts) is restored from the subtree `conflicts` in the snapshot (if not present, existing files are deleted).
+    ///  - The `git stash` entries are restored from the subtree `stash` in the snapshot, if it was captured.
     ///
     /// If there are files that are untracked and larger than `SNAPSHOT_FILE_LIMIT_BYTES`, they are excluded from snapshot creation and restoring.
     /// Returns the sha of the created revert snapshot commit or None if snapshots are disabled.
     fn restore_snapshot(&self, snapshot_commit_id: git2::Oid) -> Result<Option<git2::Oid>>;
 
+    /// Like [`restore_snapshot`](Self::restore_snapshot), but for callers that already hold exclusive access
+    /// to the worktree as `perm`, so the restore can be part of a larger operation.
+    fn restore_snapshot_with_permission(
+        &self,
+        snapshot_commit_id: git2::Oid,
+        perm: &mut WorktreeWritePermission,
+    ) -> Result<Option<git2::Oid>>;
+
+    /// Returns the restore that was started but not finished, if any, as a restore is recorded as in progress
+    /// before it changes anything and until it succeeds.
+    fn detect_incomplete_restore(&self) -> Result<Option<IncompleteRestore>>;
+
+    /// Finishes the [incomplete restore](Self::detect_incomplete_restore) by restoring its snapshot again,
+    /// or undoes it by restoring the state from right before it, depending on `resolution`.
+    /// Returns the id of the snapshot that records the restore, like [`restore_snapshot`](Self::restore_snapshot).
+    ///
+    /// Errors if there is no incomplete restore.
+    fn complete_or_rollback_restore(
+        &self,
+        resolution: RestoreResolution,
+    ) -> Result<Option<git2::Oid>>;
+
     /// Determines if a new snapshot should be created due to file changes being created since the last snapshot.
     /// The needs for the automatic snapshotting are:
     ///  - It needs to facilitate backup of work in progress code
@@ -124,13 +171,85 @@ pub trait OplogExt {
     ///      * If the sum of added and removed lines is greater than a configured threshold, return `true`, otherwise return `false`.
     fn should_auto_snapshot(&self, check_if_last_snapshot_older_than: Duration) -> Result<bool>;
 
+    /// Returns the first of the worktree-relative `changed_paths` that matches one of the project's
+    /// [snapshot triggers](Project::snapshot_triggers), if any.
+    /// Changes to such paths warrant a snapshot right away, independently of [`should_auto_snapshot`](Self::should_auto_snapshot).
+    fn snapshot_trigger<'a>(&self, changed_paths: &'a [PathBuf]) -> Option<&'a Path>;
+
     /// Returns the diff of the snapshot and it's parent. It only includes the workdir changes.
     ///
     /// This is useful to show what has changed in this particular snapshot
     fn snapshot_diff(&self, sha: git2::Oid) -> Result<HashMap<PathBuf, FileDiff>>;
 
+    /// Returns the worktree files that changed between the snapshots `from` and `to`, sorted by path.
+    ///
+    /// Only the snapshot trees are read, and renames are detected. Binary files are flagged instead of
+    /// having their lines counted.
+    fn diff_snapshots(&self, from: git2::Oid, to: git2::Oid) -> Result<Vec<FileChange>>;
+
     /// Gets the sha of the last snapshot commit if present.
     fn oplog_head(&self) -> Result<Option<git2::Oid>>;
+
+    /// Lists the paths of all files the working directory had as of the snapshot `sha`, sorted.
+    ///
+    /// This is read-only, neither the working directory nor the oplog are changed.
+    fn preview_snapshot(&self, sha: git2::Oid) -> Result<Vec<PathBuf>>;
+
+    /// Returns the content of the file at the worktree-relative `path` as of the snapshot `sha`.
+    ///
+    /// This is read-only, neither the working directory nor the oplog are changed.
+    fn snapshot_file_content(&self, sha: git2::Oid, path: &Path) -> Result<FileAtSnapshot>;
+
+    /// Writes the file at the worktree-relative `path` back into the working directory as it was in the
+    /// snapshot `sha`, leaving all other files alone. The change is then assigned to a virtual branch
+    /// like any other uncommitted change.
+    ///
+    /// A snapshot of the state right before is created first so the restore can be undone, and its sha is returned.
+    /// Errors without changing anything if `path` wasn't a file in the snapshot.
+    fn restore_snapshot_file(&self, sha: git2::Oid, path: &Path) -> Result<Option<git2::Oid>>;
+
+    /// Lists the virtual branches that were deleted no longer than `retention` ago and that can still be
+    /// recovered, most recently deleted first.
+    ///
+    /// Branches are found through the snapshots taken right before their deletion. Branches that are back in
+    /// the workspace, or whose head commit doesn't exist anymore (e.g. as it was garbage collected) are skipped.
+    fn list_recoverable_branches(&self, retention: Duration) -> Result<Vec<RecoverableBranch>>;
+
+    /// Rebuilds the chain of snapshots reachable from the oplog head so it is linear again, which is what
+    /// [`list_snapshots`](Self::list_snapshots) and undo rely on.
+    ///
+    /// Snapshots that merely repeat their predecessor and commits that aren't snapshots are dropped,
+    /// and snapshots with multiple parents have their ancestry linearized so all of them remain reachable.
+    /// The state of the oplog is backed up, and its previous head kept reachable by a reference, before the head
+    /// is changed.
+    ///
+    /// Nothing is rewritten if the chain is already linear and free of duplicates.
+    fn repair_oplog_chain(&self, perm: &mut WorktreeWritePermission) -> Result<OplogRepair>;
+
+    /// Drops the oldest snapshots that aren't retained by `policy`, so the oplog doesn't grow without bounds.
+    /// Returns the amount of snapshots that were dropped.
+    ///
+    /// The most recent snapshot is always kept, and so are the target of the most recent restore and all
+    /// snapshots after it. The kept snapshots are rewritten so the oldest of them has no parent anymore,
+    /// which changes their shas, so this only happens once the snapshots to drop make up a quarter of the
+    /// kept ones, instead of on every write. The previous oplog head stays reachable through a reference.
+    fn prune_snapshots(&self, policy: RetentionPolicy) -> Result<usize>;
+
+    /// Creates a snapshot and records it as the checkpoint `name`, replacing any checkpoint of the same name,
+    /// all while holding exclusive access to the worktree so nothing can change in between.
+    ///
+    /// `name` is trimmed and must be a single, non-empty line.
+    fn create_checkpoint(&self, name: &str) -> Result<Checkpoint>;
+
+    /// Returns to the checkpoint `name`: the working directory and the virtual branches, including which of
+    /// them are applied, are restored from its snapshot.
+    ///
+    /// Like any restore, this is recorded as a new snapshot, so the snapshots taken after the checkpoint stay
+    /// part of the oplog.
+    fn restore_checkpoint(&self, name: &str) -> Result<Checkpoint>;
+
+    /// Lists the checkpoints of the project, the most recently created first.
+    fn list_checkpoints(&self) -> Result<Vec<Checkpoint>>;
 }
 
 impl OplogExt for Project {
@@ -157,6 +276,15 @@ impl OplogExt for Project {
         commit_snapshot(self, tree_id, details, perm)
     }
 
+    fn create_named_snapshot(&self, label: &str) -> Result<git2::Oid> {
+        self.assure_writable()?;
+        let label = single_line_title(label, "snapshot label")?;
+        let details = SnapshotDetails::new(OperationKind::FileChanges).with_title(label.to_owned());
+        let mut guard = self.exclusive_worktree_access();
+        self.create_snapshot(details, guard.write_permission())?
+            .context("snapshot wasn't created")
+    }
+
     fn list_snapshots(
         &self,
         limit: usize,
@@ -262,10 +390,40 @@ impl OplogExt for Project {
     }
 
     fn restore_snapshot(&self, snapshot_commit_id: git2::Oid) -> Result<Option<git2::Oid>> {
+        self.assure_writable()?;
         let mut guard = self.exclusive_worktree_access();
         restore_snapshot(self, snapshot_commit_id, guard.write_permission())
     }
 
+    fn restore_snapshot_with_permission(
+        &self,
+        snapshot_commit_id: git2::Oid,
+        perm: &mut WorktreeWritePermission,
+    ) -> Result<Option<git2::Oid>> {
+        self.assure_writable()?;
+        restore_snapshot(self, snapshot_commit_id, perm)
+    }
+
+    fn detect_incomplete_restore(&self) -> Result<Option<IncompleteRestore>> {
+        restore::read_marker(self)
+    }
+
+    fn complete_or_rollback_restore(
+        &self,
+        resolution: RestoreResolution,
+    ) -> Result<Option<git2::Oid>> {
+        self.assure_writable()?;
+        let mut guard = self.exclusive_worktree_access();
+        let Some(incomplete) = restore::read_marker(self)? else {
+            bail!("there is no incomplete restore to complete or roll back");
+        };
+        let snapshot_commit_id = match resolution {
+            RestoreResolution::Complete => incomplete.snapshot_id,
+            RestoreResolution::Rollback => incomplete.rollback_id,
+        };
+        restore_snapshot(self, snapshot_commit_id, guard.write_permission())
+    }
+
     fn should_auto_snapshot(&self, check_if_last_snapshot_older_than: Duration) -> Result<bool> {
         let last_snapshot_time = OplogHandle::new(&self.gb_dir()).modified_at()?;
         if last_snapshot_time.elapsed()? <= check_if_last_snapshot_older_than {
@@ -279,6 +437,29 @@ impl OplogExt for Project {
         Ok(lines_since_snapshot(self, &repo)? > self.snapshot_lines_threshold())
     }
 
+    fn snapshot_trigger<'a>(&self, changed_paths: &'a [PathBuf]) -> Option<&'a Path> {
+        let patterns: Vec<_> = self
+            .snapshot_triggers
+            .iter()
+            .filter_map(|pattern| match glob::Pattern::new(pattern) {
+                Ok(glob) => Some((glob, !pattern.contains('/'))),
+                Err(err) => {
+                    tracing::warn!("ignoring invalid snapshot trigger {pattern:?}: {err}");
+                    None
+                }
+            })
+            .collect();
+        changed_paths.iter().map(PathBuf::as_path).find(|path| {
+            patterns.iter().any(|(glob, match_file_name)| {
+                glob.matches_path(path)
+                    || (*match_file_name
+                        && path
+                            .file_name()
+                            .map_or(false, |name| glob.matches_path(Path::new(name))))
+            })
+        })
+    }
+
     fn snapshot_diff(&self, sha: git2::Oid) -> Result<HashMap<PathBuf, FileDiff>> {
         let worktree_dir = self.path.as_path();
         let repo = git2::Repository::init(worktree_dir)?;
@@ -311,12 +492,549 @@ impl OplogExt for Project {
         Ok(hunks)
     }
 
+    fn diff_snapshots(&self, from: git2::Oid, to: git2::Oid) -> Result<Vec<FileChange>> {
+        diff_snapshots(self, from, to)
+    }
+
+    fn list_recoverable_branches(&self, retention: Duration) -> Result<Vec<RecoverableBranch>> {
+        list_recoverable_branches(self, retention)
+    }
+
     /// Gets the sha of the last snapshot commit if present.
     fn oplog_head(&self) -> Result<Option<git2::Oid>> {
         let oplog_state = OplogHandle::new(&self.gb_dir());
         oplog_state.oplog_head()
     }
+
+    fn preview_snapshot(&self, sha: git2::Oid) -> Result<Vec<PathBuf>> {
+        let repo = git2::Repository::open(self.path.as_path())?;
+        let wd_tree = repo.find_tree(tree_from_applied_vbranches(&repo, sha)?)?;
+
+        let mut paths = Vec::new();
+        wd_tree.walk(git2::TreeWalkMode::PreOrder, |root, entry| {
+            if entry.kind() == Some(git2::ObjectType::Blob) {
+                if let Some(name) = entry.name() {
+                    paths.push(PathBuf::from(root).join(name));
+                }
+            }
+            git2::TreeWalkResult::Ok
+        })?;
+        paths.sort();
+        Ok(paths)
+    }
+
+    fn snapshot_file_content(&self, sha: git2::Oid, path: &Path) -> Result<FileAtSnapshot> {
+        let repo = git2::Repository::open(self.path.as_path())?;
+        let wd_tree = repo.find_tree(tree_from_applied_vbranches(&repo, sha)?)?;
+        let entry = wd_tree.get_path(path).map_err(|err| match err.code() {
+            git2::ErrorCode::NotFound => {
+                anyhow!("{} does not exist in snapshot {sha}", path.display())
+            }
+            _ => err.into(),
+        })?;
+        let blob = entry
+            .to_object(&repo)?
+            .into_blob()
+            .map_err(|_| anyhow!("{} is not a file in snapshot {sha}", path.display()))?;
+        Ok(FileAtSnapshot {
+            content: blob.content().to_vec(),
+            binary: blob.is_binary(),
+        })
+    }
+
+    fn restore_snapshot_file(&self, sha: git2::Oid, path: &Path) -> Result<Option<git2::Oid>> {
+        self.assure_writable()?;
+        let mut guard = self.exclusive_worktree_access();
+        restore_snapshot_file(self, sha, path, guard.write_permission())
+    }
+
+    fn repair_oplog_chain(&self, perm: &mut WorktreeWritePermission) -> Result<OplogRepair> {
+        self.assure_writable()?;
+        repair_oplog_chain(self, perm)
+    }
+
+    fn prune_snapshots(&self, policy: RetentionPolicy) -> Result<usize> {
+        self.assure_writable()?;
+        let mut guard = self.exclusive_worktree_access();
+        prune_snapshots(self, policy, guard.write_permission())
+    }
+
+    fn create_checkpoint(&self, name: &str) -> Result<Checkpoint> {
+        self.assure_writable()?;
+        let mut guard = self.exclusive_worktree_access();
+        create_checkpoint(self, name, guard.write_permission())
+    }
+
+    fn restore_checkpoint(&self, name: &str) -> Result<Checkpoint> {
+        self.assure_writable()?;
+        let mut guard = self.exclusive_worktree_access();
+        restore_checkpoint(self, name, guard.write_permission())
+    }
+
+    fn list_checkpoints(&self) -> Result<Vec<Checkpoint>> {
+        let mut checkpoints = checkpoint::read(self)?;
+        checkpoints.sort_by(|a, b| b.created_at.cmp(&a.created_at));
+        Ok(checkpoints)
+    }
+}
+
+/// Trim `title`, the `what` of a snapshot that ends up in the title of its commit, and fail unless it's
+/// a single, non-empty line.
+fn single_line_title<'a>(title: &'a str, what: &str) -> Result<&'a str> {
+    let title = title.trim();
+    if title.is_empty() || title.contains(['\n', '\r']) {
+        bail!("a {what} must be a single, non-empty line");
+    }
+    Ok(title)
+}
+
+fn create_checkpoint(
+    ctx: &Project,
+    name: &str,
+    exclusive_access: &mut WorktreeWritePermission,
+) -> Result<Checkpoint> {
+    let name = single_line_title(name, "checkpoint name")?;
+    let mut details = SnapshotDetails::new(OperationKind::FileChanges).with_title(name.to_owned());
+    details.trailers.push(Trailer {
+        key: "checkpoint".to_string(),
+        value: name.to_owned(),
+    });
+    let tree_id = prepare_snapshot(ctx, exclusive_access.read_permission())?;
+    let snapshot_id = commit_snapshot(ctx, tree_id, details, exclusive_access)?
+        .context("snapshot wasn't created")?;
+    // The oplog may be pruned or repaired later, which must not lose the snapshot of the checkpoint.
+    let repo = git2::Repository::open(ctx.path.as_path())?;
+    repo.reference(
+        &checkpoint::refname(name),
+        snapshot_id,
+        true,
+        &format!("checkpoint '{name}'"),
+    )?;
+
+    let checkpoint = Checkpoint {
+        name: name.to_owned(),
+        snapshot_id,
+        created_at: SystemTime::now(),
+    };
+    let mut checkpoints = checkpoint::read(ctx)?;
+    checkpoints.retain(|existing| existing.name != checkpoint.name);
+    checkpoints.push(checkpoint.clone());
+    checkpoint::write(ctx, &checkpoints)?;
+    Ok(checkpoint)
+}
+
+fn restore_checkpoint(
+    ctx: &Project,
+    name: &str,
+    exclusive_access: &mut WorktreeWritePermission,
+) -> Result<Checkpoint> {
+    let checkpoint = checkpoint::read(ctx)?
+        .into_iter()
+        .find(|checkpoint| checkpoint.name == name.trim())
+        .with_context(|| format!("there is no checkpoint named '{}'", name.trim()))?;
+    restore_snapshot(ctx, checkpoint.snapshot_id, exclusive_access)?;
+    Ok(checkpoint)
+}
+
+fn restore_snapshot_file(
+    ctx: &Project,
+    snapshot_commit_id: git2::Oid,
+    path: &Path,
+    exclusive_access: &mut WorktreeWritePermission,
+) -> Result<Option<git2::Oid>> {
+    let repo = git2::Repository::open(ctx.path.as_path())?;
+    let wd_tree = repo.find_tree(tree_from_applied_vbranches(&repo, snapshot_commit_id)?)?;
+    let entry = wd_tree.get_path(path).map_err(|err| match err.code() {
+        git2::ErrorCode::NotFound => {
+            anyhow!(
+                "{} does not exist in snapshot {snapshot_commit_id}",
+                path.display()
+            )
+        }
+        _ => err.into(),
+    })?;
+    let mode = entry.filemode();
+    if mode != i32::from(FileMode::Blob) && mode != i32::from(FileMode::BlobExecutable) {
+        bail!(
+            "{} is not a regular file in snapshot {snapshot_commit_id}",
+            path.display()
+        );
+    }
+    let blob = repo.find_blob(entry.id())?;
+
+    let details = SnapshotDetails::new(OperationKind::RestoreFromSnapshot)
+        .with_title("Restored file from snapshot".to_string())
+        .with_trailers(vec![
+            Trailer {
+                key: "restored_from".to_string(),
+                value: snapshot_commit_id.to_string(),
+            },
+            Trailer {
+                key: "path".to_string(),
+                value: path.display().to_string(),
+            },
+        ]);
+    let restore_snapshot_id = ctx.create_snapshot(details, exclusive_access)?;
+
+    let worktree_path = ctx.path.join(path);
+    gitbutler_fs::create_dirs_then_write(&worktree_path, blob.content())
+        .with_context(|| format!("failed to write {}", worktree_path.display()))?;
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::PermissionsExt;
+        let mut permissions = fs::metadata(&worktree_path)?.permissions();
+        permissions.set_mode(if mode == i32::from(FileMode::BlobExecutable) {
+            0o755
+        } else {
+            0o644
+        });
+        fs::set_permissions(&worktree_path, permissions)?;
+    }
+    Ok(restore_snapshot_id)
+}
+
+fn repair_oplog_chain(
+    ctx: &Project,
+    _exclusive_access: &mut WorktreeWritePermission,
+) -> Result<OplogRepair> {
+    let oplog_state = OplogHandle::new(&ctx.gb_dir());
+    let Some(previous_head) = oplog_state.oplog_head()? else {
+        return Ok(OplogRepair::default());
+    };
+    let repo = git2::Repository::open(ctx.path.as_path())?;
+
+    let mut revwalk = repo.revwalk()?;
+    revwalk.set_sorting(git2::Sort::TOPOLOGICAL | git2::Sort::TIME | git2::Sort::REVERSE)?;
+    revwalk.push(previous_head)?;
+
+    let mut report = OplogRepair {
+        previous_head: Some(previous_head),
+        head: Some(previous_head),
+        ..Default::default()
+    };
+    // The snapshots to keep, oldest first.
+    let mut chain: Vec<git2::Commit<'_>> = Vec::new();
+    let mut is_linear = true;
+    for commit_id in revwalk {
+        let commit = repo.find_commit(commit_id?)?;
+        if commit.parent_count() > 1 {
+            report.merges_linearized += 1;
+        }
+        if commit.tree()?.get_name("virtual_branches.toml").is_none() {
+            report.non_snapshots_removed += 1;
+            continue;
+        }
+        if chain.last().map_or(false, |previous| {
+            previous.tree_id() == commit.tree_id()
+                && previous.message_bytes() == commit.message_bytes()
+        }) {
+            report.duplicates_removed += 1;
+            continue;
+        }
+        let expected_parent = chain.last().map(git2::Commit::id);
+        is_linear &= commit.parent_ids().eq(expected_parent);
+        chain.push(commit);
+    }
+
+    if is_linear
+        && report.duplicates_removed == 0
+        && report.non_snapshots_removed == 0
+        && report.merges_linearized == 0
+    {
+        return Ok(report);
+    }
+    if chain.is_empty() {
+        bail!("the oplog doesn't contain any snapshot that could be kept");
+    }
+
+    back_up_oplog_head(&repo, &oplog_state, previous_head)
+        .context("failed to back up the oplog state before repairing it")?;
+
+    let mut head: Option<git2::Oid> = None;
+    for commit in &chain {
+        let parent = head.map(|id| repo.find_commit(id)).transpose()?;
+        let new_id = repo.commit(
+            None,
+            &commit.author(),
+            &commit.committer(),
+            from_utf8(commit.message_bytes())?,
+            &commit.tree()?,
+            parent.iter().collect::<Vec<_>>().as_slice(),
+        )?;
+        head = Some(new_id);
+    }
+    let head = head.expect("the chain isn't empty");
+
+    oplog_state.set_oplog_head(head)?;
+    if let Ok(target) = VirtualBranchesHandle::new(ctx.gb_dir()).get_default_target() {
+        set_reference_to_oplog(&ctx.path, target.sha, head)?;
+    }
+
+    report.head = Some(head);
+    Ok(report)
+}
+
+fn diff_snapshots(ctx: &Project, from: git2::Oid, to: git2::Oid) -> Result<Vec<FileChange>> {
+    let repo = git2::Repository::open(ctx.path.as_path())?;
+    let from_tree = repo.find_tree(tree_from_applied_vbranches(&repo, from)?)?;
+    let to_tree = repo.find_tree(tree_from_applied_vbranches(&repo, to)?)?;
+
+    let mut opts = DiffOptions::new();
+    opts.ignore_submodules(true);
+    let mut diff = repo.diff_tree_to_tree(Some(&from_tree), Some(&to_tree), Some(&mut opts))?;
+    diff.find_similar(Some(git2::DiffFindOptions::new().renames(true)))?;
+
+    let mut changes = Vec::new();
+    for idx in 0..diff.deltas().len() {
+        let Some(patch) = git2::Patch::from_diff(&diff, idx)? else {
+            continue;
+        };
+        let delta = patch.delta();
+        let kind = match delta.status() {
+            git2::Delta::Added | git2::Delta::Untracked => FileChangeKind::Added,
+            git2::Delta::Deleted => FileChangeKind::Deleted,
+            git2::Delta::Renamed => FileChangeKind::Renamed,
+            _ => FileChangeKind::Modified,
+        };
+        let path = match kind {
+            FileChangeKind::Deleted => delta.old_file().path(),
+            _ => delta.new_file().path(),
+        }
+        .context("diffed files always have a path")?
+        .to_owned();
+        let previous_path = match kind {
+            FileChangeKind::Renamed => delta.old_file().path().map(Path::to_owned),
+            _ => None,
+        };
+        let binary = delta.flags().is_binary();
+        let (_, lines_added, lines_removed) = if binary {
+            (0, 0, 0)
+        } else {
+            patch.line_stats()?
+        };
+        changes.push(FileChange {
+            path,
+            previous_path,
+            kind,
+            lines_added,
+            lines_removed,
+            binary,
+        });
+    }
+    changes.sort_by(|a, b| a.path.cmp(&b.path));
+    Ok(changes)
+}
+
+fn prune_snapshots(
+    ctx: &Project,
+    policy: RetentionPolicy,
+    _exclusive_access: &mut WorktreeWritePermission,
+) -> Result<usize> {
+    let oplog_state = OplogHandle::new(&ctx.gb_dir());
+    let Some(oplog_head) = oplog_state.oplog_head()? else {
+        return Ok(0);
+    };
+    let repo = git2::Repository::open(ctx.path.as_path())?;
+
+    // The snapshots, most recent first.
+    let mut chain: Vec<git2::Commit<'_>> = Vec::new();
+    let mut commit = Some(repo.find_commit(oplog_head)?);
+    while let Some(current) = commit {
+        commit = current.parents().next();
+        chain.push(current);
+    }
+
+    let mut keep = match policy {
+        RetentionPolicy::KeepLast(count) => count,
+        RetentionPolicy::KeepDays(days) => {
+            let now = SystemTime::now().duration_since(UNIX_EPOCH)?.as_secs() as i64;
+            let cutoff = now.saturating_sub(days.saturating_mul(24 * 60 * 60) as i64);
+            chain
+                .iter()
+                .take_while(|commit| commit.time().seconds() >= cutoff)
+                .count()
+        }
+        RetentionPolicy::KeepUnderBytes(max_bytes) => {
+            let odb = repo.odb()?;
+            let mut seen = HashSet::new();
+            let mut total_bytes = 0u64;
+            let mut count = 0;
+            for commit in &chain {
+                commit
+                    .tree()?
+                    .walk(git2::TreeWalkMode::PreOrder, |_, entry| {
+                        if entry.kind() == Some(git2::ObjectType::Blob) && seen.insert(entry.id()) {
+                            if let Ok((size, _)) = odb.read_header(entry.id()) {
+                                total_bytes += size as u64;
+                            }
+                        }
+                        git2::TreeWalkResult::Ok
+                    })?;
+                if total_bytes > max_bytes {
+                    break;
+                }
+                count += 1;
+            }
+            count
+        }
+    };
+    keep = keep.max(1);
+
+    let latest_restore_target = chain.iter().find_map(|commit| {
+        let details = SnapshotDetails::from_str(commit.message()?).ok()?;
+        if details.operation != OperationKind::RestoreFromSnapshot {
+            return None;
+        }
+        let target = details
+            .trailers
+            .iter()
+            .find(|trailer| trailer.key == "restored_from")?;
+        git2::Oid::from_str(&target.value).ok()
+    });
+    if let Some(position) = latest_restore_target
+        .and_then(|target| chain.iter().position(|commit| commit.id() == target))
+    {
+        keep = keep.max(position + 1);
+    }
+
+    let pruned = chain.len().saturating_sub(keep);
+    // Cutting the tail rewrites all kept snapshots, so wait for enough of them to be worth it.
+    if pruned == 0 || pruned < keep / 4 {
+        return Ok(0);
+    }
+    chain.truncate(keep);
+
+    back_up_oplog_head(&repo, &oplog_state, oplog_head)
+        .context("failed to back up the oplog state before pruning it")?;
+
+    // Rewrite the kept snapshots oldest first, pointing restores at the rewritten targets.
+    let mut rewritten: HashMap<git2::Oid, git2::Oid> = HashMap::new();
+    let mut head: Option<git2::Oid> = None;
+    for commit in chain.iter().rev() {
+        let mut message = from_utf8(commit.message_bytes())?.to_owned();
+        for (old_id, new_id) in &rewritten {
+            message = message.replace(
+                &format!("restored_from: {old_id}"),
+                &format!("restored_from: {new_id}"),
+            );
+        }
+        let parent = head.map(|id| repo.find_commit(id)).transpose()?;
+        let new_id = repo.commit(
+            None,
+            &commit.author(),
+            &commit.committer(),
+            &message,
+            &commit.tree()?,
+            parent.iter().collect::<Vec<_>>().as_slice(),
+        )?;
+        rewritten.insert(commit.id(), new_id);
+        head = Some(new_id);
+    }
+    let head = head.expect("at least one snapshot is kept");
+
+    oplog_state.set_oplog_head(head)?;
+    if let Ok(target) = VirtualBranchesHandle::new(ctx.gb_dir()).get_default_target() {
+        set_reference_to_oplog(&ctx.path, target.sha, head)?;
+    }
+    Ok(pruned)
+}
+
+/// Back up the oplog state, and keep its `head` reachable under [`OPLOG_BACKUP_REFS_PREFIX`] before the head
+/// is moved, so the snapshots it leads to can't be lost.
+fn back_up_oplog_head(
+    repo: &git2::Repository,
+    oplog_state: &OplogHandle,
+    head: git2::Oid,
+) -> Result<()> {
+    oplog_state.backup()?;
+    repo.reference(
+        &format!("{OPLOG_BACKUP_REFS_PREFIX}{head}"),
+        head,
+        true,
+        "backup of the oplog head",
+    )?;
+    Ok(())
 }
+
+fn list_recoverable_branches(ctx: &Project, retention: Duration) -> Result<Vec<RecoverableBranch>> {
+    let Some(oplog_head) = OplogHandle::new(&ctx.gb_dir()).oplog_head()? else {
+        return Ok(vec![]);
+    };
+    let repo = git2::Repository::open(ctx.path.as_path())?;
+    let vb_state = VirtualBranchesHandle::new(ctx.gb_dir());
+    let in_workspace: HashSet<_> = vb_state
+        .list_branches_in_workspace()?
+        .into_iter()
+        .map(|branch| branch.id)
+        .collect();
+
+    let cutoff = SystemTime::now()
+        .checked_sub(retention)
+        .and_then(|cutoff| cutoff.duration_since(UNIX_EPOCH).ok())
+        .map_or(0, |cutoff| cutoff.as_secs() as i64);
+
+    let mut revwalk = repo.revwalk()?;
+    revwalk.push(oplog_head)?;
+
+    let mut seen = HashSet::new();
+    let mut recoverable = Vec::new();
+    for commit_id in revwalk {
+        let commit = repo.find_commit(commit_id?)?;
+        if commit.time().seconds() <= cutoff {
+            break;
+        }
+
+        let Some(details) = commit
+            .message()
+            .and_then(|msg| SnapshotDetails::from_str(msg).ok())
+        else {
+            continue;
+        };
+        if details.operation != OperationKind::DeleteBranch {
+            continue;
+        }
+        let Some(name) = details
+            .trailers
+            .iter()
+            .find(|trailer| trailer.key == "name")
+            .map(|trailer| &trailer.value)
+        else {
+            continue;
+        };
+
+        let Some(vb_toml_entry) = commit.tree()?.get_name("virtual_branches.toml") else {
+            continue;
+        };
+        let vb_toml_blob = repo.find_blob(vb_toml_entry.id())?;
+        let vbs_from_toml: VirtualBranchesState =
+            toml::from_str(from_utf8(vb_toml_blob.content())?)?;
+        let Some(branch) = vbs_from_toml
+            .list_branches_in_workspace()?
+            .into_iter()
+            .find(|branch| &branch.name == name)
+        else {
+            continue;
+        };
+
+        if in_workspace.contains(&branch.id)
+            || !seen.insert(branch.id)
+            || repo.find_commit(branch.head).is_err()
+        {
+            continue;
+        }
+
+        recoverable.push(RecoverableBranch {
+            id: branch.id,
+            name: branch.name,
+            head: branch.head,
+            snapshot_id: commit.id(),
+            deleted_at: commit.time(),
+        });
+    }
+
+    Ok(recoverable)
+}
+
 fn prepare_snapshot(ctx: &Project, _shared_access: &WorktreeReadPermission) -> Result<git2::Oid> {
     let worktree_dir = ctx.path.as_path();
     let repo = git2::Repository::open(worktree_dir)?;
@@ -346,6 +1064,12 @@ fn prepare_snapshot(ctx: &Project, _shared_access: &WorktreeReadPermission) -> R
     tree_builder.insert("conflicts", conflicts_tree_id, FileMode::Tree.into())?;
     tree_builder.insert("virtual_branches.toml", vb_blob_id, FileMode::Blob.into())?;
 
+    // capturing the stash is opt-in as it has to encode every stash commit
+    if ctx.snapshot_stash {
+        let stash_tree_id = write_stash_tree(&repo)?;
+        tree_builder.insert("stash", stash_tree_id, FileMode::Tree.into())?;
+    }
+
     // go through all virtual branches and create a subtree for each with the tree and any commits encoded
     let mut branches_tree_builder = repo.treebuilder(None)?;
     let mut head_tree_ids = Vec::new();
@@ -465,6 +1189,18 @@ fn commit_snapshot(
         parents.as_slice(),
     )?;
 
+    if ctx.snapshot_storage == SnapshotStorage::Packed {
+        // The loose objects are still valid, so the snapshot is usable even if packing fails.
+        if let Err(err) = pack_snapshot_objects(&repo, &oplog_state, snapshot_commit_id) {
+            tracing::warn!(
+                ?err,
+                "failed to pack the objects of snapshot {snapshot_commit_id}"
+            );
+        } else if let Err(err) = consolidate_snapshot_packs(&repo, &oplog_state) {
+            tracing::warn!(?err, "failed to consolidate the packs of snapshots");
+        }
+    }
+
     oplog_state.set_oplog_head(snapshot_commit_id)?;
 
     let vb_state = VirtualBranchesHandle::new(ctx.gb_dir());
@@ -474,6 +1210,132 @@ fn commit_snapshot(
     Ok(Some(snapshot_commit_id))
 }
 
+/// Move the loose objects of the snapshot `commit_id` into a new pack, which stores similar objects
+/// as deltas of each other, and remember it in `oplog_state`.
+fn pack_snapshot_objects(
+    repo: &git2::Repository,
+    oplog_state: &OplogHandle,
+    commit_id: git2::Oid,
+) -> Result<()> {
+    let objects_dir = repo.path().join("objects");
+    let loose_object_path = |id: git2::Oid| {
+        let hex = id.to_string();
+        objects_dir.join(&hex[..2]).join(&hex[2..])
+    };
+
+    let tree = repo.find_commit(commit_id)?.tree()?;
+    let mut ids = vec![commit_id, tree.id()];
+    tree.walk(git2::TreeWalkMode::PreOrder, |_, entry| {
+        ids.push(entry.id());
+        git2::TreeWalkResult::Ok
+    })?;
+    ids.sort();
+    ids.dedup();
+    // Objects that are packed already, like the ones of unchanged files, are left alone.
+    ids.retain(|id| loose_object_path(*id).is_file());
+    if ids.is_empty() {
+        return Ok(());
+    }
+
+    let mut builder = repo.packbuilder()?;
+    for id in &ids {
+        builder.insert_object(*id, None)?;
+    }
+    let pack = write_pack(repo, &mut builder)?;
+    let mut packs = oplog_state.snapshot_packs()?;
+    packs.push(pack);
+    oplog_state.set_snapshot_packs(packs)?;
+
+    // Only the loose copies of objects that are in the new pack are removed, like `git prune-packed` does.
+    for id in ids {
+        std::fs::remove_file(loose_object_path(id))?;
+    }
+    Ok(())
+}
+
+/// Merge the small packs of snapshots recorded in `oplog_state` into a single one if there are more than
+/// [`MAX_SMALL_PACKS`] of them, so packing each snapshot doesn't leave an ever-growing amount of packs behind.
+/// All other packs, like those of fetches, are left to `git gc`, and so are packs that are kept with a `.keep` file.
+fn consolidate_snapshot_packs(repo: &git2::Repository, oplog_state: &OplogHandle) -> Result<()> {
+    let pack_dir = repo.path().join("objects").join("pack");
+    let recorded_packs = oplog_state.snapshot_packs()?;
+    // Packs that git repacked in the meantime are gone, and their objects are in its packs now.
+    let mut packs: Vec<String> = recorded_packs
+        .iter()
+        .filter(|pack| pack_dir.join(format!("{pack}.pack")).is_file())
+        .cloned()
+        .collect();
+    let mut small_packs = Vec::new();
+    for pack in &packs {
+        let path = pack_dir.join(format!("{pack}.pack"));
+        if !path.with_extension("keep").exists() && path.metadata()?.len() < SMALL_PACK_SIZE_BYTES {
+            small_packs.push(pack.clone());
+        }
+    }
+    if small_packs.len() <= MAX_SMALL_PACKS {
+        if packs != recorded_packs {
+            oplog_state.set_snapshot_packs(packs)?;
+        }
+        return Ok(());
+    }
+
+    let mut builder = repo.packbuilder()?;
+    for pack in &small_packs {
+        for id in pack_index_ids(&pack_dir.join(format!("{pack}.idx")))? {
+            builder.insert_object(id, None)?;
+        }
+    }
+    let consolidated_pack = write_pack(repo, &mut builder)?;
+    packs.retain(|pack| !small_packs.contains(pack));
+    packs.push(consolidated_pack.clone());
+    oplog_state.set_snapshot_packs(packs)?;
+
+    // All of their objects are in the new pack now.
+    for pack in small_packs
+        .into_iter()
+        .filter(|pack| *pack != consolidated_pack)
+    {
+        for ext in ["pack", "idx", "rev"] {
+            let path = pack_dir.join(format!("{pack}.{ext}"));
+            if path.exists() {
+                fs::remove_file(path)?;
+            }
+        }
+    }
+    repo.odb()?.refresh()?;
+    Ok(())
+}
+
+/// Write the objects of `builder` into a new pack of `repo` and return its name, like `pack-<checksum>`.
+fn write_pack(repo: &git2::Repository, builder: &mut git2::PackBuilder) -> Result<String> {
+    let mut pack = git2::Buf::new();
+    builder.write_buf(&mut pack)?;
+    let odb = repo.odb()?;
+    let mut writer = odb.packwriter()?;
+    writer.write_all(&pack)?;
+    Ok(format!("pack-{}", writer.commit()?))
+}
+
+/// Read the ids of all objects in the pack index at `path`, which has to be of version 2.
+fn pack_index_ids(path: &Path) -> Result<Vec<git2::Oid>> {
+    const HEADER: &[u8] = b"\xfftOc\0\0\0\x02";
+    const FANOUT_LEN: usize = 256 * 4;
+    let index = fs::read(path)?;
+    if !index.starts_with(HEADER) || index.len() < HEADER.len() + FANOUT_LEN {
+        bail!("{} isn't a pack index of version 2", path.display());
+    }
+    // The last entry of the fan-out table is the amount of objects, whose ids follow the table.
+    let count_offset = HEADER.len() + FANOUT_LEN - 4;
+    let count = u32::from_be_bytes(index[count_offset..count_offset + 4].try_into()?) as usize;
+    let ids_offset = HEADER.len() + FANOUT_LEN;
+    index
+        .get(ids_offset..ids_offset + count * 20)
+        .with_context(|| format!("{} is truncated", path.display()))?
+        .chunks(20)
+        .map(|id| Ok(git2::Oid::from_bytes(id)?))
+        .collect()
+}
+
 fn restore_snapshot(
     ctx: &Project,
     snapshot_commit_id: git2::Oid,
@@ -482,7 +1344,8 @@ fn restore_snapshot(
     let worktree_dir = ctx.path.as_path();
     let repo = git2::Repository::open(worktree_dir)?;
 
-    let before_restore_snapshot_result = prepare_snapshot(ctx, exclusive_access.read_permission());
+    let before_restore_snapshot_tree_id =
+        prepare_snapshot(ctx, exclusive_access.read_permission())?;
     let snapshot_commit = repo.find_commit(snapshot_commit_id)?;
 
     let snapshot_tree = snapshot_commit.tree()?;
@@ -494,10 +1357,42 @@ fn restore_snapshot(
         .find_blob(vb_toml_entry.id())
         .context("failed to convert virtual_branches tree entry to blob")?;
 
+    // Record the restore before changing anything, so it can be completed or rolled back if it's interrupted.
+    let signature = git2::Signature::now(
+        GITBUTLER_INTEGRATION_COMMIT_AUTHOR_NAME,
+        GITBUTLER_INTEGRATION_COMMIT_AUTHOR_EMAIL,
+    )?;
+    let rollback_id = repo.commit(
+        None,
+        &signature,
+        &signature,
+        &format!("State before restoring snapshot {snapshot_commit_id}"),
+        &repo.find_tree(before_restore_snapshot_tree_id)?,
+        &[],
+    )?;
+    restore::write_marker(
+        ctx,
+        &repo,
+        &IncompleteRestore {
+            snapshot_id: snapshot_commit_id,
+            rollback_id,
+            started_at: SystemTime::now(),
+        },
+    )?;
+
     if let Err(err) = restore_conflicts_tree(&snapshot_tree, &repo) {
         tracing::warn!("failed to restore conflicts tree - ignoring: {err}")
     }
 
+    if let Some(stash_tree_entry) = snapshot_tree.get_name("stash") {
+        let stash_tree = repo
+            .find_tree(stash_tree_entry.id())
+            .context("failed to convert stash tree entry to tree")?;
+        if let Err(err) = restore_stash_tree(&stash_tree, &repo) {
+            tracing::warn!("failed to restore stash tree - ignoring: {err}")
+        }
+    }
+
     // make sure we reconstitute any commits that were in the snapshot that are not here for some reason
     // for every entry in the virtual_branches subtree, reconsitute the commits
     let vb_tree_entry = snapshot_tree
@@ -603,7 +1498,6 @@ fn restore_snapshot(
         .unwrap_or_default();
 
     // create new snapshot
-    let before_restore_snapshot_tree_id = before_restore_snapshot_result?;
     let restored_date_ms = snapshot_commit.time().seconds() * 1000;
     let details = SnapshotDetails {
         version: Default::default(),
@@ -625,12 +1519,14 @@ fn restore_snapshot(
             },
         ],
     };
-    commit_snapshot(
+    let restore_snapshot_id = commit_snapshot(
         ctx,
         before_restore_snapshot_tree_id,
         details,
         exclusive_access,
-    )
+    )?;
+    restore::clear_marker(ctx, &repo)?;
+    Ok(restore_snapshot_id)
 }
 
 /// Restore the state of .git/base_merge_parent and .git/conflicts from the snapshot
@@ -699,6 +1595,90 @@ fn write_conflicts_tree(
     Ok(conflicts_tree)
 }
 
+/// Restore `refs/stash` and its reflog from the snapshot, recreating stash commits that went missing.
+/// Will remove the stash if it was empty when the snapshot was taken.
+fn restore_stash_tree(stash_tree: &git2::Tree, repo: &git2::Repository) -> Result<()> {
+    let commits_tree_entry = stash_tree
+        .get_name("commits")
+        .context("failed to get commits tree entry")?;
+    let commits_tree = repo
+        .find_tree(commits_tree_entry.id())
+        .context("failed to convert commits tree entry to tree")?;
+    for commit_entry in commits_tree.iter() {
+        if let Some(commit_id) = commit_entry.name() {
+            let commit_oid = git2::Oid::from_str(commit_id)?;
+            if repo.find_commit(commit_oid).is_err() {
+                let new_commit_oid = deserialize_commit(repo, &commit_entry)?;
+                if new_commit_oid != commit_oid {
+                    bail!("commit id mismatch: failed to recreate a stash commit from its parts");
+                }
+            }
+        }
+    }
+
+    if let Ok(mut stash_ref) = repo.find_reference(STASH_REFERENCE) {
+        stash_ref.delete()?;
+    }
+    let reflog_path = repo.path().join("logs").join(STASH_REFERENCE);
+    match stash_tree.get_name("reflog") {
+        Some(reflog_entry) => {
+            let reflog_blob = repo
+                .find_blob(reflog_entry.id())
+                .context("failed to convert reflog tree entry to blob")?;
+            gitbutler_fs::create_dirs_then_write(&reflog_path, reflog_blob.content())?;
+            let newest_stash_id = repo
+                .reflog(STASH_REFERENCE)?
+                .get(0)
+                .map(|entry| entry.id_new())
+                .context("stash reflog in snapshot has no entries")?;
+            repo.reference(STASH_REFERENCE, newest_stash_id, true, "restore stash")?;
+            // setting the reference may have logged the update, so put the snapshotted reflog back once more
+            fs::write(&reflog_path, reflog_blob.content())?;
+        }
+        None => {
+            if reflog_path.exists() {
+                fs::remove_file(reflog_path)?;
+            }
+        }
+    }
+    Ok(())
+}
+
+/// Encode the `git stash` entries as a tree, holding the stash reflog as-is along with
+/// all stash commits and their index and untracked-files parents so they can be recreated.
+/// An empty stash leads to a tree without `reflog`.
+fn write_stash_tree(repo: &git2::Repository) -> Result<git2::Oid> {
+    let reflog_path = repo.path().join("logs").join(STASH_REFERENCE);
+    let mut commits_tree_builder = repo.treebuilder(None)?;
+    for entry in repo.reflog(STASH_REFERENCE)?.iter() {
+        let stash_commit = repo.find_commit(entry.id_new())?;
+        // the first parent is the commit the stash was created on, which isn't owned by the stash
+        let stash_commits =
+            std::iter::once(stash_commit.clone()).chain(stash_commit.parents().skip(1));
+        for commit in stash_commits {
+            let mut commit_tree_builder = repo.treebuilder(None)?;
+            let commit_data_blob_id = repo.blob(&serialize_commit(&commit))?;
+            commit_tree_builder.insert("commit", commit_data_blob_id, FileMode::Blob.into())?;
+            commit_tree_builder.insert("tree", commit.tree_id(), FileMode::Tree.into())?;
+            let commit_tree_id = commit_tree_builder.write()?;
+            commits_tree_builder.insert(
+                commit.id().to_string(),
+                commit_tree_id,
+                FileMode::Tree.into(),
+            )?;
+        }
+    }
+    let commits_tree_id = commits_tree_builder.write()?;
+
+    let mut tree_builder = repo.treebuilder(None)?;
+    tree_builder.insert("commits", commits_tree_id, FileMode::Tree.into())?;
+    if repo.find_reference(STASH_REFERENCE).is_ok() && reflog_path.exists() {
+        let reflog_blob_id = repo.blob(&fs::read(reflog_path)?)?;
+        tree_builder.insert("reflog", reflog_blob_id, FileMode::Blob.into())?;
+    }
+    Ok(tree_builder.write()?)
+}
+
 /// Exclude files that are larger than the limit (eg. database.sql which may never be intended to be committed)
 /// TODO(ST): refactor this to be path-safe and ' ' save - the returned list is space separated (!!)
 fn worktree_files_larger_than_limit_as_git2_ignore_rule(
@@ -0,0 +1,64 @@
+use std::path::PathBuf;
+
+use anyhow::{Context, Result};
+use gitbutler_project::Project;
+
+use crate::entry::IncompleteRestore;
+
+/// The name of the file in the GitButler directory of a project that exists while a snapshot is restored.
+const RESTORE_MARKER_FILE_NAME: &str = "restore-in-progress.toml";
+/// The reference that keeps the state from before the restore in progress reachable, so it can't be
+/// garbage collected before the restore is rolled back.
+const ROLLBACK_REFERENCE: &str = "refs/gitbutler/restore-rollback";
+
+fn marker_path(project: &Project) -> PathBuf {
+    project.gb_dir().join(RESTORE_MARKER_FILE_NAME)
+}
+
+/// Returns the restore that was started but not finished, if any.
+pub(crate) fn read_marker(project: &Project) -> Result<Option<IncompleteRestore>> {
+    let path = marker_path(project);
+    match std::fs::read_to_string(&path) {
+        Ok(content) => {
+            Ok(Some(toml::from_str(&content).with_context(|| {
+                format!("failed to parse {}", path.display())
+            })?))
+        }
+        Err(err) if err.kind() == std::io::ErrorKind::NotFound => Ok(None),
+        Err(err) => Err(err).with_context(|| format!("failed to read {}", path.display())),
+    }
+}
+
+/// Record that `restore` is in progress, before anything is changed, and keep its rollback commit in `repo`
+/// reachable.
+pub(crate) fn write_marker(
+    project: &Project,
+    repo: &git2::Repository,
+    restore: &IncompleteRestore,
+) -> Result<()> {
+    repo.reference(
+        ROLLBACK_REFERENCE,
+        restore.rollback_id,
+        true,
+        "state before restoring a snapshot",
+    )
+    .context("failed to keep the state before the restore")?;
+    gitbutler_fs::write(marker_path(project), toml::to_string(restore)?)
+}
+
+/// Record that no restore is in progress anymore, after it finished successfully, and let go of its
+/// rollback commit in `repo`.
+pub(crate) fn clear_marker(project: &Project, repo: &git2::Repository) -> Result<()> {
+    match repo.find_reference(ROLLBACK_REFERENCE) {
+        Ok(mut reference) => reference.delete()?,
+        Err(err) if err.code() == git2::ErrorCode::NotFound => {}
+        Err(err) => return Err(err.into()),
+    }
+    let path = marker_path(project);
+    match std::fs::remove_file(&path) {
+        Err(err) if err.kind() != std::io::ErrorKind::NotFound => {
+            Err(err).with_context(|| format!("failed to remove {}", path.display()))
+        }
+        _ => Ok(()),
+    }
+}
@@ -7,6 +7,7 @@ use gitbutler_branch::{Branch, BranchUpdateRequest};
 use gitbutler_project::access::WorktreeWritePermission;
 use gitbutler_project::Project;
 use gitbutler_reference::ReferenceName;
+use std::path::Path;
 use std::vec;
 
 use super::entry::Trailer;
@@ -46,6 +47,11 @@ pub trait SnapshotExt {
         branch_name: String,
         perm: &mut WorktreeWritePermission,
     ) -> anyhow::Result<()>;
+    fn snapshot_triggered_by(
+        &self,
+        path: &Path,
+        perm: &mut WorktreeWritePermission,
+    ) -> anyhow::Result<()>;
     fn snapshot_branch_update(
         &self,
         snapshot_tree: git2::Oid,
@@ -137,6 +143,19 @@ impl SnapshotExt for Project {
         self.create_snapshot(details, perm)?;
         Ok(())
     }
+    fn snapshot_triggered_by(
+        &self,
+        path: &Path,
+        perm: &mut WorktreeWritePermission,
+    ) -> anyhow::Result<()> {
+        let details =
+            SnapshotDetails::new(OperationKind::TriggeredFileChange).with_trailers(vec![Trailer {
+                key: "path".to_string(),
+                value: path.display().to_string(),
+            }]);
+        self.create_snapshot(details, perm)?;
+        Ok(())
+    }
     fn snapshot_branch_update(
         &self,
         snapshot_tree: git2::Oid,
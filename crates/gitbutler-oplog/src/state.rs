@@ -34,6 +34,9 @@ pub struct Oplog {
         default = "unix_epoch"
     )]
     pub modified_at: SystemTime,
+    /// The names of the packs written for packed snapshots, the only packs the oplog consolidates.
+    #[serde(default)]
+    pub snapshot_packs: Vec<String>,
 }
 
 impl Default for Oplog {
@@ -41,6 +44,7 @@ impl Default for Oplog {
         Self {
             head_sha: None,
             modified_at: SystemTime::UNIX_EPOCH,
+            snapshot_packs: Vec::new(),
         }
     }
 }
@@ -83,6 +87,37 @@ impl OplogHandle {
         Ok(oplog.modified_at)
     }
 
+    /// Gets the names of the packs that were written for packed snapshots.
+    ///
+    /// Errors if the file cannot be read or written.
+    pub fn snapshot_packs(&self) -> Result<Vec<String>> {
+        let oplog = self.read_file()?;
+        Ok(oplog.snapshot_packs)
+    }
+
+    /// Persists the names of the packs that were written for packed snapshots.
+    ///
+    /// Errors if the file cannot be read or written.
+    pub fn set_snapshot_packs(&self, packs: Vec<String>) -> Result<()> {
+        let mut oplog = self.read_file()?;
+        oplog.snapshot_packs = packs;
+        self.write_file(oplog)?;
+        Ok(())
+    }
+
+    /// Copies the current oplog state next to it as `<name>.backup`, replacing previous backups,
+    /// and returns the path of the copy.
+    ///
+    /// Errors if the file cannot be read or written.
+    pub fn backup(&self) -> Result<PathBuf> {
+        let oplog = self.read_file()?;
+        let mut backup_path = self.file_path.clone().into_os_string();
+        backup_path.push(".backup");
+        let backup_path = PathBuf::from(backup_path);
+        gitbutler_fs::write(&backup_path, toml::to_string(&oplog)?)?;
+        Ok(backup_path)
+    }
+
     /// Reads and parses the state file.
     ///
     /// If the file does not exist, it will be created.
@@ -3,7 +3,7 @@ use std::path::{Path, PathBuf};
 use anyhow::{bail, Context, Result};
 
 use super::{storage, storage::UpdateRequest, Project, ProjectId};
-use crate::AuthKey;
+use crate::{AuthKey, ProjectProfile};
 use gitbutler_error::error;
 
 #[derive(Clone)]
@@ -119,6 +119,18 @@ impl Controller {
         self.projects_storage.update(project)
     }
 
+    /// Return the portable settings of the project with `id`, without the signing settings that are kept in
+    /// the git config of its repository.
+    pub fn export_profile(&self, id: ProjectId) -> Result<ProjectProfile> {
+        Ok(ProjectProfile::from(&self.get(id)?))
+    }
+
+    /// Apply all settings of `profile` to the project with `id`, except for the signing settings that are kept in
+    /// the git config of its repository.
+    pub async fn import_profile(&self, id: ProjectId, profile: ProjectProfile) -> Result<Project> {
+        self.update(&profile.into_update_request(id)).await
+    }
+
     pub fn get(&self, id: ProjectId) -> Result<Project> {
         #[cfg_attr(not(windows), allow(unused_mut))]
         let mut project = self.projects_storage.get(id)?;
@@ -1,9 +1,16 @@
 pub mod access;
 mod controller;
 mod default_true;
+mod open_handles;
+mod profile;
 mod project;
 mod storage;
 
 pub use controller::Controller;
-pub use project::{ApiProject, AuthKey, CodePushState, FetchResult, Project, ProjectId};
+pub use open_handles::{list_open_handles, OpenHandles, ProcessHandles};
+pub use profile::{ProjectProfile, SigningSettings};
+pub use project::{
+    AiBackend, ApiProject, AuthKey, CodePushState, CommitLintRules, CommitMessageDirectives,
+    FetchResult, Project, ProjectId, ProjectMode, RetentionPolicy, SnapshotStorage, SyncTrigger,
+};
 pub use storage::UpdateRequest;
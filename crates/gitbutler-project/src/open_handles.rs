@@ -0,0 +1,193 @@
+use std::path::{Path, PathBuf};
+
+use anyhow::Result;
+use serde::Serialize;
+
+/// A process holding handles to paths inside of a directory.
+#[derive(Debug, PartialEq, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ProcessHandles {
+    pub pid: u32,
+    /// The name of the executable, if it could be determined.
+    pub name: Option<String>,
+    /// The paths inside of the directory the process has open, including its working directory.
+    /// Empty on Windows, which only tells which processes hold any of the files.
+    pub paths: Vec<PathBuf>,
+}
+
+/// The result of looking for processes with open handles into a directory.
+#[derive(Debug, PartialEq, Clone, Serialize)]
+#[serde(rename_all = "camelCase", tag = "status", content = "processes")]
+pub enum OpenHandles {
+    /// All processes we were permitted to inspect that have handles into the directory.
+    Supported(Vec<ProcessHandles>),
+    /// The platform doesn't allow to enumerate handles of other processes.
+    Unsupported,
+}
+
+/// Find the processes that hold handles to `dir` or paths inside of it, to help troubleshooting failures to
+/// lock or delete files. This is best-effort, processes we are not permitted to inspect are skipped.
+pub fn list_open_handles(dir: &Path) -> Result<OpenHandles> {
+    imp::list_open_handles(dir)
+}
+
+#[cfg(target_os = "linux")]
+mod imp {
+    use std::{fs, path::Path};
+
+    use anyhow::Result;
+
+    use super::{OpenHandles, ProcessHandles};
+
+    pub fn list_open_handles(dir: &Path) -> Result<OpenHandles> {
+        let dir = dir.canonicalize()?;
+        let mut processes = Vec::new();
+        for entry in fs::read_dir("/proc")? {
+            let Ok(entry) = entry else {
+                continue;
+            };
+            let Some(pid) = entry.file_name().to_str().and_then(|pid| pid.parse().ok()) else {
+                continue;
+            };
+            let proc_dir = entry.path();
+            let fds = fs::read_dir(proc_dir.join("fd"))
+                .into_iter()
+                .flatten()
+                .filter_map(|fd| fs::read_link(fd.ok()?.path()).ok());
+            let mut paths: Vec<_> = fs::read_link(proc_dir.join("cwd"))
+                .into_iter()
+                .chain(fds)
+                .filter(|path| path.starts_with(&dir))
+                .collect();
+            if paths.is_empty() {
+                continue;
+            }
+            paths.sort();
+            paths.dedup();
+            let name = fs::read_to_string(proc_dir.join("comm"))
+                .ok()
+                .map(|name| name.trim_end().to_owned());
+            processes.push(ProcessHandles { pid, name, paths });
+        }
+        Ok(OpenHandles::Supported(processes))
+    }
+}
+
+/// Uses the Restart Manager, which is what Windows itself uses to tell which applications keep files in use.
+#[cfg(windows)]
+mod imp {
+    use std::{fs, os::windows::ffi::OsStrExt, path::Path};
+
+    use anyhow::{bail, Result};
+    use windows::{
+        core::{PCWSTR, PWSTR},
+        Win32::{
+            Foundation::{ERROR_MORE_DATA, ERROR_SUCCESS},
+            System::RestartManager::{
+                RmEndSession, RmGetList, RmRegisterResources, RmStartSession, CCH_RM_SESSION_KEY,
+                RM_PROCESS_INFO,
+            },
+        },
+    };
+
+    use super::{OpenHandles, ProcessHandles};
+
+    pub fn list_open_handles(dir: &Path) -> Result<OpenHandles> {
+        let mut files = Vec::new();
+        collect_files(dir, &mut files);
+        if files.is_empty() {
+            return Ok(OpenHandles::Supported(Vec::new()));
+        }
+        let files: Vec<Vec<u16>> = files
+            .iter()
+            .map(|path| path.as_os_str().encode_wide().chain(Some(0)).collect())
+            .collect();
+        let file_ptrs: Vec<PCWSTR> = files.iter().map(|file| PCWSTR(file.as_ptr())).collect();
+
+        let mut session = 0;
+        let mut key = [0u16; CCH_RM_SESSION_KEY as usize + 1];
+        #[allow(unsafe_code)]
+        unsafe {
+            RmStartSession(&mut session, 0, PWSTR(key.as_mut_ptr())).ok()?;
+        }
+        let processes = processes_of_session(session, &file_ptrs);
+        #[allow(unsafe_code)]
+        unsafe {
+            let _ = RmEndSession(session);
+        }
+        Ok(OpenHandles::Supported(processes?))
+    }
+
+    fn processes_of_session(session: u32, files: &[PCWSTR]) -> Result<Vec<ProcessHandles>> {
+        #[allow(unsafe_code)]
+        unsafe {
+            RmRegisterResources(session, Some(files), None, None).ok()?;
+        }
+        // The list can grow between the calls, so retry until it fits.
+        let mut infos: Vec<RM_PROCESS_INFO> = Vec::new();
+        loop {
+            let mut needed = 0;
+            let mut count = infos.len() as u32;
+            let mut reboot_reasons = 0;
+            #[allow(unsafe_code)]
+            let result = unsafe {
+                RmGetList(
+                    session,
+                    &mut needed,
+                    &mut count,
+                    Some(infos.as_mut_ptr()),
+                    &mut reboot_reasons,
+                )
+            };
+            if result == ERROR_MORE_DATA {
+                infos.resize(needed as usize, RM_PROCESS_INFO::default());
+                continue;
+            }
+            if result != ERROR_SUCCESS {
+                bail!("failed to list the processes using files: {result:?}");
+            }
+            infos.truncate(count as usize);
+            break;
+        }
+        Ok(infos
+            .iter()
+            .map(|info| {
+                let name_len = info
+                    .strAppName
+                    .iter()
+                    .position(|c| *c == 0)
+                    .unwrap_or(info.strAppName.len());
+                let name = String::from_utf16_lossy(&info.strAppName[..name_len]);
+                ProcessHandles {
+                    pid: info.Process.dwProcessId,
+                    name: (!name.is_empty()).then_some(name),
+                    paths: Vec::new(),
+                }
+            })
+            .collect())
+    }
+
+    /// Collect all files in `dir` recursively, skipping what can't be read.
+    fn collect_files(dir: &Path, files: &mut Vec<std::path::PathBuf>) {
+        for entry in fs::read_dir(dir).into_iter().flatten().flatten() {
+            match entry.file_type() {
+                Ok(file_type) if file_type.is_dir() => collect_files(&entry.path(), files),
+                Ok(file_type) if file_type.is_file() => files.push(entry.path()),
+                _ => {}
+            }
+        }
+    }
+}
+
+#[cfg(not(any(target_os = "linux", windows)))]
+mod imp {
+    use std::path::Path;
+
+    use anyhow::Result;
+
+    use super::OpenHandles;
+
+    pub fn list_open_handles(_dir: &Path) -> Result<OpenHandles> {
+        Ok(OpenHandles::Unsupported)
+    }
+}
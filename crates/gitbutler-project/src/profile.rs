@@ -0,0 +1,119 @@
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    AiBackend, AuthKey, CommitLintRules, CommitMessageDirectives, Project, ProjectId,
+    RetentionPolicy, SnapshotStorage, SyncTrigger, UpdateRequest,
+};
+
+/// The settings of a project that are portable across machines, to replicate the configuration of
+/// a project elsewhere.
+///
+/// Only settings are included, not the identity or state of the project. Secrets are never part of
+/// it, only referenced, like the private key by its path.
+///
+/// Settings missing from a profile, like those of profiles exported by older versions, take their default.
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+#[serde(rename_all = "camelCase", default)]
+pub struct ProjectProfile {
+    pub preferred_key: AuthKey,
+    pub ok_with_force_push: bool,
+    pub omit_certificate_check: Option<bool>,
+    pub snapshot_lines_threshold: Option<usize>,
+    pub ignore_project_semaphore: bool,
+    pub auto_rebase_on_base_update: bool,
+    pub snapshot_stash: bool,
+    pub max_applied_branches: usize,
+    pub snapshot_triggers: Vec<String>,
+    pub ai_backend: AiBackend,
+    pub ai_redaction_patterns: Vec<String>,
+    pub ai_sensitive: bool,
+    pub commit_message_directives: CommitMessageDirectives,
+    pub sync_trigger: SyncTrigger,
+    pub protected_paths: Vec<String>,
+    pub commit_lint_rules: CommitLintRules,
+    pub use_fsmonitor: bool,
+    pub snapshot_retention: Option<RetentionPolicy>,
+    pub auto_snapshot_interval_secs: Option<u64>,
+    pub snapshot_storage: SnapshotStorage,
+    /// The settings for signing commits, which are kept in the git config of the repository instead of the project.
+    pub signing: SigningSettings,
+}
+
+/// The settings for signing commits, with the key referenced like in the git config, e.g. by the path of
+/// a public SSH key or the id of a GPG key.
+#[derive(Debug, Serialize, Deserialize, Clone, Default, PartialEq)]
+#[serde(rename_all = "camelCase", default)]
+pub struct SigningSettings {
+    pub sign_commits: Option<bool>,
+    pub signing_key: Option<String>,
+    pub signing_format: Option<String>,
+    pub gpg_program: Option<String>,
+    pub gpg_ssh_program: Option<String>,
+}
+
+impl Default for ProjectProfile {
+    fn default() -> Self {
+        ProjectProfile::from(&Project::default())
+    }
+}
+
+impl From<&Project> for ProjectProfile {
+    fn from(project: &Project) -> Self {
+        ProjectProfile {
+            preferred_key: project.preferred_key.clone(),
+            ok_with_force_push: *project.ok_with_force_push,
+            omit_certificate_check: project.omit_certificate_check,
+            snapshot_lines_threshold: project.snapshot_lines_threshold,
+            ignore_project_semaphore: project.ignore_project_semaphore,
+            auto_rebase_on_base_update: project.auto_rebase_on_base_update,
+            snapshot_stash: project.snapshot_stash,
+            max_applied_branches: project.max_applied_branches,
+            snapshot_triggers: project.snapshot_triggers.clone(),
+            ai_backend: project.ai_backend,
+            ai_redaction_patterns: project.ai_redaction_patterns.clone(),
+            ai_sensitive: project.ai_sensitive,
+            commit_message_directives: project.commit_message_directives,
+            sync_trigger: project.sync_trigger,
+            protected_paths: project.protected_paths.clone(),
+            commit_lint_rules: project.commit_lint_rules.clone(),
+            use_fsmonitor: project.use_fsmonitor,
+            snapshot_retention: project.snapshot_retention,
+            auto_snapshot_interval_secs: project.auto_snapshot_interval_secs,
+            snapshot_storage: project.snapshot_storage,
+            signing: SigningSettings::default(),
+        }
+    }
+}
+
+impl ProjectProfile {
+    /// Turn the profile into a request that applies all of its settings to the project with `id`.
+    ///
+    /// Optional settings that aren't set in the profile are left as they are. The signing settings aren't part
+    /// of the project and have to be applied separately.
+    pub fn into_update_request(self, id: ProjectId) -> UpdateRequest {
+        UpdateRequest {
+            id,
+            preferred_key: Some(self.preferred_key),
+            ok_with_force_push: Some(self.ok_with_force_push),
+            omit_certificate_check: self.omit_certificate_check,
+            snapshot_lines_threshold: self.snapshot_lines_threshold,
+            ignore_project_semaphore: Some(self.ignore_project_semaphore),
+            auto_rebase_on_base_update: Some(self.auto_rebase_on_base_update),
+            snapshot_stash: Some(self.snapshot_stash),
+            max_applied_branches: Some(self.max_applied_branches),
+            snapshot_triggers: Some(self.snapshot_triggers),
+            ai_backend: Some(self.ai_backend),
+            ai_redaction_patterns: Some(self.ai_redaction_patterns),
+            ai_sensitive: Some(self.ai_sensitive),
+            commit_message_directives: Some(self.commit_message_directives),
+            sync_trigger: Some(self.sync_trigger),
+            protected_paths: Some(self.protected_paths),
+            commit_lint_rules: Some(self.commit_lint_rules),
+            use_fsmonitor: Some(self.use_fsmonitor),
+            snapshot_retention: Some(self.snapshot_retention),
+            auto_snapshot_interval_secs: self.auto_snapshot_interval_secs,
+            snapshot_storage: Some(self.snapshot_storage),
+            ..Default::default()
+        }
+    }
+}
@@ -4,6 +4,9 @@ use std::{
     time,
 };
 
+use anyhow::anyhow;
+use gitbutler_error::error::Code;
+
 use crate::default_true::DefaultTrue;
 use gitbutler_id::id::Id;
 
@@ -59,6 +62,98 @@ pub struct CodePushState {
     pub timestamp: time::SystemTime,
 }
 
+/// The AI backend used to generate commit messages and branch names for a project.
+#[derive(Debug, Deserialize, Serialize, Copy, Clone, Default, PartialEq, Eq)]
+#[serde(rename_all = "camelCase")]
+pub enum AiBackend {
+    /// Use whichever backend is configured globally.
+    #[default]
+    Global,
+    /// Always use the local Ollama backend, so no project data leaves the machine.
+    Local,
+}
+
+/// The defaults for generating commit messages with AI in a project, each overriding the setting chosen
+/// in the UI if set.
+#[derive(Debug, Deserialize, Serialize, Copy, Clone, Default, PartialEq, Eq)]
+pub struct CommitMessageDirectives {
+    /// Keep the message to a single, short sentence.
+    #[serde(default)]
+    pub brief: Option<bool>,
+    /// Prefix the title with a GitMoji, or forbid emoji if `false`.
+    #[serde(default)]
+    pub emoji: Option<bool>,
+}
+
+/// The event that synchronizes a project with GitButler's servers, if syncing is enabled.
+#[derive(Debug, Deserialize, Serialize, Copy, Clone, Default, PartialEq, Eq)]
+#[serde(rename_all = "camelCase")]
+pub enum SyncTrigger {
+    /// Sync whenever a new oplog entry is created.
+    #[default]
+    Oplog,
+    /// Sync only when explicitly asked to.
+    Manual,
+    /// Sync periodically.
+    Timer,
+    /// Sync whenever a branch is pushed.
+    Push,
+}
+
+/// How the objects of new oplog snapshots are stored, trading CPU time against disk space.
+#[derive(Debug, Deserialize, Serialize, Copy, Clone, Default, PartialEq, Eq)]
+#[serde(rename_all = "camelCase")]
+pub enum SnapshotStorage {
+    /// Store new objects as loose objects, leaving packing to the next garbage collection.
+    #[default]
+    Loose,
+    /// Move new objects into a pack right away, storing similar ones as deltas of each other.
+    Packed,
+}
+
+/// Which oplog snapshots to keep when pruning the oldest ones.
+#[derive(Debug, Deserialize, Serialize, Copy, Clone, PartialEq, Eq)]
+#[serde(rename_all = "camelCase")]
+pub enum RetentionPolicy {
+    /// Keep the given amount of most recent snapshots.
+    KeepLast(usize),
+    /// Keep the snapshots created within the given amount of days.
+    KeepDays(u64),
+    /// Keep the most recent snapshots whose contents take up to the given amount of bytes.
+    KeepUnderBytes(u64),
+}
+
+/// Whether GitButler may change a project.
+#[derive(Debug, Deserialize, Serialize, Copy, Clone, Default, PartialEq, Eq)]
+#[serde(rename_all = "camelCase")]
+pub enum ProjectMode {
+    /// The project can be changed freely.
+    #[default]
+    Normal,
+    /// The project is only observed, like a checkout of someone else that is being reviewed. No snapshots
+    /// are created, the integration branch is left alone and virtual branches can't be changed.
+    ReadOnly,
+}
+
+/// Rules commit messages of a project have to follow, for teams without a `commit-msg` hook.
+#[derive(Debug, Deserialize, Serialize, Clone, Default, PartialEq, Eq)]
+#[serde(rename_all = "camelCase")]
+pub struct CommitLintRules {
+    /// The maximum amount of characters of the first line of a message, if limited.
+    #[serde(default)]
+    pub max_subject_length: Option<usize>,
+    /// The types a subject has to start with, like `feat` in `feat(ui): add button`.
+    /// Any type is allowed if empty.
+    #[serde(default)]
+    pub required_types: Vec<String>,
+    /// The maximum amount of characters of each line of the body, if limited.
+    #[serde(default)]
+    pub body_wrap: Option<usize>,
+    /// If true, body lines exceeding `body_wrap` are wrapped instead of being reported.
+    #[serde(default)]
+    pub fix_wrapping: bool,
+}
+
 pub type ProjectId = Id<Project>;
 
 #[derive(Debug, Deserialize, Serialize, Clone, Default)]
@@ -89,6 +184,57 @@ pub struct Project {
     pub snapshot_lines_threshold: Option<usize>,
     #[serde(default)]
     pub ignore_project_semaphore: bool,
+    /// If true, applied branches are rebased onto the base branch as soon as a fetch moves it.
+    #[serde(default)]
+    pub auto_rebase_on_base_update: bool,
+    /// If true, snapshots also capture the `git stash` entries so they can be restored.
+    #[serde(default)]
+    pub snapshot_stash: bool,
+    /// The maximum number of branches that can be applied at the same time, with `0` meaning no limit.
+    #[serde(default)]
+    pub max_applied_branches: usize,
+    /// Glob patterns of worktree-relative paths whose changes trigger a snapshot right away, independently
+    /// of the usual snapshot interval. Patterns without a `/` also match the file name at any depth.
+    #[serde(default)]
+    pub snapshot_triggers: Vec<String>,
+    /// The AI backend to use for this project.
+    #[serde(default)]
+    pub ai_backend: AiBackend,
+    /// Regular expressions whose matches are redacted from any context sent to an AI backend.
+    #[serde(default)]
+    pub ai_redaction_patterns: Vec<String>,
+    /// If true, the project's contents must never be sent to a cloud AI backend.
+    #[serde(default)]
+    pub ai_sensitive: bool,
+    /// How commit messages of this project are generated with AI.
+    #[serde(default)]
+    pub commit_message_directives: CommitMessageDirectives,
+    /// The event that synchronizes the project with GitButler's servers.
+    #[serde(default)]
+    pub sync_trigger: SyncTrigger,
+    /// Glob patterns of worktree-relative paths that are protected, so changing them is considered high-risk.
+    /// Patterns without a `/` also match the file name at any depth.
+    #[serde(default)]
+    pub protected_paths: Vec<String>,
+    /// The rules messages of commits created through GitButler have to follow.
+    #[serde(default)]
+    pub commit_lint_rules: CommitLintRules,
+    /// If true, the watcher also asks git's `core.fsmonitor` hook for changed files, which is faster
+    /// than waiting for filesystem events in huge repositories.
+    #[serde(default)]
+    pub use_fsmonitor: bool,
+    /// If set, the oldest oplog snapshots beyond this policy are pruned whenever a new one is created.
+    #[serde(default)]
+    pub snapshot_retention: Option<RetentionPolicy>,
+    /// How many seconds have to pass since the last snapshot before changes are snapshotted automatically.
+    #[serde(default)]
+    pub auto_snapshot_interval_secs: Option<u64>,
+    /// How the objects of new oplog snapshots are stored.
+    #[serde(default)]
+    pub snapshot_storage: SnapshotStorage,
+    /// Whether GitButler may change the project.
+    #[serde(default)]
+    pub mode: ProjectMode,
 }
 
 impl Project {
@@ -103,6 +249,12 @@ impl Project {
             .unwrap_or_default()
     }
 
+    /// Returns `true` if `trigger` should synchronize the project with GitButler's servers,
+    /// which requires syncing to be enabled and `trigger` to be the configured one.
+    pub fn syncs_on(&self, trigger: SyncTrigger) -> bool {
+        self.is_sync_enabled() && self.has_code_url() && self.sync_trigger == trigger
+    }
+
     /// Returns the path to the directory containing the `GitButler` state for this project.
     ///
     /// Normally this is `.git/gitbutler` in the project's repository.
@@ -110,10 +262,31 @@ impl Project {
         self.path.join(".git").join("gitbutler")
     }
 
+    /// Returns `true` if the project is opened in [read-only mode](ProjectMode::ReadOnly).
+    pub fn is_read_only(&self) -> bool {
+        self.mode == ProjectMode::ReadOnly
+    }
+
+    /// Fail with [`Code::ProjectReadOnly`] if the project is opened in [read-only mode](ProjectMode::ReadOnly).
+    pub fn assure_writable(&self) -> anyhow::Result<()> {
+        if self.is_read_only() {
+            return Err(
+                anyhow!("The project is opened read-only and can't be changed")
+                    .context(Code::ProjectReadOnly),
+            );
+        }
+        Ok(())
+    }
+
     pub fn snapshot_lines_threshold(&self) -> usize {
         self.snapshot_lines_threshold.unwrap_or(20)
     }
 
+    /// How long to wait after the last snapshot before changes are snapshotted automatically.
+    pub fn auto_snapshot_interval(&self) -> time::Duration {
+        time::Duration::from_secs(self.auto_snapshot_interval_secs.unwrap_or(300))
+    }
+
     pub fn worktree_path(&self) -> PathBuf {
         self.path.clone()
     }
@@ -1,8 +1,11 @@
 use anyhow::{Context, Result};
-use serde::{Deserialize, Serialize};
+use serde::{Deserialize, Deserializer, Serialize};
 use std::path::PathBuf;
 
-use crate::{ApiProject, AuthKey, CodePushState, FetchResult, Project, ProjectId};
+use crate::{
+    AiBackend, ApiProject, AuthKey, CodePushState, CommitLintRules, CommitMessageDirectives,
+    FetchResult, Project, ProjectId, ProjectMode, RetentionPolicy, SnapshotStorage, SyncTrigger,
+};
 
 const PROJECTS_FILE: &str = "projects.json";
 
@@ -26,6 +29,33 @@ pub struct UpdateRequest {
     pub use_diff_context: Option<bool>,
     pub snapshot_lines_threshold: Option<usize>,
     pub ignore_project_semaphore: Option<bool>,
+    pub auto_rebase_on_base_update: Option<bool>,
+    pub snapshot_stash: Option<bool>,
+    pub max_applied_branches: Option<usize>,
+    pub snapshot_triggers: Option<Vec<String>>,
+    pub ai_backend: Option<AiBackend>,
+    pub ai_redaction_patterns: Option<Vec<String>>,
+    pub ai_sensitive: Option<bool>,
+    pub commit_message_directives: Option<CommitMessageDirectives>,
+    pub sync_trigger: Option<SyncTrigger>,
+    pub protected_paths: Option<Vec<String>>,
+    pub commit_lint_rules: Option<CommitLintRules>,
+    pub use_fsmonitor: Option<bool>,
+    /// `Some(None)`, sent as `null`, turns pruning off, while a missing field leaves the policy as is.
+    #[serde(default, deserialize_with = "deserialize_update")]
+    pub snapshot_retention: Option<Option<RetentionPolicy>>,
+    pub auto_snapshot_interval_secs: Option<u64>,
+    pub snapshot_storage: Option<SnapshotStorage>,
+    pub mode: Option<ProjectMode>,
+}
+
+/// Deserialize a field that can be cleared, so that `null` is `Some(None)` rather than `None`.
+fn deserialize_update<'de, D, T>(deserializer: D) -> Result<Option<Option<T>>, D::Error>
+where
+    D: Deserializer<'de>,
+    T: Deserialize<'de>,
+{
+    Option::<T>::deserialize(deserializer).map(Some)
 }
 
 impl Storage {
@@ -122,6 +152,72 @@ impl Storage {
             project.ignore_project_semaphore = ignore_project_semaphore;
         }
 
+        if let Some(auto_rebase_on_base_update) = update_request.auto_rebase_on_base_update {
+            project.auto_rebase_on_base_update = auto_rebase_on_base_update;
+        }
+
+        if let Some(snapshot_stash) = update_request.snapshot_stash {
+            project.snapshot_stash = snapshot_stash;
+        }
+
+        if let Some(max_applied_branches) = update_request.max_applied_branches {
+            project.max_applied_branches = max_applied_branches;
+        }
+
+        if let Some(snapshot_triggers) = &update_request.snapshot_triggers {
+            project.snapshot_triggers.clone_from(snapshot_triggers);
+        }
+
+        if let Some(ai_backend) = update_request.ai_backend {
+            project.ai_backend = ai_backend;
+        }
+
+        if let Some(ai_redaction_patterns) = &update_request.ai_redaction_patterns {
+            project
+                .ai_redaction_patterns
+                .clone_from(ai_redaction_patterns);
+        }
+
+        if let Some(ai_sensitive) = update_request.ai_sensitive {
+            project.ai_sensitive = ai_sensitive;
+        }
+
+        if let Some(commit_message_directives) = update_request.commit_message_directives {
+            project.commit_message_directives = commit_message_directives;
+        }
+
+        if let Some(sync_trigger) = update_request.sync_trigger {
+            project.sync_trigger = sync_trigger;
+        }
+
+        if let Some(protected_paths) = &update_request.protected_paths {
+            project.protected_paths.clone_from(protected_paths);
+        }
+
+        if let Some(commit_lint_rules) = &update_request.commit_lint_rules {
+            project.commit_lint_rules.clone_from(commit_lint_rules);
+        }
+
+        if let Some(use_fsmonitor) = update_request.use_fsmonitor {
+            project.use_fsmonitor = use_fsmonitor;
+        }
+
+        if let Some(snapshot_retention) = update_request.snapshot_retention {
+            project.snapshot_retention = snapshot_retention;
+        }
+
+        if let Some(auto_snapshot_interval_secs) = update_request.auto_snapshot_interval_secs {
+            project.auto_snapshot_interval_secs = Some(auto_snapshot_interval_secs);
+        }
+
+        if let Some(snapshot_storage) = update_request.snapshot_storage {
+            project.snapshot_storage = snapshot_storage;
+        }
+
+        if let Some(mode) = update_request.mode {
+            project.mode = mode;
+        }
+
         self.inner
             .write(PROJECTS_FILE, &serde_json::to_string_pretty(&projects)?)?;
 
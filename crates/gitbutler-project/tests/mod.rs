@@ -1 +1,2 @@
+mod open_handles;
 mod projects;
@@ -0,0 +1,55 @@
+use gitbutler_project::{list_open_handles, OpenHandles};
+
+#[cfg(target_os = "linux")]
+#[test]
+fn lists_own_process_holding_a_file() {
+    let tmp = tempfile::tempdir().unwrap();
+    let file_path = tmp.path().join("file.txt");
+    let _file = std::fs::File::create(&file_path).unwrap();
+
+    let OpenHandles::Supported(processes) = list_open_handles(tmp.path()).unwrap() else {
+        panic!("linux can list open handles");
+    };
+    let own = processes
+        .iter()
+        .find(|process| process.pid == std::process::id())
+        .expect("this process holds the file open");
+    assert_eq!(own.paths, vec![file_path.canonicalize().unwrap()]);
+}
+
+#[cfg(target_os = "linux")]
+#[test]
+fn directory_without_open_handles() {
+    let tmp = tempfile::tempdir().unwrap();
+    assert_eq!(
+        list_open_handles(tmp.path()).unwrap(),
+        OpenHandles::Supported(vec![])
+    );
+}
+
+#[cfg(windows)]
+#[test]
+fn lists_own_process_holding_a_file() {
+    let tmp = tempfile::tempdir().unwrap();
+    let _file = std::fs::File::create(tmp.path().join("file.txt")).unwrap();
+
+    let OpenHandles::Supported(processes) = list_open_handles(tmp.path()).unwrap() else {
+        panic!("windows can list open handles");
+    };
+    assert!(
+        processes
+            .iter()
+            .any(|process| process.pid == std::process::id()),
+        "this process holds the file open"
+    );
+}
+
+#[cfg(not(any(target_os = "linux", windows)))]
+#[test]
+fn unsupported() {
+    let tmp = tempfile::tempdir().unwrap();
+    assert_eq!(
+        list_open_handles(tmp.path()).unwrap(),
+        OpenHandles::Unsupported
+    );
+}
@@ -132,3 +132,254 @@ mod delete {
         assert!(!project.path.join(".gitbutler.json").exists());
     }
 }
+
+mod profile {
+    use gitbutler_project::{AiBackend, CommitMessageDirectives, ProjectProfile, UpdateRequest};
+
+    use super::*;
+
+    #[tokio::test]
+    async fn round_trip() {
+        let (controller, _tmp) = new();
+        let source_repository = gitbutler_testsupport::TestProject::default();
+        let source = controller.add(source_repository.path()).unwrap();
+        let target_repository = gitbutler_testsupport::TestProject::default();
+        let target = controller.add(target_repository.path()).unwrap();
+
+        controller
+            .update(&UpdateRequest {
+                id: source.id,
+                ok_with_force_push: Some(false),
+                snapshot_lines_threshold: Some(42),
+                auto_rebase_on_base_update: Some(true),
+                max_applied_branches: Some(3),
+                snapshot_triggers: Some(vec!["Cargo.lock".into()]),
+                ai_backend: Some(AiBackend::Local),
+                ai_redaction_patterns: Some(vec!["secret-\\d+".into()]),
+                ai_sensitive: Some(true),
+                commit_message_directives: Some(CommitMessageDirectives {
+                    brief: None,
+                    emoji: Some(false),
+                }),
+                ..Default::default()
+            })
+            .await
+            .unwrap();
+
+        let profile = controller.export_profile(source.id).unwrap();
+        let json = serde_json::to_string(&profile).unwrap();
+        let target = controller
+            .import_profile(target.id, serde_json::from_str(&json).unwrap())
+            .await
+            .unwrap();
+
+        assert_eq!(controller.export_profile(target.id).unwrap(), profile);
+        assert_eq!(target.path, target_repository.path());
+        assert!(!*target.ok_with_force_push);
+        assert_eq!(target.snapshot_lines_threshold, Some(42));
+        assert_eq!(target.ai_backend, AiBackend::Local);
+        assert_eq!(target.commit_message_directives.emoji, Some(false));
+    }
+
+    #[test]
+    fn missing_settings_take_their_default() {
+        let profile: ProjectProfile =
+            serde_json::from_str(r#"{"okWithForcePush": false, "signing": {"signCommits": true}}"#)
+                .unwrap();
+        assert!(!profile.ok_with_force_push);
+        assert_eq!(profile.signing.sign_commits, Some(true));
+        assert_eq!(profile.signing.signing_key, None);
+        assert_eq!(
+            ProjectProfile {
+                ok_with_force_push: true,
+                signing: Default::default(),
+                ..profile
+            },
+            ProjectProfile::default()
+        );
+    }
+}
+
+mod auto_snapshot_interval {
+    use std::time::Duration;
+
+    use gitbutler_project::{Project, UpdateRequest};
+
+    use super::*;
+
+    #[test]
+    fn projects_from_before_the_setting_use_the_default() {
+        let project: Project = serde_json::from_str(
+            r#"{"id":"00000000-0000-0000-0000-000000000000","title":"t","path":"/p","api":null,"snapshot_lines_threshold":null}"#,
+        )
+        .unwrap();
+        assert_eq!(project.auto_snapshot_interval(), Duration::from_secs(300));
+    }
+
+    #[tokio::test]
+    async fn can_be_changed() {
+        let (controller, _tmp) = new();
+        let repository = gitbutler_testsupport::TestProject::default();
+        let project = controller.add(repository.path()).unwrap();
+
+        let project = controller
+            .update(&UpdateRequest {
+                id: project.id,
+                auto_snapshot_interval_secs: Some(60),
+                ..Default::default()
+            })
+            .await
+            .unwrap();
+        assert_eq!(project.auto_snapshot_interval(), Duration::from_secs(60));
+    }
+}
+
+mod snapshot_retention {
+    use gitbutler_project::{RetentionPolicy, UpdateRequest};
+
+    use super::*;
+
+    #[tokio::test]
+    async fn can_be_turned_off() {
+        let (controller, _tmp) = new();
+        let repository = gitbutler_testsupport::TestProject::default();
+        let project = controller.add(repository.path()).unwrap();
+
+        let project = controller
+            .update(&UpdateRequest {
+                id: project.id,
+                snapshot_retention: Some(Some(RetentionPolicy::KeepLast(10))),
+                ..Default::default()
+            })
+            .await
+            .unwrap();
+        assert_eq!(
+            project.snapshot_retention,
+            Some(RetentionPolicy::KeepLast(10))
+        );
+
+        let unchanged: UpdateRequest =
+            serde_json::from_value(serde_json::json!({ "id": project.id })).unwrap();
+        let project = controller.update(&unchanged).await.unwrap();
+        assert_eq!(
+            project.snapshot_retention,
+            Some(RetentionPolicy::KeepLast(10)),
+            "a missing policy leaves it as is"
+        );
+
+        let off: UpdateRequest = serde_json::from_value(
+            serde_json::json!({ "id": project.id, "snapshot_retention": null }),
+        )
+        .unwrap();
+        let project = controller.update(&off).await.unwrap();
+        assert_eq!(project.snapshot_retention, None);
+    }
+}
+
+mod mode {
+    use gitbutler_project::{Project, ProjectMode, UpdateRequest};
+
+    use super::*;
+
+    #[test]
+    fn projects_from_before_the_setting_are_writable() {
+        let project: Project = serde_json::from_str(
+            r#"{"id":"00000000-0000-0000-0000-000000000000","title":"t","path":"/p","api":null,"snapshot_lines_threshold":null}"#,
+        )
+        .unwrap();
+        assert_eq!(project.mode, ProjectMode::Normal);
+        assert!(project.assure_writable().is_ok());
+    }
+
+    #[tokio::test]
+    async fn can_be_made_read_only() {
+        let (controller, _tmp) = new();
+        let repository = gitbutler_testsupport::TestProject::default();
+        let project = controller.add(repository.path()).unwrap();
+
+        let project = controller
+            .update(&UpdateRequest {
+                id: project.id,
+                mode: Some(ProjectMode::ReadOnly),
+                ..Default::default()
+            })
+            .await
+            .unwrap();
+        assert!(project.is_read_only());
+        assert!(project.assure_writable().is_err());
+        assert!(controller.get(project.id).unwrap().is_read_only());
+    }
+}
+
+mod sync_trigger {
+    use gitbutler_project::{ApiProject, SyncTrigger, UpdateRequest};
+
+    use super::*;
+
+    fn api(sync: bool) -> ApiProject {
+        ApiProject {
+            name: "name".into(),
+            description: None,
+            repository_id: "repository".into(),
+            git_url: "git@example.com:repository.git".into(),
+            code_git_url: Some("git@example.com:code.git".into()),
+            created_at: "0".into(),
+            updated_at: "0".into(),
+            sync,
+        }
+    }
+
+    #[tokio::test]
+    async fn only_the_configured_trigger_syncs() {
+        let (controller, _tmp) = new();
+        let repository = gitbutler_testsupport::TestProject::default();
+        let project = controller.add(repository.path()).unwrap();
+
+        let project = controller
+            .update(&UpdateRequest {
+                id: project.id,
+                api: Some(api(true)),
+                ..Default::default()
+            })
+            .await
+            .unwrap();
+        assert_eq!(
+            project.sync_trigger,
+            SyncTrigger::Oplog,
+            "oplog is the default"
+        );
+        assert!(project.syncs_on(SyncTrigger::Oplog));
+        assert!(!project.syncs_on(SyncTrigger::Push));
+
+        let project = controller
+            .update(&UpdateRequest {
+                id: project.id,
+                sync_trigger: Some(SyncTrigger::Push),
+                ..Default::default()
+            })
+            .await
+            .unwrap();
+        assert!(project.syncs_on(SyncTrigger::Push));
+        for trigger in [SyncTrigger::Oplog, SyncTrigger::Manual, SyncTrigger::Timer] {
+            assert!(!project.syncs_on(trigger));
+        }
+    }
+
+    #[tokio::test]
+    async fn nothing_syncs_if_syncing_is_disabled() {
+        let (controller, _tmp) = new();
+        let repository = gitbutler_testsupport::TestProject::default();
+        let project = controller.add(repository.path()).unwrap();
+
+        let project = controller
+            .update(&UpdateRequest {
+                id: project.id,
+                api: Some(api(false)),
+                sync_trigger: Some(SyncTrigger::Timer),
+                ..Default::default()
+            })
+            .await
+            .unwrap();
+        assert!(!project.syncs_on(SyncTrigger::Timer));
+    }
+}
@@ -1,7 +1,9 @@
 mod refname;
 use gitbutler_tagged_string::TaggedString;
 pub use refname::{LocalRefname, Refname, RemoteRefname, VirtualRefname};
+mod validate;
 use regex::Regex;
+pub use validate::{sanitize_branch_name, validate_refname, RefnameViolation};
 
 pub fn normalize_branch_name(name: &str) -> String {
     // Remove specific symbols
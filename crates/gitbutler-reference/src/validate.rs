@@ -0,0 +1,117 @@
+/// A rule of git's reference names that a proposed branch name violates.
+#[derive(Debug, Clone, PartialEq, Eq, thiserror::Error)]
+pub enum RefnameViolation {
+    #[error("branch name is empty")]
+    Empty,
+    #[error("branch name cannot be '@'")]
+    OnlyAt,
+    #[error("branch name cannot contain control characters, found {0:?}")]
+    ControlCharacter(char),
+    #[error("branch name cannot contain '{0}'")]
+    ForbiddenCharacter(char),
+    #[error("branch name cannot contain '..'")]
+    DoubleDot,
+    #[error("branch name cannot contain '@{{'")]
+    AtBrace,
+    #[error("branch name cannot end with '.'")]
+    TrailingDot,
+    #[error("path components of a branch name cannot start with '.', found '{0}'")]
+    ComponentStartsWithDot(String),
+    #[error("path components of a branch name cannot end with '.lock', found '{0}'")]
+    LockSuffix(String),
+}
+
+/// Characters that git doesn't allow anywhere in a reference name.
+const FORBIDDEN_CHARACTERS: &[char] = &['~', '^', ':', '?', '*', '[', '\\'];
+
+/// Normalize `name` into a branch name, then check the result against git's rules for reference names
+/// and return it.
+///
+/// Normalizing is done like [`normalize_branch_name()`](crate::normalize_branch_name()), and additionally
+/// collapses consecutive slashes. What is still invalid after normalizing is returned as the violated rule.
+pub fn validate_refname(name: &str) -> Result<String, RefnameViolation> {
+    let name = crate::normalize_branch_name(name);
+    let components = name
+        .split('/')
+        .filter(|component| !component.is_empty())
+        .collect::<Vec<_>>();
+    let name = components.join("/");
+
+    if name.is_empty() {
+        return Err(RefnameViolation::Empty);
+    }
+    if name == "@" {
+        return Err(RefnameViolation::OnlyAt);
+    }
+    if let Some(control) = name.chars().find(|c| c.is_control()) {
+        return Err(RefnameViolation::ControlCharacter(control));
+    }
+    if let Some(forbidden) = name.chars().find(|c| FORBIDDEN_CHARACTERS.contains(c)) {
+        return Err(RefnameViolation::ForbiddenCharacter(forbidden));
+    }
+    if name.contains("..") {
+        return Err(RefnameViolation::DoubleDot);
+    }
+    if name.contains("@{") {
+        return Err(RefnameViolation::AtBrace);
+    }
+    if name.ends_with('.') {
+        return Err(RefnameViolation::TrailingDot);
+    }
+    for component in components {
+        if component.starts_with('.') {
+            return Err(RefnameViolation::ComponentStartsWithDot(
+                component.to_owned(),
+            ));
+        }
+        if component.ends_with(".lock") {
+            return Err(RefnameViolation::LockSuffix(component.to_owned()));
+        }
+    }
+    Ok(name)
+}
+
+/// Turn an arbitrary `title`, like the one of an issue, into a branch name that passes [`validate_refname()`].
+///
+/// Whitespace and characters git doesn't allow are replaced with `-`, and dots and dashes that aren't allowed
+/// where they are are removed. Non-ASCII characters are kept as git allows them.
+pub fn sanitize_branch_name(title: &str) -> String {
+    let name: String = title
+        .chars()
+        .map(|c| {
+            if c.is_whitespace() || c.is_control() || FORBIDDEN_CHARACTERS.contains(&c) {
+                '-'
+            } else {
+                c
+            }
+        })
+        .collect();
+    let name = name
+        .replace("@{", "-")
+        .split('/')
+        .filter_map(sanitize_component)
+        .collect::<Vec<_>>()
+        .join("/");
+    if name.is_empty() || name == "@" {
+        "branch".to_owned()
+    } else {
+        name
+    }
+}
+
+/// Sanitize a single path component of a branch name, or return `None` if nothing is left of it.
+fn sanitize_component(component: &str) -> Option<String> {
+    let mut component = component
+        .split('-')
+        .filter(|part| !part.is_empty())
+        .collect::<Vec<_>>()
+        .join("-");
+    while component.contains("..") {
+        component = component.replace("..", ".");
+    }
+    let mut component = component.trim_matches(['.', '-']);
+    while let Some(stripped) = component.strip_suffix(".lock") {
+        component = stripped.trim_end_matches(['.', '-']);
+    }
+    (!component.is_empty()).then(|| component.to_owned())
+}
@@ -0,0 +1,31 @@
+use gitbutler_reference::{sanitize_branch_name, validate_refname};
+
+#[test]
+fn titles_become_valid_branch_names() {
+    for (title, expected) in [
+        ("Fix the login page", "Fix-the-login-page"),
+        ("feature/new  thing", "feature/new-thing"),
+        ("fix: crash on start?", "fix-crash-on-start"),
+        ("Ünïcödé 名前 🚀", "Ünïcödé-名前-🚀"),
+        ("--leading dashes", "leading-dashes"),
+        ("-", "branch"),
+        ("trailing dots...", "trailing-dots"),
+        ("v1..v2", "v1.v2"),
+        (".hidden/.config", "hidden/config"),
+        ("branch.lock", "branch"),
+        ("a.lock.lock/b", "a/b"),
+        ("HEAD@{1}", "HEAD-1}"),
+        ("//a//b//", "a/b"),
+        ("", "branch"),
+        ("@", "branch"),
+        ("~^:?*[\\", "branch"),
+    ] {
+        let name = sanitize_branch_name(title);
+        assert_eq!(name, expected, "{title:?}");
+        assert_eq!(
+            validate_refname(&name).as_deref(),
+            Ok(name.as_str()),
+            "{title:?} is sanitized into a valid name that stays as is"
+        );
+    }
+}
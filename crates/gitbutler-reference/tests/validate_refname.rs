@@ -0,0 +1,46 @@
+use gitbutler_reference::{validate_refname, RefnameViolation};
+
+#[test]
+fn valid_names_are_normalized() {
+    for (name, expected) in [
+        ("feature/branch", "feature/branch"),
+        ("foo#branch", "foo#branch"),
+        ("  my new branch ", "my-new-branch"),
+        ("/leading/slash", "leading/slash"),
+        ("double//slash", "double/slash"),
+        ("dots.in.name", "dots.in.name"),
+        ("fix-ünïcödé/名前", "fix-ünïcödé/名前"),
+        ("trailing-dash-", "trailing-dash"),
+        ("fix: it", "fix--it"),
+        ("feature/", "feature"),
+        ("-leading-dash", "leading-dash"),
+        ("new\tbranch", "new-branch"),
+    ] {
+        assert_eq!(validate_refname(name).as_deref(), Ok(expected), "{name:?}");
+    }
+}
+
+#[test]
+fn invalid_names_report_the_violation() {
+    for (name, violation) in [
+        ("", RefnameViolation::Empty),
+        ("  ", RefnameViolation::Empty),
+        ("/", RefnameViolation::Empty),
+        ("@", RefnameViolation::OnlyAt),
+        ("bell\u{7}", RefnameViolation::ControlCharacter('\u{7}')),
+        ("what?", RefnameViolation::ForbiddenCharacter('?')),
+        ("a..b", RefnameViolation::DoubleDot),
+        ("branch@{1}", RefnameViolation::AtBrace),
+        ("branch.", RefnameViolation::TrailingDot),
+        (
+            "feature/.hidden",
+            RefnameViolation::ComponentStartsWithDot(".hidden".into()),
+        ),
+        (
+            "branch.lock/child",
+            RefnameViolation::LockSuffix("branch.lock".into()),
+        ),
+    ] {
+        assert_eq!(validate_refname(name), Err(violation), "{name:?}");
+    }
+}
@@ -1,4 +1,9 @@
-use std::{collections::HashMap, path::Path, sync::Arc};
+use std::{
+    collections::HashMap,
+    path::Path,
+    sync::Arc,
+    time::{Duration, Instant},
+};
 
 use gitbutler_branch::BranchId;
 use gitbutler_id::id::Id;
@@ -47,6 +52,18 @@ pub enum Context {
 pub struct AskpassBroker {
     pending_requests: Arc<Mutex<HashMap<Id<AskpassRequest>, AskpassRequest>>>,
     submit_prompt_event: Arc<dyn Fn(PromptEvent<Context>) + Send + Sync>,
+    response_cache: Arc<Mutex<ResponseCache>>,
+}
+
+/// Responses to prompts, kept in memory only for as long as `ttl`.
+///
+/// Responses are keyed by their prompt, which names the remote host or the key and what is asked for,
+/// so a password is never given in response to a username prompt or to another host.
+/// It deliberately doesn't implement `Debug` to keep the responses out of logs.
+#[derive(Default)]
+struct ResponseCache {
+    ttl: Duration,
+    responses: HashMap<String, (Instant, String)>,
 }
 
 #[derive(Debug, Clone, serde::Serialize)]
@@ -56,25 +73,77 @@ pub struct PromptEvent<C: Serialize + Clone> {
     context: C,
 }
 
+impl<C: Serialize + Clone> PromptEvent<C> {
+    /// The id to pass to [`AskpassBroker::handle_response()`] along with the response.
+    pub fn id(&self) -> Id<AskpassRequest> {
+        self.id
+    }
+}
+
 impl AskpassBroker {
     pub fn init(submit_prompt: impl Fn(PromptEvent<Context>) + Send + Sync + 'static) -> Self {
         Self {
             pending_requests: Arc::new(Mutex::new(HashMap::new())),
             submit_prompt_event: Arc::new(submit_prompt),
+            response_cache: Arc::new(Mutex::new(ResponseCache::default())),
+        }
+    }
+
+    /// Remember responses for `ttl`, so the same prompt isn't shown again until it elapsed.
+    /// A `ttl` of zero, the default, turns caching off and forgets all remembered responses.
+    pub async fn set_cache_ttl(&self, ttl: Duration) {
+        let mut cache = self.response_cache.lock().await;
+        cache.ttl = ttl;
+        if ttl.is_zero() {
+            cache.responses.clear();
         }
     }
 
+    /// Forget all remembered responses, for instance when the user logs out.
+    pub async fn clear_cache(&self) {
+        self.response_cache.lock().await.responses.clear();
+    }
+
+    /// Forget the remembered response to `prompt`, for instance because it was a wrong password.
+    pub async fn forget(&self, prompt: &str) {
+        self.response_cache.lock().await.responses.remove(prompt);
+    }
+
     pub async fn submit_prompt(&self, prompt: String, context: Context) -> Option<String> {
+        {
+            let mut cache = self.response_cache.lock().await;
+            let ttl = cache.ttl;
+            match cache.responses.get(&prompt) {
+                Some((cached_at, response)) if cached_at.elapsed() < ttl => {
+                    return Some(response.clone());
+                }
+                Some(_) => {
+                    cache.responses.remove(&prompt);
+                }
+                None => {}
+            }
+        }
+
         let (sender, receiver) = oneshot::channel();
         let id = Id::generate();
         let request = AskpassRequest { sender };
         self.pending_requests.lock().await.insert(id, request);
         (self.submit_prompt_event)(PromptEvent {
             id,
-            prompt,
+            prompt: prompt.clone(),
             context,
         });
-        receiver.await.unwrap()
+        let response = receiver.await.unwrap();
+
+        if let Some(response) = &response {
+            let mut cache = self.response_cache.lock().await;
+            if !cache.ttl.is_zero() {
+                cache
+                    .responses
+                    .insert(prompt, (Instant::now(), response.clone()));
+            }
+        }
+        response
     }
 
     pub async fn handle_response(&self, id: Id<AskpassRequest>, response: Option<String>) {
@@ -1,10 +1,23 @@
 use crate::Config;
-use anyhow::Result;
+use anyhow::{Context, Result};
 use bstr::BString;
 use gitbutler_command_context::ProjectRepository;
 use gitbutler_project::Project;
+use serde::Serialize;
+use std::path::Path;
 
-use crate::RepositoryExt;
+use crate::{ignore, reflog, GitActivity, IgnoreExplanation, RepositoryExt};
+
+/// The commits connecting two commits, as returned by [`RepoCommands::commit_path()`].
+#[derive(Debug, Clone, PartialEq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct CommitPath {
+    /// `true` if there is no path leading from `from` to `to`, in which case `commits` is empty.
+    pub unrelated: bool,
+    /// The commits on the path, starting with `from` and ending with `to`.
+    #[serde(with = "gitbutler_serde::serde::oid_vec")]
+    pub commits: Vec<git2::Oid>,
+}
 
 pub trait RepoCommands {
     fn add_remote(&self, name: &str, url: &str) -> Result<()>;
@@ -12,6 +25,15 @@ pub trait RepoCommands {
     fn get_local_config(&self, key: &str) -> Result<Option<String>>;
     fn set_local_config(&self, key: &str, value: &str) -> Result<()>;
     fn check_signing_settings(&self) -> Result<bool>;
+    /// Return the commits on the way from `from` to its descendant `to`, following only
+    /// first parents if `first_parent` is set.
+    fn commit_path(&self, from: git2::Oid, to: git2::Oid, first_parent: bool)
+        -> Result<CommitPath>;
+    /// Explain which rule causes the worktree-relative `path` to be ignored, if any.
+    fn explain_ignore(&self, path: &Path) -> Result<Option<IgnoreExplanation>>;
+    /// Return the `limit` most recent git operations that moved `HEAD`, newest first, as recorded
+    /// in its reflog. Malformed reflog lines are skipped.
+    fn recent_git_activity(&self, limit: usize) -> Result<Vec<GitActivity>>;
 }
 
 impl RepoCommands for Project {
@@ -46,4 +68,79 @@ impl RepoCommands for Project {
         project_repository.repo().remote(name, url)?;
         Ok(())
     }
+
+    fn commit_path(
+        &self,
+        from: git2::Oid,
+        to: git2::Oid,
+        first_parent: bool,
+    ) -> Result<CommitPath> {
+        let project_repository = ProjectRepository::open(self)?;
+        commit_path(project_repository.repo(), from, to, first_parent)
+    }
+
+    fn explain_ignore(&self, path: &Path) -> Result<Option<IgnoreExplanation>> {
+        ignore::explain_ignore(&self.path, path)
+    }
+
+    fn recent_git_activity(&self, limit: usize) -> Result<Vec<GitActivity>> {
+        let project_repository = ProjectRepository::open(self)?;
+        reflog::recent_git_activity(project_repository.repo().path(), limit)
+    }
+}
+
+fn commit_path(
+    repo: &git2::Repository,
+    from: git2::Oid,
+    to: git2::Oid,
+    first_parent: bool,
+) -> Result<CommitPath> {
+    let unrelated = CommitPath {
+        unrelated: true,
+        commits: vec![],
+    };
+
+    if first_parent {
+        let mut commits = vec![];
+        let mut commit = repo
+            .find_commit(to)
+            .context(format!("failed to find commit {}", to))?;
+        loop {
+            commits.push(commit.id());
+            if commit.id() == from {
+                commits.reverse();
+                return Ok(CommitPath {
+                    unrelated: false,
+                    commits,
+                });
+            }
+            match commit.parents().next() {
+                Some(parent) => commit = parent,
+                None => return Ok(unrelated),
+            }
+        }
+    }
+
+    if from != to && !repo.graph_descendant_of(to, from)? {
+        return Ok(unrelated);
+    }
+
+    // Only keep the commits that have `from` in their ancestry, the others lead elsewhere.
+    let mut revwalk = repo.revwalk().context("failed to create revwalk")?;
+    revwalk.set_sorting(git2::Sort::TOPOLOGICAL | git2::Sort::REVERSE)?;
+    revwalk.push(to).context(format!("failed to push {}", to))?;
+    revwalk
+        .hide(from)
+        .context(format!("failed to hide {}", from))?;
+    let mut commits = vec![from];
+    for oid in revwalk {
+        let oid = oid.context("failed to get oid")?;
+        if repo.graph_descendant_of(oid, from)? {
+            commits.push(oid);
+        }
+    }
+    Ok(CommitPath {
+        unrelated: false,
+        commits,
+    })
 }
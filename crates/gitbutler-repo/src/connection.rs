@@ -0,0 +1,82 @@
+use serde::Serialize;
+
+/// The category of a failure to connect to a remote, as far as it could be determined.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub enum RemoteConnectionFailure {
+    /// The host of the remote couldn't be resolved.
+    Dns,
+    /// The TLS handshake failed, for instance because the certificate isn't trusted.
+    Tls,
+    /// The credentials were rejected, or there were none to try.
+    Auth,
+    /// The remote repository doesn't exist.
+    NotFound,
+    /// Anything else, see the message for details.
+    Other,
+}
+
+/// The result of checking whether a remote is reachable with the available credentials.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "camelCase", tag = "status")]
+pub enum RemoteConnectionStatus {
+    /// The remote could be listed.
+    Ok,
+    /// The remote could not be listed.
+    Failed {
+        failure: RemoteConnectionFailure,
+        message: String,
+    },
+}
+
+impl RemoteConnectionFailure {
+    /// Categorizes a failure by the message of the error, which is all there is to go by
+    /// for errors of the `git` executable.
+    pub fn from_message(message: &str) -> Self {
+        let message = message.to_lowercase();
+        let mentions = |needles: &[&str]| needles.iter().any(|needle| message.contains(needle));
+        if mentions(&[
+            "could not resolve host",
+            "failed to resolve address",
+            "name or service not known",
+            "nodename nor servname",
+            "no such host",
+        ]) {
+            Self::Dns
+        } else if mentions(&["ssl", "tls", "certificate"]) {
+            Self::Tls
+        } else if mentions(&[
+            "authentication",
+            "permission denied",
+            "could not read username",
+            "error: 401",
+            "error: 403",
+            "status code: 401",
+            "status code: 403",
+        ]) {
+            Self::Auth
+        } else if mentions(&[
+            "not found",
+            "does not exist",
+            "does not appear to be a git repository",
+            "could not find repository",
+            "error: 404",
+            "status code: 404",
+        ]) {
+            Self::NotFound
+        } else {
+            Self::Other
+        }
+    }
+}
+
+impl From<&git2::Error> for RemoteConnectionFailure {
+    fn from(err: &git2::Error) -> Self {
+        match (err.code(), err.class()) {
+            (git2::ErrorCode::Auth, _) => Self::Auth,
+            (git2::ErrorCode::Certificate, _) | (_, git2::ErrorClass::Ssl) => Self::Tls,
+            (git2::ErrorCode::NotFound, _) => Self::NotFound,
+            _ => Self::from_message(err.message()),
+        }
+    }
+}
@@ -0,0 +1,131 @@
+use std::path::{Path, PathBuf};
+
+use anyhow::{Context, Result};
+use ignore::gitignore::{Gitignore, GitignoreBuilder};
+use serde::Serialize;
+
+/// The name of the file at the root of a worktree with gitignore-style patterns of paths that GitButler
+/// disregards, even though git doesn't ignore them.
+pub const GITBUTLER_IGNORE_FILE_NAME: &str = ".gitbutlerignore";
+
+/// Untracked files larger than this are excluded from snapshots.
+pub const SNAPSHOT_FILE_LIMIT_BYTES: u64 = 32 * 1024 * 1024;
+
+/// The mechanism that causes a path to be ignored.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub enum IgnoreSource {
+    /// A pattern in a `.gitignore` file, `.git/info/exclude` or `core.excludesFile`.
+    Gitignore,
+    /// A pattern in the [`.gitbutlerignore`](GITBUTLER_IGNORE_FILE_NAME) file, which only GitButler disregards.
+    GitbutlerIgnore,
+    /// The file is untracked and larger than [`SNAPSHOT_FILE_LIMIT_BYTES`].
+    SizeLimit,
+}
+
+/// Why a path is ignored, as returned by [`RepoCommands::explain_ignore()`](crate::RepoCommands::explain_ignore()).
+#[derive(Debug, Clone, PartialEq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct IgnoreExplanation {
+    pub source: IgnoreSource,
+    /// The rule that matched, like an ignore pattern or the size limit.
+    pub rule: String,
+    /// The file the rule was read from, relative to the worktree if it is inside of it.
+    pub file: Option<PathBuf>,
+}
+
+/// Return the first rule that causes `relative_path` to be ignored in the worktree at `worktree_dir`,
+/// or `None` if the path isn't ignored.
+pub(crate) fn explain_ignore(
+    worktree_dir: &Path,
+    relative_path: &Path,
+) -> Result<Option<IgnoreExplanation>> {
+    let repo = gix::open(worktree_dir).context("failed to open repository")?;
+    let index = repo.index_or_empty()?;
+    let mut excludes = repo.excludes(
+        &index,
+        None,
+        gix::worktree::stack::state::ignore::Source::WorktreeThenIdMappingIfNotSkipped,
+    )?;
+    let platform = excludes.at_path(relative_path, None)?;
+    if platform.is_excluded() {
+        if let Some(matched) = platform.matching_exclude_pattern() {
+            return Ok(Some(IgnoreExplanation {
+                source: IgnoreSource::Gitignore,
+                rule: matched.pattern.to_string(),
+                file: matched.source.map(|source| {
+                    source
+                        .strip_prefix(worktree_dir)
+                        .unwrap_or(source)
+                        .to_owned()
+                }),
+            }));
+        }
+    }
+
+    if let Some(pattern) = GitButlerIgnore::load(worktree_dir)?.matching_pattern(relative_path) {
+        return Ok(Some(IgnoreExplanation {
+            source: IgnoreSource::GitbutlerIgnore,
+            rule: pattern,
+            file: Some(PathBuf::from(GITBUTLER_IGNORE_FILE_NAME)),
+        }));
+    }
+
+    let git2_repo = git2::Repository::open(worktree_dir)?;
+    let is_untracked = git2_repo
+        .status_file(relative_path)
+        .map_or(false, |status| status.is_wt_new());
+    let is_too_large = std::fs::metadata(worktree_dir.join(relative_path))
+        .map_or(false, |metadata| {
+            metadata.is_file() && metadata.len() > SNAPSHOT_FILE_LIMIT_BYTES
+        });
+    if is_untracked && is_too_large {
+        return Ok(Some(IgnoreExplanation {
+            source: IgnoreSource::SizeLimit,
+            rule: format!("untracked files larger than {SNAPSHOT_FILE_LIMIT_BYTES} bytes"),
+            file: None,
+        }));
+    }
+
+    Ok(None)
+}
+
+/// The patterns of the [`.gitbutlerignore`](GITBUTLER_IGNORE_FILE_NAME) file of a worktree. Changes to matching
+/// paths don't trigger any work and aren't assigned to virtual branches automatically.
+///
+/// It's cheap to load, so it's loaded whenever it's needed to pick up changes to the file right away.
+#[derive(Debug, Clone)]
+pub struct GitButlerIgnore(Gitignore);
+
+impl GitButlerIgnore {
+    /// Read the patterns of the `.gitbutlerignore` file in `worktree_dir`. Nothing matches if it doesn't exist,
+    /// and invalid patterns are skipped.
+    pub fn load(worktree_dir: &Path) -> Result<Self> {
+        let mut builder = GitignoreBuilder::new(worktree_dir);
+        let path = worktree_dir.join(GITBUTLER_IGNORE_FILE_NAME);
+        if path.is_file() {
+            if let Some(err) = builder.add(&path) {
+                tracing::warn!(?err, "skipping invalid patterns in {}", path.display());
+            }
+        }
+        Ok(Self(
+            builder
+                .build()
+                .context("failed to build .gitbutlerignore matcher")?,
+        ))
+    }
+
+    /// Return `true` if the worktree-relative `relative_path`, or one of its parent directories, matches.
+    pub fn is_ignored(&self, relative_path: &Path) -> bool {
+        self.matching_pattern(relative_path).is_some()
+    }
+
+    /// Return the pattern that matches the worktree-relative `relative_path` or one of its parent directories,
+    /// as written in the file, or `None` if it isn't ignored.
+    pub fn matching_pattern(&self, relative_path: &Path) -> Option<String> {
+        match self.0.matched_path_or_any_parents(relative_path, false) {
+            ignore::Match::Ignore(glob) => Some(glob.original().to_owned()),
+            _ => None,
+        }
+    }
+}
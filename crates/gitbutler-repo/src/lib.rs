@@ -1,10 +1,23 @@
 pub mod rebase;
 
 mod repository;
+pub use gitbutler_git::FetchDepth;
 pub use repository::{LogUntil, RepoActionsExt};
 
+mod connection;
+pub use connection::{RemoteConnectionFailure, RemoteConnectionStatus};
+
 mod commands;
-pub use commands::RepoCommands;
+pub use commands::{CommitPath, RepoCommands};
+
+mod reflog;
+pub use reflog::GitActivity;
+
+mod ignore;
+pub use self::ignore::{
+    GitButlerIgnore, IgnoreExplanation, IgnoreSource, GITBUTLER_IGNORE_FILE_NAME,
+    SNAPSHOT_FILE_LIMIT_BYTES,
+};
 
 mod repository_ext;
 pub use repository_ext::RepositoryExt;
@@ -62,7 +62,19 @@ pub fn cherry_rebase_group(
                     .context("failed to cherry pick")?;
 
                 if cherrypick_index.has_conflicts() {
-                    return Err(anyhow!("failed to rebase")).context(Marker::BranchConflict);
+                    let conflicting_paths = cherrypick_index
+                        .conflicts()
+                        .context("failed to get cherry pick conflicts")?
+                        .filter_map(Result::ok)
+                        .filter_map(|conflict| conflict.our.or(conflict.their))
+                        .map(|entry| entry.path.to_str_lossy().into_owned())
+                        .collect::<Vec<_>>();
+                    return Err(anyhow!(
+                        "failed to rebase {}, which conflicts in {}",
+                        to_rebase.id(),
+                        conflicting_paths.join(", ")
+                    ))
+                    .context(Marker::BranchConflict);
                 }
 
                 let merge_tree_oid = cherrypick_index
@@ -82,7 +94,7 @@ pub fn cherry_rebase_group(
                         None,
                         &to_rebase.author(),
                         &to_rebase.committer(),
-                        &to_rebase.message_bstr().to_str_lossy(),
+                        &to_rebase.message_decoded().to_str_lossy(),
                         &merge_tree,
                         &[&head],
                         commit_headers,
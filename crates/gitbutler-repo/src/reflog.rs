@@ -0,0 +1,72 @@
+use std::path::Path;
+
+use anyhow::{Context, Result};
+use serde::Serialize;
+
+/// An entry of the reflog of `HEAD`, describing a git operation that moved it.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct GitActivity {
+    /// The commit `HEAD` pointed to before the operation, all zeros if it didn't exist yet.
+    #[serde(with = "gitbutler_serde::serde::oid")]
+    pub old_id: git2::Oid,
+    /// The commit `HEAD` pointed to after the operation.
+    #[serde(with = "gitbutler_serde::serde::oid")]
+    pub new_id: git2::Oid,
+    /// The kind of operation, like `commit`, `checkout` or `rebase (finish)`.
+    pub action: String,
+    /// What the operation did, like the commit subject or the branches checked out.
+    pub message: String,
+    /// When the operation happened, in milliseconds since the unix epoch.
+    pub timestamp_ms: u128,
+}
+
+/// Read the reflog of `HEAD` in `git_dir` and return its `limit` most recent entries, newest first.
+pub(crate) fn recent_git_activity(git_dir: &Path, limit: usize) -> Result<Vec<GitActivity>> {
+    let path = git_dir.join("logs").join("HEAD");
+    let content = match std::fs::read(&path) {
+        Ok(content) => content,
+        Err(err) if err.kind() == std::io::ErrorKind::NotFound => return Ok(Vec::new()),
+        Err(err) => return Err(err).with_context(|| format!("failed to read {}", path.display())),
+    };
+    let content = String::from_utf8_lossy(&content);
+    Ok(content
+        .lines()
+        .rev()
+        .filter_map(parse_line)
+        .take(limit)
+        .collect())
+}
+
+/// Parse a reflog `line` of the form `<old> <new> <name> <<email>> <seconds> <offset>\t<message>`,
+/// or return `None` if it's malformed.
+fn parse_line(line: &str) -> Option<GitActivity> {
+    let (header, message) = line.split_once('\t').unwrap_or((line, ""));
+    let mut ids = header.splitn(3, ' ');
+    let old_id = parse_id(ids.next()?)?;
+    let new_id = parse_id(ids.next()?)?;
+    let signature = ids.next()?;
+    // The name may contain anything but `<`, so the time is found after the end of the email.
+    let (_, time) = signature.rsplit_once('>')?;
+    let mut time = time.split_whitespace();
+    let seconds: u128 = time.next()?.parse().ok()?;
+    let _offset = time.next()?;
+
+    let message = message.trim();
+    let (action, message) = message.split_once(": ").unwrap_or((message, ""));
+    Some(GitActivity {
+        old_id,
+        new_id,
+        action: action.to_owned(),
+        message: message.to_owned(),
+        timestamp_ms: seconds * 1000,
+    })
+}
+
+/// Parse a full hexadecimal object id, as abbreviated ones are never written to the reflog.
+fn parse_id(id: &str) -> Option<git2::Oid> {
+    if id.len() != 40 {
+        return None;
+    }
+    git2::Oid::from_str(id).ok()
+}
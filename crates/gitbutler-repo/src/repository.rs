@@ -1,20 +1,29 @@
 use std::str::FromStr;
 
-use anyhow::{anyhow, Context, Result};
+use anyhow::{anyhow, bail, Context, Result};
 
 use gitbutler_branch::{Branch, BranchId};
 use gitbutler_command_context::ProjectRepository;
 use gitbutler_commit::commit_headers::CommitHeadersV2;
 use gitbutler_error::error::Code;
+use gitbutler_git::FetchDepth;
 use gitbutler_reference::{Refname, RemoteRefname};
 
 use crate::{askpass, ssh, Config};
 use gitbutler_project::AuthKey;
 
-use crate::{credentials::Helper, RepositoryExt};
+use crate::{
+    credentials::{Credential, Helper},
+    RemoteConnectionFailure, RemoteConnectionStatus, RepositoryExt,
+};
 pub trait RepoActionsExt {
-    fn fetch(&self, remote_name: &str, credentials: &Helper, askpass: Option<String>)
-        -> Result<()>;
+    fn fetch(
+        &self,
+        remote_name: &str,
+        credentials: &Helper,
+        askpass: Option<String>,
+        depth: Option<FetchDepth>,
+    ) -> Result<()>;
     fn push(
         &self,
         head: &git2::Oid,
@@ -45,6 +54,14 @@ pub trait RepoActionsExt {
         branch_name: &str,
         askpass: Option<Option<BranchId>>,
     ) -> Result<()>;
+    /// Checks that `remote_name` is reachable and accepts the credentials by listing its branches,
+    /// without fetching anything.
+    fn test_remote_connection(
+        &self,
+        remote_name: &str,
+        credentials: &Helper,
+        askpass: Option<String>,
+    ) -> Result<RemoteConnectionStatus>;
 }
 
 impl RepoActionsExt for ProjectRepository {
@@ -265,21 +282,36 @@ impl RepoActionsExt for ProjectRepository {
         // NOTE(qix-): without a lot of work. This is a temporary measure to
         // NOTE(qix-): work around a time-sensitive change that was necessary
         // NOTE(qix-): without having to refactor a large portion of the codebase.
-        if self.project().preferred_key == AuthKey::SystemExecutable {
+        if self.project().preferred_key == AuthKey::SystemExecutable
+            && Helper::configured_source(self, branch.remote()).is_none()
+        {
             let path = self.project().worktree_path();
             let remote = branch.remote().to_string();
             return std::thread::spawn(move || {
                 tokio::runtime::Runtime::new()
                     .unwrap()
-                    .block_on(gitbutler_git::push(
-                        path,
-                        gitbutler_git::tokio::TokioExecutor,
-                        &remote,
-                        gitbutler_git::RefSpec::parse(refspec).unwrap(),
-                        with_force,
-                        handle_git_prompt_push,
-                        askpass_broker,
-                    ))
+                    .block_on(async move {
+                        let mut prompts = Vec::new();
+                        let result = gitbutler_git::push(
+                            path,
+                            gitbutler_git::tokio::TokioExecutor,
+                            &remote,
+                            gitbutler_git::RefSpec::parse(refspec).unwrap(),
+                            with_force,
+                            |prompt: String, askpass_broker: Option<Option<BranchId>>| {
+                                if askpass_broker.is_some() {
+                                    prompts.push(prompt.clone());
+                                }
+                                handle_git_prompt_push(prompt, askpass_broker)
+                            },
+                            askpass_broker,
+                        )
+                        .await;
+                        if matches!(result, Err(gitbutler_git::Error::AuthorizationFailed(_))) {
+                            forget_answers(prompts).await;
+                        }
+                        result
+                    })
             })
             .join()
             .unwrap()
@@ -287,6 +319,7 @@ impl RepoActionsExt for ProjectRepository {
         }
 
         let auth_flows = credentials.help(self, branch.remote())?;
+        let mut key_error = None;
         for (mut remote, callbacks) in auth_flows {
             if let Some(url) = remote.url() {
                 if !self.project().omit_certificate_check.unwrap_or(false) {
@@ -296,7 +329,21 @@ impl RepoActionsExt for ProjectRepository {
             }
             let mut update_refs_error: Option<git2::Error> = None;
             for callback in callbacks {
-                let mut cbs: git2::RemoteCallbacks = callback.into();
+                let passphrase_prompt = callback
+                    .passphrase_prompt()
+                    .filter(|_| askpass_broker.is_some());
+                let credential = match callback.with_passphrase(|prompt| {
+                    block_on(handle_git_prompt_push(prompt, askpass_broker))
+                }) {
+                    Ok(credential) => credential,
+                    Err(err) => {
+                        tracing::warn!(project_id = %self.project().id, ?err, "failed to use the ssh key");
+                        forget_prompt(passphrase_prompt);
+                        key_error = Some(err);
+                        continue;
+                    }
+                };
+                let mut cbs: git2::RemoteCallbacks = credential.clone().into();
                 if self.project().omit_certificate_check.unwrap_or(false) {
                     cbs.certificate_check(|_, _| Ok(git2::CertificateCheckStatus::CertificateOk));
                 }
@@ -323,6 +370,11 @@ impl RepoActionsExt for ProjectRepository {
                         );
                         return Ok(());
                     }
+                    Err(err) if credential.key_error(&err).is_some() => {
+                        tracing::warn!(project_id = %self.project().id, ?err, "push failed due to the ssh key");
+                        forget_prompt(passphrase_prompt);
+                        return Err(credential.key_error(&err).unwrap_or_else(|| err.into()));
+                    }
                     Err(err) => match err.class() {
                         git2::ErrorClass::Net | git2::ErrorClass::Http => {
                             tracing::warn!(project_id = %self.project().id, ?err, "push failed due to network");
@@ -331,6 +383,7 @@ impl RepoActionsExt for ProjectRepository {
                         _ => match err.code() {
                             git2::ErrorCode::Auth => {
                                 tracing::warn!(project_id = %self.project().id, ?err, "push failed due to auth");
+                                forget_prompt(passphrase_prompt);
                                 continue;
                             }
                             _ => {
@@ -345,7 +398,8 @@ impl RepoActionsExt for ProjectRepository {
             }
         }
 
-        Err(anyhow!("authentication failed").context(Code::ProjectGitAuth))
+        Err(key_error
+            .unwrap_or_else(|| anyhow!("authentication failed").context(Code::ProjectGitAuth)))
     }
 
     fn fetch(
@@ -353,6 +407,7 @@ impl RepoActionsExt for ProjectRepository {
         remote_name: &str,
         credentials: &Helper,
         askpass: Option<String>,
+        depth: Option<FetchDepth>,
     ) -> Result<()> {
         let refspec = format!("+refs/heads/*:refs/remotes/{}/*", remote_name);
 
@@ -361,27 +416,54 @@ impl RepoActionsExt for ProjectRepository {
         // NOTE(qix-): without a lot of work. This is a temporary measure to
         // NOTE(qix-): work around a time-sensitive change that was necessary
         // NOTE(qix-): without having to refactor a large portion of the codebase.
-        if self.project().preferred_key == AuthKey::SystemExecutable {
+        if self.project().preferred_key == AuthKey::SystemExecutable
+            && Helper::configured_source(self, remote_name).is_none()
+        {
             let path = self.project().worktree_path();
             let remote = remote_name.to_string();
             return std::thread::spawn(move || {
                 tokio::runtime::Runtime::new()
                     .unwrap()
-                    .block_on(gitbutler_git::fetch(
-                        path,
-                        gitbutler_git::tokio::TokioExecutor,
-                        &remote,
-                        gitbutler_git::RefSpec::parse(refspec).unwrap(),
-                        handle_git_prompt_fetch,
-                        askpass,
-                    ))
+                    .block_on(async move {
+                        let mut prompts = Vec::new();
+                        let result = gitbutler_git::fetch(
+                            path,
+                            gitbutler_git::tokio::TokioExecutor,
+                            &remote,
+                            gitbutler_git::RefSpec::parse(refspec).unwrap(),
+                            depth,
+                            |prompt: String, askpass: Option<String>| {
+                                if askpass.is_some() {
+                                    prompts.push(prompt.clone());
+                                }
+                                handle_git_prompt_fetch(prompt, askpass)
+                            },
+                            askpass,
+                        )
+                        .await;
+                        if matches!(result, Err(gitbutler_git::Error::AuthorizationFailed(_))) {
+                            forget_answers(prompts).await;
+                        }
+                        result
+                    })
             })
             .join()
             .unwrap()
-            .map_err(Into::into);
+            .map_err(|err| match err {
+                gitbutler_git::Error::ShallowNotSupported(..) => shallow_not_supported(remote_name),
+                err => err.into(),
+            });
+        }
+
+        if let Some(FetchDepth::Deepen(_)) = depth {
+            return Err(anyhow!(
+                "Deepening a shallow clone is only supported when using the Git executable"
+            )
+            .context(Code::Validation));
         }
 
         let auth_flows = credentials.help(self, remote_name)?;
+        let mut key_error = None;
         for (mut remote, callbacks) in auth_flows {
             if let Some(url) = remote.url() {
                 if !self.project().omit_certificate_check.unwrap_or(false) {
@@ -390,19 +472,43 @@ impl RepoActionsExt for ProjectRepository {
                 }
             }
             for callback in callbacks {
+                let passphrase_prompt = callback.passphrase_prompt().filter(|_| askpass.is_some());
+                let credential = match callback.with_passphrase(|prompt| {
+                    block_on(handle_git_prompt_fetch(prompt, askpass.clone()))
+                }) {
+                    Ok(credential) => credential,
+                    Err(err) => {
+                        tracing::warn!(project_id = %self.project().id, ?err, "failed to use the ssh key");
+                        forget_prompt(passphrase_prompt);
+                        key_error = Some(err);
+                        continue;
+                    }
+                };
                 let mut fetch_opts = git2::FetchOptions::new();
-                let mut cbs: git2::RemoteCallbacks = callback.into();
+                let mut cbs: git2::RemoteCallbacks = credential.clone().into();
                 if self.project().omit_certificate_check.unwrap_or(false) {
                     cbs.certificate_check(|_, _| Ok(git2::CertificateCheckStatus::CertificateOk));
                 }
                 fetch_opts.remote_callbacks(cbs);
                 fetch_opts.prune(git2::FetchPrune::On);
+                if let Some(FetchDepth::Depth(depth)) = depth {
+                    fetch_opts.depth(i32::try_from(depth).unwrap_or(i32::MAX));
+                }
 
                 match remote.fetch(&[&refspec], Some(&mut fetch_opts), None) {
                     Ok(()) => {
-                        tracing::info!(project_id = %self.project().id, %refspec, "git fetched");
+                        tracing::info!(project_id = %self.project().id, %refspec, ?depth, "git fetched");
                         return Ok(());
                     }
+                    Err(err) if credential.key_error(&err).is_some() => {
+                        tracing::warn!(project_id = %self.project().id, ?err, "fetch failed due to the ssh key");
+                        forget_prompt(passphrase_prompt);
+                        return Err(credential.key_error(&err).unwrap_or_else(|| err.into()));
+                    }
+                    Err(err) if depth.is_some() && err.message().contains("shallow") => {
+                        tracing::warn!(project_id = %self.project().id, ?err, "shallow fetch failed");
+                        return Err(shallow_not_supported(remote_name));
+                    }
                     Err(err) => match err.class() {
                         git2::ErrorClass::Net | git2::ErrorClass::Http => {
                             tracing::warn!(project_id = %self.project().id, ?err, "fetch failed due to network");
@@ -411,6 +517,7 @@ impl RepoActionsExt for ProjectRepository {
                         _ => match err.code() {
                             git2::ErrorCode::Auth => {
                                 tracing::warn!(project_id = %self.project().id, ?err, "fetch failed due to auth");
+                                forget_prompt(passphrase_prompt);
                                 continue;
                             }
                             _ => {
@@ -422,10 +529,123 @@ impl RepoActionsExt for ProjectRepository {
             }
         }
 
-        Err(anyhow!("authentication failed")).context(Code::ProjectGitAuth)
+        Err(key_error
+            .unwrap_or_else(|| anyhow!("authentication failed").context(Code::ProjectGitAuth)))
+    }
+
+    fn test_remote_connection(
+        &self,
+        remote_name: &str,
+        credentials: &Helper,
+        askpass: Option<String>,
+    ) -> Result<RemoteConnectionStatus> {
+        // NOTE: see `fetch()` for why the executable is special-cased here.
+        if self.project().preferred_key == AuthKey::SystemExecutable
+            && Helper::configured_source(self, remote_name).is_none()
+        {
+            let path = self.project().worktree_path();
+            let remote = remote_name.to_string();
+            let result = std::thread::spawn(move || {
+                tokio::runtime::Runtime::new()
+                    .unwrap()
+                    .block_on(async move {
+                        let mut prompts = Vec::new();
+                        let result = gitbutler_git::ls_remote(
+                            path,
+                            gitbutler_git::tokio::TokioExecutor,
+                            &remote,
+                            |prompt: String, askpass: Option<String>| {
+                                if askpass.is_some() {
+                                    prompts.push(prompt.clone());
+                                }
+                                handle_git_prompt_fetch(prompt, askpass)
+                            },
+                            askpass,
+                        )
+                        .await;
+                        if matches!(result, Err(gitbutler_git::Error::AuthorizationFailed(_))) {
+                            forget_answers(prompts).await;
+                        }
+                        result
+                    })
+            })
+            .join()
+            .unwrap();
+            return Ok(match result {
+                Ok(()) => RemoteConnectionStatus::Ok,
+                Err(err) => {
+                    let message = err.to_string();
+                    let failure = if matches!(err, gitbutler_git::Error::AuthorizationFailed(_)) {
+                        RemoteConnectionFailure::Auth
+                    } else {
+                        RemoteConnectionFailure::from_message(&message)
+                    };
+                    RemoteConnectionStatus::Failed { failure, message }
+                }
+            });
+        }
+
+        let mut status = RemoteConnectionStatus::Failed {
+            failure: RemoteConnectionFailure::Auth,
+            message: "no credentials to try".into(),
+        };
+        for (mut remote, mut callbacks) in credentials.help(self, remote_name)? {
+            if callbacks.is_empty() {
+                // still try to connect, which also tells apart an unreachable remote
+                callbacks.push(Credential::Noop);
+            }
+            for callback in callbacks {
+                let passphrase_prompt = callback.passphrase_prompt().filter(|_| askpass.is_some());
+                let credential = match callback.with_passphrase(|prompt| {
+                    block_on(handle_git_prompt_fetch(prompt, askpass.clone()))
+                }) {
+                    Ok(credential) => credential,
+                    Err(err) => {
+                        forget_prompt(passphrase_prompt);
+                        status = RemoteConnectionStatus::Failed {
+                            failure: RemoteConnectionFailure::Auth,
+                            message: err.root_cause().to_string(),
+                        };
+                        continue;
+                    }
+                };
+                let mut cbs: git2::RemoteCallbacks = credential.clone().into();
+                if self.project().omit_certificate_check.unwrap_or(false) {
+                    cbs.certificate_check(|_, _| Ok(git2::CertificateCheckStatus::CertificateOk));
+                }
+                match remote
+                    .connect_auth(git2::Direction::Fetch, Some(cbs), None)
+                    .and_then(|connection| connection.list().map(|_| ()))
+                {
+                    Ok(()) => return Ok(RemoteConnectionStatus::Ok),
+                    Err(err) => {
+                        tracing::warn!(project_id = %self.project().id, ?err, "remote connection failed");
+                        status = match credential.key_error(&err) {
+                            Some(key_error) => {
+                                forget_prompt(passphrase_prompt);
+                                RemoteConnectionStatus::Failed {
+                                    failure: RemoteConnectionFailure::Auth,
+                                    message: key_error.root_cause().to_string(),
+                                }
+                            }
+                            None => RemoteConnectionStatus::Failed {
+                                failure: (&err).into(),
+                                message: err.message().to_owned(),
+                            },
+                        };
+                    }
+                }
+            }
+        }
+        Ok(status)
     }
 }
 
+fn shallow_not_supported(remote_name: &str) -> anyhow::Error {
+    anyhow!("The remote '{remote_name}' doesn't support shallow fetches, fetch its full history instead")
+        .context(Code::Validation)
+}
+
 fn signatures(project_repo: &ProjectRepository) -> Result<(git2::Signature, git2::Signature)> {
     let config: Config = project_repo.repo().into();
 
@@ -454,6 +674,33 @@ pub enum LogUntil {
     End,
 }
 
+/// Wait for `future`, like the answer to an askpass prompt, from synchronous code on the current runtime.
+fn block_on<F: std::future::Future>(future: F) -> Result<F::Output> {
+    let handle = tokio::runtime::Handle::try_current()
+        .context("waiting for a prompt needs to happen on a Tokio runtime")?;
+    if handle.runtime_flavor() != tokio::runtime::RuntimeFlavor::MultiThread {
+        bail!("waiting for a prompt needs a multi-threaded Tokio runtime");
+    }
+    Ok(tokio::task::block_in_place(|| handle.block_on(future)))
+}
+
+/// Forget the remembered answers to `prompts`, as authenticating with them failed, so they are asked for again.
+async fn forget_answers(prompts: Vec<String>) {
+    for prompt in prompts {
+        askpass::get_broker().forget(&prompt).await;
+    }
+}
+
+/// Forget the remembered answer to `prompt`, as it was for a passphrase that turned out not to work.
+fn forget_prompt(prompt: Option<String>) {
+    let Some(prompt) = prompt else {
+        return;
+    };
+    if let Err(err) = block_on(askpass::get_broker().forget(&prompt)) {
+        tracing::warn!(?err, "failed to forget the answer to a prompt");
+    }
+}
+
 async fn handle_git_prompt_push(
     prompt: String,
     askpass: Option<Option<BranchId>>,
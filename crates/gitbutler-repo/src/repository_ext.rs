@@ -1,5 +1,5 @@
 use anyhow::{anyhow, bail, Context, Result};
-use bstr::BString;
+use bstr::{BString, ByteSlice};
 use git2::{BlameOptions, Repository, Tree};
 use gitbutler_commit::{commit_buffer::CommitBuffer, commit_headers::CommitHeadersV2};
 use gitbutler_config::git::{GbConfig, GitConfig};
@@ -183,27 +183,83 @@ impl RepositoryExt for Repository {
 
         buffer.set_gitbutler_headers(commit_headers);
 
-        let oid = if self.gb_config()?.sign_commits.unwrap_or(false) {
-            let signature = self.sign_buffer(&buffer);
-            match signature {
-                Ok(signature) => self
-                    .commit_signed(
-                        buffer.as_bstring().to_string().as_str(),
-                        signature.to_string().as_str(),
-                        None,
-                    )
-                    .map_err(Into::into),
+        let config = self.gb_config()?;
+        if let Some(encoding) = config.commit_encoding.as_deref() {
+            let encoding = encoding_rs::Encoding::for_label(encoding.as_bytes())
+                .ok_or_else(|| anyhow!("unknown commit encoding '{encoding}'"))?
+                // Messages can't be encoded in UTF-16 and the like, which are written as UTF-8 instead.
+                .output_encoding();
+            if encoding != encoding_rs::UTF_8 {
+                let (encoded_message, _, had_unmappable_characters) = encoding.encode(message);
+                if had_unmappable_characters {
+                    bail!(
+                        "commit message can't be represented in the {} encoding",
+                        encoding.name()
+                    );
+                }
+                buffer.set_header("encoding", encoding.name());
+                buffer.set_message(encoded_message.as_ref());
+            }
+        }
+
+        // Our own setting takes precedence, so signing can be turned off for GitButler only.
+        // Signing just because of `commit.gpgSign` never fails a commit, as `git` would, but leaves it unsigned.
+        let git_config = self.config()?;
+        let (sign_commits, signing_required) = match config.sign_commits {
+            Some(sign_commits) => (sign_commits, true),
+            None => (
+                git_config.get_bool("commit.gpgSign").unwrap_or(false),
+                false,
+            ),
+        };
+        // OpenPGP can sign with the default key of gpg, but SSH needs to be told which key to use.
+        let is_ssh = git_config
+            .get_string("gpg.format")
+            .is_ok_and(|format| format == "ssh");
+        let has_signing_key = git_config.get_string("user.signingKey").is_ok();
+        if sign_commits && is_ssh && !has_signing_key {
+            tracing::warn!(
+                "commit signing with SSH is enabled, but user.signingKey isn't set - committing unsigned"
+            );
+        }
+
+        let signature = if sign_commits && (has_signing_key || !is_ssh) {
+            match self.sign_buffer(&buffer) {
+                Ok(signature) => Some(signature),
+                Err(err) if !signing_required => {
+                    tracing::warn!(?err, "failed to sign commit - committing unsigned");
+                    None
+                }
                 Err(e) => {
                     // If signing fails, set the "gitbutler.signCommits" config to false before erroring out
                     self.set_gb_config(GbConfig {
                         sign_commits: Some(false),
                         ..GbConfig::default()
                     })?;
-                    Err(anyhow!("Failed to sign commit: {}", e).context(Code::CommitSigningFailed))
+                    return Err(
+                        anyhow!("Failed to sign commit: {}", e).context(Code::CommitSigningFailed)
+                    );
                 }
             }
         } else {
-            commit_buffer(self, &buffer)
+            None
+        };
+
+        let oid = match signature {
+            Some(signature) => match buffer.as_bstring().to_str() {
+                Ok(buffer_str) => self
+                    .commit_signed(buffer_str, signature.to_string().as_str(), None)
+                    .map_err(Into::into),
+                Err(_) => {
+                    // `commit_signed()` needs UTF-8, so add the signature header ourselves.
+                    buffer.set_header(
+                        "gpgsig",
+                        &signature.to_string().trim_end().replace('\n', "\n "),
+                    );
+                    commit_buffer(self, &buffer)
+                }
+            },
+            None => commit_buffer(self, &buffer),
         }?;
         // update reference
         if let Some(refname) = update_ref {
@@ -311,48 +367,17 @@ impl RepositoryExt for Repository {
                     bail!("Failed to sign SSH: {}", std_both);
                 }
             } else {
-                // is gpg
-                let gpg_program = self.config()?.get_string("gpg.program");
-                let mut gpg_program = gpg_program.unwrap_or("gpg".to_string());
-                // if cmd is "", use gpg
-                if gpg_program.is_empty() {
-                    gpg_program = "gpg".to_string();
-                }
-
-                let mut cmd = std::process::Command::new(gpg_program);
-
-                cmd.args(["--status-fd=2", "-bsau", &signing_key])
-                    //.arg(&signed_storage)
-                    .arg("-")
-                    .stdout(Stdio::piped())
-                    .stderr(Stdio::piped())
-                    .stdin(Stdio::piped());
-
-                #[cfg(windows)]
-                cmd.creation_flags(0x08000000); // CREATE_NO_WINDOW
-
-                let mut child = cmd
-                    .spawn()
-                    .context(anyhow::format_err!("failed to spawn {:?}", cmd))?;
-                child
-                    .stdin
-                    .take()
-                    .expect("configured")
-                    .write_all(&buffer.as_bstring())?;
-
-                let output = child.wait_with_output()?;
-                if output.status.success() {
-                    // read stdout
-                    let signature = BString::new(output.stdout);
-                    return Ok(signature);
-                } else {
-                    let stderr = BString::new(output.stderr);
-                    let stdout = BString::new(output.stdout);
-                    let std_both = format!("{} {}", stdout, stderr);
-                    bail!("Failed to sign GPG: {}", std_both);
-                }
+                return sign_with_gpg(self, buffer, Some(&signing_key));
             }
         }
+        // OpenPGP signs with the default key of gpg if there is no signing key, unlike SSH.
+        let is_ssh = self
+            .config()?
+            .get_string("gpg.format")
+            .is_ok_and(|format| format == "ssh");
+        if !is_ssh {
+            return sign_with_gpg(self, buffer, None);
+        }
         Err(anyhow::anyhow!("No signing key found"))
     }
 
@@ -438,3 +463,49 @@ impl CheckoutIndexBuilder<'_> {
             .map_err(Into::into)
     }
 }
+
+/// Sign `buffer` with `gpg`, or the configured `gpg.program`, using `signing_key` or the default key of gpg.
+fn sign_with_gpg(
+    repo: &git2::Repository,
+    buffer: &CommitBuffer,
+    signing_key: Option<&str>,
+) -> Result<BString> {
+    let gpg_program = repo.config()?.get_string("gpg.program");
+    let mut gpg_program = gpg_program.unwrap_or("gpg".to_string());
+    // if cmd is "", use gpg
+    if gpg_program.is_empty() {
+        gpg_program = "gpg".to_string();
+    }
+
+    let mut cmd = std::process::Command::new(gpg_program);
+
+    cmd.args(["--status-fd=2", "-bsa"]);
+    if let Some(signing_key) = signing_key {
+        cmd.args(["-u", signing_key]);
+    }
+    cmd.arg("-")
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .stdin(Stdio::piped());
+
+    #[cfg(windows)]
+    cmd.creation_flags(0x08000000); // CREATE_NO_WINDOW
+
+    let mut child = cmd
+        .spawn()
+        .context(anyhow::format_err!("failed to spawn {:?}", cmd))?;
+    child
+        .stdin
+        .take()
+        .expect("configured")
+        .write_all(&buffer.as_bstring())?;
+
+    let output = child.wait_with_output()?;
+    if !output.status.success() {
+        let stderr = BString::new(output.stderr);
+        let stdout = BString::new(output.stdout);
+        let std_both = format!("{} {}", stdout, stderr);
+        bail!("Failed to sign GPG: {}", std_both);
+    }
+    Ok(BString::new(output.stdout))
+}
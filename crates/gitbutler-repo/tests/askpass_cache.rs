@@ -0,0 +1,125 @@
+use std::{
+    sync::{
+        atomic::{AtomicUsize, Ordering},
+        Arc,
+    },
+    time::Duration,
+};
+
+use gitbutler_repo::askpass::{AskpassBroker, Context};
+
+/// A broker which answers every prompt with `secret`, counting how often it was asked.
+fn answering_broker() -> (AskpassBroker, Arc<AtomicUsize>) {
+    let prompts = Arc::new(AtomicUsize::new(0));
+    let (tx, mut rx) = tokio::sync::mpsc::unbounded_channel();
+    let broker = AskpassBroker::init({
+        let prompts = prompts.clone();
+        move |event| {
+            prompts.fetch_add(1, Ordering::SeqCst);
+            tx.send(event.id()).unwrap();
+        }
+    });
+    tokio::spawn({
+        let broker = broker.clone();
+        async move {
+            while let Some(id) = rx.recv().await {
+                broker.handle_response(id, Some("secret".into())).await;
+            }
+        }
+    });
+    (broker, prompts)
+}
+
+async fn prompt(broker: &AskpassBroker, prompt: &str) -> Option<String> {
+    broker
+        .submit_prompt(
+            prompt.into(),
+            Context::Fetch {
+                action: "test".into(),
+            },
+        )
+        .await
+}
+
+#[tokio::test]
+async fn responses_are_not_cached_by_default() {
+    let (broker, prompts) = answering_broker();
+    let password_prompt = "Password for 'https://user@example.com': ";
+
+    assert_eq!(
+        prompt(&broker, password_prompt).await.as_deref(),
+        Some("secret")
+    );
+    assert_eq!(
+        prompt(&broker, password_prompt).await.as_deref(),
+        Some("secret")
+    );
+    assert_eq!(prompts.load(Ordering::SeqCst), 2);
+}
+
+#[tokio::test]
+async fn responses_are_reused_per_prompt_until_cleared() {
+    let (broker, prompts) = answering_broker();
+    broker.set_cache_ttl(Duration::from_secs(60)).await;
+    let password_prompt = "Password for 'https://user@example.com': ";
+
+    assert_eq!(
+        prompt(&broker, password_prompt).await.as_deref(),
+        Some("secret")
+    );
+    assert_eq!(
+        prompt(&broker, password_prompt).await.as_deref(),
+        Some("secret")
+    );
+    assert_eq!(
+        prompts.load(Ordering::SeqCst),
+        1,
+        "the second prompt is answered from the cache"
+    );
+
+    prompt(&broker, "Password for 'https://user@other.example.com': ").await;
+    assert_eq!(
+        prompts.load(Ordering::SeqCst),
+        2,
+        "other hosts are asked for separately"
+    );
+
+    broker.clear_cache().await;
+    prompt(&broker, password_prompt).await;
+    assert_eq!(
+        prompts.load(Ordering::SeqCst),
+        3,
+        "clearing forgets all responses"
+    );
+}
+
+#[tokio::test]
+async fn responses_expire_after_the_ttl() {
+    let (broker, prompts) = answering_broker();
+    broker.set_cache_ttl(Duration::from_millis(50)).await;
+    let password_prompt = "Password for 'https://user@example.com': ";
+
+    prompt(&broker, password_prompt).await;
+    tokio::time::sleep(Duration::from_millis(100)).await;
+    prompt(&broker, password_prompt).await;
+    assert_eq!(prompts.load(Ordering::SeqCst), 2);
+}
+
+#[tokio::test]
+async fn forgotten_responses_are_asked_for_again() {
+    let (broker, prompts) = answering_broker();
+    broker.set_cache_ttl(Duration::from_secs(60)).await;
+    let password_prompt = "Password for 'https://user@example.com': ";
+    let username_prompt = "Username for 'https://example.com': ";
+
+    prompt(&broker, password_prompt).await;
+    prompt(&broker, username_prompt).await;
+    broker.forget(password_prompt).await;
+    prompt(&broker, password_prompt).await;
+    prompt(&broker, username_prompt).await;
+    assert_eq!(
+        prompts.load(Ordering::SeqCst),
+        3,
+        "only the forgotten response is asked for again"
+    );
+}
@@ -0,0 +1,74 @@
+use bstr::ByteSlice;
+use gitbutler_commit::commit_ext::CommitExt;
+use gitbutler_repo::RepositoryExt;
+use gitbutler_testsupport::test_repository;
+
+fn commit(repo: &git2::Repository, message: &str) -> anyhow::Result<git2::Oid> {
+    let head = repo.head().unwrap().peel_to_commit().unwrap();
+    let signature = git2::Signature::now("test", "test@email.com").unwrap();
+    repo.commit_with_signature(
+        None,
+        &signature,
+        &signature,
+        message,
+        &head.tree().unwrap(),
+        &[&head],
+        None,
+    )
+}
+
+#[test]
+fn utf8_by_default() {
+    let (repo, _tmp) = test_repository();
+
+    let oid = commit(&repo, "Grüße").unwrap();
+
+    let commit = repo.find_commit(oid).unwrap();
+    assert_eq!(commit.message_encoding(), None);
+    assert_eq!(commit.message_bytes(), "Grüße".as_bytes());
+    assert_eq!(commit.message_decoded(), "Grüße");
+}
+
+#[test]
+fn non_utf8_encoding_round_trips() {
+    let (repo, _tmp) = test_repository();
+    repo.config()
+        .unwrap()
+        .set_str("i18n.commitEncoding", "ISO-8859-1")
+        .unwrap();
+
+    let oid = commit(&repo, "Grüße").unwrap();
+
+    let commit = repo.find_commit(oid).unwrap();
+    // ISO-8859-1 is an alias for windows-1252 as far as the encoding standard is concerned
+    assert_eq!(commit.message_encoding(), Some("windows-1252"));
+    assert_eq!(commit.message_bytes(), b"Gr\xfc\xdfe");
+    assert!(commit.message_bytes().to_str().is_err());
+    assert_eq!(commit.message_decoded(), "Grüße");
+}
+
+#[test]
+fn utf16_is_written_as_utf8() {
+    let (repo, _tmp) = test_repository();
+    repo.config()
+        .unwrap()
+        .set_str("i18n.commitEncoding", "UTF-16LE")
+        .unwrap();
+
+    let oid = commit(&repo, "Grüße").unwrap();
+
+    let commit = repo.find_commit(oid).unwrap();
+    assert_eq!(commit.message_encoding(), None);
+    assert_eq!(commit.message_bytes(), "Grüße".as_bytes());
+}
+
+#[test]
+fn unmappable_characters_are_rejected() {
+    let (repo, _tmp) = test_repository();
+    repo.config()
+        .unwrap()
+        .set_str("i18n.commitEncoding", "ISO-8859-1")
+        .unwrap();
+
+    assert!(commit(&repo, "日本語").is_err());
+}
@@ -0,0 +1,54 @@
+use gitbutler_project as projects;
+use gitbutler_repo::RepoCommands;
+use gitbutler_testsupport::{commit_all, test_repository};
+
+fn project_for(repo: &git2::Repository) -> projects::Project {
+    projects::Project {
+        path: repo.workdir().unwrap().to_path_buf(),
+        ..Default::default()
+    }
+}
+
+#[test]
+fn linear() {
+    let (repo, _tmp) = test_repository();
+    let first = repo.head().unwrap().target().unwrap();
+    std::fs::write(repo.workdir().unwrap().join("file"), "second").unwrap();
+    let second = commit_all(&repo);
+    std::fs::write(repo.workdir().unwrap().join("file"), "third").unwrap();
+    let third = commit_all(&repo);
+    let project = project_for(&repo);
+
+    for first_parent in [true, false] {
+        let path = project.commit_path(first, third, first_parent).unwrap();
+        assert!(!path.unrelated);
+        assert_eq!(path.commits, vec![first, second, third]);
+
+        let path = project.commit_path(second, second, first_parent).unwrap();
+        assert!(!path.unrelated);
+        assert_eq!(path.commits, vec![second]);
+
+        // there is no way back to an ancestor
+        let path = project.commit_path(third, first, first_parent).unwrap();
+        assert!(path.unrelated);
+        assert!(path.commits.is_empty());
+    }
+}
+
+#[test]
+fn unrelated() {
+    let (repo, _tmp) = test_repository();
+    let head = repo.head().unwrap().target().unwrap();
+    let signature = git2::Signature::now("test", "test@email.com").unwrap();
+    let tree = repo.head().unwrap().peel_to_tree().unwrap();
+    let orphan = repo
+        .commit(None, &signature, &signature, "orphan", &tree, &[])
+        .unwrap();
+    let project = project_for(&repo);
+
+    for first_parent in [true, false] {
+        let path = project.commit_path(orphan, head, first_parent).unwrap();
+        assert!(path.unrelated);
+        assert!(path.commits.is_empty());
+    }
+}
@@ -0,0 +1,162 @@
+use std::path::Path;
+use std::process::Command;
+
+use gitbutler_commit::commit_ext::CommitExt;
+use gitbutler_repo::RepositoryExt;
+use gitbutler_testsupport::test_repository;
+
+fn commit(repo: &git2::Repository) -> anyhow::Result<git2::Oid> {
+    let head = repo.head().unwrap().peel_to_commit().unwrap();
+    let signature = git2::Signature::now("test", "test@email.com").unwrap();
+    repo.commit_with_signature(
+        None,
+        &signature,
+        &signature,
+        "signed",
+        &head.tree().unwrap(),
+        &[&head],
+        None,
+    )
+}
+
+/// Generate an SSH key in `dir` and configure `repo` to sign commits with it, like `git` would.
+fn configure_ssh_signing(repo: &git2::Repository, dir: &Path) {
+    let key = dir.join("key");
+    let status = Command::new("ssh-keygen")
+        .args([
+            "-q",
+            "-t",
+            "ed25519",
+            "-N",
+            "",
+            "-C",
+            "test@email.com",
+            "-f",
+        ])
+        .arg(&key)
+        .status()
+        .unwrap();
+    assert!(status.success());
+
+    let public_key = std::fs::read_to_string(key.with_extension("pub")).unwrap();
+    let allowed_signers = dir.join("allowed_signers");
+    std::fs::write(&allowed_signers, format!("test@email.com {public_key}")).unwrap();
+
+    let mut config = repo.config().unwrap();
+    config.set_bool("commit.gpgSign", true).unwrap();
+    config.set_str("gpg.format", "ssh").unwrap();
+    config
+        .set_str("user.signingKey", key.to_str().unwrap())
+        .unwrap();
+    config
+        .set_str(
+            "gpg.ssh.allowedSignersFile",
+            allowed_signers.to_str().unwrap(),
+        )
+        .unwrap();
+}
+
+#[test]
+fn commit_gpgsign_signs_with_the_configured_ssh_key() {
+    let (repo, _tmp) = test_repository();
+    let keys = tempfile::tempdir().unwrap();
+    configure_ssh_signing(&repo, keys.path());
+
+    let oid = commit(&repo).unwrap();
+    assert!(repo.find_commit(oid).unwrap().is_signed());
+
+    let output = Command::new("git")
+        .arg("verify-commit")
+        .arg(oid.to_string())
+        .current_dir(repo.workdir().unwrap())
+        .output()
+        .unwrap();
+    assert!(
+        output.status.success(),
+        "the signature verifies: {}",
+        String::from_utf8_lossy(&output.stderr)
+    );
+}
+
+#[test]
+fn gitbutler_setting_takes_precedence_over_commit_gpgsign() {
+    let (repo, _tmp) = test_repository();
+    let keys = tempfile::tempdir().unwrap();
+    configure_ssh_signing(&repo, keys.path());
+    repo.config()
+        .unwrap()
+        .set_bool("gitbutler.signCommits", false)
+        .unwrap();
+
+    let oid = commit(&repo).unwrap();
+    assert!(!repo.find_commit(oid).unwrap().is_signed());
+}
+
+#[test]
+fn missing_signing_key_commits_unsigned() {
+    let (repo, _tmp) = test_repository();
+    repo.config()
+        .unwrap()
+        .set_bool("commit.gpgSign", true)
+        .unwrap();
+
+    let oid = commit(&repo).unwrap();
+    assert!(!repo.find_commit(oid).unwrap().is_signed());
+}
+
+#[test]
+fn failing_to_sign_for_commit_gpgsign_commits_unsigned() {
+    let (repo, _tmp) = test_repository();
+    let keys = tempfile::tempdir().unwrap();
+    configure_ssh_signing(&repo, keys.path());
+    repo.config()
+        .unwrap()
+        .set_str(
+            "user.signingKey",
+            keys.path().join("missing").to_str().unwrap(),
+        )
+        .unwrap();
+
+    let oid = commit(&repo).unwrap();
+    assert!(!repo.find_commit(oid).unwrap().is_signed());
+    assert!(
+        repo.config()
+            .unwrap()
+            .get_bool("gitbutler.signCommits")
+            .is_err(),
+        "signing stays enabled for the next commit"
+    );
+}
+
+#[test]
+#[cfg(unix)]
+fn openpgp_signs_with_the_default_key_without_signing_key() {
+    use std::os::unix::fs::PermissionsExt;
+
+    let (repo, _tmp) = test_repository();
+    let dir = tempfile::tempdir().unwrap();
+    let args_path = dir.path().join("args");
+    let gpg = dir.path().join("gpg");
+    std::fs::write(
+        &gpg,
+        format!(
+            "#!/bin/sh\necho \"$@\" > '{}'\ncat > /dev/null\nprintf -- '-----BEGIN PGP SIGNATURE-----\\n\\nsignature\\n-----END PGP SIGNATURE-----\\n'\n",
+            args_path.display()
+        ),
+    )
+    .unwrap();
+    std::fs::set_permissions(&gpg, std::fs::Permissions::from_mode(0o755)).unwrap();
+    let mut config = repo.config().unwrap();
+    config.set_bool("commit.gpgSign", true).unwrap();
+    config
+        .set_str("gpg.program", gpg.to_str().unwrap())
+        .unwrap();
+
+    let oid = commit(&repo).unwrap();
+    assert!(repo.find_commit(oid).unwrap().is_signed());
+    let args = std::fs::read_to_string(args_path).unwrap();
+    assert!(
+        !args.split_whitespace().any(|arg| arg == "-u"),
+        "no key is passed to gpg: {args}"
+    );
+}
@@ -0,0 +1,73 @@
+use std::path::Path;
+
+use gitbutler_project as projects;
+use gitbutler_repo::{IgnoreSource, RepoCommands, SNAPSHOT_FILE_LIMIT_BYTES};
+use gitbutler_testsupport::test_repository;
+
+fn project_for(repo: &git2::Repository) -> projects::Project {
+    projects::Project {
+        path: repo.workdir().unwrap().to_path_buf(),
+        ..Default::default()
+    }
+}
+
+#[test]
+fn not_ignored() {
+    let (repo, _tmp) = test_repository();
+    std::fs::write(repo.workdir().unwrap().join("file.txt"), "content").unwrap();
+
+    let explanation = project_for(&repo)
+        .explain_ignore(Path::new("file.txt"))
+        .unwrap();
+    assert_eq!(explanation, None);
+}
+
+#[test]
+fn gitignore() {
+    let (repo, _tmp) = test_repository();
+    let worktree_dir = repo.workdir().unwrap();
+    std::fs::write(worktree_dir.join(".gitignore"), "*.log\n").unwrap();
+    std::fs::write(worktree_dir.join("debug.log"), "content").unwrap();
+
+    let explanation = project_for(&repo)
+        .explain_ignore(Path::new("debug.log"))
+        .unwrap()
+        .expect("ignored");
+    assert_eq!(explanation.source, IgnoreSource::Gitignore);
+    assert_eq!(explanation.rule, "*.log");
+    assert_eq!(explanation.file.as_deref(), Some(Path::new(".gitignore")));
+}
+
+#[test]
+fn gitbutler_ignore() {
+    let (repo, _tmp) = test_repository();
+    let worktree_dir = repo.workdir().unwrap();
+    std::fs::write(worktree_dir.join(".gitbutlerignore"), "generated/\n").unwrap();
+    std::fs::create_dir(worktree_dir.join("generated")).unwrap();
+    std::fs::write(worktree_dir.join("generated/schema.rs"), "content").unwrap();
+
+    let explanation = project_for(&repo)
+        .explain_ignore(Path::new("generated/schema.rs"))
+        .unwrap()
+        .expect("ignored");
+    assert_eq!(explanation.source, IgnoreSource::GitbutlerIgnore);
+    assert_eq!(explanation.rule, "generated/");
+    assert_eq!(
+        explanation.file.as_deref(),
+        Some(Path::new(".gitbutlerignore"))
+    );
+}
+
+#[test]
+fn size_limit() {
+    let (repo, _tmp) = test_repository();
+    let file = std::fs::File::create(repo.workdir().unwrap().join("database.sql")).unwrap();
+    file.set_len(SNAPSHOT_FILE_LIMIT_BYTES + 1).unwrap();
+
+    let explanation = project_for(&repo)
+        .explain_ignore(Path::new("database.sql"))
+        .unwrap()
+        .expect("ignored");
+    assert_eq!(explanation.source, IgnoreSource::SizeLimit);
+    assert_eq!(explanation.file, None);
+}
@@ -0,0 +1,27 @@
+use std::path::Path;
+
+use gitbutler_repo::{GitButlerIgnore, GITBUTLER_IGNORE_FILE_NAME};
+
+#[test]
+fn matches_gitignore_style_patterns() {
+    let dir = tempfile::tempdir().unwrap();
+    std::fs::write(
+        dir.path().join(GITBUTLER_IGNORE_FILE_NAME),
+        "target/**\n*.log\n!keep.log\n",
+    )
+    .unwrap();
+
+    let ignore = GitButlerIgnore::load(dir.path()).unwrap();
+    assert!(ignore.is_ignored(Path::new("target/debug/build/out.o")));
+    assert!(ignore.is_ignored(Path::new("nested/debug.log")));
+    assert!(!ignore.is_ignored(Path::new("keep.log")));
+    assert!(!ignore.is_ignored(Path::new("src/main.rs")));
+    assert!(!ignore.is_ignored(Path::new("targets/file")));
+}
+
+#[test]
+fn missing_file_matches_nothing() {
+    let dir = tempfile::tempdir().unwrap();
+    let ignore = GitButlerIgnore::load(dir.path()).unwrap();
+    assert!(!ignore.is_ignored(Path::new("target/debug/out.o")));
+}
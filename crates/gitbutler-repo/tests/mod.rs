@@ -1 +1,11 @@
+mod askpass_cache;
+mod commit_encoding;
+mod commit_path;
+mod commit_signing;
 mod credentials;
+mod explain_ignore;
+mod gitbutler_ignore;
+mod recent_git_activity;
+mod remote_connection;
+mod shallow_fetch;
+mod ssh_key_passphrase;
@@ -0,0 +1,65 @@
+use gitbutler_project as projects;
+use gitbutler_repo::RepoCommands;
+use gitbutler_testsupport::{commit_all, test_repository};
+
+fn project_for(repo: &git2::Repository) -> projects::Project {
+    projects::Project {
+        path: repo.workdir().unwrap().to_path_buf(),
+        ..Default::default()
+    }
+}
+
+#[test]
+fn commits_are_listed_newest_first() {
+    let (repo, _tmp) = test_repository();
+    let first = repo.head().unwrap().target().unwrap();
+    std::fs::write(repo.workdir().unwrap().join("file"), "second").unwrap();
+    let second = commit_all(&repo);
+    std::fs::write(repo.workdir().unwrap().join("file"), "third").unwrap();
+    let third = commit_all(&repo);
+    let project = project_for(&repo);
+
+    let activity = project.recent_git_activity(2).unwrap();
+    assert_eq!(activity.len(), 2);
+    assert_eq!((activity[0].old_id, activity[0].new_id), (second, third));
+    assert_eq!((activity[1].old_id, activity[1].new_id), (first, second));
+    assert!(activity[0].timestamp_ms >= activity[1].timestamp_ms);
+}
+
+#[test]
+fn malformed_lines_are_skipped() {
+    let (repo, _tmp) = test_repository();
+    let old = "1111111111111111111111111111111111111111";
+    let new = "2222222222222222222222222222222222222222";
+    std::fs::write(
+        repo.path().join("logs").join("HEAD"),
+        format!(
+            "{old} {new} A <a@example.com> 1700000000 +0100\tcommit (amend): fix typo\n\
+             not a reflog line\n\
+             {old} {new} A <a@example.com> yesterday +0100\tcheckout: moving from a to b\n\
+             {new} {old} Some  One <one@example.com> 1700000060 -0500\tcheckout: moving from main to feature\n\
+             {old} {new} A <a@example.com> 1700000120 +0000\n"
+        ),
+    )
+    .unwrap();
+    let project = project_for(&repo);
+
+    let activity = project.recent_git_activity(10).unwrap();
+    assert_eq!(
+        activity
+            .iter()
+            .map(|entry| (
+                entry.action.as_str(),
+                entry.message.as_str(),
+                entry.timestamp_ms
+            ))
+            .collect::<Vec<_>>(),
+        vec![
+            ("", "", 1_700_000_120_000),
+            ("checkout", "moving from main to feature", 1_700_000_060_000),
+            ("commit (amend)", "fix typo", 1_700_000_000_000),
+        ]
+    );
+    assert_eq!(activity[1].old_id.to_string(), new);
+    assert_eq!(activity[1].new_id.to_string(), old);
+}
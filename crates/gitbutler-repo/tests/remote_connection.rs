@@ -0,0 +1,106 @@
+use gitbutler_command_context::ProjectRepository;
+use gitbutler_project as projects;
+use gitbutler_repo::{
+    credentials::Helper, RemoteConnectionFailure, RemoteConnectionStatus, RepoActionsExt,
+};
+use gitbutler_testsupport::{empty_bare_repository, test_repository};
+
+fn test_connection(remote_url: &str) -> RemoteConnectionStatus {
+    let (repo, _tmp) = test_repository();
+    repo.remote("origin", remote_url).unwrap();
+    let project = projects::Project {
+        path: repo.workdir().unwrap().to_path_buf(),
+        preferred_key: projects::AuthKey::GitCredentialsHelper,
+        ..Default::default()
+    };
+    let project_repository = ProjectRepository::open(&project).unwrap();
+    project_repository
+        .test_remote_connection("origin", &Helper::default(), None)
+        .unwrap()
+}
+
+fn failure(status: RemoteConnectionStatus) -> RemoteConnectionFailure {
+    match status {
+        RemoteConnectionStatus::Failed { failure, .. } => failure,
+        RemoteConnectionStatus::Ok => panic!("expected the connection to fail"),
+    }
+}
+
+#[test]
+fn local_remote_is_reachable() {
+    let (remote, _tmp) = empty_bare_repository();
+    let status = test_connection(remote.path().to_str().unwrap());
+    assert_eq!(status, RemoteConnectionStatus::Ok);
+}
+
+#[test]
+fn missing_local_remote_is_not_found() {
+    let tmp = gitbutler_testsupport::temp_dir();
+    let missing = tmp.path().join("does-not-exist");
+    let status = test_connection(missing.to_str().unwrap());
+    assert_eq!(failure(status), RemoteConnectionFailure::NotFound);
+}
+
+#[test]
+fn unresolvable_host_is_a_dns_failure() {
+    let status = test_connection("https://gitbutler-test.invalid/test.git");
+    assert_eq!(failure(status), RemoteConnectionFailure::Dns);
+}
+
+mod failure_from_message {
+    use super::*;
+
+    #[test]
+    fn dns() {
+        assert_eq!(
+            RemoteConnectionFailure::from_message(
+                "fatal: unable to access 'https://example.invalid/test.git/': Could not resolve host: example.invalid"
+            ),
+            RemoteConnectionFailure::Dns
+        );
+    }
+
+    #[test]
+    fn tls() {
+        assert_eq!(
+            RemoteConnectionFailure::from_message(
+                "fatal: unable to access 'https://example.com/test.git/': SSL certificate problem: self-signed certificate"
+            ),
+            RemoteConnectionFailure::Tls
+        );
+    }
+
+    #[test]
+    fn auth() {
+        assert_eq!(
+            RemoteConnectionFailure::from_message(
+                "git@example.com: Permission denied (publickey).\nfatal: Could not read from remote repository."
+            ),
+            RemoteConnectionFailure::Auth
+        );
+        assert_eq!(
+            RemoteConnectionFailure::from_message(
+                "fatal: Authentication failed for 'https://example.com/test.git/'"
+            ),
+            RemoteConnectionFailure::Auth
+        );
+    }
+
+    #[test]
+    fn not_found() {
+        assert_eq!(
+            RemoteConnectionFailure::from_message(
+                "remote: Repository not found.\nfatal: repository 'https://example.com/test.git/' not found"
+            ),
+            RemoteConnectionFailure::NotFound
+        );
+    }
+
+    #[test]
+    fn other() {
+        assert_eq!(
+            RemoteConnectionFailure::from_message("fatal: the remote end hung up unexpectedly"),
+            RemoteConnectionFailure::Other
+        );
+    }
+}
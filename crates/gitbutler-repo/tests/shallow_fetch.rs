@@ -0,0 +1,89 @@
+use gitbutler_command_context::ProjectRepository;
+use gitbutler_project as projects;
+use gitbutler_repo::{credentials::Helper, FetchDepth, RepoActionsExt};
+use gitbutler_testsupport::{commit_all, test_repository};
+
+fn fetch(depth: Option<FetchDepth>) -> anyhow::Result<()> {
+    gitbutler_testsupport::secrets::setup_blackhole_store();
+    let (remote, _remote_tmp) = test_repository();
+    let (repo, _tmp) = test_repository();
+    repo.remote("origin", remote.path().to_str().unwrap())
+        .unwrap();
+    let project = projects::Project {
+        path: repo.workdir().unwrap().to_path_buf(),
+        preferred_key: projects::AuthKey::GitCredentialsHelper,
+        ..Default::default()
+    };
+    let project_repository = ProjectRepository::open(&project).unwrap();
+    project_repository.fetch("origin", &Helper::default(), None, depth)
+}
+
+/// Return how many commits of the first-parent history of `id` are present in `repo`.
+fn available_history(repo: &git2::Repository, id: git2::Oid) -> usize {
+    let mut count = 0;
+    let mut next = Some(id);
+    while let Some(commit) = next.and_then(|id| repo.find_commit(id).ok()) {
+        count += 1;
+        next = commit.parent_id(0).ok();
+    }
+    count
+}
+
+#[test]
+fn full_fetch_from_local_remote() {
+    fetch(None).unwrap();
+}
+
+#[test]
+fn shallow_fetch_is_limited_to_the_depth() {
+    gitbutler_testsupport::secrets::setup_blackhole_store();
+    let (remote, _remote_tmp) = test_repository();
+    for content in ["one", "two", "three"] {
+        std::fs::write(remote.workdir().unwrap().join("file.txt"), content).unwrap();
+        commit_all(&remote);
+    }
+    let (repo, _tmp) = test_repository();
+    // Unlike plain paths, which use a local transport, `file://` URLs support shallow fetches.
+    let url = format!("file://{}", remote.path().display());
+    repo.remote("origin", &url).unwrap();
+    let project = projects::Project {
+        path: repo.workdir().unwrap().to_path_buf(),
+        preferred_key: projects::AuthKey::SystemExecutable,
+        ..Default::default()
+    };
+    let project_repository = ProjectRepository::open(&project).unwrap();
+    project_repository
+        .fetch(
+            "origin",
+            &Helper::default(),
+            None,
+            Some(FetchDepth::Depth(2)),
+        )
+        .unwrap();
+
+    let remote_head = remote.head().unwrap().target().unwrap();
+    assert_eq!(available_history(&remote, remote_head), 4);
+    let fetched_head = repo.refname_to_id("refs/remotes/origin/master").unwrap();
+    assert_eq!(fetched_head, remote_head);
+    assert!(repo.is_shallow());
+    assert_eq!(
+        available_history(&repo, fetched_head),
+        2,
+        "only the last two commits were fetched"
+    );
+}
+
+#[test]
+fn shallow_fetch_from_remote_without_support_is_a_clear_error() {
+    let err = fetch(Some(FetchDepth::Depth(1))).unwrap_err();
+    assert!(
+        format!("{err:#}").contains("The remote 'origin' doesn't support shallow fetches"),
+        "{err:#}"
+    );
+}
+
+#[test]
+fn deepen_needs_the_git_executable() {
+    let err = fetch(Some(FetchDepth::Deepen(10))).unwrap_err();
+    assert!(format!("{err:#}").contains("Git executable"), "{err:#}");
+}
@@ -0,0 +1,166 @@
+use std::{
+    path::{Path, PathBuf},
+    sync::{
+        atomic::{AtomicUsize, Ordering},
+        Arc,
+    },
+    time::Duration,
+};
+
+use gitbutler_command_context::ProjectRepository;
+use gitbutler_error::error::Code;
+use gitbutler_project as projects;
+use gitbutler_repo::{
+    askpass,
+    credentials::{ssh_key_is_encrypted, Credential, Helper, SshCredential},
+    RepoActionsExt,
+};
+use gitbutler_testsupport::test_repository;
+
+fn fixture(name: &str) -> PathBuf {
+    Path::new(env!("CARGO_MANIFEST_DIR"))
+        .join("tests/fixtures/ssh")
+        .join(name)
+}
+
+fn keyfile(name: &str, passphrase: Option<&str>) -> Credential {
+    Credential::Ssh(SshCredential::Keyfile {
+        key_path: fixture(name),
+        passphrase: passphrase.map(ToOwned::to_owned),
+    })
+}
+
+fn wrong_passphrase_error() -> git2::Error {
+    git2::Error::new(
+        git2::ErrorCode::GenericError,
+        git2::ErrorClass::Ssh,
+        "Failed to authenticate SSH session: Unable to extract public key from private key file: \
+         Wrong passphrase or invalid/unrecognized private key file format",
+    )
+}
+
+#[test]
+fn detects_encrypted_keys() {
+    assert!(ssh_key_is_encrypted(&fixture("encrypted_ed25519")).unwrap());
+    assert!(!ssh_key_is_encrypted(&fixture("plain_ed25519")).unwrap());
+}
+
+#[test]
+fn asks_for_the_passphrase_of_encrypted_keys() {
+    let mut prompts = Vec::new();
+    let credential = keyfile("encrypted_ed25519", None)
+        .with_passphrase(|prompt| {
+            prompts.push(prompt);
+            Ok(Some("correct horse".into()))
+        })
+        .unwrap();
+
+    assert_eq!(
+        credential,
+        keyfile("encrypted_ed25519", Some("correct horse"))
+    );
+    assert_eq!(prompts.len(), 1);
+    assert!(prompts[0].starts_with("Enter passphrase for key"));
+}
+
+#[test]
+fn does_not_ask_for_plain_keys() {
+    let credential = keyfile("plain_ed25519", None)
+        .with_passphrase(|_| panic!("plain keys have no passphrase"))
+        .unwrap();
+    assert_eq!(credential, keyfile("plain_ed25519", None));
+}
+
+#[test]
+fn missing_passphrase_is_an_auth_error() {
+    let err = keyfile("encrypted_ed25519", None)
+        .with_passphrase(|_| Ok(None))
+        .unwrap_err();
+    assert_eq!(err.downcast_ref::<Code>(), Some(&Code::ProjectGitAuth));
+    assert!(format!("{err:#}").contains("A passphrase is needed to use the SSH key"));
+}
+
+#[test]
+fn wrong_passphrase_is_rejected_right_away() {
+    let err = keyfile("encrypted_ed25519", None)
+        .with_passphrase(|_| Ok(Some("wrong".into())))
+        .unwrap_err();
+    assert_eq!(err.downcast_ref::<Code>(), Some(&Code::ProjectGitAuth));
+    assert!(format!("{err:#}").contains("is the passphrase correct?"));
+}
+
+#[test]
+fn wrong_passphrase_is_an_auth_error() {
+    let credential = keyfile("encrypted_ed25519", Some("wrong"));
+    let err = credential
+        .key_error(&wrong_passphrase_error())
+        .expect("the key couldn't be unlocked");
+    assert_eq!(err.downcast_ref::<Code>(), Some(&Code::ProjectGitAuth));
+    assert!(format!("{err:#}").contains("is the passphrase correct?"));
+}
+
+#[test]
+fn other_errors_are_not_about_the_key() {
+    let credential = keyfile("encrypted_ed25519", Some("correct horse"));
+    let err = git2::Error::new(
+        git2::ErrorCode::GenericError,
+        git2::ErrorClass::Net,
+        "failed to resolve address",
+    );
+    assert!(credential.key_error(&err).is_none());
+    assert!(keyfile("encrypted_ed25519", None)
+        .key_error(&wrong_passphrase_error())
+        .is_none());
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn wrong_passphrase_fails_the_fetch_and_is_forgotten() {
+    let prompts = Arc::new(AtomicUsize::new(0));
+    let (tx, mut rx) = tokio::sync::mpsc::unbounded_channel();
+    // SAFETY: this is the only test of this binary using the global broker.
+    unsafe {
+        askpass::init({
+            let prompts = prompts.clone();
+            move |event| {
+                prompts.fetch_add(1, Ordering::SeqCst);
+                tx.send(event.id()).unwrap();
+            }
+        });
+    }
+    tokio::spawn(async move {
+        while let Some(id) = rx.recv().await {
+            askpass::get_broker()
+                .handle_response(id, Some("wrong".into()))
+                .await;
+        }
+    });
+    askpass::get_broker()
+        .set_cache_ttl(Duration::from_secs(60))
+        .await;
+
+    let (repo, _tmp) = test_repository();
+    repo.remote("origin", "ssh://git@127.0.0.1:1/repo.git")
+        .unwrap();
+    let project = projects::Project {
+        path: repo.workdir().unwrap().to_path_buf(),
+        preferred_key: projects::AuthKey::Local {
+            private_key_path: fixture("encrypted_ed25519"),
+        },
+        omit_certificate_check: Some(true),
+        ..Default::default()
+    };
+    let project_repository = ProjectRepository::open(&project).unwrap();
+
+    for attempt in 1..=2 {
+        let err = project_repository
+            .fetch("origin", &Helper::default(), Some("test".into()), None)
+            .unwrap_err();
+        assert_eq!(err.downcast_ref::<Code>(), Some(&Code::ProjectGitAuth));
+        assert!(format!("{err:#}").contains("is the passphrase correct?"));
+        assert_eq!(
+            prompts.load(Ordering::SeqCst),
+            attempt,
+            "the wrong passphrase isn't answered from the cache"
+        );
+    }
+}
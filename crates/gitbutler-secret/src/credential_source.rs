@@ -0,0 +1,118 @@
+//! Per-remote choices of how to authenticate, persisted in the secret store along with the token they
+//! may need, so neither is ever written to disk in plain text.
+
+use std::path::PathBuf;
+
+use anyhow::{anyhow, bail, Result};
+use serde::{Deserialize, Serialize};
+
+use crate::secret::{self, Namespace};
+use crate::Sensitive;
+
+/// How to authenticate with a remote.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase", tag = "type", content = "keyPath")]
+pub enum CredentialSource {
+    /// Use the token stored for the remote, over HTTPS.
+    Token,
+    /// Use the private SSH key at the given path.
+    SshKey(PathBuf),
+    /// Use the keys offered by the running SSH agent.
+    SshAgent,
+    /// Ask the user, which is also what happens if no source is configured.
+    Askpass,
+}
+
+/// Persist `source` as the way to authenticate with `remote` among the secrets of `scope`.
+///
+/// `token` is required for [`CredentialSource::Token`] unless one was stored before, and is ignored otherwise.
+/// Switching to any other source deletes the stored token.
+pub fn persist(
+    scope: &str,
+    remote: &str,
+    source: &CredentialSource,
+    token: Option<&Sensitive<String>>,
+) -> Result<()> {
+    if *source == CredentialSource::Token {
+        match token {
+            Some(token) if !token.0.is_empty() => {
+                secret::persist_scoped(scope, &token_name(remote), token, Namespace::BuildKind)?;
+            }
+            _ => {
+                if retrieve_token(scope, remote)?.is_none() {
+                    bail!("a token is needed to authenticate with '{remote}' using a token");
+                }
+            }
+        }
+    } else {
+        secret::delete_scoped(scope, &token_name(remote), Namespace::BuildKind)?;
+    }
+    secret::persist_scoped(
+        scope,
+        &source_name(remote),
+        &Sensitive(encode(source)?),
+        Namespace::BuildKind,
+    )
+}
+
+/// Obtain the source previously [persisted](persist()) for `remote` among the secrets of `scope`.
+pub fn retrieve(scope: &str, remote: &str) -> Result<Option<CredentialSource>> {
+    secret::retrieve_scoped(scope, &source_name(remote), Namespace::BuildKind)?
+        .map(|encoded| decode(&encoded.0))
+        .transpose()
+}
+
+/// Obtain the token to use with [`CredentialSource::Token`] for `remote` among the secrets of `scope`.
+pub fn retrieve_token(scope: &str, remote: &str) -> Result<Option<Sensitive<String>>> {
+    secret::retrieve_scoped(scope, &token_name(remote), Namespace::BuildKind)
+}
+
+/// Delete the source of `remote` and its token, if any, from the secrets of `scope`.
+pub fn delete(scope: &str, remote: &str) -> Result<()> {
+    secret::delete_scoped(scope, &token_name(remote), Namespace::BuildKind)?;
+    secret::delete_scoped(scope, &source_name(remote), Namespace::BuildKind)
+}
+
+/// Return `true` if the secret `name` is one of the sources or tokens managed by this module, and not a
+/// secret stored by the user.
+pub fn is_managed_secret(name: &str) -> bool {
+    name.starts_with(SOURCE_NAME_PREFIX) || name.starts_with(TOKEN_NAME_PREFIX)
+}
+
+const SOURCE_NAME_PREFIX: &str = "credential-source-";
+const TOKEN_NAME_PREFIX: &str = "token-";
+
+fn source_name(remote: &str) -> String {
+    format!("{SOURCE_NAME_PREFIX}{remote}")
+}
+
+fn token_name(remote: &str) -> String {
+    format!("{TOKEN_NAME_PREFIX}{remote}")
+}
+
+const SSH_KEY_PREFIX: &str = "ssh-key:";
+
+fn encode(source: &CredentialSource) -> Result<String> {
+    Ok(match source {
+        CredentialSource::Token => "token".into(),
+        CredentialSource::SshKey(path) => format!(
+            "{SSH_KEY_PREFIX}{}",
+            path.to_str()
+                .ok_or_else(|| anyhow!("the SSH key path must be valid UTF-8: {path:?}"))?
+        ),
+        CredentialSource::SshAgent => "ssh-agent".into(),
+        CredentialSource::Askpass => "askpass".into(),
+    })
+}
+
+fn decode(encoded: &str) -> Result<CredentialSource> {
+    Ok(match encoded {
+        "token" => CredentialSource::Token,
+        "ssh-agent" => CredentialSource::SshAgent,
+        "askpass" => CredentialSource::Askpass,
+        _ => match encoded.strip_prefix(SSH_KEY_PREFIX) {
+            Some(path) => CredentialSource::SshKey(path.into()),
+            None => bail!("unknown credential source: {encoded:?}"),
+        },
+    })
+}
@@ -1,3 +1,4 @@
+pub mod credential_source;
 pub mod secret;
 pub mod sensitive;
 
@@ -4,7 +4,7 @@
 //! in memory beyond their use.
 
 use crate::Sensitive;
-use anyhow::Result;
+use anyhow::{bail, Result};
 use std::sync::Mutex;
 
 /// Determines how a secret's name should be modified to produce a namespace.
@@ -46,6 +46,87 @@ pub fn delete(handle: &str, namespace: Namespace) -> Result<()> {
     Ok(entry_for(handle, namespace)?.delete_password()?)
 }
 
+/// Persist `secret` as `name` among the secrets of `scope`, like a project, in `namespace`.
+/// Unlike secrets persisted with [`persist()`], the names of scoped secrets can be [listed](list_scoped()).
+///
+/// An empty `secret` deletes it, just like [`delete_scoped()`].
+pub fn persist_scoped(
+    scope: &str,
+    name: &str,
+    secret: &Sensitive<String>,
+    namespace: Namespace,
+) -> Result<()> {
+    if secret.0.is_empty() {
+        return delete_scoped(scope, name, namespace);
+    }
+    if name.is_empty() || name.contains('\n') {
+        bail!("secret names must not be empty or contain newlines: {name:?}");
+    }
+    persist(&scoped_handle(scope, name), secret, namespace)?;
+    let mut names = list_scoped(scope, namespace)?;
+    if !names.iter().any(|existing| existing == name) {
+        names.push(name.to_owned());
+        write_scope_index(scope, &names, namespace)?;
+    }
+    Ok(())
+}
+
+/// Obtain the secret known as `name` among the secrets of `scope` from `namespace`.
+pub fn retrieve_scoped(
+    scope: &str,
+    name: &str,
+    namespace: Namespace,
+) -> Result<Option<Sensitive<String>>> {
+    retrieve(&scoped_handle(scope, name), namespace)
+}
+
+/// List the names of all secrets of `scope` in `namespace`, in the order they were first persisted.
+/// The secrets themselves are never revealed.
+pub fn list_scoped(scope: &str, namespace: Namespace) -> Result<Vec<String>> {
+    Ok(retrieve(&scope_index_handle(scope), namespace)?
+        .map(|index| index.0.lines().map(ToOwned::to_owned).collect())
+        .unwrap_or_default())
+}
+
+/// Delete the secret known as `name` among the secrets of `scope` permanently from `namespace`.
+/// It's not an error if there is no such secret.
+pub fn delete_scoped(scope: &str, name: &str, namespace: Namespace) -> Result<()> {
+    match entry_for(&scoped_handle(scope, name), namespace)?.delete_password() {
+        Ok(()) | Err(keyring::Error::NoEntry) => {}
+        Err(err) => return Err(err.into()),
+    }
+    let mut names = list_scoped(scope, namespace)?;
+    let previous_len = names.len();
+    names.retain(|existing| existing != name);
+    if names.len() != previous_len {
+        write_scope_index(scope, &names, namespace)?;
+    }
+    Ok(())
+}
+
+/// The scope of all secrets that belong to the project with `project_id`.
+pub fn project_scope(project_id: impl std::fmt::Display) -> String {
+    format!("project-{project_id}")
+}
+
+fn scoped_handle(scope: &str, name: &str) -> String {
+    format!("{scope}-secret-{name}")
+}
+
+/// The handle of the secret holding the names of all secrets of `scope`, one per line,
+/// as secret stores generally can't be enumerated.
+fn scope_index_handle(scope: &str) -> String {
+    format!("{scope}-secrets")
+}
+
+fn write_scope_index(scope: &str, names: &[String], namespace: Namespace) -> Result<()> {
+    persist(
+        &scope_index_handle(scope),
+        &Sensitive(names.join("\n")),
+        namespace,
+    )
+}
+
 /// Use this `identifier` as 'namespace' for identifying secrets.
 /// Each namespace has its own set of secrets, useful for different application versions.
 ///
@@ -5,3 +5,11 @@ fn sensitive_does_not_debug_print_itself() {
     let s = Sensitive("password");
     assert_eq!(format!("{s:?}"), "\"<redacted>\"");
 }
+
+#[test]
+fn credential_sources_and_tokens_are_managed_secrets() {
+    use gitbutler_secret::credential_source::is_managed_secret;
+    assert!(is_managed_secret("credential-source-origin"));
+    assert!(is_managed_secret("token-origin"));
+    assert!(!is_managed_secret("api-key"));
+}
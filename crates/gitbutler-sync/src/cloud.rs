@@ -1,3 +1,4 @@
+use std::cell::Cell;
 use std::sync::atomic::AtomicUsize;
 use std::sync::Arc;
 use std::time;
@@ -16,11 +17,69 @@ use gitbutler_url::Url;
 use gitbutler_user as users;
 use itertools::Itertools;
 
+/// The progress of all pushes to GitButler's servers during a sync, accumulated.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub struct SyncProgress {
+    /// The amount of objects that were counted to be sent so far
+    pub objects_counted: usize,
+    /// The amount of objects that were sent so far
+    pub objects_sent: usize,
+    /// The amount of bytes that were sent so far
+    pub bytes_transferred: usize,
+    /// `true` once the sync is complete
+    pub done: bool,
+}
+
+/// Reports the accumulated [`SyncProgress`] of all pushes of a sync.
+struct ProgressReporter<'a> {
+    callback: &'a dyn Fn(SyncProgress),
+    progress: Cell<SyncProgress>,
+    /// The progress when the current push started, which the progress of the push is added to
+    push_start: Cell<SyncProgress>,
+}
+
+impl<'a> ProgressReporter<'a> {
+    fn new(callback: &'a dyn Fn(SyncProgress)) -> Self {
+        ProgressReporter {
+            callback,
+            progress: Default::default(),
+            push_start: Default::default(),
+        }
+    }
+
+    fn start_push(&self) {
+        self.push_start.set(self.progress.get());
+    }
+
+    /// Change the progress with `update`, which also receives the progress at the start of the current push,
+    /// and report it.
+    fn report(&self, update: impl FnOnce(&mut SyncProgress, &SyncProgress)) {
+        let mut progress = self.progress.get();
+        update(&mut progress, &self.push_start.get());
+        self.progress.set(progress);
+        (self.callback)(progress);
+    }
+}
+
 pub async fn sync_with_gitbutler(
     project_repository: &ProjectRepository,
     user: &users::User,
     projects: &projects::Controller,
 ) -> Result<()> {
+    sync_with_gitbutler_with_progress(project_repository, user, projects, |_| {}).await
+}
+
+/// Like [`sync_with_gitbutler`], but calls `progress` with the objects and bytes pushed so far as the pushes
+/// advance. It's called at least once when the sync starts, and once more with [`SyncProgress::done`] when it's complete.
+pub async fn sync_with_gitbutler_with_progress(
+    project_repository: &ProjectRepository,
+    user: &users::User,
+    projects: &projects::Controller,
+    progress: impl Fn(SyncProgress),
+) -> Result<()> {
+    let progress = ProgressReporter::new(&progress);
+    progress.report(|_, _| {});
+
     let project = project_repository.project();
     let vb_state = VirtualBranchesHandle::new(project.gb_dir());
     let default_target = vb_state.get_default_target()?;
@@ -39,11 +98,12 @@ pub async fn sync_with_gitbutler(
         project.id,
         user,
         12,
+        &progress,
     )
     .await?;
 
     // Push all refs
-    push_all_refs(project_repository, user, project.id)?;
+    push_all_refs(project_repository, user, project.id, &progress)?;
 
     // Push Oplog head
     let oplog_refspec = project_repository
@@ -52,10 +112,12 @@ pub async fn sync_with_gitbutler(
         .map(|sha| format!("+{}:refs/gitbutler/oplog/oplog", sha));
 
     if let Some(oplog_refspec) = oplog_refspec {
-        let x = push_to_gitbutler_server(project_repository, Some(user), &[&oplog_refspec]);
+        let x =
+            push_to_gitbutler_server(project_repository, Some(user), &[&oplog_refspec], &progress);
         println!("\n\n\nHERE: {:?}", x?);
     }
 
+    progress.report(|progress, _| progress.done = true);
     Ok(())
 }
 
@@ -67,6 +129,7 @@ async fn push_target(
     project_id: Id<Project>,
     user: &users::User,
     batch_size: usize,
+    progress: &ProgressReporter<'_>,
 ) -> Result<()> {
     let ids = batch_rev_walk(
         project_repository.repo(),
@@ -85,7 +148,7 @@ async fn push_target(
     for (idx, id) in ids.iter().enumerate().rev() {
         let refspec = format!("+{}:refs/push-tmp/{}", id, project_id);
 
-        push_to_gitbutler_server(project_repository, Some(user), &[&refspec])?;
+        push_to_gitbutler_server(project_repository, Some(user), &[&refspec], progress)?;
         update_project(projects, project_id, *id).await?;
 
         tracing::info!(
@@ -100,6 +163,7 @@ async fn push_target(
         project_repository,
         Some(user),
         &[&format!("+{}:refs/{}", default_target.sha, project_id)],
+        progress,
     )?;
 
     //TODO: remove push-tmp ref
@@ -154,6 +218,7 @@ fn push_all_refs(
     project_repository: &ProjectRepository,
     user: &users::User,
     project_id: Id<projects::Project>,
+    progress: &ProgressReporter<'_>,
 ) -> Result<()> {
     let gb_references = collect_refs(project_repository)?;
     let all_refs: Vec<_> = gb_references
@@ -169,7 +234,8 @@ fn push_all_refs(
 
     let all_refs: Vec<_> = all_refs.iter().map(String::as_str).collect();
 
-    let anything_pushed = push_to_gitbutler_server(project_repository, Some(user), &all_refs)?;
+    let anything_pushed =
+        push_to_gitbutler_server(project_repository, Some(user), &all_refs, progress)?;
     if anything_pushed {
         tracing::info!(
             %project_id,
@@ -201,6 +267,7 @@ fn push_to_gitbutler_server(
     project_repo: &ProjectRepository,
     user: Option<&users::User>,
     ref_specs: &[&str],
+    progress: &ProgressReporter<'_>,
 ) -> Result<bool> {
     let project = project_repo.project();
     let url = project
@@ -233,11 +300,23 @@ fn push_to_gitbutler_server(
     {
         let byte_counter = Arc::<AtomicUsize>::clone(&bytes_pushed);
         let total_counter = Arc::<AtomicUsize>::clone(&total_objects);
-        callbacks.push_transfer_progress(move |_current, total, bytes| {
+        callbacks.push_transfer_progress(move |current, total, bytes| {
             byte_counter.store(bytes, std::sync::atomic::Ordering::Relaxed);
             total_counter.store(total, std::sync::atomic::Ordering::Relaxed);
+            progress.report(|progress, push_start| {
+                progress.objects_sent = push_start.objects_sent + current;
+                progress.bytes_transferred = push_start.bytes_transferred + bytes;
+            });
         });
     }
+    callbacks.pack_progress(|stage, current, _total| {
+        if matches!(stage, git2::PackBuilderStage::AddingObjects) {
+            progress.report(|progress, push_start| {
+                progress.objects_counted = push_start.objects_counted + current;
+            });
+        }
+    });
+    progress.start_push();
 
     let mut push_options = git2::PushOptions::new();
     push_options.remote_callbacks(callbacks);
@@ -250,7 +329,7 @@ fn push_to_gitbutler_server(
     remote
         .push(ref_specs, Some(&mut push_options))
         .map_err(|err| match err.class() {
-            git2::ErrorClass::Net => anyhow!("network failed"),
+            git2::ErrorClass::Net => anyhow::Error::from(err).context("network failed"),
             _ => match err.code() {
                 git2::ErrorCode::Auth => anyhow!("authentication failed")
                     .context(Code::ProjectGitAuth)
@@ -1 +1,3 @@
 pub mod cloud;
+pub mod retry;
+pub mod scheduler;
@@ -0,0 +1,107 @@
+use std::future::Future;
+use std::time::Duration;
+
+use anyhow::Result;
+use gitbutler_project::ProjectId;
+
+use crate::scheduler::SyncScheduler;
+
+/// How often and how quickly to retry an operation that failed due to a [transient](is_transient) error.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct RetryPolicy {
+    /// The amount of retries after the first attempt.
+    pub max_retries: usize,
+    /// The delay before the first retry, which doubles with every retry after it.
+    pub base_delay: Duration,
+}
+
+impl Default for RetryPolicy {
+    fn default() -> Self {
+        RetryPolicy {
+            max_retries: 3,
+            base_delay: Duration::from_millis(500),
+        }
+    }
+}
+
+/// Return `true` if `err` is likely to go away by itself, like a reset connection, a timeout or
+/// a failed DNS lookup. Authentication failures and rejections by the server are never transient.
+pub fn is_transient(err: &anyhow::Error) -> bool {
+    if let Some(err) = err.downcast_ref::<git2::Error>() {
+        return err.code() != git2::ErrorCode::Auth && err.class() == git2::ErrorClass::Net;
+    }
+    err.chain().any(|cause| {
+        cause.downcast_ref::<std::io::Error>().map_or(false, |err| {
+            matches!(
+                err.kind(),
+                std::io::ErrorKind::ConnectionReset
+                    | std::io::ErrorKind::ConnectionAborted
+                    | std::io::ErrorKind::ConnectionRefused
+                    | std::io::ErrorKind::TimedOut
+                    | std::io::ErrorKind::NotConnected
+            )
+        })
+    })
+}
+
+/// Call `attempt` until it succeeds, retrying according to `policy` as long as it fails with
+/// [transient](is_transient) errors. Any other error, or the last one, is returned right away.
+pub async fn retry_transient<T, Fut>(policy: RetryPolicy, attempt: impl FnMut() -> Fut) -> Result<T>
+where
+    Fut: Future<Output = Result<T>>,
+{
+    retry(policy, None, attempt).await
+}
+
+/// Like [`retry_transient()`], but additionally wait for a turn of `scheduler` before each retry, which
+/// staggers the retries of all projects sharing it.
+///
+/// If the last sync of `project_id` failed, even after all retries, the first attempt waits for a turn as well,
+/// so projects that keep failing don't hit the network all at once on their next sync.
+pub async fn retry_transient_staggered<T, Fut>(
+    policy: RetryPolicy,
+    scheduler: &SyncScheduler,
+    project_id: ProjectId,
+    attempt: impl FnMut() -> Fut,
+) -> Result<T>
+where
+    Fut: Future<Output = Result<T>>,
+{
+    if scheduler.is_failing(project_id) {
+        scheduler.wait_for_turn().await;
+    }
+    let result = retry(policy, Some(scheduler), attempt).await;
+    scheduler.record_outcome(project_id, result.is_err());
+    result
+}
+
+async fn retry<T, Fut>(
+    policy: RetryPolicy,
+    scheduler: Option<&SyncScheduler>,
+    mut attempt: impl FnMut() -> Fut,
+) -> Result<T>
+where
+    Fut: Future<Output = Result<T>>,
+{
+    let mut delay = policy.base_delay;
+    let mut retries = 0;
+    loop {
+        match attempt().await {
+            Err(err) if retries < policy.max_retries && is_transient(&err) => {
+                tracing::warn!(
+                    ?err,
+                    retry = retries + 1,
+                    ?delay,
+                    "retrying after transient error"
+                );
+                tokio::time::sleep(delay).await;
+                if let Some(scheduler) = scheduler {
+                    scheduler.wait_for_turn().await;
+                }
+                delay *= 2;
+                retries += 1;
+            }
+            result => return result,
+        }
+    }
+}
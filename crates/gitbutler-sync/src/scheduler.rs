@@ -0,0 +1,99 @@
+use std::collections::hash_map::RandomState;
+use std::collections::HashSet;
+use std::hash::{BuildHasher, Hasher};
+use std::sync::{Arc, Mutex, MutexGuard};
+use std::time::Duration;
+
+use gitbutler_project::ProjectId;
+use tokio::time::Instant;
+
+/// The least amount of time between the turns of two sync attempts by default.
+pub const DEFAULT_SYNC_SPACING: Duration = Duration::from_secs(2);
+/// The most a turn is delayed by chance by default, so attempts don't line up again.
+pub const DEFAULT_SYNC_JITTER: Duration = Duration::from_secs(1);
+
+/// Staggers sync attempts across projects, so they don't all hit the network at once when it
+/// comes back after an outage.
+///
+/// It's cheap to clone, and all clones share the same turns, which is why one instance should be shared
+/// among the syncs of all projects.
+#[derive(Clone)]
+pub struct SyncScheduler {
+    state: Arc<Mutex<State>>,
+}
+
+struct State {
+    spacing: Duration,
+    max_jitter: Duration,
+    /// The earliest time the next turn may start at, if a turn was given out already.
+    next_turn: Option<Instant>,
+    /// The projects whose last sync failed.
+    failing: HashSet<ProjectId>,
+}
+
+impl Default for SyncScheduler {
+    fn default() -> Self {
+        Self::new(DEFAULT_SYNC_SPACING, DEFAULT_SYNC_JITTER)
+    }
+}
+
+impl SyncScheduler {
+    /// Space turns at least `spacing` apart, and delay each by a random amount of up to `max_jitter`.
+    pub fn new(spacing: Duration, max_jitter: Duration) -> Self {
+        SyncScheduler {
+            state: Arc::new(Mutex::new(State {
+                spacing,
+                max_jitter,
+                next_turn: None,
+                failing: HashSet::new(),
+            })),
+        }
+    }
+
+    /// Change the least amount of time between two turns to `spacing`, affecting turns given out from now on.
+    pub fn set_spacing(&self, spacing: Duration) {
+        self.state().spacing = spacing;
+    }
+
+    /// Remember whether the last sync of `project_id` `failed`, as each sync after a failure waits for its turn.
+    pub fn record_outcome(&self, project_id: ProjectId, failed: bool) {
+        let mut state = self.state();
+        if failed {
+            state.failing.insert(project_id);
+        } else {
+            state.failing.remove(&project_id);
+        }
+    }
+
+    /// Return `true` if the last sync of `project_id` failed.
+    pub fn is_failing(&self, project_id: ProjectId) -> bool {
+        self.state().failing.contains(&project_id)
+    }
+
+    /// Wait until it's the caller's turn to sync, which is at least the configured spacing after
+    /// the turn before it.
+    pub async fn wait_for_turn(&self) {
+        let turn = {
+            let mut state = self.state();
+            let now = Instant::now();
+            let turn = state.next_turn.map_or(now, |next_turn| next_turn.max(now))
+                + jitter(state.max_jitter);
+            state.next_turn = Some(turn + state.spacing);
+            turn
+        };
+        tokio::time::sleep_until(turn).await;
+    }
+
+    fn state(&self) -> MutexGuard<'_, State> {
+        self.state
+            .lock()
+            .unwrap_or_else(|poisoned| poisoned.into_inner())
+    }
+}
+
+/// Return a random duration of up to `max`.
+fn jitter(max: Duration) -> Duration {
+    // Hashers of a new `RandomState` are randomly seeded, which is random enough for spreading out turns.
+    let random = RandomState::new().build_hasher().finish();
+    max.mul_f64(random as f64 / u64::MAX as f64)
+}
@@ -0,0 +1,64 @@
+use std::cell::Cell;
+use std::time::Duration;
+
+use gitbutler_sync::retry::{is_transient, retry_transient, RetryPolicy};
+
+const POLICY: RetryPolicy = RetryPolicy {
+    max_retries: 3,
+    base_delay: Duration::from_millis(1),
+};
+
+/// A stand-in for a push that fails with `code` for the first `failures` attempts.
+fn fake_push(attempts: &Cell<usize>, failures: usize, code: git2::ErrorCode) -> anyhow::Result<()> {
+    attempts.set(attempts.get() + 1);
+    if attempts.get() <= failures {
+        let err = git2::Error::new(code, git2::ErrorClass::Net, "connection reset by peer");
+        return Err(anyhow::Error::from(err).context("network failed"));
+    }
+    Ok(())
+}
+
+#[tokio::test]
+async fn transient_errors_are_retried() {
+    let attempts = Cell::new(0);
+    retry_transient(POLICY, || async {
+        fake_push(&attempts, 2, git2::ErrorCode::GenericError)
+    })
+    .await
+    .unwrap();
+    assert_eq!(attempts.get(), 3);
+}
+
+#[tokio::test]
+async fn retries_are_limited() {
+    let attempts = Cell::new(0);
+    let err = retry_transient(POLICY, || async {
+        fake_push(&attempts, usize::MAX, git2::ErrorCode::GenericError)
+    })
+    .await
+    .unwrap_err();
+    assert!(is_transient(&err));
+    assert_eq!(attempts.get(), 1 + POLICY.max_retries);
+}
+
+#[tokio::test]
+async fn auth_failures_fail_fast() {
+    let attempts = Cell::new(0);
+    let err = retry_transient(POLICY, || async {
+        fake_push(&attempts, 2, git2::ErrorCode::Auth)
+    })
+    .await
+    .unwrap_err();
+    assert!(!is_transient(&err));
+    assert_eq!(attempts.get(), 1);
+}
+
+#[test]
+fn io_errors_are_classified_by_kind() {
+    let timeout = anyhow::Error::from(std::io::Error::from(std::io::ErrorKind::TimedOut))
+        .context("request failed");
+    assert!(is_transient(&timeout));
+
+    let denied = anyhow::Error::from(std::io::Error::from(std::io::ErrorKind::PermissionDenied));
+    assert!(!is_transient(&denied));
+}
@@ -0,0 +1,109 @@
+use std::cell::RefCell;
+use std::time::Duration;
+
+use gitbutler_project::ProjectId;
+use gitbutler_sync::retry::{retry_transient_staggered, RetryPolicy};
+use gitbutler_sync::scheduler::SyncScheduler;
+use tokio::time::Instant;
+
+const SPACING: Duration = Duration::from_millis(100);
+
+/// Sync a project whose pushes fail with a network error until `recovery`, and record when it succeeded in `synced_at`.
+async fn sync_project(
+    scheduler: &SyncScheduler,
+    project_id: ProjectId,
+    recovery: Instant,
+    synced_at: &RefCell<Vec<Instant>>,
+) -> anyhow::Result<()> {
+    let policy = RetryPolicy {
+        max_retries: 3,
+        base_delay: Duration::from_millis(10),
+    };
+    retry_transient_staggered(policy, scheduler, project_id, || async {
+        if Instant::now() < recovery {
+            let err = git2::Error::new(
+                git2::ErrorCode::GenericError,
+                git2::ErrorClass::Net,
+                "network is unreachable",
+            );
+            return Err(anyhow::Error::from(err).context("network failed"));
+        }
+        synced_at.borrow_mut().push(Instant::now());
+        Ok(())
+    })
+    .await
+}
+
+#[tokio::test(start_paused = true)]
+async fn retries_after_an_outage_are_staggered() {
+    let scheduler = SyncScheduler::new(SPACING, Duration::from_millis(20));
+    let recovery = Instant::now() + Duration::from_millis(5);
+    let synced_at = RefCell::new(Vec::new());
+
+    let results = tokio::join!(
+        sync_project(&scheduler, ProjectId::generate(), recovery, &synced_at),
+        sync_project(&scheduler, ProjectId::generate(), recovery, &synced_at),
+        sync_project(&scheduler, ProjectId::generate(), recovery, &synced_at),
+        sync_project(&scheduler, ProjectId::generate(), recovery, &synced_at),
+    );
+    results.0.unwrap();
+    results.1.unwrap();
+    results.2.unwrap();
+    results.3.unwrap();
+
+    let mut synced_at = synced_at.into_inner();
+    synced_at.sort();
+    assert_eq!(synced_at.len(), 4);
+    for pair in synced_at.windows(2) {
+        assert!(
+            pair[1] - pair[0] >= SPACING,
+            "syncs are at least {SPACING:?} apart, but two were {:?} apart",
+            pair[1] - pair[0]
+        );
+    }
+}
+
+#[tokio::test(start_paused = true)]
+async fn the_first_turn_is_immediate() {
+    let scheduler = SyncScheduler::new(SPACING, Duration::ZERO);
+    let start = Instant::now();
+    scheduler.wait_for_turn().await;
+    assert!(Instant::now() - start < SPACING);
+    scheduler.wait_for_turn().await;
+    assert!(Instant::now() - start >= SPACING);
+}
+
+#[tokio::test(start_paused = true)]
+async fn syncs_after_exhausted_retries_wait_for_their_turn() {
+    let scheduler = SyncScheduler::new(SPACING, Duration::ZERO);
+    let project_id = ProjectId::generate();
+    let other_project_id = ProjectId::generate();
+    let recovery = Instant::now() + Duration::from_secs(60);
+    let synced_at = RefCell::new(Vec::new());
+
+    sync_project(&scheduler, project_id, recovery, &synced_at)
+        .await
+        .unwrap_err();
+    assert!(scheduler.is_failing(project_id));
+    assert!(!scheduler.is_failing(other_project_id));
+
+    tokio::time::sleep_until(recovery).await;
+    // Another project takes the next turn, so the failing one has to wait until the one after it.
+    scheduler.wait_for_turn().await;
+    sync_project(&scheduler, project_id, recovery, &synced_at)
+        .await
+        .unwrap();
+    assert_eq!(
+        *synced_at.borrow(),
+        [recovery + SPACING],
+        "the first attempt after the failure waited for its turn"
+    );
+    assert!(!scheduler.is_failing(project_id), "the project recovered");
+
+    // Healthy projects sync right away again.
+    let start = Instant::now();
+    sync_project(&scheduler, project_id, recovery, &synced_at)
+        .await
+        .unwrap();
+    assert_eq!(synced_at.borrow()[1], start);
+}
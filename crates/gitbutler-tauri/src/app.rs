@@ -5,7 +5,7 @@ use gitbutler_command_context::ProjectRepository;
 use gitbutler_project as projects;
 use gitbutler_project::ProjectId;
 use gitbutler_reference::RemoteRefname;
-use gitbutler_repo::{credentials, RepoActionsExt, RepositoryExt};
+use gitbutler_repo::{credentials, RemoteConnectionStatus, RepoActionsExt, RepositoryExt};
 use std::path::PathBuf;
 
 #[derive(Clone)]
@@ -61,7 +61,19 @@ impl App {
     ) -> Result<()> {
         let project = self.projects().get(project_id)?;
         let project_repository = ProjectRepository::open(&project)?;
-        project_repository.fetch(remote_name, credentials, askpass)
+        project_repository.fetch(remote_name, credentials, askpass, None)
+    }
+
+    pub fn test_remote_connection(
+        &self,
+        project_id: ProjectId,
+        remote_name: &str,
+        credentials: &credentials::Helper,
+        askpass: Option<String>,
+    ) -> Result<RemoteConnectionStatus> {
+        let project = self.projects().get(project_id)?;
+        let project_repository = ProjectRepository::open(&project)?;
+        project_repository.test_remote_connection(remote_name, credentials, askpass)
     }
 
     pub fn git_index_size(&self, project_id: ProjectId) -> Result<usize> {
@@ -1,4 +1,6 @@
 pub mod commands {
+    use std::time::Duration;
+
     use gitbutler_id::id::Id;
     use gitbutler_repo::askpass::{self, AskpassRequest};
 
@@ -11,4 +13,14 @@ pub mod commands {
         askpass::get_broker().handle_response(id, response).await;
         Ok(())
     }
+
+    /// Remember responses to prompts in memory for `ttl_ms` milliseconds, or not at all if it's `0`.
+    #[tauri::command(async)]
+    #[tracing::instrument]
+    pub async fn set_askpass_cache_ttl(ttl_ms: u64) -> Result<(), ()> {
+        askpass::get_broker()
+            .set_cache_ttl(Duration::from_millis(ttl_ms))
+            .await;
+        Ok(())
+    }
 }
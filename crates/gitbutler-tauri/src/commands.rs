@@ -1,8 +1,17 @@
+use std::path::PathBuf;
+use std::time::UNIX_EPOCH;
+
 use crate::error::Error;
 use crate::App;
+use gitbutler_feedback::Archival;
 use gitbutler_project::ProjectId;
 use gitbutler_reference::RemoteRefname;
-use gitbutler_repo::credentials;
+use gitbutler_repo::{credentials, RemoteConnectionStatus};
+use gitbutler_url::ForgeUrls;
+use gitbutler_watcher::{
+    ActiveTimer, EventKind, RecalculationLimit, RecentEvents, RecordedEvent, SyncScheduler,
+    WatcherTimers,
+};
 use tauri::State;
 use tracing::instrument;
 
@@ -51,6 +60,17 @@ pub async fn git_test_fetch(
     )?)
 }
 
+#[tauri::command(async)]
+#[instrument(skip(app, helper), err(Debug))]
+pub async fn test_remote_connection(
+    app: State<'_, App>,
+    helper: State<'_, credentials::Helper>,
+    project_id: ProjectId,
+    remote_name: &str,
+) -> Result<RemoteConnectionStatus, Error> {
+    Ok(app.test_remote_connection(project_id, remote_name, &helper, Some("test".to_string()))?)
+}
+
 #[tauri::command(async)]
 #[instrument(skip(app), err(Debug))]
 pub async fn git_index_size(app: State<'_, App>, project_id: ProjectId) -> Result<usize, Error> {
@@ -98,3 +118,76 @@ pub async fn git_remove_global_config(key: &str) -> Result<(), Error> {
 pub async fn git_get_global_config(key: &str) -> Result<Option<String>, Error> {
     Ok(App::git_get_global_config(key)?)
 }
+
+#[tauri::command(async)]
+#[instrument(err(Debug))]
+pub async fn derive_forge_urls(remote_url: &str) -> Result<ForgeUrls, Error> {
+    Ok(gitbutler_url::derive_forge_urls(remote_url))
+}
+
+/// Set how many projects may recalculate their virtual branches at the same time.
+#[tauri::command(async)]
+#[instrument(skip(limit), err(Debug))]
+pub async fn set_recalculation_concurrency(
+    limit: State<'_, RecalculationLimit>,
+    concurrency: usize,
+) -> Result<(), Error> {
+    limit.set_concurrency(concurrency);
+    Ok(())
+}
+
+/// Set the least amount of milliseconds between retries of failed syncs across all projects.
+#[tauri::command(async)]
+#[instrument(skip(scheduler), err(Debug))]
+pub async fn set_sync_retry_spacing(
+    scheduler: State<'_, SyncScheduler>,
+    spacing_ms: u64,
+) -> Result<(), Error> {
+    scheduler.set_spacing(std::time::Duration::from_millis(spacing_ms));
+    Ok(())
+}
+
+/// List the events the watcher of `project_id` received most recently, oldest first, optionally only those of `kind`.
+#[tauri::command(async)]
+#[instrument(skip(recent_events), err(Debug))]
+pub async fn recent_internal_events(
+    recent_events: State<'_, RecentEvents>,
+    project_id: ProjectId,
+    kind: Option<EventKind>,
+) -> Result<Vec<RecordedEvent>, Error> {
+    Ok(recent_events.list(project_id, kind))
+}
+
+/// List the timers of the watcher of `project_id` that didn't elapse yet, like the window in which changes
+/// to project files are collected, along with the time left until they do.
+#[tauri::command(async)]
+#[instrument(skip(timers), err(Debug))]
+pub async fn watcher_timers(
+    timers: State<'_, WatcherTimers>,
+    project_id: ProjectId,
+) -> Result<Vec<ActiveTimer>, Error> {
+    Ok(timers.list(project_id))
+}
+
+/// Write a regression case of `project_id` into a new directory of the cache, consisting of its redacted
+/// virtual branch state and the events its watcher received most recently, and return the directory.
+#[tauri::command(async)]
+#[instrument(skip(projects, recent_events, archival), err(Debug))]
+pub async fn generate_regression_case(
+    projects: State<'_, gitbutler_project::Controller>,
+    recent_events: State<'_, RecentEvents>,
+    archival: State<'_, Archival>,
+    project_id: ProjectId,
+) -> Result<PathBuf, Error> {
+    let project = projects.get(project_id)?;
+    let events = recent_events.list(project_id, None);
+    let created_at = UNIX_EPOCH
+        .elapsed()
+        .map(|elapsed| elapsed.as_millis())
+        .unwrap_or_default();
+    let dir = archival
+        .cache_dir
+        .join(format!("regression-case-{project_id}-{created_at}"));
+    gitbutler_watcher::write_regression_case(&project, &events, &dir)?;
+    Ok(dir)
+}
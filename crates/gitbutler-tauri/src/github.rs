@@ -1,3 +1,191 @@
+use std::collections::BTreeMap;
+use std::path::Path;
+use std::sync::Mutex;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+use anyhow::{bail, Context, Result};
+use reqwest::{header::HeaderMap, StatusCode};
+use serde::{Deserialize, Serialize};
+
+/// The longest time to wait for a rate limit to reset before giving up on a request.
+const MAX_RATE_LIMIT_DELAY: Duration = Duration::from_secs(60);
+/// How often to retry a request after it was rejected due to the rate limit.
+const MAX_RATE_LIMIT_RETRIES: usize = 2;
+
+static RATE_LIMIT: Mutex<RateLimitState> = Mutex::new(RateLimitState {
+    limit: None,
+    remaining: None,
+    reset_at: None,
+});
+
+/// The rate limit of the GitHub API, as of the last response that reported it.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct RateLimitState {
+    /// The amount of requests allowed per window.
+    pub limit: Option<u32>,
+    /// The amount of requests left in the current window.
+    pub remaining: Option<u32>,
+    /// When the current window ends, in seconds since the Unix epoch.
+    pub reset_at: Option<u64>,
+}
+
+impl RateLimitState {
+    /// Read the state from the `X-RateLimit-*` headers of a response, or return `None` if there are none.
+    fn from_headers(headers: &HeaderMap) -> Option<Self> {
+        let number = |name: &str| {
+            headers
+                .get(name)
+                .and_then(|value| value.to_str().ok())
+                .and_then(|value| value.trim().parse::<u64>().ok())
+        };
+        let state = RateLimitState {
+            limit: number("x-ratelimit-limit").and_then(|n| u32::try_from(n).ok()),
+            remaining: number("x-ratelimit-remaining").and_then(|n| u32::try_from(n).ok()),
+            reset_at: number("x-ratelimit-reset"),
+        };
+        (state != RateLimitState::default()).then_some(state)
+    }
+
+    /// How long to wait at `now` before the next request can succeed, if no requests are left.
+    fn delay(&self, now: SystemTime) -> Option<Duration> {
+        if self.remaining != Some(0) {
+            return None;
+        }
+        let now = now.duration_since(UNIX_EPOCH).ok()?.as_secs();
+        self.reset_at
+            .filter(|reset_at| *reset_at > now)
+            .map(|reset_at| Duration::from_secs(reset_at - now))
+    }
+}
+
+/// How long to wait before retrying a request that was rejected with `status` and `headers`,
+/// or `None` if it wasn't rejected due to a rate limit.
+fn retry_delay(status: StatusCode, headers: &HeaderMap, now: SystemTime) -> Option<Duration> {
+    if status != StatusCode::FORBIDDEN && status != StatusCode::TOO_MANY_REQUESTS {
+        return None;
+    }
+    // Secondary rate limits tell how long to wait explicitly.
+    if let Some(seconds) = headers
+        .get(reqwest::header::RETRY_AFTER)
+        .and_then(|value| value.to_str().ok())
+        .and_then(|value| value.trim().parse::<u64>().ok())
+    {
+        return Some(Duration::from_secs(seconds));
+    }
+    RateLimitState::from_headers(headers).and_then(|state| state.delay(now))
+}
+
+/// Return the rate limit of the GitHub API as of the last response.
+pub fn rate_limit() -> RateLimitState {
+    *RATE_LIMIT
+        .lock()
+        .unwrap_or_else(|poisoned| poisoned.into_inner())
+}
+
+/// Remember `state` as the rate limit of the GitHub API as of the last response, including those to
+/// requests the frontend sends itself.
+fn set_rate_limit(state: RateLimitState) {
+    *RATE_LIMIT
+        .lock()
+        .unwrap_or_else(|poisoned| poisoned.into_inner()) = state;
+}
+
+/// Send `request` to GitHub from the backend, delaying it while the rate limit is exhausted, and retrying it if it's
+/// rejected due to a primary or secondary rate limit, as long as the wait doesn't exceed
+/// [`MAX_RATE_LIMIT_DELAY`].
+pub async fn send(request: reqwest::RequestBuilder) -> Result<reqwest::Response> {
+    let mut retries = 0;
+    loop {
+        if let Some(delay) = rate_limit().delay(SystemTime::now()) {
+            if delay > MAX_RATE_LIMIT_DELAY {
+                bail!(
+                    "GitHub API rate limit exceeded, it resets in {} seconds",
+                    delay.as_secs()
+                );
+            }
+            tracing::warn!(
+                ?delay,
+                "GitHub API rate limit exhausted, waiting for it to reset"
+            );
+            tokio::time::sleep(delay).await;
+        }
+
+        let response = request
+            .try_clone()
+            .context("request can't be retried")?
+            .send()
+            .await
+            .context("Failed to send request")?;
+        if let Some(state) = RateLimitState::from_headers(response.headers()) {
+            set_rate_limit(state);
+        }
+
+        match retry_delay(response.status(), response.headers(), SystemTime::now()) {
+            Some(delay) if retries < MAX_RATE_LIMIT_RETRIES && delay <= MAX_RATE_LIMIT_DELAY => {
+                tracing::warn!(?delay, "GitHub API request was rate limited, retrying");
+                tokio::time::sleep(delay).await;
+                retries += 1;
+            }
+            _ => return Ok(response),
+        }
+    }
+}
+
+/// The directories GitHub looks for pull request templates in, in order of precedence.
+const PULL_REQUEST_TEMPLATE_DIRS: &[&str] = &[".github", "", "docs"];
+const PULL_REQUEST_TEMPLATE_NAME: &str = "pull_request_template";
+
+/// Return the pull request templates of the repository in `worktree_dir`, keyed by their file name.
+///
+/// Like GitHub, it looks for a single `pull_request_template.md` and for any amount of templates in a
+/// `PULL_REQUEST_TEMPLATE/` directory, in `.github/`, the root and `docs/`, ignoring case.
+/// The single template is always keyed in lowercase, and if there are templates with the same name,
+/// the one in the directory with the higher precedence wins.
+pub fn pull_request_templates(worktree_dir: &Path) -> Result<BTreeMap<String, String>> {
+    let mut templates = BTreeMap::new();
+    for dir in PULL_REQUEST_TEMPLATE_DIRS {
+        let dir = worktree_dir.join(dir);
+        for entry in read_dir_if_exists(&dir)? {
+            let path = entry.path();
+            let name = entry.file_name().to_string_lossy().into_owned();
+            let lowercase_name = name.to_lowercase();
+            if path.is_file()
+                && Path::new(&lowercase_name).file_stem()
+                    == Some(PULL_REQUEST_TEMPLATE_NAME.as_ref())
+            {
+                add_template(&mut templates, lowercase_name, &path)?;
+            } else if path.is_dir() && lowercase_name == PULL_REQUEST_TEMPLATE_NAME {
+                for entry in read_dir_if_exists(&path)? {
+                    let path = entry.path();
+                    if path.is_file() {
+                        let name = entry.file_name().to_string_lossy().into_owned();
+                        add_template(&mut templates, name, &path)?;
+                    }
+                }
+            }
+        }
+    }
+    Ok(templates)
+}
+
+fn read_dir_if_exists(dir: &Path) -> Result<Vec<std::fs::DirEntry>> {
+    match std::fs::read_dir(dir) {
+        Ok(entries) => Ok(entries.collect::<std::io::Result<_>>()?),
+        Err(err) if err.kind() == std::io::ErrorKind::NotFound => Ok(Vec::new()),
+        Err(err) => Err(err).with_context(|| format!("failed to read {}", dir.display())),
+    }
+}
+
+fn add_template(templates: &mut BTreeMap<String, String>, name: String, path: &Path) -> Result<()> {
+    if !templates.contains_key(&name) {
+        let template = std::fs::read_to_string(path)
+            .with_context(|| format!("failed to read pull request template {}", path.display()))?;
+        templates.insert(name, template);
+    }
+    Ok(())
+}
+
 pub mod commands {
     use std::collections::HashMap;
 
@@ -5,6 +193,13 @@ pub mod commands {
     use serde::{Deserialize, Serialize};
     use tracing::instrument;
 
+    use std::collections::BTreeMap;
+
+    use gitbutler_project as projects;
+    use gitbutler_project::ProjectId;
+    use tauri::State;
+
+    use super::RateLimitState;
     use crate::error::Error;
 
     const GITHUB_CLIENT_ID: &str = "cd51880daa675d9e6452";
@@ -29,13 +224,13 @@ pub mod commands {
         );
 
         let client = reqwest::Client::new();
-        let res = client
-            .post("https://github.com/login/device/code")
-            .headers(headers)
-            .json(&req_body)
-            .send()
-            .await
-            .context("Failed to send request")?;
+        let res = super::send(
+            client
+                .post("https://github.com/login/device/code")
+                .headers(headers)
+                .json(&req_body),
+        )
+        .await?;
 
         let rsp_body = res.text().await.context("Failed to get response body")?;
 
@@ -64,13 +259,13 @@ pub mod commands {
         );
 
         let client = reqwest::Client::new();
-        let res = client
-            .post("https://github.com/login/oauth/access_token")
-            .headers(headers)
-            .json(&req_body)
-            .send()
-            .await
-            .context("Failed to send request")?;
+        let res = super::send(
+            client
+                .post("https://github.com/login/oauth/access_token")
+                .headers(headers)
+                .json(&req_body),
+        )
+        .await?;
 
         let rsp_body = res.text().await.context("Failed to get response body")?;
 
@@ -79,4 +274,142 @@ pub mod commands {
             .context("Failed to parse response body")
             .map_err(Into::into)
     }
+
+    /// The rate limit of the GitHub API as of the last response, for display.
+    #[tauri::command(async)]
+    #[instrument]
+    pub async fn rate_limit_status() -> Result<RateLimitState, Error> {
+        Ok(super::rate_limit())
+    }
+
+    /// Record the rate limit reported by a response to a request the frontend sent to the GitHub API,
+    /// which it throttles itself.
+    #[tauri::command(async)]
+    #[instrument]
+    pub async fn report_rate_limit(state: RateLimitState) -> Result<(), Error> {
+        super::set_rate_limit(state);
+        Ok(())
+    }
+
+    /// The pull request templates of the project, keyed by name, to prefill the body of a new pull request.
+    #[tauri::command(async)]
+    #[instrument(skip(projects), err(Debug))]
+    pub async fn pull_request_templates(
+        projects: State<'_, projects::Controller>,
+        project_id: ProjectId,
+    ) -> Result<BTreeMap<String, String>, Error> {
+        let project = projects.get(project_id)?;
+        Ok(super::pull_request_templates(&project.path)?)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn headers(pairs: &[(&'static str, &str)]) -> HeaderMap {
+        pairs
+            .iter()
+            .map(|(name, value)| {
+                (
+                    reqwest::header::HeaderName::from_static(name),
+                    value.parse().unwrap(),
+                )
+            })
+            .collect()
+    }
+
+    fn at(secs: u64) -> SystemTime {
+        UNIX_EPOCH + Duration::from_secs(secs)
+    }
+
+    #[test]
+    fn state_is_read_from_headers() {
+        let state = RateLimitState::from_headers(&headers(&[
+            ("x-ratelimit-limit", "5000"),
+            ("x-ratelimit-remaining", "0"),
+            ("x-ratelimit-reset", "1000"),
+        ]))
+        .unwrap();
+        assert_eq!(
+            state,
+            RateLimitState {
+                limit: Some(5000),
+                remaining: Some(0),
+                reset_at: Some(1000),
+            }
+        );
+        assert_eq!(state.delay(at(970)), Some(Duration::from_secs(30)));
+        assert_eq!(state.delay(at(1000)), None, "the limit was reset already");
+
+        assert_eq!(RateLimitState::from_headers(&headers(&[])), None);
+    }
+
+    #[test]
+    fn no_delay_while_requests_are_left() {
+        let state = RateLimitState {
+            limit: Some(5000),
+            remaining: Some(1),
+            reset_at: Some(1000),
+        };
+        assert_eq!(state.delay(at(970)), None);
+    }
+
+    #[test]
+    fn pull_request_templates_are_found_in_all_locations() -> Result<()> {
+        let tmp = tempfile::tempdir()?;
+        let root = tmp.path();
+        assert!(pull_request_templates(root)?.is_empty());
+
+        std::fs::create_dir_all(root.join(".github/PULL_REQUEST_TEMPLATE"))?;
+        std::fs::create_dir_all(root.join("docs"))?;
+        std::fs::write(root.join(".github/PULL_REQUEST_TEMPLATE.md"), "github")?;
+        std::fs::write(root.join("pull_request_template.md"), "root")?;
+        std::fs::write(
+            root.join(".github/PULL_REQUEST_TEMPLATE/bugfix.md"),
+            "bugfix",
+        )?;
+        std::fs::write(root.join("docs/pull_request_template.md"), "docs")?;
+        std::fs::write(root.join("README.md"), "not a template")?;
+
+        let templates = pull_request_templates(root)?;
+        assert_eq!(
+            templates,
+            BTreeMap::from([
+                ("bugfix.md".to_owned(), "bugfix".to_owned()),
+                ("pull_request_template.md".to_owned(), "github".to_owned()),
+            ]),
+            "templates in .github win over the root, which wins over docs"
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn retry_after_is_honored_for_secondary_rate_limits() {
+        assert_eq!(
+            retry_delay(
+                StatusCode::FORBIDDEN,
+                &headers(&[("retry-after", "10")]),
+                at(0)
+            ),
+            Some(Duration::from_secs(10))
+        );
+        assert_eq!(
+            retry_delay(
+                StatusCode::TOO_MANY_REQUESTS,
+                &headers(&[("x-ratelimit-remaining", "0"), ("x-ratelimit-reset", "5")]),
+                at(0)
+            ),
+            Some(Duration::from_secs(5))
+        );
+        assert_eq!(
+            retry_delay(StatusCode::OK, &headers(&[("retry-after", "10")]), at(0)),
+            None
+        );
+        assert_eq!(
+            retry_delay(StatusCode::FORBIDDEN, &headers(&[]), at(0)),
+            None,
+            "forbidden for other reasons"
+        );
+    }
 }
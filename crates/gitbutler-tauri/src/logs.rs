@@ -1,9 +1,50 @@
+use std::collections::HashMap;
 use std::path::Path;
+use std::sync::{Arc, PoisonError, RwLock};
 use std::{fs, net::Ipv4Addr, time::Duration};
-use tauri::{AppHandle, Manager};
-use tracing::{instrument, metadata::LevelFilter, subscriber::set_global_default};
+
+use anyhow::anyhow;
+use gitbutler_error::error::Code;
+use gitbutler_project::ProjectId;
+use tauri::{AppHandle, Manager, State};
+use tracing::{
+    field::{Field, Visit},
+    instrument,
+    metadata::LevelFilter,
+    span,
+    subscriber::set_global_default,
+    Subscriber,
+};
 use tracing_appender::rolling::{RollingFileAppender, Rotation};
-use tracing_subscriber::{fmt::format::FmtSpan, layer::SubscriberExt, Layer};
+use tracing_subscriber::{
+    fmt::format::FmtSpan,
+    layer::{Context, Filter, SubscriberExt},
+    registry::LookupSpan,
+    Layer,
+};
+
+use crate::error::Error;
+
+/// The log levels of projects that override the default one, by project id.
+///
+/// They apply to everything logged within a span with a `project_id` field.
+#[derive(Clone, Default)]
+pub struct ProjectLogLevels(Arc<RwLock<HashMap<String, LevelFilter>>>);
+
+impl ProjectLogLevels {
+    /// Override the log level of the project with `project_id`, or go back to the default level without `level`.
+    fn set(&self, project_id: String, level: Option<LevelFilter>) {
+        {
+            let mut levels = self.0.write().unwrap_or_else(PoisonError::into_inner);
+            match level {
+                Some(level) => levels.insert(project_id, level),
+                None => levels.remove(&project_id),
+            };
+        }
+        // The most verbose level may have changed, which is cached along with the interest of all callsites.
+        tracing::callsite::rebuild_interest_cache();
+    }
+}
 
 pub fn init(app_handle: &AppHandle) {
     let logs_dir = app_handle
@@ -29,6 +70,8 @@ pub fn init(app_handle: &AppHandle) {
     prune_old_logs(&logs_dir, Some(log_prefix), Some(log_suffix), max_log_files).ok();
 
     app_handle.manage(guard); // keep the guard alive for the lifetime of the app
+    let project_log_levels = ProjectLogLevels::default();
+    app_handle.manage(project_log_levels.clone());
 
     let format_for_humans = tracing_subscriber::fmt::format()
         .with_file(true)
@@ -44,6 +87,7 @@ pub fn init(app_handle: &AppHandle) {
 
     let use_colors_in_logs = cfg!(not(feature = "windows"));
     let subscriber = tracing_subscriber::registry()
+        .with(ProjectIdLayer)
         .with(
             // subscriber for https://github.com/tokio-rs/console
             console_subscriber::ConsoleLayer::builder()
@@ -59,7 +103,10 @@ pub fn init(app_handle: &AppHandle) {
                 .event_format(format_for_humans.clone())
                 .with_ansi(use_colors_in_logs)
                 .with_span_events(FmtSpan::CLOSE)
-                .with_filter(log_level_filter),
+                .with_filter(ProjectLevelFilter {
+                    default_level: log_level_filter,
+                    levels: project_log_levels.clone(),
+                }),
         )
         .with(
             // subscriber that writes spans to a file
@@ -68,12 +115,110 @@ pub fn init(app_handle: &AppHandle) {
                 .with_ansi(false)
                 .with_span_events(FmtSpan::NEW | FmtSpan::CLOSE)
                 .with_writer(file_writer)
-                .with_filter(log_level_filter),
+                .with_filter(ProjectLevelFilter {
+                    default_level: log_level_filter,
+                    levels: project_log_levels,
+                }),
         );
 
     set_global_default(subscriber).expect("failed to set subscriber");
 }
 
+/// Override the log level of the project with `project_id`, or go back to the default level without `level`.
+#[tauri::command(async)]
+#[instrument(skip(levels), err(Debug))]
+pub async fn set_project_log_level(
+    levels: State<'_, ProjectLogLevels>,
+    project_id: ProjectId,
+    level: Option<String>,
+) -> Result<(), Error> {
+    let level = level
+        .map(|level| {
+            level.to_lowercase().parse::<LevelFilter>().map_err(|_| {
+                anyhow!("'{level}' isn't a log level, like 'info' or 'debug'")
+                    .context(Code::Validation)
+            })
+        })
+        .transpose()?;
+    levels.set(project_id.to_string(), level);
+    Ok(())
+}
+
+/// Filter by the log level of the project the span or event belongs to, or by `default_level` if it doesn't
+/// belong to a project with an overridden level.
+struct ProjectLevelFilter {
+    default_level: LevelFilter,
+    levels: ProjectLogLevels,
+}
+
+impl ProjectLevelFilter {
+    /// The most verbose of the default level and all overrides.
+    fn max_level(&self) -> LevelFilter {
+        let levels = self.levels.0.read().unwrap_or_else(PoisonError::into_inner);
+        levels.values().copied().fold(self.default_level, Ord::max)
+    }
+}
+
+impl<S> Filter<S> for ProjectLevelFilter
+where
+    S: Subscriber + for<'a> LookupSpan<'a>,
+{
+    fn enabled(&self, metadata: &tracing::Metadata<'_>, cx: &Context<'_, S>) -> bool {
+        if metadata.is_span() {
+            // Spans are needed to find the project of the events within them, so they use the most verbose level.
+            return self.max_level() >= *metadata.level();
+        }
+        let levels = self.levels.0.read().unwrap_or_else(PoisonError::into_inner);
+        let project_level = cx.lookup_current().and_then(|span| {
+            span.scope().find_map(|span| {
+                let extensions = span.extensions();
+                let ProjectIdField(project_id) = extensions.get::<ProjectIdField>()?;
+                levels.get(project_id).copied()
+            })
+        });
+        project_level.unwrap_or(self.default_level) >= *metadata.level()
+    }
+
+    fn max_level_hint(&self) -> Option<LevelFilter> {
+        Some(self.max_level())
+    }
+}
+
+/// The `project_id` field of a span, kept in its extensions so filters can find it.
+struct ProjectIdField(String);
+
+/// Remember the `project_id` field of new spans as [`ProjectIdField`].
+struct ProjectIdLayer;
+
+impl<S> Layer<S> for ProjectIdLayer
+where
+    S: Subscriber + for<'a> LookupSpan<'a>,
+{
+    fn on_new_span(&self, attrs: &span::Attributes<'_>, id: &span::Id, ctx: Context<'_, S>) {
+        let mut visitor = ProjectIdVisitor(None);
+        attrs.record(&mut visitor);
+        if let (Some(project_id), Some(span)) = (visitor.0, ctx.span(id)) {
+            span.extensions_mut().insert(ProjectIdField(project_id));
+        }
+    }
+}
+
+struct ProjectIdVisitor(Option<String>);
+
+impl Visit for ProjectIdVisitor {
+    fn record_str(&mut self, field: &Field, value: &str) {
+        if field.name() == "project_id" {
+            self.0 = Some(value.to_owned());
+        }
+    }
+
+    fn record_debug(&mut self, field: &Field, value: &dyn std::fmt::Debug) {
+        if field.name() == "project_id" {
+            self.0 = Some(format!("{value:?}"));
+        }
+    }
+}
+
 fn get_server_addr(app_handle: &AppHandle) -> (Ipv4Addr, u16) {
     let config = app_handle.config();
     let product_name = config
@@ -183,3 +328,119 @@ fn remove_old_logs(log_directory: &Path) -> anyhow::Result<()> {
 
     Ok(())
 }
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Mutex;
+
+    use tracing::Event;
+
+    use super::*;
+
+    /// Collects the messages of all events it sees.
+    #[derive(Clone, Default)]
+    struct Messages(Arc<Mutex<Vec<String>>>);
+
+    impl<S: Subscriber> Layer<S> for Messages {
+        fn on_event(&self, event: &Event<'_>, _ctx: Context<'_, S>) {
+            struct Message<'a>(&'a mut Vec<String>);
+            impl Visit for Message<'_> {
+                fn record_debug(&mut self, field: &Field, value: &dyn std::fmt::Debug) {
+                    if field.name() == "message" {
+                        self.0.push(format!("{value:?}"));
+                    }
+                }
+            }
+            event.record(&mut Message(&mut self.0.lock().unwrap()));
+        }
+    }
+
+    fn subscriber(levels: &ProjectLogLevels, messages: &Messages) -> impl Subscriber {
+        tracing_subscriber::registry()
+            .with(ProjectIdLayer)
+            .with(messages.clone().with_filter(ProjectLevelFilter {
+                default_level: LevelFilter::INFO,
+                levels: levels.clone(),
+            }))
+    }
+
+    #[test]
+    fn project_id_of_spans_is_remembered() {
+        struct HasProjectId(Arc<Mutex<Vec<Option<String>>>>);
+        impl<S> Layer<S> for HasProjectId
+        where
+            S: Subscriber + for<'a> LookupSpan<'a>,
+        {
+            fn on_enter(&self, id: &span::Id, ctx: Context<'_, S>) {
+                let span = ctx.span(id).unwrap();
+                let project_id = span
+                    .extensions()
+                    .get::<ProjectIdField>()
+                    .map(|ProjectIdField(project_id)| project_id.clone());
+                self.0.lock().unwrap().push(project_id);
+            }
+        }
+
+        let seen = Arc::new(Mutex::new(Vec::new()));
+        let subscriber = tracing_subscriber::registry()
+            .with(ProjectIdLayer)
+            .with(HasProjectId(seen.clone()));
+        tracing::subscriber::with_default(subscriber, || {
+            tracing::info_span!("str", project_id = "a").in_scope(|| {});
+            tracing::info_span!("display", project_id = %"b").in_scope(|| {});
+            tracing::info_span!("other", id = "c").in_scope(|| {});
+        });
+        assert_eq!(
+            *seen.lock().unwrap(),
+            [Some("a".to_owned()), Some("b".to_owned()), None]
+        );
+    }
+
+    #[test]
+    fn project_levels_override_the_default_level() {
+        let levels = ProjectLogLevels::default();
+        let messages = Messages::default();
+        tracing::subscriber::with_default(subscriber(&levels, &messages), || {
+            tracing::debug!("before");
+            levels.set("a".to_owned(), Some(LevelFilter::DEBUG));
+            tracing::debug!("outside");
+            tracing::info_span!("op", project_id = "a").in_scope(|| tracing::debug!("in a"));
+            tracing::info_span!("op", project_id = "b").in_scope(|| {
+                tracing::debug!("in b");
+                tracing::info!("info in b");
+            });
+            tracing::info_span!("op", project_id = "a").in_scope(|| {
+                tracing::info_span!("nested").in_scope(|| tracing::debug!("nested in a"))
+            });
+
+            levels.set("a".to_owned(), None);
+            tracing::info_span!("op", project_id = "a").in_scope(|| tracing::debug!("reset"));
+        });
+        assert_eq!(
+            *messages.0.lock().unwrap(),
+            ["in a", "info in b", "nested in a"]
+        );
+    }
+
+    #[test]
+    fn max_level_hint_follows_the_most_verbose_level() {
+        let levels = ProjectLogLevels::default();
+        let filter = ProjectLevelFilter {
+            default_level: LevelFilter::INFO,
+            levels: levels.clone(),
+        };
+        let hint = || Filter::<tracing_subscriber::Registry>::max_level_hint(&filter);
+        assert_eq!(hint(), Some(LevelFilter::INFO));
+
+        levels.set("a".to_owned(), Some(LevelFilter::TRACE));
+        levels.set("b".to_owned(), Some(LevelFilter::WARN));
+        assert_eq!(hint(), Some(LevelFilter::TRACE));
+
+        levels.set("a".to_owned(), None);
+        assert_eq!(
+            hint(),
+            Some(LevelFilter::INFO),
+            "the default is more verbose than b"
+        );
+    }
+}
@@ -98,6 +98,10 @@ fn main() {
                     tracing::info!(version = %app_handle.package_info().version,
                                    name = %app_handle.package_info().name, "starting app");
 
+                    app_handle.manage(gitbutler_watcher::RecalculationLimit::default());
+                    app_handle.manage(gitbutler_watcher::RecentEvents::default());
+                    app_handle.manage(gitbutler_watcher::WatcherTimers::default());
+                    app_handle.manage(gitbutler_watcher::SyncScheduler::default());
                     app_handle.manage(WindowState::new(app_handle.clone()));
 
                     let app = App {
@@ -129,66 +133,155 @@ fn main() {
                     commands::git_set_global_config,
                     commands::git_remove_global_config,
                     commands::git_get_global_config,
+                    commands::derive_forge_urls,
+                    commands::set_recalculation_concurrency,
+                    commands::set_sync_retry_spacing,
+                    commands::recent_internal_events,
+                    commands::watcher_timers,
+                    commands::generate_regression_case,
                     commands::git_test_push,
                     commands::git_test_fetch,
+                    commands::test_remote_connection,
                     commands::git_index_size,
                     zip::commands::get_logs_archive_path,
+                    zip::commands::tail_logs,
                     zip::commands::get_project_archive_path,
                     zip::commands::get_project_data_archive_path,
+                    zip::commands::archive_project_state,
+                    zip::commands::verify_project_state,
+                    zip::commands::restore_project_state,
                     users::commands::set_user,
                     users::commands::delete_user,
                     users::commands::get_user,
                     projects::commands::add_project,
                     projects::commands::get_project,
                     projects::commands::update_project,
+                    projects::commands::export_project_profile,
+                    projects::commands::import_project_profile,
                     projects::commands::delete_project,
+                    projects::commands::sync_now,
+                    projects::commands::get_auto_snapshot_interval,
+                    projects::commands::set_auto_snapshot_interval,
+                    projects::commands::set_snapshot_storage,
+                    projects::commands::set_project_mode,
+                    projects::commands::list_open_handles,
                     projects::commands::list_projects,
                     projects::commands::set_project_active,
                     projects::commands::open_project_in_window,
                     repo::commands::git_get_local_config,
                     repo::commands::git_set_local_config,
                     repo::commands::check_signing_settings,
+                    repo::commands::commit_path,
+                    repo::commands::explain_ignore,
+                    repo::commands::recent_git_activity,
                     virtual_branches::commands::list_virtual_branches,
+                    virtual_branches::commands::validate_refname,
+                    virtual_branches::commands::refresh_virtual_branches,
                     virtual_branches::commands::create_virtual_branch,
                     virtual_branches::commands::commit_virtual_branch,
+                    virtual_branches::commands::preview_hunk_selection,
+                    virtual_branches::commands::commit_hunks,
+                    virtual_branches::commands::virtual_blame,
+                    virtual_branches::commands::lint_commit_message,
                     virtual_branches::commands::get_base_branch_data,
                     virtual_branches::commands::set_base_branch,
+                    virtual_branches::commands::autodetect_base_branch,
                     virtual_branches::commands::update_base_branch,
+                    virtual_branches::commands::base_merge_base,
+                    virtual_branches::commands::branch_divergence,
+                    virtual_branches::commands::clear_base_cache,
                     virtual_branches::commands::integrate_upstream_commits,
                     virtual_branches::commands::update_virtual_branch,
+                    virtual_branches::commands::set_branch_description,
                     virtual_branches::commands::delete_virtual_branch,
                     virtual_branches::commands::convert_to_real_branch,
+                    virtual_branches::commands::reconcile_ownership,
                     virtual_branches::commands::unapply_ownership,
+                    virtual_branches::commands::launch_external_diff,
+                    virtual_branches::commands::change_id_of,
+                    virtual_branches::commands::detect_ownership_conflicts,
+                    virtual_branches::commands::resolve_ownership_conflicts,
+                    virtual_branches::commands::move_changes,
+                    virtual_branches::commands::reorder_virtual_branches,
+                    virtual_branches::commands::set_assignment_mode,
+                    virtual_branches::commands::list_unassigned_files,
                     virtual_branches::commands::reset_files,
                     virtual_branches::commands::push_virtual_branch,
                     virtual_branches::commands::create_virtual_branch_from_branch,
+                    virtual_branches::commands::apply_stack,
+                    virtual_branches::commands::import_all_local_branches,
+                    virtual_branches::commands::adopt_orphan_commits,
                     virtual_branches::commands::can_apply_remote_branch,
                     virtual_branches::commands::list_remote_commit_files,
+                    virtual_branches::commands::integration_net_diff,
+                    virtual_branches::commands::subset_net_diff,
+                    virtual_branches::commands::branch_changed_files,
+                    virtual_branches::commands::find_duplicate_branches,
+                    virtual_branches::commands::check_repo_linkage,
+                    virtual_branches::commands::diagnose_project,
+                    virtual_branches::commands::repair_project,
+                    virtual_branches::commands::show_file_at_commit,
+                    virtual_branches::commands::commits_at_risk,
+                    virtual_branches::commands::can_fast_forward,
+                    virtual_branches::commands::can_unapply,
+                    virtual_branches::commands::list_workspaces,
+                    virtual_branches::commands::save_workspace,
+                    virtual_branches::commands::workspace_conflicts,
+                    virtual_branches::commands::switch_workspace,
+                    virtual_branches::commands::detect_base_history_rewrite,
+                    virtual_branches::commands::apply_blast_radius,
                     virtual_branches::commands::reset_virtual_branch,
                     virtual_branches::commands::amend_virtual_branch,
                     virtual_branches::commands::move_commit_file,
                     virtual_branches::commands::undo_commit,
                     virtual_branches::commands::insert_blank_commit,
                     virtual_branches::commands::reorder_commit,
+                    virtual_branches::commands::set_branch_parent,
+                    virtual_branches::commands::reparent_commit,
                     virtual_branches::commands::update_commit_message,
+                    virtual_branches::commands::list_conflicts,
+                    virtual_branches::commands::resolve_conflict,
+                    virtual_branches::commands::resolve_conflicts,
                     virtual_branches::commands::list_remote_branches,
                     virtual_branches::commands::get_remote_branch_data,
                     virtual_branches::commands::squash_branch_commit,
+                    virtual_branches::commands::squash_branch_commits,
                     virtual_branches::commands::fetch_from_remotes,
                     virtual_branches::commands::move_commit,
                     virtual_branches::commands::normalize_branch_name,
                     secret::secret_get_global,
                     secret::secret_set_global,
+                    secret::list_project_secrets,
+                    secret::delete_project_secret,
+                    secret::set_credential_source,
+                    secret::get_credential_source,
                     undo::list_snapshots,
+                    logs::set_project_log_level,
+                    undo::create_named_snapshot,
                     undo::restore_snapshot,
                     undo::snapshot_diff,
+                    undo::diff_snapshots,
+                    undo::preview_snapshot,
+                    undo::snapshot_file_content,
+                    undo::restore_snapshot_file,
+                    undo::list_recoverable_branches,
+                    undo::repair_oplog_chain,
+                    undo::detect_incomplete_restore,
+                    undo::complete_or_rollback_restore,
+                    undo::create_checkpoint,
+                    undo::restore_checkpoint,
+                    undo::list_checkpoints,
                     config::get_gb_config,
                     config::set_gb_config,
                     menu::menu_item_set_enabled,
                     menu::get_editor_link_scheme,
                     github::commands::init_device_oauth,
                     github::commands::check_auth_status,
+                    github::commands::rate_limit_status,
+                    github::commands::report_rate_limit,
+                    github::commands::pull_request_templates,
                     askpass::commands::submit_prompt_response,
+                    askpass::commands::set_askpass_cache_ttl,
                     remotes::list_remotes,
                     remotes::add_remote
                 ])
@@ -4,6 +4,7 @@ pub mod commands {
     use anyhow::Context;
     use std::path;
 
+    use gitbutler_config::api::ProjectCommands;
     use gitbutler_project::ProjectId;
     use gitbutler_project::{self as projects, Controller};
     use tauri::{State, Window};
@@ -22,6 +23,30 @@ pub mod commands {
         Ok(projects.update(&project).await?)
     }
 
+    #[tauri::command(async)]
+    #[instrument(skip(projects), err(Debug))]
+    pub async fn export_project_profile(
+        projects: State<'_, Controller>,
+        project_id: ProjectId,
+    ) -> Result<projects::ProjectProfile, Error> {
+        let mut profile = projects.export_profile(project_id)?;
+        profile.signing = projects.get(project_id)?.gb_config()?.into();
+        Ok(profile)
+    }
+
+    #[tauri::command(async)]
+    #[instrument(skip(projects), err(Debug))]
+    pub async fn import_project_profile(
+        projects: State<'_, Controller>,
+        project_id: ProjectId,
+        profile: projects::ProjectProfile,
+    ) -> Result<projects::Project, Error> {
+        let signing = profile.signing.clone();
+        let project = projects.import_profile(project_id, profile).await?;
+        project.set_gb_config(signing.into())?;
+        Ok(project)
+    }
+
     #[tauri::command(async)]
     #[instrument(skip(projects), err(Debug))]
     pub async fn add_project(
@@ -92,6 +117,92 @@ pub mod commands {
         Ok(())
     }
 
+    #[tauri::command(async)]
+    #[instrument(skip(projects), err(Debug))]
+    pub async fn list_open_handles(
+        projects: State<'_, Controller>,
+        id: ProjectId,
+    ) -> Result<projects::OpenHandles, Error> {
+        let project = projects.get(id)?;
+        Ok(projects::list_open_handles(&project.path)?)
+    }
+
+    /// Return how many seconds have to pass since the last snapshot before changes are snapshotted automatically.
+    #[tauri::command(async)]
+    #[instrument(skip(projects), err(Debug))]
+    pub async fn get_auto_snapshot_interval(
+        projects: State<'_, Controller>,
+        project_id: ProjectId,
+    ) -> Result<u64, Error> {
+        Ok(projects.get(project_id)?.auto_snapshot_interval().as_secs())
+    }
+
+    /// Set how many seconds have to pass since the last snapshot before changes are snapshotted automatically.
+    #[tauri::command(async)]
+    #[instrument(skip(projects), err(Debug))]
+    pub async fn set_auto_snapshot_interval(
+        projects: State<'_, Controller>,
+        project_id: ProjectId,
+        seconds: u64,
+    ) -> Result<(), Error> {
+        projects
+            .update(&projects::UpdateRequest {
+                id: project_id,
+                auto_snapshot_interval_secs: Some(seconds),
+                ..Default::default()
+            })
+            .await?;
+        Ok(())
+    }
+
+    /// Set how the objects of new oplog snapshots of the project are stored.
+    #[tauri::command(async)]
+    #[instrument(skip(projects), err(Debug))]
+    pub async fn set_snapshot_storage(
+        projects: State<'_, Controller>,
+        project_id: ProjectId,
+        storage: projects::SnapshotStorage,
+    ) -> Result<(), Error> {
+        projects
+            .update(&projects::UpdateRequest {
+                id: project_id,
+                snapshot_storage: Some(storage),
+                ..Default::default()
+            })
+            .await?;
+        Ok(())
+    }
+
+    /// Open the project in `mode`, which allows to only observe it without GitButler changing anything.
+    #[tauri::command(async)]
+    #[instrument(skip(projects), err(Debug))]
+    pub async fn set_project_mode(
+        projects: State<'_, Controller>,
+        project_id: ProjectId,
+        mode: projects::ProjectMode,
+    ) -> Result<(), Error> {
+        projects
+            .update(&projects::UpdateRequest {
+                id: project_id,
+                mode: Some(mode),
+                ..Default::default()
+            })
+            .await?;
+        Ok(())
+    }
+
+    /// Synchronize the project with GitButler's servers right away, independently of its sync trigger.
+    #[tauri::command(async)]
+    #[instrument(skip(window_state), err(Debug))]
+    pub async fn sync_now(
+        window_state: State<'_, WindowState>,
+        project_id: ProjectId,
+    ) -> Result<(), Error> {
+        Ok(window_state
+            .post(gitbutler_watcher::Action::SyncNow(project_id))
+            .await?)
+    }
+
     #[tauri::command(async)]
     #[instrument(skip(projects), err(Debug))]
     pub async fn delete_project(
@@ -1,8 +1,10 @@
 pub mod commands {
     use crate::error::Error;
+    use anyhow::anyhow;
     use gitbutler_project as projects;
     use gitbutler_project::ProjectId;
-    use gitbutler_repo::RepoCommands;
+    use gitbutler_repo::{CommitPath, GitActivity, IgnoreExplanation, RepoCommands};
+    use std::path::Path;
     use tauri::State;
     use tracing::instrument;
 
@@ -38,4 +40,43 @@ pub mod commands {
         let project = projects.get(id)?;
         project.check_signing_settings().map_err(Into::into)
     }
+
+    #[tauri::command(async)]
+    #[instrument(skip(projects), err(Debug))]
+    pub async fn commit_path(
+        projects: State<'_, projects::Controller>,
+        id: ProjectId,
+        from: String,
+        to: String,
+        first_parent: bool,
+    ) -> Result<CommitPath, Error> {
+        let project = projects.get(id)?;
+        let from = git2::Oid::from_str(&from).map_err(|e| anyhow!(e))?;
+        let to = git2::Oid::from_str(&to).map_err(|e| anyhow!(e))?;
+        project
+            .commit_path(from, to, first_parent)
+            .map_err(Into::into)
+    }
+
+    #[tauri::command(async)]
+    #[instrument(skip(projects), err(Debug))]
+    pub async fn explain_ignore(
+        projects: State<'_, projects::Controller>,
+        id: ProjectId,
+        path: &str,
+    ) -> Result<Option<IgnoreExplanation>, Error> {
+        let project = projects.get(id)?;
+        project.explain_ignore(Path::new(path)).map_err(Into::into)
+    }
+
+    #[tauri::command(async)]
+    #[instrument(skip(projects), err(Debug))]
+    pub async fn recent_git_activity(
+        projects: State<'_, projects::Controller>,
+        id: ProjectId,
+        limit: usize,
+    ) -> Result<Vec<GitActivity>, Error> {
+        let project = projects.get(id)?;
+        project.recent_git_activity(limit).map_err(Into::into)
+    }
 }
@@ -1,4 +1,6 @@
 use crate::error::Error;
+use gitbutler_project::ProjectId;
+use gitbutler_secret::credential_source::{self, CredentialSource};
 use gitbutler_secret::secret;
 use gitbutler_secret::Sensitive;
 use std::sync::Mutex;
@@ -21,3 +23,56 @@ pub async fn secret_set_global(handle: &str, secret: String) -> Result<(), Error
         secret::Namespace::Global,
     )?)
 }
+
+/// List the names of all secrets stored for the project, without their values.
+/// The credential sources and tokens of remotes are managed separately and aren't listed.
+#[tauri::command(async)]
+#[instrument(err(Debug))]
+pub async fn list_project_secrets(project_id: ProjectId) -> Result<Vec<String>, Error> {
+    let mut names = secret::list_scoped(
+        &secret::project_scope(project_id),
+        secret::Namespace::BuildKind,
+    )?;
+    names.retain(|name| !credential_source::is_managed_secret(name));
+    Ok(names)
+}
+
+#[tauri::command(async)]
+#[instrument(err(Debug))]
+pub async fn delete_project_secret(project_id: ProjectId, name: &str) -> Result<(), Error> {
+    Ok(secret::delete_scoped(
+        &secret::project_scope(project_id),
+        name,
+        secret::Namespace::BuildKind,
+    )?)
+}
+
+/// Authenticate with `remote` of the project using `source` from now on.
+/// `token` is only used with [`CredentialSource::Token`], and is stored in the secret store.
+#[tauri::command(async)]
+#[instrument(skip(token), err(Debug), fields(token = "<redacted>"))]
+pub async fn set_credential_source(
+    project_id: ProjectId,
+    remote: &str,
+    source: CredentialSource,
+    token: Option<String>,
+) -> Result<(), Error> {
+    Ok(credential_source::persist(
+        &secret::project_scope(project_id),
+        remote,
+        &source,
+        token.map(Sensitive).as_ref(),
+    )?)
+}
+
+#[tauri::command(async)]
+#[instrument(err(Debug))]
+pub async fn get_credential_source(
+    project_id: ProjectId,
+    remote: &str,
+) -> Result<Option<CredentialSource>, Error> {
+    Ok(credential_source::retrieve(
+        &secret::project_scope(project_id),
+        remote,
+    )?)
+}
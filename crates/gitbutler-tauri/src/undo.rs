@@ -1,8 +1,11 @@
 use crate::error::Error;
 use anyhow::Context;
 use gitbutler_diff::FileDiff;
-use gitbutler_oplog::entry::Snapshot;
-use gitbutler_oplog::OplogExt;
+use gitbutler_oplog::entry::{
+    Checkpoint, FileAtSnapshot, FileChange, IncompleteRestore, OplogRepair, RecoverableBranch,
+    RestoreResolution, Snapshot,
+};
+use gitbutler_oplog::{OplogExt, BRANCH_RECOVERY_RETENTION};
 use gitbutler_project as projects;
 use gitbutler_project::ProjectId;
 use std::collections::HashMap;
@@ -27,6 +30,18 @@ pub async fn list_snapshots(
     Ok(snapshots)
 }
 
+#[tauri::command(async)]
+#[instrument(skip(projects), err(Debug))]
+pub async fn create_named_snapshot(
+    projects: State<'_, projects::Controller>,
+    project_id: ProjectId,
+    label: String,
+) -> Result<String, Error> {
+    let project = projects.get(project_id).context("failed to get project")?;
+    let snapshot_id = project.create_named_snapshot(&label)?;
+    Ok(snapshot_id.to_string())
+}
+
 #[tauri::command(async)]
 #[instrument(skip(projects), err(Debug))]
 pub async fn restore_snapshot(
@@ -51,3 +66,134 @@ pub async fn snapshot_diff(
     let diff = project.snapshot_diff(sha.parse().map_err(anyhow::Error::from)?)?;
     Ok(diff)
 }
+
+/// Return the worktree files that changed between the snapshots `from` and `to`.
+#[tauri::command(async)]
+#[instrument(skip(projects), err(Debug))]
+pub async fn diff_snapshots(
+    projects: State<'_, projects::Controller>,
+    project_id: ProjectId,
+    from: String,
+    to: String,
+) -> Result<Vec<FileChange>, Error> {
+    let project = projects.get(project_id).context("failed to get project")?;
+    let changes = project.diff_snapshots(
+        from.parse().map_err(anyhow::Error::from)?,
+        to.parse().map_err(anyhow::Error::from)?,
+    )?;
+    Ok(changes)
+}
+
+#[tauri::command(async)]
+#[instrument(skip(projects), err(Debug))]
+pub async fn preview_snapshot(
+    projects: State<'_, projects::Controller>,
+    project_id: ProjectId,
+    sha: String,
+) -> Result<Vec<PathBuf>, Error> {
+    let project = projects.get(project_id).context("failed to get project")?;
+    let files = project.preview_snapshot(sha.parse().map_err(anyhow::Error::from)?)?;
+    Ok(files)
+}
+
+#[tauri::command(async)]
+#[instrument(skip(projects), err(Debug))]
+pub async fn snapshot_file_content(
+    projects: State<'_, projects::Controller>,
+    project_id: ProjectId,
+    sha: String,
+    path: PathBuf,
+) -> Result<FileAtSnapshot, Error> {
+    let project = projects.get(project_id).context("failed to get project")?;
+    let file = project.snapshot_file_content(sha.parse().map_err(anyhow::Error::from)?, &path)?;
+    Ok(file)
+}
+
+#[tauri::command(async)]
+#[instrument(skip(projects), err(Debug))]
+pub async fn restore_snapshot_file(
+    projects: State<'_, projects::Controller>,
+    project_id: ProjectId,
+    sha: String,
+    path: PathBuf,
+) -> Result<(), Error> {
+    let project = projects.get(project_id).context("failed to get project")?;
+    project.restore_snapshot_file(sha.parse().map_err(anyhow::Error::from)?, &path)?;
+    Ok(())
+}
+
+#[tauri::command(async)]
+#[instrument(skip(projects), err(Debug))]
+pub async fn list_recoverable_branches(
+    projects: State<'_, projects::Controller>,
+    project_id: ProjectId,
+) -> Result<Vec<RecoverableBranch>, Error> {
+    let project = projects.get(project_id).context("failed to get project")?;
+    let branches = project.list_recoverable_branches(BRANCH_RECOVERY_RETENTION)?;
+    Ok(branches)
+}
+
+#[tauri::command(async)]
+#[instrument(skip(projects), err(Debug))]
+pub async fn repair_oplog_chain(
+    projects: State<'_, projects::Controller>,
+    project_id: ProjectId,
+) -> Result<OplogRepair, Error> {
+    let project = projects.get(project_id).context("failed to get project")?;
+    let mut guard = project.exclusive_worktree_access();
+    Ok(project.repair_oplog_chain(guard.write_permission())?)
+}
+
+#[tauri::command(async)]
+#[instrument(skip(projects), err(Debug))]
+pub async fn detect_incomplete_restore(
+    projects: State<'_, projects::Controller>,
+    project_id: ProjectId,
+) -> Result<Option<IncompleteRestore>, Error> {
+    let project = projects.get(project_id).context("failed to get project")?;
+    Ok(project.detect_incomplete_restore()?)
+}
+
+#[tauri::command(async)]
+#[instrument(skip(projects), err(Debug))]
+pub async fn complete_or_rollback_restore(
+    projects: State<'_, projects::Controller>,
+    project_id: ProjectId,
+    resolution: RestoreResolution,
+) -> Result<(), Error> {
+    let project = projects.get(project_id).context("failed to get project")?;
+    project.complete_or_rollback_restore(resolution)?;
+    Ok(())
+}
+
+#[tauri::command(async)]
+#[instrument(skip(projects), err(Debug))]
+pub async fn create_checkpoint(
+    projects: State<'_, projects::Controller>,
+    project_id: ProjectId,
+    name: String,
+) -> Result<Checkpoint, Error> {
+    let project = projects.get(project_id).context("failed to get project")?;
+    Ok(project.create_checkpoint(&name)?)
+}
+
+#[tauri::command(async)]
+#[instrument(skip(projects), err(Debug))]
+pub async fn restore_checkpoint(
+    projects: State<'_, projects::Controller>,
+    project_id: ProjectId,
+    name: String,
+) -> Result<Checkpoint, Error> {
+    let project = projects.get(project_id).context("failed to get project")?;
+    Ok(project.restore_checkpoint(&name)?)
+}
+
+#[tauri::command(async)]
+#[instrument(skip(projects), err(Debug))]
+pub async fn list_checkpoints(
+    projects: State<'_, projects::Controller>,
+    project_id: ProjectId,
+) -> Result<Vec<Checkpoint>, Error> {
+    let project = projects.get(project_id).context("failed to get project")?;
+    Ok(project.list_checkpoints()?)
+}
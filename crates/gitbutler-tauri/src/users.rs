@@ -32,6 +32,7 @@ pub mod commands {
     #[instrument(skip(login), err(Debug))]
     pub async fn delete_user(login: State<'_, Controller>) -> Result<(), Error> {
         login.delete_user()?;
+        gitbutler_repo::askpass::get_broker().clear_cache().await;
         Ok(())
     }
 
@@ -1,18 +1,43 @@
 pub mod commands {
+    use std::collections::HashMap;
+    use std::path::{Path, PathBuf};
+
     use crate::error::Error;
     use anyhow::{anyhow, Context};
-    use gitbutler_branch::BranchOwnershipClaims;
+    use gitbutler_branch::{AssignmentMode, BranchOwnershipClaims, OwnershipClaim};
     use gitbutler_branch::{BranchCreateRequest, BranchId, BranchUpdateRequest};
-    use gitbutler_branch_actions::BaseBranch;
+    use gitbutler_branch_actions::conflicts::{ConflictResolution, ConflictedFile};
+    use gitbutler_branch_actions::BaseHistoryRewrite;
+    use gitbutler_branch_actions::CommitLintReport;
+    use gitbutler_branch_actions::DanglingReference;
+    use gitbutler_branch_actions::DiffSide;
+    use gitbutler_branch_actions::DuplicateBranch;
+    use gitbutler_branch_actions::FileAtCommit;
+    use gitbutler_branch_actions::LineAttribution;
+    use gitbutler_branch_actions::OwnershipConflict;
+    use gitbutler_branch_actions::OwnershipDrift;
     use gitbutler_branch_actions::RemoteBranchFile;
+    use gitbutler_branch_actions::StackApplication;
+    use gitbutler_branch_actions::SubsetNetDiff;
+    use gitbutler_branch_actions::VirtualBranchFile;
+    use gitbutler_branch_actions::{BaseBranch, BranchDivergence};
+    use gitbutler_branch_actions::{
+        CommitAtRisk, FastForward, RiskyOperation, TouchedFile, UnapplyReport,
+    };
+    use gitbutler_branch_actions::{HunkSelection, HunkSelector};
+    use gitbutler_branch_actions::{LocalBranchImport, LocalBranchImportOptions};
     use gitbutler_branch_actions::{NameConflictResolution, VirtualBranchActions, VirtualBranches};
+    use gitbutler_branch_actions::{ProjectDiagnostics, ProjectRepair};
     use gitbutler_branch_actions::{RemoteBranch, RemoteBranchData};
+    use gitbutler_branch_actions::{Workspace, WorkspaceConflict, WorkspaceSwitch};
     use gitbutler_error::error::Code;
     use gitbutler_project as projects;
     use gitbutler_project::ProjectId;
     use gitbutler_reference::normalize_branch_name as normalize_name;
     use gitbutler_reference::ReferenceName;
     use gitbutler_reference::{Refname, RemoteRefname};
+    use gitbutler_repo::FetchDepth;
+    use gitbutler_watcher::WatcherTimers;
     use tauri::State;
     use tracing::instrument;
 
@@ -43,6 +68,77 @@ pub mod commands {
         Ok(oid.to_string())
     }
 
+    #[tauri::command(async)]
+    #[instrument(skip(projects), err(Debug))]
+    pub async fn preview_hunk_selection(
+        projects: State<'_, projects::Controller>,
+        project_id: ProjectId,
+        branch: BranchId,
+        path: &str,
+        hunks: Vec<HunkSelector>,
+    ) -> Result<HunkSelection, Error> {
+        let project = projects.get(project_id)?;
+        VirtualBranchActions
+            .preview_hunk_selection(&project, branch, Path::new(path), &hunks)
+            .await
+            .map_err(Into::into)
+    }
+
+    #[tauri::command(async)]
+    #[instrument(skip(projects), err(Debug))]
+    pub async fn virtual_blame(
+        projects: State<'_, projects::Controller>,
+        project_id: ProjectId,
+        path: &str,
+    ) -> Result<Vec<LineAttribution>, Error> {
+        let project = projects.get(project_id)?;
+        VirtualBranchActions
+            .virtual_blame(&project, Path::new(path))
+            .await
+            .map_err(Into::into)
+    }
+
+    #[tauri::command(async)]
+    #[instrument(skip(projects, windows), err(Debug))]
+    pub async fn commit_hunks(
+        windows: State<'_, WindowState>,
+        projects: State<'_, projects::Controller>,
+        project_id: ProjectId,
+        branch: BranchId,
+        path: &str,
+        hunks: Vec<HunkSelector>,
+        message: &str,
+        run_hooks: bool,
+    ) -> Result<String, Error> {
+        let project = projects.get(project_id)?;
+        let oid = VirtualBranchActions
+            .commit_hunks(
+                &project,
+                branch,
+                Path::new(path),
+                &hunks,
+                message,
+                run_hooks,
+            )
+            .await?;
+        emit_vbranches(&windows, project_id).await;
+        Ok(oid.to_string())
+    }
+
+    #[tauri::command(async)]
+    #[instrument(skip(projects), err(Debug))]
+    pub async fn lint_commit_message(
+        projects: State<'_, projects::Controller>,
+        project_id: ProjectId,
+        message: &str,
+    ) -> Result<CommitLintReport, Error> {
+        let project = projects.get(project_id)?;
+        VirtualBranchActions
+            .lint_commit_message(&project, message)
+            .await
+            .map_err(Into::into)
+    }
+
     #[tauri::command(async)]
     #[instrument(skip(projects), err(Debug))]
     pub async fn list_virtual_branches(
@@ -54,241 +150,778 @@ pub mod commands {
             .list_virtual_branches(&project)
             .await
             .map_err(Into::into)
-            .map(|(branches, skipped_files)| VirtualBranches {
-                branches,
-                skipped_files,
-            })
+            .map(|(branches, skipped_files)| VirtualBranches {
+                branches,
+                skipped_files,
+            })
+    }
+
+    /// Check `name` against git's rules for branch names and return it normalized.
+    #[tauri::command(async)]
+    #[instrument(err(Debug))]
+    pub async fn validate_refname(name: String) -> Result<String, Error> {
+        gitbutler_reference::validate_refname(&name).map_err(|violation| {
+            anyhow::Error::from(violation)
+                .context(Code::Validation)
+                .into()
+        })
+    }
+
+    /// Emit the current virtual branches of the project again, for the UI to resync with the backend.
+    /// Unlike changes to files, this never creates a snapshot.
+    #[tauri::command(async)]
+    #[instrument(skip(windows), err(Debug))]
+    pub async fn refresh_virtual_branches(
+        windows: State<'_, WindowState>,
+        project_id: ProjectId,
+    ) -> Result<(), Error> {
+        Ok(windows
+            .post(gitbutler_watcher::Action::CalculateVirtualBranches(
+                project_id,
+            ))
+            .await?)
+    }
+
+    #[tauri::command(async)]
+    #[instrument(skip(projects, windows), err(Debug))]
+    pub async fn create_virtual_branch(
+        windows: State<'_, WindowState>,
+        projects: State<'_, projects::Controller>,
+        project_id: ProjectId,
+        branch: BranchCreateRequest,
+    ) -> Result<BranchId, Error> {
+        let project = projects.get(project_id)?;
+        let branch_id = VirtualBranchActions
+            .create_virtual_branch(&project, &branch)
+            .await?;
+        emit_vbranches(&windows, project_id).await;
+        Ok(branch_id)
+    }
+
+    #[tauri::command(async)]
+    #[instrument(skip(projects, windows), err(Debug))]
+    pub async fn create_virtual_branch_from_branch(
+        windows: State<'_, WindowState>,
+        projects: State<'_, projects::Controller>,
+        project_id: ProjectId,
+        branch: Refname,
+    ) -> Result<BranchId, Error> {
+        let project = projects.get(project_id)?;
+        let branch_id = VirtualBranchActions
+            .create_virtual_branch_from_branch(&project, &branch)
+            .await?;
+        emit_vbranches(&windows, project_id).await;
+        Ok(branch_id)
+    }
+
+    #[tauri::command(async)]
+    #[instrument(skip(projects, windows), err(Debug))]
+    pub async fn adopt_orphan_commits(
+        windows: State<'_, WindowState>,
+        projects: State<'_, projects::Controller>,
+        project_id: ProjectId,
+        commit_ids: Vec<String>,
+        branch_name: String,
+    ) -> Result<BranchId, Error> {
+        let project = projects.get(project_id)?;
+        let commit_ids = commit_ids
+            .iter()
+            .map(|commit_id| git2::Oid::from_str(commit_id).map_err(|e| anyhow!(e)))
+            .collect::<Result<Vec<_>, _>>()?;
+        let branch_id = VirtualBranchActions
+            .adopt_orphan_commits(&project, &commit_ids, &branch_name)
+            .await?;
+        emit_vbranches(&windows, project_id).await;
+        Ok(branch_id)
+    }
+
+    #[tauri::command(async)]
+    #[instrument(skip(projects, windows), err(Debug))]
+    pub async fn apply_stack(
+        windows: State<'_, WindowState>,
+        projects: State<'_, projects::Controller>,
+        project_id: ProjectId,
+        branches: Vec<BranchId>,
+    ) -> Result<StackApplication, Error> {
+        let project = projects.get(project_id)?;
+        let application = VirtualBranchActions
+            .apply_stack(&project, &branches)
+            .await?;
+        emit_vbranches(&windows, project_id).await;
+        Ok(application)
+    }
+
+    #[tauri::command(async)]
+    #[instrument(skip(projects, windows), err(Debug))]
+    pub async fn import_all_local_branches(
+        windows: State<'_, WindowState>,
+        projects: State<'_, projects::Controller>,
+        project_id: ProjectId,
+        options: LocalBranchImportOptions,
+    ) -> Result<LocalBranchImport, Error> {
+        let project = projects.get(project_id)?;
+        let import = VirtualBranchActions
+            .import_all_local_branches(&project, &options)
+            .await?;
+        emit_vbranches(&windows, project_id).await;
+        Ok(import)
+    }
+
+    #[tauri::command(async)]
+    #[instrument(skip(projects, windows), err(Debug))]
+    pub async fn integrate_upstream_commits(
+        windows: State<'_, WindowState>,
+        projects: State<'_, projects::Controller>,
+        project_id: ProjectId,
+        branch: BranchId,
+    ) -> Result<(), Error> {
+        let project = projects.get(project_id)?;
+        VirtualBranchActions
+            .integrate_upstream_commits(&project, branch)
+            .await?;
+        emit_vbranches(&windows, project_id).await;
+        Ok(())
+    }
+
+    #[tauri::command(async)]
+    #[instrument(skip(projects), err(Debug))]
+    pub async fn get_base_branch_data(
+        projects: State<'_, projects::Controller>,
+        project_id: ProjectId,
+    ) -> Result<Option<BaseBranch>, Error> {
+        let project = projects.get(project_id)?;
+        if let Ok(base_branch) = VirtualBranchActions::get_base_branch_data(&project).await {
+            Ok(Some(base_branch))
+        } else {
+            Ok(None)
+        }
+    }
+
+    #[tauri::command(async)]
+    #[instrument(skip(projects, windows), err(Debug))]
+    pub async fn set_base_branch(
+        windows: State<'_, WindowState>,
+        projects: State<'_, projects::Controller>,
+        project_id: ProjectId,
+        branch: &str,
+        push_remote: Option<&str>, // optional different name of a remote to push to (defaults to same as the branch)
+    ) -> Result<BaseBranch, Error> {
+        let project = projects.get(project_id)?;
+        let branch_name = format!("refs/remotes/{}", branch)
+            .parse()
+            .context("Invalid branch name")?;
+        let base_branch = VirtualBranchActions
+            .set_base_branch(&project, &branch_name)
+            .await?;
+
+        // if they also sent a different push remote, set that too
+        if let Some(push_remote) = push_remote {
+            VirtualBranchActions
+                .set_target_push_remote(&project, push_remote)
+                .await?;
+        }
+        emit_vbranches(&windows, project_id).await;
+        Ok(base_branch)
+    }
+
+    #[tauri::command(async)]
+    #[instrument(skip(projects, windows), err(Debug))]
+    pub async fn autodetect_base_branch(
+        windows: State<'_, WindowState>,
+        projects: State<'_, projects::Controller>,
+        project_id: ProjectId,
+    ) -> Result<BaseBranch, Error> {
+        let project = projects.get(project_id)?;
+        let base_branch = VirtualBranchActions
+            .autodetect_base_branch(&project)
+            .await?;
+        emit_vbranches(&windows, project_id).await;
+        Ok(base_branch)
+    }
+
+    #[tauri::command(async)]
+    #[instrument(skip(projects, windows), err(Debug))]
+    pub async fn update_base_branch(
+        windows: State<'_, WindowState>,
+        projects: State<'_, projects::Controller>,
+        project_id: ProjectId,
+    ) -> Result<Vec<ReferenceName>, Error> {
+        let project = projects.get(project_id)?;
+        let unapplied_branches = VirtualBranchActions.update_base_branch(&project).await?;
+        emit_vbranches(&windows, project_id).await;
+        Ok(unapplied_branches)
+    }
+
+    #[tauri::command(async)]
+    #[instrument(skip(projects), err(Debug))]
+    pub async fn base_merge_base(
+        projects: State<'_, projects::Controller>,
+        project_id: ProjectId,
+    ) -> Result<String, Error> {
+        let project = projects.get(project_id)?;
+        let merge_base = VirtualBranchActions.base_merge_base(&project).await?;
+        Ok(merge_base.to_string())
+    }
+
+    #[tauri::command(async)]
+    #[instrument(skip(projects), err(Debug))]
+    pub async fn branch_divergence(
+        projects: State<'_, projects::Controller>,
+        project_id: ProjectId,
+    ) -> Result<Option<BranchDivergence>, Error> {
+        let project = projects.get(project_id)?;
+        VirtualBranchActions
+            .branch_divergence(&project)
+            .await
+            .map_err(Into::into)
+    }
+
+    #[tauri::command(async)]
+    #[instrument(skip(projects), err(Debug))]
+    pub async fn clear_base_cache(
+        projects: State<'_, projects::Controller>,
+        project_id: ProjectId,
+    ) -> Result<(), Error> {
+        let project = projects.get(project_id)?;
+        VirtualBranchActions.clear_base_cache(&project).await?;
+        Ok(())
+    }
+
+    #[tauri::command(async)]
+    #[instrument(skip(projects, windows), err(Debug))]
+    pub async fn update_virtual_branch(
+        windows: State<'_, WindowState>,
+        projects: State<'_, projects::Controller>,
+        project_id: ProjectId,
+        branch: BranchUpdateRequest,
+    ) -> Result<(), Error> {
+        let project = projects.get(project_id)?;
+        VirtualBranchActions
+            .update_virtual_branch(&project, branch)
+            .await?;
+
+        emit_vbranches(&windows, project_id).await;
+        Ok(())
+    }
+
+    #[tauri::command(async)]
+    #[instrument(skip(projects, windows), err(Debug))]
+    pub async fn set_branch_description(
+        windows: State<'_, WindowState>,
+        projects: State<'_, projects::Controller>,
+        project_id: ProjectId,
+        branch_id: BranchId,
+        description: String,
+    ) -> Result<(), Error> {
+        let project = projects.get(project_id)?;
+        VirtualBranchActions
+            .set_branch_description(&project, branch_id, &description)
+            .await?;
+        emit_vbranches(&windows, project_id).await;
+        Ok(())
+    }
+
+    #[tauri::command(async)]
+    #[instrument(skip(projects, windows), err(Debug))]
+    pub async fn delete_virtual_branch(
+        windows: State<'_, WindowState>,
+        projects: State<'_, projects::Controller>,
+        project_id: ProjectId,
+        branch_id: BranchId,
+    ) -> Result<(), Error> {
+        let project = projects.get(project_id)?;
+        VirtualBranchActions
+            .delete_virtual_branch(&project, branch_id)
+            .await?;
+        emit_vbranches(&windows, project_id).await;
+        Ok(())
+    }
+
+    #[tauri::command(async)]
+    #[instrument(skip(projects, windows), err(Debug))]
+    pub async fn convert_to_real_branch(
+        windows: State<'_, WindowState>,
+        projects: State<'_, projects::Controller>,
+        project_id: ProjectId,
+        branch: BranchId,
+        name_conflict_resolution: NameConflictResolution,
+    ) -> Result<(), Error> {
+        let project = projects.get(project_id)?;
+        VirtualBranchActions
+            .convert_to_real_branch(&project, branch, name_conflict_resolution)
+            .await?;
+        emit_vbranches(&windows, project_id).await;
+        Ok(())
+    }
+
+    #[tauri::command(async)]
+    #[instrument(skip(projects, windows), err(Debug))]
+    pub async fn reconcile_ownership(
+        windows: State<'_, WindowState>,
+        projects: State<'_, projects::Controller>,
+        project_id: ProjectId,
+    ) -> Result<Vec<OwnershipDrift>, Error> {
+        let project = projects.get(project_id)?;
+        let drifts = VirtualBranchActions.reconcile_ownership(&project).await?;
+        emit_vbranches(&windows, project_id).await;
+        Ok(drifts)
+    }
+
+    #[tauri::command(async)]
+    #[instrument(skip(projects, windows), err(Debug))]
+    pub async fn unapply_ownership(
+        windows: State<'_, WindowState>,
+        projects: State<'_, projects::Controller>,
+        project_id: ProjectId,
+        ownership: BranchOwnershipClaims,
+    ) -> Result<(), Error> {
+        let project = projects.get(project_id)?;
+        VirtualBranchActions
+            .unapply_ownership(&project, &ownership)
+            .await?;
+        emit_vbranches(&windows, project_id).await;
+        Ok(())
+    }
+
+    /// Open the worktree file at `path` in the configured external diff tool, compared with its `side` version.
+    #[tauri::command(async)]
+    #[instrument(skip(projects), err(Debug))]
+    pub async fn launch_external_diff(
+        projects: State<'_, projects::Controller>,
+        project_id: ProjectId,
+        path: &str,
+        side: DiffSide,
+    ) -> Result<(), Error> {
+        let project = projects.get(project_id)?;
+        VirtualBranchActions
+            .launch_external_diff(&project, Path::new(path), side)
+            .await
+            .map_err(Into::into)
+    }
+
+    /// Return the change-id of `commit_id`, which stays the same when the commit is rebased.
+    #[tauri::command(async)]
+    #[instrument(skip(projects), err(Debug))]
+    pub async fn change_id_of(
+        projects: State<'_, projects::Controller>,
+        project_id: ProjectId,
+        commit_id: String,
+    ) -> Result<String, Error> {
+        let project = projects.get(project_id)?;
+        let commit_id = git2::Oid::from_str(&commit_id).map_err(|e| anyhow!(e))?;
+        VirtualBranchActions
+            .change_id_of(&project, commit_id)
+            .await
+            .map_err(Into::into)
+    }
+
+    #[tauri::command(async)]
+    #[instrument(skip(projects), err(Debug))]
+    pub async fn detect_ownership_conflicts(
+        projects: State<'_, projects::Controller>,
+        project_id: ProjectId,
+    ) -> Result<Vec<OwnershipConflict>, Error> {
+        let project = projects.get(project_id)?;
+        VirtualBranchActions
+            .detect_ownership_conflicts(&project)
+            .await
+            .map_err(Into::into)
+    }
+
+    /// Each of `changes` is either a claim like `path:1-5,10-12` or the path of a file to move entirely.
+    #[tauri::command(async)]
+    #[instrument(skip(projects, windows), err(Debug))]
+    pub async fn move_changes(
+        windows: State<'_, WindowState>,
+        projects: State<'_, projects::Controller>,
+        project_id: ProjectId,
+        from_branch: BranchId,
+        to_branch: BranchId,
+        changes: Vec<String>,
+    ) -> Result<(), Error> {
+        let project = projects.get(project_id)?;
+        let changes = changes
+            .iter()
+            .map(|change| {
+                change.parse().unwrap_or_else(|_| OwnershipClaim {
+                    file_path: PathBuf::from(change),
+                    hunks: Vec::new(),
+                })
+            })
+            .collect::<Vec<_>>();
+        VirtualBranchActions
+            .move_changes(&project, from_branch, to_branch, &changes)
+            .await?;
+        emit_vbranches(&windows, project_id).await;
+        Ok(())
+    }
+
+    #[tauri::command(async)]
+    #[instrument(skip(projects, windows), err(Debug))]
+    pub async fn resolve_ownership_conflicts(
+        windows: State<'_, WindowState>,
+        projects: State<'_, projects::Controller>,
+        project_id: ProjectId,
+        owner: BranchId,
+        ownership: BranchOwnershipClaims,
+    ) -> Result<(), Error> {
+        let project = projects.get(project_id)?;
+        VirtualBranchActions
+            .resolve_ownership_conflicts(&project, owner, &ownership)
+            .await?;
+        emit_vbranches(&windows, project_id).await;
+        Ok(())
+    }
+
+    #[tauri::command(async)]
+    #[instrument(skip(projects, windows), err(Debug))]
+    pub async fn reorder_virtual_branches(
+        windows: State<'_, WindowState>,
+        projects: State<'_, projects::Controller>,
+        project_id: ProjectId,
+        ordered_ids: Vec<BranchId>,
+    ) -> Result<(), Error> {
+        let project = projects.get(project_id)?;
+        VirtualBranchActions
+            .reorder_virtual_branches(&project, &ordered_ids)
+            .await?;
+        emit_vbranches(&windows, project_id).await;
+        Ok(())
+    }
+
+    #[tauri::command(async)]
+    #[instrument(skip(projects, windows), err(Debug))]
+    pub async fn set_assignment_mode(
+        windows: State<'_, WindowState>,
+        projects: State<'_, projects::Controller>,
+        project_id: ProjectId,
+        mode: AssignmentMode,
+    ) -> Result<(), Error> {
+        let project = projects.get(project_id)?;
+        VirtualBranchActions
+            .set_assignment_mode(&project, mode)
+            .await?;
+        emit_vbranches(&windows, project_id).await;
+        Ok(())
+    }
+
+    #[tauri::command(async)]
+    #[instrument(skip(projects), err(Debug))]
+    pub async fn list_unassigned_files(
+        projects: State<'_, projects::Controller>,
+        project_id: ProjectId,
+    ) -> Result<Vec<VirtualBranchFile>, Error> {
+        let project = projects.get(project_id)?;
+        VirtualBranchActions
+            .list_unassigned_files(&project)
+            .await
+            .map_err(Into::into)
+    }
+
+    #[tauri::command(async)]
+    #[instrument(skip(projects, windows), err(Debug))]
+    pub async fn reset_files(
+        windows: State<'_, WindowState>,
+        projects: State<'_, projects::Controller>,
+        project_id: ProjectId,
+        files: &str,
+    ) -> Result<(), Error> {
+        let project = projects.get(project_id)?;
+        // convert files to Vec<String>
+        let files = files
+            .split('\n')
+            .map(std::string::ToString::to_string)
+            .collect::<Vec<String>>();
+        VirtualBranchActions.reset_files(&project, &files).await?;
+        emit_vbranches(&windows, project_id).await;
+        Ok(())
+    }
+
+    #[tauri::command(async)]
+    #[instrument(skip(projects, windows), err(Debug))]
+    pub async fn push_virtual_branch(
+        windows: State<'_, WindowState>,
+        projects: State<'_, projects::Controller>,
+        project_id: ProjectId,
+        branch_id: BranchId,
+        with_force: bool,
+    ) -> Result<(), Error> {
+        let project = projects.get(project_id)?;
+        VirtualBranchActions
+            .push_virtual_branch(&project, branch_id, with_force, Some(Some(branch_id)))
+            .await
+            .map_err(|err| err.context(Code::Unknown))?;
+        emit_vbranches(&windows, project_id).await;
+        if let Err(error) = windows
+            .post(gitbutler_watcher::Action::BranchPushed(project_id))
+            .await
+        {
+            tracing::error!(?error);
+        }
+        Ok(())
+    }
+
+    #[tauri::command(async)]
+    #[instrument(skip(projects), err(Debug))]
+    pub async fn can_apply_remote_branch(
+        projects: State<'_, projects::Controller>,
+        project_id: ProjectId,
+        branch: RemoteRefname,
+    ) -> Result<bool, Error> {
+        let project = projects.get(project_id)?;
+        Ok(VirtualBranchActions
+            .can_apply_remote_branch(&project, &branch)
+            .await?)
+    }
+
+    #[tauri::command(async)]
+    #[instrument(skip(projects), err(Debug))]
+    pub async fn integration_net_diff(
+        projects: State<'_, projects::Controller>,
+        project_id: ProjectId,
+    ) -> Result<Vec<RemoteBranchFile>, Error> {
+        let project = projects.get(project_id)?;
+        VirtualBranchActions
+            .integration_net_diff(&project)
+            .await
+            .map_err(Into::into)
+    }
+
+    #[tauri::command(async)]
+    #[instrument(skip(projects), err(Debug))]
+    pub async fn subset_net_diff(
+        projects: State<'_, projects::Controller>,
+        project_id: ProjectId,
+        branch_ids: Vec<BranchId>,
+    ) -> Result<SubsetNetDiff, Error> {
+        let project = projects.get(project_id)?;
+        VirtualBranchActions
+            .subset_net_diff(&project, &branch_ids)
+            .await
+            .map_err(Into::into)
+    }
+
+    #[tauri::command(async)]
+    #[instrument(skip(projects), err(Debug))]
+    pub async fn list_remote_commit_files(
+        projects: State<'_, projects::Controller>,
+        project_id: ProjectId,
+        commit_oid: String,
+    ) -> Result<Vec<RemoteBranchFile>, Error> {
+        let project = projects.get(project_id)?;
+        let commit_oid = git2::Oid::from_str(&commit_oid).map_err(|e| anyhow!(e))?;
+        VirtualBranchActions
+            .list_remote_commit_files(&project, commit_oid)
+            .await
+            .map_err(Into::into)
+    }
+
+    #[tauri::command(async)]
+    #[instrument(skip(projects), err(Debug))]
+    pub async fn branch_changed_files(
+        projects: State<'_, projects::Controller>,
+        project_id: ProjectId,
+        branch_id: BranchId,
+    ) -> Result<Vec<PathBuf>, Error> {
+        let project = projects.get(project_id)?;
+        VirtualBranchActions
+            .branch_changed_files(&project, branch_id)
+            .await
+            .map_err(Into::into)
     }
 
     #[tauri::command(async)]
-    #[instrument(skip(projects, windows), err(Debug))]
-    pub async fn create_virtual_branch(
-        windows: State<'_, WindowState>,
+    #[instrument(skip(projects), err(Debug))]
+    pub async fn show_file_at_commit(
         projects: State<'_, projects::Controller>,
         project_id: ProjectId,
-        branch: BranchCreateRequest,
-    ) -> Result<BranchId, Error> {
+        branch_id: BranchId,
+        commit_id: String,
+        path: &str,
+    ) -> Result<FileAtCommit, Error> {
         let project = projects.get(project_id)?;
-        let branch_id = VirtualBranchActions
-            .create_virtual_branch(&project, &branch)
-            .await?;
-        emit_vbranches(&windows, project_id).await;
-        Ok(branch_id)
+        let commit_id = git2::Oid::from_str(&commit_id).map_err(|e| anyhow!(e))?;
+        VirtualBranchActions
+            .show_file_at_commit(&project, branch_id, commit_id, Path::new(path))
+            .await
+            .map_err(Into::into)
     }
 
     #[tauri::command(async)]
-    #[instrument(skip(projects, windows), err(Debug))]
-    pub async fn create_virtual_branch_from_branch(
-        windows: State<'_, WindowState>,
+    #[instrument(skip(projects), err(Debug))]
+    pub async fn find_duplicate_branches(
         projects: State<'_, projects::Controller>,
         project_id: ProjectId,
-        branch: Refname,
-    ) -> Result<BranchId, Error> {
+    ) -> Result<Vec<DuplicateBranch>, Error> {
         let project = projects.get(project_id)?;
-        let branch_id = VirtualBranchActions
-            .create_virtual_branch_from_branch(&project, &branch)
-            .await?;
-        emit_vbranches(&windows, project_id).await;
-        Ok(branch_id)
+        VirtualBranchActions
+            .find_duplicate_branches(&project)
+            .await
+            .map_err(Into::into)
     }
 
     #[tauri::command(async)]
-    #[instrument(skip(projects, windows), err(Debug))]
-    pub async fn integrate_upstream_commits(
-        windows: State<'_, WindowState>,
+    #[instrument(skip(projects), err(Debug))]
+    pub async fn check_repo_linkage(
         projects: State<'_, projects::Controller>,
         project_id: ProjectId,
-        branch: BranchId,
-    ) -> Result<(), Error> {
+    ) -> Result<Vec<DanglingReference>, Error> {
         let project = projects.get(project_id)?;
         VirtualBranchActions
-            .integrate_upstream_commits(&project, branch)
-            .await?;
-        emit_vbranches(&windows, project_id).await;
-        Ok(())
+            .check_repo_linkage(&project)
+            .await
+            .map_err(Into::into)
     }
 
     #[tauri::command(async)]
     #[instrument(skip(projects), err(Debug))]
-    pub async fn get_base_branch_data(
+    pub async fn diagnose_project(
         projects: State<'_, projects::Controller>,
         project_id: ProjectId,
-    ) -> Result<Option<BaseBranch>, Error> {
+    ) -> Result<ProjectDiagnostics, Error> {
         let project = projects.get(project_id)?;
-        if let Ok(base_branch) = VirtualBranchActions::get_base_branch_data(&project).await {
-            Ok(Some(base_branch))
-        } else {
-            Ok(None)
-        }
+        VirtualBranchActions
+            .diagnose_project(&project)
+            .await
+            .map_err(Into::into)
     }
 
     #[tauri::command(async)]
     #[instrument(skip(projects, windows), err(Debug))]
-    pub async fn set_base_branch(
+    pub async fn repair_project(
         windows: State<'_, WindowState>,
         projects: State<'_, projects::Controller>,
         project_id: ProjectId,
-        branch: &str,
-        push_remote: Option<&str>, // optional different name of a remote to push to (defaults to same as the branch)
-    ) -> Result<BaseBranch, Error> {
+    ) -> Result<ProjectRepair, Error> {
         let project = projects.get(project_id)?;
-        let branch_name = format!("refs/remotes/{}", branch)
-            .parse()
-            .context("Invalid branch name")?;
-        let base_branch = VirtualBranchActions
-            .set_base_branch(&project, &branch_name)
-            .await?;
-
-        // if they also sent a different push remote, set that too
-        if let Some(push_remote) = push_remote {
-            VirtualBranchActions
-                .set_target_push_remote(&project, push_remote)
-                .await?;
-        }
+        let repair = VirtualBranchActions.repair_project(&project).await?;
         emit_vbranches(&windows, project_id).await;
-        Ok(base_branch)
+        Ok(repair)
     }
 
     #[tauri::command(async)]
-    #[instrument(skip(projects, windows), err(Debug))]
-    pub async fn update_base_branch(
-        windows: State<'_, WindowState>,
+    #[instrument(skip(projects), err(Debug))]
+    pub async fn commits_at_risk(
         projects: State<'_, projects::Controller>,
         project_id: ProjectId,
-    ) -> Result<Vec<ReferenceName>, Error> {
+        branch_id: BranchId,
+        operation: RiskyOperation,
+    ) -> Result<Vec<CommitAtRisk>, Error> {
         let project = projects.get(project_id)?;
-        let unapplied_branches = VirtualBranchActions.update_base_branch(&project).await?;
-        emit_vbranches(&windows, project_id).await;
-        Ok(unapplied_branches)
+        VirtualBranchActions
+            .commits_at_risk(&project, branch_id, operation)
+            .await
+            .map_err(Into::into)
     }
 
     #[tauri::command(async)]
-    #[instrument(skip(projects, windows), err(Debug))]
-    pub async fn update_virtual_branch(
-        windows: State<'_, WindowState>,
+    #[instrument(skip(projects), err(Debug))]
+    pub async fn can_fast_forward(
         projects: State<'_, projects::Controller>,
         project_id: ProjectId,
-        branch: BranchUpdateRequest,
-    ) -> Result<(), Error> {
+        branch_id: BranchId,
+    ) -> Result<FastForward, Error> {
         let project = projects.get(project_id)?;
         VirtualBranchActions
-            .update_virtual_branch(&project, branch)
-            .await?;
-
-        emit_vbranches(&windows, project_id).await;
-        Ok(())
+            .can_fast_forward(&project, branch_id)
+            .await
+            .map_err(Into::into)
     }
 
     #[tauri::command(async)]
-    #[instrument(skip(projects, windows), err(Debug))]
-    pub async fn delete_virtual_branch(
-        windows: State<'_, WindowState>,
+    #[instrument(skip(projects), err(Debug))]
+    pub async fn can_unapply(
         projects: State<'_, projects::Controller>,
         project_id: ProjectId,
         branch_id: BranchId,
-    ) -> Result<(), Error> {
+    ) -> Result<UnapplyReport, Error> {
         let project = projects.get(project_id)?;
         VirtualBranchActions
-            .delete_virtual_branch(&project, branch_id)
-            .await?;
-        emit_vbranches(&windows, project_id).await;
-        Ok(())
+            .can_unapply(&project, branch_id)
+            .await
+            .map_err(Into::into)
     }
 
     #[tauri::command(async)]
-    #[instrument(skip(projects, windows), err(Debug))]
-    pub async fn convert_to_real_branch(
-        windows: State<'_, WindowState>,
+    #[instrument(skip(projects), err(Debug))]
+    pub async fn list_workspaces(
         projects: State<'_, projects::Controller>,
         project_id: ProjectId,
-        branch: BranchId,
-        name_conflict_resolution: NameConflictResolution,
-    ) -> Result<(), Error> {
+    ) -> Result<Vec<Workspace>, Error> {
         let project = projects.get(project_id)?;
         VirtualBranchActions
-            .convert_to_real_branch(&project, branch, name_conflict_resolution)
-            .await?;
-        emit_vbranches(&windows, project_id).await;
-        Ok(())
+            .list_workspaces(&project)
+            .await
+            .map_err(Into::into)
     }
 
     #[tauri::command(async)]
-    #[instrument(skip(projects, windows), err(Debug))]
-    pub async fn unapply_ownership(
-        windows: State<'_, WindowState>,
+    #[instrument(skip(projects), err(Debug))]
+    pub async fn save_workspace(
         projects: State<'_, projects::Controller>,
         project_id: ProjectId,
-        ownership: BranchOwnershipClaims,
-    ) -> Result<(), Error> {
+        name: &str,
+    ) -> Result<Workspace, Error> {
         let project = projects.get(project_id)?;
         VirtualBranchActions
-            .unapply_ownership(&project, &ownership)
-            .await?;
-        emit_vbranches(&windows, project_id).await;
-        Ok(())
+            .save_workspace(&project, name)
+            .await
+            .map_err(Into::into)
     }
 
     #[tauri::command(async)]
-    #[instrument(skip(projects, windows), err(Debug))]
-    pub async fn reset_files(
-        windows: State<'_, WindowState>,
+    #[instrument(skip(projects), err(Debug))]
+    pub async fn workspace_conflicts(
         projects: State<'_, projects::Controller>,
         project_id: ProjectId,
-        files: &str,
-    ) -> Result<(), Error> {
+        name: &str,
+    ) -> Result<Vec<WorkspaceConflict>, Error> {
         let project = projects.get(project_id)?;
-        // convert files to Vec<String>
-        let files = files
-            .split('\n')
-            .map(std::string::ToString::to_string)
-            .collect::<Vec<String>>();
-        VirtualBranchActions.reset_files(&project, &files).await?;
-        emit_vbranches(&windows, project_id).await;
-        Ok(())
+        VirtualBranchActions
+            .workspace_conflicts(&project, name)
+            .await
+            .map_err(Into::into)
     }
 
     #[tauri::command(async)]
     #[instrument(skip(projects, windows), err(Debug))]
-    pub async fn push_virtual_branch(
+    pub async fn switch_workspace(
         windows: State<'_, WindowState>,
         projects: State<'_, projects::Controller>,
         project_id: ProjectId,
-        branch_id: BranchId,
-        with_force: bool,
-    ) -> Result<(), Error> {
+        name: &str,
+    ) -> Result<WorkspaceSwitch, Error> {
         let project = projects.get(project_id)?;
-        VirtualBranchActions
-            .push_virtual_branch(&project, branch_id, with_force, Some(Some(branch_id)))
-            .await
-            .map_err(|err| err.context(Code::Unknown))?;
+        let switch = VirtualBranchActions
+            .switch_workspace(&project, name)
+            .await?;
         emit_vbranches(&windows, project_id).await;
-        Ok(())
+        Ok(switch)
     }
 
     #[tauri::command(async)]
     #[instrument(skip(projects), err(Debug))]
-    pub async fn can_apply_remote_branch(
+    pub async fn detect_base_history_rewrite(
         projects: State<'_, projects::Controller>,
         project_id: ProjectId,
-        branch: RemoteRefname,
-    ) -> Result<bool, Error> {
+    ) -> Result<Option<BaseHistoryRewrite>, Error> {
         let project = projects.get(project_id)?;
-        Ok(VirtualBranchActions
-            .can_apply_remote_branch(&project, &branch)
-            .await?)
+        VirtualBranchActions
+            .detect_base_history_rewrite(&project)
+            .await
+            .map_err(Into::into)
     }
 
     #[tauri::command(async)]
     #[instrument(skip(projects), err(Debug))]
-    pub async fn list_remote_commit_files(
+    pub async fn apply_blast_radius(
         projects: State<'_, projects::Controller>,
         project_id: ProjectId,
-        commit_oid: String,
-    ) -> Result<Vec<RemoteBranchFile>, Error> {
+        branch_id: BranchId,
+    ) -> Result<Vec<TouchedFile>, Error> {
         let project = projects.get(project_id)?;
-        let commit_oid = git2::Oid::from_str(&commit_oid).map_err(|e| anyhow!(e))?;
         VirtualBranchActions
-            .list_remote_commit_files(&project, commit_oid)
+            .apply_blast_radius(&project, branch_id)
             .await
             .map_err(Into::into)
     }
@@ -413,6 +1046,43 @@ pub mod commands {
         Ok(())
     }
 
+    #[tauri::command(async)]
+    #[instrument(skip(projects, windows), err(Debug))]
+    pub async fn set_branch_parent(
+        windows: State<'_, WindowState>,
+        projects: State<'_, projects::Controller>,
+        project_id: ProjectId,
+        branch_id: BranchId,
+        parent_id: Option<BranchId>,
+    ) -> Result<(), Error> {
+        let project = projects.get(project_id)?;
+        VirtualBranchActions
+            .set_branch_parent(&project, branch_id, parent_id)
+            .await?;
+        emit_vbranches(&windows, project_id).await;
+        Ok(())
+    }
+
+    #[tauri::command(async)]
+    #[instrument(skip(projects, windows), err(Debug))]
+    pub async fn reparent_commit(
+        windows: State<'_, WindowState>,
+        projects: State<'_, projects::Controller>,
+        project_id: ProjectId,
+        branch_id: BranchId,
+        commit_oid: String,
+        new_parent: String,
+    ) -> Result<(), Error> {
+        let project = projects.get(project_id)?;
+        let commit_oid = git2::Oid::from_str(&commit_oid).map_err(|e| anyhow!(e))?;
+        let new_parent = git2::Oid::from_str(&new_parent).map_err(|e| anyhow!(e))?;
+        VirtualBranchActions
+            .reparent_commit(&project, branch_id, commit_oid, new_parent)
+            .await?;
+        emit_vbranches(&windows, project_id).await;
+        Ok(())
+    }
+
     #[tauri::command(async)]
     #[instrument(skip(projects), err(Debug))]
     pub async fn list_remote_branches(
@@ -457,18 +1127,47 @@ pub mod commands {
     }
 
     #[tauri::command(async)]
-    #[instrument(skip(projects), err(Debug))]
+    #[instrument(skip(projects, windows), err(Debug))]
+    pub async fn squash_branch_commits(
+        windows: State<'_, WindowState>,
+        projects: State<'_, projects::Controller>,
+        project_id: ProjectId,
+        branch_id: BranchId,
+        commit_oids: Vec<String>,
+        message: Option<String>,
+    ) -> Result<String, Error> {
+        let project = projects.get(project_id)?;
+        let commit_oids = commit_oids
+            .iter()
+            .map(|oid| git2::Oid::from_str(oid))
+            .collect::<Result<Vec<_>, _>>()
+            .map_err(|e| anyhow!(e))?;
+        let oid = VirtualBranchActions
+            .squash_commits(&project, branch_id, commit_oids, message.as_deref())
+            .await?;
+        emit_vbranches(&windows, project_id).await;
+        Ok(oid.to_string())
+    }
+
+    #[tauri::command(async)]
+    #[instrument(skip(projects, timers), err(Debug))]
     pub async fn fetch_from_remotes(
         projects: State<'_, projects::Controller>,
+        timers: State<'_, WatcherTimers>,
         project_id: ProjectId,
         action: Option<String>,
+        depth: Option<FetchDepth>,
     ) -> Result<BaseBranch, Error> {
         let project = projects.get(project_id)?;
+        if action.as_deref() == Some("auto") {
+            timers.auto_fetch_started(project_id);
+        }
 
         let project_data_last_fetched = VirtualBranchActions
             .fetch_from_remotes(
                 &project,
                 Some(action.unwrap_or_else(|| "unknown".to_string())),
+                depth,
             )
             .await?;
 
@@ -525,6 +1224,50 @@ pub mod commands {
         Ok(())
     }
 
+    #[tauri::command(async)]
+    #[instrument(skip(projects), err(Debug))]
+    pub async fn list_conflicts(
+        projects: State<'_, projects::Controller>,
+        project_id: ProjectId,
+    ) -> Result<Vec<ConflictedFile>, Error> {
+        let project = projects.get(project_id)?;
+        Ok(VirtualBranchActions.list_conflicts(&project).await?)
+    }
+
+    #[tauri::command(async)]
+    #[instrument(skip(projects, windows), err(Debug))]
+    pub async fn resolve_conflict(
+        windows: State<'_, WindowState>,
+        projects: State<'_, projects::Controller>,
+        project_id: ProjectId,
+        path: &str,
+        resolution: ConflictResolution,
+    ) -> Result<(), Error> {
+        let project = projects.get(project_id)?;
+        VirtualBranchActions
+            .resolve_conflict(&project, path, resolution)
+            .await?;
+        emit_vbranches(&windows, project_id).await;
+        Ok(())
+    }
+
+    #[tauri::command(async)]
+    #[instrument(skip(projects, windows), err(Debug))]
+    pub async fn resolve_conflicts(
+        windows: State<'_, WindowState>,
+        projects: State<'_, projects::Controller>,
+        project_id: ProjectId,
+        resolutions: HashMap<String, ConflictResolution>,
+    ) -> Result<(), Error> {
+        let project = projects.get(project_id)?;
+        let resolutions: Vec<_> = resolutions.into_iter().collect();
+        VirtualBranchActions
+            .resolve_conflicts(&project, &resolutions)
+            .await?;
+        emit_vbranches(&windows, project_id).await;
+        Ok(())
+    }
+
     async fn emit_vbranches(windows: &WindowState, project_id: projects::ProjectId) {
         if let Err(error) = windows
             .post(gitbutler_watcher::Action::CalculateVirtualBranches(
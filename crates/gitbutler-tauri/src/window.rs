@@ -50,6 +50,37 @@ pub(super) mod state {
                         payload: serde_json::json!(virtual_branches),
                         project_id,
                     },
+                    Change::IntegrationReset { project_id } => ChangeForFrontend {
+                        name: format!("project://{}/git/integration-reset", project_id),
+                        payload: serde_json::json!({}),
+                        project_id,
+                    },
+                    Change::HeadOnIntegration { project_id } => ChangeForFrontend {
+                        name: format!("project://{}/git/head-on-integration", project_id),
+                        payload: serde_json::json!({}),
+                        project_id,
+                    },
+                    Change::SyncProgress {
+                        project_id,
+                        progress,
+                    } => ChangeForFrontend {
+                        name: format!("project://{}/sync-progress", project_id),
+                        payload: serde_json::json!({
+                            "objectsCounted": progress.objects_counted,
+                            "objectsSent": progress.objects_sent,
+                            "bytesTransferred": progress.bytes_transferred,
+                            "done": progress.done,
+                        }),
+                        project_id,
+                    },
+                    Change::BranchNeedsRebase {
+                        project_id,
+                        branch_name,
+                    } => ChangeForFrontend {
+                        name: format!("project://{}/branch-needs-rebase", project_id),
+                        payload: serde_json::json!({ "branchName": branch_name }),
+                        project_id,
+                    },
                 }
             }
         }
@@ -102,16 +133,37 @@ pub(super) mod state {
         let projects = app.state::<projects::Controller>().inner().clone();
         let users = app.state::<users::Controller>().inner().clone();
         let vbranches = gitbutler_branch_actions::VirtualBranchActions;
+        let recalculation_limit = app
+            .state::<gitbutler_watcher::RecalculationLimit>()
+            .inner()
+            .clone();
+        let recent_events = app
+            .state::<gitbutler_watcher::RecentEvents>()
+            .inner()
+            .clone();
+        let timers = app
+            .state::<gitbutler_watcher::WatcherTimers>()
+            .inner()
+            .clone();
+        let sync_scheduler = app
+            .state::<gitbutler_watcher::SyncScheduler>()
+            .inner()
+            .clone();
 
         Ok(gitbutler_watcher::Handler::new(
             projects,
             users,
             vbranches,
+            gitbutler_watcher::DEFAULT_PROJECT_FILES_CHANGE_WINDOW,
             {
                 let app = app.clone();
                 move |change| ChangeForFrontend::from(change).send(&app)
             },
-        ))
+        )
+        .with_recalculation_limit(recalculation_limit)
+        .with_recent_events(recent_events)
+        .with_timers(timers)
+        .with_sync_scheduler(sync_scheduler))
     }
 
     impl WindowState {
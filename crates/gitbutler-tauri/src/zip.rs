@@ -4,6 +4,7 @@ pub mod commands {
     use gitbutler_error::error;
     use gitbutler_error::error::Code;
     use gitbutler_feedback::Archival;
+    use gitbutler_project::ProjectId;
     use std::path::PathBuf;
     use tauri::State;
     use tracing::instrument;
@@ -36,9 +37,54 @@ pub mod commands {
         archival.data_archive(project_id).map_err(Into::into)
     }
 
+    #[tauri::command(async)]
+    #[instrument(skip(archival), err(Debug))]
+    pub async fn archive_project_state(
+        archival: State<'_, Archival>,
+        project_id: ProjectId,
+        signing_key: Option<PathBuf>,
+    ) -> Result<PathBuf, Error> {
+        archival
+            .archive_project_state(project_id, signing_key.as_deref())
+            .map_err(Into::into)
+    }
+
+    #[tauri::command(async)]
+    #[instrument(skip(archival), err(Debug))]
+    pub async fn verify_project_state(
+        archival: State<'_, Archival>,
+        archive_path: PathBuf,
+    ) -> Result<Option<String>, Error> {
+        archival
+            .verify_project_state(&archive_path)
+            .map_err(Into::into)
+    }
+
+    #[tauri::command(async)]
+    #[instrument(skip(archival), err(Debug))]
+    pub async fn restore_project_state(
+        archival: State<'_, Archival>,
+        archive_path: PathBuf,
+        target_dir: PathBuf,
+    ) -> Result<(), Error> {
+        archival
+            .restore_project_state(&archive_path, &target_dir)
+            .map_err(Into::into)
+    }
+
     #[tauri::command(async)]
     #[instrument(skip(archival), err(Debug))]
     pub async fn get_logs_archive_path(archival: State<'_, Archival>) -> Result<PathBuf, Error> {
         archival.logs_archive().map_err(Into::into)
     }
+
+    #[tauri::command(async)]
+    #[instrument(skip(archival), err(Debug))]
+    pub async fn tail_logs(
+        archival: State<'_, Archival>,
+        project_id: ProjectId,
+        lines: usize,
+    ) -> Result<Vec<String>, Error> {
+        archival.tail_logs(project_id, lines).map_err(Into::into)
+    }
 }
@@ -0,0 +1,339 @@
+use bstr::ByteSlice;
+use serde::Serialize;
+
+use crate::Url;
+
+/// The hosting service of a remote repository.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+pub enum ForgeType {
+    GitHub,
+    GitLab,
+    Bitbucket,
+    AzureDevOps,
+    Unknown,
+}
+
+impl ForgeType {
+    /// Detect the forge hosting `remote_url`, which may be an SSH or an HTTPS URL.
+    pub fn from_remote_url(remote_url: &str) -> ForgeType {
+        derive_forge_urls(remote_url).forge
+    }
+
+    /// Return the web page of `branch` in the repository whose web page is `base`,
+    /// or `None` if the forge is unknown.
+    pub fn web_url_for_branch(&self, base: &str, branch: &str) -> Option<String> {
+        let (branch_template, _, _) = self.url_templates()?;
+        Some(
+            branch_template
+                .replace("{repo}", base.trim_end_matches('/'))
+                .replace("{branch}", branch),
+        )
+    }
+
+    /// The templates of the web pages of a branch, a commit and a comparison, relative to the `{repo}` page.
+    fn url_templates(&self) -> Option<(&'static str, &'static str, &'static str)> {
+        Some(match self {
+            ForgeType::GitHub => (
+                "{repo}/tree/{branch}",
+                "{repo}/commit/{commit}",
+                "{repo}/compare/{base}...{branch}",
+            ),
+            ForgeType::GitLab => (
+                "{repo}/-/tree/{branch}",
+                "{repo}/-/commit/{commit}",
+                "{repo}/-/compare/{base}...{branch}",
+            ),
+            ForgeType::Bitbucket => (
+                "{repo}/src/{branch}",
+                "{repo}/commits/{commit}",
+                "{repo}/branch/{branch}?dest={base}",
+            ),
+            ForgeType::AzureDevOps => (
+                "{repo}?version=GB{branch}",
+                "{repo}/commit/{commit}",
+                "{repo}/pullrequestcreate?sourceRef={branch}&targetRef={base}",
+            ),
+            ForgeType::Unknown => return None,
+        })
+    }
+}
+
+/// The web URLs of a remote repository on its forge.
+///
+/// Templates contain placeholders to substitute: `{branch}`, `{commit}` and `{base}`, the latter
+/// being the branch to merge into. All URLs are `None` if the forge is unknown.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ForgeUrls {
+    pub forge: ForgeType,
+    /// The web page of the repository.
+    pub repo_url: Option<String>,
+    /// The web page of a branch, with a `{branch}` placeholder.
+    pub branch_url_template: Option<String>,
+    /// The web page of a commit, with a `{commit}` placeholder.
+    pub commit_url_template: Option<String>,
+    /// The page to compare a branch to a base branch or to open a pull request for it,
+    /// with `{branch}` and `{base}` placeholders.
+    pub compare_url_template: Option<String>,
+}
+
+impl ForgeUrls {
+    fn unknown() -> Self {
+        ForgeUrls {
+            forge: ForgeType::Unknown,
+            repo_url: None,
+            branch_url_template: None,
+            commit_url_template: None,
+            compare_url_template: None,
+        }
+    }
+}
+
+/// Detect the forge hosting `remote_url` and derive the web URLs of the repository from it.
+///
+/// Remote URLs that can't be parsed, or that point to an unknown forge, yield [`ForgeType::Unknown`].
+pub fn derive_forge_urls(remote_url: &str) -> ForgeUrls {
+    let Ok(url) = remote_url.parse::<Url>() else {
+        return ForgeUrls::unknown();
+    };
+    let Some(host) = url.host.as_deref() else {
+        return ForgeUrls::unknown();
+    };
+    let Ok(path) = url.path.to_str() else {
+        return ForgeUrls::unknown();
+    };
+    let path = path.trim_matches('/');
+    let path = path.strip_suffix(".git").unwrap_or(path);
+
+    let (forge, repo_url) = if is_domain_or_subdomain(host, "github.com") {
+        (ForgeType::GitHub, format!("https://{host}/{path}"))
+    } else if is_domain_or_subdomain(host, "gitlab.com") || host.starts_with("gitlab.") {
+        // Self-hosted instances are commonly served from a `gitlab.` subdomain.
+        (ForgeType::GitLab, format!("https://{host}/{path}"))
+    } else if is_domain_or_subdomain(host, "bitbucket.org") {
+        (ForgeType::Bitbucket, format!("https://{host}/{path}"))
+    } else if let Some(repo_url) = azure_devops_repo_url(host, path) {
+        (ForgeType::AzureDevOps, repo_url)
+    } else {
+        return ForgeUrls::unknown();
+    };
+
+    let (branch, commit, compare) = forge.url_templates().expect("unknown forges return early");
+    let template = |template: &str| Some(template.replace("{repo}", &repo_url));
+    ForgeUrls {
+        forge,
+        branch_url_template: template(branch),
+        commit_url_template: template(commit),
+        compare_url_template: template(compare),
+        repo_url: Some(repo_url),
+    }
+}
+
+/// Return `true` if `host` is `domain` or one of its subdomains, but not just a host ending with the same text.
+fn is_domain_or_subdomain(host: &str, domain: &str) -> bool {
+    host.strip_suffix(domain)
+        .is_some_and(|prefix| prefix.is_empty() || prefix.ends_with('.'))
+}
+
+/// Azure DevOps uses a different layout for its SSH URLs, `v3/<org>/<project>/<repo>`,
+/// than for its web URLs, `<org>/<project>/_git/<repo>`.
+fn azure_devops_repo_url(host: &str, path: &str) -> Option<String> {
+    let ssh_path = || -> Option<(&str, &str, &str)> {
+        let mut components = path.strip_prefix("v3/")?.splitn(3, '/');
+        Some((components.next()?, components.next()?, components.next()?))
+    };
+    if host == "ssh.dev.azure.com" {
+        let (org, project, repo) = ssh_path()?;
+        Some(format!("https://dev.azure.com/{org}/{project}/_git/{repo}"))
+    } else if host == "vs-ssh.visualstudio.com" {
+        let (org, project, repo) = ssh_path()?;
+        Some(format!(
+            "https://{org}.visualstudio.com/{project}/_git/{repo}"
+        ))
+    } else if host == "dev.azure.com" || host.ends_with(".visualstudio.com") {
+        Some(format!("https://{host}/{path}"))
+    } else {
+        None
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn github() {
+        for remote_url in [
+            "https://github.com/gitbutlerapp/gitbutler.git",
+            "git@github.com:gitbutlerapp/gitbutler.git",
+            "ssh://git@github.com/gitbutlerapp/gitbutler",
+        ] {
+            let urls = derive_forge_urls(remote_url);
+            assert_eq!(urls.forge, ForgeType::GitHub, "{remote_url}");
+            assert_eq!(
+                urls.repo_url.as_deref(),
+                Some("https://github.com/gitbutlerapp/gitbutler"),
+                "{remote_url}"
+            );
+            assert_eq!(
+                urls.branch_url_template.as_deref(),
+                Some("https://github.com/gitbutlerapp/gitbutler/tree/{branch}")
+            );
+            assert_eq!(
+                urls.commit_url_template.as_deref(),
+                Some("https://github.com/gitbutlerapp/gitbutler/commit/{commit}")
+            );
+            assert_eq!(
+                urls.compare_url_template.as_deref(),
+                Some("https://github.com/gitbutlerapp/gitbutler/compare/{base}...{branch}")
+            );
+        }
+    }
+
+    #[test]
+    fn gitlab() {
+        let urls = derive_forge_urls("git@gitlab.com:group/subgroup/project.git");
+        assert_eq!(urls.forge, ForgeType::GitLab);
+        assert_eq!(
+            urls.repo_url.as_deref(),
+            Some("https://gitlab.com/group/subgroup/project")
+        );
+        assert_eq!(
+            urls.branch_url_template.as_deref(),
+            Some("https://gitlab.com/group/subgroup/project/-/tree/{branch}")
+        );
+        assert_eq!(
+            urls.commit_url_template.as_deref(),
+            Some("https://gitlab.com/group/subgroup/project/-/commit/{commit}")
+        );
+        assert_eq!(
+            urls.compare_url_template.as_deref(),
+            Some("https://gitlab.com/group/subgroup/project/-/compare/{base}...{branch}")
+        );
+    }
+
+    #[test]
+    fn self_hosted_gitlab() {
+        for remote_url in [
+            "git@gitlab.example.com:group/subgroup/project.git",
+            "https://gitlab.example.com/group/subgroup/project.git",
+            "ssh://git@gitlab.example.com/group/subgroup/project",
+        ] {
+            assert_eq!(
+                ForgeType::from_remote_url(remote_url),
+                ForgeType::GitLab,
+                "{remote_url}"
+            );
+            assert_eq!(
+                derive_forge_urls(remote_url).repo_url.as_deref(),
+                Some("https://gitlab.example.com/group/subgroup/project"),
+                "{remote_url}"
+            );
+        }
+    }
+
+    #[test]
+    fn web_url_for_branch() {
+        assert_eq!(
+            ForgeType::GitHub.web_url_for_branch("https://github.com/org/repo/", "feature"),
+            Some("https://github.com/org/repo/tree/feature".to_owned())
+        );
+        assert_eq!(
+            ForgeType::GitLab
+                .web_url_for_branch("https://gitlab.example.com/group/sub/project", "feature"),
+            Some("https://gitlab.example.com/group/sub/project/-/tree/feature".to_owned())
+        );
+        assert_eq!(
+            ForgeType::Bitbucket.web_url_for_branch("https://bitbucket.org/team/repo", "feature"),
+            Some("https://bitbucket.org/team/repo/src/feature".to_owned())
+        );
+        assert_eq!(
+            ForgeType::AzureDevOps
+                .web_url_for_branch("https://dev.azure.com/org/project/_git/repo", "feature"),
+            Some("https://dev.azure.com/org/project/_git/repo?version=GBfeature".to_owned())
+        );
+        assert_eq!(
+            ForgeType::Unknown.web_url_for_branch("https://git.example.com/repo", "feature"),
+            None
+        );
+    }
+
+    #[test]
+    fn bitbucket() {
+        let urls = derive_forge_urls("https://user@bitbucket.org/team/repo.git");
+        assert_eq!(urls.forge, ForgeType::Bitbucket);
+        assert_eq!(
+            urls.repo_url.as_deref(),
+            Some("https://bitbucket.org/team/repo")
+        );
+        assert_eq!(
+            urls.branch_url_template.as_deref(),
+            Some("https://bitbucket.org/team/repo/src/{branch}")
+        );
+        assert_eq!(
+            urls.commit_url_template.as_deref(),
+            Some("https://bitbucket.org/team/repo/commits/{commit}")
+        );
+        assert_eq!(
+            urls.compare_url_template.as_deref(),
+            Some("https://bitbucket.org/team/repo/branch/{branch}?dest={base}")
+        );
+    }
+
+    #[test]
+    fn azure_devops() {
+        for remote_url in [
+            "https://dev.azure.com/org/project/_git/repo",
+            "https://org@dev.azure.com/org/project/_git/repo",
+            "git@ssh.dev.azure.com:v3/org/project/repo",
+        ] {
+            let urls = derive_forge_urls(remote_url);
+            assert_eq!(urls.forge, ForgeType::AzureDevOps, "{remote_url}");
+            assert_eq!(
+                urls.repo_url.as_deref(),
+                Some("https://dev.azure.com/org/project/_git/repo"),
+                "{remote_url}"
+            );
+            assert_eq!(
+                urls.branch_url_template.as_deref(),
+                Some("https://dev.azure.com/org/project/_git/repo?version=GB{branch}")
+            );
+            assert_eq!(
+                urls.commit_url_template.as_deref(),
+                Some("https://dev.azure.com/org/project/_git/repo/commit/{commit}")
+            );
+            assert_eq!(
+                urls.compare_url_template.as_deref(),
+                Some("https://dev.azure.com/org/project/_git/repo/pullrequestcreate?sourceRef={branch}&targetRef={base}")
+            );
+        }
+
+        let urls = derive_forge_urls("org@vs-ssh.visualstudio.com:v3/org/project/repo");
+        assert_eq!(urls.forge, ForgeType::AzureDevOps);
+        assert_eq!(
+            urls.repo_url.as_deref(),
+            Some("https://org.visualstudio.com/project/_git/repo")
+        );
+    }
+
+    #[test]
+    fn unknown() {
+        for remote_url in [
+            "https://git.example.com/team/repo.git",
+            "https://notgithub.com/team/repo.git",
+            "https://github.com.evil.com/team/repo.git",
+            "git@evilgitlab.com:team/repo.git",
+            "https://example.gitlab.evil.com/team/repo.git",
+            "https://mybitbucket.org/team/repo.git",
+            "https://notvisualstudio.com/team/repo.git",
+            "file:///path/to/repo",
+            "not a url",
+        ] {
+            assert_eq!(
+                derive_forge_urls(remote_url),
+                ForgeUrls::unknown(),
+                "{remote_url}"
+            );
+        }
+    }
+}
@@ -1,4 +1,5 @@
 mod convert;
+mod forge;
 mod parse;
 mod scheme;
 
@@ -6,6 +7,7 @@ use std::str::FromStr;
 
 use bstr::ByteSlice;
 pub use convert::ConvertError;
+pub use forge::{derive_forge_urls, ForgeType, ForgeUrls};
 // pub use parse::Error as ParseError;
 pub use scheme::Scheme;
 
@@ -1,6 +1,7 @@
 //! Note that these tests *must* be run in their own process, as they rely on having a deterministic
 //! credential store. Due to its global nature, tests cannot run in parallel
 //! (or mixed with parallel tests that set their own credential store)
+use gitbutler_secret::credential_source::{self, CredentialSource};
 use gitbutler_secret::secret;
 use gitbutler_secret::Sensitive;
 use serial_test::serial;
@@ -51,6 +52,114 @@ fn store_empty_equals_deletion() -> anyhow::Result<()> {
     Ok(())
 }
 
+#[test]
+#[serial]
+fn scoped_secrets_are_listed_by_name() -> anyhow::Result<()> {
+    credentials::setup();
+    for ns in all_namespaces() {
+        secret::persist_scoped("project", "token", &Sensitive("one".into()), *ns)?;
+        secret::persist_scoped("project", "passphrase", &Sensitive("two".into()), *ns)?;
+        secret::persist_scoped("project", "token", &Sensitive("three".into()), *ns)?;
+        secret::persist_scoped("other", "token", &Sensitive("four".into()), *ns)?;
+
+        assert_eq!(
+            secret::list_scoped("project", *ns)?,
+            ["token", "passphrase"]
+        );
+        assert_eq!(secret::list_scoped("other", *ns)?, ["token"]);
+        assert_eq!(
+            secret::retrieve_scoped("project", "token", *ns)?.map(|s| s.0),
+            Some("three".into())
+        );
+    }
+    Ok(())
+}
+
+#[test]
+#[serial]
+fn deleting_scoped_secrets_removes_them_from_the_store() -> anyhow::Result<()> {
+    credentials::setup();
+    for ns in all_namespaces() {
+        secret::persist_scoped("project", "token", &Sensitive("one".into()), *ns)?;
+        secret::persist_scoped("project", "passphrase", &Sensitive("two".into()), *ns)?;
+
+        secret::delete_scoped("project", "token", *ns)?;
+        assert_eq!(secret::list_scoped("project", *ns)?, ["passphrase"]);
+        assert_eq!(
+            secret::retrieve_scoped("project", "token", *ns)?.map(|s| s.0),
+            None
+        );
+
+        secret::persist_scoped("project", "passphrase", &Sensitive("".into()), *ns)?;
+        assert!(secret::list_scoped("project", *ns)?.is_empty());
+        assert_eq!(credentials::count(), 0, "nothing is left behind");
+
+        secret::delete_scoped("project", "unknown", *ns)?;
+    }
+    Ok(())
+}
+
+#[test]
+#[serial]
+fn credential_sources_are_stored_per_remote() -> anyhow::Result<()> {
+    credentials::setup();
+    let token = Sensitive("token".to_owned());
+    credential_source::persist("project", "origin", &CredentialSource::Token, Some(&token))?;
+    credential_source::persist(
+        "project",
+        "corp",
+        &CredentialSource::SshKey("/keys/id_ed25519".into()),
+        None,
+    )?;
+
+    assert_eq!(
+        credential_source::retrieve("project", "origin")?,
+        Some(CredentialSource::Token)
+    );
+    assert_eq!(
+        credential_source::retrieve_token("project", "origin")?.map(|s| s.0),
+        Some("token".into())
+    );
+    assert_eq!(
+        credential_source::retrieve("project", "corp")?,
+        Some(CredentialSource::SshKey("/keys/id_ed25519".into()))
+    );
+    assert_eq!(credential_source::retrieve("project", "unknown")?, None);
+    assert_eq!(credential_source::retrieve("other", "origin")?, None);
+
+    credential_source::persist("project", "origin", &CredentialSource::Token, None)?;
+    assert_eq!(
+        credential_source::retrieve_token("project", "origin")?.map(|s| s.0),
+        Some("token".into()),
+        "the stored token is kept if none is given"
+    );
+
+    credential_source::persist("project", "origin", &CredentialSource::SshAgent, None)?;
+    assert_eq!(
+        credential_source::retrieve_token("project", "origin")?.map(|s| s.0),
+        None,
+        "the token is deleted once it's not used anymore"
+    );
+
+    credential_source::delete("project", "origin")?;
+    credential_source::delete("project", "corp")?;
+    assert_eq!(credentials::count(), 0, "nothing is left behind");
+    Ok(())
+}
+
+#[test]
+#[serial]
+fn token_credential_source_needs_a_token() {
+    credentials::setup();
+    assert!(
+        credential_source::persist("project", "origin", &CredentialSource::Token, None).is_err()
+    );
+    assert_eq!(
+        credential_source::retrieve("project", "origin").unwrap(),
+        None
+    );
+}
+
 fn all_namespaces() -> &'static [secret::Namespace] {
     &[secret::Namespace::Global, secret::Namespace::BuildKind]
 }
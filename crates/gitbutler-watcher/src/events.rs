@@ -3,6 +3,7 @@ use std::path::PathBuf;
 
 use gitbutler_branch_actions::VirtualBranches;
 use gitbutler_project::ProjectId;
+use gitbutler_sync::cloud::SyncProgress;
 
 /// An event for internal use, as merge between [super::file_monitor::Event] and [Action].
 #[derive(Debug)]
@@ -15,6 +16,11 @@ pub(super) enum InternalEvent {
     ProjectFilesChange(ProjectId, Vec<PathBuf>),
     // Triggered on change in the `.git/gitbutler` directory
     GitButlerOplogChange(ProjectId),
+
+    // Triggers of a sync with GitButler's servers
+    BranchPushed(ProjectId),
+    SyncTimer(ProjectId),
+    SyncNow(ProjectId),
 }
 
 /// This type captures all operations that can be fed into a watcher that runs in the background.
@@ -24,13 +30,34 @@ pub(super) enum InternalEvent {
 #[allow(missing_docs)]
 pub enum Action {
     CalculateVirtualBranches(ProjectId),
+    /// A branch of the project was pushed to its remote.
+    BranchPushed(ProjectId),
+    /// Sync the project with GitButler's servers right away, independently of its configured trigger.
+    SyncNow(ProjectId),
 }
 
 impl Action {
     /// Return the action's associated project id.
     pub fn project_id(&self) -> ProjectId {
         match self {
-            Action::CalculateVirtualBranches(project_id) => *project_id,
+            Action::CalculateVirtualBranches(project_id)
+            | Action::BranchPushed(project_id)
+            | Action::SyncNow(project_id) => *project_id,
+        }
+    }
+}
+
+impl InternalEvent {
+    /// Return the event's associated project id.
+    pub fn project_id(&self) -> ProjectId {
+        match self {
+            InternalEvent::CalculateVirtualBranches(project_id)
+            | InternalEvent::GitFilesChange(project_id, _)
+            | InternalEvent::ProjectFilesChange(project_id, _)
+            | InternalEvent::GitButlerOplogChange(project_id)
+            | InternalEvent::BranchPushed(project_id)
+            | InternalEvent::SyncTimer(project_id)
+            | InternalEvent::SyncNow(project_id) => *project_id,
         }
     }
 }
@@ -39,6 +66,8 @@ impl From<Action> for InternalEvent {
     fn from(value: Action) -> Self {
         match value {
             Action::CalculateVirtualBranches(v) => InternalEvent::CalculateVirtualBranches(v),
+            Action::BranchPushed(v) => InternalEvent::BranchPushed(v),
+            Action::SyncNow(v) => InternalEvent::SyncNow(v),
         }
     }
 }
@@ -66,6 +95,9 @@ impl Display for InternalEvent {
                 )
             }
             InternalEvent::CalculateVirtualBranches(pid) => write!(f, "VirtualBranch({})", pid),
+            InternalEvent::BranchPushed(pid) => write!(f, "BranchPushed({})", pid),
+            InternalEvent::SyncTimer(pid) => write!(f, "SyncTimer({})", pid),
+            InternalEvent::SyncNow(pid) => write!(f, "SyncNow({})", pid),
         }
     }
 }
@@ -100,4 +132,25 @@ pub enum Change {
         project_id: ProjectId,
         virtual_branches: VirtualBranches,
     },
+    /// `HEAD` moved off the integration branch, which was deleted as GitButler isn't in control anymore.
+    /// It's emitted along with [`Change::GitHead`].
+    IntegrationReset {
+        project_id: ProjectId,
+    },
+    /// `HEAD` moved onto the integration branch from elsewhere, for instance by checking it out in a terminal.
+    /// It's emitted along with [`Change::GitHead`].
+    HeadOnIntegration {
+        project_id: ProjectId,
+    },
+    /// A sync with GitButler's servers advanced.
+    SyncProgress {
+        project_id: ProjectId,
+        progress: SyncProgress,
+    },
+    /// The applied branch conflicts with the updated base branch, which is why the workspace wasn't
+    /// updated to it automatically.
+    BranchNeedsRebase {
+        project_id: ProjectId,
+        branch_name: String,
+    },
 }
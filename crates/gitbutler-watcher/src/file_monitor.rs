@@ -7,6 +7,7 @@ use anyhow::{anyhow, Context, Result};
 use gitbutler_notify_debouncer::{new_debouncer, Debouncer, NoCache};
 use gitbutler_oplog::OPLOG_FILE_NAME;
 use gitbutler_project::ProjectId;
+use gitbutler_repo::GitButlerIgnore;
 use notify::RecommendedWatcher;
 use notify::Watcher;
 use tokio::task;
@@ -19,7 +20,7 @@ use tracing::Level;
 const DEBOUNCE_TIMEOUT: Duration = Duration::from_secs(60);
 
 // The internal rate at which the debouncer will update its state.
-const TICK_RATE: Duration = Duration::from_millis(250);
+pub(super) const TICK_RATE: Duration = Duration::from_millis(250);
 
 // The number of TICK_RATE intervals required of "dead air" (i.e. no new events
 // arriving) before we will automatically flush pending events. This means that
@@ -36,7 +37,7 @@ struct RunError {
     source: anyhow::Error,
 }
 
-/// Listen to interesting filesystem events of files in `path` that are not `.gitignore`d,
+/// Listen to interesting filesystem events of files in `path` that are not `.gitignore`d or `.gitbutlerignore`d,
 /// turn them into [`Events`](Event) which classifies it, and associates it with `project_id`.
 /// These are sent through the passed `out` channel, to indicate either **Git** repository changes
 /// or **ProjectWorktree** changes
@@ -163,6 +164,24 @@ pub fn spawn(
                                 }
                             }
                         }
+                        // Loaded for each batch so changes to it apply right away.
+                        match GitButlerIgnore::load(&worktree_path) {
+                            Ok(gitbutler_ignore) => {
+                                for (file_path, kind) in classified_file_paths.iter_mut() {
+                                    if *kind != FileKind::Project {
+                                        continue;
+                                    }
+                                    if let Ok(relative_path) =
+                                        file_path.strip_prefix(&worktree_path)
+                                    {
+                                        if gitbutler_ignore.is_ignored(relative_path) {
+                                            *kind = FileKind::ProjectIgnored
+                                        }
+                                    }
+                                }
+                            }
+                            Err(err) => tracing::warn!(?err, "failed to read .gitbutlerignore"),
+                        }
                     }
                     let mut oplog_changed = false;
                     let (mut stripped_git_paths, mut worktree_relative_paths) =
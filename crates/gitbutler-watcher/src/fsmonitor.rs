@@ -0,0 +1,136 @@
+use std::path::{Path, PathBuf};
+use std::process::Command;
+use std::time::Duration;
+
+use crate::events::InternalEvent;
+use anyhow::{bail, Context, Result};
+use gitbutler_project::ProjectId;
+use tokio::{sync::mpsc::UnboundedSender, task};
+use tokio_util::sync::CancellationToken;
+
+/// How often the `core.fsmonitor` hook is asked for the files that changed since it was last asked.
+const POLL_INTERVAL: Duration = Duration::from_millis(500);
+
+/// A client of the hook configured as `core.fsmonitor`, speaking version 2 of its protocol.
+///
+/// Git's builtin daemon (`core.fsmonitor = true`) can only be queried by git itself, so only hooks,
+/// like the one backed by watchman, are supported.
+struct FsMonitor {
+    hook: PathBuf,
+    worktree_path: PathBuf,
+    /// The token of the last query, so the next one only returns what changed since.
+    token: String,
+}
+
+impl FsMonitor {
+    /// Return a client for the hook configured in the repository at `worktree_path`, or `None` if there is none.
+    fn discover(worktree_path: &Path) -> Result<Option<Self>> {
+        let repo = gix::open(worktree_path)?;
+        let config = repo.config_snapshot();
+        if config.boolean("core.fsmonitor").is_some() {
+            return Ok(None);
+        }
+        let Some(hook) = config.string("core.fsmonitor") else {
+            return Ok(None);
+        };
+        let hook = gix::path::try_from_bstr(hook.as_ref())
+            .context("the core.fsmonitor hook isn't a valid path")?;
+        let mut monitor = FsMonitor {
+            // Like git, resolve relative paths from the worktree root.
+            hook: worktree_path.join(hook),
+            worktree_path: worktree_path.to_owned(),
+            token: String::new(),
+        };
+        // Without a token, hooks report everything as changed, so the first answer only provides the token.
+        monitor.query()?;
+        Ok(Some(monitor))
+    }
+
+    /// Return the worktree-relative paths that changed since the last query.
+    ///
+    /// If the hook can't tell what changed, nothing is returned as the filesystem watcher still sees all changes.
+    fn query(&mut self) -> Result<Vec<PathBuf>> {
+        let output = Command::new(&self.hook)
+            .arg("2")
+            .arg(&self.token)
+            .current_dir(&self.worktree_path)
+            .output()
+            .with_context(|| format!("failed to run {}", self.hook.display()))?;
+        if !output.status.success() {
+            bail!(
+                "{} failed with {}: {}",
+                self.hook.display(),
+                output.status,
+                String::from_utf8_lossy(&output.stderr)
+            );
+        }
+
+        let mut fields = output.stdout.split(|b| *b == 0).filter(|f| !f.is_empty());
+        let token = fields
+            .next()
+            .context("the fsmonitor hook didn't return a token")?;
+        self.token = String::from_utf8(token.to_owned())?;
+
+        let mut paths = Vec::new();
+        for field in fields {
+            // The trivial response, meaning that everything may have changed.
+            if field == b"/" {
+                return Ok(Vec::new());
+            }
+            let path = gix::path::try_from_byte_slice(field)?;
+            if path.starts_with(".git") {
+                continue;
+            }
+            // Directories are reported with a trailing slash.
+            paths.push(path.components().collect());
+        }
+        paths.sort();
+        paths.dedup();
+        Ok(paths)
+    }
+}
+
+/// Periodically ask the `core.fsmonitor` hook of the repository at `worktree_path` for changed files,
+/// and send them as [`InternalEvent::ProjectFilesChange`] through `out` until `cancellation_token` is cancelled.
+///
+/// Nothing is done if the repository has no such hook.
+pub fn spawn(
+    project_id: ProjectId,
+    worktree_path: &Path,
+    out: UnboundedSender<InternalEvent>,
+    cancellation_token: CancellationToken,
+) -> Result<()> {
+    let Some(mut monitor) = FsMonitor::discover(worktree_path)? else {
+        tracing::warn!(%project_id, "fsmonitor is enabled, but no core.fsmonitor hook is configured");
+        return Ok(());
+    };
+    tokio::spawn(async move {
+        let mut interval = tokio::time::interval(POLL_INTERVAL);
+        loop {
+            tokio::select! {
+                _ = interval.tick() => {}
+                () = cancellation_token.cancelled() => break,
+            }
+            let (returned, result) = task::spawn_blocking(move || {
+                let result = monitor.query();
+                (monitor, result)
+            })
+            .await?;
+            monitor = returned;
+            match result {
+                Ok(paths) if paths.is_empty() => {}
+                Ok(paths) => {
+                    if out
+                        .send(InternalEvent::ProjectFilesChange(project_id, paths))
+                        .is_err()
+                    {
+                        break;
+                    }
+                }
+                Err(err) => tracing::warn!(%project_id, ?err, "failed to query fsmonitor hook"),
+            }
+        }
+        Ok::<_, anyhow::Error>(())
+    });
+    Ok(())
+}
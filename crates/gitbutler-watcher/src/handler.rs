@@ -1,5 +1,7 @@
+use std::collections::HashMap;
 use std::path::PathBuf;
-use std::sync::Arc;
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
 
 use anyhow::{Context, Result};
 use gitbutler_branch_actions::VirtualBranches;
@@ -7,16 +9,21 @@ use gitbutler_command_context::ProjectRepository;
 use gitbutler_error::error::Marker;
 use gitbutler_oplog::{
     entry::{OperationKind, SnapshotDetails},
-    OplogExt,
+    OplogExt, SnapshotExt,
 };
 use gitbutler_project as projects;
-use gitbutler_project::ProjectId;
+use gitbutler_project::{ProjectId, SyncTrigger};
 use gitbutler_reference::{LocalRefname, Refname};
-use gitbutler_sync::cloud::sync_with_gitbutler;
+use gitbutler_sync::cloud::sync_with_gitbutler_with_progress;
+use gitbutler_sync::retry::{retry_transient_staggered, RetryPolicy};
+use gitbutler_sync::scheduler::SyncScheduler;
 use gitbutler_user as users;
 use tracing::instrument;
 
-use super::{events, Change};
+use super::{
+    events, Change, EventRecorder, RecalculationLimit, RecentEvents, RecordedEvent, TimerKind,
+    WatcherTimers,
+};
 
 /// A type that contains enough state to make decisions based on changes in the filesystem, which themselves
 /// may trigger [Changes](Change)
@@ -32,6 +39,30 @@ pub struct Handler {
     users: users::Controller,
     vbranch_controller: gitbutler_branch_actions::VirtualBranchActions,
 
+    /// The head last emitted as [`Change::GitHead`] for each project, shared among all clones
+    /// so that rewriting `HEAD` with the same value doesn't emit the same change again.
+    last_heads: Arc<Mutex<HashMap<ProjectId, String>>>,
+
+    /// Bounds the virtual branch recalculations running at once, possibly shared with handlers of other projects.
+    recalculation_limit: RecalculationLimit,
+
+    /// The events most recently received for each project, for debugging.
+    recent_events: RecentEvents,
+
+    /// The timers that are waiting to elapse for each project, for debugging.
+    timers: WatcherTimers,
+
+    /// Staggers retries of failed syncs, possibly shared with handlers of other projects.
+    sync_scheduler: SyncScheduler,
+
+    /// If set, all received events are written to a file to be replayed later.
+    recorder: Option<EventRecorder>,
+
+    /// How long to wait for more changes to project files after the first one, to handle them all at once.
+    project_files_change_window: Duration,
+    /// The paths of project file changes waiting for the window of the first change to pass, per project.
+    pending_project_files_changes: Arc<Mutex<HashMap<ProjectId, Vec<PathBuf>>>>,
+
     /// A function to send events - decoupled from app-handle for testing purposes.
     #[allow(clippy::type_complexity)]
     send_event: Arc<dyn Fn(Change) -> Result<()> + Send + Sync + 'static>,
@@ -39,27 +70,113 @@ pub struct Handler {
 
 impl Handler {
     /// A constructor whose primary use is the test-suite.
+    ///
+    /// Changes to project files received within `project_files_change_window` of each other are handled as one,
+    /// with `Duration::ZERO` handling each of them on its own.
     #[allow(clippy::too_many_arguments)]
     pub fn new(
         projects: projects::Controller,
         users: users::Controller,
         vbranch_controller: gitbutler_branch_actions::VirtualBranchActions,
+        project_files_change_window: Duration,
         send_event: impl Fn(Change) -> Result<()> + Send + Sync + 'static,
     ) -> Self {
         Handler {
             projects,
             users,
             vbranch_controller,
+            last_heads: Default::default(),
+            recalculation_limit: Default::default(),
+            recent_events: Default::default(),
+            timers: Default::default(),
+            sync_scheduler: Default::default(),
+            recorder: None,
+            project_files_change_window,
+            pending_project_files_changes: Default::default(),
             send_event: Arc::new(send_event),
         }
     }
 
+    /// Use `limit` to bound concurrent recalculations, typically shared with the handlers of all other projects.
+    pub fn with_recalculation_limit(mut self, limit: RecalculationLimit) -> Self {
+        self.recalculation_limit = limit;
+        self
+    }
+
+    /// Record received events in `recent_events`, which may be shared with the handlers of other projects
+    /// and also determines how many events are kept.
+    pub fn with_recent_events(mut self, recent_events: RecentEvents) -> Self {
+        self.recent_events = recent_events;
+        self
+    }
+
+    /// Keep track of pending timers in `timers`, which may be shared with the handlers of other projects.
+    pub fn with_timers(mut self, timers: WatcherTimers) -> Self {
+        self.timers = timers;
+        self
+    }
+
+    /// Stagger retries of failed syncs with `scheduler`, typically shared with the handlers of all other projects.
+    pub fn with_sync_scheduler(mut self, scheduler: SyncScheduler) -> Self {
+        self.sync_scheduler = scheduler;
+        self
+    }
+
+    /// Write all received events to `recorder`, to be able to [replay](Self::replay()) them later.
+    pub fn with_event_recorder(mut self, recorder: EventRecorder) -> Self {
+        self.recorder = Some(recorder);
+        self
+    }
+
+    /// Return the events most recently received for `project_id`, oldest first.
+    pub fn recent_internal_events(&self, project_id: ProjectId) -> Vec<RecordedEvent> {
+        self.recent_events.list(project_id, None)
+    }
+
+    /// Return the timers that are waiting to elapse, shared with all clones of this handler.
+    pub(super) fn timers(&self) -> WatcherTimers {
+        self.timers.clone()
+    }
+
+    /// Return `true` if changed files of `project_id` should also be obtained from git's fsmonitor.
+    pub(super) fn uses_fsmonitor(&self, project_id: ProjectId) -> bool {
+        self.projects
+            .get(project_id)
+            .map_or(false, |project| project.use_fsmonitor)
+    }
+
+    /// Remember `event` as received, before it's handled.
+    pub(super) fn record_event(&self, event: &events::InternalEvent) {
+        self.recent_events.record(event);
+        if let Some(recorder) = &self.recorder {
+            if let Err(err) = recorder.record(event) {
+                tracing::warn!(?err, "failed to record event");
+            }
+        }
+    }
+
+    /// Handle `events` one after another as if they were received for `project_id`, waiting for each to be
+    /// handled before the next, which makes the [changes](Change) they cause deterministic.
+    /// Like in the watcher, failing to handle an event doesn't stop the ones after it from being handled.
+    pub async fn replay(
+        &self,
+        project_id: ProjectId,
+        events: impl IntoIterator<Item = RecordedEvent>,
+    ) {
+        for event in events {
+            self.handle(event.into_internal(project_id)).await.ok();
+        }
+    }
+
     /// Handle the events that come in from the filesystem, or the public API.
-    #[instrument(skip(self), fields(event = %event), err(Debug))]
+    #[instrument(skip(self), fields(event = %event, project_id = %event.project_id()), err(Debug))]
     pub(super) async fn handle(&self, event: events::InternalEvent) -> Result<()> {
         match event {
-            events::InternalEvent::ProjectFilesChange(project_id, path) => {
-                self.recalculate_everything(path, project_id).await
+            events::InternalEvent::ProjectFilesChange(project_id, paths) => {
+                match self.coalesce_project_files_change(project_id, paths).await {
+                    Some(paths) => self.recalculate_everything(paths, project_id).await,
+                    None => Ok(()),
+                }
             }
 
             events::InternalEvent::GitFilesChange(project_id, paths) => self
@@ -72,6 +189,21 @@ impl Handler {
                 .await
                 .context("failed to handle gitbutler oplog change event"),
 
+            events::InternalEvent::BranchPushed(project_id) => self
+                .sync(project_id, Some(SyncTrigger::Push))
+                .await
+                .context("failed to handle branch pushed event"),
+
+            events::InternalEvent::SyncTimer(project_id) => self
+                .sync(project_id, Some(SyncTrigger::Timer))
+                .await
+                .context("failed to handle sync timer event"),
+
+            events::InternalEvent::SyncNow(project_id) => self
+                .sync(project_id, None)
+                .await
+                .context("failed to handle sync event"),
+
             // This is only produced at the end of mutating Tauri commands to trigger a fresh state being served to the UI.
             events::InternalEvent::CalculateVirtualBranches(project_id) => self
                 .calculate_virtual_branches(project_id)
@@ -86,12 +218,13 @@ impl Handler {
         (self.send_event)(event).context("failed to send event")
     }
 
-    #[instrument(skip(self, project_id))]
+    #[instrument(skip(self, project_id), fields(%project_id))]
     async fn calculate_virtual_branches(&self, project_id: ProjectId) -> Result<()> {
         let project = self
             .projects
             .get(project_id)
             .context("failed to get project")?;
+        let _permit = self.recalculation_limit.acquire(project_id).await;
         match self
             .vbranch_controller
             .list_virtual_branches(&project)
@@ -116,24 +249,77 @@ impl Handler {
         }
     }
 
-    #[instrument(skip(self, paths, project_id), fields(paths = paths.len()))]
+    /// Add `paths` to the changes of `project_id` waiting to be handled, and return all of them once the window
+    /// of the first change passed if this is the first change. Return `None` if another change is already waiting.
+    #[instrument(skip(self, project_id, paths), fields(%project_id, paths = paths.len()))]
+    async fn coalesce_project_files_change(
+        &self,
+        project_id: ProjectId,
+        paths: Vec<PathBuf>,
+    ) -> Option<Vec<PathBuf>> {
+        if self.project_files_change_window.is_zero() {
+            return Some(paths);
+        }
+        {
+            let mut pending = self
+                .pending_project_files_changes
+                .lock()
+                .unwrap_or_else(|poisoned| poisoned.into_inner());
+            if let Some(pending_paths) = pending.get_mut(&project_id) {
+                pending_paths.extend(paths);
+                return None;
+            }
+            pending.insert(project_id, paths);
+        }
+
+        self.timers.set(
+            project_id,
+            TimerKind::ProjectFilesChangeWindow,
+            tokio::time::Instant::now() + self.project_files_change_window,
+        );
+        tokio::time::sleep(self.project_files_change_window).await;
+        self.timers
+            .clear(project_id, TimerKind::ProjectFilesChangeWindow);
+        let mut paths = self
+            .pending_project_files_changes
+            .lock()
+            .unwrap_or_else(|poisoned| poisoned.into_inner())
+            .remove(&project_id)
+            .unwrap_or_default();
+        paths.sort();
+        paths.dedup();
+        Some(paths)
+    }
+
+    #[instrument(skip(self, paths, project_id), fields(%project_id, paths = paths.len()))]
     async fn recalculate_everything(
         &self,
         paths: Vec<PathBuf>,
         project_id: ProjectId,
     ) -> Result<()> {
-        self.maybe_create_snapshot(project_id).ok();
+        self.maybe_create_snapshot(project_id, &paths).ok();
         self.calculate_virtual_branches(project_id).await?;
         Ok(())
     }
 
-    fn maybe_create_snapshot(&self, project_id: ProjectId) -> anyhow::Result<()> {
+    #[instrument(skip(self, project_id, paths), fields(%project_id))]
+    fn maybe_create_snapshot(
+        &self,
+        project_id: ProjectId,
+        paths: &[PathBuf],
+    ) -> anyhow::Result<()> {
         let project = self
             .projects
             .get(project_id)
             .context("failed to get project")?;
-        if project
-            .should_auto_snapshot(std::time::Duration::from_secs(300))
+        if project.is_read_only() {
+            return Ok(());
+        }
+        if let Some(trigger) = project.snapshot_trigger(paths) {
+            let mut guard = project.exclusive_worktree_access();
+            project.snapshot_triggered_by(trigger, guard.write_permission())?;
+        } else if project
+            .should_auto_snapshot(project.auto_snapshot_interval())
             .unwrap_or_default()
         {
             let mut guard = project.exclusive_worktree_access();
@@ -145,6 +331,7 @@ impl Handler {
         Ok(())
     }
 
+    #[instrument(skip(self, paths, project_id), fields(%project_id, paths = paths.len()))]
     pub async fn git_files_change(&self, paths: Vec<PathBuf>, project_id: ProjectId) -> Result<()> {
         let project = self
             .projects
@@ -162,6 +349,9 @@ impl Handler {
             match file_name {
                 "FETCH_HEAD" => {
                     self.emit_app_event(Change::GitFetch(project_id))?;
+                    if let Err(err) = self.auto_update_base_branch(project_id).await {
+                        tracing::warn!(%project_id, ?err, "failed to update the base branch automatically");
+                    }
                 }
                 "logs/HEAD" => {
                     self.emit_app_event(Change::GitActivity(project.id))?;
@@ -173,18 +363,28 @@ impl Handler {
                         .head()
                         .context("failed to get head")?;
                     let head_ref_name = head_ref.name().context("failed to get head name")?;
-                    if head_ref_name != "refs/heads/gitbutler/integration" {
+                    // An observed project keeps its integration branch for when GitButler may take over again.
+                    if head_ref_name != "refs/heads/gitbutler/integration"
+                        && !project.is_read_only()
+                    {
                         let mut integration_reference = project_repository.repo().find_reference(
                             &Refname::from(LocalRefname::new("gitbutler/integration", None))
                                 .to_string(),
                         )?;
                         integration_reference.delete()?;
+                        self.emit_app_event(Change::IntegrationReset { project_id })?;
                     }
                     if let Some(head) = head_ref.name() {
-                        self.emit_app_event(Change::GitHead {
-                            project_id,
-                            head: head.to_string(),
-                        })?;
+                        let last_head = self.replace_last_head(project_id, head);
+                        if last_head.as_deref() != Some(head) {
+                            if last_head.is_some() && head == "refs/heads/gitbutler/integration" {
+                                self.emit_app_event(Change::HeadOnIntegration { project_id })?;
+                            }
+                            self.emit_app_event(Change::GitHead {
+                                project_id,
+                                head: head.to_string(),
+                            })?;
+                        }
                     }
                 }
                 _ => {}
@@ -193,19 +393,99 @@ impl Handler {
         Ok(())
     }
 
-    /// Invoked whenever there's a new oplog entry.
-    /// If synchronizing with GitButler's servers is enabled it will push Oplog refs
+    /// Remember `head` as the last head of `project_id`, and return the last head it replaces, if any.
+    fn replace_last_head(&self, project_id: ProjectId, head: &str) -> Option<String> {
+        self.last_heads
+            .lock()
+            .unwrap_or_else(|poisoned| poisoned.into_inner())
+            .insert(project_id, head.to_owned())
+    }
+
+    /// Rebase applied branches onto the fetched base branch if the project opted into it,
+    /// and flag the branches that conflict with it.
+    #[instrument(skip(self, project_id), fields(%project_id))]
+    async fn auto_update_base_branch(&self, project_id: ProjectId) -> Result<()> {
+        let project = self
+            .projects
+            .get(project_id)
+            .context("failed to get project")?;
+        if !project.auto_rebase_on_base_update {
+            return Ok(());
+        }
+        let conflicting_branches = self
+            .vbranch_controller
+            .auto_update_base_branch(&project)
+            .await
+            .context("failed to update base branch")?;
+        for branch_name in conflicting_branches {
+            self.emit_app_event(Change::BranchNeedsRebase {
+                project_id,
+                branch_name,
+            })?;
+        }
+        self.calculate_virtual_branches(project_id).await
+    }
+
+    /// Invoked whenever a new oplog entry was written, to prune old snapshots if the project
+    /// has a retention policy, and to sync.
+    #[instrument(skip(self, project_id), fields(%project_id))]
     async fn gitbutler_oplog_change(&self, project_id: ProjectId) -> Result<()> {
         let project = self
             .projects
             .get(project_id)
             .context("failed to get project")?;
+        if let Some(policy) = project
+            .snapshot_retention
+            .filter(|_| !project.is_read_only())
+        {
+            // Pruning changes the oplog head too, but the event it causes has nothing left to prune.
+            if let Err(err) = project.prune_snapshots(policy) {
+                tracing::warn!(%project_id, ?err, "failed to prune snapshots");
+            }
+        }
+        self.sync(project_id, Some(SyncTrigger::Oplog)).await
+    }
 
-        if project.is_sync_enabled() && project.has_code_url() {
+    /// Invoked whenever something happened that may trigger a sync, like a new oplog entry.
+    /// If synchronizing with GitButler's servers is enabled and `trigger` is the one configured for the project,
+    /// it will push Oplog refs. Without `trigger`, the sync was requested explicitly and happens regardless.
+    #[instrument(skip(self, project_id), fields(%project_id))]
+    async fn sync(&self, project_id: ProjectId, trigger: Option<SyncTrigger>) -> Result<()> {
+        let project = self
+            .projects
+            .get(project_id)
+            .context("failed to get project")?;
+
+        let should_sync = match trigger {
+            Some(trigger) => project.syncs_on(trigger),
+            None => project.is_sync_enabled() && project.has_code_url(),
+        };
+        if should_sync {
             if let Some(user) = self.users.get_user()? {
                 let repository = ProjectRepository::open(&project)
                     .context("failed to open project repository for project")?;
-                return sync_with_gitbutler(&repository, &user, &self.projects).await;
+                let emit_progress = |progress| {
+                    if let Err(err) = self.emit_app_event(Change::SyncProgress {
+                        project_id,
+                        progress,
+                    }) {
+                        tracing::warn!(%project_id, ?err, "failed to emit sync progress");
+                    }
+                };
+                return retry_transient_staggered(
+                    RetryPolicy::default(),
+                    &self.sync_scheduler,
+                    project_id,
+                    || {
+                        sync_with_gitbutler_with_progress(
+                            &repository,
+                            &user,
+                            &self.projects,
+                            emit_progress,
+                        )
+                    },
+                )
+                .await;
             }
         }
         Ok(())
@@ -8,8 +8,17 @@ use events::InternalEvent;
 pub use events::{Action, Change};
 
 use gitbutler_project::ProjectId;
+pub use gitbutler_sync::scheduler::SyncScheduler;
 pub use handler::Handler;
+pub use recalculation::{
+    RecalculationLimit, RecalculationPermit, DEFAULT_RECALCULATION_CONCURRENCY,
+};
+pub use recent_events::{EventKind, RecentEvents, RecordedEvent, DEFAULT_RECENT_EVENTS_CAPACITY};
+pub use recording::{read_recording, write_recording, EventRecorder};
+pub use regression::{install_regression_case, write_regression_case};
 use std::path::Path;
+use std::time::Duration;
+pub use timers::{ActiveTimer, TimerKind, WatcherTimers, AUTO_FETCH_INTERVAL};
 
 use anyhow::{Context, Result};
 use tokio::{
@@ -18,8 +27,20 @@ use tokio::{
 };
 use tokio_util::sync::CancellationToken;
 
+/// How long the [handler](Handler) waits for more changes to project files by default, to handle them all at once.
+pub const DEFAULT_PROJECT_FILES_CHANGE_WINDOW: Duration = Duration::from_millis(100);
+
+/// How often projects that sync on a timer are synchronized with GitButler's servers.
+const SYNC_INTERVAL: Duration = Duration::from_secs(10 * 60);
+
 mod file_monitor;
+mod fsmonitor;
 mod handler;
+mod recalculation;
+mod recent_events;
+mod recording;
+mod regression;
+mod timers;
 
 /// An abstraction over a link to the spawned watcher, which runs in the background.
 pub struct WatcherHandle {
@@ -73,6 +94,9 @@ impl WatcherHandle {
 /// up if they take longer to process than the 100ms window between them, causing high-CPU and possibly
 /// high-memory. However, the likelihood for this is much lower than it was before the architecture
 /// was changed to what it is now, which should be much less wasteful.
+///
+/// Projects that [use fsmonitor](gitbutler_project::Project::use_fsmonitor) also get changes reported by
+/// the `core.fsmonitor` hook of their repository, alongside those of the filesystem watcher.
 pub fn watch_in_background(
     handler: handler::Handler,
     worktree_path: impl AsRef<Path>,
@@ -84,6 +108,15 @@ pub fn watch_in_background(
     let debounce = file_monitor::spawn(project_id, worktree_path.as_ref(), events_out.clone())?;
 
     let cancellation_token = CancellationToken::new();
+    if handler.uses_fsmonitor(project_id) {
+        fsmonitor::spawn(
+            project_id,
+            worktree_path.as_ref(),
+            events_out.clone(),
+            cancellation_token.clone(),
+        )?;
+    }
+    let timers = handler.timers();
     let handle = WatcherHandle {
         tx: events_out,
         project_id,
@@ -91,6 +124,8 @@ pub fn watch_in_background(
         cancellation_token: cancellation_token.clone(),
     };
     let handle_event = move |event: InternalEvent| -> Result<()> {
+        // Record events in the order they are received, as handling them happens in parallel.
+        handler.record_event(&event);
         let handler = handler.clone();
         // NOTE: Traditional parallelization (blocking) is required as `tokio::spawn()` on
         //       the `handler.handle()` future isn't `Send` as it keeps non-Send things
@@ -104,14 +139,32 @@ pub fn watch_in_background(
         Ok(())
     };
 
+    let next_sync = tokio::time::Instant::now() + SYNC_INTERVAL;
+    timers.set(project_id, TimerKind::SyncInterval, next_sync);
     tokio::spawn(async move {
+        let mut sync_timer = tokio::time::interval_at(next_sync, SYNC_INTERVAL);
+        // The debouncer doesn't tell when events arrive, so look at what it holds back as often as it does.
+        let mut debounce_timer = tokio::time::interval(file_monitor::TICK_RATE);
         loop {
             tokio::select! {
                 Some(event) = events_in.recv() => handle_event(event)?,
+                _ = sync_timer.tick() => {
+                    let next_sync = tokio::time::Instant::now() + SYNC_INTERVAL;
+                    timers.set(project_id, TimerKind::SyncInterval, next_sync);
+                    handle_event(InternalEvent::SyncTimer(project_id))?;
+                }
+                _ = debounce_timer.tick() => match debounce.next_flush() {
+                    Some(deadline) => {
+                        timers.set(project_id, TimerKind::FileMonitorDebounce, deadline.into());
+                    }
+                    None => timers.clear(project_id, TimerKind::FileMonitorDebounce),
+                },
                 Some(_signal_flush) = flush_rx.recv() => {
                     debounce.flush_nonblocking();
                 }
                 () = cancellation_token.cancelled() => {
+                    timers.clear(project_id, TimerKind::SyncInterval);
+                    timers.clear(project_id, TimerKind::FileMonitorDebounce);
                     tracing::debug!(%project_id, "stopped watcher");
                     break;
                 }
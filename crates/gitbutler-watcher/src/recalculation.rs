@@ -0,0 +1,116 @@
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex, MutexGuard};
+
+use gitbutler_project::ProjectId;
+use tokio::sync::{OwnedMutexGuard, OwnedSemaphorePermit, Semaphore};
+
+/// The amount of projects that may recalculate their virtual branches at the same time by default.
+pub const DEFAULT_RECALCULATION_CONCURRENCY: usize = 4;
+
+/// Bounds how many projects may recalculate their virtual branches at the same time, while making sure
+/// that recalculations of the same project never overlap.
+///
+/// It's cheap to clone, and all clones share the same limit, which is why one instance should be shared
+/// among the [handlers](crate::Handler) of all projects.
+#[derive(Clone)]
+pub struct RecalculationLimit {
+    /// The permits for recalculating, one per project that may recalculate concurrently.
+    semaphore: Arc<Semaphore>,
+    permits: Arc<Mutex<Permits>>,
+    /// One lock per project to serialize its recalculations.
+    projects: Arc<Mutex<HashMap<ProjectId, Arc<tokio::sync::Mutex<()>>>>>,
+}
+
+/// The bookkeeping of the permits of the semaphore.
+struct Permits {
+    /// The amount of permits the semaphore has in total, held or not.
+    concurrency: usize,
+    /// The amount of held permits to forget once they are released, as the concurrency was lowered
+    /// while they were held.
+    excess: usize,
+}
+
+/// Held while a project recalculates, allowing the next recalculation to start once dropped.
+pub struct RecalculationPermit {
+    permit: Option<OwnedSemaphorePermit>,
+    permits: Arc<Mutex<Permits>>,
+    _project: OwnedMutexGuard<()>,
+}
+
+impl Drop for RecalculationPermit {
+    fn drop(&mut self) {
+        let mut permits = lock(&self.permits);
+        if permits.excess > 0 {
+            permits.excess -= 1;
+            if let Some(permit) = self.permit.take() {
+                permit.forget();
+            }
+        }
+    }
+}
+
+impl Default for RecalculationLimit {
+    fn default() -> Self {
+        Self::new(DEFAULT_RECALCULATION_CONCURRENCY)
+    }
+}
+
+impl RecalculationLimit {
+    /// Allow up to `concurrency` projects to recalculate at the same time, with `0` being treated as `1`.
+    pub fn new(concurrency: usize) -> Self {
+        let concurrency = concurrency.max(1);
+        RecalculationLimit {
+            semaphore: Arc::new(Semaphore::new(concurrency)),
+            permits: Arc::new(Mutex::new(Permits {
+                concurrency,
+                excess: 0,
+            })),
+            projects: Default::default(),
+        }
+    }
+
+    /// Change the amount of projects that may recalculate at the same time to `concurrency`, with `0` being
+    /// treated as `1`.
+    ///
+    /// Recalculations that are already running are not affected, but if there are more of them than allowed now,
+    /// new recalculations wait until enough of them finished.
+    pub fn set_concurrency(&self, concurrency: usize) {
+        let concurrency = concurrency.max(1);
+        let mut permits = lock(&self.permits);
+        if concurrency > permits.concurrency {
+            let added = concurrency - permits.concurrency;
+            let kept = added.min(permits.excess);
+            permits.excess -= kept;
+            self.semaphore.add_permits(added - kept);
+        } else {
+            let removed = permits.concurrency - concurrency;
+            let forgotten = self.semaphore.forget_permits(removed);
+            permits.excess += removed - forgotten;
+        }
+        permits.concurrency = concurrency;
+    }
+
+    /// Wait until `project_id` is allowed to recalculate, which is when no other recalculation of the
+    /// same project is running and the limit of concurrent recalculations isn't reached.
+    pub async fn acquire(&self, project_id: ProjectId) -> RecalculationPermit {
+        let project = Arc::clone(lock(&self.projects).entry(project_id).or_default());
+        // Wait for our own project first so queued recalculations of one project don't hold permits
+        // that other projects could use.
+        let project = project.lock_owned().await;
+        let permit = Arc::clone(&self.semaphore)
+            .acquire_owned()
+            .await
+            .expect("the semaphore is never closed");
+        RecalculationPermit {
+            permit: Some(permit),
+            permits: Arc::clone(&self.permits),
+            _project: project,
+        }
+    }
+}
+
+fn lock<T>(mutex: &Mutex<T>) -> MutexGuard<'_, T> {
+    mutex
+        .lock()
+        .unwrap_or_else(|poisoned| poisoned.into_inner())
+}
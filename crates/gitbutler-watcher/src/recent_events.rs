@@ -0,0 +1,153 @@
+use std::collections::{HashMap, VecDeque};
+use std::path::PathBuf;
+use std::sync::{Arc, Mutex};
+use std::time::UNIX_EPOCH;
+
+use gitbutler_project::ProjectId;
+use serde::{Deserialize, Serialize};
+
+use crate::events::InternalEvent;
+
+/// The amount of events kept per project by default.
+pub const DEFAULT_RECENT_EVENTS_CAPACITY: usize = 100;
+
+/// The kind of an event received by the watcher.
+#[derive(Debug, PartialEq, Eq, Clone, Copy, Serialize, Deserialize)]
+pub enum EventKind {
+    /// A virtual branch recalculation was requested through the public API.
+    CalculateVirtualBranches,
+    /// Files in the `.git` directory changed.
+    GitFilesChange,
+    /// Files in the worktree changed.
+    ProjectFilesChange,
+    /// The oplog changed.
+    GitButlerOplogChange,
+    /// A branch was pushed.
+    BranchPushed,
+    /// The interval for syncing periodically elapsed.
+    SyncTimer,
+    /// A sync was requested through the public API.
+    SyncNow,
+}
+
+/// An event as received by the watcher, for debugging why it did or didn't react to something.
+#[derive(Debug, PartialEq, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct RecordedEvent {
+    pub kind: EventKind,
+    /// The paths that changed, relative to the worktree or the `.git` directory, depending on the kind.
+    pub paths: Vec<PathBuf>,
+    /// The time at which the event was received, in milliseconds since Unix epoch.
+    pub timestamp_ms: u128,
+}
+
+/// A ring buffer of the most recent events received by the watcher, per project.
+///
+/// It's cheap to clone, and all clones share the same events.
+#[derive(Clone)]
+pub struct RecentEvents {
+    capacity: usize,
+    events: Arc<Mutex<HashMap<ProjectId, VecDeque<RecordedEvent>>>>,
+}
+
+impl Default for RecentEvents {
+    fn default() -> Self {
+        Self::new(DEFAULT_RECENT_EVENTS_CAPACITY)
+    }
+}
+
+impl RecentEvents {
+    /// Keep up to `capacity` events per project, dropping the oldest ones first.
+    pub fn new(capacity: usize) -> Self {
+        RecentEvents {
+            capacity,
+            events: Default::default(),
+        }
+    }
+
+    /// Return the recent events of `project_id`, oldest first, and only those of `kind` if set.
+    pub fn list(&self, project_id: ProjectId, kind: Option<EventKind>) -> Vec<RecordedEvent> {
+        self.events
+            .lock()
+            .unwrap_or_else(|poisoned| poisoned.into_inner())
+            .get(&project_id)
+            .map(|events| {
+                events
+                    .iter()
+                    .filter(|event| kind.map_or(true, |kind| event.kind == kind))
+                    .cloned()
+                    .collect()
+            })
+            .unwrap_or_default()
+    }
+
+    pub(crate) fn record(&self, event: &InternalEvent) {
+        if self.capacity == 0 {
+            return;
+        }
+        let (project_id, event) = RecordedEvent::received(event);
+        let mut events = self
+            .events
+            .lock()
+            .unwrap_or_else(|poisoned| poisoned.into_inner());
+        let events = events.entry(project_id).or_default();
+        if events.len() == self.capacity {
+            events.pop_front();
+        }
+        events.push_back(event);
+    }
+}
+
+impl RecordedEvent {
+    /// Record `event` as received now, along with the project it belongs to.
+    pub(crate) fn received(event: &InternalEvent) -> (ProjectId, Self) {
+        let (project_id, kind, paths) = match event {
+            InternalEvent::CalculateVirtualBranches(project_id) => {
+                (*project_id, EventKind::CalculateVirtualBranches, Vec::new())
+            }
+            InternalEvent::GitFilesChange(project_id, paths) => {
+                (*project_id, EventKind::GitFilesChange, paths.clone())
+            }
+            InternalEvent::ProjectFilesChange(project_id, paths) => {
+                (*project_id, EventKind::ProjectFilesChange, paths.clone())
+            }
+            InternalEvent::GitButlerOplogChange(project_id) => {
+                (*project_id, EventKind::GitButlerOplogChange, Vec::new())
+            }
+            InternalEvent::BranchPushed(project_id) => {
+                (*project_id, EventKind::BranchPushed, Vec::new())
+            }
+            InternalEvent::SyncTimer(project_id) => (*project_id, EventKind::SyncTimer, Vec::new()),
+            InternalEvent::SyncNow(project_id) => (*project_id, EventKind::SyncNow, Vec::new()),
+        };
+        let timestamp_ms = UNIX_EPOCH
+            .elapsed()
+            .map(|elapsed| elapsed.as_millis())
+            .unwrap_or_default();
+        (
+            project_id,
+            RecordedEvent {
+                kind,
+                paths,
+                timestamp_ms,
+            },
+        )
+    }
+
+    /// Turn this event back into one that can be handled, as if it was received for `project_id`.
+    pub(crate) fn into_internal(self, project_id: ProjectId) -> InternalEvent {
+        match self.kind {
+            EventKind::CalculateVirtualBranches => {
+                InternalEvent::CalculateVirtualBranches(project_id)
+            }
+            EventKind::GitFilesChange => InternalEvent::GitFilesChange(project_id, self.paths),
+            EventKind::ProjectFilesChange => {
+                InternalEvent::ProjectFilesChange(project_id, self.paths)
+            }
+            EventKind::GitButlerOplogChange => InternalEvent::GitButlerOplogChange(project_id),
+            EventKind::BranchPushed => InternalEvent::BranchPushed(project_id),
+            EventKind::SyncTimer => InternalEvent::SyncTimer(project_id),
+            EventKind::SyncNow => InternalEvent::SyncNow(project_id),
+        }
+    }
+}
@@ -0,0 +1,77 @@
+use std::fs::File;
+use std::io::{BufRead, BufReader, Write};
+use std::path::Path;
+use std::sync::{Arc, Mutex};
+
+use anyhow::{Context, Result};
+
+use crate::events::InternalEvent;
+use crate::RecordedEvent;
+
+/// Writes every event received by a [handler](crate::Handler) to a file, one JSON object per line,
+/// so the exact sequence can be [replayed](crate::Handler::replay()) later to reproduce a problem.
+///
+/// It's cheap to clone, and all clones write to the same file.
+#[derive(Clone)]
+pub struct EventRecorder {
+    file: Arc<Mutex<File>>,
+}
+
+impl EventRecorder {
+    /// Record into a new file at `path`, replacing a previous recording there.
+    pub fn create(path: impl AsRef<Path>) -> Result<Self> {
+        let path = path.as_ref();
+        let file = File::create(path)
+            .with_context(|| format!("failed to create recording at {}", path.display()))?;
+        Ok(EventRecorder {
+            file: Arc::new(Mutex::new(file)),
+        })
+    }
+
+    /// Append `event` to the recording, writing it through right away so nothing is lost on a crash.
+    pub(crate) fn record(&self, event: &InternalEvent) -> Result<()> {
+        let (_project_id, event) = RecordedEvent::received(event);
+        let line = to_line(&event)?;
+        let mut file = self
+            .file
+            .lock()
+            .unwrap_or_else(|poisoned| poisoned.into_inner());
+        file.write_all(line.as_bytes())?;
+        file.flush()?;
+        Ok(())
+    }
+}
+
+/// Write `events` to a new file at `path` in the format of an [`EventRecorder`], so they can be read
+/// with [`read_recording()`].
+pub fn write_recording(path: impl AsRef<Path>, events: &[RecordedEvent]) -> Result<()> {
+    let path = path.as_ref();
+    let mut content = String::new();
+    for event in events {
+        content.push_str(&to_line(event)?);
+    }
+    std::fs::write(path, content)
+        .with_context(|| format!("failed to write recording to {}", path.display()))
+}
+
+fn to_line(event: &RecordedEvent) -> Result<String> {
+    let mut line = serde_json::to_string(event)?;
+    line.push('\n');
+    Ok(line)
+}
+
+/// Read the events written to the file at `path` by an [`EventRecorder`], in the order they were received.
+pub fn read_recording(path: impl AsRef<Path>) -> Result<Vec<RecordedEvent>> {
+    let path = path.as_ref();
+    let file = File::open(path)
+        .with_context(|| format!("failed to open recording at {}", path.display()))?;
+    BufReader::new(file)
+        .lines()
+        .enumerate()
+        .filter(|(_, line)| !line.as_ref().is_ok_and(|line| line.trim().is_empty()))
+        .map(|(idx, line)| {
+            serde_json::from_str(&line?)
+                .with_context(|| format!("invalid event on line {} of {}", idx + 1, path.display()))
+        })
+        .collect()
+}
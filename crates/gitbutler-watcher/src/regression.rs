@@ -0,0 +1,319 @@
+use std::collections::HashMap;
+use std::io::Write;
+use std::path::{Component, Path, PathBuf};
+
+use anyhow::{bail, Context, Result};
+use gitbutler_branch::{Branch, Target, VirtualBranchesHandle, GITBUTLER_INTEGRATION_REFERENCE};
+use gitbutler_command_context::ProjectRepository;
+use gitbutler_project::Project;
+use gitbutler_reference::RemoteRefname;
+use gitbutler_repo::RepositoryExt;
+use serde::{Deserialize, Serialize};
+
+use crate::{read_recording, write_recording, EventKind, RecordedEvent};
+
+/// The file of a regression case with the events to replay, in the format of recordings.
+const EVENTS_FILE_NAME: &str = "events.jsonl";
+/// The file of a regression case with the state of the virtual branches to replay the events against.
+const STATE_FILE_NAME: &str = "state.toml";
+/// The file of a regression case with a pack of all objects the state refers to.
+const OBJECTS_FILE_NAME: &str = "objects.pack";
+/// The URL of the remote of the base branch in a regression case, as the real one may contain credentials.
+const REDACTED_REMOTE_URL: &str = "https://example.com/redacted.git";
+/// The files in the `.git` directory the handler reacts to, which are kept as is in the events of a case.
+const KNOWN_GIT_PATHS: &[&str] = &["HEAD", "FETCH_HEAD", "ORIG_HEAD", "logs/HEAD", "index"];
+
+/// The minimal state needed to replay events: the base, the virtual branches and the worktree, without any of
+/// the text users wrote themselves.
+#[derive(Serialize, Deserialize)]
+struct RegressionState {
+    default_target: Target,
+    branches: Vec<Branch>,
+    /// The tree with the content of the worktree.
+    #[serde(with = "gitbutler_serde::serde::oid")]
+    worktree: git2::Oid,
+}
+
+/// Write a self-contained regression case into the new directory `dir`, consisting of the virtual branch
+/// state of `project`, the content of its worktree, all commits the state refers to, and the `events` it
+/// received, so the problem they caused can be reproduced in a test.
+///
+/// Nothing users wrote leaves their machine: branch names are replaced by placeholders, notes, refnames and
+/// the remote URL are dropped or hashed, and the names of files as well as every line of their content and
+/// commit messages are replaced by hashes, which keeps the shape of the changes intact.
+/// All hashes are salted with a random value that is different for each case and isn't stored, so they can't
+/// be reversed by hashing guesses.
+/// The events are stored as recording, to be replayed with [`Handler::replay()`](crate::Handler::replay()).
+pub fn write_regression_case(
+    project: &Project,
+    events: &[RecordedEvent],
+    dir: &Path,
+) -> Result<()> {
+    if dir.exists() {
+        bail!("{} already exists", dir.display());
+    }
+    let ctx = ProjectRepository::open(project)?;
+    let objects = git2::Repository::from_odb(git2::Odb::new()?)?;
+    let odb = objects.odb()?;
+    let mempack = odb.add_new_mempack_backend(1)?;
+    let mut redactor = Redactor {
+        repo: ctx.repo(),
+        objects: &objects,
+        salt: uuid::Uuid::new_v4().into_bytes(),
+        base: git2::Oid::zero(),
+        rewritten: HashMap::new(),
+    };
+
+    let vb_state = VirtualBranchesHandle::new(project.gb_dir());
+    let mut default_target = vb_state.get_default_target()?;
+    redactor.base = default_target.sha;
+    default_target.branch = redactor.remote_refname(&default_target.branch)?;
+    default_target.remote_url = REDACTED_REMOTE_URL.to_owned();
+    default_target.push_remote_name = default_target
+        .push_remote_name
+        .as_deref()
+        .map(|name| redactor.name(name.as_bytes()))
+        .transpose()?;
+    default_target.sha = redactor.commit(default_target.sha)?;
+
+    let mut branches = vb_state.list_all_branches()?;
+    branches.sort_by_key(|branch| branch.order);
+    for (idx, branch) in branches.iter_mut().enumerate() {
+        branch.name = format!("branch-{}", idx + 1);
+        branch.notes.clear();
+        branch.source_refname = None;
+        branch.upstream = branch
+            .upstream
+            .as_ref()
+            .map(|upstream| redactor.remote_refname(upstream))
+            .transpose()?;
+        // the commits of the upstream aren't part of the case
+        branch.upstream_head = None;
+        branch.head = redactor.commit(branch.head)?;
+        branch.tree = redactor.tree(branch.tree)?;
+        for claim in &mut branch.ownership.claims {
+            claim.file_path = redactor.path(&claim.file_path)?;
+            // the hashes are of the content, which is replaced
+            for hunk in &mut claim.hunks {
+                hunk.hash = None;
+            }
+        }
+    }
+    let worktree = ctx.repo().get_wd_tree()?.id();
+    let state = RegressionState {
+        default_target,
+        branches,
+        worktree: redactor.tree(worktree)?,
+    };
+    let events = events
+        .iter()
+        .map(|event| redactor.event(event))
+        .collect::<Result<Vec<_>>>()?;
+
+    let mut pack = git2::Buf::new();
+    mempack.dump(&objects, &mut pack)?;
+
+    std::fs::create_dir_all(dir).with_context(|| format!("failed to create {}", dir.display()))?;
+    std::fs::write(dir.join(STATE_FILE_NAME), toml::to_string(&state)?)?;
+    std::fs::write(dir.join(OBJECTS_FILE_NAME), &*pack)?;
+    write_recording(dir.join(EVENTS_FILE_NAME), &events)
+}
+
+/// Install the regression case in `dir` into `project`, which may be a fresh repository, and return its
+/// events to be replayed.
+///
+/// All virtual branches of `project` are replaced by those of the case, and its worktree is replaced by the one
+/// of the case, with `HEAD` on the integration branch.
+pub fn install_regression_case(dir: &Path, project: &Project) -> Result<Vec<RecordedEvent>> {
+    let state_path = dir.join(STATE_FILE_NAME);
+    let state: RegressionState = toml::from_str(
+        &std::fs::read_to_string(&state_path)
+            .with_context(|| format!("failed to read {}", state_path.display()))?,
+    )?;
+    let events = read_recording(dir.join(EVENTS_FILE_NAME))?;
+    let objects_path = dir.join(OBJECTS_FILE_NAME);
+    let pack = std::fs::read(&objects_path)
+        .with_context(|| format!("failed to read {}", objects_path.display()))?;
+
+    let ctx = ProjectRepository::open(project)?;
+    let repo = ctx.repo();
+    let odb = repo.odb()?;
+    let mut writer = odb.packwriter()?;
+    writer.write_all(&pack)?;
+    writer.commit()?;
+
+    let vb_state = VirtualBranchesHandle::new(project.gb_dir());
+    for branch in vb_state.list_all_branches()? {
+        vb_state.delete_branch_entry(&branch.id)?;
+    }
+    repo.reference(
+        &state.default_target.branch.to_string(),
+        state.default_target.sha,
+        true,
+        "installed regression case",
+    )?;
+    vb_state.set_default_target(state.default_target.clone())?;
+    for branch in state.branches {
+        vb_state.set_branch(branch)?;
+    }
+
+    let worktree = repo.find_tree(state.worktree)?;
+    repo.checkout_tree_builder(&worktree)
+        .force()
+        .remove_untracked()
+        .checkout()
+        .context("failed to checkout the worktree of the case")?;
+    // Updating the integration branch needs `HEAD` to point to a commit, so start from the base.
+    let integration_refname = GITBUTLER_INTEGRATION_REFERENCE.to_string();
+    repo.reference(
+        &integration_refname,
+        state.default_target.sha,
+        true,
+        "installed regression case",
+    )?;
+    repo.set_head(&integration_refname)?;
+    gitbutler_branch_actions::update_gitbutler_integration(&vb_state, &ctx)?;
+    Ok(events)
+}
+
+/// Rewrites objects of `repo` into `objects`, replacing names of files, every line of content and the
+/// metadata of commits by hashes.
+struct Redactor<'a> {
+    repo: &'a git2::Repository,
+    objects: &'a git2::Repository,
+    /// The random value all hashes of a case are salted with.
+    salt: [u8; 16],
+    /// The commit the history of a case starts at.
+    base: git2::Oid,
+    /// The redacted objects by the id of their original.
+    rewritten: HashMap<git2::Oid, git2::Oid>,
+}
+
+impl Redactor<'_> {
+    /// Return a short hash of `name` that is stable within a case and valid as path component and in refnames.
+    fn name(&self, name: &[u8]) -> Result<String> {
+        let mut salted = self.salt.to_vec();
+        salted.extend_from_slice(name);
+        let mut hash = git2::Oid::hash_object(git2::ObjectType::Blob, &salted)?.to_string();
+        hash.truncate(12);
+        Ok(hash)
+    }
+
+    /// Return `path` with each of its components replaced by a hash of it.
+    fn path(&self, path: &Path) -> Result<PathBuf> {
+        path.components()
+            .map(|component| match component {
+                Component::Normal(name) => self.name(name.as_encoded_bytes()),
+                _ => Ok(component.as_os_str().to_string_lossy().into_owned()),
+            })
+            .collect()
+    }
+
+    fn remote_refname(&self, refname: &RemoteRefname) -> Result<RemoteRefname> {
+        Ok(RemoteRefname::new(
+            &self.name(refname.remote().as_bytes())?,
+            &self.name(refname.branch().as_bytes())?,
+        ))
+    }
+
+    /// Redact the paths of `event`, except for the well-known files in the `.git` directory.
+    fn event(&self, event: &RecordedEvent) -> Result<RecordedEvent> {
+        let paths = event
+            .paths
+            .iter()
+            .map(|path| {
+                if event.kind == EventKind::GitFilesChange
+                    && KNOWN_GIT_PATHS.iter().any(|known| path == Path::new(known))
+                {
+                    Ok(path.clone())
+                } else {
+                    self.path(path)
+                }
+            })
+            .collect::<Result<_>>()?;
+        Ok(RecordedEvent {
+            paths,
+            ..event.clone()
+        })
+    }
+
+    fn commit(&mut self, id: git2::Oid) -> Result<git2::Oid> {
+        if let Some(rewritten) = self.rewritten.get(&id) {
+            return Ok(*rewritten);
+        }
+        let commit = self.repo.find_commit(id)?;
+        // The history is cut at the base, and at all commits that aren't on top of it.
+        let mut parents = Vec::new();
+        if id != self.base && self.repo.graph_descendant_of(id, self.base)? {
+            for parent_id in commit.parent_ids() {
+                let parent_id = self.commit(parent_id)?;
+                parents.push(self.objects.find_commit(parent_id)?);
+            }
+        }
+        let tree = self.tree(commit.tree_id())?;
+        let author = redact_signature(&commit.author())?;
+        let committer = redact_signature(&commit.committer())?;
+        let rewritten = self.objects.commit(
+            None,
+            &author,
+            &committer,
+            &self.name(commit.message_bytes())?,
+            &self.objects.find_tree(tree)?,
+            &parents.iter().collect::<Vec<_>>(),
+        )?;
+        self.rewritten.insert(id, rewritten);
+        Ok(rewritten)
+    }
+
+    fn tree(&mut self, id: git2::Oid) -> Result<git2::Oid> {
+        if let Some(rewritten) = self.rewritten.get(&id) {
+            return Ok(*rewritten);
+        }
+        let tree = self.repo.find_tree(id)?;
+        let mut builder = self.objects.treebuilder(None)?;
+        for entry in tree.iter() {
+            let object_id = match entry.kind() {
+                Some(git2::ObjectType::Tree) => self.tree(entry.id())?,
+                Some(git2::ObjectType::Blob) => self.blob(entry.id())?,
+                // submodules are referenced by commit, which isn't part of the repository anyway
+                _ => entry.id(),
+            };
+            builder.insert(self.name(entry.name_bytes())?, object_id, entry.filemode())?;
+        }
+        let rewritten = builder.write()?;
+        self.rewritten.insert(id, rewritten);
+        Ok(rewritten)
+    }
+
+    fn blob(&mut self, id: git2::Oid) -> Result<git2::Oid> {
+        if let Some(rewritten) = self.rewritten.get(&id) {
+            return Ok(*rewritten);
+        }
+        let blob = self.repo.find_blob(id)?;
+        let content = if blob.is_binary() {
+            self.name(blob.content())?.into_bytes()
+        } else {
+            let mut content = Vec::with_capacity(blob.size());
+            for line in blob.content().split_inclusive(|byte| *byte == b'\n') {
+                let (text, newline) = match line.strip_suffix(b"\n") {
+                    Some(text) => (text, &b"\n"[..]),
+                    None => (line, &b""[..]),
+                };
+                content.extend_from_slice(self.name(text)?.as_bytes());
+                content.extend_from_slice(newline);
+            }
+            content
+        };
+        let rewritten = self.objects.blob(&content)?;
+        self.rewritten.insert(id, rewritten);
+        Ok(rewritten)
+    }
+}
+
+fn redact_signature(signature: &git2::Signature) -> Result<git2::Signature<'static>> {
+    Ok(git2::Signature::new(
+        "GitButler",
+        "gitbutler@gitbutler.com",
+        &signature.when(),
+    )?)
+}
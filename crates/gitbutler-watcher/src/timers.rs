@@ -0,0 +1,100 @@
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+use gitbutler_project::ProjectId;
+use serde::{Deserialize, Serialize};
+use tokio::time::Instant;
+
+/// How often the UI fetches the remotes of the open project, with `auto` as action.
+pub const AUTO_FETCH_INTERVAL: Duration = Duration::from_secs(15 * 60);
+
+/// The kind of a timer of the watcher.
+#[derive(Debug, PartialEq, Eq, Hash, Clone, Copy, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum TimerKind {
+    /// Filesystem events are held back until it's quiet for a moment, or the oldest of them is too old.
+    FileMonitorDebounce,
+    /// Changes to project files are collected until the window of the first change passes.
+    ProjectFilesChangeWindow,
+    /// The interval after which projects that sync on a timer are synchronized.
+    SyncInterval,
+    /// The interval after which the remotes of the project are fetched again.
+    AutoFetch,
+}
+
+/// A timer of the watcher that didn't elapse yet, for debugging why something didn't happen yet.
+#[derive(Debug, PartialEq, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ActiveTimer {
+    pub kind: TimerKind,
+    /// The time left until the timer elapses, in milliseconds.
+    pub remaining_ms: u128,
+}
+
+/// The timers of the watcher that are currently waiting to elapse, per project.
+///
+/// It's cheap to clone, and all clones share the same timers.
+#[derive(Clone, Default)]
+pub struct WatcherTimers {
+    deadlines: Arc<Mutex<HashMap<ProjectId, HashMap<TimerKind, Instant>>>>,
+}
+
+impl WatcherTimers {
+    /// Return the timers of `project_id` that didn't elapse yet, the one elapsing first coming first.
+    pub fn list(&self, project_id: ProjectId) -> Vec<ActiveTimer> {
+        let now = Instant::now();
+        let mut timers: Vec<_> = self
+            .deadlines
+            .lock()
+            .unwrap_or_else(|poisoned| poisoned.into_inner())
+            .get(&project_id)
+            .map(|deadlines| {
+                deadlines
+                    .iter()
+                    .filter(|(_, deadline)| **deadline > now)
+                    .map(|(kind, deadline)| ActiveTimer {
+                        kind: *kind,
+                        remaining_ms: (*deadline - now).as_millis(),
+                    })
+                    .collect()
+            })
+            .unwrap_or_default();
+        timers.sort_by_key(|timer| timer.remaining_ms);
+        timers
+    }
+
+    /// Remember that the remotes of `project_id` are being fetched automatically, which happens
+    /// again after [`AUTO_FETCH_INTERVAL`].
+    pub fn auto_fetch_started(&self, project_id: ProjectId) {
+        self.set(
+            project_id,
+            TimerKind::AutoFetch,
+            Instant::now() + AUTO_FETCH_INTERVAL,
+        );
+    }
+
+    /// Remember that the timer of `kind` for `project_id` elapses at `deadline`.
+    pub(crate) fn set(&self, project_id: ProjectId, kind: TimerKind, deadline: Instant) {
+        self.deadlines
+            .lock()
+            .unwrap_or_else(|poisoned| poisoned.into_inner())
+            .entry(project_id)
+            .or_default()
+            .insert(kind, deadline);
+    }
+
+    /// Forget the timer of `kind` for `project_id`, as it elapsed or was stopped.
+    pub(crate) fn clear(&self, project_id: ProjectId, kind: TimerKind) {
+        let mut deadlines = self
+            .deadlines
+            .lock()
+            .unwrap_or_else(|poisoned| poisoned.into_inner());
+        if let Some(timers) = deadlines.get_mut(&project_id) {
+            timers.remove(&kind);
+            if timers.is_empty() {
+                deadlines.remove(&project_id);
+            }
+        }
+    }
+}
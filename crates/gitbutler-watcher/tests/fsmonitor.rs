@@ -0,0 +1,116 @@
+use std::os::unix::fs::PermissionsExt;
+use std::path::PathBuf;
+use std::time::Duration;
+
+use gitbutler_branch_actions::VirtualBranchActions;
+use gitbutler_project::UpdateRequest;
+use gitbutler_testsupport::{virtual_branches::set_test_target, Suite};
+use gitbutler_watcher::{Change, EventKind, Handler, RecentEvents};
+
+/// A `core.fsmonitor` hook that reports everything on the first query and `file.txt` on all others.
+const HOOK: &str = r#"#!/bin/sh
+if [ -z "$2" ]; then
+    printf 'token-1\0/\0'
+else
+    printf 'token-2\0file.txt\0'
+fi
+"#;
+
+#[tokio::test]
+async fn changes_reported_by_fsmonitor_are_recalculated() -> anyhow::Result<()> {
+    let suite = Suite::default();
+    let case = suite.new_case();
+    set_test_target(&case.project_repository)?;
+    suite
+        .projects
+        .update(&UpdateRequest {
+            id: case.project.id,
+            use_fsmonitor: Some(true),
+            ..Default::default()
+        })
+        .await?;
+
+    let hook = case.project.path.join(".git").join("fsmonitor-hook");
+    std::fs::write(&hook, HOOK)?;
+    std::fs::set_permissions(&hook, std::fs::Permissions::from_mode(0o755))?;
+    case.project_repository
+        .repo()
+        .config()?
+        .set_str("core.fsmonitor", hook.to_str().unwrap())?;
+    // Written before watching, so only fsmonitor can report it.
+    std::fs::write(case.project.path.join("file.txt"), "content")?;
+
+    let (tx, mut rx) = tokio::sync::mpsc::unbounded_channel();
+    let recent = RecentEvents::default();
+    let handler = Handler::new(
+        suite.projects.clone(),
+        suite.users.clone(),
+        VirtualBranchActions,
+        Duration::ZERO,
+        move |change| {
+            tx.send(change)?;
+            Ok(())
+        },
+    )
+    .with_recent_events(recent.clone());
+    let _watcher =
+        gitbutler_watcher::watch_in_background(handler, &case.project.path, case.project.id)?;
+
+    let virtual_branches = tokio::time::timeout(Duration::from_secs(10), async {
+        loop {
+            if let Change::VirtualBranches {
+                virtual_branches, ..
+            } = rx.recv().await.expect("the handler is alive")
+            {
+                return virtual_branches;
+            }
+        }
+    })
+    .await?;
+    let files = virtual_branches
+        .branches
+        .iter()
+        .flat_map(|branch| &branch.files)
+        .map(|file| file.path.clone())
+        .collect::<Vec<_>>();
+    assert_eq!(files, [PathBuf::from("file.txt")]);
+
+    let reported = recent.list(case.project.id, Some(EventKind::ProjectFilesChange));
+    assert_eq!(
+        reported[0].paths,
+        [PathBuf::from("file.txt")],
+        "the first answer of the hook isn't reported as it doesn't say what changed"
+    );
+    Ok(())
+}
+
+#[tokio::test]
+async fn fsmonitor_is_only_used_if_enabled() -> anyhow::Result<()> {
+    let suite = Suite::default();
+    let case = suite.new_case();
+    let hook = case.project.path.join(".git").join("fsmonitor-hook");
+    std::fs::write(&hook, HOOK)?;
+    std::fs::set_permissions(&hook, std::fs::Permissions::from_mode(0o755))?;
+    case.project_repository
+        .repo()
+        .config()?
+        .set_str("core.fsmonitor", hook.to_str().unwrap())?;
+
+    let recent = RecentEvents::default();
+    let handler = Handler::new(
+        suite.projects.clone(),
+        suite.users.clone(),
+        VirtualBranchActions,
+        Duration::ZERO,
+        |_| Ok(()),
+    )
+    .with_recent_events(recent.clone());
+    let _watcher =
+        gitbutler_watcher::watch_in_background(handler, &case.project.path, case.project.id)?;
+
+    tokio::time::sleep(Duration::from_secs(1)).await;
+    assert!(recent
+        .list(case.project.id, Some(EventKind::ProjectFilesChange))
+        .is_empty());
+    Ok(())
+}
@@ -0,0 +1,534 @@
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+use gitbutler_branch_actions::{VirtualBranchActions, VirtualBranches};
+use gitbutler_oplog::{
+    entry::{OperationKind, SnapshotDetails, Trailer},
+    OplogExt,
+};
+use gitbutler_project::{ApiProject, ProjectId, ProjectMode, SyncTrigger, UpdateRequest};
+use gitbutler_testsupport::{virtual_branches::set_test_target, Suite};
+use gitbutler_watcher::{Action, Change, EventKind, Handler, RecalculationLimit, RecordedEvent};
+use tokio::sync::mpsc::UnboundedReceiver;
+
+#[tokio::test]
+async fn rewriting_head_with_the_same_ref_emits_once() -> anyhow::Result<()> {
+    let suite = Suite::default();
+    let case = suite.new_case();
+    let repo = case.project_repository.repo();
+    let head_commit = repo.head()?.peel_to_commit()?;
+    repo.branch("gitbutler/integration", &head_commit, false)?;
+
+    let events = Arc::new(Mutex::new(Vec::new()));
+    let handler = Handler::new(
+        suite.projects.clone(),
+        suite.users.clone(),
+        VirtualBranchActions,
+        Duration::ZERO,
+        {
+            let events = Arc::clone(&events);
+            move |change| {
+                events.lock().unwrap().push(change);
+                Ok(())
+            }
+        },
+    );
+    let heads = || {
+        events
+            .lock()
+            .unwrap()
+            .iter()
+            .filter_map(|change| match change {
+                Change::GitHead { head, .. } => Some(head.clone()),
+                _ => None,
+            })
+            .collect::<Vec<_>>()
+    };
+
+    for _ in 0..2 {
+        repo.set_head("refs/heads/gitbutler/integration")?;
+        handler
+            .git_files_change(vec!["HEAD".into()], case.project.id)
+            .await?;
+    }
+    assert_eq!(heads(), ["refs/heads/gitbutler/integration"]);
+
+    repo.set_head("refs/heads/master")?;
+    handler
+        .git_files_change(vec!["HEAD".into()], case.project.id)
+        .await?;
+    assert_eq!(
+        heads(),
+        ["refs/heads/gitbutler/integration", "refs/heads/master"],
+        "a changed head is emitted again"
+    );
+    Ok(())
+}
+
+#[tokio::test]
+async fn leaving_the_integration_branch_emits_a_reset() -> anyhow::Result<()> {
+    let suite = Suite::default();
+    let case = suite.new_case();
+    let repo = case.project_repository.repo();
+    let head_commit = repo.head()?.peel_to_commit()?;
+    repo.branch("gitbutler/integration", &head_commit, false)?;
+
+    let (tx, mut rx) = tokio::sync::mpsc::unbounded_channel();
+    let handler = Handler::new(
+        suite.projects.clone(),
+        suite.users.clone(),
+        VirtualBranchActions,
+        Duration::ZERO,
+        move |change| {
+            tx.send(change)?;
+            Ok(())
+        },
+    );
+
+    repo.set_head("refs/heads/master")?;
+    handler
+        .git_files_change(vec!["HEAD".into()], case.project.id)
+        .await?;
+    assert!(matches!(
+        rx.try_recv()?,
+        Change::IntegrationReset { project_id } if project_id == case.project.id
+    ));
+    assert!(
+        matches!(rx.try_recv()?, Change::GitHead { head, .. } if head == "refs/heads/master"),
+        "the head is still emitted"
+    );
+    assert!(repo
+        .find_reference("refs/heads/gitbutler/integration")
+        .is_err());
+    Ok(())
+}
+
+#[tokio::test]
+async fn checking_out_the_integration_branch_is_reported() -> anyhow::Result<()> {
+    let suite = Suite::default();
+    let case = suite.new_case();
+    let repo = case.project_repository.repo();
+    let head_commit = repo.head()?.peel_to_commit()?;
+    repo.branch("gitbutler/integration", &head_commit, false)?;
+
+    let (tx, mut rx) = tokio::sync::mpsc::unbounded_channel();
+    let handler = Handler::new(
+        suite.projects.clone(),
+        suite.users.clone(),
+        VirtualBranchActions,
+        Duration::ZERO,
+        move |change| {
+            tx.send(change)?;
+            Ok(())
+        },
+    );
+
+    repo.set_head("refs/heads/master")?;
+    handler
+        .git_files_change(vec!["HEAD".into()], case.project.id)
+        .await?;
+    assert!(matches!(rx.try_recv()?, Change::IntegrationReset { .. }));
+    assert!(
+        matches!(rx.try_recv()?, Change::GitHead { head, .. } if head == "refs/heads/master"),
+        "the initial head is only remembered"
+    );
+
+    repo.branch("gitbutler/integration", &head_commit, false)?;
+    repo.set_head("refs/heads/gitbutler/integration")?;
+    handler
+        .git_files_change(vec!["HEAD".into()], case.project.id)
+        .await?;
+    assert!(matches!(
+        rx.try_recv()?,
+        Change::HeadOnIntegration { project_id } if project_id == case.project.id
+    ));
+    assert!(
+        matches!(rx.try_recv()?, Change::GitHead { head, .. } if head == "refs/heads/gitbutler/integration"),
+        "the head is still emitted"
+    );
+
+    handler
+        .git_files_change(vec!["HEAD".into()], case.project.id)
+        .await?;
+    assert!(rx.try_recv().is_err(), "nothing changed");
+    Ok(())
+}
+
+/// Wait for the next [`Change::VirtualBranches`] sent to `changes`, skipping all other changes.
+async fn next_virtual_branches(changes: &mut UnboundedReceiver<Change>) -> VirtualBranches {
+    tokio::time::timeout(Duration::from_secs(10), async {
+        loop {
+            if let Change::VirtualBranches {
+                virtual_branches, ..
+            } = changes.recv().await.expect("the handler is alive")
+            {
+                return virtual_branches;
+            }
+        }
+    })
+    .await
+    .expect("virtual branches are emitted in time")
+}
+
+#[tokio::test]
+async fn refreshing_emits_the_current_virtual_branches_without_snapshot() -> anyhow::Result<()> {
+    let suite = Suite::default();
+    let case = suite.new_case();
+    set_test_target(&case.project_repository)?;
+    std::fs::write(case.project.path.join("file.txt"), "content")?;
+
+    let (tx, mut rx) = tokio::sync::mpsc::unbounded_channel();
+    let handler = Handler::new(
+        suite.projects.clone(),
+        suite.users.clone(),
+        VirtualBranchActions,
+        Duration::ZERO,
+        move |change| {
+            tx.send(change)?;
+            Ok(())
+        },
+    );
+    let watcher =
+        gitbutler_watcher::watch_in_background(handler, &case.project.path, case.project.id)?;
+
+    let files = |virtual_branches: &VirtualBranches| {
+        virtual_branches
+            .branches
+            .iter()
+            .flat_map(|branch| {
+                branch
+                    .files
+                    .iter()
+                    .map(|file| file.path.to_string_lossy().into_owned())
+            })
+            .collect::<Vec<_>>()
+    };
+
+    for _ in 0..2 {
+        watcher
+            .post(Action::CalculateVirtualBranches(case.project.id))
+            .await?;
+        let virtual_branches = next_virtual_branches(&mut rx).await;
+        assert_eq!(files(&virtual_branches), ["file.txt"]);
+    }
+    assert!(
+        case.project.list_snapshots(10, None)?.is_empty(),
+        "unlike file changes, refreshing never creates a snapshot"
+    );
+    Ok(())
+}
+
+#[tokio::test]
+async fn bursts_of_project_file_changes_recalculate_once() -> anyhow::Result<()> {
+    let suite = Suite::default();
+    let case = suite.new_case();
+    set_test_target(&case.project_repository)?;
+
+    let (tx, mut rx) = tokio::sync::mpsc::unbounded_channel();
+    let handler = Handler::new(
+        suite.projects.clone(),
+        suite.users.clone(),
+        VirtualBranchActions,
+        Duration::from_millis(200),
+        move |change| {
+            tx.send(change)?;
+            Ok(())
+        },
+    );
+
+    let change = |name: &str| {
+        std::fs::write(case.project.path.join(name), "content").unwrap();
+        handler.replay(
+            case.project.id,
+            [RecordedEvent {
+                kind: EventKind::ProjectFilesChange,
+                paths: vec![name.into()],
+                timestamp_ms: 0,
+            }],
+        )
+    };
+    futures::join!(change("a.txt"), change("b.txt"), change("c.txt"));
+
+    let mut virtual_branches = Vec::new();
+    while let Ok(change) = rx.try_recv() {
+        if let Change::VirtualBranches {
+            virtual_branches: vbranches,
+            ..
+        } = change
+        {
+            virtual_branches.push(vbranches);
+        }
+    }
+    assert_eq!(virtual_branches.len(), 1, "the changes are handled as one");
+    let mut files = virtual_branches[0]
+        .branches
+        .iter()
+        .flat_map(|branch| &branch.files)
+        .map(|file| file.path.to_string_lossy().into_owned())
+        .collect::<Vec<_>>();
+    files.sort();
+    assert_eq!(files, ["a.txt", "b.txt", "c.txt"]);
+    Ok(())
+}
+
+#[tokio::test]
+async fn changes_to_snapshot_triggers_are_snapshotted_right_away() -> anyhow::Result<()> {
+    let suite = Suite::default();
+    let case = suite.new_case();
+    set_test_target(&case.project_repository)?;
+    let project = suite
+        .projects
+        .update(&UpdateRequest {
+            id: case.project.id,
+            snapshot_triggers: Some(vec!["Cargo.lock".into()]),
+            ..Default::default()
+        })
+        .await?;
+    project.create_snapshot(
+        SnapshotDetails::new(OperationKind::Unknown),
+        project.exclusive_worktree_access().write_permission(),
+    )?;
+
+    let handler = Handler::new(
+        suite.projects.clone(),
+        suite.users.clone(),
+        VirtualBranchActions,
+        Duration::ZERO,
+        |_| Ok(()),
+    );
+    let change = |name: &str| {
+        std::fs::write(project.path.join(name), "content").unwrap();
+        handler.replay(
+            project.id,
+            [RecordedEvent {
+                kind: EventKind::ProjectFilesChange,
+                paths: vec![name.into()],
+                timestamp_ms: 0,
+            }],
+        )
+    };
+
+    change("file.txt").await;
+    assert_eq!(
+        project.list_snapshots(10, None)?.len(),
+        1,
+        "other changes wait for the snapshot interval"
+    );
+
+    change("Cargo.lock").await;
+    let snapshots = project.list_snapshots(10, None)?;
+    assert_eq!(snapshots.len(), 2, "the trigger ignores the interval");
+    let details = snapshots[0].details.as_ref().expect("parseable");
+    assert_eq!(details.operation, OperationKind::TriggeredFileChange);
+    assert_eq!(
+        details.trailers,
+        vec![Trailer {
+            key: "path".into(),
+            value: "Cargo.lock".into()
+        }]
+    );
+    Ok(())
+}
+
+#[tokio::test]
+async fn read_only_projects_are_only_observed() -> anyhow::Result<()> {
+    let suite = Suite::default();
+    let case = suite.new_case();
+    set_test_target(&case.project_repository)?;
+    let repo = case.project_repository.repo();
+    let head_commit = repo.head()?.peel_to_commit()?;
+    repo.branch("gitbutler/integration", &head_commit, false)?;
+    suite
+        .projects
+        .update(&UpdateRequest {
+            id: case.project.id,
+            mode: Some(ProjectMode::ReadOnly),
+            snapshot_triggers: Some(vec!["*.txt".into()]),
+            ..Default::default()
+        })
+        .await?;
+    let state_path = case.project.gb_dir().join("virtual_branches.toml");
+    let state = std::fs::read(&state_path)?;
+
+    let (tx, mut rx) = tokio::sync::mpsc::unbounded_channel();
+    let handler = Handler::new(
+        suite.projects.clone(),
+        suite.users.clone(),
+        VirtualBranchActions,
+        Duration::ZERO,
+        move |change| {
+            tx.send(change)?;
+            Ok(())
+        },
+    );
+
+    std::fs::write(case.project.path.join("file.txt"), "content")?;
+    handler
+        .replay(
+            case.project.id,
+            [RecordedEvent {
+                kind: EventKind::ProjectFilesChange,
+                paths: vec!["file.txt".into()],
+                timestamp_ms: 0,
+            }],
+        )
+        .await;
+    assert!(
+        case.project.list_snapshots(10, None)?.is_empty(),
+        "even triggers don't create snapshots"
+    );
+    assert_eq!(
+        std::fs::read(&state_path)?,
+        state,
+        "no branch is created for the change"
+    );
+
+    repo.set_head("refs/heads/master")?;
+    handler
+        .git_files_change(vec!["HEAD".into()], case.project.id)
+        .await?;
+    assert!(
+        repo.find_reference("refs/heads/gitbutler/integration")
+            .is_ok(),
+        "the integration branch is kept"
+    );
+    while let Ok(change) = rx.try_recv() {
+        assert!(!matches!(change, Change::IntegrationReset { .. }));
+    }
+    Ok(())
+}
+
+#[tokio::test]
+async fn changed_recalculation_concurrency_applies_to_the_handler() -> anyhow::Result<()> {
+    let suite = Suite::default();
+    let case = suite.new_case();
+    set_test_target(&case.project_repository)?;
+
+    let limit = RecalculationLimit::new(1);
+    let (tx, mut rx) = tokio::sync::mpsc::unbounded_channel();
+    let handler = Handler::new(
+        suite.projects.clone(),
+        suite.users.clone(),
+        VirtualBranchActions,
+        Duration::ZERO,
+        move |change| {
+            tx.send(change)?;
+            Ok(())
+        },
+    )
+    .with_recalculation_limit(limit.clone());
+    let change = || {
+        handler.replay(
+            case.project.id,
+            [RecordedEvent {
+                kind: EventKind::ProjectFilesChange,
+                paths: vec!["file.txt".into()],
+                timestamp_ms: 0,
+            }],
+        )
+    };
+    let blocked = Duration::from_millis(200);
+
+    // Another project uses up the only permit.
+    let running = limit.acquire(ProjectId::generate()).await;
+    let recalculation = change();
+    tokio::pin!(recalculation);
+    assert!(
+        tokio::time::timeout(blocked, &mut recalculation)
+            .await
+            .is_err(),
+        "the limit is reached"
+    );
+    limit.set_concurrency(2);
+    tokio::time::timeout(Duration::from_secs(10), recalculation)
+        .await
+        .expect("a raised limit lets the recalculation run");
+    next_virtual_branches(&mut rx).await;
+
+    // Lowering the limit while the other project still holds its permit leaves no room.
+    limit.set_concurrency(1);
+    let recalculation = change();
+    tokio::pin!(recalculation);
+    assert!(
+        tokio::time::timeout(blocked, &mut recalculation)
+            .await
+            .is_err(),
+        "the lowered limit is reached"
+    );
+    drop(running);
+    tokio::time::timeout(Duration::from_secs(10), recalculation)
+        .await
+        .expect("the finished recalculation frees up room");
+    next_virtual_branches(&mut rx).await;
+    Ok(())
+}
+
+#[tokio::test]
+async fn only_the_configured_sync_trigger_syncs() -> anyhow::Result<()> {
+    let suite = Suite::default();
+    let case = suite.new_case();
+    suite.sign_in();
+    suite
+        .projects
+        .update(&UpdateRequest {
+            id: case.project.id,
+            api: Some(ApiProject {
+                name: "project".into(),
+                description: None,
+                repository_id: "repository".into(),
+                git_url: "https://example.com/project.git".into(),
+                code_git_url: Some("https://example.com/code.git".into()),
+                created_at: "0".into(),
+                updated_at: "0".into(),
+                sync: true,
+            }),
+            sync_trigger: Some(SyncTrigger::Push),
+            ..Default::default()
+        })
+        .await?;
+
+    let syncs = Arc::new(Mutex::new(0));
+    let handler = Handler::new(
+        suite.projects.clone(),
+        suite.users.clone(),
+        VirtualBranchActions,
+        Duration::ZERO,
+        {
+            let syncs = Arc::clone(&syncs);
+            move |change| {
+                // Each sync reports progress as soon as it starts.
+                if matches!(change, Change::SyncProgress { .. }) {
+                    *syncs.lock().unwrap() += 1;
+                }
+                Ok(())
+            }
+        },
+    );
+    let (handler, syncs, project_id) = (&handler, &syncs, case.project.id);
+    let sync_after = |kind| async move {
+        *syncs.lock().unwrap() = 0;
+        handler
+            .replay(
+                project_id,
+                [RecordedEvent {
+                    kind,
+                    paths: Vec::new(),
+                    timestamp_ms: 0,
+                }],
+            )
+            .await;
+        *syncs.lock().unwrap() > 0
+    };
+
+    assert!(sync_after(EventKind::BranchPushed).await);
+    assert!(
+        !sync_after(EventKind::GitButlerOplogChange).await,
+        "other triggers are ignored"
+    );
+    assert!(!sync_after(EventKind::SyncTimer).await);
+    assert!(
+        sync_after(EventKind::SyncNow).await,
+        "explicit syncs happen regardless of the trigger"
+    );
+    Ok(())
+}
@@ -0,0 +1,7 @@
+#[cfg(unix)]
+mod fsmonitor;
+mod handler;
+mod recalculation;
+mod recent_events;
+mod recording;
+mod timers;
@@ -0,0 +1,48 @@
+use futures::FutureExt;
+use gitbutler_project::ProjectId;
+use gitbutler_watcher::RecalculationLimit;
+
+#[tokio::test]
+async fn projects_recalculate_concurrently_up_to_the_limit() {
+    let limit = RecalculationLimit::new(2);
+    let (a, b, c) = (
+        ProjectId::generate(),
+        ProjectId::generate(),
+        ProjectId::generate(),
+    );
+
+    let first = limit.acquire(a).now_or_never();
+    let second = limit.acquire(b).now_or_never();
+    assert!(first.is_some(), "project A isn't blocked by anything");
+    assert!(second.is_some(), "project B can run alongside project A");
+    assert!(
+        limit.acquire(c).now_or_never().is_none(),
+        "the third project has to wait for one of the others"
+    );
+
+    drop(first);
+    assert!(
+        limit.acquire(c).now_or_never().is_some(),
+        "a finished recalculation frees up room"
+    );
+}
+
+#[tokio::test]
+async fn recalculations_of_one_project_are_serialized() {
+    let limit = RecalculationLimit::new(2);
+    let project = ProjectId::generate();
+
+    let running = limit.acquire(project).now_or_never();
+    assert!(running.is_some());
+    assert!(
+        limit.acquire(project).now_or_never().is_none(),
+        "the same project never recalculates twice at once, even with permits left"
+    );
+    assert!(limit
+        .acquire(ProjectId::generate())
+        .now_or_never()
+        .is_some());
+
+    drop(running);
+    assert!(limit.acquire(project).now_or_never().is_some());
+}
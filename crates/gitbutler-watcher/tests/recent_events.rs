@@ -0,0 +1,83 @@
+use std::time::Duration;
+
+use gitbutler_branch_actions::VirtualBranchActions;
+use gitbutler_project::ProjectId;
+use gitbutler_testsupport::{Case, Suite};
+use gitbutler_watcher::{Action, EventKind, Handler, RecentEvents, WatcherHandle};
+
+/// Post a recalculation for each of `project_ids`, and wait until all of them were received.
+async fn post_and_wait(watcher: &WatcherHandle, recent: &RecentEvents, project_ids: &[ProjectId]) {
+    for project_id in project_ids {
+        watcher
+            .post(Action::CalculateVirtualBranches(*project_id))
+            .await
+            .unwrap();
+    }
+    // Events are recorded in the order they are received, so once this one is there, all others are too.
+    let sentinel = ProjectId::generate();
+    watcher
+        .post(Action::CalculateVirtualBranches(sentinel))
+        .await
+        .unwrap();
+    for _ in 0..500 {
+        if !recent.list(sentinel, None).is_empty() {
+            return;
+        }
+        tokio::time::sleep(Duration::from_millis(10)).await;
+    }
+    panic!("the posted events weren't received in time");
+}
+
+fn watch(suite: &Suite, recent: &RecentEvents) -> (Case, WatcherHandle) {
+    let case = suite.new_case();
+    let handler = Handler::new(
+        suite.projects.clone(),
+        suite.users.clone(),
+        VirtualBranchActions,
+        Duration::ZERO,
+        |_| Ok(()),
+    )
+    .with_recent_events(recent.clone());
+    let watcher =
+        gitbutler_watcher::watch_in_background(handler, &case.project.path, case.project.id)
+            .unwrap();
+    (case, watcher)
+}
+
+#[tokio::test]
+async fn received_events_are_recorded_in_order() {
+    let suite = Suite::default();
+    let recent = RecentEvents::new(10);
+    let (case, watcher) = watch(&suite, &recent);
+    let project_id = case.project.id;
+    let other_project_id = ProjectId::generate();
+
+    post_and_wait(
+        &watcher,
+        &recent,
+        &[project_id, other_project_id, project_id, project_id],
+    )
+    .await;
+
+    let events = recent.list(project_id, Some(EventKind::CalculateVirtualBranches));
+    assert_eq!(events.len(), 3, "events are kept per project");
+    assert!(events.iter().all(|event| event.paths.is_empty()));
+    assert!(
+        events
+            .windows(2)
+            .all(|pair| pair[0].timestamp_ms <= pair[1].timestamp_ms),
+        "oldest events come first"
+    );
+    assert_eq!(recent.list(other_project_id, None).len(), 1);
+}
+
+#[tokio::test]
+async fn only_the_most_recent_events_are_kept() {
+    let suite = Suite::default();
+    let recent = RecentEvents::new(2);
+    let (case, watcher) = watch(&suite, &recent);
+    let project_id = case.project.id;
+
+    post_and_wait(&watcher, &recent, &[project_id; 5]).await;
+    assert_eq!(recent.list(project_id, None).len(), 2);
+}
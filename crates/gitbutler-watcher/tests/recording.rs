@@ -0,0 +1,251 @@
+use std::time::Duration;
+
+use gitbutler_branch::VirtualBranchesHandle;
+use gitbutler_branch_actions::VirtualBranchActions;
+use gitbutler_project::ProjectId;
+use gitbutler_testsupport::{virtual_branches::set_test_target, Suite};
+use gitbutler_watcher::{
+    install_regression_case, read_recording, write_regression_case, Action, Change, EventKind,
+    EventRecorder, Handler, RecordedEvent,
+};
+use tokio::sync::mpsc::UnboundedReceiver;
+
+/// Summarize a [`Change::VirtualBranches`] as the names of the branches along with their files,
+/// or return `None` for all other changes.
+fn virtual_branches(change: Change) -> Option<Vec<(String, Vec<String>)>> {
+    let Change::VirtualBranches {
+        virtual_branches, ..
+    } = change
+    else {
+        return None;
+    };
+    Some(
+        virtual_branches
+            .branches
+            .into_iter()
+            .map(|branch| {
+                let files = branch
+                    .files
+                    .iter()
+                    .map(|file| file.path.to_string_lossy().into_owned())
+                    .collect();
+                (branch.name, files)
+            })
+            .collect(),
+    )
+}
+
+async fn next_virtual_branches(
+    changes: &mut UnboundedReceiver<Change>,
+) -> Vec<(String, Vec<String>)> {
+    tokio::time::timeout(Duration::from_secs(10), async {
+        loop {
+            let change = changes.recv().await.expect("the handler is alive");
+            if let Some(branches) = virtual_branches(change) {
+                return branches;
+            }
+        }
+    })
+    .await
+    .expect("virtual branches are emitted in time")
+}
+
+#[tokio::test]
+async fn replaying_a_recording_emits_the_same_changes() -> anyhow::Result<()> {
+    let suite = Suite::default();
+    let case = suite.new_case();
+    set_test_target(&case.project_repository)?;
+    std::fs::write(case.project.path.join("file.txt"), "content")?;
+    let tmp = tempfile::tempdir()?;
+    let recording = tmp.path().join("events.jsonl");
+
+    let (tx, mut rx) = tokio::sync::mpsc::unbounded_channel();
+    let handler = Handler::new(
+        suite.projects.clone(),
+        suite.users.clone(),
+        VirtualBranchActions,
+        Duration::ZERO,
+        move |change| {
+            tx.send(change)?;
+            Ok(())
+        },
+    )
+    .with_event_recorder(EventRecorder::create(&recording)?);
+    let watcher =
+        gitbutler_watcher::watch_in_background(handler, &case.project.path, case.project.id)?;
+
+    let mut recorded_changes = Vec::new();
+    for _ in 0..2 {
+        watcher
+            .post(Action::CalculateVirtualBranches(case.project.id))
+            .await?;
+        recorded_changes.push(next_virtual_branches(&mut rx).await);
+    }
+    drop(watcher);
+
+    let events = read_recording(&recording)?;
+    assert_eq!(
+        events
+            .iter()
+            .filter(|event| event.kind == EventKind::CalculateVirtualBranches)
+            .count(),
+        2,
+        "every received event is recorded"
+    );
+
+    let (tx, mut rx) = tokio::sync::mpsc::unbounded_channel();
+    let fresh_handler = Handler::new(
+        suite.projects.clone(),
+        suite.users.clone(),
+        VirtualBranchActions,
+        Duration::ZERO,
+        move |change| {
+            tx.send(change)?;
+            Ok(())
+        },
+    );
+    fresh_handler.replay(case.project.id, events).await;
+
+    let mut replayed_changes = Vec::new();
+    while let Ok(change) = rx.try_recv() {
+        replayed_changes.extend(virtual_branches(change));
+    }
+    assert_eq!(replayed_changes, recorded_changes);
+    Ok(())
+}
+
+/// Replay `events` against a fresh handler and return the virtual branches it emitted.
+async fn replay(
+    suite: &Suite,
+    project_id: ProjectId,
+    events: Vec<RecordedEvent>,
+) -> Vec<Vec<(String, Vec<String>)>> {
+    let (tx, mut rx) = tokio::sync::mpsc::unbounded_channel();
+    Handler::new(
+        suite.projects.clone(),
+        suite.users.clone(),
+        VirtualBranchActions,
+        Duration::ZERO,
+        move |change| {
+            tx.send(change)?;
+            Ok(())
+        },
+    )
+    .replay(project_id, events)
+    .await;
+    let mut changes = Vec::new();
+    while let Ok(change) = rx.try_recv() {
+        changes.extend(virtual_branches(change));
+    }
+    changes
+}
+
+fn regression_events() -> Vec<RecordedEvent> {
+    vec![
+        RecordedEvent {
+            kind: EventKind::ProjectFilesChange,
+            paths: vec!["file.txt".into()],
+            timestamp_ms: 1,
+        },
+        RecordedEvent {
+            kind: EventKind::CalculateVirtualBranches,
+            paths: Vec::new(),
+            timestamp_ms: 2,
+        },
+    ]
+}
+
+#[tokio::test]
+async fn regression_cases_are_redacted() -> anyhow::Result<()> {
+    let suite = Suite::default();
+    let case = suite.new_case();
+    set_test_target(&case.project_repository)?;
+    std::fs::write(case.project.path.join("file.txt"), "content")?;
+    let tmp = tempfile::tempdir()?;
+    let dir = tmp.path().join("case");
+
+    let events = regression_events();
+    replay(&suite, case.project.id, events.clone()).await;
+    let vb_state = VirtualBranchesHandle::new(case.project.gb_dir());
+    let target = vb_state.get_default_target()?;
+    let branch = vb_state.list_all_branches()?.remove(0);
+
+    write_regression_case(&case.project, &events, &dir)?;
+    assert!(
+        write_regression_case(&case.project, &events, &dir).is_err(),
+        "existing cases are never overwritten"
+    );
+
+    let state = std::fs::read_to_string(dir.join("state.toml"))?;
+    for clear_text in [
+        "file.txt",
+        branch.name.as_str(),
+        target.remote_url.as_str(),
+        "origin",
+        "master",
+    ] {
+        assert!(!state.contains(clear_text), "{clear_text:?} is redacted");
+    }
+    let fixture_events = read_recording(dir.join("events.jsonl"))?;
+    assert_eq!(fixture_events[0].paths.len(), 1);
+    let redacted_file = fixture_events[0].paths[0].to_string_lossy();
+    assert_ne!(
+        redacted_file, "file.txt",
+        "the events can be read like any recording, with redacted paths"
+    );
+    assert!(
+        state.contains(redacted_file.as_ref()),
+        "paths are redacted the same way in the events and the state"
+    );
+
+    let other_dir = tmp.path().join("other-case");
+    write_regression_case(&case.project, &events, &other_dir)?;
+    assert_ne!(
+        read_recording(other_dir.join("events.jsonl"))?[0].paths,
+        fixture_events[0].paths,
+        "each case is salted differently, so hashes can't be compared against known names"
+    );
+    Ok(())
+}
+
+#[tokio::test]
+async fn regression_cases_replay_in_a_fresh_repository() -> anyhow::Result<()> {
+    let suite = Suite::default();
+    let case = suite.new_case();
+    set_test_target(&case.project_repository)?;
+    std::fs::write(case.project.path.join("file.txt"), "content")?;
+    let tmp = tempfile::tempdir()?;
+    let dir = tmp.path().join("case");
+
+    let events = regression_events();
+    let original = replay(&suite, case.project.id, events.clone()).await;
+    assert_eq!(
+        original,
+        vec![vec![("Virtual branch".to_owned(), vec!["file.txt".to_owned()])]; 2]
+    );
+    write_regression_case(&case.project, &events, &dir)?;
+    drop(case);
+
+    let fresh = suite.new_case();
+    let fixture_events = install_regression_case(&dir, &fresh.project)?;
+    let redacted_file = fixture_events[0].paths[0].clone();
+    assert_ne!(
+        std::fs::read_to_string(fresh.project.path.join(&redacted_file))?,
+        "content",
+        "the worktree is installed with redacted content"
+    );
+
+    let replayed = replay(&suite, fresh.project.id, fixture_events).await;
+    assert_eq!(
+        replayed,
+        vec![
+            vec![(
+                "branch-1".to_owned(),
+                vec![redacted_file.to_string_lossy().into_owned()]
+            )];
+            2
+        ],
+        "the same changes are emitted, but redacted"
+    );
+    Ok(())
+}
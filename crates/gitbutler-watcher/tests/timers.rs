@@ -0,0 +1,141 @@
+use std::time::Duration;
+
+use gitbutler_branch_actions::VirtualBranchActions;
+use gitbutler_project::ProjectId;
+use gitbutler_testsupport::Suite;
+use gitbutler_watcher::{ActiveTimer, Handler, TimerKind, WatcherTimers, AUTO_FETCH_INTERVAL};
+
+/// Wait until `project_id` has a timer of `kind` and return it.
+async fn timer_of(timers: &WatcherTimers, project_id: ProjectId, kind: TimerKind) -> ActiveTimer {
+    tokio::time::timeout(Duration::from_secs(10), async {
+        loop {
+            if let Some(timer) = timers
+                .list(project_id)
+                .into_iter()
+                .find(|timer| timer.kind == kind)
+            {
+                return timer;
+            }
+            tokio::time::sleep(Duration::from_millis(10)).await;
+        }
+    })
+    .await
+    .expect("the timer is set in time")
+}
+
+#[tokio::test]
+async fn pending_project_files_change_window_is_listed() -> anyhow::Result<()> {
+    let suite = Suite::default();
+    let case = suite.new_case();
+    let window = Duration::from_secs(30);
+    let timers = WatcherTimers::default();
+    let handler = Handler::new(
+        suite.projects.clone(),
+        suite.users.clone(),
+        VirtualBranchActions,
+        window,
+        |_| Ok(()),
+    )
+    .with_timers(timers.clone());
+    let watcher =
+        gitbutler_watcher::watch_in_background(handler, &case.project.path, case.project.id)?;
+
+    let kinds = || {
+        timers
+            .list(case.project.id)
+            .into_iter()
+            .map(|timer| timer.kind)
+            .filter(|kind| *kind != TimerKind::FileMonitorDebounce)
+            .collect::<Vec<_>>()
+    };
+    assert_eq!(kinds(), [TimerKind::SyncInterval]);
+
+    std::fs::write(case.project.path.join("file.txt"), "content")?;
+    let window_timer = timer_of(
+        &timers,
+        case.project.id,
+        TimerKind::ProjectFilesChangeWindow,
+    )
+    .await;
+    assert!(window_timer.remaining_ms > 0 && window_timer.remaining_ms <= window.as_millis());
+    assert_eq!(
+        kinds(),
+        [TimerKind::ProjectFilesChangeWindow, TimerKind::SyncInterval],
+        "the timer elapsing first comes first"
+    );
+
+    drop(watcher);
+    tokio::time::sleep(Duration::from_millis(100)).await;
+    assert_eq!(
+        kinds(),
+        [TimerKind::ProjectFilesChangeWindow],
+        "the sync interval stops with the watcher"
+    );
+    Ok(())
+}
+
+#[tokio::test]
+async fn pending_file_monitor_debounce_is_listed() -> anyhow::Result<()> {
+    let suite = Suite::default();
+    let case = suite.new_case();
+    let timers = WatcherTimers::default();
+    let handler = Handler::new(
+        suite.projects.clone(),
+        suite.users.clone(),
+        VirtualBranchActions,
+        Duration::ZERO,
+        |_| Ok(()),
+    )
+    .with_timers(timers.clone());
+    let _watcher =
+        gitbutler_watcher::watch_in_background(handler, &case.project.path, case.project.id)?;
+
+    std::fs::write(case.project.path.join("file.txt"), "content")?;
+    let debounce = timer_of(&timers, case.project.id, TimerKind::FileMonitorDebounce).await;
+    assert!(
+        debounce.remaining_ms > 0 && debounce.remaining_ms <= 60_000,
+        "events are held back for a minute at most"
+    );
+
+    tokio::time::timeout(Duration::from_secs(10), async {
+        while timers
+            .list(case.project.id)
+            .iter()
+            .any(|timer| timer.kind == TimerKind::FileMonitorDebounce)
+        {
+            tokio::time::sleep(Duration::from_millis(10)).await;
+        }
+    })
+    .await
+    .expect("the debounce ends once the events are emitted");
+    Ok(())
+}
+
+#[test]
+fn started_auto_fetches_are_listed() {
+    let project_id = ProjectId::generate();
+    let timers = WatcherTimers::default();
+    assert!(timers.list(project_id).is_empty());
+
+    timers.auto_fetch_started(project_id);
+    let timers = timers.list(project_id);
+    assert_eq!(timers.len(), 1);
+    assert_eq!(timers[0].kind, TimerKind::AutoFetch);
+    assert!(
+        timers[0].remaining_ms > AUTO_FETCH_INTERVAL.as_millis() - 1000
+            && timers[0].remaining_ms <= AUTO_FETCH_INTERVAL.as_millis()
+    );
+}
+
+#[test]
+fn timer_kinds_are_camel_case() -> anyhow::Result<()> {
+    assert_eq!(
+        serde_json::to_value(TimerKind::FileMonitorDebounce)?,
+        "fileMonitorDebounce"
+    );
+    assert_eq!(
+        serde_json::to_value(TimerKind::ProjectFilesChangeWindow)?,
+        "projectFilesChangeWindow"
+    );
+    Ok(())
+}